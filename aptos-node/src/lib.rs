@@ -488,6 +488,16 @@ async fn periodic_state_dump(node_config: NodeConfig, db: DbReaderWriter) {
 pub fn setup_environment(node_config: &NodeConfig, logger: Option<Arc<Logger>>) -> AptosHandle {
     let debug_if = setup_debug_interface(node_config, logger);
 
+    // Catch a misconfiguration of safety rules, including an unreachable storage, before
+    // anything is started
+    if node_config.base.role.is_validator() {
+        node_config
+            .consensus
+            .safety_rules
+            .validate()
+            .expect("Invalid safety rules config");
+    }
+
     let metrics_port = node_config.debug_interface.metrics_server_port;
     let metric_host = node_config.debug_interface.address.clone();
     thread::spawn(move || metric_server::start_server(metric_host, metrics_port, false));
@@ -0,0 +1,111 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::verify::validator_set;
+use aptos_config::config::{RocksdbConfig, NO_OP_STORAGE_PRUNER_CONFIG};
+use aptos_crypto::HashValue;
+use aptos_temppath::TempPath;
+use aptos_types::{on_chain_config::ValidatorSet, transaction::Transaction};
+use aptos_vm::AptosVM;
+use aptosdb::AptosDB;
+use executor::db_bootstrapper;
+use storage_interface::DbReaderWriter;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum VerificationError {
+    #[error("Unable to execute genesis: {0}")]
+    Execution(String),
+    #[error("Accumulator root hash {1} does not match the expected {0}")]
+    RootHashMismatch(HashValue, HashValue),
+    #[error("Validator set {1} does not match the expected {0}")]
+    ValidatorSetMismatch(ValidatorSet, ValidatorSet),
+}
+
+/// Executes the genesis transaction on a temporary database, and checks that it produces the
+/// expected transaction accumulator root hash and on-chain validator set. Participants of a
+/// genesis ceremony use this to confirm a genesis transaction before it is broadcast.
+pub fn verify_genesis_ceremony(
+    genesis_txn: &Transaction,
+    expected_root_hash: HashValue,
+    expected_validator_set: &ValidatorSet,
+) -> Result<(), VerificationError> {
+    let db_path = TempPath::new();
+    let aptosdb = AptosDB::open(
+        &db_path,
+        false,
+        NO_OP_STORAGE_PRUNER_CONFIG,
+        RocksdbConfig::default(),
+    )
+    .map_err(|e| VerificationError::Execution(e.to_string()))?;
+    let db_rw = DbReaderWriter::new(aptosdb);
+
+    let waypoint = db_bootstrapper::generate_waypoint::<AptosVM>(&db_rw, genesis_txn)
+        .map_err(|e| VerificationError::Execution(e.to_string()))?;
+    db_bootstrapper::maybe_bootstrap::<AptosVM>(&db_rw, genesis_txn, waypoint)
+        .map_err(|e| VerificationError::Execution(e.to_string()))?;
+
+    let root_hash = db_rw
+        .reader
+        .get_latest_ledger_info()
+        .map_err(|e| VerificationError::Execution(e.to_string()))?
+        .ledger_info()
+        .transaction_accumulator_hash();
+    if root_hash != expected_root_hash {
+        return Err(VerificationError::RootHashMismatch(
+            expected_root_hash,
+            root_hash,
+        ));
+    }
+
+    let validator_set = validator_set(db_rw.reader.clone())
+        .map_err(|e| VerificationError::Execution(e.to_string()))?;
+    if &validator_set != expected_validator_set {
+        return Err(VerificationError::ValidatorSetMismatch(
+            expected_validator_set.clone(),
+            validator_set,
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_types::transaction::WriteSetPayload;
+
+    #[test]
+    fn test_verify_genesis_ceremony() {
+        let genesis = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+        let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis.0));
+
+        let root_hash = match verify_genesis_ceremony(
+            &genesis_txn,
+            HashValue::zero(),
+            &ValidatorSet::empty(),
+        ) {
+            Err(VerificationError::RootHashMismatch(expected, actual)) => {
+                assert_eq!(expected, HashValue::zero());
+                actual
+            }
+            result => panic!("Unexpected result: {:?}", result),
+        };
+
+        let validator_set =
+            match verify_genesis_ceremony(&genesis_txn, root_hash, &ValidatorSet::empty()) {
+                Err(VerificationError::ValidatorSetMismatch(expected, actual)) => {
+                    assert_eq!(expected, ValidatorSet::empty());
+                    actual
+                }
+                result => panic!("Unexpected result: {:?}", result),
+            };
+        assert_eq!(validator_set.payload().count(), 1);
+        assert_eq!(
+            validator_set.payload().next().unwrap().account_address(),
+            &genesis.1[0].data.address
+        );
+
+        verify_genesis_ceremony(&genesis_txn, root_hash, &validator_set).unwrap();
+    }
+}
@@ -4,6 +4,7 @@
 #![forbid(unsafe_code)]
 
 pub mod builder;
+pub mod ceremony;
 pub mod command;
 pub mod fullnode_builder;
 mod genesis;
@@ -239,6 +239,21 @@ fn validator_config(
     validator_account: AccountAddress,
     reader: Arc<dyn DbReader>,
 ) -> Result<ValidatorConfig, Error> {
+    let validator_set = validator_set(reader)?;
+    let info = validator_set
+        .payload()
+        .find(|vi| vi.account_address() == &validator_account)
+        .ok_or_else(|| {
+            Error::UnexpectedError(format!(
+                "Unable to find Validator account {:?}",
+                &validator_account
+            ))
+        })?;
+    Ok(info.config().clone())
+}
+
+/// Read the validator set from the ledger
+pub(crate) fn validator_set(reader: Arc<dyn DbReader>) -> Result<ValidatorSet, Error> {
     let blob = reader
         .get_latest_state_value(StateKey::AccountAddressKey(
             account_config::validator_set_address(),
@@ -251,14 +266,5 @@ fn validator_config(
         .get_validator_set()
         .map_err(|e| Error::UnexpectedError(format!("ValidatorSet issue {}", e)))?
         .ok_or_else(|| Error::UnexpectedError("ValidatorSet does not exist".into()))?;
-    let info = validator_set
-        .payload()
-        .find(|vi| vi.account_address() == &validator_account)
-        .ok_or_else(|| {
-            Error::UnexpectedError(format!(
-                "Unable to find Validator account {:?}",
-                &validator_account
-            ))
-        })?;
-    Ok(info.config().clone())
+    Ok(validator_set)
 }
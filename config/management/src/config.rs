@@ -124,6 +124,8 @@ impl Config {
     pub fn shared_backend_with_namespace(&self, namespace: String) -> StorageWrapper {
         let mut shared_backend = self.shared_backend.clone();
         match &mut shared_backend {
+            config::SecureBackend::Aws(config) => config.namespace = Some(namespace),
+            config::SecureBackend::Gcp(config) => config.namespace = Some(namespace),
             config::SecureBackend::GitHub(config) => config.namespace = Some(namespace),
             config::SecureBackend::InMemoryStorage => panic!("Unsupported namespace for InMemory"),
             config::SecureBackend::Vault(config) => config.namespace = Some(namespace),
@@ -180,6 +182,10 @@ mod tests {
                 disable_cas: None,
                 connection_timeout_ms: None,
                 response_timeout_ms: None,
+                max_key_versions: None,
+                kv_mount: None,
+                kv_version: None,
+                enterprise_namespace: None,
             }),
             validator_backend: SecureBackend::Vault(VaultConfig {
                 namespace: None,
@@ -190,6 +196,10 @@ mod tests {
                 disable_cas: None,
                 connection_timeout_ms: None,
                 response_timeout_ms: None,
+                max_key_versions: None,
+                kv_mount: None,
+                kv_version: None,
+                enterprise_namespace: None,
             }),
         };
 
@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::error::Error;
-use aptos_config::config::{self, GitHubConfig, OnDiskStorageConfig, Token, VaultConfig};
+use aptos_config::config::{
+    self, AwsConfig, GcpConfig, GitHubConfig, OnDiskStorageConfig, Token, VaultConfig,
+};
 use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
@@ -11,13 +13,15 @@ use std::{
 };
 use structopt::StructOpt;
 
+pub const AWS: &str = "aws";
 pub const BACKEND: &str = "backend";
 pub const DISK: &str = "disk";
+pub const GCP: &str = "gcp";
 pub const GITHUB: &str = "github";
 pub const MEMORY: &str = "memory";
 pub const VAULT: &str = "vault";
 
-// Custom timeouts for vault backend operations when using the management tooling.
+// Custom timeouts for vault, AWS and GCP backend operations when using the management tooling.
 const CONNECTION_TIMEOUT_MS: u64 = 10_000;
 const RESPONSE_TIMEOUT_MS: u64 = 10_000;
 
@@ -69,6 +73,35 @@ impl TryInto<config::SecureBackend> for SecureBackend {
 
     fn try_into(mut self) -> Result<config::SecureBackend, Error> {
         let backend = match self.backend.as_ref() {
+            AWS => {
+                let region = self
+                    .parameters
+                    .remove("region")
+                    .ok_or_else(|| Error::BackendParsingError("missing region".into()))?;
+                config::SecureBackend::Aws(AwsConfig {
+                    region,
+                    prefix: self.parameters.remove("prefix"),
+                    endpoint: self.parameters.remove("endpoint"),
+                    namespace: self.parameters.remove("namespace"),
+                    connection_timeout_ms: Some(CONNECTION_TIMEOUT_MS),
+                    response_timeout_ms: Some(RESPONSE_TIMEOUT_MS),
+                })
+            }
+            GCP => {
+                let project = self
+                    .parameters
+                    .remove("project")
+                    .ok_or_else(|| Error::BackendParsingError("missing project".into()))?;
+                config::SecureBackend::Gcp(GcpConfig {
+                    project,
+                    prefix: self.parameters.remove("prefix"),
+                    credentials_path: self.parameters.remove("credentials").map(PathBuf::from),
+                    endpoint: self.parameters.remove("endpoint"),
+                    namespace: self.parameters.remove("namespace"),
+                    connection_timeout_ms: Some(CONNECTION_TIMEOUT_MS),
+                    response_timeout_ms: Some(RESPONSE_TIMEOUT_MS),
+                })
+            }
             DISK => {
                 let mut config = OnDiskStorageConfig::default();
                 config.set_data_dir(PathBuf::from(""));
@@ -122,6 +155,10 @@ impl TryInto<config::SecureBackend> for SecureBackend {
                     disable_cas: Some(true),
                     connection_timeout_ms: Some(CONNECTION_TIMEOUT_MS),
                     response_timeout_ms: Some(RESPONSE_TIMEOUT_MS),
+                    max_key_versions: None,
+                    kv_mount: self.parameters.remove("kv_mount"),
+                    kv_version: None,
+                    enterprise_namespace: self.parameters.remove("enterprise_namespace"),
                 })
             }
             _ => panic!("Invalid backend: {}", self.backend),
@@ -151,6 +188,15 @@ macro_rules! secure_backend {
 
 Secure backends are represented as a semi-colon deliminted key value
 pair: "k0=v0;k1=v1;...".  The current supported formats are:
+    AWS: "backend=aws;region=REGION"
+        an optional secret name prefix: "prefix=PREFIX"
+        an optional endpoint: "endpoint=URL"
+        an optional namespace: "namespace=NAMESPACE"
+    GCP: "backend=gcp;project=PROJECT"
+        an optional secret id prefix: "prefix=PREFIX"
+        an optional service account key: "credentials=PATH_TO_KEY"
+        an optional endpoint: "endpoint=URL"
+        an optional namespace: "namespace=NAMESPACE"
     Vault: "backend=vault;server=URL;token=PATH_TO_TOKEN"
         an optional namespace: "namespace=NAMESPACE"
         an optional server certificate: "ca_certificate=PATH_TO_CERT"
@@ -212,6 +258,21 @@ mod tests {
         assert!(storage(disk).is_err());
     }
 
+    #[test]
+    fn test_aws() {
+        storage("backend=aws;region=us-west-2").unwrap();
+        storage("backend=aws;region=us-west-2;prefix=validator/;namespace=test").unwrap();
+        storage("backend=aws;region=us-east-1;endpoint=http://localhost:4566").unwrap();
+        storage("backend=aws").unwrap_err();
+    }
+
+    #[test]
+    fn test_gcp() {
+        storage("backend=gcp;project=aptos-validator").unwrap();
+        storage("backend=gcp;project=aptos-validator;prefix=validator_;namespace=test").unwrap();
+        storage("backend=gcp").unwrap_err();
+    }
+
     #[test]
     fn test_github() {
         let path = aptos_temppath::TempPath::new();
@@ -2,10 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    config::{LoggerConfig, SecureBackend},
+    config::{Error, LoggerConfig, SecureBackend},
     keys::ConfigKey,
 };
 use aptos_crypto::{ed25519::Ed25519PrivateKey, Uniform};
+use aptos_secure_storage::{KVStorage, Storage};
 use aptos_types::{network_address::NetworkAddress, waypoint::Waypoint, PeerId};
 use rand::rngs::StdRng;
 use serde::{Deserialize, Serialize};
@@ -25,7 +26,26 @@ pub struct SafetyRulesConfig {
     pub export_consensus_key: bool,
     // Read/Write/Connect networking operation timeout in milliseconds.
     pub network_timeout_ms: u64,
+    // Largest message, in bytes, exchanged with a remote safety rules service.
+    pub max_frame_size: usize,
+    // Caches the safety data in memory, which only a remote storage backend requires.
     pub enable_cached_safety_data: bool,
+    // Consensus keys older than this (in seconds) trigger a warning, if set.
+    pub consensus_key_max_age_secs: Option<u64>,
+    // Number of epochs for which a backup of the safety data is retained.
+    pub max_safety_data_backups: usize,
+    // Start as a hot standby that tracks safety data but cannot sign until promoted.
+    pub standby: bool,
+    // The lease that fences signing between a primary and its hot standbys.
+    pub signing_lease: SigningLeaseConfig,
+    // How far (in milliseconds) proposal timestamps may be ahead of the local clock.
+    pub max_proposal_timestamp_skew_ms: u64,
+    // Consecutive storage write failures after which writes are refused, if set.
+    pub storage_circuit_failure_threshold: Option<u32>,
+    // How long (in milliseconds) writes are refused once the failure threshold is reached.
+    pub storage_circuit_reset_timeout_ms: u64,
+    // Rejects storage operations outside of the safety rules key policy, e.g., owner overwrites.
+    pub enforce_storage_key_policy: bool,
 }
 
 impl Default for SafetyRulesConfig {
@@ -39,17 +59,86 @@ impl Default for SafetyRulesConfig {
             export_consensus_key: true,
             // Default value of 30 seconds for a timeout
             network_timeout_ms: 30_000,
-            enable_cached_safety_data: true,
+            // Leaves room for the JSON encoding of full proposals
+            max_frame_size: 64 * 1024 * 1024,
+            enable_cached_safety_data: false,
+            consensus_key_max_age_secs: None,
+            max_safety_data_backups: 3,
+            standby: false,
+            signing_lease: SigningLeaseConfig::default(),
+            max_proposal_timestamp_skew_ms: 500,
+            storage_circuit_failure_threshold: None,
+            storage_circuit_reset_timeout_ms: 10_000,
+            enforce_storage_key_policy: false,
         }
     }
 }
 
 impl SafetyRulesConfig {
     pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        if let Some(SecureBackend::OnDiskStorage(backend)) = &mut self.signing_lease.backend {
+            backend.set_data_dir(data_dir.clone());
+        }
         if let SecureBackend::OnDiskStorage(backend) = &mut self.backend {
             backend.set_data_dir(data_dir);
         }
     }
+
+    /// Checks the config before safety rules are started: caching the safety data requires a
+    /// remote storage backend, which must be reachable, and storage initialized from the test
+    /// config requires a waypoint other than the default one.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.enable_cached_safety_data && !self.backend.is_remote() {
+            return Err(Error::InvariantViolation(
+                "Safety data can only be cached with a remote storage backend".into(),
+            ));
+        }
+        self.check_storage_available()?;
+        if let Some(test_config) = &self.test {
+            let waypoint = test_config.waypoint.ok_or(Error::Missing("waypoint"))?;
+            if waypoint == Waypoint::default() {
+                return Err(Error::InvariantViolation(
+                    "Safety rules cannot be initialized with the default waypoint".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a remote storage backend is reachable.
+    fn check_storage_available(&self) -> Result<(), Error> {
+        if !self.backend.is_remote() {
+            return Ok(());
+        }
+        Storage::from(&self.backend).available().map_err(|error| {
+            Error::InvariantViolation(format!("Safety rules storage is not available: {}", error))
+        })
+    }
+}
+
+/// Where and for whom the signing lease is held. Only the holder of an unexpired lease signs, a
+/// node that is not in standby acquires it at startup and renews it while signing.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SigningLeaseConfig {
+    // The backend shared by the primary and its standbys that holds the lease. If not set, the
+    // lease is held in the safety rules backend.
+    pub backend: Option<SecureBackend>,
+    // Identifies this node as the holder of the lease, which must differ between the primary and
+    // its standbys. If not set, the validator account address is used.
+    pub holder: Option<String>,
+    // How long (in milliseconds) the lease is held without being renewed.
+    pub duration_ms: u64,
+}
+
+impl Default for SigningLeaseConfig {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            holder: None,
+            duration_ms: 30_000,
+        }
+    }
 }
 
 /// Defines how safety rules should be executed
@@ -71,6 +160,13 @@ pub enum SafetyRulesService {
 #[serde(deny_unknown_fields)]
 pub struct RemoteService {
     pub server_address: NetworkAddress,
+    // If set, the service is served over a Unix domain socket at this path instead of over TCP,
+    // so that it is only reachable from the local host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket_path: Option<PathBuf>,
+    // If set, connections to the service use mutually authenticated TLS. Only supported over TCP.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<RemoteServiceTlsConfig>,
 }
 
 impl RemoteService {
@@ -83,6 +179,31 @@ impl RemoteService {
     }
 }
 
+/// The TLS identity of one end of the connection to the remote service, i.e., the server
+/// certificate in the config of the service and the client certificate in the config of the node.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteServiceTlsConfig {
+    /// The PEM encoded certificate chain presented to the peer
+    pub certificate: TlsMaterial,
+    /// The PEM encoded PKCS#8 private key of the certificate
+    pub private_key: TlsMaterial,
+    /// The PEM encoded certificate of the authority that signs the certificates of valid peers
+    pub ca_certificate: TlsMaterial,
+    /// The DNS name the server certificate is issued for
+    pub server_name: String,
+}
+
+/// TLS certificates and keys can either be stored on disk or in the secure backend.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsMaterial {
+    /// This is an absolute path and not relative to data_dir
+    FromDisk(PathBuf),
+    /// The name of a string value in the safety rules secure backend
+    FromStorage(String),
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct SafetyRulesTestConfig {
     pub author: PeerId,
@@ -119,3 +240,79 @@ impl SafetyRulesTestConfig {
         self.execution_key = Some(ConfigKey::<Ed25519PrivateKey>::new(privkey));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{OnDiskStorageConfig, Token, VaultConfig};
+    use aptos_crypto::HashValue;
+    use aptos_types::{block_info::BlockInfo, ledger_info::LedgerInfo};
+
+    #[test]
+    fn test_validate_default() {
+        SafetyRulesConfig::default().validate().unwrap();
+    }
+
+    #[test]
+    fn test_validate_cached_safety_data() {
+        let mut config = SafetyRulesConfig {
+            backend: SecureBackend::OnDiskStorage(OnDiskStorageConfig::default()),
+            ..Default::default()
+        };
+        config.validate().unwrap();
+
+        config.enable_cached_safety_data = true;
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvariantViolation(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_storage_unreachable() {
+        let config = SafetyRulesConfig {
+            backend: SecureBackend::Vault(VaultConfig {
+                ca_certificate: None,
+                namespace: None,
+                renew_ttl_secs: None,
+                // Nothing listens on the discard port
+                server: "http://127.0.0.1:9".into(),
+                token: Token::FromConfig("token".into()),
+                disable_cas: None,
+                connection_timeout_ms: Some(100),
+                response_timeout_ms: Some(100),
+                max_key_versions: None,
+                kv_mount: None,
+                kv_version: None,
+                enterprise_namespace: None,
+            }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvariantViolation(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_waypoint() {
+        let mut test_config = SafetyRulesTestConfig::new(PeerId::random());
+        let mut config = SafetyRulesConfig {
+            test: Some(test_config.clone()),
+            ..Default::default()
+        };
+        assert!(matches!(config.validate(), Err(Error::Missing("waypoint"))));
+
+        test_config.waypoint = Some(Waypoint::default());
+        config.test = Some(test_config.clone());
+        assert!(matches!(
+            config.validate(),
+            Err(Error::InvariantViolation(_))
+        ));
+
+        let ledger_info = LedgerInfo::new(BlockInfo::empty(), HashValue::zero());
+        test_config.waypoint = Some(Waypoint::new_any(&ledger_info));
+        config.test = Some(test_config);
+        config.validate().unwrap();
+    }
+}
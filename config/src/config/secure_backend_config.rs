@@ -3,7 +3,8 @@
 
 use crate::config::Error;
 use aptos_secure_storage::{
-    GitHubStorage, InMemoryStorage, Namespaced, OnDiskStorage, Storage, VaultStorage,
+    AwsStorage, GcsSecretManagerStorage, GitHubStorage, InMemoryStorage, KvVersion, Namespaced,
+    OnDiskStorage, Storage, VaultStorage, DEFAULT_KV_MOUNT,
 };
 use serde::{Deserialize, Serialize};
 use std::{
@@ -15,6 +16,8 @@ use std::{
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum SecureBackend {
+    Aws(AwsConfig),
+    Gcp(GcpConfig),
     GitHub(GitHubConfig),
     InMemoryStorage,
     Vault(VaultConfig),
@@ -24,7 +27,9 @@ pub enum SecureBackend {
 impl SecureBackend {
     pub fn namespace(&self) -> Option<&str> {
         match self {
-            SecureBackend::GitHub(GitHubConfig { namespace, .. })
+            SecureBackend::Aws(AwsConfig { namespace, .. })
+            | SecureBackend::Gcp(GcpConfig { namespace, .. })
+            | SecureBackend::GitHub(GitHubConfig { namespace, .. })
             | SecureBackend::Vault(VaultConfig { namespace, .. })
             | SecureBackend::OnDiskStorage(OnDiskStorageConfig { namespace, .. }) => {
                 namespace.as_deref()
@@ -33,9 +38,22 @@ impl SecureBackend {
         }
     }
 
+    /// Returns true if the backend is reached over the network rather than held by the node.
+    pub fn is_remote(&self) -> bool {
+        match self {
+            SecureBackend::Aws(_)
+            | SecureBackend::Gcp(_)
+            | SecureBackend::GitHub(_)
+            | SecureBackend::Vault(_) => true,
+            SecureBackend::InMemoryStorage | SecureBackend::OnDiskStorage(_) => false,
+        }
+    }
+
     pub fn clear_namespace(&mut self) {
         match self {
-            SecureBackend::GitHub(GitHubConfig { namespace, .. })
+            SecureBackend::Aws(AwsConfig { namespace, .. })
+            | SecureBackend::Gcp(GcpConfig { namespace, .. })
+            | SecureBackend::GitHub(GitHubConfig { namespace, .. })
             | SecureBackend::Vault(VaultConfig { namespace, .. })
             | SecureBackend::OnDiskStorage(OnDiskStorageConfig { namespace, .. }) => {
                 *namespace = None;
@@ -45,6 +63,53 @@ impl SecureBackend {
     }
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AwsConfig {
+    /// The AWS region hosting the secrets, e.g., us-west-2. Credentials are resolved through the
+    /// standard AWS credential chain: environment, shared credentials file and instance role.
+    pub region: String,
+    /// An optional prefix of the names of all secrets, e.g., "validator/". Unlike a namespace,
+    /// the prefix is not part of the keys, so it can be used to separate deployments that share
+    /// an account.
+    pub prefix: Option<String>,
+    /// Overrides the regional Secrets Manager endpoint, e.g., for VPC endpoints or localstack.
+    pub endpoint: Option<String>,
+    /// A namespace is an optional portion of the path to a key stored within AWS. For example, a
+    /// key, S, without a namespace would be available in the secret S, with a namespace, N, it
+    /// would be in N/S.
+    pub namespace: Option<String>,
+    /// Timeout for new AWS socket connections, in milliseconds.
+    pub connection_timeout_ms: Option<u64>,
+    /// Timeout for generic AWS operations (e.g., reads and writes), in milliseconds.
+    pub response_timeout_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GcpConfig {
+    /// The GCP project hosting the secrets.
+    pub project: String,
+    /// An optional prefix of the ids of all secrets, e.g., "validator_". Unlike a namespace, the
+    /// prefix is not part of the keys, so it can be used to separate deployments that share a
+    /// project. Secret ids may only contain letters, digits, underscores and hyphens.
+    pub prefix: Option<String>,
+    /// The service account key file used to authenticate. Without one, credentials are resolved
+    /// like Google's application default credentials: from the file named by
+    /// GOOGLE_APPLICATION_CREDENTIALS, or else from the metadata server of the instance.
+    pub credentials_path: Option<PathBuf>,
+    /// Overrides the Secret Manager endpoint, e.g., for regional endpoints.
+    pub endpoint: Option<String>,
+    /// A namespace is an optional portion of the path to a key stored within GCP. For example, a
+    /// key, S, without a namespace would be available in the secret S, with a namespace, N, it
+    /// would be in the secret for N/S.
+    pub namespace: Option<String>,
+    /// Timeout for new GCP socket connections, in milliseconds.
+    pub connection_timeout_ms: Option<u64>,
+    /// Timeout for generic GCP operations (e.g., reads and writes), in milliseconds.
+    pub response_timeout_ms: Option<u64>,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct GitHubConfig {
@@ -69,7 +134,7 @@ pub struct VaultConfig {
     pub ca_certificate: Option<PathBuf>,
     /// A namespace is an optional portion of the path to a key stored within Vault. For example,
     /// a secret, S, without a namespace would be available in secret/data/S, with a namespace, N, it
-    /// would be in secret/data/N/S.
+    /// would be in secret/data/N/S (or secret/N/S with KV v1).
     pub namespace: Option<String>,
     /// Vault leverages leases on many tokens, specify this to automatically have your lease
     /// renewed up to that many seconds more. If this is not specified, the lease will not
@@ -85,6 +150,18 @@ pub struct VaultConfig {
     pub connection_timeout_ms: Option<u64>,
     /// Timeout for generic vault operations (e.g., reads and writes), in milliseconds.
     pub response_timeout_ms: Option<u64>,
+    /// The number of versions of each key retained on rotation, including the current one.
+    /// Defaults to 4.
+    pub max_key_versions: Option<u32>,
+    /// The path at which the KV secrets engine holding secrets is mounted. Defaults to secret.
+    pub kv_mount: Option<String>,
+    /// The version of the KV secrets engine, either v1 or v2. If not set, it is read from Vault
+    /// when kv_mount is set, and defaults to v2 otherwise.
+    pub kv_version: Option<KvVersion>,
+    /// The Vault Enterprise namespace, sent in the X-Vault-Namespace header of every request.
+    /// Unlike namespace above, which prefixes the path of keys, it selects the tenant of a shared
+    /// cluster whose mounts, policies and tokens are used.
+    pub enterprise_namespace: Option<String>,
 }
 
 impl VaultConfig {
@@ -106,6 +183,9 @@ pub struct OnDiskStorageConfig {
     /// example, a key, S, without a namespace would be available in S, with a namespace, N, it
     /// would be in N/S.
     pub namespace: Option<String>,
+    /// The number of versions of each key retained on rotation, including the current one.
+    /// Defaults to 2.
+    pub max_key_versions: Option<usize>,
     #[serde(skip)]
     data_dir: PathBuf,
 }
@@ -144,6 +224,7 @@ impl Default for OnDiskStorageConfig {
     fn default() -> Self {
         Self {
             namespace: None,
+            max_key_versions: None,
             path: PathBuf::from("secure_storage.json"),
             data_dir: PathBuf::from("/opt/aptos/data"),
         }
@@ -176,6 +257,39 @@ fn read_file(path: &Path) -> Result<String, Error> {
 impl From<&SecureBackend> for Storage {
     fn from(backend: &SecureBackend) -> Self {
         match backend {
+            SecureBackend::Aws(config) => {
+                let storage = Storage::from(AwsStorage::new(
+                    config.region.clone(),
+                    config.prefix.clone(),
+                    config.endpoint.clone(),
+                    None,
+                    config.connection_timeout_ms,
+                    config.response_timeout_ms,
+                ));
+                if let Some(namespace) = &config.namespace {
+                    Storage::from(Namespaced::new(namespace, Box::new(storage)))
+                } else {
+                    storage
+                }
+            }
+            SecureBackend::Gcp(config) => {
+                let storage = Storage::from(
+                    GcsSecretManagerStorage::from_credentials_path(
+                        config.project.clone(),
+                        config.prefix.clone(),
+                        config.endpoint.clone(),
+                        config.credentials_path.as_deref(),
+                        config.connection_timeout_ms,
+                        config.response_timeout_ms,
+                    )
+                    .expect("Unable to read GCP credentials"),
+                );
+                if let Some(namespace) = &config.namespace {
+                    Storage::from(Namespaced::new(namespace, Box::new(storage)))
+                } else {
+                    storage
+                }
+            }
             SecureBackend::GitHub(config) => {
                 let storage = Storage::from(GitHubStorage::new(
                     config.repository_owner.clone(),
@@ -195,7 +309,11 @@ impl From<&SecureBackend> for Storage {
             }
             SecureBackend::InMemoryStorage => Storage::from(InMemoryStorage::new()),
             SecureBackend::OnDiskStorage(config) => {
-                let storage = Storage::from(OnDiskStorage::new(config.path()));
+                let mut storage = OnDiskStorage::new(config.path());
+                if let Some(max_key_versions) = config.max_key_versions {
+                    storage.set_max_key_versions(max_key_versions);
+                }
+                let storage = Storage::from(storage);
                 if let Some(namespace) = &config.namespace {
                     Storage::from(Namespaced::new(namespace, Box::new(storage)))
                 } else {
@@ -203,7 +321,7 @@ impl From<&SecureBackend> for Storage {
                 }
             }
             SecureBackend::Vault(config) => {
-                let storage = Storage::from(VaultStorage::new(
+                let mut storage = VaultStorage::new(
                     config.server.clone(),
                     config.token.read_token().expect("Unable to read token"),
                     config
@@ -214,7 +332,18 @@ impl From<&SecureBackend> for Storage {
                     config.disable_cas.map_or_else(|| true, |disable| !disable),
                     config.connection_timeout_ms,
                     config.response_timeout_ms,
-                ));
+                );
+                if let Some(max_key_versions) = config.max_key_versions {
+                    storage.set_max_key_versions(max_key_versions);
+                }
+                storage.set_enterprise_namespace(config.enterprise_namespace.clone());
+                if config.kv_mount.is_some() || config.kv_version.is_some() {
+                    storage.set_kv_mount(
+                        config.kv_mount.as_deref().unwrap_or(DEFAULT_KV_MOUNT),
+                        config.kv_version,
+                    );
+                }
+                let storage = Storage::from(storage);
                 if let Some(namespace) = &config.namespace {
                     Storage::from(Namespaced::new(namespace, Box::new(storage)))
                 } else {
@@ -246,6 +375,10 @@ mod tests {
                 disable_cas: None,
                 connection_timeout_ms: None,
                 response_timeout_ms: None,
+                max_key_versions: None,
+                kv_mount: None,
+                kv_version: None,
+                enterprise_namespace: None,
             },
         };
 
@@ -274,6 +407,10 @@ vault:
                 disable_cas: None,
                 connection_timeout_ms: Some(3000),
                 response_timeout_ms: Some(5000),
+                max_key_versions: None,
+                kv_mount: None,
+                kv_version: None,
+                enterprise_namespace: None,
             },
         };
 
@@ -292,6 +429,27 @@ vault:
         serde_yaml::to_string(&from_config).unwrap();
     }
 
+    #[test]
+    fn test_vault_kv_engine_and_enterprise_namespace_parsing() {
+        let text = r#"
+vault:
+    server: "127.0.0.1:8200"
+    token:
+        from_config: "test"
+    kv_mount: "kv"
+    kv_version: "v1"
+    enterprise_namespace: "team/validators"
+        "#;
+
+        let config: Config = serde_yaml::from_str(text).unwrap();
+        assert_eq!(config.vault.kv_mount.as_deref(), Some("kv"));
+        assert_eq!(config.vault.kv_version, Some(KvVersion::V1));
+        assert_eq!(
+            config.vault.enterprise_namespace.as_deref(),
+            Some("team/validators")
+        );
+    }
+
     #[test]
     fn test_token_disk_parsing() {
         let from_disk = Config {
@@ -304,6 +462,10 @@ vault:
                 disable_cas: None,
                 connection_timeout_ms: None,
                 response_timeout_ms: None,
+                max_key_versions: None,
+                kv_mount: None,
+                kv_version: None,
+                enterprise_namespace: None,
             },
         };
 
@@ -320,6 +482,56 @@ vault:
         serde_yaml::to_string(&from_disk).unwrap();
     }
 
+    #[test]
+    fn test_aws_parsing() {
+        let from_config = SecureBackend::Aws(AwsConfig {
+            region: "us-west-2".to_string(),
+            prefix: Some("validator/".to_string()),
+            endpoint: None,
+            namespace: None,
+            connection_timeout_ms: None,
+            response_timeout_ms: Some(5000),
+        });
+
+        let text_from_config = r#"
+type: aws
+region: us-west-2
+prefix: validator/
+response_timeout_ms: 5000
+        "#;
+
+        let de_from_config: SecureBackend = serde_yaml::from_str(text_from_config).unwrap();
+        assert_eq!(de_from_config, from_config);
+        // Just assert that it can be serialized, no need to do string comparison
+        serde_yaml::to_string(&from_config).unwrap();
+    }
+
+    #[test]
+    fn test_gcp_parsing() {
+        let from_config = SecureBackend::Gcp(GcpConfig {
+            project: "aptos-validator".to_string(),
+            prefix: Some("validator_".to_string()),
+            credentials_path: Some(PathBuf::from("/opt/aptos/gcp_credentials.json")),
+            endpoint: None,
+            namespace: Some("safety_rules".to_string()),
+            connection_timeout_ms: None,
+            response_timeout_ms: None,
+        });
+
+        let text_from_config = r#"
+type: gcp
+project: aptos-validator
+prefix: validator_
+credentials_path: /opt/aptos/gcp_credentials.json
+namespace: safety_rules
+        "#;
+
+        let de_from_config: SecureBackend = serde_yaml::from_str(text_from_config).unwrap();
+        assert_eq!(de_from_config, from_config);
+        // Just assert that it can be serialized, no need to do string comparison
+        serde_yaml::to_string(&from_config).unwrap();
+    }
+
     #[test]
     fn test_token_reading() {
         let temppath = aptos_temppath::TempPath::new();
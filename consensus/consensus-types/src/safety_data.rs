@@ -1,9 +1,9 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::vote::Vote;
+use crate::{common::Round, vote::Vote};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{cmp::Ordering, fmt};
 
 /// Data structure for safety rules to ensure consensus safety.
 #[derive(Debug, Deserialize, Eq, PartialEq, Serialize, Clone, Default)]
@@ -34,6 +34,54 @@ impl SafetyData {
             last_vote,
         }
     }
+
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = epoch;
+        self
+    }
+
+    pub fn with_last_voted_round(mut self, round: Round) -> Self {
+        self.last_voted_round = round;
+        self
+    }
+
+    pub fn with_preferred_round(mut self, round: Round) -> Self {
+        self.preferred_round = round;
+        self
+    }
+
+    pub fn with_one_chain_round(mut self, round: Round) -> Self {
+        self.one_chain_round = round;
+        self
+    }
+
+    pub fn with_last_vote(mut self, vote: Option<Vote>) -> Self {
+        self.last_vote = vote;
+        self
+    }
+
+    /// Joins safety data received from two independent sources into the most conservative one.
+    /// Rounds restart with each epoch, so the data of the higher epoch is taken as a whole. Within
+    /// the same epoch, each round is the highest of the two, so that nothing either source voted
+    /// for can be voted for again. As the merged rounds may not belong to either last vote, the
+    /// last vote is then dropped, unless both sources hold the same one.
+    pub fn merge(a: SafetyData, b: SafetyData) -> SafetyData {
+        match a.epoch.cmp(&b.epoch) {
+            Ordering::Greater => a,
+            Ordering::Less => b,
+            Ordering::Equal => SafetyData::new(
+                a.epoch,
+                a.last_voted_round.max(b.last_voted_round),
+                a.preferred_round.max(b.preferred_round),
+                a.one_chain_round.max(b.one_chain_round),
+                if a.last_vote == b.last_vote {
+                    a.last_vote
+                } else {
+                    None
+                },
+            ),
+        }
+    }
 }
 
 impl fmt::Display for SafetyData {
@@ -46,6 +94,164 @@ impl fmt::Display for SafetyData {
     }
 }
 
+#[cfg(any(test, feature = "fuzzing"))]
+impl proptest::arbitrary::Arbitrary for SafetyData {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    /// Generates safety data that safety rules could have persisted: the epoch is at least 1 and
+    /// neither the preferred round nor the round of the last vote exceed the last voted round.
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        use aptos_crypto::HashValue;
+        use aptos_types::{
+            block_info::BlockInfo, ledger_info::LedgerInfo, validator_signer::proptests,
+        };
+        use proptest::prelude::*;
+
+        (1..u64::MAX, any::<u64>())
+            .prop_flat_map(|(epoch, last_voted_round)| {
+                let last_vote = (
+                    0..=last_voted_round,
+                    any::<HashValue>(),
+                    proptests::arb_signer(),
+                )
+                    .prop_map(move |(round, id, signer)| {
+                        let block_info = |round, id| {
+                            BlockInfo::new(epoch, round, id, HashValue::zero(), 0, 0, None)
+                        };
+                        let vote_data = crate::vote_data::VoteData::new(
+                            block_info(round, id),
+                            block_info(round.saturating_sub(1), HashValue::zero()),
+                        );
+                        let ledger_info = LedgerInfo::new(BlockInfo::empty(), HashValue::zero());
+                        Vote::new(vote_data, signer.author(), ledger_info, &signer)
+                    });
+                (
+                    Just(epoch),
+                    Just(last_voted_round),
+                    (0..=last_voted_round).prop_flat_map(move |one_chain_round| {
+                        (0..=one_chain_round, Just(one_chain_round))
+                    }),
+                    proptest::option::of(last_vote),
+                )
+            })
+            .prop_map(
+                |(epoch, last_voted_round, (preferred_round, one_chain_round), last_vote)| {
+                    SafetyData::new(
+                        epoch,
+                        last_voted_round,
+                        preferred_round,
+                        one_chain_round,
+                        last_vote,
+                    )
+                },
+            )
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_safety_data_bcs_round_trip(safety_data in any::<SafetyData>()) {
+            prop_assert!(safety_data.epoch >= 1);
+            prop_assert!(safety_data.preferred_round <= safety_data.last_voted_round);
+            if let Some(vote) = &safety_data.last_vote {
+                prop_assert!(vote.vote_data().proposed().round() <= safety_data.last_voted_round);
+            }
+
+            let bytes = bcs::to_bytes(&safety_data).unwrap();
+            prop_assert_eq!(bcs::from_bytes::<SafetyData>(&bytes).unwrap(), safety_data);
+        }
+    }
+
+    #[test]
+    fn test_builders() {
+        let baseline = SafetyData::new(1, 5, 3, 4, None);
+        assert_eq!(
+            baseline.clone().with_epoch(2),
+            SafetyData::new(2, 5, 3, 4, None)
+        );
+        assert_eq!(
+            baseline.clone().with_last_voted_round(6),
+            SafetyData::new(1, 6, 3, 4, None)
+        );
+        assert_eq!(
+            baseline.clone().with_preferred_round(2),
+            SafetyData::new(1, 5, 2, 4, None)
+        );
+        assert_eq!(
+            baseline.clone().with_one_chain_round(5),
+            SafetyData::new(1, 5, 3, 5, None)
+        );
+        assert_eq!(baseline.clone().with_last_vote(None), baseline);
+    }
+
+    #[test]
+    fn test_merge() {
+        // Within an epoch, every round of either side is lower than, equal to and higher than
+        // that of the other
+        let values = [3, 5, 7];
+        for &last_voted_round in &values {
+            for &preferred_round in &values {
+                for &one_chain_round in &values {
+                    let a = SafetyData::new(5, 5, 5, 5, None);
+                    let b = SafetyData::new(
+                        5,
+                        last_voted_round,
+                        preferred_round,
+                        one_chain_round,
+                        None,
+                    );
+                    let expected = SafetyData::new(
+                        5,
+                        last_voted_round.max(5),
+                        preferred_round.max(5),
+                        one_chain_round.max(5),
+                        None,
+                    );
+                    assert_eq!(SafetyData::merge(a.clone(), b.clone()), expected);
+                    assert_eq!(SafetyData::merge(b, a), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_across_epochs() {
+        // The rounds of an older epoch do not carry over to the new one
+        let old = SafetyData::new(5, 100, 99, 98, None);
+        let new = SafetyData::new(6, 2, 1, 0, None);
+        assert_eq!(SafetyData::merge(old.clone(), new.clone()), new);
+        assert_eq!(SafetyData::merge(new.clone(), old), new);
+    }
+
+    proptest! {
+        #[test]
+        fn test_merge_properties(a in any::<SafetyData>(), b in any::<SafetyData>()) {
+            let merged = SafetyData::merge(a.clone(), b.clone());
+            prop_assert_eq!(&merged, &SafetyData::merge(b.clone(), a.clone()));
+            prop_assert_eq!(SafetyData::merge(merged.clone(), a.clone()), merged.clone());
+            if a.epoch > b.epoch {
+                prop_assert_eq!(&merged, &a);
+            }
+
+            // Within an epoch, the rounds are the highest and differing last votes are dropped
+            let b = b.with_epoch(a.epoch);
+            let merged = SafetyData::merge(a.clone(), b.clone());
+            prop_assert_eq!(merged.last_voted_round, a.last_voted_round.max(b.last_voted_round));
+            prop_assert_eq!(merged.preferred_round, a.preferred_round.max(b.preferred_round));
+            if a.last_vote != b.last_vote {
+                prop_assert!(merged.last_vote.is_none());
+            }
+        }
+    }
+}
+
 #[test]
 fn test_safety_data_upgrade() {
     #[derive(Debug, Deserialize, Eq, PartialEq, Serialize, Clone, Default)]
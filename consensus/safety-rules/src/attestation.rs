@@ -0,0 +1,184 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Error;
+use aptos_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    HashValue, Signature,
+};
+use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use aptos_types::transaction::Version;
+use consensus_types::common::{Author, Round};
+use serde::{Deserialize, Serialize};
+
+/// A statement of the current state of SafetyRules, produced for external monitors. The nonce is
+/// chosen by the monitor so that a stale attestation cannot be replayed. As the type has its own
+/// CryptoHasher, its signature can never be mistaken for that of a consensus message.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize, CryptoHasher, BCSCryptoHash)]
+pub struct SafetyStateAttestation {
+    author: Author,
+    epoch: u64,
+    last_voted_round: Round,
+    waypoint_version: Version,
+    nonce: HashValue,
+}
+
+impl SafetyStateAttestation {
+    pub fn new(
+        author: Author,
+        epoch: u64,
+        last_voted_round: Round,
+        waypoint_version: Version,
+        nonce: HashValue,
+    ) -> Self {
+        Self {
+            author,
+            epoch,
+            last_voted_round,
+            waypoint_version,
+            nonce,
+        }
+    }
+
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn last_voted_round(&self) -> Round {
+        self.last_voted_round
+    }
+
+    pub fn waypoint_version(&self) -> Version {
+        self.waypoint_version
+    }
+
+    pub fn nonce(&self) -> HashValue {
+        self.nonce
+    }
+}
+
+/// A SafetyStateAttestation signed with the consensus key.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SignedSafetyStateAttestation {
+    attestation: SafetyStateAttestation,
+    signature: Ed25519Signature,
+}
+
+impl SignedSafetyStateAttestation {
+    pub fn new(attestation: SafetyStateAttestation, signature: Ed25519Signature) -> Self {
+        Self {
+            attestation,
+            signature,
+        }
+    }
+
+    pub fn attestation(&self) -> &SafetyStateAttestation {
+        &self.attestation
+    }
+
+    pub fn signature(&self) -> &Ed25519Signature {
+        &self.signature
+    }
+
+    /// Verifies that the attestation answers the given nonce and was signed by the holder of the
+    /// given consensus key.
+    pub fn verify(&self, public_key: &Ed25519PublicKey, nonce: HashValue) -> Result<(), Error> {
+        if self.attestation.nonce != nonce {
+            return Err(Error::InvalidAttestation(format!(
+                "Expected nonce {}, found {}",
+                nonce, self.attestation.nonce
+            )));
+        }
+        self.signature
+            .verify(&self.attestation, public_key)
+            .map_err(|error| Error::InvalidAttestation(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{
+        ed25519::Ed25519PrivateKey,
+        hash::{CryptoHash, CryptoHasher},
+        PrivateKey, SigningKey, Uniform,
+    };
+    use aptos_types::ledger_info::LedgerInfo;
+    use consensus_types::{
+        block_data::BlockData, timeout::Timeout, timeout_2chain::TimeoutSigningRepr,
+        vote_data::VoteData, vote_proposal::VoteProposal,
+    };
+
+    fn signed_attestation(private_key: &Ed25519PrivateKey) -> SignedSafetyStateAttestation {
+        let attestation =
+            SafetyStateAttestation::new(Author::random(), 3, 7, 42, HashValue::random());
+        let signature = private_key.sign(&attestation);
+        SignedSafetyStateAttestation::new(attestation, signature)
+    }
+
+    #[test]
+    fn test_verify() {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let public_key = private_key.public_key();
+        let signed = signed_attestation(&private_key);
+        let nonce = signed.attestation().nonce();
+        signed.verify(&public_key, nonce).unwrap();
+
+        // A replayed attestation does not answer a new nonce
+        assert!(matches!(
+            signed.verify(&public_key, HashValue::random()),
+            Err(Error::InvalidAttestation(_))
+        ));
+
+        // Nor does it verify with another key, or once its content is altered
+        let other_key = Ed25519PrivateKey::generate_for_testing().public_key();
+        assert!(matches!(
+            signed.verify(&other_key, nonce),
+            Err(Error::InvalidAttestation(_))
+        ));
+        let mut altered = signed;
+        altered.attestation.last_voted_round += 1;
+        assert!(matches!(
+            altered.verify(&public_key, nonce),
+            Err(Error::InvalidAttestation(_))
+        ));
+    }
+
+    #[test]
+    fn test_not_a_consensus_message() {
+        let private_key = Ed25519PrivateKey::generate_for_testing();
+        let public_key = private_key.public_key();
+        let signed = signed_attestation(&private_key);
+        let attestation_bytes = bcs::to_bytes(signed.attestation()).unwrap();
+
+        // Every signed consensus message is prefixed by the seed of its own hasher, so even a
+        // message whose serialization matched the attestation would not share its signature
+        let consensus_seeds = [
+            <BlockData as CryptoHash>::Hasher::seed(),
+            <LedgerInfo as CryptoHash>::Hasher::seed(),
+            <Timeout as CryptoHash>::Hasher::seed(),
+            <TimeoutSigningRepr as CryptoHash>::Hasher::seed(),
+            <VoteData as CryptoHash>::Hasher::seed(),
+            <VoteProposal as CryptoHash>::Hasher::seed(),
+        ];
+        let attestation_seed = SafetyStateAttestationHasher::seed();
+        for seed in consensus_seeds {
+            assert_ne!(seed, attestation_seed);
+            let message = [&seed[..], &attestation_bytes].concat();
+            signed
+                .signature()
+                .verify_arbitrary_msg(&message, &public_key)
+                .unwrap_err();
+        }
+
+        let message = [&attestation_seed[..], &attestation_bytes].concat();
+        signed
+            .signature()
+            .verify_arbitrary_msg(&message, &public_key)
+            .unwrap();
+    }
+}
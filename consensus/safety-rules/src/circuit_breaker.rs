@@ -0,0 +1,118 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    counters,
+    logging::{self, LogEntry, LogEvent},
+    Error,
+};
+use aptos_logger::prelude::*;
+use aptos_secure_storage::{CircuitBreaker as StorageCircuitBreaker, CircuitState};
+use std::time::{Duration, Instant};
+
+/// The value of the storage circuit state metric.
+fn state_as_i64(state: CircuitState) -> i64 {
+    match state {
+        CircuitState::Closed => 0,
+        CircuitState::HalfOpen => 1,
+        CircuitState::Open => 2,
+    }
+}
+
+/// Stops issuing writes to a degraded storage backend, following the state machine of
+/// aptos_secure_storage::CircuitBreaker: writes fail with `StorageCircuitOpen` while the circuit
+/// is open. Any failed write counts as a failure. State changes are exported in the storage
+/// circuit state metric and logged.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CircuitBreaker {
+    breaker: StorageCircuitBreaker,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, reset_timeout: Duration) -> Self {
+        Self {
+            breaker: StorageCircuitBreaker::new(failure_threshold, reset_timeout),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.breaker.state() == CircuitState::Open
+    }
+
+    /// Checks whether a write may be issued at `now`.
+    pub fn check(&mut self, now: Instant) -> Result<(), Error> {
+        let previous = self.breaker.state();
+        let allowed = self.breaker.check(now);
+        self.report(previous);
+        if allowed {
+            Ok(())
+        } else {
+            Err(Error::StorageCircuitOpen)
+        }
+    }
+
+    /// Records the outcome of a write allowed by `check`.
+    pub fn record(&mut self, success: bool, now: Instant) {
+        let previous = self.breaker.state();
+        self.breaker.record(!success, now);
+        self.report(previous);
+    }
+
+    fn report(&self, previous: CircuitState) {
+        let state = self.breaker.state();
+        if previous == state {
+            return;
+        }
+
+        counters::set_state(counters::STORAGE_CIRCUIT_STATE, state_as_i64(state));
+        let schema = logging::SafetyLogSchema::new(LogEntry::StorageCircuit, LogEvent::Update);
+        if self.is_open() {
+            warn!(
+                schema,
+                "Storage circuit {} -> {}, refusing writes for {} ms",
+                previous,
+                state,
+                self.breaker.cooldown().as_millis(),
+            );
+        } else {
+            info!(schema, "Storage circuit {} -> {}", previous, state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut breaker = CircuitBreaker::new(3, Duration::from_millis(100));
+
+        // Failures must be consecutive to open the circuit
+        breaker.check(at(0)).unwrap();
+        breaker.record(false, at(0));
+        breaker.record(false, at(1));
+        breaker.record(true, at(2));
+        breaker.record(false, at(3));
+        breaker.record(false, at(4));
+        assert!(!breaker.is_open());
+        breaker.check(at(5)).unwrap();
+        breaker.record(false, at(5));
+        assert!(breaker.is_open());
+        assert_eq!(breaker.check(at(104)), Err(Error::StorageCircuitOpen));
+
+        // A single trial write is let through: a failed one opens the circuit again, a successful
+        // one closes it
+        breaker.check(at(105)).unwrap();
+        assert!(!breaker.is_open());
+        assert_eq!(breaker.check(at(105)), Err(Error::StorageCircuitOpen));
+        breaker.record(false, at(105));
+        assert_eq!(breaker.check(at(204)), Err(Error::StorageCircuitOpen));
+        breaker.check(at(205)).unwrap();
+        breaker.record(true, at(205));
+        assert_eq!(breaker.breaker.state(), CircuitState::Closed);
+        breaker.check(at(206)).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::RejectionInfo;
 use aptos_types::waypoint::Waypoint;
 use consensus_types::{common::Round, safety_data::SafetyData};
 use serde::{Deserialize, Serialize};
@@ -14,6 +15,9 @@ pub struct ConsensusState {
     safety_data: SafetyData,
     waypoint: Waypoint,
     in_validator_set: bool,
+    /// Absent from the state reported by a safety rules service that predates it.
+    #[serde(default)]
+    last_rejection: Option<RejectionInfo>,
 }
 
 impl Display for ConsensusState {
@@ -37,11 +41,17 @@ impl Display for ConsensusState {
 }
 
 impl ConsensusState {
-    pub fn new(safety_data: SafetyData, waypoint: Waypoint, in_validator_set: bool) -> Self {
+    pub fn new(
+        safety_data: SafetyData,
+        waypoint: Waypoint,
+        in_validator_set: bool,
+        last_rejection: Option<RejectionInfo>,
+    ) -> Self {
         Self {
             safety_data,
             waypoint,
             in_validator_set,
+            last_rejection,
         }
     }
 
@@ -76,8 +86,38 @@ impl ConsensusState {
         self.in_validator_set
     }
 
+    /// The most recent request rejected by a safety rule, if any.
+    pub fn last_rejection(&self) -> Option<&RejectionInfo> {
+        self.last_rejection.as_ref()
+    }
+
     /// Return a copy of the safety data.
     pub fn safety_data(&mut self) -> SafetyData {
         self.safety_data.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_without_last_rejection() {
+        let state = ConsensusState::new(
+            SafetyData::new(2, 3, 1, 0, None),
+            Waypoint::default(),
+            true,
+            None,
+        );
+        let mut value = serde_json::to_value(&state).unwrap();
+        value
+            .as_object_mut()
+            .unwrap()
+            .remove("last_rejection")
+            .unwrap();
+        assert_eq!(
+            serde_json::from_value::<ConsensusState>(value).unwrap(),
+            state
+        );
+    }
+}
@@ -1,22 +1,97 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(test)]
+use aptos_secure_push_metrics::Opts;
 use aptos_secure_push_metrics::{
-    register_histogram_vec, register_int_counter_vec, register_int_gauge_vec, HistogramTimer,
-    HistogramVec, IntCounterVec, IntGaugeVec,
+    gather, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge_vec, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
+use std::collections::HashMap;
 
+pub const CONSENSUS_KEY_AGE_SECONDS: &str = "consensus_key_age_seconds";
 pub const EPOCH: &str = "epoch";
 pub const LAST_VOTED_ROUND: &str = "last_voted_round";
 pub const PREFERRED_ROUND: &str = "preferred_round";
+pub const STORAGE_CIRCUIT_STATE: &str = "storage_circuit_state";
 pub const WAYPOINT_VERSION: &str = "waypoint_version";
 
+/// Prefixes the names of all metrics of this module.
+const METRICS_PREFIX: &str = "aptos_safety_rules_";
+const STATE_GAUGE_NAME: &str = "aptos_safety_rules_state";
+const STATE_GAUGE_HELP: &str = "Current internal state of LSR";
+const STORAGE_BACKEND_NAME: &str = "aptos_safety_rules_storage_backend";
+const STORAGE_BACKEND_HELP: &str = "Set to 1 for the backend of the safety rules storage in use";
+
+/// The fields of the state gauge.
+const STATE_FIELDS: &[&str] = &[
+    CONSENSUS_KEY_AGE_SECONDS,
+    EPOCH,
+    LAST_VOTED_ROUND,
+    PREFERRED_ROUND,
+    STORAGE_CIRCUIT_STATE,
+    WAYPOINT_VERSION,
+];
+
 pub static LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
     register_histogram_vec!(
         "aptos_safety_rules_latency",
         "Time to perform an operation",
-        &["source", "field"]
+        &["source", "field", "backend"]
+    )
+    .unwrap()
+});
+
+/// Sizes of stored values range from a few bytes (e.g., the owner account) to a SafetyData
+/// holding a vote with an epoch change, which grows with the validator set.
+const STORAGE_BYTES_BUCKETS: &[f64] = &[
+    32.0, 64.0, 128.0, 256.0, 512.0, 1024.0, 2048.0, 4096.0, 8192.0, 16384.0, 65536.0, 262144.0,
+    1048576.0,
+];
+
+static STORAGE_READ_BYTES: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_safety_rules_storage_read_bytes",
+        "BCS serialized size of values read from storage",
+        &["key"],
+        STORAGE_BYTES_BUCKETS.to_vec()
+    )
+    .unwrap()
+});
+
+static STORAGE_WRITE_BYTES: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_safety_rules_storage_write_bytes",
+        "BCS serialized size of values written to storage",
+        &["key"],
+        STORAGE_BYTES_BUCKETS.to_vec()
+    )
+    .unwrap()
+});
+
+static KEY_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_safety_rules_key_events",
+        "Number of key imports and rotations",
+        &["key", "event"]
+    )
+    .unwrap()
+});
+
+static PROPOSAL_TIMESTAMP_REJECTIONS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "aptos_safety_rules_proposal_timestamp_rejections",
+        "Number of proposals rejected for a timestamp too far in the future"
+    )
+    .unwrap()
+});
+
+static REJECTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_safety_rules_rejections",
+        "Number of requests rejected by a safety rule",
+        &["reason"]
     )
     .unwrap()
 });
@@ -30,28 +105,202 @@ static QUERY_COUNTER: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
-static STATE_GAUGE: Lazy<IntGaugeVec> = Lazy::new(|| {
-    register_int_gauge_vec!(
-        "aptos_safety_rules_state",
-        "Current internal state of LSR",
-        &["field"]
-    )
-    .unwrap()
+static STORAGE_BACKEND: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(STORAGE_BACKEND_NAME, STORAGE_BACKEND_HELP, &["backend"]).unwrap()
 });
 
+static STATE_GAUGE: Lazy<IntGaugeVec> =
+    Lazy::new(|| register_int_gauge_vec!(STATE_GAUGE_NAME, STATE_GAUGE_HELP, &["field"]).unwrap());
+
+/// Resets all counters, gauges and histograms to zero, for tests that assert on them.
+#[cfg(any(test, feature = "testing"))]
+pub fn reset_all() {
+    LATENCY.reset();
+    STORAGE_READ_BYTES.reset();
+    STORAGE_WRITE_BYTES.reset();
+    KEY_EVENTS.reset();
+    PROPOSAL_TIMESTAMP_REJECTIONS.reset();
+    REJECTIONS.reset();
+    QUERY_COUNTER.reset();
+    STORAGE_BACKEND.reset();
+    STATE_GAUGE.reset();
+}
+
+pub fn increment_key_event(key: &str, event: &str) {
+    KEY_EVENTS.with_label_values(&[key, event]).inc();
+}
+
+#[cfg(test)]
+pub fn get_key_events(key: &str, event: &str) -> u64 {
+    KEY_EVENTS.with_label_values(&[key, event]).get()
+}
+
+pub fn increment_proposal_timestamp_rejections() {
+    PROPOSAL_TIMESTAMP_REJECTIONS.inc();
+}
+
+#[cfg(test)]
+pub fn get_proposal_timestamp_rejections() -> u64 {
+    PROPOSAL_TIMESTAMP_REJECTIONS.get()
+}
+
+pub fn increment_rejection(reason: &str) {
+    REJECTIONS.with_label_values(&[reason]).inc();
+}
+
+#[cfg(test)]
+pub fn get_rejections(reason: &str) -> u64 {
+    REJECTIONS.with_label_values(&[reason]).get()
+}
+
 pub fn increment_query(method: &str, result: &str) {
     QUERY_COUNTER.with_label_values(&[method, result]).inc();
 }
 
 pub fn start_timer(source: &str, field: &str) -> HistogramTimer {
-    LATENCY.with_label_values(&[source, field]).start_timer()
+    LATENCY
+        .with_label_values(&[source, field, ""])
+        .start_timer()
+}
+
+/// Times a storage operation, labeled by the backend of the storage (see `Storage::backend`).
+pub fn start_storage_timer(source: &str, field: &str, backend: &str) -> HistogramTimer {
+    LATENCY
+        .with_label_values(&[source, field, backend])
+        .start_timer()
+}
+
+pub fn observe_read_bytes(key: &str, bytes: usize) {
+    STORAGE_READ_BYTES
+        .with_label_values(&[key])
+        .observe(bytes as f64);
+}
+
+pub fn observe_write_bytes(key: &str, bytes: usize) {
+    STORAGE_WRITE_BYTES
+        .with_label_values(&[key])
+        .observe(bytes as f64);
+}
+
+/// Returns the number of reads and the total number of bytes read for the given key.
+#[cfg(test)]
+pub fn get_read_bytes(key: &str) -> (u64, f64) {
+    let histogram = STORAGE_READ_BYTES.with_label_values(&[key]);
+    (histogram.get_sample_count(), histogram.get_sample_sum())
+}
+
+/// Returns the number of writes and the total number of bytes written for the given key.
+#[cfg(test)]
+pub fn get_write_bytes(key: &str) -> (u64, f64) {
+    let histogram = STORAGE_WRITE_BYTES.with_label_values(&[key]);
+    (histogram.get_sample_count(), histogram.get_sample_sum())
 }
 
 pub fn set_state(field: &str, value: i64) {
     STATE_GAUGE.with_label_values(&[field]).set(value);
 }
 
-#[cfg(any(test))]
-pub fn get_state(field: &str) -> i64 {
-    STATE_GAUGE.with_label_values(&[field]).get()
+/// The gauges that a PersistentSafetyStorage reports its state and storage backend to. These are
+/// the gauges of the process, unless a test gives the storage gauges of its own, which other
+/// tests running concurrently do not update.
+#[derive(Clone)]
+pub struct StateGauges {
+    state: IntGaugeVec,
+    storage_backend: IntGaugeVec,
+}
+
+impl Default for StateGauges {
+    fn default() -> Self {
+        Self {
+            state: STATE_GAUGE.clone(),
+            storage_backend: STORAGE_BACKEND.clone(),
+        }
+    }
+}
+
+impl StateGauges {
+    /// Gauges that are not registered, so that they are neither exported nor shared.
+    #[cfg(test)]
+    pub fn unregistered() -> Self {
+        Self {
+            state: IntGaugeVec::new(Opts::new(STATE_GAUGE_NAME, STATE_GAUGE_HELP), &["field"])
+                .unwrap(),
+            storage_backend: IntGaugeVec::new(
+                Opts::new(STORAGE_BACKEND_NAME, STORAGE_BACKEND_HELP),
+                &["backend"],
+            )
+            .unwrap(),
+        }
+    }
+
+    pub fn set_state(&self, field: &str, value: i64) {
+        self.state.with_label_values(&[field]).set(value);
+    }
+
+    #[cfg(test)]
+    pub fn get_state(&self, field: &str) -> i64 {
+        self.state.with_label_values(&[field]).get()
+    }
+
+    /// Reports the backend of the storage in use (see `Storage::backend`), replacing any backend
+    /// reported before.
+    pub fn set_storage_backend(&self, backend: &str) {
+        self.storage_backend.reset();
+        self.storage_backend.with_label_values(&[backend]).set(1);
+    }
+
+    #[cfg(test)]
+    pub fn get_storage_backend(&self, backend: &str) -> i64 {
+        self.storage_backend.with_label_values(&[backend]).get()
+    }
+}
+
+/// Returns the current value of every counter and gauge of this module. The fields of the state
+/// gauge are keyed by their label (e.g., "epoch"), and other series as in the Prometheus text
+/// format (e.g., `aptos_safety_rules_queries{method="sign_proposal",result="success"}`), with
+/// the number and the sum of the observations of histograms as their `_count` and `_sum` series.
+///
+/// The metrics are global to the process, so the values cover every safety rules instance of the
+/// process, not a single one.
+pub fn snapshot() -> HashMap<String, f64> {
+    // Registers the counter without labels, which is reported even if it was never incremented
+    Lazy::force(&PROPOSAL_TIMESTAMP_REJECTIONS);
+    let mut snapshot: HashMap<_, _> = STATE_FIELDS
+        .iter()
+        .map(|field| {
+            let value = STATE_GAUGE.with_label_values(&[field]).get();
+            (field.to_string(), value as f64)
+        })
+        .collect();
+
+    for family in gather() {
+        let name = family.get_name();
+        if !name.starts_with(METRICS_PREFIX) || name == STATE_GAUGE_NAME {
+            continue;
+        }
+        for metric in family.get_metric() {
+            let labels: Vec<_> = metric
+                .get_label()
+                .iter()
+                .map(|label| format!("{}=\"{}\"", label.get_name(), label.get_value()))
+                .collect();
+            let series = |suffix: &str| {
+                if labels.is_empty() {
+                    format!("{}{}", name, suffix)
+                } else {
+                    format!("{}{}{{{}}}", name, suffix, labels.join(","))
+                }
+            };
+            if metric.has_histogram() {
+                let histogram = metric.get_histogram();
+                snapshot.insert(series("_count"), histogram.get_sample_count() as f64);
+                snapshot.insert(series("_sum"), histogram.get_sample_sum());
+            } else if metric.has_counter() {
+                snapshot.insert(series(""), metric.get_counter().get_value());
+            } else if metric.has_gauge() {
+                snapshot.insert(series(""), metric.get_gauge().get_value());
+            }
+        }
+    }
+    snapshot
 }
@@ -32,7 +32,9 @@ pub enum Error {
     #[error("Data not found in secure storage: {0}")]
     SecureStorageMissingDataError(String),
     #[error("Unexpected error returned by secure storage: {0}")]
-    SecureStorageUnexpectedError(String),
+    SecureStorageError(aptos_secure_storage::Error),
+    #[error("Key version pruned from secure storage, key name: {0}, version: {1}")]
+    KeyVersionPruned(String, String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
     #[error("Validator key not found: {0}")]
@@ -55,6 +57,84 @@ pub enum Error {
     WaypointOutOfDate(u64, u64, u64, u64),
     #[error("Invalid Timeout: {0}")]
     InvalidTimeout(String),
+    #[error("Invalid safety rules storage configuration: {0}")]
+    InvalidStorageConfig(String),
+    #[error("Safety rules storage configuration is missing {0}")]
+    MissingStorageConfig(String),
+    #[error("Validator key {0} cannot be used for signing: {1}")]
+    ValidatorKeyUnusable(String, String),
+    #[error("Safety rules is in standby mode and cannot sign")]
+    StandbyMode,
+    #[error("Signing lease is held by {0} until {1} (Unix ms)")]
+    SigningLeaseHeld(String, u64),
+    #[error("Signing lease expired at {0} (Unix ms) and must be renewed before signing")]
+    SigningLeaseExpired(u64),
+    #[error("Signing lease is not held and must be acquired before signing")]
+    SigningLeaseNotHeld,
+    #[error("Invalid TLS configuration: {0}")]
+    InvalidTlsConfig(String),
+    #[error("Message of {0} bytes exceeds the maximum frame size of {1} bytes")]
+    FrameTooLarge(usize, usize),
+    #[error("Proposal timestamp {0} is too far ahead of the local time {1} (Unix usecs)")]
+    ProposalTimestampTooFarInFuture(u64, u64),
+    #[error("Round {0} does not advance the last voted round {1}")]
+    InvalidRound(u64, u64),
+    #[error("Invalid safety state attestation: {0}")]
+    InvalidAttestation(String),
+    #[error("Invalid public key bundle: {0}")]
+    InvalidPublicKeyBundle(String),
+    #[error("Invalid consensus key master seed: {0}")]
+    InvalidMasterSeed(String),
+    #[error("Consensus key in storage {1} does not match the key in the validator set {0}")]
+    ConsensusKeyMismatch(String, String),
+    #[error("Owner account in storage {1} does not match the expected account {0}")]
+    OwnerAccountMismatch(String, String),
+    #[error("Validator has already voted in epoch {0}, up to round {1}")]
+    VotedInCurrentEpoch(u64, u64),
+    #[error("Storage writes are suspended after repeated failures")]
+    StorageCircuitOpen,
+    #[error("Storage {0} rate limit exceeded")]
+    RateLimited(String),
+}
+
+/// Replaces details that may reveal operator infrastructure in logs.
+const REDACTED: &str = "<redacted>";
+
+impl Error {
+    /// Displays the error for log lines that may reach a broader audience than the operator.
+    /// Details of storage errors, e.g., storage paths or key names, are redacted after their
+    /// first colon, or entirely if they have none.
+    pub fn display_without_secrets(&self) -> String {
+        match self {
+            Self::SecureStorageMissingDataError(details) => {
+                Self::SecureStorageMissingDataError(redact(details)).to_string()
+            }
+            Self::SecureStorageError(error) => format!(
+                "Unexpected error returned by secure storage: {}",
+                redact(&error.to_string())
+            ),
+            Self::KeyVersionPruned(_, version) => {
+                Self::KeyVersionPruned(REDACTED.into(), version.clone()).to_string()
+            }
+            Self::ValidatorKeyNotFound(details) => {
+                Self::ValidatorKeyNotFound(redact(details)).to_string()
+            }
+            Self::ValidatorKeyUnusable(_, details) => {
+                Self::ValidatorKeyUnusable(REDACTED.into(), redact(details)).to_string()
+            }
+            Self::InvalidStorageConfig(details) => {
+                Self::InvalidStorageConfig(redact(details)).to_string()
+            }
+            error => error.to_string(),
+        }
+    }
+}
+
+fn redact(details: &str) -> String {
+    match details.split_once(':') {
+        Some((prefix, _)) => format!("{}: {}", prefix, REDACTED),
+        None => REDACTED.into(),
+    }
 }
 
 impl From<serde_json::Error> for Error {
@@ -65,14 +145,23 @@ impl From<serde_json::Error> for Error {
 
 impl From<aptos_secure_net::Error> for Error {
     fn from(error: aptos_secure_net::Error) -> Self {
-        Self::InternalError(error.to_string())
+        match error {
+            aptos_secure_net::Error::InvalidTlsConfig(error) => Self::InvalidTlsConfig(error),
+            aptos_secure_net::Error::FrameTooLarge(size, max_frame_size) => {
+                Self::FrameTooLarge(size, max_frame_size)
+            }
+            error => Self::InternalError(error.to_string()),
+        }
     }
 }
 
-impl From<aptos_secure_storage::Error> for Error {
-    fn from(error: aptos_secure_storage::Error) -> Self {
+impl Error {
+    /// Converts an error of secure storage. Missing data and pruned key versions are reported as
+    /// such, while other errors are kept as is in `SecureStorageError`, so that callers can match
+    /// on them. Permission errors, i.e., an expired storage token, panic.
+    pub fn from_storage_error(error: aptos_secure_storage::Error) -> Self {
         match error {
-            aptos_secure_storage::Error::PermissionDenied => {
+            aptos_secure_storage::Error::PermissionDenied(_) => {
                 // If a storage error is thrown that indicates a permission failure, we
                 // want to panic immediately to alert an operator that something has gone
                 // wrong. For example, this error is thrown when a storage (e.g., vault)
@@ -83,11 +172,126 @@ impl From<aptos_secure_storage::Error> for Error {
                     error
                 );
             }
+            aptos_secure_storage::Error::KeyVersionPruned(name, version) => {
+                Self::KeyVersionPruned(name, version)
+            }
             aptos_secure_storage::Error::KeyVersionNotFound(_, _)
             | aptos_secure_storage::Error::KeyNotSet(_) => {
                 Self::SecureStorageMissingDataError(error.to_string())
             }
-            _ => Self::SecureStorageUnexpectedError(error.to_string()),
+            // Including unavailability, so that callers can tell whether to retry
+            error => Self::SecureStorageError(error),
         }
     }
 }
+
+impl From<aptos_secure_storage::Error> for Error {
+    fn from(error: aptos_secure_storage::Error) -> Self {
+        Self::from_storage_error(error)
+    }
+}
+
+/// Why `PersistentSafetyStorage::compare_and_swap_safety_data` did not write the safety data.
+#[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
+pub enum CasError {
+    /// The current safety data does not match the expected one, and was left unchanged.
+    #[error("Safety data does not match the expected safety data")]
+    Mismatch,
+    /// The safety data could not be read or written.
+    #[error("Unable to compare and swap safety data: {0}")]
+    Storage(Error),
+}
+
+impl From<Error> for CasError {
+    fn from(error: Error) -> Self {
+        Self::Storage(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_without_secrets() {
+        let error: Error = aptos_secure_storage::Error::KeyVersionNotFound(
+            "/opt/aptos/consensus".into(),
+            "v3".into(),
+        )
+        .into();
+        assert_eq!(
+            error.display_without_secrets(),
+            "Data not found in secure storage: Key version not found, key name: <redacted>"
+        );
+
+        let error: Error = aptos_secure_storage::Error::StorageInUse(
+            "/opt/aptos/secure-data.json".into(),
+            "42".into(),
+        )
+        .into();
+        assert_eq!(
+            error.display_without_secrets(),
+            "Unexpected error returned by secure storage: <redacted>"
+        );
+
+        let error = Error::ValidatorKeyUnusable("consensus".into(), "Key not set: vault".into());
+        assert_eq!(
+            error.display_without_secrets(),
+            "Validator key <redacted> cannot be used for signing: Key not set: <redacted>"
+        );
+
+        let error: Error =
+            aptos_secure_storage::Error::InternalError("vault: timed out".into()).into();
+        assert_eq!(
+            error.display_without_secrets(),
+            "Unexpected error returned by secure storage: Internal error: <redacted>"
+        );
+
+        // Errors without storage details are displayed as is
+        let error = Error::IncorrectEpoch(1, 2);
+        assert_eq!(error.display_without_secrets(), error.to_string());
+    }
+
+    #[test]
+    fn test_from_storage_error() {
+        let error = aptos_secure_storage::Error::VersionMismatch {
+            expected: 1,
+            actual: 2,
+        };
+        assert_eq!(
+            Error::from_storage_error(error.clone()),
+            Error::SecureStorageError(error.clone())
+        );
+        assert_eq!(
+            Error::from(error).to_string(),
+            "Unexpected error returned by secure storage: Version mismatch, expected: 1, actual: 2"
+        );
+
+        // Denied accesses other than an expired token are not fatal
+        let error = aptos_secure_storage::Error::AccessDenied("secure-data.json".into());
+        assert_eq!(
+            Error::from_storage_error(error.clone()),
+            Error::SecureStorageError(error)
+        );
+
+        assert!(matches!(
+            Error::from_storage_error(aptos_secure_storage::Error::KeyNotSet("waypoint".into())),
+            Error::SecureStorageMissingDataError(_)
+        ));
+        assert_eq!(
+            Error::from_storage_error(aptos_secure_storage::Error::KeyVersionPruned(
+                "consensus".into(),
+                "3".into()
+            )),
+            Error::KeyVersionPruned("consensus".into(), "3".into())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_storage_error_permission_denied() {
+        Error::from_storage_error(aptos_secure_storage::Error::PermissionDenied(
+            "denied".into(),
+        ));
+    }
+}
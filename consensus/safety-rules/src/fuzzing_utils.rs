@@ -17,25 +17,31 @@ use aptos_types::{
     proptest_types::{AccountInfoUniverse, BlockInfoGen},
     transaction::SignedTransaction,
     validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
+    waypoint::Waypoint,
 };
 #[cfg(any(test, feature = "fuzzing"))]
 use consensus_types::block::Block;
 use consensus_types::{
     block_data::{BlockData, BlockType},
     quorum_cert::QuorumCert,
+    safety_data::SafetyData,
     timeout::Timeout,
     vote_data::VoteData,
     vote_proposal::{MaybeSignedVoteProposal, VoteProposal},
 };
 use proptest::prelude::*;
 use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+use std::fmt::Debug;
 
 const MAX_BLOCK_SIZE: usize = 10000;
+const MAX_MESSAGE_SIZE: usize = 10000;
 const MAX_NUM_ADDR_TO_VALIDATOR_INFO: usize = 10;
 const MAX_NUM_LEAVES: usize = 20;
 const MAX_NUM_LEDGER_INFO_WITH_SIGS: usize = 10;
 const MAX_NUM_SUBTREE_ROOTS: usize = 20;
 const MAX_PROPOSAL_TRANSACTIONS: usize = 5;
+const MAX_TRAILING_BYTES: usize = 100;
 const NUM_UNIVERSE_ACCOUNTS: usize = 3;
 
 // This generates an arbitrary AccumulatorExtensionProof<TransactionAccumulatorHasher>.
@@ -212,6 +218,19 @@ prop_compose! {
     }
 }
 
+// This generates an arbitrary SafetyData.
+prop_compose! {
+    pub fn arb_safety_data(
+    )(
+        epoch in any::<u64>(),
+        last_voted_round in any::<u64>(),
+        preferred_round in any::<u64>(),
+        one_chain_round in any::<u64>(),
+    ) -> SafetyData {
+        SafetyData::new(epoch, last_voted_round, preferred_round, one_chain_round, None)
+    }
+}
+
 // This generates an arbitrary ValidatorConsensusInfo.
 prop_compose! {
     pub fn arb_validator_consensus_info(
@@ -244,14 +263,90 @@ pub fn arb_safety_rules_input() -> impl Strategy<Value = SafetyRulesInput> {
     ]
 }
 
+// This generates an encoded SafetyRulesInput.
+fn arb_encoded_safety_rules_input() -> impl Strategy<Value = Vec<u8>> {
+    arb_safety_rules_input()
+        .prop_map(|input| serde_json::to_vec(&input).expect("Unable to serialize input"))
+}
+
+// This generates a message as received by the serializer service: a valid encoding, a truncated
+// one, one followed by trailing bytes, or entirely arbitrary bytes.
+pub fn arb_safety_rules_message() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        arb_encoded_safety_rules_input(),
+        (
+            arb_encoded_safety_rules_input(),
+            any::<prop::sample::Index>()
+        )
+            .prop_map(|(message, index)| message[..index.index(message.len())].to_vec()),
+        (
+            arb_encoded_safety_rules_input(),
+            prop::collection::vec(any::<u8>(), 1..MAX_TRAILING_BYTES)
+        )
+            .prop_map(|(mut message, garbage)| {
+                message.extend(garbage);
+                message
+            }),
+        prop::collection::vec(any::<u8>(), 0..MAX_MESSAGE_SIZE),
+    ]
+}
+
+// This generates bytes to decode a value from: its JSON or BCS encoding, possibly truncated, with
+// a flipped byte or followed by trailing bytes, or entirely arbitrary bytes.
+fn arb_encoding<T: Debug + Serialize>(
+    values: impl Strategy<Value = T>,
+) -> impl Strategy<Value = Vec<u8>> {
+    let encodings = (values, any::<bool>()).prop_map(|(value, json)| {
+        if json {
+            serde_json::to_vec(&value).expect("Unable to serialize value")
+        } else {
+            bcs::to_bytes(&value).expect("Unable to serialize value")
+        }
+    });
+    prop_oneof![
+        (
+            encodings,
+            0..4u8,
+            any::<prop::sample::Index>(),
+            prop::collection::vec(any::<u8>(), 1..MAX_TRAILING_BYTES)
+        )
+            .prop_map(|(mut bytes, mutation, index, garbage)| {
+                match mutation {
+                    0 => (),
+                    1 => bytes.truncate(index.index(bytes.len() + 1)),
+                    2 if !bytes.is_empty() => bytes[index.index(bytes.len())] ^= garbage[0] | 1,
+                    _ => bytes.extend(garbage),
+                }
+                bytes
+            }),
+        prop::collection::vec(any::<u8>(), 0..MAX_MESSAGE_SIZE),
+    ]
+}
+
+pub fn arb_safety_rules_input_bytes() -> impl Strategy<Value = Vec<u8>> {
+    arb_encoding(arb_safety_rules_input())
+}
+
+pub fn arb_safety_data_bytes() -> impl Strategy<Value = Vec<u8>> {
+    arb_encoding(arb_safety_data())
+}
+
+pub fn arb_waypoint_bytes() -> impl Strategy<Value = Vec<u8>> {
+    arb_encoding(any::<Waypoint>())
+}
+
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod fuzzing {
     use crate::{error::Error, serializer::SafetyRulesInput, test_utils, TSafetyRules};
     use aptos_crypto::ed25519::Ed25519Signature;
-    use aptos_types::epoch_change::EpochChangeProof;
+    use aptos_types::{
+        epoch_change::EpochChangeProof, validator_signer::ValidatorSigner, waypoint::Waypoint,
+    };
     use consensus_types::{
-        block_data::BlockData, timeout::Timeout, vote::Vote, vote_proposal::MaybeSignedVoteProposal,
+        block_data::BlockData, safety_data::SafetyData, timeout::Timeout, vote::Vote,
+        vote_proposal::MaybeSignedVoteProposal,
     };
+    use serde::de::DeserializeOwned;
 
     pub fn fuzz_initialize(proof: EpochChangeProof) -> Result<(), Error> {
         let mut safety_rules = test_utils::test_safety_rules_uninitialized();
@@ -279,6 +374,20 @@ pub mod fuzzing {
         }
     }
 
+    pub fn fuzz_handle_message_bytes(message: Vec<u8>) -> Result<Vec<u8>, Error> {
+        // Create a safety rules serializer test instance and feed it the raw message
+        let mut serializer_service = test_utils::test_serializer();
+        serializer_service.handle_message(message)
+    }
+
+    pub fn fuzz_set_safety_data(safety_data: SafetyData) -> Result<(), Error> {
+        // Use an in-memory backend to avoid interacting with the file system
+        let mut storage = test_utils::test_storage(&ValidatorSigner::from_int(0));
+        storage.set_safety_data(safety_data.clone())?;
+        assert_eq!(storage.safety_data()?, safety_data);
+        Ok(())
+    }
+
     pub fn fuzz_sign_proposal(block_data: &BlockData) -> Result<Ed25519Signature, Error> {
         let mut safety_rules = test_utils::test_safety_rules();
         safety_rules.sign_proposal(block_data)
@@ -288,6 +397,27 @@ pub mod fuzzing {
         let mut safety_rules = test_utils::test_safety_rules();
         safety_rules.sign_timeout(&timeout)
     }
+
+    pub fn fuzz_decode_safety_rules_input(bytes: &[u8]) -> Result<SafetyRulesInput, Error> {
+        decode(bytes)
+    }
+
+    pub fn fuzz_decode_safety_data(bytes: &[u8]) -> Result<SafetyData, Error> {
+        decode(bytes)
+    }
+
+    pub fn fuzz_decode_waypoint(bytes: &[u8]) -> Result<Waypoint, Error> {
+        decode(bytes)
+    }
+
+    /// Decodes the bytes both as JSON, the encoding of the serializer service and the storage
+    /// backends, and as BCS. Returns the first value that decodes.
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+        let from_json = serde_json::from_slice(bytes).map_err(Error::from);
+        let from_bcs =
+            bcs::from_bytes(bytes).map_err(|error| Error::SerializationError(error.to_string()));
+        from_json.or(from_bcs)
+    }
 }
 
 // Note: these tests ensure that the various fuzzers are maintained (i.e., not broken
@@ -295,16 +425,51 @@ pub mod fuzzing {
 #[cfg(test)]
 mod tests {
     use crate::{
+        error::Error,
         fuzzing::{
-            fuzz_construct_and_sign_vote, fuzz_handle_message, fuzz_initialize, fuzz_sign_proposal,
-            fuzz_sign_timeout,
+            fuzz_construct_and_sign_vote, fuzz_decode_safety_data, fuzz_decode_safety_rules_input,
+            fuzz_decode_waypoint, fuzz_handle_message, fuzz_handle_message_bytes, fuzz_initialize,
+            fuzz_set_safety_data, fuzz_sign_proposal, fuzz_sign_timeout,
         },
         fuzzing_utils::{
-            arb_block_data, arb_epoch_change_proof, arb_maybe_signed_vote_proposal,
-            arb_safety_rules_input, arb_timeout,
+            arb_block_data, arb_encoded_safety_rules_input, arb_epoch_change_proof,
+            arb_maybe_signed_vote_proposal, arb_safety_data, arb_safety_data_bytes,
+            arb_safety_rules_input, arb_safety_rules_input_bytes, arb_safety_rules_message,
+            arb_timeout, arb_waypoint_bytes,
         },
     };
     use proptest::prelude::*;
+    use std::{fs, path::Path};
+
+    // Replays the corpus of each decoding fuzz target, which holds inputs that once crashed or
+    // are likely to. Inputs named valid_* must decode, all others must be rejected.
+    #[test]
+    fn decode_corpus() {
+        let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+        let targets: [(&str, fn(&[u8]) -> bool); 3] = [
+            ("SafetyRulesDecodeInput", |bytes| {
+                fuzz_decode_safety_rules_input(bytes).is_ok()
+            }),
+            ("SafetyRulesDecodeSafetyData", |bytes| {
+                fuzz_decode_safety_data(bytes).is_ok()
+            }),
+            ("SafetyRulesDecodeWaypoint", |bytes| {
+                fuzz_decode_waypoint(bytes).is_ok()
+            }),
+        ];
+        for (target, decodes) in targets {
+            for entry in fs::read_dir(corpus.join(target)).unwrap() {
+                let path = entry.unwrap().path();
+                let valid = path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .starts_with("valid_");
+                let bytes = fs::read(&path).unwrap();
+                assert_eq!(decodes(&bytes), valid, "{}", path.display());
+            }
+        }
+    }
 
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(10))]
@@ -314,6 +479,52 @@ mod tests {
             let _ = fuzz_handle_message(input);
         }
 
+        #[test]
+        fn handle_message_bytes_proptest(input in arb_safety_rules_message()) {
+            let _ = fuzz_handle_message_bytes(input);
+        }
+
+        #[test]
+        fn handle_truncated_message_proptest(
+            input in arb_encoded_safety_rules_input(),
+            index in any::<prop::sample::Index>(),
+        ) {
+            let input = input[..index.index(input.len())].to_vec();
+            prop_assert!(matches!(
+                fuzz_handle_message_bytes(input),
+                Err(Error::SerializationError(_))
+            ));
+        }
+
+        #[test]
+        fn handle_message_with_trailing_garbage_proptest(
+            mut input in arb_encoded_safety_rules_input(),
+            garbage in prop::collection::vec(any::<u8>(), 0..100),
+        ) {
+            // Trailing whitespace is valid JSON, so always start the garbage with something else
+            input.push(b'x');
+            input.extend(garbage);
+            prop_assert!(matches!(
+                fuzz_handle_message_bytes(input),
+                Err(Error::SerializationError(_))
+            ));
+        }
+
+        #[test]
+        fn decode_safety_rules_input_proptest(input in arb_safety_rules_input_bytes()) {
+            let _ = fuzz_decode_safety_rules_input(&input);
+        }
+
+        #[test]
+        fn decode_safety_data_proptest(input in arb_safety_data_bytes()) {
+            let _ = fuzz_decode_safety_data(&input);
+        }
+
+        #[test]
+        fn decode_waypoint_proptest(input in arb_waypoint_bytes()) {
+            let _ = fuzz_decode_waypoint(&input);
+        }
+
         #[test]
         fn initialize_proptest(input in arb_epoch_change_proof()) {
             let _ = fuzz_initialize(input);
@@ -324,6 +535,11 @@ mod tests {
             let _ = fuzz_construct_and_sign_vote(input);
         }
 
+        #[test]
+        fn set_safety_data_proptest(input in arb_safety_data()) {
+            let _ = fuzz_set_safety_data(input);
+        }
+
         #[test]
         fn sign_proposal_proptest(input in arb_block_data()) {
             let _ = fuzz_sign_proposal(&input);
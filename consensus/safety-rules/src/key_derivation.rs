@@ -0,0 +1,109 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Derivation of per-epoch consensus keys from a master seed, so that each epoch can register a
+//! fresh consensus key on-chain and a compromised key only exposes its own epoch.
+//!
+//! The key for an epoch is HKDF-SHA3-256 (RFC 5869) over the master seed, with
+//! `CONSENSUS_KEY_DERIVATION_SALT` as salt and the little-endian epoch as info. This must never
+//! change, or validators would derive keys other than those registered on-chain; the test vectors
+//! below pin it down.
+
+use crate::Error;
+use aptos_crypto::{ed25519::Ed25519PrivateKey, hkdf::Hkdf};
+use sha3::Sha3_256;
+use std::convert::TryFrom;
+use zeroize::Zeroizing;
+
+pub const CONSENSUS_KEY_DERIVATION_SALT: &[u8] = b"APTOS::SafetyRules::ConsensusKeyDerivation";
+
+/// The minimum length of a master seed, i.e., the security level of the derived keys.
+pub const MIN_MASTER_SEED_LENGTH: usize = 32;
+
+const DERIVED_KEY_LENGTH: usize = 32;
+
+pub fn check_master_seed(master_seed: &[u8]) -> Result<(), Error> {
+    if master_seed.len() < MIN_MASTER_SEED_LENGTH {
+        return Err(Error::InvalidMasterSeed(format!(
+            "Expected at least {} bytes, found {}",
+            MIN_MASTER_SEED_LENGTH,
+            master_seed.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Derives the consensus key of the given epoch. Intermediate key material is zeroized.
+pub fn derive_consensus_key(master_seed: &[u8], epoch: u64) -> Result<Ed25519PrivateKey, Error> {
+    check_master_seed(master_seed)?;
+    let key_bytes = Zeroizing::new(
+        Hkdf::<Sha3_256>::extract_then_expand(
+            Some(CONSENSUS_KEY_DERIVATION_SALT),
+            master_seed,
+            Some(&epoch.to_le_bytes()),
+            DERIVED_KEY_LENGTH,
+        )
+        .map_err(|error| Error::InvalidMasterSeed(error.to_string()))?,
+    );
+    Ed25519PrivateKey::try_from(&key_bytes[..])
+        .map_err(|error| Error::InvalidMasterSeed(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{ed25519::Ed25519PublicKey, PrivateKey, ValidCryptoMaterialStringExt};
+
+    // (epoch, private key, public key) for the master seed 0x00..0x1f
+    const TEST_VECTORS: [(u64, &str, &str); 4] = [
+        (
+            0,
+            "078b09cafbd61f4b132f3ece0eeb2753d8388aa64abaf32b0ead829ec6b7bf00",
+            "f17c14a4e8d208342ba97d36afcac7339b5714ab7dc41dfe33acb0784515aced",
+        ),
+        (
+            1,
+            "fdf531822fab1eb8e0181138392e55c35718361fd9bbddfe8d974d5c6b74e366",
+            "0a244cea99572bd8af1554f8b29b0f54a7fe19564cc10bef7c2f6e49c13cfd27",
+        ),
+        (
+            2,
+            "12b65e5355eb14c251a5b83bef09576e3765240545e35b478dd7ac366d5950c1",
+            "a180ff9bd80a61422ea71e251678220b32cfab25324668248a73735346c564d0",
+        ),
+        (
+            u64::MAX,
+            "1f8bc3d8815fbc98ec79ece00453729cb5747c6a47ee9f779dcfd87a7f4ae3d1",
+            "46b326cab16a8918129096e502383b7592b3ac8f9ed0b080660a1a8bff21a0b1",
+        ),
+    ];
+
+    fn test_master_seed() -> Vec<u8> {
+        (0..32).collect()
+    }
+
+    #[test]
+    fn test_vectors() {
+        let master_seed = test_master_seed();
+        for (epoch, private_key, public_key) in TEST_VECTORS {
+            let derived = derive_consensus_key(&master_seed, epoch).unwrap();
+            assert_eq!(
+                derived,
+                Ed25519PrivateKey::from_encoded_string(private_key).unwrap()
+            );
+            assert_eq!(
+                derived.public_key(),
+                Ed25519PublicKey::from_encoded_string(public_key).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_short_master_seed() {
+        let master_seed = vec![0; MIN_MASTER_SEED_LENGTH - 1];
+        assert!(matches!(
+            derive_consensus_key(&master_seed, 1),
+            Err(Error::InvalidMasterSeed(_))
+        ));
+    }
+}
@@ -3,26 +3,46 @@
 
 #![forbid(unsafe_code)]
 
+mod attestation;
+mod circuit_breaker;
 mod configurable_validator_signer;
 mod consensus_state;
 mod counters;
 mod error;
+mod key_derivation;
 mod local_client;
 mod logging;
+mod observer;
 mod persistent_safety_storage;
+mod persistent_safety_storage_builder;
 mod process;
+mod public_key_bundle;
+mod rejection;
 mod remote_service;
 mod safety_rules;
 mod safety_rules_2chain;
 mod safety_rules_manager;
 mod serializer;
+mod signing_lease;
+mod storage_proxy;
 mod t_safety_rules;
 mod thread;
 
 pub use crate::{
-    consensus_state::ConsensusState, error::Error,
-    persistent_safety_storage::PersistentSafetyStorage, process::Process,
-    safety_rules::SafetyRules, safety_rules_manager::SafetyRulesManager,
+    attestation::{SafetyStateAttestation, SignedSafetyStateAttestation},
+    circuit_breaker::CircuitBreaker,
+    consensus_state::ConsensusState,
+    error::{CasError, Error},
+    observer::ConsensusObserver,
+    persistent_safety_storage::PersistentSafetyStorage,
+    persistent_safety_storage_builder::{PersistentSafetyStorageBuilder, RetryPolicy},
+    process::Process,
+    public_key_bundle::{PublicKeyBundle, PublicKeyBundleSigningRepr},
+    rejection::{RejectionInfo, RejectionReason},
+    safety_rules::SafetyRules,
+    safety_rules_manager::SafetyRulesManager,
+    signing_lease::{SigningLease, SIGNING_LEASE},
+    storage_proxy::StorageProxy,
     t_safety_rules::TSafetyRules,
 };
 
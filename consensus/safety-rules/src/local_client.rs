@@ -1,8 +1,8 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{ConsensusState, Error, SafetyRules, TSafetyRules};
-use aptos_crypto::ed25519::Ed25519Signature;
+use crate::{ConsensusState, Error, SafetyRules, SignedSafetyStateAttestation, TSafetyRules};
+use aptos_crypto::{ed25519::Ed25519Signature, HashValue};
 use aptos_infallible::RwLock;
 use aptos_types::{
     epoch_change::EpochChangeProof,
@@ -83,4 +83,11 @@ impl TSafetyRules for LocalClient {
             .write()
             .sign_commit_vote(ledger_info, new_ledger_info)
     }
+
+    fn attest_safety_state(
+        &mut self,
+        nonce: HashValue,
+    ) -> Result<SignedSafetyStateAttestation, Error> {
+        self.internal.write().attest_safety_state(nonce)
+    }
 }
@@ -3,7 +3,6 @@
 
 use crate::Error;
 use aptos_logger::Schema;
-use aptos_types::waypoint::Waypoint;
 use consensus_types::common::{Author, Round};
 use serde::Serialize;
 
@@ -17,8 +16,10 @@ pub struct SafetyLogSchema<'a> {
     epoch: Option<u64>,
     #[schema(display)]
     error: Option<&'a Error>,
-    waypoint: Option<Waypoint>,
+    waypoint: Option<String>,
     author: Option<Author>,
+    key_name: Option<&'a str>,
+    key_fingerprint: Option<String>,
 }
 
 impl<'a> SafetyLogSchema<'a> {
@@ -33,6 +34,8 @@ impl<'a> SafetyLogSchema<'a> {
             error: None,
             waypoint: None,
             author: None,
+            key_name: None,
+            key_fingerprint: None,
         }
     }
 }
@@ -40,19 +43,25 @@ impl<'a> SafetyLogSchema<'a> {
 #[derive(Clone, Copy, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum LogEntry {
+    AttestSafetyState,
     ConsensusState,
     ConstructAndSignVote,
     ConstructAndSignVoteTwoChain,
     Epoch,
     Initialize,
     KeyReconciliation,
+    KeyRotation,
     LastVotedRound,
     OneChainRound,
+    OwnerAccount,
     PreferredRound,
+    SafetyDataMigration,
     SignProposal,
     SignTimeout,
     SignTimeoutWithQC,
+    Standby,
     State,
+    StorageCircuit,
     Waypoint,
     SignCommitVote,
 }
@@ -60,6 +69,7 @@ pub enum LogEntry {
 impl LogEntry {
     pub fn as_str(&self) -> &'static str {
         match self {
+            LogEntry::AttestSafetyState => "attest_safety_state",
             LogEntry::ConsensusState => "consensus_state",
             LogEntry::ConstructAndSignVote => "construct_and_sign_vote",
             LogEntry::ConstructAndSignVoteTwoChain => "construct_and_sign_vote_2chain",
@@ -67,12 +77,17 @@ impl LogEntry {
             LogEntry::Initialize => "initialize",
             LogEntry::LastVotedRound => "last_voted_round",
             LogEntry::KeyReconciliation => "key_reconciliation",
+            LogEntry::KeyRotation => "key_rotation",
             LogEntry::OneChainRound => "one_chain_round",
+            LogEntry::OwnerAccount => "owner_account",
             LogEntry::PreferredRound => "preferred_round",
+            LogEntry::SafetyDataMigration => "safety_data_migration",
             LogEntry::SignProposal => "sign_proposal",
             LogEntry::SignTimeout => "sign_timeout",
             LogEntry::SignTimeoutWithQC => "sign_timeout_with_qc",
+            LogEntry::Standby => "standby",
             LogEntry::State => "state",
+            LogEntry::StorageCircuit => "storage_circuit",
             LogEntry::Waypoint => "waypoint",
             LogEntry::SignCommitVote => "sign_commit_vote",
         }
@@ -83,6 +98,7 @@ impl LogEntry {
 #[serde(rename_all = "snake_case")]
 pub enum LogEvent {
     Error,
+    Import,
     Request,
     Success,
     Update,
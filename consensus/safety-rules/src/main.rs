@@ -22,6 +22,10 @@ fn main() {
         eprintln!("Unable to read provided config: {}", e);
         process::exit(1);
     });
+    if let Err(e) = config.validate() {
+        eprintln!("Invalid config: {}", e);
+        process::exit(1);
+    }
 
     aptos_logger::Logger::new()
         .channel_size(config.logger.chan_size)
@@ -35,6 +39,9 @@ fn main() {
     crash_handler::setup_panic_handler();
     let _mp = MetricsPusher::start();
 
-    let mut service = Process::new(config);
-    service.start();
+    let result = Process::new(config).and_then(|mut service| service.start());
+    if let Err(e) = result {
+        eprintln!("Unable to start SafetyRules: {}", e);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,21 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_crypto::ed25519::Ed25519PublicKey;
+use aptos_types::waypoint::Waypoint;
+use consensus_types::safety_data::SafetyData;
+
+/// Observes the changes PersistentSafetyStorage makes to the safety state, e.g., to export them
+/// to an external monitoring system. Each method is called once the change has been persisted,
+/// on the thread that made it and while the storage is borrowed, so implementations should return
+/// quickly and must not call back into the storage. Every method defaults to doing nothing.
+pub trait ConsensusObserver {
+    /// The safety data was written, e.g., after a vote or a timeout was signed.
+    fn on_safety_data_updated(&self, _safety_data: &SafetyData) {}
+
+    /// A new waypoint was written.
+    fn on_waypoint_updated(&self, _waypoint: &Waypoint) {}
+
+    /// The consensus key was rotated, and `public_key` is its new version.
+    fn on_key_rotation(&self, _public_key: &Ed25519PublicKey) {}
+}
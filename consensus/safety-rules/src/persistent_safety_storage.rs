@@ -2,20 +2,88 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    counters,
+    circuit_breaker::CircuitBreaker,
+    counters, key_derivation,
     logging::{self, LogEntry, LogEvent},
-    Error,
+    observer::ConsensusObserver,
+    safety_rules::DEFAULT_MAX_PROPOSAL_TIMESTAMP_SKEW_MS,
+    signing_lease::{SigningLease, DEFAULT_SIGNING_LEASE_DURATION},
+    CasError, Error, PersistentSafetyStorageBuilder, PublicKeyBundle, PublicKeyBundleSigningRepr,
 };
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
+    PrivateKey, Signature, SigningKey,
 };
-use aptos_global_constants::{CONSENSUS_KEY, EXECUTION_KEY, OWNER_ACCOUNT, SAFETY_DATA, WAYPOINT};
+use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use aptos_global_constants::{
+    CONSENSUS_KEY, CONSENSUS_KEY_CREATED_AT, EXECUTION_KEY, OWNER_ACCOUNT, SAFETY_DATA, WAYPOINT,
+};
+use aptos_infallible::Mutex;
 use aptos_logger::prelude::*;
-use aptos_secure_storage::{CryptoStorage, KVStorage, Storage};
+use aptos_secure_push_metrics::HistogramTimer;
+use aptos_secure_storage::{
+    ArchiveReport, Capability, CryptoStorage, GetResponse, ImportResult, InMemoryStorage,
+    KVStorage, KeyPolicy, MigrationReport, Passphrase, Storage, ValueMetadata,
+};
+use aptos_time_service::{TimeService, TimeServiceTrait};
 use aptos_types::waypoint::Waypoint;
-use consensus_types::{common::Author, safety_data::SafetyData};
-use serde::Serialize;
+use consensus_types::{
+    common::{Author, Round},
+    safety_data::SafetyData,
+    vote::Vote,
+};
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use std::{cell::Cell, collections::HashMap, convert::TryFrom, sync::Arc, time::Duration};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+/// Marks storage whose keys and accounts have been fully initialized. It is written last, so its
+/// absence means that a previous initialization attempt may have been interrupted.
+const INITIALIZATION_COMPLETE: &str = "safety_rules_initialized";
+
+/// The names of the keys held by safety rules. All other entries hold values.
+const KEY_NAMES: &[&str] = &[CONSENSUS_KEY, EXECUTION_KEY];
+
+/// Holds the list of epochs for which a safety data backup is retained.
+const SAFETY_DATA_BACKUPS: &str = "safety_data_backups";
+
+/// The maximum number of consensus key versions held in the in-memory key cache.
+const CONSENSUS_KEY_CACHE_SIZE: usize = 4;
+
+/// Holds the master seed from which per-epoch consensus keys are derived, if enabled.
+const CONSENSUS_KEY_MASTER_SEED: &str = "consensus_key_master_seed";
+
+/// The number of most recent epochs whose derived consensus keys remain addressable.
+const DERIVED_CONSENSUS_KEYS: usize = 2;
+
+/// The largest validator set, as bounded by the framework.
+const MAX_VALIDATOR_SET_SIZE: usize = 65536;
+
+/// An upper bound on the size of a validator in an epoch state (its account address, public key
+/// and voting power), or of its signature in a quorum certificate, as counted by
+/// BoundedSafetyData.
+const MAX_VALIDATOR_SIZE: usize = 256;
+
+/// The block infos of the last vote and of the quorum certificate of its timeout, any of which
+/// may carry the next validator set, e.g., for the blocks that end an epoch.
+const MAX_EPOCH_STATES: usize = 6;
+
+/// The largest SafetyData accepted from storage: that of an honest vote on an epoch-ending block
+/// of the largest validator set, with its timeout and quorum certificate signed by every validator.
+/// Everything else in SafetyData is small and of bounded size.
+const MAX_SAFETY_DATA_BCS_SIZE: usize =
+    (MAX_EPOCH_STATES + 1) * MAX_VALIDATOR_SET_SIZE * MAX_VALIDATOR_SIZE + 64 * 1024;
+
+/// Signed to check that a pre-provisioned key is usable. It has its own hasher, so that the
+/// signature cannot be mistaken for one over consensus data.
+#[derive(CryptoHasher, BCSCryptoHash, Deserialize, Serialize)]
+struct KeyProbe {
+    name: String,
+}
 
 /// SafetyRules needs an abstract storage interface to act as a common utility for storing
 /// persistent data to local disk, cloud, secrets managers, or even memory (for tests)
@@ -25,233 +93,2041 @@ use serde::Serialize;
 /// only ever be used by safety rules, we maintain an in-memory copy to avoid issuing reads
 /// to the internal storage if the SafetyData hasn't changed. On writes, we update the
 /// cache and internal storage.
+///
+/// Note: consensus_key_cache memoizes successful consensus key version lookups, as the same
+/// version is requested for an entire epoch. It is bounded to CONSENSUS_KEY_CACHE_SIZE entries
+/// and cleared whenever the internal storage may have been modified out of band.
+///
+/// Note: derived_consensus_keys holds the consensus keys derived from the master seed for the two
+/// most recent epochs, if per-epoch keys are enabled. They are never written to storage, as they
+/// can always be derived again.
+///
+/// Note: consensus_key_created_at is read from storage once and reused to report the age of
+/// the consensus key on every safety data read, until the consensus key cache is invalidated.
+///
+/// Note: whenever the epoch in SafetyData increases, a SafetyDataBackup is written under an
+/// epoch-suffixed key, and only the most recent max_safety_data_backups epochs are retained.
+///
+/// Note: signing_lease is the lease last acquired in signing_lease_store (or the internal storage),
+/// on behalf of signing_lease_holder (or the author). Nothing can be signed unless it is held and
+/// has not expired, see renew_signing_lease.
+///
+/// Note: SafetyData read from storage is bounded to MAX_SAFETY_DATA_BCS_SIZE while it is decoded,
+/// so that a faulty backend cannot make safety rules decode and cache arbitrarily large data.
+///
+/// Note: time_service is the clock of safety rules, it dates keys and leases and is used by
+/// SafetyRules to check that proposal timestamps are at most max_proposal_timestamp_skew_ms
+/// ahead.
+///
+/// Note: circuit_breaker, if set, refuses writes for a while once the internal storage failed
+/// repeatedly, rather than issuing more writes to a degraded backend.
+///
+/// Note: state_gauges are the gauges the state and storage backend are reported to, those of the
+/// process unless a test sets its own.
+///
+/// Note: observer, if set, is notified of every safety data, waypoint and consensus key change
+/// once it has been persisted.
 pub struct PersistentSafetyStorage {
     enable_cached_safety_data: bool,
     cached_safety_data: Option<SafetyData>,
+    consensus_key_cache: Mutex<Vec<(Ed25519PublicKey, Ed25519PrivateKey)>>,
+    derived_consensus_keys: Mutex<Vec<(u64, Ed25519PrivateKey)>>,
+    consensus_key_created_at: Mutex<Option<Option<u64>>>,
+    consensus_key_max_age_secs: Option<u64>,
+    max_safety_data_backups: usize,
+    standby: bool,
+    signing_lease: Option<SigningLease>,
+    signing_lease_store: Option<Storage>,
+    signing_lease_holder: Option<String>,
+    signing_lease_duration: Duration,
+    time_service: TimeService,
+    max_proposal_timestamp_skew_ms: u64,
+    circuit_breaker: Option<CircuitBreaker>,
+    observer: Option<Arc<dyn ConsensusObserver + Send + Sync>>,
+    state_gauges: counters::StateGauges,
     internal_store: Storage,
 }
 
+/// The subset of SafetyData required by callers that only need the epoch or last voted round.
+/// Unknown fields (e.g., the last vote) are skipped during deserialization.
+#[derive(Deserialize)]
+struct SafetyDataSummary {
+    epoch: u64,
+    last_voted_round: u64,
+}
+
+/// A layout in which an earlier release stored SafetyData.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum LegacySafetyDataLayout {
+    /// Before the one-chain round was tracked for the 2-chain safety rules.
+    V1,
+}
+
+/// SafetyData as stored in the V1 layout.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SafetyDataV1 {
+    epoch: u64,
+    last_voted_round: u64,
+    preferred_round: u64,
+    last_vote: Option<Vote>,
+}
+
+impl From<SafetyDataV1> for SafetyData {
+    fn from(legacy: SafetyDataV1) -> Self {
+        SafetyData::new(
+            legacy.epoch,
+            legacy.last_voted_round,
+            legacy.preferred_round,
+            0,
+            legacy.last_vote,
+        )
+    }
+}
+
+/// The last-known-good safety state at the start of an epoch.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SafetyDataBackup {
+    pub safety_data: SafetyData,
+    pub waypoint: Waypoint,
+    pub consensus_key: Ed25519PublicKey,
+}
+
 impl PersistentSafetyStorage {
     /// Use this to instantiate a PersistentStorage for a new data store, one that has no
-    /// SafetyRules values set.
+    /// SafetyRules values set. Prefer PersistentSafetyStorageBuilder::build for new code.
+    #[tracing::instrument(skip_all)]
     pub fn initialize(
-        mut internal_store: Storage,
+        internal_store: Storage,
         author: Author,
         consensus_private_key: Ed25519PrivateKey,
         execution_private_key: Ed25519PrivateKey,
         waypoint: Waypoint,
         enable_cached_safety_data: bool,
     ) -> Self {
+        PersistentSafetyStorageBuilder::new()
+            .author(author)
+            .consensus_key(consensus_private_key)
+            .execution_key(execution_private_key)
+            .waypoint(waypoint)
+            .enable_caching(enable_cached_safety_data)
+            .build(internal_store)
+            .expect("Unable to initialize PersistentSafetyStorage")
+    }
+
+    pub(crate) fn try_initialize(
+        mut internal_store: Storage,
+        author: Author,
+        consensus_private_key: Ed25519PrivateKey,
+        execution_private_key: Ed25519PrivateKey,
+        waypoint: Waypoint,
+        enable_cached_safety_data: bool,
+        time_service: TimeService,
+    ) -> Result<Self, Error> {
+        Self::lock_storage(&mut internal_store)?;
+
         // Initialize the keys and accounts
         Self::initialize_keys_and_accounts(
             &mut internal_store,
             author,
             consensus_private_key,
             execution_private_key,
+            time_service.now_unix_time().as_millis() as u64,
+        )?;
+
+        Self::initialize_safety_data(
+            internal_store,
+            waypoint,
+            enable_cached_safety_data,
+            time_service,
+        )
+    }
+
+    /// Use this to instantiate a PersistentStorage for a new data store whose consensus key, and
+    /// optionally execution key, were created directly in the backend by provisioning tooling,
+    /// so that the private keys never pass through this process. The keys must exist and be able
+    /// to sign; only the owner account, safety data and waypoint are written.
+    #[tracing::instrument(skip_all)]
+    pub fn initialize_with_existing_keys(
+        mut internal_store: Storage,
+        author: Author,
+        waypoint: Waypoint,
+        require_execution_key: bool,
+        enable_cached_safety_data: bool,
+        time_service: TimeService,
+    ) -> Result<Self, Error> {
+        Self::lock_storage(&mut internal_store)?;
+
+        Self::verify_existing_key(&internal_store, CONSENSUS_KEY)?;
+        if require_execution_key {
+            Self::verify_existing_key(&internal_store, EXECUTION_KEY)?;
+        }
+        internal_store.set(OWNER_ACCOUNT, author)?;
+        observe_write_bytes(OWNER_ACCOUNT, &author);
+        internal_store.set(INITIALIZATION_COMPLETE, true)?;
+
+        Self::initialize_safety_data(
+            internal_store,
+            waypoint,
+            enable_cached_safety_data,
+            time_service,
         )
-        .expect("Unable to initialize keys and accounts in storage");
+    }
+
+    /// Use this to instantiate a PersistentStorage for a new data store whose consensus and
+    /// execution keys are generated in the backend as non-exportable keys, so that the private
+    /// keys never pass through this process. Signing is then always delegated to the backend,
+    /// which requires `export_consensus_key` to be disabled in the safety rules config.
+    #[tracing::instrument(skip_all)]
+    pub fn initialize_with_generated_keys(
+        mut internal_store: Storage,
+        author: Author,
+        waypoint: Waypoint,
+        enable_cached_safety_data: bool,
+        time_service: TimeService,
+    ) -> Result<Self, Error> {
+        Self::lock_storage(&mut internal_store)?;
+
+        if !internal_store.supports_non_exportable_keys() {
+            warn!(
+                logging::SafetyLogSchema::new(LogEntry::Initialize, LogEvent::Update),
+                "The storage backend holds non-exportable keys in process memory",
+            );
+        }
+        for name in KEY_NAMES {
+            let _timer = counters::start_storage_timer("generate", name, internal_store.backend());
+            internal_store.generate_key(name)?;
+        }
+
+        Self::initialize_with_existing_keys(
+            internal_store,
+            author,
+            waypoint,
+            true,
+            enable_cached_safety_data,
+            time_service,
+        )
+    }
 
+    fn initialize_safety_data(
+        internal_store: Storage,
+        waypoint: Waypoint,
+        enable_cached_safety_data: bool,
+        time_service: TimeService,
+    ) -> Result<Self, Error> {
         // Create the new persistent safety storage
         let safety_data = SafetyData::new(1, 0, 0, 0, None);
         let mut persisent_safety_storage = Self {
             enable_cached_safety_data,
             cached_safety_data: Some(safety_data.clone()),
+            consensus_key_cache: Mutex::new(Vec::new()),
+            derived_consensus_keys: Mutex::new(Vec::new()),
+            consensus_key_created_at: Mutex::new(None),
+            consensus_key_max_age_secs: None,
+            max_safety_data_backups: 0,
+            standby: false,
+            signing_lease: None,
+            signing_lease_store: None,
+            signing_lease_holder: None,
+            signing_lease_duration: DEFAULT_SIGNING_LEASE_DURATION,
+            time_service,
+            max_proposal_timestamp_skew_ms: DEFAULT_MAX_PROPOSAL_TIMESTAMP_SKEW_MS,
+            circuit_breaker: None,
+            observer: None,
+            state_gauges: counters::StateGauges::default(),
             internal_store,
         };
 
         // Initialize the safety data and waypoint
-        persisent_safety_storage
-            .set_safety_data(safety_data)
-            .expect("Unable to initialize safety data");
-        persisent_safety_storage
-            .set_waypoint(&waypoint)
-            .expect("Unable to initialize waypoint");
+        persisent_safety_storage.set_safety_data(safety_data)?;
+        persisent_safety_storage.set_waypoint(&waypoint)?;
+
+        Ok(persisent_safety_storage)
+    }
+
+    /// Checks that a key provisioned outside of this process exists and produces signatures
+    /// that verify against its public key.
+    fn verify_existing_key(internal_store: &Storage, name: &str) -> Result<(), Error> {
+        let public_key = match internal_store.get_public_key(name) {
+            Ok(response) => response.public_key,
+            Err(aptos_secure_storage::Error::KeyNotSet(_)) => {
+                return Err(Error::ValidatorKeyNotFound(format!(
+                    "{} must be created in secure storage before initialization",
+                    name
+                )))
+            }
+            Err(error) => return Err(error.into()),
+        };
 
-        persisent_safety_storage
+        let probe = KeyProbe {
+            name: name.to_string(),
+        };
+        let signature = internal_store
+            .sign(name, &probe)
+            .map_err(|error| Error::ValidatorKeyUnusable(name.to_string(), error.to_string()))?;
+        signature
+            .verify(&probe, &public_key)
+            .map_err(|error| Error::ValidatorKeyUnusable(name.to_string(), error.to_string()))
     }
 
-    fn initialize_keys_and_accounts(
-        internal_store: &mut Storage,
+    fn initialize_keys_and_accounts<S: KVStorage + CryptoStorage>(
+        internal_store: &mut S,
         author: Author,
         consensus_private_key: Ed25519PrivateKey,
         execution_private_key: Ed25519PrivateKey,
+        created_at_ms: u64,
     ) -> Result<(), Error> {
-        let result = internal_store.import_private_key(CONSENSUS_KEY, consensus_private_key);
         // Attempting to re-initialize existing storage. This can happen in environments like
         // forge. Rather than be rigid here, leave it up to the developer to detect
-        // inconsistencies or why they did not reset storage between rounds. Do not repeat the
-        // checks again below, because it is just too strange to have a partially configured
-        // storage.
-        if let Err(aptos_secure_storage::Error::KeyAlreadyExists(_)) = result {
+        // inconsistencies or why they did not reset storage between rounds. Storage initialized
+        // before the completion marker existed is recognized by the owner account, which has
+        // always been written last.
+        if Self::is_set::<S, bool>(internal_store, INITIALIZATION_COMPLETE)?
+            || Self::is_set::<S, Author>(internal_store, OWNER_ACCOUNT)?
+        {
             warn!("Attempted to re-initialize existing storage");
+            internal_store.set(INITIALIZATION_COMPLETE, true)?;
             return Ok(());
         }
 
-        internal_store.import_private_key(EXECUTION_KEY, execution_private_key)?;
+        // Any entries found below were left behind by an interrupted initialization, so it is
+        // safe to redo each step. The completion marker must be written last.
+        let consensus_key = Self::import_key(internal_store, CONSENSUS_KEY, consensus_private_key)?;
+        internal_store.set(CONSENSUS_KEY_CREATED_AT, created_at_ms)?;
+        let execution_key = Self::import_key(internal_store, EXECUTION_KEY, execution_private_key)?;
+        if consensus_key == ImportResult::AlreadyExisted
+            || execution_key == ImportResult::AlreadyExisted
+        {
+            warn!(
+                "Resuming a partial initialization, consensus key: {:?}, execution key: {:?}",
+                consensus_key, execution_key
+            );
+        }
         internal_store.set(OWNER_ACCOUNT, author)?;
+        observe_write_bytes(OWNER_ACCOUNT, &author);
+        internal_store.set(INITIALIZATION_COMPLETE, true)?;
         Ok(())
     }
 
+    fn is_set<S: KVStorage, T: DeserializeOwned>(
+        internal_store: &S,
+        key: &str,
+    ) -> Result<bool, Error> {
+        match internal_store.get::<T>(key) {
+            Ok(_) => Ok(true),
+            Err(aptos_secure_storage::Error::KeyNotSet(_)) => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Imports the given key, keeping a key of the same name that already exists in storage.
+    fn import_key<S: CryptoStorage>(
+        internal_store: &mut S,
+        name: &str,
+        private_key: Ed25519PrivateKey,
+    ) -> Result<ImportResult, Error> {
+        let public_key = private_key.public_key();
+        let result = internal_store.import_private_key_if_absent(name, private_key)?;
+        match result {
+            ImportResult::Fresh => log_key_import(name, &public_key),
+            ImportResult::AlreadyExisted => {
+                if internal_store.get_public_key(name)?.public_key != public_key {
+                    warn!(
+                        "Keeping existing {} key that differs from the given key",
+                        name
+                    );
+                }
+            }
+        }
+        Ok(result)
+    }
+
     /// Use this to instantiate a PersistentStorage with an existing data store. This is intended
-    /// for constructed environments.
+    /// for constructed environments. Prefer PersistentSafetyStorageBuilder::open for new code.
+    #[tracing::instrument(skip_all)]
     pub fn new(internal_store: Storage, enable_cached_safety_data: bool) -> Self {
-        Self {
+        PersistentSafetyStorageBuilder::new()
+            .enable_caching(enable_cached_safety_data)
+            .open(internal_store)
+            .expect("Unable to open PersistentSafetyStorage")
+    }
+
+    /// Copies the keys, with their previous versions where `dst` supports it, and the values held
+    /// by safety rules from `src` to `dst`, e.g., when moving to another backend. The signing
+    /// lease is not copied, as it belongs to the running instance. The initialization marker is
+    /// copied last, so that an interrupted migration is not mistaken for initialized storage.
+    #[tracing::instrument(skip_all)]
+    pub fn migrate_storage(src: &mut Storage, dst: &mut Storage) -> Result<MigrationReport, Error> {
+        let backups = src.list_keys(Some(&format!("{}_", SAFETY_DATA_BACKUPS)))?;
+        let mut keys = vec![
+            OWNER_ACCOUNT,
+            CONSENSUS_KEY,
+            EXECUTION_KEY,
+            CONSENSUS_KEY_CREATED_AT,
+            CONSENSUS_KEY_MASTER_SEED,
+            SAFETY_DATA,
+            WAYPOINT,
+            SAFETY_DATA_BACKUPS,
+        ];
+        keys.extend(backups.iter().map(String::as_str));
+        keys.push(INITIALIZATION_COMPLETE);
+        Ok(aptos_secure_storage::migrate(
+            src,
+            dst,
+            Some(&keys),
+            KEY_NAMES,
+        )?)
+    }
+
+    /// Exports all the values and keys of the internal storage, which is scoped to the namespace
+    /// of safety rules, if any, into an archive encrypted with the passphrase, e.g., for disaster
+    /// recovery (see `aptos_secure_storage::export_namespace`). Keys that are not exportable are
+    /// left out. The archive is restored with `import_archive`.
+    #[tracing::instrument(skip_all)]
+    pub fn export_archive(&self, passphrase: &Passphrase) -> Result<Vec<u8>, Error> {
+        Ok(aptos_secure_storage::export_namespace(
+            &self.internal_store,
+            None,
+            KEY_NAMES,
+            passphrase,
+        )?)
+    }
+
+    /// Restores an archive written by `export_archive` into the internal storage, overwriting
+    /// the entries it holds. As with `restore_from_epoch_backup`, this is refused if the archived
+    /// safety data is older than the currently persisted (or cached) safety data, so that an old
+    /// archive cannot make safety rules vote again in a round it already voted in. The archive
+    /// is decrypted and checked before anything is written.
+    #[tracing::instrument(skip_all)]
+    pub fn import_archive(
+        &mut self,
+        archive: &[u8],
+        passphrase: &Passphrase,
+    ) -> Result<ArchiveReport, Error> {
+        let mut staging = Storage::from(InMemoryStorage::new());
+        let report =
+            aptos_secure_storage::import_namespace(&mut staging, None, archive, passphrase)?;
+        let restored: SafetyData = staging.get(SAFETY_DATA)?.value;
+        match self.get_safety_data_value() {
+            Ok(response) => check_monotonic(&decode_safety_data(response.value)?.0, &restored)?,
+            // Restoring into storage without safety data, e.g., after losing it
+            Err(Error::SecureStorageMissingDataError(_)) => (),
+            Err(error) => return Err(error),
+        }
+        if let Some(cached_safety_data) = &self.cached_safety_data {
+            check_monotonic(cached_safety_data, &restored)?;
+        }
+
+        let names: Vec<_> = report.entries.iter().map(String::as_str).collect();
+        let migration = aptos_secure_storage::migrate(
+            &mut staging,
+            &mut self.internal_store,
+            Some(&names),
+            KEY_NAMES,
+        )?;
+        self.cached_safety_data = None;
+        self.invalidate_consensus_key_cache();
+        match migration.failed.into_iter().next() {
+            Some((_, error)) => Err(error.into()),
+            None => Ok(report),
+        }
+    }
+
+    pub(crate) fn try_new(
+        mut internal_store: Storage,
+        enable_cached_safety_data: bool,
+        time_service: TimeService,
+    ) -> Result<Self, Error> {
+        Self::lock_storage(&mut internal_store)?;
+        let state_gauges = counters::StateGauges::default();
+        state_gauges.set_storage_backend(internal_store.backend());
+        Ok(Self {
             enable_cached_safety_data,
             cached_safety_data: None,
+            consensus_key_cache: Mutex::new(Vec::new()),
+            derived_consensus_keys: Mutex::new(Vec::new()),
+            consensus_key_created_at: Mutex::new(None),
+            consensus_key_max_age_secs: None,
+            max_safety_data_backups: 0,
+            standby: false,
+            signing_lease: None,
+            signing_lease_store: None,
+            signing_lease_holder: None,
+            signing_lease_duration: DEFAULT_SIGNING_LEASE_DURATION,
+            time_service,
+            max_proposal_timestamp_skew_ms: DEFAULT_MAX_PROPOSAL_TIMESTAMP_SKEW_MS,
+            circuit_breaker: None,
+            observer: None,
+            state_gauges,
             internal_store,
+        })
+    }
+
+    /// On-disk storage is locked for the lifetime of the PersistentSafetyStorage, so that two
+    /// processes accidentally sharing the same file cannot interleave (and corrupt) writes.
+    fn lock_storage(internal_store: &mut Storage) -> Result<(), Error> {
+        match internal_store {
+            Storage::OnDiskStorage(on_disk_storage) => Ok(on_disk_storage.lock()?),
+            Storage::EncryptedOnDiskStorage(on_disk_storage) => Ok(on_disk_storage.lock()?),
+            Storage::NamespacedStorage(namespaced_storage) => {
+                Self::lock_storage(namespaced_storage.inner_mut())
+            }
+            Storage::OverlayStorage(overlay_storage) => {
+                Self::lock_storage(overlay_storage.writable_mut())
+            }
+            Storage::InstrumentedStorage(instrumented_storage) => {
+                Self::lock_storage(instrumented_storage.inner_mut())
+            }
+            Storage::CircuitBreakerStorage(circuit_breaker_storage) => {
+                Self::lock_storage(circuit_breaker_storage.inner_mut())
+            }
+            Storage::PolicyStorage(policy_storage) => {
+                Self::lock_storage(policy_storage.inner_mut())
+            }
+            Storage::RateLimitedStorage(rate_limited_storage) => {
+                Self::lock_storage(rate_limited_storage.inner_mut())
+            }
+            _ => Ok(()),
         }
     }
 
-    pub fn author(&self) -> Result<Author, Error> {
-        let _timer = counters::start_timer("get", OWNER_ACCOUNT);
-        Ok(self.internal_store.get(OWNER_ACCOUNT).map(|v| v.value)?)
+    /// Sets the age (in seconds) after which the consensus key is reported as overdue for
+    /// rotation. If None, no warnings are emitted.
+    pub fn set_consensus_key_max_age_secs(&mut self, consensus_key_max_age_secs: Option<u64>) {
+        self.consensus_key_max_age_secs = consensus_key_max_age_secs;
     }
 
-    pub fn consensus_key_for_version(
-        &self,
-        version: Ed25519PublicKey,
-    ) -> Result<Ed25519PrivateKey, Error> {
-        let _timer = counters::start_timer("get", CONSENSUS_KEY);
-        Ok(self
-            .internal_store
-            .export_private_key_for_version(CONSENSUS_KEY, version)?)
+    /// Sets the number of epochs for which a safety data backup is retained. If 0, no backups
+    /// are written.
+    pub fn set_max_safety_data_backups(&mut self, max_safety_data_backups: usize) {
+        self.max_safety_data_backups = max_safety_data_backups;
     }
 
-    pub fn execution_public_key(&self) -> Result<Ed25519PublicKey, Error> {
-        let _timer = counters::start_timer("get", EXECUTION_KEY);
-        Ok(self
-            .internal_store
-            .get_public_key(EXECUTION_KEY)
-            .map(|r| r.public_key)?)
+    /// Replaces the clock, e.g., with a mock time service.
+    pub fn set_time_service(&mut self, time_service: TimeService) {
+        self.time_service = time_service;
     }
 
-    pub fn sign<T: Serialize + CryptoHash>(
-        &self,
-        key_name: String,
-        key_version: Ed25519PublicKey,
-        message: &T,
-    ) -> Result<Ed25519Signature, Error> {
-        Ok(self
-            .internal_store
-            .sign_using_version(&key_name, key_version, message)?)
+    /// Reports the state and the storage backend to the given gauges rather than to those of the
+    /// process, so that tests can assert on them.
+    #[cfg(test)]
+    pub(crate) fn set_state_gauges(&mut self, state_gauges: counters::StateGauges) {
+        state_gauges.set_storage_backend(self.internal_store.backend());
+        self.state_gauges = state_gauges;
     }
 
-    pub fn safety_data(&mut self) -> Result<SafetyData, Error> {
-        if !self.enable_cached_safety_data {
-            let _timer = counters::start_timer("get", SAFETY_DATA);
-            return self.internal_store.get(SAFETY_DATA).map(|v| v.value)?;
+    pub fn time_service(&self) -> &TimeService {
+        &self.time_service
+    }
+
+    /// Sets how far (in milliseconds) proposal timestamps may be ahead of the time service.
+    pub fn set_max_proposal_timestamp_skew_ms(&mut self, max_proposal_timestamp_skew_ms: u64) {
+        self.max_proposal_timestamp_skew_ms = max_proposal_timestamp_skew_ms;
+    }
+
+    pub fn max_proposal_timestamp_skew_ms(&self) -> u64 {
+        self.max_proposal_timestamp_skew_ms
+    }
+
+    /// Guards writes to the internal storage with the given circuit breaker. If None, every
+    /// write is issued.
+    pub fn set_circuit_breaker(&mut self, circuit_breaker: Option<CircuitBreaker>) {
+        self.circuit_breaker = circuit_breaker;
+    }
+
+    /// Notifies the observer of the subsequent changes to the safety state, see
+    /// ConsensusObserver.
+    pub fn with_observer(mut self, observer: Arc<dyn ConsensusObserver + Send + Sync>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// The key policy of an initialized data store: the owner account can no longer be written,
+    /// so `migrate_owner_account` fails with a `PolicyDenied` storage error, and every other key
+    /// is left unrestricted.
+    pub fn key_policy() -> KeyPolicy {
+        KeyPolicy::allow_all().key(OWNER_ACCOUNT, vec![Capability::Read])
+    }
+
+    /// The key policy of monitoring tooling sharing the storage, which never writes.
+    pub fn monitoring_key_policy() -> KeyPolicy {
+        KeyPolicy::read_only()
+    }
+
+    /// Rejects subsequent storage operations that the policy does not grant, see
+    /// Storage::with_key_policy.
+    pub fn with_key_policy(self, key_policy: KeyPolicy) -> Self {
+        Self {
+            internal_store: self.internal_store.with_key_policy(key_policy),
+            ..self
+        }
+    }
+
+    /// Writes to the internal storage, unless the circuit breaker is open.
+    fn write<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        if let Some(circuit_breaker) = &mut self.circuit_breaker {
+            circuit_breaker.check(self.time_service.now())?;
         }
+        let result = self.internal_store.set(key, value);
+        if let Some(circuit_breaker) = &mut self.circuit_breaker {
+            circuit_breaker.record(!is_storage_failure(&result), self.time_service.now());
+        }
+        Ok(result?)
+    }
 
-        if let Some(cached_safety_data) = self.cached_safety_data.clone() {
-            Ok(cached_safety_data)
-        } else {
-            let _timer = counters::start_timer("get", SAFETY_DATA);
-            let safety_data: SafetyData = self.internal_store.get(SAFETY_DATA).map(|v| v.value)?;
-            self.cached_safety_data = Some(safety_data.clone());
-            Ok(safety_data)
+    /// Writes several keys to the internal storage in a single batch, unless the circuit breaker
+    /// is open.
+    fn write_batch(&mut self, entries: &[(&str, serde_json::Value)]) -> Result<(), Error> {
+        if let Some(circuit_breaker) = &mut self.circuit_breaker {
+            circuit_breaker.check(self.time_service.now())?;
+        }
+        let result = self.internal_store.set_batch(entries);
+        if let Some(circuit_breaker) = &mut self.circuit_breaker {
+            circuit_breaker.record(!is_storage_failure(&result), self.time_service.now());
         }
+        Ok(result?)
     }
 
-    pub fn set_safety_data(&mut self, data: SafetyData) -> Result<(), Error> {
-        let _timer = counters::start_timer("set", SAFETY_DATA);
-        counters::set_state(counters::EPOCH, data.epoch as i64);
-        counters::set_state(counters::LAST_VOTED_ROUND, data.last_voted_round as i64);
-        counters::set_state(counters::PREFERRED_ROUND, data.preferred_round as i64);
+    /// Deletes from the internal storage, unless the circuit breaker is open.
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        if let Some(circuit_breaker) = &mut self.circuit_breaker {
+            circuit_breaker.check(self.time_service.now())?;
+        }
+        let result = self.internal_store.delete(key);
+        if let Some(circuit_breaker) = &mut self.circuit_breaker {
+            circuit_breaker.record(!is_storage_failure(&result), self.time_service.now());
+        }
+        Ok(result?)
+    }
 
-        match self.internal_store.set(SAFETY_DATA, data.clone()) {
-            Ok(_) => {
-                self.cached_safety_data = Some(data);
-                Ok(())
+    /// In standby mode, safety data and the waypoint are kept up to date but nothing can be
+    /// signed, nor can the consensus key be exported, until the storage is promoted.
+    pub fn set_standby(&mut self, standby: bool) {
+        self.standby = standby;
+    }
+
+    pub fn is_standby(&self) -> bool {
+        self.standby
+    }
+
+    /// Holds the signing lease in `signing_lease_store` rather than in the internal storage, e.g.,
+    /// in a backend shared with the standbys, on behalf of `holder` rather than the author.
+    pub fn set_signing_lease_config(
+        &mut self,
+        signing_lease_store: Option<Storage>,
+        holder: Option<String>,
+        duration: Duration,
+    ) {
+        self.signing_lease_store = signing_lease_store;
+        self.signing_lease_holder = holder;
+        self.signing_lease_duration = duration;
+    }
+
+    /// Fails if nothing can be signed, i.e., in standby mode or unless the signing lease is held
+    /// and has not expired.
+    pub fn check_can_sign(&self) -> Result<(), Error> {
+        if self.standby {
+            return Err(Error::StandbyMode);
+        }
+        match &self.signing_lease {
+            None => Err(Error::SigningLeaseNotHeld),
+            Some(lease)
+                if lease.is_expired(self.time_service.now_unix_time().as_millis() as u64) =>
+            {
+                Err(Error::SigningLeaseExpired(lease.expiration_ms))
             }
-            Err(error) => {
-                self.cached_safety_data = None;
-                Err(Error::SecureStorageUnexpectedError(error.to_string()))
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Fails in standby mode, in which the consensus key cannot be exported. Signing with an
+    /// exported key is subject to check_can_sign.
+    fn check_not_standby(&self) -> Result<(), Error> {
+        if self.standby {
+            return Err(Error::StandbyMode);
+        }
+        Ok(())
+    }
+
+    fn signing_lease_holder(&self) -> Result<String, Error> {
+        match &self.signing_lease_holder {
+            Some(holder) => Ok(holder.clone()),
+            None => Ok(self.author()?.to_string()),
+        }
+    }
+
+    /// Acquires (or renews) the signing lease, which fails while another holder has an unexpired
+    /// lease.
+    fn acquire_signing_lease(&mut self) -> Result<SigningLease, Error> {
+        let holder = self.signing_lease_holder()?;
+        let lease_store = match &mut self.signing_lease_store {
+            Some(signing_lease_store) => signing_lease_store,
+            None => &mut self.internal_store,
+        };
+        let lease = SigningLease::acquire(
+            lease_store,
+            &holder,
+            self.signing_lease_duration,
+            &self.time_service,
+        )?;
+        self.signing_lease = Some(lease.clone());
+        Ok(lease)
+    }
+
+    /// Acquires the signing lease if none is held, or renews it once less than half of its
+    /// duration remains, unless in standby mode. This is called at startup and before signing, so
+    /// that a primary keeps the lease while it is alive. Failures are only logged, as signing is
+    /// refused anyway once the lease expires.
+    #[tracing::instrument(skip_all)]
+    pub fn renew_signing_lease(&mut self) {
+        if self.standby {
+            return;
+        }
+        let now_ms = self.time_service.now_unix_time().as_millis() as u64;
+        let renew_within_ms = self.signing_lease_duration.as_millis() as u64 / 2;
+        if let Some(lease) = &self.signing_lease {
+            if lease.expiration_ms.saturating_sub(now_ms) > renew_within_ms {
+                return;
             }
         }
+        if let Err(error) = self.acquire_signing_lease() {
+            warn!(logging::SafetyLogSchema::new(LogEntry::Standby, LogEvent::Error).error(&error));
+        }
     }
 
-    pub fn waypoint(&self) -> Result<Waypoint, Error> {
-        let _timer = counters::start_timer("get", WAYPOINT);
-        Ok(self.internal_store.get(WAYPOINT).map(|v| v.value)?)
+    /// Leaves standby mode. This requires acquiring the signing lease, which fails while another
+    /// node (e.g., a live primary) holds an unexpired lease. Once promoted, the lease is renewed
+    /// before signing, see renew_signing_lease.
+    #[tracing::instrument(skip_all)]
+    pub fn promote(&mut self) -> Result<SigningLease, Error> {
+        let lease = self.acquire_signing_lease().map_err(|error| {
+            warn!(logging::SafetyLogSchema::new(LogEntry::Standby, LogEvent::Error).error(&error));
+            error
+        })?;
+        self.standby = false;
+        info!(logging::SafetyLogSchema::new(
+            LogEntry::Standby,
+            LogEvent::Success
+        ));
+        Ok(lease)
     }
 
-    pub fn set_waypoint(&mut self, waypoint: &Waypoint) -> Result<(), Error> {
-        let _timer = counters::start_timer("set", WAYPOINT);
-        counters::set_state(counters::WAYPOINT_VERSION, waypoint.version() as i64);
-        self.internal_store.set(WAYPOINT, waypoint)?;
-        info!(
-            logging::SafetyLogSchema::new(LogEntry::Waypoint, LogEvent::Update).waypoint(*waypoint)
-        );
+    /// Enters standby mode and releases the signing lease, so that a standby can be promoted
+    /// without waiting for the lease to expire.
+    #[tracing::instrument(skip_all)]
+    pub fn demote(&mut self) -> Result<(), Error> {
+        self.standby = true;
+        self.signing_lease = None;
+        let holder = self.signing_lease_holder()?;
+        let lease_store = match &mut self.signing_lease_store {
+            Some(signing_lease_store) => signing_lease_store,
+            None => &mut self.internal_store,
+        };
+        SigningLease::release(lease_store, &holder)?;
+        info!(logging::SafetyLogSchema::new(
+            LogEntry::Standby,
+            LogEvent::Update
+        ));
         Ok(())
     }
 
-    #[cfg(any(test, feature = "testing"))]
-    pub fn internal_store(&mut self) -> &mut Storage {
-        &mut self.internal_store
+    #[tracing::instrument(skip_all)]
+    pub fn author(&self) -> Result<Author, Error> {
+        let _timer = self.start_timer("get", OWNER_ACCOUNT);
+        let author = self.internal_store.get(OWNER_ACCOUNT).map(|v| v.value)?;
+        observe_read_bytes(OWNER_ACCOUNT, &author);
+        Ok(author)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::counters;
-    use aptos_crypto::{hash::HashValue, Uniform};
-    use aptos_secure_storage::InMemoryStorage;
-    use aptos_types::{
-        block_info::BlockInfo, epoch_state::EpochState, ledger_info::LedgerInfo,
-        transaction::Version, validator_signer::ValidatorSigner, waypoint::Waypoint,
-    };
+    /// Replaces the owner account, e.g., when an operator rotates the account of the validator.
+    /// The stored account must be `expected_current`, and the validator must not have voted in
+    /// the current epoch, so that the votes of an epoch are never split across two authors.
+    /// Safety rules only uses the new account once it is initialized again.
+    #[tracing::instrument(skip_all)]
+    pub fn migrate_owner_account(
+        &mut self,
+        new_author: Author,
+        expected_current: Author,
+    ) -> Result<(), Error> {
+        let current_author = self.author()?;
+        if current_author != expected_current {
+            return Err(Error::OwnerAccountMismatch(
+                expected_current.to_string(),
+                current_author.to_string(),
+            ));
+        }
 
-    #[test]
-    fn test_counters() {
-        let consensus_private_key = ValidatorSigner::from_int(0).private_key().clone();
-        let storage = Storage::from(InMemoryStorage::new());
-        let mut safety_storage = PersistentSafetyStorage::initialize(
-            storage,
-            Author::random(),
-            consensus_private_key,
-            Ed25519PrivateKey::generate_for_testing(),
-            Waypoint::default(),
-            true,
+        let safety_data = self.safety_data()?;
+        if safety_data.last_voted_round > 0 || safety_data.last_vote.is_some() {
+            return Err(Error::VotedInCurrentEpoch(
+                safety_data.epoch,
+                safety_data.last_voted_round,
+            ));
+        }
+
+        let _timer = self.start_timer("set", OWNER_ACCOUNT);
+        self.write(OWNER_ACCOUNT, new_author)?;
+        observe_write_bytes(OWNER_ACCOUNT, &new_author);
+        warn!(
+            logging::SafetyLogSchema::new(LogEntry::OwnerAccount, LogEvent::Update)
+                .author(new_author)
+                .epoch(safety_data.epoch),
+            "Migrated the owner account from {} to {}", current_author, new_author,
         );
-        // they both touch the global counters, running it serially to prevent race condition.
-        test_safety_data_counters(&mut safety_storage);
-        test_waypoint_counters(&mut safety_storage);
+        Ok(())
     }
 
-    fn test_safety_data_counters(safety_storage: &mut PersistentSafetyStorage) {
-        let safety_data = safety_storage.safety_data().unwrap();
-        assert_eq!(safety_data.epoch, 1);
-        assert_eq!(safety_data.last_voted_round, 0);
-        assert_eq!(safety_data.preferred_round, 0);
-        assert_eq!(counters::get_state(counters::EPOCH), 1);
-        assert_eq!(counters::get_state(counters::LAST_VOTED_ROUND), 0);
-        assert_eq!(counters::get_state(counters::PREFERRED_ROUND), 0);
+    #[tracing::instrument(skip_all)]
+    pub fn consensus_key_for_version(
+        &self,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        self.check_not_standby()?;
+        if let Some(private_key) = self.derived_consensus_key(&version) {
+            return Ok(private_key);
+        }
+        if let Some(private_key) = self.cached_consensus_key(&version) {
+            return Ok(private_key);
+        }
 
-        safety_storage
-            .set_safety_data(SafetyData::new(9, 8, 1, 0, None))
-            .unwrap();
+        let _timer = self.start_timer("get", CONSENSUS_KEY);
+        let private_key = self
+            .internal_store
+            .export_private_key_for_version(CONSENSUS_KEY, version.clone())?;
 
-        let safety_data = safety_storage.safety_data().unwrap();
-        assert_eq!(safety_data.epoch, 9);
-        assert_eq!(safety_data.last_voted_round, 8);
-        assert_eq!(safety_data.preferred_round, 1);
-        assert_eq!(counters::get_state(counters::EPOCH), 9);
-        assert_eq!(counters::get_state(counters::LAST_VOTED_ROUND), 8);
-        assert_eq!(counters::get_state(counters::PREFERRED_ROUND), 1);
+        if let Some(cached_key) = copy_private_key(&private_key) {
+            let mut cache = self.consensus_key_cache.lock();
+            if cache.len() >= CONSENSUS_KEY_CACHE_SIZE {
+                cache.remove(0);
+            }
+            cache.push((version, cached_key));
+        }
+        Ok(private_key)
     }
 
-    fn test_waypoint_counters(safety_storage: &mut PersistentSafetyStorage) {
-        let waypoint = safety_storage.waypoint().unwrap();
-        assert_eq!(waypoint.version(), Version::default());
-        assert_eq!(
-            counters::get_state(counters::WAYPOINT_VERSION) as u64,
-            Version::default()
-        );
+    /// Returns the cached private key for the given version, if any. Public keys are compared
+    /// in constant time.
+    fn cached_consensus_key(&self, version: &Ed25519PublicKey) -> Option<Ed25519PrivateKey> {
+        let version_bytes = version.to_bytes();
+        self.consensus_key_cache
+            .lock()
+            .iter()
+            .find(|(public_key, _)| bool::from(public_key.to_bytes()[..].ct_eq(&version_bytes[..])))
+            .and_then(|(_, private_key)| copy_private_key(private_key))
+    }
 
-        for expected_version in 1..=10u64 {
-            let li = LedgerInfo::new(
-                BlockInfo::new(
+    /// Stores the master seed from which each epoch's consensus key is derived, enabling per-epoch
+    /// consensus keys. The given copy of the seed is zeroized.
+    #[tracing::instrument(skip_all)]
+    pub fn set_consensus_key_master_seed(&mut self, master_seed: Vec<u8>) -> Result<(), Error> {
+        let master_seed = Zeroizing::new(master_seed);
+        key_derivation::check_master_seed(&master_seed)?;
+        let _timer = self.start_timer("set", CONSENSUS_KEY_MASTER_SEED);
+        self.write(CONSENSUS_KEY_MASTER_SEED, &*master_seed)?;
+        self.derived_consensus_keys.lock().clear();
+        Ok(())
+    }
+
+    /// Returns whether per-epoch consensus keys are enabled, i.e., a master seed is stored.
+    #[tracing::instrument(skip_all)]
+    pub fn has_consensus_key_master_seed(&self) -> Result<bool, Error> {
+        Self::is_set::<_, Vec<u8>>(&self.internal_store, CONSENSUS_KEY_MASTER_SEED)
+    }
+
+    /// Derives the consensus key of the given epoch from the master seed, so that it can be
+    /// registered on-chain. The derived keys of the two most recent epochs remain available to
+    /// consensus_key_for_version and for signing. None of the storage backends can derive keys,
+    /// so derivation happens in-process and all copies of the seed are zeroized after use.
+    #[tracing::instrument(skip_all)]
+    pub fn derive_epoch_consensus_key(&self, epoch: u64) -> Result<Ed25519PublicKey, Error> {
+        let master_seed = {
+            let _timer = self.start_timer("get", CONSENSUS_KEY_MASTER_SEED);
+            Zeroizing::new(
+                self.internal_store
+                    .get::<Vec<u8>>(CONSENSUS_KEY_MASTER_SEED)?
+                    .value,
+            )
+        };
+        let private_key = key_derivation::derive_consensus_key(&master_seed, epoch)?;
+        let public_key = private_key.public_key();
+
+        let mut derived_keys = self.derived_consensus_keys.lock();
+        derived_keys.retain(|(derived_epoch, _)| *derived_epoch != epoch);
+        derived_keys.push((epoch, private_key));
+        derived_keys.sort_by_key(|(derived_epoch, _)| *derived_epoch);
+        if derived_keys.len() > DERIVED_CONSENSUS_KEYS {
+            derived_keys.remove(0);
+        }
+        Ok(public_key)
+    }
+
+    /// Rotates the consensus key in storage. The previous version remains available for signing
+    /// until the next rotation, and the creation time of the key is reset. The observer, if any,
+    /// is notified of the new version.
+    #[tracing::instrument(skip_all)]
+    pub fn rotate_consensus_key(&mut self) -> Result<Ed25519PublicKey, Error> {
+        let public_key = self.internal_store.rotate_key(CONSENSUS_KEY)?;
+        self.invalidate_consensus_key_cache();
+        let created_at_ms = self.time_service.now_unix_time().as_millis() as u64;
+        self.write(CONSENSUS_KEY_CREATED_AT, created_at_ms)?;
+
+        counters::increment_key_event(CONSENSUS_KEY, "rotate");
+        info!(
+            logging::SafetyLogSchema::new(LogEntry::KeyRotation, LogEvent::Update)
+                .key_name(CONSENSUS_KEY)
+                .key_fingerprint(key_fingerprint(&public_key)),
+            "Rotated the consensus key",
+        );
+        if let Some(observer) = &self.observer {
+            observer.on_key_rotation(&public_key);
+        }
+        Ok(public_key)
+    }
+
+    /// Returns the derived consensus key for the given version, if any. Public keys are compared
+    /// in constant time.
+    fn derived_consensus_key(&self, version: &Ed25519PublicKey) -> Option<Ed25519PrivateKey> {
+        let version_bytes = version.to_bytes();
+        self.derived_consensus_keys
+            .lock()
+            .iter()
+            .find(|(_, private_key)| {
+                bool::from(private_key.public_key().to_bytes()[..].ct_eq(&version_bytes[..]))
+            })
+            .and_then(|(_, private_key)| copy_private_key(private_key))
+    }
+
+    /// Drops all cached consensus key versions, e.g., after the keys in storage were rotated.
+    pub fn invalidate_consensus_key_cache(&self) {
+        self.consensus_key_cache.lock().clear();
+        *self.consensus_key_created_at.lock() = None;
+    }
+
+    /// Checks that the consensus key in storage, or one derived for a recent epoch, is the one
+    /// registered in the validator set, e.g., at startup, so that a mismatch is caught before the
+    /// validator fails to vote.
+    #[tracing::instrument(skip_all)]
+    pub fn assert_consensus_key_matches(&self, expected: &Ed25519PublicKey) -> Result<(), Error> {
+        if self.derived_consensus_key(expected).is_some() {
+            return Ok(());
+        }
+        let _timer = self.start_timer("get", CONSENSUS_KEY);
+        let public_key = self
+            .internal_store
+            .get_public_key(CONSENSUS_KEY)
+            .map(|r| r.public_key)?;
+        if &public_key != expected {
+            return Err(Error::ConsensusKeyMismatch(
+                expected.to_string(),
+                public_key.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the time (Unix ms) at which the consensus key was imported into storage.
+    #[tracing::instrument(skip_all)]
+    pub fn consensus_key_created_at(&self) -> Result<u64, Error> {
+        let _timer = self.start_timer("get", CONSENSUS_KEY_CREATED_AT);
+        Ok(self
+            .internal_store
+            .get(CONSENSUS_KEY_CREATED_AT)
+            .map(|v| v.value)?)
+    }
+
+    /// Updates the consensus key age gauge and warns if the key is older than the configured
+    /// maximum age. Storage without a creation timestamp (e.g., created by older versions) is
+    /// silently skipped.
+    fn update_consensus_key_age(&self) {
+        let created_at = *self
+            .consensus_key_created_at
+            .lock()
+            .get_or_insert_with(|| self.consensus_key_created_at().ok());
+        let created_at = match created_at {
+            Some(created_at) => created_at,
+            None => return,
+        };
+
+        let age_secs = self
+            .time_service
+            .now_unix_time()
+            .saturating_sub(Duration::from_millis(created_at))
+            .as_secs();
+        self.state_gauges
+            .set_state(counters::CONSENSUS_KEY_AGE_SECONDS, age_secs as i64);
+        if let Some(max_age_secs) = self.consensus_key_max_age_secs {
+            if age_secs > max_age_secs {
+                sample!(
+                    SampleRate::Duration(Duration::from_secs(60)),
+                    warn!(
+                        age_secs = age_secs,
+                        max_age_secs = max_age_secs,
+                        "Consensus key is older than the maximum age and should be rotated"
+                    )
+                );
+            }
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn execution_public_key(&self) -> Result<Ed25519PublicKey, Error> {
+        let _timer = self.start_timer("get", EXECUTION_KEY);
+        Ok(self
+            .internal_store
+            .get_public_key(EXECUTION_KEY)
+            .map(|r| r.public_key)?)
+    }
+
+    /// Exports the consensus and execution public keys of the validator, signed with the
+    /// execution key, e.g., for key transparency auditing. The consensus keys derived for recent
+    /// epochs are exported along with the consensus key in storage.
+    #[tracing::instrument(skip_all)]
+    pub fn export_public_keys(&self) -> Result<PublicKeyBundle, Error> {
+        let consensus_pub_key = {
+            let _timer = self.start_timer("get", CONSENSUS_KEY);
+            self.internal_store
+                .get_public_key(CONSENSUS_KEY)
+                .map(|r| r.public_key)?
+        };
+        let epoch_consensus_pub_keys = self
+            .derived_consensus_keys
+            .lock()
+            .iter()
+            .map(|(epoch, private_key)| (*epoch, private_key.public_key()))
+            .collect();
+        let signing_repr = PublicKeyBundleSigningRepr {
+            consensus_pub_key,
+            epoch_consensus_pub_keys,
+            execution_pub_key: self.execution_public_key()?,
+            author: self.author()?,
+            created_at: self.time_service.now_unix_time().as_millis() as u64,
+        };
+
+        let _timer = self.start_timer("sign", EXECUTION_KEY);
+        let signature = self.internal_store.sign(EXECUTION_KEY, &signing_repr)?;
+        Ok(PublicKeyBundle {
+            consensus_pub_key: signing_repr.consensus_pub_key,
+            epoch_consensus_pub_keys: signing_repr.epoch_consensus_pub_keys,
+            execution_pub_key: signing_repr.execution_pub_key,
+            author: signing_repr.author,
+            created_at: signing_repr.created_at,
+            signature,
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(key_name = %key_name))]
+    pub fn sign<T: Serialize + CryptoHash>(
+        &self,
+        key_name: String,
+        key_version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.check_can_sign()?;
+        if key_name == CONSENSUS_KEY {
+            if let Some(private_key) = self.derived_consensus_key(&key_version) {
+                return Ok(private_key.sign(message));
+            }
+        }
+        Ok(self
+            .internal_store
+            .sign_using_version(&key_name, key_version, message)?)
+    }
+
+    /// Signs all messages with the same key version, e.g., the votes gathered while aggregating
+    /// a quorum certificate, in a single interaction with the storage. Like `sign`, the private
+    /// key never leaves the storage, unless it is a derived consensus key. No signature is
+    /// returned unless all messages could be signed.
+    #[tracing::instrument(skip_all, fields(key_name = %key_name))]
+    pub fn sign_batch<T: Serialize + CryptoHash>(
+        &self,
+        key_name: &str,
+        key_version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        self.check_can_sign()?;
+        let _timer = self.start_timer("sign_batch", key_name);
+        if key_name == CONSENSUS_KEY {
+            if let Some(private_key) = self.derived_consensus_key(&key_version) {
+                return Ok(messages
+                    .iter()
+                    .map(|message| private_key.sign(message))
+                    .collect());
+            }
+        }
+        Ok(self
+            .internal_store
+            .sign_batch_using_version(key_name, key_version, messages)?)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn safety_data(&mut self) -> Result<SafetyData, Error> {
+        self.update_consensus_key_age();
+
+        if !self.enable_cached_safety_data {
+            return self.read_safety_data();
+        }
+
+        if let Some(cached_safety_data) = self.cached_safety_data.clone() {
+            count_safety_data_copy();
+            Ok(cached_safety_data)
+        } else {
+            let safety_data = self.read_safety_data()?;
+            self.cached_safety_data = Some(safety_data.clone());
+            Ok(safety_data)
+        }
+    }
+
+    /// Safety data still stored in a legacy layout, e.g., as its migration failed when the storage
+    /// was opened, is converted but not written back.
+    fn read_safety_data(&mut self) -> Result<SafetyData, Error> {
+        let value = self.get_safety_data_value()?.value;
+        let (safety_data, _) = decode_safety_data(value)?;
+        observe_read_bytes(SAFETY_DATA, &safety_data);
+        Ok(safety_data)
+    }
+
+    /// Reads the stored safety data without decoding it into SafetyData. The size limit is checked
+    /// while the backend decodes what it stores, so oversized safety data is skipped rather than
+    /// held in memory, let alone cached or acted upon. Every read of the full safety data goes
+    /// through here, and reads of its summary through safety_data_summary.
+    fn get_safety_data_value(&self) -> Result<GetResponse<serde_json::Value>, Error> {
+        let _timer = self.start_timer("get", SAFETY_DATA);
+        let response = self.internal_store.get::<BoundedSafetyData>(SAFETY_DATA)?;
+        match response.value {
+            BoundedSafetyData::Value(value) => Ok(GetResponse::new_with_version(
+                value,
+                response.last_update,
+                response.version,
+            )),
+            BoundedSafetyData::TooLarge => Err(safety_data_too_large()),
+        }
+    }
+
+    /// Reads the owner account and waypoint in a single batch, for paths that need them along with
+    /// the safety data, e.g., reporting the consensus state. Cached safety data is used if
+    /// available, otherwise it is read on its own, so that its size is checked before decoding.
+    #[tracing::instrument(skip_all)]
+    pub fn author_safety_data_and_waypoint(
+        &mut self,
+    ) -> Result<(Author, SafetyData, Waypoint), Error> {
+        self.update_consensus_key_age();
+
+        let cached_safety_data = if self.enable_cached_safety_data {
+            self.cached_safety_data.clone()
+        } else {
+            None
+        };
+        if cached_safety_data.is_some() {
+            count_safety_data_copy();
+        }
+        let mut values = {
+            let _timer = self.start_timer("get_batch", SAFETY_DATA);
+            self.internal_store.get_batch(&[OWNER_ACCOUNT, WAYPOINT])?
+        };
+        let mut take = |key: &str| {
+            values
+                .remove(key)
+                .map(|response| response.value)
+                .ok_or_else(|| Error::SecureStorageMissingDataError(key.into()))
+        };
+
+        let author: Author = serde_json::from_value(take(OWNER_ACCOUNT)?)?;
+        observe_read_bytes(OWNER_ACCOUNT, &author);
+        let waypoint: Waypoint = serde_json::from_value(take(WAYPOINT)?)?;
+        observe_read_bytes(WAYPOINT, &waypoint);
+        let safety_data = match cached_safety_data {
+            Some(safety_data) => safety_data,
+            None => {
+                let safety_data = self.read_safety_data()?;
+                if self.enable_cached_safety_data {
+                    self.cached_safety_data = Some(safety_data.clone());
+                }
+                safety_data
+            }
+        };
+        Ok((author, safety_data, waypoint))
+    }
+
+    /// Rewrites safety data stored in a legacy layout in the current layout. This runs once, when
+    /// an existing data store is opened, rather than on every read. Safety data that cannot be
+    /// read is left to the reads to report, and a failed rewrite is retried when the storage is
+    /// next opened, as reads convert legacy layouts in the meantime.
+    pub(crate) fn migrate_safety_data(&mut self) {
+        let value = match self.get_safety_data_value() {
+            Ok(response) => response.value,
+            Err(_) => return,
+        };
+        let (safety_data, from) = match decode_safety_data(value) {
+            Ok((safety_data, Some(from))) => (safety_data, from),
+            _ => return,
+        };
+
+        let _timer = self.start_timer("set", SAFETY_DATA);
+        let schema = logging::SafetyLogSchema::new(LogEntry::SafetyDataMigration, LogEvent::Update)
+            .epoch(safety_data.epoch);
+        match self.write(SAFETY_DATA, &safety_data) {
+            Ok(()) => info!(schema, "Migrated safety data from the {:?} layout", from),
+            Err(error) => {
+                error!(
+                    schema.error(&error),
+                    "Unable to migrate safety data from the {:?} layout", from
+                );
+            }
+        }
+    }
+
+    /// Returns the current epoch without cloning (or deserializing) the full SafetyData.
+    #[tracing::instrument(skip_all)]
+    pub fn current_epoch(&self) -> Result<u64, Error> {
+        Ok(self.safety_data_summary()?.epoch)
+    }
+
+    /// Returns the last voted round without cloning (or deserializing) the full SafetyData.
+    #[tracing::instrument(skip_all)]
+    pub fn current_last_voted_round(&self) -> Result<u64, Error> {
+        Ok(self.safety_data_summary()?.last_voted_round)
+    }
+
+    /// Serves the summary from the cache if enabled and populated, otherwise reads only the
+    /// summary fields from storage: the other fields, e.g., the last vote, are skipped by the
+    /// backend as it decodes what it stores, though still bounded to MAX_SAFETY_DATA_BCS_SIZE.
+    /// The cache is not populated by partial reads.
+    fn safety_data_summary(&self) -> Result<SafetyDataSummary, Error> {
+        if self.enable_cached_safety_data {
+            if let Some(cached_safety_data) = &self.cached_safety_data {
+                return Ok(SafetyDataSummary {
+                    epoch: cached_safety_data.epoch,
+                    last_voted_round: cached_safety_data.last_voted_round,
+                });
+            }
+        }
+
+        let _timer = self.start_timer("get", SAFETY_DATA);
+        match self
+            .internal_store
+            .get::<BoundedSafetyDataSummary>(SAFETY_DATA)?
+            .value
+        {
+            BoundedSafetyDataSummary::Value(summary) => Ok(summary),
+            BoundedSafetyDataSummary::TooLarge => Err(safety_data_too_large()),
+        }
+    }
+
+    /// Returns when and by which write the safety data was last updated, as read from storage
+    /// rather than the cache, so that checks can detect safety data changed by another writer.
+    #[tracing::instrument(skip_all)]
+    pub fn safety_data_metadata(&self) -> Result<ValueMetadata, Error> {
+        Ok(self.get_safety_data_value()?.metadata())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn set_safety_data(&mut self, data: SafetyData) -> Result<(), Error> {
+        let _timer = self.start_timer("set", SAFETY_DATA);
+        self.state_gauges
+            .set_state(counters::EPOCH, data.epoch as i64);
+        self.state_gauges
+            .set_state(counters::LAST_VOTED_ROUND, data.last_voted_round as i64);
+        self.state_gauges
+            .set_state(counters::PREFERRED_ROUND, data.preferred_round as i64);
+
+        let previous_epoch = self.previous_epoch();
+        match self.write(SAFETY_DATA, data.clone()) {
+            Ok(_) => {
+                observe_write_bytes(SAFETY_DATA, &data);
+                if previous_epoch.map_or(false, |epoch| data.epoch > epoch) {
+                    // A failed backup must never prevent safety rules from making progress
+                    if let Err(error) = self.backup_safety_data(&data) {
+                        error!(
+                            logging::SafetyLogSchema::new(LogEntry::Epoch, LogEvent::Error)
+                                .epoch(data.epoch)
+                                .error(&error),
+                            "Unable to back up safety data",
+                        );
+                    }
+                }
+                if let Some(observer) = &self.observer {
+                    observer.on_safety_data_updated(&data);
+                }
+                self.cached_safety_data = Some(data);
+                Ok(())
+            }
+            Err(error) => {
+                self.cached_safety_data = None;
+                Err(error)
+            }
+        }
+    }
+
+    /// Writes `new` only if the current safety data, cached or read from storage, matches
+    /// `expected` in every field. Callers sharing the storage behind a mutex can then compute an
+    /// update from the safety data without holding the mutex in between, and retry on
+    /// `CasError::Mismatch` if another caller updated it first.
+    #[tracing::instrument(skip_all)]
+    pub fn compare_and_swap_safety_data(
+        &mut self,
+        expected: &SafetyData,
+        new: SafetyData,
+    ) -> Result<(), CasError> {
+        let current = self.safety_data()?;
+        if !safety_data_matches(&current, expected) {
+            return Err(CasError::Mismatch);
+        }
+        Ok(self.set_safety_data(new)?)
+    }
+
+    /// Advances the last voted round to `new_round` and persists it before returning, so callers
+    /// never act on a round that has not been recorded. Fails with `InvalidRound` unless
+    /// `new_round` is strictly greater than the persisted last voted round.
+    #[tracing::instrument(skip_all)]
+    pub fn try_advance_round(&mut self, new_round: Round) -> Result<Round, Error> {
+        let mut safety_data = self.safety_data()?;
+        if new_round <= safety_data.last_voted_round {
+            return Err(Error::InvalidRound(new_round, safety_data.last_voted_round));
+        }
+
+        safety_data.last_voted_round = new_round;
+        self.set_safety_data(safety_data)?;
+        info!(
+            logging::SafetyLogSchema::new(LogEntry::LastVotedRound, LogEvent::Update)
+                .last_voted_round(new_round)
+        );
+        Ok(new_round)
+    }
+
+    /// Returns the epoch of the currently persisted safety data, if backups are enabled.
+    fn previous_epoch(&self) -> Option<u64> {
+        if self.max_safety_data_backups == 0 {
+            return None;
+        }
+
+        self.safety_data_summary().ok().map(|summary| summary.epoch)
+    }
+
+    /// Writes a backup for the epoch of the given safety data and prunes the oldest backups.
+    fn backup_safety_data(&mut self, safety_data: &SafetyData) -> Result<(), Error> {
+        let backup = SafetyDataBackup {
+            safety_data: safety_data.clone(),
+            waypoint: self.waypoint()?,
+            consensus_key: self
+                .internal_store
+                .get_public_key(CONSENSUS_KEY)?
+                .public_key,
+        };
+
+        let _timer = self.start_timer("set", SAFETY_DATA_BACKUPS);
+        self.write(&safety_data_backup_key(safety_data.epoch), backup)?;
+
+        // Pruned backups are dropped from the index before being deleted, so the index never
+        // refers to a missing backup
+        let mut epochs = self.safety_data_backup_epochs()?;
+        epochs.retain(|epoch| *epoch != safety_data.epoch);
+        epochs.push(safety_data.epoch);
+        epochs.sort_unstable();
+        let num_pruned = epochs.len().saturating_sub(self.max_safety_data_backups);
+        let pruned: Vec<_> = epochs.drain(..num_pruned).collect();
+        self.write(SAFETY_DATA_BACKUPS, epochs)?;
+        for epoch in pruned {
+            self.delete(&safety_data_backup_key(epoch))?;
+        }
+        Ok(())
+    }
+
+    /// Deletes all safety data backups, e.g., once the voting history they hold is discarded.
+    fn clear_safety_data_backups(&mut self) -> Result<(), Error> {
+        let epochs = self.safety_data_backup_epochs()?;
+        self.delete(SAFETY_DATA_BACKUPS)?;
+        for epoch in epochs {
+            self.delete(&safety_data_backup_key(epoch))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the epochs for which a safety data backup is retained, in ascending order.
+    #[tracing::instrument(skip_all)]
+    pub fn safety_data_backup_epochs(&self) -> Result<Vec<u64>, Error> {
+        let _timer = self.start_timer("get", SAFETY_DATA_BACKUPS);
+        match self.internal_store.get(SAFETY_DATA_BACKUPS) {
+            Ok(response) => Ok(response.value),
+            Err(aptos_secure_storage::Error::KeyNotSet(_)) => Ok(vec![]),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Restores the safety data and waypoint from the backup of the given epoch. This is
+    /// refused if the backup is older than the currently persisted (or cached) safety data. If
+    /// the current safety data cannot be read at all (e.g., it was corrupted), the backup must
+    /// not be older than the most recent backup instead.
+    #[tracing::instrument(skip_all)]
+    pub fn restore_from_epoch_backup(&mut self, epoch: u64) -> Result<(), Error> {
+        let epochs = self.safety_data_backup_epochs()?;
+        if !epochs.contains(&epoch) {
+            return Err(Error::SecureStorageMissingDataError(format!(
+                "No safety data backup for epoch {}",
+                epoch
+            )));
+        }
+        let backup: SafetyDataBackup = self
+            .internal_store
+            .get(&safety_data_backup_key(epoch))
+            .map(|v| v.value)?;
+
+        let current = match self
+            .get_safety_data_value()
+            .and_then(|response| decode_safety_data(response.value))
+        {
+            Ok((current, _)) => current,
+            Err(error) => {
+                // The most recent backup is the latest known safety data
+                let latest_epoch = epochs.last().copied().unwrap_or(epoch);
+                warn!(
+                    logging::SafetyLogSchema::new(LogEntry::State, LogEvent::Update).epoch(epoch),
+                    "Current safety data is unreadable ({}), checking the backup against the one of epoch {}",
+                    error,
+                    latest_epoch,
+                );
+                self.internal_store
+                    .get::<SafetyDataBackup>(&safety_data_backup_key(latest_epoch))?
+                    .value
+                    .safety_data
+            }
+        };
+        check_monotonic(&current, &backup.safety_data)?;
+        if let Some(cached_safety_data) = &self.cached_safety_data {
+            check_monotonic(cached_safety_data, &backup.safety_data)?;
+        }
+        if let Ok(response) = self.internal_store.get_public_key(CONSENSUS_KEY) {
+            if response.public_key != backup.consensus_key {
+                warn!(
+                    logging::SafetyLogSchema::new(LogEntry::State, LogEvent::Update).epoch(epoch),
+                    "The consensus key has changed since the backup was taken",
+                );
+            }
+        }
+
+        self.set_waypoint(&backup.waypoint)?;
+        self.set_safety_data(backup.safety_data)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn waypoint(&self) -> Result<Waypoint, Error> {
+        let _timer = self.start_timer("get", WAYPOINT);
+        let waypoint = self.internal_store.get(WAYPOINT).map(|v| v.value)?;
+        observe_read_bytes(WAYPOINT, &waypoint);
+        Ok(waypoint)
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub fn set_waypoint(&mut self, waypoint: &Waypoint) -> Result<(), Error> {
+        let _timer = self.start_timer("set", WAYPOINT);
+        self.state_gauges
+            .set_state(counters::WAYPOINT_VERSION, waypoint.version() as i64);
+        self.write(WAYPOINT, waypoint)?;
+        observe_write_bytes(WAYPOINT, waypoint);
+        info!(
+            logging::SafetyLogSchema::new(LogEntry::Waypoint, LogEvent::Update)
+                .waypoint(waypoint.display_compact())
+        );
+        if let Some(observer) = &self.observer {
+            observer.on_waypoint_updated(waypoint);
+        }
+        Ok(())
+    }
+
+    /// Resets the safety state to a new trusted waypoint provided by an operator, e.g., when the
+    /// validator has fallen behind by more than one epoch. The voting history, including the
+    /// safety data backups, is discarded and the epoch is set to `new_epoch`, which must be
+    /// greater than the current epoch: the voting history of an epoch that may still be live is
+    /// never discarded. The safety data and the waypoint are written in a single batch, which the
+    /// on-disk backends write atomically, so that neither is updated without the other.
+    #[tracing::instrument(skip_all)]
+    pub fn reset_to_waypoint(
+        &mut self,
+        new_waypoint: Waypoint,
+        new_epoch: u64,
+    ) -> Result<(), Error> {
+        let previous_safety_data = self.safety_data()?;
+        if new_epoch <= previous_safety_data.epoch {
+            return Err(Error::IncorrectEpoch(new_epoch, previous_safety_data.epoch));
+        }
+        warn!(
+            logging::SafetyLogSchema::new(LogEntry::Waypoint, LogEvent::Update)
+                .waypoint(new_waypoint)
+                .epoch(new_epoch),
+            "Resetting safety rules to a new waypoint, discarding the voting history of epoch {}",
+            previous_safety_data.epoch,
+        );
+
+        let safety_data = SafetyData::new(new_epoch, 0, 0, 0, None);
+        let entries = [
+            (SAFETY_DATA, serde_json::to_value(&safety_data)?),
+            (WAYPOINT, serde_json::to_value(&new_waypoint)?),
+        ];
+        let result = {
+            let _timer = self.start_timer("set_batch", SAFETY_DATA);
+            self.write_batch(&entries)
+        };
+        // Force the next read to go to storage, so it observes exactly what was persisted
+        self.cached_safety_data = None;
+        result?;
+
+        self.state_gauges
+            .set_state(counters::EPOCH, safety_data.epoch as i64);
+        self.state_gauges.set_state(
+            counters::LAST_VOTED_ROUND,
+            safety_data.last_voted_round as i64,
+        );
+        self.state_gauges.set_state(
+            counters::PREFERRED_ROUND,
+            safety_data.preferred_round as i64,
+        );
+        self.state_gauges
+            .set_state(counters::WAYPOINT_VERSION, new_waypoint.version() as i64);
+        observe_write_bytes(SAFETY_DATA, &safety_data);
+        observe_write_bytes(WAYPOINT, &new_waypoint);
+        if let Some(observer) = &self.observer {
+            observer.on_safety_data_updated(&safety_data);
+            observer.on_waypoint_updated(&new_waypoint);
+        }
+        if let Err(error) = self.clear_safety_data_backups() {
+            warn!(
+                logging::SafetyLogSchema::new(LogEntry::State, LogEvent::Error).error(&error),
+                "Unable to delete the safety data backups after a waypoint reset",
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the current values of all safety rules counters and gauges (e.g., the epoch, the
+    /// last voted round and the number of rejections), for checks that do not parse the
+    /// Prometheus text format, see `counters::snapshot`. The metrics are global to the process,
+    /// so they also cover other instances of the process, if any.
+    pub fn metrics_snapshot(&self) -> HashMap<String, f64> {
+        self.update_consensus_key_age();
+        counters::snapshot()
+    }
+
+    /// The kind of backend of the internal storage, e.g., "vault" (see `Storage::backend`), which
+    /// is also reported by the `aptos_safety_rules_storage_backend` gauge.
+    pub fn storage_backend_name(&self) -> &'static str {
+        self.internal_store.backend()
+    }
+
+    /// Times a storage operation, which is also recorded as an event of the current span.
+    fn start_timer(&self, source: &str, field: &str) -> HistogramTimer {
+        tracing::trace!(operation = source, key = field, "Storage operation");
+        counters::start_storage_timer(source, field, self.storage_backend_name())
+    }
+
+    /// Deletes all the values held by safety rules, i.e., the keys with their previous versions,
+    /// the owner account, the safety data with its backups and the waypoint, so that the storage
+    /// can be initialized again without restarting the process, e.g., between rounds of a test.
+    /// Keys held by the backend apart from its values (e.g., in Vault's transit engine) are kept.
+    #[cfg(any(test, feature = "testing"))]
+    #[tracing::instrument(skip_all)]
+    pub fn clear(&mut self) -> Result<(), Error> {
+        // The initialization markers go first, so that a partial clear is not mistaken for
+        // initialized storage
+        let mut keys = vec![
+            INITIALIZATION_COMPLETE.to_string(),
+            OWNER_ACCOUNT.to_string(),
+            SAFETY_DATA.to_string(),
+            WAYPOINT.to_string(),
+            CONSENSUS_KEY.to_string(),
+            EXECUTION_KEY.to_string(),
+        ];
+        for prefix in [CONSENSUS_KEY, EXECUTION_KEY, SAFETY_DATA_BACKUPS] {
+            keys.extend(
+                self.internal_store
+                    .list_keys(Some(&format!("{}_", prefix)))?,
+            );
+        }
+        keys.push(SAFETY_DATA_BACKUPS.to_string());
+        for key in keys {
+            self.delete(&key)?;
+        }
+
+        self.cached_safety_data = None;
+        self.invalidate_consensus_key_cache();
+        self.derived_consensus_keys.lock().clear();
+        Ok(())
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    #[tracing::instrument(skip_all)]
+    pub fn internal_store(&mut self) -> &mut Storage {
+        // The caller may rotate or replace keys, so cached versions can no longer be trusted.
+        self.invalidate_consensus_key_cache();
+        &mut self.internal_store
+    }
+}
+
+/// Compares safety data field by field. The fields are destructured, so that a field added to
+/// SafetyData cannot be left out of the comparison.
+fn safety_data_matches(current: &SafetyData, expected: &SafetyData) -> bool {
+    let SafetyData {
+        epoch,
+        last_voted_round,
+        preferred_round,
+        one_chain_round,
+        last_vote,
+    } = current;
+    *epoch == expected.epoch
+        && *last_voted_round == expected.last_voted_round
+        && *preferred_round == expected.preferred_round
+        && *one_chain_round == expected.one_chain_round
+        && *last_vote == expected.last_vote
+}
+
+/// Decodes safety data as stored. A value that is not exactly in the current layout, e.g., as it
+/// lacks fields added since, is decoded against the legacy layouts so that it can be migrated.
+/// Values that only decode in the current layout, e.g., with fields of a later release, are left
+/// as they are.
+fn decode_safety_data(
+    value: serde_json::Value,
+) -> Result<(SafetyData, Option<LegacySafetyDataLayout>), Error> {
+    count_safety_data_copy();
+    let current = serde_json::from_value::<SafetyData>(value.clone());
+    if let Ok(safety_data) = &current {
+        if serde_json::to_value(safety_data)? == value {
+            return Ok((safety_data.clone(), None));
+        }
+    }
+    if let Ok(legacy) = serde_json::from_value::<SafetyDataV1>(value) {
+        return Ok((legacy.into(), Some(LegacySafetyDataLayout::V1)));
+    }
+    Ok((current?, None))
+}
+
+#[cfg(test)]
+thread_local! {
+    /// The copies of the full SafetyData made by this thread, i.e., the clones of the cached safety
+    /// data and the safety data decoded from storage, so that tests can check which reads make
+    /// them.
+    static SAFETY_DATA_COPIES: Cell<usize> = Cell::new(0);
+}
+
+fn count_safety_data_copy() {
+    #[cfg(test)]
+    SAFETY_DATA_COPIES.with(|copies| copies.set(copies.get() + 1));
+}
+
+fn safety_data_too_large() -> Error {
+    Error::SecureStorageUnexpectedError("safety data too large".into())
+}
+
+/// Safety data as decoded by the storage backend from what it stores, or TooLarge if its size
+/// exceeds MAX_SAFETY_DATA_BCS_SIZE. The size is counted as the value is decoded, by the bytes of
+/// its strings and 8 bytes for any other value, which is no less than its BCS encoding takes for
+/// the hex encoded hashes, keys and signatures of SafetyData. Once the limit is exceeded, the rest
+/// of the value is skipped without being held in memory.
+enum BoundedSafetyData {
+    Value(serde_json::Value),
+    TooLarge,
+}
+
+impl<'de> Deserialize<'de> for BoundedSafetyData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let remaining = Cell::new(Some(MAX_SAFETY_DATA_BCS_SIZE));
+        let value = BoundedValue {
+            remaining: &remaining,
+            keep: true,
+        }
+        .deserialize(deserializer)?;
+        Ok(value.map_or(Self::TooLarge, Self::Value))
+    }
+}
+
+/// The summary of safety data as decoded by the storage backend, or TooLarge if the size of the
+/// safety data exceeds MAX_SAFETY_DATA_BCS_SIZE, see BoundedSafetyData. Only the fields of the
+/// summary are kept, the other fields are counted but skipped.
+enum BoundedSafetyDataSummary {
+    Value(SafetyDataSummary),
+    TooLarge,
+}
+
+impl<'de> Deserialize<'de> for BoundedSafetyDataSummary {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let remaining = Cell::new(Some(MAX_SAFETY_DATA_BCS_SIZE));
+        let fields = deserializer.deserialize_any(BoundedSummaryFields {
+            remaining: &remaining,
+        })?;
+        match fields {
+            Some(fields) => serde_json::from_value(fields.into())
+                .map(Self::Value)
+                .map_err(de::Error::custom),
+            None => Ok(Self::TooLarge),
+        }
+    }
+}
+
+/// Decodes the fields of the summary of safety data, see BoundedSafetyDataSummary.
+struct BoundedSummaryFields<'a> {
+    remaining: &'a Cell<Option<usize>>,
+}
+
+impl<'de, 'a> Visitor<'de> for BoundedSummaryFields<'a> {
+    type Value = Option<serde_json::Map<String, serde_json::Value>>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("safety data")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let bounded = |keep| BoundedValue {
+            remaining: self.remaining,
+            keep,
+        };
+        let mut fields = serde_json::Map::new();
+        while let Some(key) = map.next_key_seed(bounded(true))? {
+            match key {
+                Some(serde_json::Value::String(key))
+                    if key == "epoch" || key == "last_voted_round" =>
+                {
+                    if let Some(value) = map.next_value_seed(bounded(true))? {
+                        fields.insert(key, value);
+                    }
+                }
+                _ => {
+                    map.next_value_seed(bounded(false))?;
+                }
+            }
+        }
+        Ok(bounded(true).charge(1).then(|| fields))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        // Not safety data, but still only an error if it is within the size limit
+        let bounded = BoundedValue {
+            remaining: self.remaining,
+            keep: false,
+        };
+        match bounded.visit_seq(seq)? {
+            Some(_) => Err(de::Error::invalid_type(de::Unexpected::Seq, &self)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Decodes a value within the remaining size, see BoundedSafetyData. None once it is exhausted.
+/// Unless the value is kept, it is only counted, and Null is returned in its place.
+#[derive(Clone, Copy)]
+struct BoundedValue<'a> {
+    remaining: &'a Cell<Option<usize>>,
+    keep: bool,
+}
+
+impl<'a> BoundedValue<'a> {
+    /// Takes `size` bytes from the remaining size, returning whether they were available.
+    fn charge(self, size: usize) -> bool {
+        let remaining = self
+            .remaining
+            .get()
+            .and_then(|remaining| remaining.checked_sub(size));
+        self.remaining.set(remaining);
+        remaining.is_some()
+    }
+
+    /// Takes `size` bytes for the value, which is only built if it is kept.
+    fn take(
+        self,
+        size: usize,
+        value: impl FnOnce() -> serde_json::Value,
+    ) -> Option<serde_json::Value> {
+        if !self.charge(size) {
+            return None;
+        }
+        Some(if self.keep {
+            value()
+        } else {
+            serde_json::Value::Null
+        })
+    }
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for BoundedValue<'a> {
+    type Value = Option<serde_json::Value>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        if self.remaining.get().is_none() {
+            deserializer.deserialize_ignored_any(IgnoredAny)?;
+            return Ok(None);
+        }
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for BoundedValue<'a> {
+    type Value = Option<serde_json::Value>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("safety data")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(self.take(8, || value.into()))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(self.take(8, || value.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(self.take(8, || value.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(self.take(8, || value.into()))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(self.take(value.len(), || value.into()))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(self.take(8, || serde_json::Value::Null))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = vec![];
+        while let Some(value) = seq.next_element_seed(self)? {
+            if self.keep {
+                values.extend(value);
+            }
+        }
+        Ok(self.take(1, || values.into()))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut values = serde_json::Map::new();
+        while let Some(key) = map.next_key_seed(self)? {
+            let value = map.next_value_seed(self)?;
+            if let (Some(serde_json::Value::String(key)), Some(value)) = (key, value) {
+                if self.keep {
+                    values.insert(key, value);
+                }
+            }
+        }
+        Ok(self.take(1, || values.into()))
+    }
+}
+
+/// Whether a storage operation failed as the backend is degraded. Other errors, e.g., a version
+/// mismatch, show that it is reachable.
+fn is_storage_failure<T>(result: &Result<T, aptos_secure_storage::Error>) -> bool {
+    matches!(
+        result,
+        Err(aptos_secure_storage::Error::Unavailable { .. })
+            | Err(aptos_secure_storage::Error::InternalError(_))
+    )
+}
+
+fn safety_data_backup_key(epoch: u64) -> String {
+    format!("{}_{}", SAFETY_DATA_BACKUPS, epoch)
+}
+
+/// Ensures that replacing `current` with `restored` does not move safety rules backwards.
+fn check_monotonic(current: &SafetyData, restored: &SafetyData) -> Result<(), Error> {
+    if restored.epoch < current.epoch {
+        return Err(Error::IncorrectEpoch(restored.epoch, current.epoch));
+    }
+    if restored.epoch == current.epoch {
+        if restored.last_voted_round < current.last_voted_round {
+            return Err(Error::IncorrectLastVotedRound(
+                restored.last_voted_round,
+                current.last_voted_round,
+            ));
+        }
+        if restored.preferred_round < current.preferred_round {
+            return Err(Error::IncorrectPreferredRound(
+                restored.preferred_round,
+                current.preferred_round,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Records a key import. Only a short fingerprint of the public key is logged, so that logs can
+/// be correlated with storage audit logs without exposing full keys.
+fn log_key_import(name: &str, public_key: &Ed25519PublicKey) {
+    counters::increment_key_event(name, "import");
+    info!(key_import_log_schema(name, public_key));
+}
+
+fn key_import_log_schema<'a>(
+    name: &'a str,
+    public_key: &Ed25519PublicKey,
+) -> logging::SafetyLogSchema<'a> {
+    logging::SafetyLogSchema::new(LogEntry::KeyRotation, LogEvent::Import)
+        .key_name(name)
+        .key_fingerprint(key_fingerprint(public_key))
+}
+
+/// The first 8 bytes of the public key, in hex, enough to tell keys apart.
+fn key_fingerprint(public_key: &Ed25519PublicKey) -> String {
+    public_key.to_bytes()[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn observe_read_bytes<T: Serialize>(key: &str, value: &T) {
+    if let Ok(size) = bcs::serialized_size(value) {
+        counters::observe_read_bytes(key, size);
+    }
+}
+
+fn observe_write_bytes<T: Serialize>(key: &str, value: &T) {
+    if let Ok(size) = bcs::serialized_size(value) {
+        counters::observe_write_bytes(key, size);
+    }
+}
+
+/// Private keys are intentionally not cloneable outside of tests, so copies handed out from the
+/// consensus key cache are rebuilt from the key bytes.
+fn copy_private_key(private_key: &Ed25519PrivateKey) -> Option<Ed25519PrivateKey> {
+    Ed25519PrivateKey::try_from(&private_key.to_bytes()[..]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{counters, test_utils::mock_time_service};
+    use aptos_crypto::{hash::HashValue, Uniform, ValidCryptoMaterialStringExt};
+    use aptos_logger::{Key, Schema, Value, Visitor};
+    use aptos_secure_storage::{
+        ChaosOperation, ChaosPolicy, ChaosStorage, EnvStorage, InMemoryStorage, Namespaced,
+        OnDiskStorage, Overlay, StorageSnapshot,
+    };
+    use aptos_temppath::TempPath;
+    use aptos_types::{
+        account_address::AccountAddress,
+        block_info::BlockInfo,
+        epoch_state::EpochState,
+        ledger_info::LedgerInfo,
+        proof::TransactionAccumulatorProof,
+        transaction::Version,
+        validator_signer::ValidatorSigner,
+        validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
+        waypoint::Waypoint,
+    };
+    use consensus_types::{timeout::Timeout, vote_data::VoteData};
+    use serial_test::serial;
+    use std::{fs, path::Path};
+    use tracing_test::traced_test;
+
+    fn counters_safety_storage() -> PersistentSafetyStorage {
+        PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            Author::random(),
+            ValidatorSigner::from_int(0).private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        )
+    }
+
+    /// Captures the in-memory storage of the safety storage, see restore_storage.
+    fn snapshot_storage(safety_storage: &mut PersistentSafetyStorage) -> StorageSnapshot {
+        match safety_storage.internal_store() {
+            Storage::InMemoryStorage(storage) => storage.snapshot(),
+            _ => panic!("Only in-memory storage can be snapshotted"),
+        }
+    }
+
+    /// Restores the storage to a snapshot, without initializing it again, and drops the cached
+    /// safety data that the snapshot may have outdated.
+    fn restore_storage(safety_storage: &mut PersistentSafetyStorage, snapshot: StorageSnapshot) {
+        match safety_storage.internal_store() {
+            Storage::InMemoryStorage(storage) => storage.restore(snapshot),
+            _ => panic!("Only in-memory storage can be restored"),
+        }
+        safety_storage.cached_safety_data = None;
+    }
+
+    #[test]
+    fn test_storage_backend_name() {
+        let state_gauges = counters::StateGauges::unregistered();
+        let mut safety_storage = counters_safety_storage();
+        safety_storage.set_state_gauges(state_gauges.clone());
+        assert_eq!(safety_storage.storage_backend_name(), "in_memory");
+        assert_eq!(state_gauges.get_storage_backend("in_memory"), 1);
+
+        let temp_dir = TempPath::new();
+        temp_dir.create_as_dir().unwrap();
+        let mut safety_storage = PersistentSafetyStorage::new(
+            Storage::from(OnDiskStorage::new(temp_dir.path().join("safety_rules"))),
+            true,
+        );
+        safety_storage.set_state_gauges(state_gauges.clone());
+        assert_eq!(safety_storage.storage_backend_name(), "on_disk");
+        assert_eq!(state_gauges.get_storage_backend("on_disk"), 1);
+        assert_eq!(state_gauges.get_storage_backend("in_memory"), 0);
+    }
+
+    #[test]
+    fn test_safety_data_counters() {
+        let state_gauges = counters::StateGauges::unregistered();
+        let mut safety_storage = counters_safety_storage();
+        safety_storage.set_state_gauges(state_gauges.clone());
+        safety_storage
+            .set_safety_data(SafetyData::new(1, 0, 0, 0, None))
+            .unwrap();
+        assert_eq!(state_gauges.get_state(counters::EPOCH), 1);
+        assert_eq!(state_gauges.get_state(counters::LAST_VOTED_ROUND), 0);
+        assert_eq!(state_gauges.get_state(counters::PREFERRED_ROUND), 0);
+
+        safety_storage
+            .set_safety_data(SafetyData::new(9, 8, 1, 0, None))
+            .unwrap();
+
+        let safety_data = safety_storage.safety_data().unwrap();
+        assert_eq!(safety_data.epoch, 9);
+        assert_eq!(safety_data.last_voted_round, 8);
+        assert_eq!(safety_data.preferred_round, 1);
+        assert_eq!(state_gauges.get_state(counters::EPOCH), 9);
+        assert_eq!(state_gauges.get_state(counters::LAST_VOTED_ROUND), 8);
+        assert_eq!(state_gauges.get_state(counters::PREFERRED_ROUND), 1);
+    }
+
+    #[test]
+    fn test_metrics_snapshot() {
+        let safety_storage = counters_safety_storage();
+        // The metrics are global to the process, so other tests may update or reset their values
+        // concurrently, but every field of the state is reported, along with the other metrics
+        let snapshot = safety_storage.metrics_snapshot();
+        for field in &[
+            counters::CONSENSUS_KEY_AGE_SECONDS,
+            counters::EPOCH,
+            counters::LAST_VOTED_ROUND,
+            counters::PREFERRED_ROUND,
+            counters::STORAGE_CIRCUIT_STATE,
+            counters::WAYPOINT_VERSION,
+        ] {
+            assert!(snapshot.contains_key(*field));
+        }
+        assert!(snapshot.contains_key("aptos_safety_rules_proposal_timestamp_rejections"));
+    }
+
+    #[test]
+    fn test_waypoint_counters() {
+        let state_gauges = counters::StateGauges::unregistered();
+        let mut safety_storage = counters_safety_storage();
+        safety_storage.set_state_gauges(state_gauges.clone());
+        let waypoint = safety_storage.waypoint().unwrap();
+        assert_eq!(waypoint.version(), Version::default());
+
+        for expected_version in 1..=10u64 {
+            let li = LedgerInfo::new(
+                BlockInfo::new(
                     1,
                     10,
                     HashValue::random(),
@@ -262,15 +2138,1938 @@ mod tests {
                 ),
                 HashValue::zero(),
             );
-            let waypoint = &Waypoint::new_epoch_boundary(&li).unwrap();
-            safety_storage.set_waypoint(waypoint).unwrap();
+            // A single-leaf accumulator: the root hash is the ledger info hash itself.
+            let proof = TransactionAccumulatorProof::new(vec![]);
+            let waypoint = &Waypoint::from_ledger_info_with_proof(&li, &proof, li.hash()).unwrap();
+            safety_storage.set_waypoint(waypoint).unwrap();
+
+            let waypoint = safety_storage.waypoint().unwrap();
+            assert!(li.matches_waypoint(&waypoint));
+            assert_eq!(
+                state_gauges.get_state(counters::WAYPOINT_VERSION) as u64,
+                expected_version
+            );
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_storage_bytes_counters() {
+        counters::reset_all();
+        let mut safety_storage = counters_safety_storage();
+        // Other tests may touch the same histograms concurrently, hence the lower bounds
+        let assert_observed = |before: (u64, f64), after: (u64, f64), size: usize| {
+            assert!(after.0 > before.0);
+            assert!(after.1 >= before.1 + size as f64);
+        };
+
+        let safety_data = SafetyData::new(9, 8, 1, 0, None);
+        let size = bcs::serialized_size(&safety_data).unwrap();
+        let writes = counters::get_write_bytes(SAFETY_DATA);
+        safety_storage.set_safety_data(safety_data).unwrap();
+        assert_observed(writes, counters::get_write_bytes(SAFETY_DATA), size);
+
+        let reads = counters::get_read_bytes(SAFETY_DATA);
+        safety_storage.cached_safety_data = None;
+        safety_storage.safety_data().unwrap();
+        assert_observed(reads, counters::get_read_bytes(SAFETY_DATA), size);
+
+        let waypoint = safety_storage.waypoint().unwrap();
+        let size = bcs::serialized_size(&waypoint).unwrap();
+        let writes = counters::get_write_bytes(WAYPOINT);
+        safety_storage.set_waypoint(&waypoint).unwrap();
+        assert_observed(writes, counters::get_write_bytes(WAYPOINT), size);
+
+        let reads = counters::get_read_bytes(WAYPOINT);
+        safety_storage.waypoint().unwrap();
+        assert_observed(reads, counters::get_read_bytes(WAYPOINT), size);
+
+        let reads = counters::get_read_bytes(OWNER_ACCOUNT);
+        let author = safety_storage.author().unwrap();
+        let size = bcs::serialized_size(&author).unwrap();
+        assert_observed(reads, counters::get_read_bytes(OWNER_ACCOUNT), size);
+    }
+
+    #[test]
+    fn test_consensus_key_age_counter() {
+        let state_gauges = counters::StateGauges::unregistered();
+        let mut safety_storage = counters_safety_storage();
+        safety_storage.set_state_gauges(state_gauges.clone());
+        let created_at = safety_storage.consensus_key_created_at().unwrap();
+        assert!(created_at <= safety_storage.time_service().now_unix_time().as_millis() as u64);
+
+        // A day after the key was created
+        let time = mock_time_service(Duration::from_millis(created_at));
+        safety_storage.set_time_service(time.clone().into());
+        time.advance(Duration::from_secs(24 * 60 * 60));
+        safety_storage.set_consensus_key_max_age_secs(Some(60));
+        safety_storage.safety_data().unwrap();
+
+        let age_secs = state_gauges.get_state(counters::CONSENSUS_KEY_AGE_SECONDS) as u64;
+        assert_eq!(age_secs, 24 * 60 * 60);
+
+        // Once the key is rotated, the age is that of the new key
+        let rotated_at = safety_storage.time_service().now_unix_time().as_millis() as u64;
+        safety_storage
+            .internal_store()
+            .set(CONSENSUS_KEY_CREATED_AT, rotated_at)
+            .unwrap();
+        time.advance(Duration::from_secs(60));
+        safety_storage.safety_data().unwrap();
+        let age_secs = state_gauges.get_state(counters::CONSENSUS_KEY_AGE_SECONDS) as u64;
+        assert_eq!(age_secs, 60);
+    }
+
+    #[test]
+    fn test_oversized_safety_data() {
+        let signer = ValidatorSigner::from_int(0);
+        let storage = Storage::from(InMemoryStorage::new());
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            storage,
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            false,
+        );
+
+        // A vote for a block that ends the epoch carries the next validator set, which is written
+        // and read back as is
+        let validators = (0..100)
+            .map(|_| {
+                let info = ValidatorConsensusInfo::new(signer.public_key(), 1);
+                (AccountAddress::random(), info)
+            })
+            .collect();
+        let next_epoch_state = EpochState {
+            epoch: 2,
+            verifier: ValidatorVerifier::new(validators),
+        };
+        let proposed = BlockInfo::new(
+            1,
+            1,
+            HashValue::zero(),
+            HashValue::zero(),
+            0,
+            0,
+            Some(next_epoch_state),
+        );
+        let vote = Vote::new(
+            VoteData::new(proposed, BlockInfo::empty()),
+            signer.author(),
+            LedgerInfo::new(BlockInfo::empty(), HashValue::zero()),
+            &signer,
+        );
+        let safety_data = SafetyData::new(1, 1, 0, 0, Some(vote));
+        safety_storage.set_safety_data(safety_data.clone()).unwrap();
+        assert_eq!(safety_storage.safety_data().unwrap(), safety_data);
+
+        // But a crafted blob beyond the limit is not decoded, even if only part of it is read
+        safety_storage
+            .internal_store()
+            .set(SAFETY_DATA, vec![0u64; MAX_SAFETY_DATA_BCS_SIZE / 8 + 1])
+            .unwrap();
+        assert_eq!(
+            safety_storage.safety_data().unwrap_err(),
+            Error::SecureStorageUnexpectedError("safety data too large".into())
+        );
+        assert_eq!(
+            safety_storage.current_epoch().unwrap_err(),
+            Error::SecureStorageUnexpectedError("safety data too large".into())
+        );
+    }
+
+    #[test]
+    fn test_legacy_safety_data_migration() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/safety_data");
+        let layouts = [("v1.json", SafetyData::new(5, 10, 8, 0, None))];
+        for (fixture, expected) in layouts {
+            let legacy: serde_json::Value =
+                serde_json::from_slice(&fs::read(fixtures.join(fixture)).unwrap()).unwrap();
+            for enable_cached_safety_data in [true, false] {
+                let temp_dir = TempPath::new();
+                temp_dir.create_as_dir().unwrap();
+                let file_path = temp_dir.path().join("safety_rules");
+                let signer = ValidatorSigner::from_int(0);
+                let mut safety_storage = PersistentSafetyStorage::initialize(
+                    Storage::from(OnDiskStorage::new(file_path.clone())),
+                    signer.author(),
+                    signer.private_key().clone(),
+                    Ed25519PrivateKey::generate_for_testing(),
+                    Waypoint::default(),
+                    enable_cached_safety_data,
+                );
+                safety_storage
+                    .internal_store()
+                    .set(SAFETY_DATA, legacy.clone())
+                    .unwrap();
+                safety_storage.cached_safety_data = None;
+
+                // Reads convert the legacy layout, but do not write
+                assert_eq!(safety_storage.safety_data().unwrap(), expected);
+                let stored: serde_json::Value = safety_storage
+                    .internal_store()
+                    .get(SAFETY_DATA)
+                    .unwrap()
+                    .value;
+                assert_eq!(stored, legacy);
+                drop(safety_storage);
+
+                // The safety data is rewritten in the current layout once the storage is opened
+                let mut safety_storage = PersistentSafetyStorage::new(
+                    Storage::from(OnDiskStorage::new(file_path)),
+                    enable_cached_safety_data,
+                );
+                let stored: serde_json::Value = safety_storage
+                    .internal_store()
+                    .get(SAFETY_DATA)
+                    .unwrap()
+                    .value;
+                assert_eq!(stored, serde_json::to_value(&expected).unwrap());
+                assert_eq!(
+                    decode_safety_data(stored).unwrap(),
+                    (expected.clone(), None)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_safety_data_in_unknown_layout() {
+        // Fields of a later release are kept, rather than dropped by a rewrite
+        let mut value = serde_json::to_value(SafetyData::new(5, 10, 8, 9, None)).unwrap();
+        value["highest_timeout_round"] = serde_json::json!(11);
+        assert_eq!(
+            decode_safety_data(value).unwrap(),
+            (SafetyData::new(5, 10, 8, 9, None), None)
+        );
+
+        let value = serde_json::json!({ "epoch": 5 });
+        assert!(matches!(
+            decode_safety_data(value),
+            Err(Error::SerializationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_consensus_key_cache() {
+        let consensus_private_key = ValidatorSigner::from_int(0).private_key().clone();
+        let consensus_public_key = consensus_private_key.public_key();
+        let storage = Storage::from(InMemoryStorage::new());
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            storage,
+            Author::random(),
+            consensus_private_key.clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+
+        // The first lookup hits the backend and populates the cache
+        let private_key = safety_storage
+            .consensus_key_for_version(consensus_public_key.clone())
+            .unwrap();
+        assert_eq!(private_key, consensus_private_key);
+
+        // Swap out the backend: cached lookups must not need it
+        safety_storage.internal_store = Storage::from(InMemoryStorage::new());
+        let private_key = safety_storage
+            .consensus_key_for_version(consensus_public_key.clone())
+            .unwrap();
+        assert_eq!(private_key, consensus_private_key);
+
+        // Unknown versions still go to the backend
+        let unknown_public_key = Ed25519PrivateKey::generate_for_testing().public_key();
+        safety_storage
+            .consensus_key_for_version(unknown_public_key)
+            .unwrap_err();
+
+        // Once invalidated, the (now empty) backend is consulted again
+        safety_storage.invalidate_consensus_key_cache();
+        safety_storage
+            .consensus_key_for_version(consensus_public_key)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_consensus_key_cache_bounded() {
+        let storage = Storage::from(InMemoryStorage::new());
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            storage,
+            Author::random(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+
+        for _ in 0..2 * CONSENSUS_KEY_CACHE_SIZE {
+            let public_key = safety_storage
+                .internal_store
+                .rotate_key(CONSENSUS_KEY)
+                .unwrap();
+            safety_storage
+                .consensus_key_for_version(public_key)
+                .unwrap();
+        }
+        assert_eq!(
+            safety_storage.consensus_key_cache.lock().len(),
+            CONSENSUS_KEY_CACHE_SIZE
+        );
+    }
+
+    #[test]
+    fn test_consensus_key_for_pruned_version() {
+        let mut storage = InMemoryStorage::new();
+        storage.set_max_key_versions(6);
+        let consensus_private_key = Ed25519PrivateKey::generate_for_testing();
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(storage),
+            Author::random(),
+            consensus_private_key.clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+
+        let mut versions = vec![consensus_private_key.public_key()];
+        for _ in 0..5 {
+            versions.push(
+                safety_storage
+                    .internal_store()
+                    .rotate_key(CONSENSUS_KEY)
+                    .unwrap(),
+            );
+        }
+        safety_storage
+            .internal_store()
+            .prune_key_versions(CONSENSUS_KEY, 2, None)
+            .unwrap();
+
+        for version in &versions[4..] {
+            safety_storage
+                .consensus_key_for_version(version.clone())
+                .unwrap();
+        }
+        for version in &versions[..4] {
+            assert!(matches!(
+                safety_storage.consensus_key_for_version(version.clone()),
+                Err(Error::KeyVersionPruned(_, _))
+            ));
+        }
+        assert!(matches!(
+            safety_storage
+                .consensus_key_for_version(Ed25519PrivateKey::generate_for_testing().public_key()),
+            Err(Error::SecureStorageMissingDataError(_))
+        ));
+    }
+
+    #[test]
+    fn test_current_epoch_and_last_voted_round() {
+        for enable_cached_safety_data in [true, false] {
+            let storage = Storage::from(InMemoryStorage::new());
+            let mut safety_storage = PersistentSafetyStorage::initialize(
+                storage,
+                Author::random(),
+                Ed25519PrivateKey::generate_for_testing(),
+                Ed25519PrivateKey::generate_for_testing(),
+                Waypoint::default(),
+                enable_cached_safety_data,
+            );
+            safety_storage
+                .set_safety_data(SafetyData::new(5, 7, 6, 6, None))
+                .unwrap();
+            assert_eq!(safety_storage.current_epoch().unwrap(), 5);
+            assert_eq!(safety_storage.current_last_voted_round().unwrap(), 7);
+
+            // Write safety data whose last vote cannot be deserialized: only a partial read of
+            // the summary fields (or the cache) can still answer
+            let safety_data = serde_json::json!({
+                "epoch": 8,
+                "last_voted_round": 9,
+                "preferred_round": 0,
+                "one_chain_round": 0,
+                "last_vote": "not a vote",
+            });
+            safety_storage
+                .internal_store
+                .set(SAFETY_DATA, safety_data)
+                .unwrap();
+            if enable_cached_safety_data {
+                assert_eq!(safety_storage.current_epoch().unwrap(), 5);
+                assert_eq!(safety_storage.current_last_voted_round().unwrap(), 7);
+                safety_storage.cached_safety_data = None;
+            } else {
+                safety_storage.safety_data().unwrap_err();
+            }
+            assert_eq!(safety_storage.current_epoch().unwrap(), 8);
+            assert_eq!(safety_storage.current_last_voted_round().unwrap(), 9);
+            if enable_cached_safety_data {
+                // Partial reads do not populate the cache
+                assert!(safety_storage.cached_safety_data.is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn test_current_epoch_and_last_voted_round_do_not_copy() {
+        let copies = || SAFETY_DATA_COPIES.with(Cell::get);
+        for enable_cached_safety_data in [true, false] {
+            let signer = ValidatorSigner::from_int(0);
+            let storage = Storage::from(InMemoryStorage::new());
+            let mut safety_storage = PersistentSafetyStorage::initialize(
+                storage,
+                signer.author(),
+                signer.private_key().clone(),
+                Ed25519PrivateKey::generate_for_testing(),
+                Waypoint::default(),
+                enable_cached_safety_data,
+            );
+            let proposed = BlockInfo::new(1, 1, HashValue::zero(), HashValue::zero(), 0, 0, None);
+            let vote = Vote::new(
+                VoteData::new(proposed, BlockInfo::empty()),
+                signer.author(),
+                LedgerInfo::new(BlockInfo::empty(), HashValue::zero()),
+                &signer,
+            );
+            let safety_data = SafetyData::new(1, 1, 0, 0, Some(vote));
+            safety_storage.set_safety_data(safety_data.clone()).unwrap();
+            assert_eq!(safety_storage.safety_data().unwrap(), safety_data);
+
+            // Neither the cache nor the storage backend hands out a copy of the safety data
+            let before = copies();
+            assert_eq!(safety_storage.current_epoch().unwrap(), 1);
+            assert_eq!(safety_storage.current_last_voted_round().unwrap(), 1);
+            assert_eq!(copies(), before);
+
+            // Unlike a full read
+            assert_eq!(safety_storage.safety_data().unwrap(), safety_data);
+            assert_eq!(copies(), before + 1);
+        }
+    }
+
+    #[test]
+    fn test_try_advance_round() {
+        for enable_cached_safety_data in [true, false] {
+            let storage = Storage::from(InMemoryStorage::new());
+            let mut safety_storage = PersistentSafetyStorage::initialize(
+                storage,
+                Author::random(),
+                Ed25519PrivateKey::generate_for_testing(),
+                Ed25519PrivateKey::generate_for_testing(),
+                Waypoint::default(),
+                enable_cached_safety_data,
+            );
+            let safety_data = SafetyData::new(5, 7, 6, 6, None);
+            safety_storage.set_safety_data(safety_data.clone()).unwrap();
+
+            assert_eq!(
+                safety_storage.try_advance_round(7),
+                Err(Error::InvalidRound(7, 7))
+            );
+            assert_eq!(
+                safety_storage.try_advance_round(3),
+                Err(Error::InvalidRound(3, 7))
+            );
+            assert_eq!(safety_storage.try_advance_round(8), Ok(8));
+
+            // The new round is persisted and the other fields are untouched
+            safety_storage.cached_safety_data = None;
+            assert_eq!(
+                safety_storage.safety_data().unwrap(),
+                safety_data.with_last_voted_round(8)
+            );
+            assert_eq!(
+                safety_storage.try_advance_round(8),
+                Err(Error::InvalidRound(8, 8))
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_advance_round_cases() {
+        let mut safety_storage = counters_safety_storage();
+        let safety_data = SafetyData::new(5, 7, 6, 6, None);
+        safety_storage.set_safety_data(safety_data.clone()).unwrap();
+        let snapshot = snapshot_storage(&mut safety_storage);
+
+        // Every case starts from the same safety data
+        let cases = [
+            (0, Err(Error::InvalidRound(0, 7))),
+            (6, Err(Error::InvalidRound(6, 7))),
+            (7, Err(Error::InvalidRound(7, 7))),
+            (8, Ok(8)),
+            (u64::MAX, Ok(u64::MAX)),
+        ];
+        for (new_round, expected) in cases {
+            restore_storage(&mut safety_storage, snapshot.clone());
+            assert_eq!(safety_storage.try_advance_round(new_round), expected);
+            safety_storage.cached_safety_data = None;
+            assert_eq!(
+                safety_storage.safety_data().unwrap(),
+                safety_data
+                    .clone()
+                    .with_last_voted_round(expected.unwrap_or(7))
+            );
+        }
+    }
+
+    #[test]
+    fn test_initialize_with_existing_keys() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut storage = Storage::from(InMemoryStorage::new());
+        storage
+            .import_private_key(CONSENSUS_KEY, signer.private_key().clone())
+            .unwrap();
+
+        let time = mock_time_service(Duration::from_secs(1_000));
+        let mut safety_storage = PersistentSafetyStorage::initialize_with_existing_keys(
+            storage,
+            signer.author(),
+            Waypoint::default(),
+            false,
+            true,
+            time.clone().into(),
+        )
+        .unwrap();
+        // The storage keeps the given clock
+        time.advance(Duration::from_secs(1));
+        assert_eq!(
+            safety_storage.time_service().now_unix_time(),
+            Duration::from_secs(1_001)
+        );
+        assert!(
+            safety_storage
+                .internal_store
+                .get::<bool>(INITIALIZATION_COMPLETE)
+                .unwrap()
+                .value
+        );
+        assert_eq!(safety_storage.author().unwrap(), signer.author());
+        assert_eq!(safety_storage.waypoint().unwrap(), Waypoint::default());
+        assert_eq!(
+            safety_storage.safety_data().unwrap(),
+            SafetyData::default().with_epoch(1)
+        );
+        safety_storage
+            .consensus_key_for_version(signer.public_key())
+            .unwrap();
+        // Only the pre-provisioned keys are present
+        safety_storage
+            .internal_store
+            .get_public_key(EXECUTION_KEY)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_initialize_with_generated_keys() {
+        let signer = ValidatorSigner::from_int(0);
+        let safety_storage = PersistentSafetyStorage::initialize_with_generated_keys(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            Waypoint::default(),
+            true,
+            TimeService::real(),
+        )
+        .unwrap();
+        assert_eq!(safety_storage.author().unwrap(), signer.author());
+        assert_eq!(safety_storage.waypoint().unwrap(), Waypoint::default());
+
+        // The keys sign inside the storage, but cannot be exported
+        let consensus_key = safety_storage
+            .internal_store
+            .get_public_key(CONSENSUS_KEY)
+            .unwrap()
+            .public_key;
+        let messages = [Timeout::new(1, 1), Timeout::new(1, 2)];
+        safety_storage.renew_signing_lease();
+        let signatures = safety_storage
+            .sign_batch(CONSENSUS_KEY, consensus_key.clone(), &messages)
+            .unwrap();
+        for (message, signature) in messages.iter().zip(&signatures) {
+            signature.verify(message, &consensus_key).unwrap();
+            let expected = safety_storage
+                .sign(CONSENSUS_KEY.into(), consensus_key.clone(), message)
+                .unwrap();
+            assert_eq!(signature, &expected);
+        }
+        assert!(matches!(
+            safety_storage.consensus_key_for_version(consensus_key),
+            Err(Error::SecureStorageError(
+                aptos_secure_storage::Error::KeyNotExportable(_)
+            ))
+        ));
+        for name in [CONSENSUS_KEY, EXECUTION_KEY] {
+            assert!(matches!(
+                safety_storage.internal_store.export_private_key(name),
+                Err(aptos_secure_storage::Error::KeyNotExportable(_))
+            ));
+        }
+        safety_storage
+            .export_public_keys()
+            .unwrap()
+            .verify()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_initialize_with_keys_from_env() {
+        let signer = ValidatorSigner::from_int(0);
+        let execution_private_key = Ed25519PrivateKey::generate_for_testing();
+        let prefix = "APTOS_TEST_SAFETY_RULES_ENV_";
+        std::env::set_var(
+            format!("{}CONSENSUS", prefix),
+            signer.private_key().to_encoded_string().unwrap(),
+        );
+        std::env::set_var(
+            format!("{}EXECUTION", prefix),
+            execution_private_key.to_encoded_string().unwrap(),
+        );
+
+        // Keys are read from the environment, everything else lives in memory
+        let storage = Storage::from(Overlay::new(
+            Box::new(Storage::from(EnvStorage::new(Some(prefix.into())))),
+            Box::new(Storage::from(InMemoryStorage::new())),
+        ));
+        let mut safety_storage = PersistentSafetyStorage::initialize_with_existing_keys(
+            storage,
+            signer.author(),
+            Waypoint::default(),
+            true,
+            false,
+            TimeService::real(),
+        )
+        .unwrap();
+        assert_eq!(safety_storage.author().unwrap(), signer.author());
+        assert_eq!(safety_storage.waypoint().unwrap(), Waypoint::default());
+
+        let safety_data = SafetyData::new(2, 3, 1, 0, None);
+        safety_storage.set_safety_data(safety_data.clone()).unwrap();
+        assert_eq!(safety_storage.safety_data().unwrap(), safety_data);
+        assert_eq!(
+            safety_storage.execution_public_key().unwrap(),
+            execution_private_key.public_key()
+        );
+        safety_storage
+            .consensus_key_for_version(signer.public_key())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_initialize_with_existing_keys_missing_key() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut storage = Storage::from(InMemoryStorage::new());
+        let error = PersistentSafetyStorage::initialize_with_existing_keys(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            Waypoint::default(),
+            false,
+            true,
+            TimeService::real(),
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(error, Error::ValidatorKeyNotFound(_)));
+
+        // The execution key is only checked when required
+        storage
+            .import_private_key(CONSENSUS_KEY, signer.private_key().clone())
+            .unwrap();
+        let error = PersistentSafetyStorage::initialize_with_existing_keys(
+            storage,
+            signer.author(),
+            Waypoint::default(),
+            true,
+            true,
+            TimeService::real(),
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(error, Error::ValidatorKeyNotFound(name) if name.contains(EXECUTION_KEY)));
+    }
+
+    #[test]
+    fn test_sign_batch() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        let timeouts: Vec<_> = (1..=3).map(|round| Timeout::new(1, round)).collect();
+
+        safety_storage.renew_signing_lease();
+        let signatures = safety_storage
+            .sign_batch(CONSENSUS_KEY, signer.public_key(), &timeouts)
+            .unwrap();
+        assert_eq!(signatures.len(), timeouts.len());
+        for (timeout, signature) in timeouts.iter().zip(signatures) {
+            let expected = safety_storage
+                .sign(CONSENSUS_KEY.into(), signer.public_key(), timeout)
+                .unwrap();
+            assert_eq!(signature, expected);
+        }
+        assert!(safety_storage
+            .sign_batch::<Timeout>(CONSENSUS_KEY, signer.public_key(), &[])
+            .unwrap()
+            .is_empty());
+
+        // The private key is not exported to sign a batch
+        safety_storage = safety_storage.with_key_policy(
+            KeyPolicy::allow_all().key(CONSENSUS_KEY, vec![Capability::Read, Capability::Sign]),
+        );
+        safety_storage
+            .sign_batch(CONSENSUS_KEY, signer.public_key(), &timeouts)
+            .unwrap();
+
+        // An unknown key version fails the whole batch
+        let unknown_public_key = Ed25519PrivateKey::generate_for_testing().public_key();
+        safety_storage
+            .sign_batch(CONSENSUS_KEY, unknown_public_key, &timeouts)
+            .unwrap_err();
+
+        safety_storage.set_standby(true);
+        assert_eq!(
+            safety_storage
+                .sign_batch(CONSENSUS_KEY, signer.public_key(), &timeouts)
+                .unwrap_err(),
+            Error::StandbyMode
+        );
+    }
+
+    #[test]
+    fn test_assert_consensus_key_matches() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        safety_storage
+            .assert_consensus_key_matches(&signer.public_key())
+            .unwrap();
+
+        let other_key = ValidatorSigner::from_int(1).public_key();
+        assert_eq!(
+            safety_storage
+                .assert_consensus_key_matches(&other_key)
+                .unwrap_err(),
+            Error::ConsensusKeyMismatch(other_key.to_string(), signer.public_key().to_string())
+        );
+
+        // With per-epoch consensus keys, the validator set holds a derived key
+        safety_storage
+            .set_consensus_key_master_seed(vec![7; 32])
+            .unwrap();
+        let epoch_key = safety_storage.derive_epoch_consensus_key(1).unwrap();
+        safety_storage
+            .assert_consensus_key_matches(&epoch_key)
+            .unwrap();
+        safety_storage
+            .assert_consensus_key_matches(&signer.public_key())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_export_public_keys() {
+        let signer = ValidatorSigner::from_int(0);
+        let execution_private_key = Ed25519PrivateKey::generate_for_testing();
+        let execution_public_key = execution_private_key.public_key();
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            signer.private_key().clone(),
+            execution_private_key,
+            Waypoint::default(),
+            true,
+        );
+        safety_storage.set_time_service(mock_time_service(Duration::from_secs(1)).into());
+
+        let bundle = safety_storage.export_public_keys().unwrap();
+        assert_eq!(bundle.consensus_pub_key, signer.public_key());
+        assert_eq!(bundle.execution_pub_key, execution_public_key);
+        assert_eq!(bundle.author, signer.author());
+        assert_eq!(bundle.created_at, 1_000);
+        assert!(bundle.epoch_consensus_pub_keys.is_empty());
+        bundle.verify().unwrap();
+
+        // Keys derived for recent epochs are exported too
+        safety_storage
+            .set_consensus_key_master_seed(vec![7; 32])
+            .unwrap();
+        let epoch_keys: Vec<_> = (1..=3)
+            .map(|epoch| {
+                let epoch_key = safety_storage.derive_epoch_consensus_key(epoch).unwrap();
+                (epoch, epoch_key)
+            })
+            .collect();
+        let bundle = safety_storage.export_public_keys().unwrap();
+        assert_eq!(bundle.consensus_pub_key, signer.public_key());
+        assert_eq!(bundle.epoch_consensus_pub_keys, epoch_keys[1..]);
+        bundle.verify().unwrap();
+
+        // The bundle round trips through BCS, and remains verifiable
+        let bundle: PublicKeyBundle = bcs::from_bytes(&bcs::to_bytes(&bundle).unwrap()).unwrap();
+        bundle.verify().unwrap();
+    }
+
+    #[test]
+    fn test_author_safety_data_and_waypoint() {
+        for enable_cached_safety_data in [true, false] {
+            let signer = ValidatorSigner::from_int(0);
+            let waypoint = Waypoint::default();
+            let mut safety_storage = PersistentSafetyStorage::initialize(
+                Storage::from(InMemoryStorage::new()),
+                signer.author(),
+                signer.private_key().clone(),
+                Ed25519PrivateKey::generate_for_testing(),
+                waypoint,
+                enable_cached_safety_data,
+            );
+            let safety_data = SafetyData::new(3, 5, 4, 0, None);
+            safety_storage.set_safety_data(safety_data.clone()).unwrap();
+            safety_storage.cached_safety_data = None;
+
+            assert_eq!(
+                safety_storage.author_safety_data_and_waypoint().unwrap(),
+                (signer.author(), safety_data.clone(), waypoint)
+            );
+            assert_eq!(
+                safety_storage.cached_safety_data.is_some(),
+                enable_cached_safety_data
+            );
+            // Served from the cache, if enabled, on the next read
+            assert_eq!(
+                safety_storage.author_safety_data_and_waypoint().unwrap(),
+                (signer.author(), safety_data, waypoint)
+            );
+        }
+    }
+
+    #[test]
+    fn test_safety_data_metadata() {
+        let mut safety_storage = counters_safety_storage();
+        safety_storage
+            .set_safety_data(SafetyData::new(1, 1, 0, 0, None))
+            .unwrap();
+        let first = safety_storage.safety_data_metadata().unwrap();
+
+        // Reads leave the metadata unchanged, while writes update it
+        safety_storage.safety_data().unwrap();
+        assert_eq!(safety_storage.safety_data_metadata().unwrap(), first);
+        safety_storage
+            .set_safety_data(SafetyData::new(1, 2, 0, 0, None))
+            .unwrap();
+        let second = safety_storage.safety_data_metadata().unwrap();
+        assert!(first.version < second.version);
+        assert!(first.last_update <= second.last_update);
+    }
+
+    #[test]
+    fn test_migrate_owner_account() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        let new_author = ValidatorSigner::from_int(1).author();
+
+        // The stored account must be the expected one
+        let other_author = ValidatorSigner::from_int(2).author();
+        assert_eq!(
+            safety_storage
+                .migrate_owner_account(new_author, other_author)
+                .unwrap_err(),
+            Error::OwnerAccountMismatch(other_author.to_string(), signer.author().to_string())
+        );
+
+        // Nor can it change once the validator voted in the current epoch
+        safety_storage
+            .set_safety_data(SafetyData::new(1, 3, 2, 0, None))
+            .unwrap();
+        assert_eq!(
+            safety_storage
+                .migrate_owner_account(new_author, signer.author())
+                .unwrap_err(),
+            Error::VotedInCurrentEpoch(1, 3)
+        );
+        assert_eq!(safety_storage.author().unwrap(), signer.author());
+
+        safety_storage
+            .set_safety_data(SafetyData::default().with_epoch(2))
+            .unwrap();
+        safety_storage
+            .migrate_owner_account(new_author, signer.author())
+            .unwrap();
+        assert_eq!(safety_storage.author().unwrap(), new_author);
+
+        // The key policy of an initialized data store denies the migration without panicking
+        let mut safety_storage =
+            safety_storage.with_key_policy(PersistentSafetyStorage::key_policy());
+        assert_eq!(
+            safety_storage
+                .migrate_owner_account(signer.author(), new_author)
+                .unwrap_err(),
+            Error::SecureStorageError(aptos_secure_storage::Error::PolicyDenied(format!(
+                "Write access to {}",
+                OWNER_ACCOUNT
+            )))
+        );
+        assert_eq!(safety_storage.author().unwrap(), new_author);
+    }
+
+    #[test]
+    fn test_migrate_storage() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        let safety_data = SafetyData::new(3, 5, 4, 0, None);
+        safety_storage.set_safety_data(safety_data.clone()).unwrap();
+
+        let mut dst = Storage::from(InMemoryStorage::new());
+        let report =
+            PersistentSafetyStorage::migrate_storage(safety_storage.internal_store(), &mut dst)
+                .unwrap();
+        assert!(report.is_complete());
+        for key in [
+            OWNER_ACCOUNT,
+            CONSENSUS_KEY,
+            EXECUTION_KEY,
+            SAFETY_DATA,
+            WAYPOINT,
+        ] {
+            assert!(report.copied.contains(&key.to_string()));
+        }
+        assert_eq!(report.copied.last().unwrap(), INITIALIZATION_COMPLETE);
+        assert!(report
+            .skipped
+            .contains(&CONSENSUS_KEY_MASTER_SEED.to_string()));
+
+        let mut migrated = PersistentSafetyStorage::new(dst, true);
+        assert_eq!(
+            migrated.author_safety_data_and_waypoint().unwrap(),
+            (signer.author(), safety_data, Waypoint::default())
+        );
+        let message = Timeout::new(1, 5);
+        migrated.renew_signing_lease();
+        assert_eq!(
+            migrated
+                .sign(CONSENSUS_KEY.into(), signer.public_key(), &message)
+                .unwrap(),
+            safety_storage
+                .sign(CONSENSUS_KEY.into(), signer.public_key(), &message)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compare_and_swap_safety_data() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            false,
+        );
+        let initial = safety_storage.safety_data().unwrap();
+        let next = initial.clone().with_last_voted_round(1);
+        safety_storage
+            .compare_and_swap_safety_data(&initial, next.clone())
+            .unwrap();
+
+        // Stale expectations are rejected, and the safety data is left unchanged
+        assert_eq!(
+            safety_storage
+                .compare_and_swap_safety_data(&initial, initial.clone().with_last_voted_round(2)),
+            Err(CasError::Mismatch)
+        );
+        assert_eq!(
+            safety_storage.compare_and_swap_safety_data(
+                &next.clone().with_one_chain_round(1),
+                next.clone().with_last_voted_round(2)
+            ),
+            Err(CasError::Mismatch)
+        );
+        assert_eq!(safety_storage.safety_data().unwrap(), next);
+
+        // Threads compute updates without holding the lock, and retry when they lose the race
+        let safety_storage = Arc::new(Mutex::new(safety_storage));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let safety_storage = safety_storage.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..25 {
+                        loop {
+                            let expected = safety_storage.lock().safety_data().unwrap();
+                            let round = expected.last_voted_round + 1;
+                            let new = expected.clone().with_last_voted_round(round);
+                            match safety_storage
+                                .lock()
+                                .compare_and_swap_safety_data(&expected, new)
+                            {
+                                Ok(()) => break,
+                                Err(CasError::Mismatch) => continue,
+                                Err(error) => panic!("Unexpected error: {}", error),
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let mut safety_storage = safety_storage.lock();
+        assert_eq!(safety_storage.safety_data().unwrap().last_voted_round, 101);
+
+        // Storage errors are told apart from mismatches
+        safety_storage.internal_store().delete(SAFETY_DATA).unwrap();
+        assert!(matches!(
+            safety_storage.compare_and_swap_safety_data(&next, next.clone()),
+            Err(CasError::Storage(Error::SecureStorageMissingDataError(_)))
+        ));
+    }
+
+    #[test]
+    fn test_export_archive() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(Namespaced::new(
+                "safety_rules",
+                Box::new(Storage::from(InMemoryStorage::new())),
+            )),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        let safety_data = SafetyData::new(3, 5, 4, 0, None);
+        safety_storage.set_safety_data(safety_data.clone()).unwrap();
+        let passphrase_path = TempPath::new();
+        fs::write(passphrase_path.path(), "correct horse battery staple").unwrap();
+        let passphrase = Passphrase::FromDisk(passphrase_path.path().to_path_buf());
+
+        let archive = safety_storage.export_archive(&passphrase).unwrap();
+        let mut dst = Storage::from(InMemoryStorage::new());
+        let report =
+            aptos_secure_storage::import_namespace(&mut dst, None, &archive, &passphrase).unwrap();
+        assert!(report.skipped.is_empty());
+        for key in [
+            OWNER_ACCOUNT,
+            CONSENSUS_KEY,
+            EXECUTION_KEY,
+            SAFETY_DATA,
+            WAYPOINT,
+        ] {
+            assert!(report.entries.contains(&key.to_string()));
+        }
+
+        let mut restored = PersistentSafetyStorage::new(dst, true);
+        assert_eq!(
+            restored.author_safety_data_and_waypoint().unwrap(),
+            (signer.author(), safety_data, Waypoint::default())
+        );
+        let message = Timeout::new(1, 5);
+        restored.renew_signing_lease();
+        assert_eq!(
+            restored
+                .sign(CONSENSUS_KEY.into(), signer.public_key(), &message)
+                .unwrap(),
+            safety_storage
+                .sign(CONSENSUS_KEY.into(), signer.public_key(), &message)
+                .unwrap()
+        );
+
+        // Safety rules refuses an archive older than its safety data, and writes nothing
+        let newer = SafetyData::new(3, 7, 4, 0, None);
+        safety_storage.set_safety_data(newer.clone()).unwrap();
+        assert_eq!(
+            safety_storage
+                .import_archive(&archive, &passphrase)
+                .unwrap_err(),
+            Error::IncorrectLastVotedRound(5, 7)
+        );
+        safety_storage.cached_safety_data = None;
+        assert_eq!(safety_storage.safety_data().unwrap(), newer);
+
+        // But restores one that does not move it backwards
+        safety_storage.set_safety_data(safety_data.clone()).unwrap();
+        safety_storage.internal_store().delete(WAYPOINT).unwrap();
+        let report = safety_storage
+            .import_archive(&archive, &passphrase)
+            .unwrap();
+        assert!(report.entries.contains(&WAYPOINT.to_string()));
+        assert_eq!(safety_storage.waypoint().unwrap(), Waypoint::default());
+    }
+
+    #[test]
+    fn test_derive_epoch_consensus_key() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        assert!(!safety_storage.has_consensus_key_master_seed().unwrap());
+        safety_storage.derive_epoch_consensus_key(1).unwrap_err();
+        assert!(matches!(
+            safety_storage.set_consensus_key_master_seed(vec![7; 16]),
+            Err(Error::InvalidMasterSeed(_))
+        ));
+
+        let master_seed = vec![7; 32];
+        safety_storage
+            .set_consensus_key_master_seed(master_seed.clone())
+            .unwrap();
+        assert!(safety_storage.has_consensus_key_master_seed().unwrap());
+        let epoch_keys: Vec<_> = (1..=3)
+            .map(|epoch| safety_storage.derive_epoch_consensus_key(epoch).unwrap())
+            .collect();
+        assert_eq!(
+            epoch_keys[2],
+            key_derivation::derive_consensus_key(&master_seed, 3)
+                .unwrap()
+                .public_key()
+        );
+        assert_eq!(
+            safety_storage.derive_epoch_consensus_key(3).unwrap(),
+            epoch_keys[2]
+        );
+
+        // Only the keys of the two most recent epochs are addressable
+        safety_storage
+            .consensus_key_for_version(epoch_keys[0].clone())
+            .unwrap_err();
+        let timeout = Timeout::new(3, 1);
+        safety_storage.renew_signing_lease();
+        for public_key in &epoch_keys[1..] {
+            let private_key = safety_storage
+                .consensus_key_for_version(public_key.clone())
+                .unwrap();
+            assert_eq!(&private_key.public_key(), public_key);
+            let signature = safety_storage
+                .sign(CONSENSUS_KEY.into(), public_key.clone(), &timeout)
+                .unwrap();
+            signature.verify(&timeout, public_key).unwrap();
+        }
+
+        // The stored consensus key remains usable
+        safety_storage
+            .consensus_key_for_version(signer.public_key())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_standby_promote_while_primary_alive() {
+        let signer = ValidatorSigner::from_int(0);
+        let time = mock_time_service(Duration::from_secs(1_000));
+        let lease_path = TempPath::new();
+        let start = |holder: &str, standby: bool| {
+            let mut safety_storage = PersistentSafetyStorage::initialize(
+                Storage::from(InMemoryStorage::new()),
+                signer.author(),
+                signer.private_key().clone(),
+                Ed25519PrivateKey::generate_for_testing(),
+                Waypoint::default(),
+                true,
+            );
+            safety_storage.set_time_service(time.clone().into());
+            safety_storage.set_signing_lease_config(
+                Some(Storage::from(OnDiskStorage::new(
+                    lease_path.path().to_path_buf(),
+                ))),
+                Some(holder.into()),
+                Duration::from_secs(60),
+            );
+            safety_storage.set_standby(standby);
+            safety_storage
+        };
+        let sign = |safety_storage: &PersistentSafetyStorage| {
+            safety_storage.sign(
+                CONSENSUS_KEY.into(),
+                signer.public_key(),
+                &Timeout::new(0, 0),
+            )
+        };
 
-            let waypoint = safety_storage.waypoint().unwrap();
-            assert_eq!(waypoint.version(), expected_version);
+        // Nothing is signed without the lease, which the primary acquires at startup
+        let mut primary = start("primary", false);
+        assert_eq!(sign(&primary).unwrap_err(), Error::SigningLeaseNotHeld);
+        primary.renew_signing_lease();
+        sign(&primary).unwrap();
+
+        // The standby keeps tracking the network, but nothing can be signed
+        let mut standby = start("standby", true);
+        standby
+            .set_safety_data(SafetyData::new(2, 10, 8, 0, None))
+            .unwrap();
+        assert_eq!(standby.current_last_voted_round().unwrap(), 10);
+        standby.renew_signing_lease();
+        assert_eq!(sign(&standby).unwrap_err(), Error::StandbyMode);
+        assert_eq!(
+            standby
+                .consensus_key_for_version(signer.public_key())
+                .unwrap_err(),
+            Error::StandbyMode
+        );
+
+        // The primary is alive and renews its lease while signing, so promotion fails
+        time.advance(Duration::from_secs(40));
+        primary.renew_signing_lease();
+        sign(&primary).unwrap();
+        let error = standby.promote().unwrap_err();
+        assert_eq!(error, Error::SigningLeaseHeld("primary".into(), 1_100_000));
+        assert!(standby.is_standby());
+
+        // Once the primary stops renewing its lease, the standby takes over, and the primary can
+        // no longer sign nor get the lease back
+        time.advance(Duration::from_secs(60));
+        assert_eq!(
+            sign(&primary).unwrap_err(),
+            Error::SigningLeaseExpired(1_100_000)
+        );
+        let lease = standby.promote().unwrap();
+        assert_eq!(lease.holder, "standby");
+        assert!(!standby.is_standby());
+        sign(&standby).unwrap();
+        primary.renew_signing_lease();
+        assert_eq!(
+            sign(&primary).unwrap_err(),
+            Error::SigningLeaseExpired(1_100_000)
+        );
+
+        // Demoting releases the lease at once, so that the primary can take it back
+        standby.demote().unwrap();
+        assert!(standby.is_standby());
+        assert_eq!(sign(&standby).unwrap_err(), Error::StandbyMode);
+        primary.renew_signing_lease();
+        sign(&primary).unwrap();
+    }
+
+    #[test]
+    fn test_reset_to_waypoint() {
+        let storage = Storage::from(InMemoryStorage::new());
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            storage,
+            Author::random(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        safety_storage
+            .set_safety_data(SafetyData::new(3, 20, 18, 19, None))
+            .unwrap();
+
+        let li = LedgerInfo::new(
+            BlockInfo::new(
+                7,
+                10,
+                HashValue::random(),
+                HashValue::random(),
+                500,
+                1000,
+                Some(EpochState::empty()),
+            ),
+            HashValue::zero(),
+        );
+        let new_waypoint = Waypoint::new_epoch_boundary(&li).unwrap();
+        assert_eq!(safety_storage.safety_data_backup_epochs().unwrap(), vec![3]);
+
+        // The voting history of the current, or an earlier, epoch is never discarded
+        for new_epoch in [2, 3] {
             assert_eq!(
-                counters::get_state(counters::WAYPOINT_VERSION) as u64,
-                expected_version
+                safety_storage.reset_to_waypoint(new_waypoint, new_epoch),
+                Err(Error::IncorrectEpoch(new_epoch, 3))
+            );
+        }
+        assert_eq!(
+            safety_storage.safety_data().unwrap(),
+            SafetyData::new(3, 20, 18, 19, None)
+        );
+        assert_eq!(safety_storage.waypoint().unwrap(), Waypoint::default());
+
+        safety_storage.reset_to_waypoint(new_waypoint, 8).unwrap();
+        assert!(safety_storage.cached_safety_data.is_none());
+
+        // The backups of the discarded voting history are deleted
+        assert!(safety_storage
+            .safety_data_backup_epochs()
+            .unwrap()
+            .is_empty());
+        assert!(safety_storage
+            .internal_store
+            .list_keys(Some(SAFETY_DATA_BACKUPS))
+            .unwrap()
+            .is_empty());
+
+        // Both the safety data and the waypoint must have reached the internal store
+        let stored_safety_data: SafetyData = safety_storage
+            .internal_store
+            .get(SAFETY_DATA)
+            .unwrap()
+            .value;
+        assert_eq!(stored_safety_data, SafetyData::default().with_epoch(8));
+        let stored_waypoint: Waypoint = safety_storage.internal_store.get(WAYPOINT).unwrap().value;
+        assert_eq!(stored_waypoint, new_waypoint);
+
+        assert_eq!(safety_storage.safety_data().unwrap(), stored_safety_data);
+        assert_eq!(safety_storage.waypoint().unwrap(), new_waypoint);
+    }
+
+    #[test]
+    fn test_reset_to_waypoint_failed_write() {
+        let temp_dir = TempPath::new();
+        temp_dir.create_as_dir().unwrap();
+        let storage = Storage::from(OnDiskStorage::new(temp_dir.path().join("safety_rules")));
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            storage,
+            Author::random(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        let safety_data = SafetyData::new(3, 20, 18, 19, None);
+        safety_storage.set_safety_data(safety_data.clone()).unwrap();
+        let version = |safety_storage: &PersistentSafetyStorage, key| {
+            safety_storage
+                .internal_store
+                .get::<serde_json::Value>(key)
+                .unwrap()
+                .version
+        };
+        let safety_data_version = version(&safety_storage, SAFETY_DATA);
+        let waypoint_version = version(&safety_storage, WAYPOINT);
+
+        // The waypoint cannot be written, which fails the whole batch
+        let mut safety_storage = safety_storage
+            .with_key_policy(KeyPolicy::allow_all().key(WAYPOINT, vec![Capability::Read]));
+        let li = LedgerInfo::new(
+            BlockInfo::new(
+                7,
+                10,
+                HashValue::random(),
+                HashValue::random(),
+                500,
+                1000,
+                Some(EpochState::empty()),
+            ),
+            HashValue::zero(),
+        );
+        let new_waypoint = Waypoint::new_epoch_boundary(&li).unwrap();
+        safety_storage
+            .reset_to_waypoint(new_waypoint, 8)
+            .unwrap_err();
+
+        // Neither field was written, not even to be restored
+        assert_eq!(safety_storage.safety_data().unwrap(), safety_data);
+        assert_eq!(safety_storage.waypoint().unwrap(), Waypoint::default());
+        assert_eq!(version(&safety_storage, SAFETY_DATA), safety_data_version);
+        assert_eq!(version(&safety_storage, WAYPOINT), waypoint_version);
+    }
+
+    #[test]
+    fn test_safety_data_backups() {
+        let storage = Storage::from(InMemoryStorage::new());
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            storage,
+            Author::random(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        safety_storage.set_max_safety_data_backups(2);
+
+        // Voting within an epoch does not create backups
+        safety_storage
+            .set_safety_data(SafetyData::new(1, 5, 4, 4, None))
+            .unwrap();
+        assert!(safety_storage
+            .safety_data_backup_epochs()
+            .unwrap()
+            .is_empty());
+
+        // Each epoch change creates a backup, only the most recent ones are retained
+        for epoch in 2..=4 {
+            safety_storage
+                .set_safety_data(SafetyData::default().with_epoch(epoch))
+                .unwrap();
+        }
+        assert_eq!(
+            safety_storage.safety_data_backup_epochs().unwrap(),
+            vec![3, 4]
+        );
+        assert_eq!(
+            safety_storage
+                .internal_store
+                .get::<SafetyDataBackup>(&safety_data_backup_key(2))
+                .unwrap_err(),
+            aptos_secure_storage::Error::KeyNotSet(safety_data_backup_key(2))
+        );
+
+        // Restoring must not move safety rules backwards
+        assert_eq!(
+            safety_storage.restore_from_epoch_backup(3),
+            Err(Error::IncorrectEpoch(3, 4))
+        );
+        safety_storage
+            .set_safety_data(SafetyData::new(4, 10, 8, 9, None))
+            .unwrap();
+        assert_eq!(
+            safety_storage.restore_from_epoch_backup(4),
+            Err(Error::IncorrectLastVotedRound(0, 10))
+        );
+        assert!(matches!(
+            safety_storage.restore_from_epoch_backup(2),
+            Err(Error::SecureStorageMissingDataError(_))
+        ));
+
+        // Once the primary copy is corrupted, e.g., found so after a restart, the most recent
+        // backup can be restored
+        safety_storage
+            .internal_store()
+            .set(SAFETY_DATA, "corrupted")
+            .unwrap();
+        assert_eq!(
+            safety_storage.restore_from_epoch_backup(4),
+            Err(Error::IncorrectLastVotedRound(0, 10))
+        );
+        safety_storage.cached_safety_data = None;
+        assert_eq!(
+            safety_storage.restore_from_epoch_backup(3),
+            Err(Error::IncorrectEpoch(3, 4))
+        );
+        safety_storage.restore_from_epoch_backup(4).unwrap();
+        assert_eq!(
+            safety_storage.safety_data().unwrap(),
+            SafetyData::default().with_epoch(4)
+        );
+        assert_eq!(safety_storage.waypoint().unwrap(), Waypoint::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "already in use by PID")]
+    fn test_on_disk_storage_lock() {
+        let temp_dir = TempPath::new();
+        temp_dir.create_as_dir().unwrap();
+        let file_path = temp_dir.path().join("safety_rules");
+
+        let _safety_storage = PersistentSafetyStorage::new(
+            Storage::from(OnDiskStorage::new(file_path.clone())),
+            true,
+        );
+        PersistentSafetyStorage::new(Storage::from(OnDiskStorage::new(file_path)), true);
+    }
+
+    #[test]
+    fn test_storage_circuit_breaker() {
+        let temp_dir = TempPath::new();
+        temp_dir.create_as_dir().unwrap();
+        let moved_dir = TempPath::new();
+        let signer = ValidatorSigner::from_int(0);
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(OnDiskStorage::new(temp_dir.path().join("safety_rules"))),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        let time = mock_time_service(Duration::from_secs(1));
+        safety_storage.set_time_service(time.clone().into());
+        safety_storage.set_circuit_breaker(Some(CircuitBreaker::new(2, Duration::from_secs(10))));
+
+        // Writes fail while the storage file is out of reach, until the circuit opens
+        fs::rename(temp_dir.path(), moved_dir.path()).unwrap();
+        for round in 1..=2 {
+            let error = safety_storage
+                .set_safety_data(
+                    SafetyData::default()
+                        .with_epoch(1)
+                        .with_last_voted_round(round),
+                )
+                .unwrap_err();
+            assert!(matches!(error, Error::SecureStorageError(_)));
+        }
+
+        // The circuit stays open until the reset timeout elapsed, even once storage recovered
+        fs::rename(moved_dir.path(), temp_dir.path()).unwrap();
+        assert_eq!(
+            safety_storage
+                .set_safety_data(SafetyData::default().with_epoch(1).with_last_voted_round(3)),
+            Err(Error::StorageCircuitOpen)
+        );
+        assert_eq!(
+            safety_storage.set_waypoint(&Waypoint::default()),
+            Err(Error::StorageCircuitOpen)
+        );
+        time.advance(Duration::from_secs(10));
+        safety_storage
+            .set_safety_data(SafetyData::default().with_epoch(1).with_last_voted_round(4))
+            .unwrap();
+        safety_storage.cached_safety_data = None;
+        assert_eq!(safety_storage.safety_data().unwrap().last_voted_round, 4);
+    }
+
+    #[test]
+    fn test_observer() {
+        #[derive(Debug, Eq, PartialEq)]
+        enum Change {
+            SafetyData(SafetyData),
+            Waypoint(Waypoint),
+            KeyRotation(Ed25519PublicKey),
+        }
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<Change>>);
+
+        impl ConsensusObserver for Recorder {
+            fn on_safety_data_updated(&self, safety_data: &SafetyData) {
+                self.0.lock().push(Change::SafetyData(safety_data.clone()));
+            }
+
+            fn on_waypoint_updated(&self, waypoint: &Waypoint) {
+                self.0.lock().push(Change::Waypoint(*waypoint));
+            }
+
+            fn on_key_rotation(&self, public_key: &Ed25519PublicKey) {
+                self.0.lock().push(Change::KeyRotation(public_key.clone()));
+            }
+        }
+
+        let temp_dir = TempPath::new();
+        temp_dir.create_as_dir().unwrap();
+        let moved_dir = TempPath::new();
+        let signer = ValidatorSigner::from_int(0);
+        let recorder = Arc::new(Recorder::default());
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(OnDiskStorage::new(temp_dir.path().join("safety_rules"))),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        )
+        .with_observer(recorder.clone());
+        safety_storage
+            .set_consensus_key_master_seed(vec![7; 32])
+            .unwrap();
+        assert!(recorder.0.lock().is_empty());
+
+        let safety_data = SafetyData::default().with_epoch(2);
+        safety_storage.set_safety_data(safety_data.clone()).unwrap();
+        safety_storage.try_advance_round(1).unwrap();
+        let waypoint = Waypoint::new_any(&LedgerInfo::new(BlockInfo::empty(), HashValue::zero()));
+        safety_storage.set_waypoint(&waypoint).unwrap();
+        // Deriving an epoch key is not a rotation
+        safety_storage.derive_epoch_consensus_key(2).unwrap();
+        let public_key = safety_storage.rotate_consensus_key().unwrap();
+        assert_eq!(
+            *recorder.0.lock(),
+            vec![
+                Change::SafetyData(safety_data.clone()),
+                Change::SafetyData(safety_data.with_last_voted_round(1)),
+                Change::Waypoint(waypoint),
+                Change::KeyRotation(public_key.clone()),
+            ]
+        );
+
+        // Failed writes are not observed
+        fs::rename(temp_dir.path(), moved_dir.path()).unwrap();
+        safety_storage
+            .set_safety_data(SafetyData::default().with_epoch(3))
+            .unwrap_err();
+        safety_storage
+            .set_waypoint(&Waypoint::default())
+            .unwrap_err();
+        assert_eq!(recorder.0.lock().len(), 4);
+
+        // The rotated key is the one used for signing
+        fs::rename(moved_dir.path(), temp_dir.path()).unwrap();
+        safety_storage.renew_signing_lease();
+        safety_storage
+            .sign(CONSENSUS_KEY.into(), public_key, &Timeout::new(2, 1))
+            .unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_key_import_counter() {
+        counters::reset_all();
+        let imports = counters::get_key_events(EXECUTION_KEY, "import");
+        PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            Author::random(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        assert!(counters::get_key_events(EXECUTION_KEY, "import") > imports);
+    }
+
+    #[test]
+    fn test_key_import_log_schema() {
+        struct Collector(Vec<String>);
+        impl Visitor for Collector {
+            fn visit_pair(&mut self, key: Key, value: Value<'_>) {
+                self.0.push(format!("{:?}={:?}", key, value));
+            }
+        }
+
+        let public_key = Ed25519PrivateKey::generate_for_testing().public_key();
+        let mut collector = Collector(Vec::new());
+        key_import_log_schema(CONSENSUS_KEY, &public_key).visit(&mut collector);
+        let output = collector.0.join(" ");
+
+        let fingerprint = key_fingerprint(&public_key);
+        assert_eq!(fingerprint.len(), 16);
+        assert!(output.contains(&fingerprint));
+        assert!(output.contains(CONSENSUS_KEY));
+        let full_key: String = public_key
+            .to_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        assert!(!output.contains(&full_key));
+        assert!(!output.contains(&format!("{:?}", public_key)));
+    }
+
+    #[test]
+    fn test_initialize_keys_and_accounts_retry() {
+        let failure_points = [
+            CONSENSUS_KEY,
+            CONSENSUS_KEY_CREATED_AT,
+            EXECUTION_KEY,
+            OWNER_ACCOUNT,
+            INITIALIZATION_COMPLETE,
+        ];
+        for fail_on in &failure_points {
+            // Fails the first write to the key
+            let mut storage = ChaosStorage::new(
+                InMemoryStorage::new(),
+                ChaosPolicy::default()
+                    .fail_keys_matching(fail_on, Some(ChaosOperation::Write))
+                    .max_failures(1),
+            );
+            let author = Author::random();
+            let consensus_private_key = Ed25519PrivateKey::generate_for_testing();
+            let execution_private_key = Ed25519PrivateKey::generate_for_testing();
+
+            PersistentSafetyStorage::initialize_keys_and_accounts(
+                &mut storage,
+                author,
+                consensus_private_key.clone(),
+                execution_private_key.clone(),
+                0,
+            )
+            .unwrap_err();
+            PersistentSafetyStorage::initialize_keys_and_accounts(
+                &mut storage,
+                author,
+                consensus_private_key.clone(),
+                execution_private_key.clone(),
+                0,
+            )
+            .unwrap();
+
+            assert_eq!(
+                storage.get_public_key(CONSENSUS_KEY).unwrap().public_key,
+                consensus_private_key.public_key()
+            );
+            assert_eq!(
+                storage.get_public_key(EXECUTION_KEY).unwrap().public_key,
+                execution_private_key.public_key()
             );
+            assert_eq!(storage.get::<Author>(OWNER_ACCOUNT).unwrap().value, author);
+            storage.get::<u64>(CONSENSUS_KEY_CREATED_AT).unwrap();
+            assert!(storage.get::<bool>(INITIALIZATION_COMPLETE).unwrap().value);
         }
     }
+
+    #[test]
+    fn test_initialize_keys_and_accounts_partial_storage() {
+        let mut storage = InMemoryStorage::new();
+        let author = Author::random();
+        let consensus_private_key = Ed25519PrivateKey::generate_for_testing();
+        let execution_private_key = Ed25519PrivateKey::generate_for_testing();
+
+        // Initialization was interrupted after importing the consensus key
+        storage
+            .import_private_key(CONSENSUS_KEY, consensus_private_key.clone())
+            .unwrap();
+
+        PersistentSafetyStorage::initialize_keys_and_accounts(
+            &mut storage,
+            author,
+            ValidatorSigner::from_int(1).private_key().clone(),
+            execution_private_key.clone(),
+            0,
+        )
+        .unwrap();
+
+        // The existing key is kept, and the missing ones are imported
+        assert_eq!(
+            storage.get_public_key(CONSENSUS_KEY).unwrap().public_key,
+            consensus_private_key.public_key()
+        );
+        assert_eq!(
+            storage.get_public_key(EXECUTION_KEY).unwrap().public_key,
+            execution_private_key.public_key()
+        );
+        assert_eq!(storage.get::<Author>(OWNER_ACCOUNT).unwrap().value, author);
+        assert!(storage.get::<bool>(INITIALIZATION_COMPLETE).unwrap().value);
+    }
+
+    #[test]
+    fn test_initialize_keys_and_accounts_existing_storage() {
+        let mut storage = InMemoryStorage::new();
+        let author = Author::random();
+        let consensus_private_key = Ed25519PrivateKey::generate_for_testing();
+
+        // Storage initialized before the completion marker existed
+        storage
+            .import_private_key(CONSENSUS_KEY, consensus_private_key.clone())
+            .unwrap();
+        storage
+            .import_private_key(EXECUTION_KEY, Ed25519PrivateKey::generate_for_testing())
+            .unwrap();
+        storage.set(OWNER_ACCOUNT, author).unwrap();
+
+        PersistentSafetyStorage::initialize_keys_and_accounts(
+            &mut storage,
+            Author::random(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Ed25519PrivateKey::generate_for_testing(),
+            0,
+        )
+        .unwrap();
+
+        // Nothing is overwritten, but the storage is now marked as initialized
+        assert_eq!(
+            storage.get_public_key(CONSENSUS_KEY).unwrap().public_key,
+            consensus_private_key.public_key()
+        );
+        assert_eq!(storage.get::<Author>(OWNER_ACCOUNT).unwrap().value, author);
+        assert!(storage.get::<bool>(INITIALIZATION_COMPLETE).unwrap().value);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut storage = counters_safety_storage();
+        storage.set_max_safety_data_backups(2);
+        storage
+            .set_safety_data(SafetyData::new(1, 4, 3, 2, None))
+            .unwrap();
+        storage
+            .set_safety_data(SafetyData::new(2, 0, 0, 0, None))
+            .unwrap();
+        storage.internal_store().rotate_key(CONSENSUS_KEY).unwrap();
+        storage.set_consensus_key_master_seed(vec![7; 32]).unwrap();
+        assert_eq!(storage.safety_data_backup_epochs().unwrap(), vec![2]);
+
+        storage.clear().unwrap();
+        assert!(storage.cached_safety_data.is_none());
+        assert!(storage.internal_store().list_keys(None).unwrap().is_empty());
+        assert!(storage.safety_data().is_err());
+        assert!(storage.waypoint().is_err());
+        assert!(storage.author().is_err());
+        assert!(storage.safety_data_backup_epochs().unwrap().is_empty());
+
+        // The storage is initialized again with new values, as if it were new
+        let author = Author::random();
+        let consensus_private_key = Ed25519PrivateKey::generate_for_testing();
+        PersistentSafetyStorage::initialize_keys_and_accounts(
+            storage.internal_store(),
+            author,
+            consensus_private_key.clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            0,
+        )
+        .unwrap();
+        let waypoint = Waypoint::new_any(&LedgerInfo::new(BlockInfo::empty(), HashValue::random()));
+        storage.set_waypoint(&waypoint).unwrap();
+        storage
+            .set_safety_data(SafetyData::new(1, 0, 0, 0, None))
+            .unwrap();
+
+        assert_eq!(storage.author().unwrap(), author);
+        assert_eq!(storage.waypoint().unwrap(), waypoint);
+        assert_eq!(storage.safety_data().unwrap().epoch, 1);
+        assert_eq!(storage.consensus_key_created_at().unwrap(), 0,);
+        storage
+            .assert_consensus_key_matches(&consensus_private_key.public_key())
+            .unwrap();
+        assert!(storage
+            .internal_store()
+            .get_public_key_previous_version(CONSENSUS_KEY)
+            .is_err());
+    }
+
+    /// Runs the storage operations of safety rules against a Vault dev server, which catches
+    /// serialization issues missed by the in-memory storage. Vault is started by the vault
+    /// client's dev utilities, and the test is skipped if it cannot be started.
+    #[cfg(feature = "vault-integration-test")]
+    #[test]
+    fn test_vault_storage() {
+        use aptos_secure_storage::VaultStorage;
+        use aptos_vault_client::dev::{self, ROOT_TOKEN};
+
+        let host = match dev::test_host_safe() {
+            Some(host) => host,
+            None => return,
+        };
+        let mut vault = VaultStorage::new(host, ROOT_TOKEN.into(), None, None, true, None, None);
+        vault.reset_and_clear().unwrap();
+
+        let signer = ValidatorSigner::from_int(0);
+        let execution_private_key = Ed25519PrivateKey::generate_for_testing();
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(vault),
+            signer.author(),
+            signer.private_key().clone(),
+            execution_private_key.clone(),
+            Waypoint::default(),
+            false,
+        );
+        assert_eq!(safety_storage.author().unwrap(), signer.author());
+        assert_eq!(
+            safety_storage.execution_public_key().unwrap(),
+            execution_private_key.public_key()
+        );
+        assert_eq!(
+            safety_storage
+                .consensus_key_for_version(signer.public_key())
+                .unwrap(),
+            *signer.private_key()
+        );
+
+        // Safety data and waypoint updates
+        let safety_data = SafetyData::new(3, 20, 18, 19, None);
+        safety_storage.set_safety_data(safety_data.clone()).unwrap();
+        assert_eq!(safety_storage.safety_data().unwrap(), safety_data);
+        let li = LedgerInfo::new(
+            BlockInfo::new(
+                3,
+                10,
+                HashValue::random(),
+                HashValue::random(),
+                500,
+                1000,
+                Some(EpochState::empty()),
+            ),
+            HashValue::zero(),
+        );
+        let waypoint = Waypoint::new_epoch_boundary(&li).unwrap();
+        safety_storage.set_waypoint(&waypoint).unwrap();
+        assert_eq!(safety_storage.waypoint().unwrap(), waypoint);
+        assert_eq!(
+            safety_storage.author_safety_data_and_waypoint().unwrap(),
+            (signer.author(), safety_data, waypoint)
+        );
+
+        // Both the rotated and the previous consensus keys remain usable
+        let public_key = safety_storage
+            .internal_store()
+            .rotate_key(CONSENSUS_KEY)
+            .unwrap();
+        assert_eq!(
+            safety_storage
+                .consensus_key_for_version(public_key.clone())
+                .unwrap()
+                .public_key(),
+            public_key
+        );
+        safety_storage
+            .consensus_key_for_version(signer.public_key())
+            .unwrap();
+        let probe = KeyProbe {
+            name: CONSENSUS_KEY.into(),
+        };
+        safety_storage.renew_signing_lease();
+        let signature = safety_storage
+            .sign(CONSENSUS_KEY.into(), public_key.clone(), &probe)
+            .unwrap();
+        signature.verify(&probe, &public_key).unwrap();
+
+        safety_storage.internal_store().reset_and_clear().unwrap();
+    }
+
+    #[test]
+    #[traced_test]
+    fn test_storage_spans() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            false,
+        );
+        safety_storage.safety_data().unwrap();
+        safety_storage.waypoint().unwrap();
+        safety_storage.renew_signing_lease();
+        let consensus_key = signer.public_key();
+        safety_storage
+            .sign(
+                CONSENSUS_KEY.into(),
+                consensus_key.clone(),
+                &Timeout::new(1, 1),
+            )
+            .unwrap();
+        tracing::info!("Storage spans exited");
+
+        logs_assert(|lines: &[&str]| {
+            let in_span = |span: &str, key: &str| {
+                lines
+                    .iter()
+                    .any(|line| line.contains(span) && line.contains(key))
+            };
+            // Storage operations are recorded within the span of the method that issued them
+            if !in_span(":safety_data: ", "key=\"safety_data\"")
+                || !in_span(":waypoint: ", "key=\"waypoint\"")
+                || !in_span(":sign{key_name=consensus}: ", "operation=\"sign\"")
+            {
+                return Err("Missing storage operation spans".into());
+            }
+            // The key version is never recorded
+            let key_version = consensus_key.to_encoded_string().unwrap();
+            if lines.iter().any(|line| line.contains(&key_version)) {
+                return Err("The key version was recorded".into());
+            }
+            // All spans are exited once the methods return
+            match lines
+                .iter()
+                .find(|line| line.contains("Storage spans exited"))
+            {
+                Some(line) if line.contains("test_storage_spans: ") => Ok(()),
+                line => Err(format!("Spans were not exited: {:?}", line)),
+            }
+        });
+    }
 }
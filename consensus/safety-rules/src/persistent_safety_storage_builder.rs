@@ -0,0 +1,483 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    circuit_breaker::CircuitBreaker, persistent_safety_storage::PersistentSafetyStorage,
+    safety_rules::DEFAULT_MAX_PROPOSAL_TIMESTAMP_SKEW_MS,
+    signing_lease::DEFAULT_SIGNING_LEASE_DURATION, Error,
+};
+use aptos_crypto::ed25519::Ed25519PrivateKey;
+use aptos_secure_storage::{KVStorage, KeyPolicy, Namespaced, Storage};
+use aptos_time_service::TimeService;
+use aptos_types::{validator_signer::Author, waypoint::Waypoint};
+use std::time::Duration;
+
+/// How often, and how long apart, to retry checking that the backing storage is available
+/// before giving up.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    pub delay_ms: u64,
+    pub tries: usize,
+}
+
+impl RetryPolicy {
+    pub fn new(delay_ms: u64, tries: usize) -> Self {
+        Self { delay_ms, tries }
+    }
+}
+
+/// Constructs a PersistentSafetyStorage. Use `build` for a new data store, which requires the
+/// author, keys and waypoint, and `open` for a data store that has already been initialized.
+pub struct PersistentSafetyStorageBuilder {
+    author: Option<Author>,
+    consensus_key: Option<Ed25519PrivateKey>,
+    execution_key: Option<Ed25519PrivateKey>,
+    waypoint: Option<Waypoint>,
+    enable_cached_safety_data: bool,
+    namespace: Option<String>,
+    retry_policy: Option<RetryPolicy>,
+    instrument_storage: bool,
+    consensus_key_max_age_secs: Option<u64>,
+    max_safety_data_backups: usize,
+    standby: bool,
+    signing_lease_store: Option<Storage>,
+    signing_lease_holder: Option<String>,
+    signing_lease_duration: Duration,
+    time_service: TimeService,
+    max_proposal_timestamp_skew_ms: u64,
+    circuit_breaker: Option<CircuitBreaker>,
+    storage_circuit_breaker: Option<(u32, Duration)>,
+    key_policy: Option<KeyPolicy>,
+}
+
+impl Default for PersistentSafetyStorageBuilder {
+    fn default() -> Self {
+        Self {
+            author: None,
+            consensus_key: None,
+            execution_key: None,
+            waypoint: None,
+            enable_cached_safety_data: true,
+            namespace: None,
+            retry_policy: None,
+            instrument_storage: false,
+            consensus_key_max_age_secs: None,
+            max_safety_data_backups: 0,
+            standby: false,
+            signing_lease_store: None,
+            signing_lease_holder: None,
+            signing_lease_duration: DEFAULT_SIGNING_LEASE_DURATION,
+            time_service: TimeService::real(),
+            max_proposal_timestamp_skew_ms: DEFAULT_MAX_PROPOSAL_TIMESTAMP_SKEW_MS,
+            circuit_breaker: None,
+            storage_circuit_breaker: None,
+            key_policy: None,
+        }
+    }
+}
+
+impl PersistentSafetyStorageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn author(mut self, author: Author) -> Self {
+        self.author = Some(author);
+        self
+    }
+
+    pub fn consensus_key(mut self, consensus_key: Ed25519PrivateKey) -> Self {
+        self.consensus_key = Some(consensus_key);
+        self
+    }
+
+    pub fn execution_key(mut self, execution_key: Ed25519PrivateKey) -> Self {
+        self.execution_key = Some(execution_key);
+        self
+    }
+
+    pub fn waypoint(mut self, waypoint: Waypoint) -> Self {
+        self.waypoint = Some(waypoint);
+        self
+    }
+
+    pub fn enable_caching(mut self, enable_cached_safety_data: bool) -> Self {
+        self.enable_cached_safety_data = enable_cached_safety_data;
+        self
+    }
+
+    /// Prefixes all keys written by safety rules with the given namespace.
+    pub fn namespace<N: Into<String>>(mut self, namespace: N) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Records the latency and failures of every storage operation, labeled by backend, see
+    /// Storage::instrumented.
+    pub fn instrument_storage(mut self, instrument_storage: bool) -> Self {
+        self.instrument_storage = instrument_storage;
+        self
+    }
+
+    pub fn consensus_key_max_age_secs(mut self, consensus_key_max_age_secs: Option<u64>) -> Self {
+        self.consensus_key_max_age_secs = consensus_key_max_age_secs;
+        self
+    }
+
+    pub fn max_safety_data_backups(mut self, max_safety_data_backups: usize) -> Self {
+        self.max_safety_data_backups = max_safety_data_backups;
+        self
+    }
+
+    /// Starts the storage in standby mode, see PersistentSafetyStorage::promote.
+    pub fn standby(mut self, standby: bool) -> Self {
+        self.standby = standby;
+        self
+    }
+
+    /// Holds the signing lease in `lease_store` on behalf of `holder`, see
+    /// PersistentSafetyStorage::set_signing_lease_config.
+    pub fn signing_lease(
+        mut self,
+        lease_store: Option<Storage>,
+        holder: Option<String>,
+        duration: Duration,
+    ) -> Self {
+        self.signing_lease_store = lease_store;
+        self.signing_lease_holder = holder;
+        self.signing_lease_duration = duration;
+        self
+    }
+
+    /// Replaces the system clock, e.g., with a mock time service.
+    pub fn time_service(mut self, time_service: TimeService) -> Self {
+        self.time_service = time_service;
+        self
+    }
+
+    pub fn max_proposal_timestamp_skew_ms(mut self, max_proposal_timestamp_skew_ms: u64) -> Self {
+        self.max_proposal_timestamp_skew_ms = max_proposal_timestamp_skew_ms;
+        self
+    }
+
+    /// Guards writes to the storage with the given circuit breaker, see CircuitBreaker.
+    pub fn circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Fails storage operations fast once the storage failed `failure_threshold` consecutive
+    /// times, until `cooldown` elapsed, see Storage::with_circuit_breaker. The circuit breaker
+    /// wraps the storage under the retry policy, so that retries fail fast while it is open.
+    pub fn storage_circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.storage_circuit_breaker = Some((failure_threshold, cooldown));
+        self
+    }
+
+    /// Rejects storage operations that the policy does not grant once the data store is
+    /// initialized, see PersistentSafetyStorage::key_policy.
+    pub fn key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = Some(key_policy);
+        self
+    }
+
+    /// Initializes a new data store, one that has no SafetyRules values set.
+    pub fn build(self, storage: Storage) -> Result<PersistentSafetyStorage, Error> {
+        let author = self
+            .author
+            .ok_or_else(|| Error::MissingStorageConfig("author".into()))?;
+        let consensus_key = self
+            .consensus_key
+            .ok_or_else(|| Error::MissingStorageConfig("consensus_key".into()))?;
+        let execution_key = self
+            .execution_key
+            .ok_or_else(|| Error::MissingStorageConfig("execution_key".into()))?;
+        let waypoint = self
+            .waypoint
+            .ok_or_else(|| Error::MissingStorageConfig("waypoint".into()))?;
+
+        let storage = Self::prepare_storage(
+            storage,
+            self.namespace,
+            self.retry_policy,
+            self.instrument_storage,
+            self.storage_circuit_breaker,
+        )?;
+        let mut persistent_storage = PersistentSafetyStorage::try_initialize(
+            storage,
+            author,
+            consensus_key,
+            execution_key,
+            waypoint,
+            self.enable_cached_safety_data,
+            self.time_service,
+        )?;
+        persistent_storage.set_consensus_key_max_age_secs(self.consensus_key_max_age_secs);
+        persistent_storage.set_max_safety_data_backups(self.max_safety_data_backups);
+        persistent_storage.set_standby(self.standby);
+        persistent_storage.set_signing_lease_config(
+            self.signing_lease_store,
+            self.signing_lease_holder,
+            self.signing_lease_duration,
+        );
+        persistent_storage.set_max_proposal_timestamp_skew_ms(self.max_proposal_timestamp_skew_ms);
+        persistent_storage.set_circuit_breaker(self.circuit_breaker);
+        if let Some(key_policy) = self.key_policy {
+            persistent_storage = persistent_storage.with_key_policy(key_policy);
+        }
+        Ok(persistent_storage)
+    }
+
+    /// Opens an existing data store. Initialization values (author, keys and waypoint) are
+    /// rejected as they would otherwise be silently ignored.
+    pub fn open(self, storage: Storage) -> Result<PersistentSafetyStorage, Error> {
+        let unexpected: Vec<&str> = [
+            ("author", self.author.is_some()),
+            ("consensus_key", self.consensus_key.is_some()),
+            ("execution_key", self.execution_key.is_some()),
+            ("waypoint", self.waypoint.is_some()),
+        ]
+        .iter()
+        .filter(|(_, set)| *set)
+        .map(|(name, _)| *name)
+        .collect();
+        if !unexpected.is_empty() {
+            return Err(Error::InvalidStorageConfig(format!(
+                "{} cannot be set when opening an existing store",
+                unexpected.join(", ")
+            )));
+        }
+
+        let storage = Self::prepare_storage(
+            storage,
+            self.namespace,
+            self.retry_policy,
+            self.instrument_storage,
+            self.storage_circuit_breaker,
+        )?;
+        let mut persistent_storage = PersistentSafetyStorage::try_new(
+            storage,
+            self.enable_cached_safety_data,
+            self.time_service,
+        )?;
+        persistent_storage.set_consensus_key_max_age_secs(self.consensus_key_max_age_secs);
+        persistent_storage.set_max_safety_data_backups(self.max_safety_data_backups);
+        persistent_storage.set_standby(self.standby);
+        persistent_storage.set_signing_lease_config(
+            self.signing_lease_store,
+            self.signing_lease_holder,
+            self.signing_lease_duration,
+        );
+        persistent_storage.set_max_proposal_timestamp_skew_ms(self.max_proposal_timestamp_skew_ms);
+        persistent_storage.set_circuit_breaker(self.circuit_breaker);
+        if let Some(key_policy) = self.key_policy {
+            persistent_storage = persistent_storage.with_key_policy(key_policy);
+        }
+        persistent_storage.migrate_safety_data();
+        Ok(persistent_storage)
+    }
+
+    fn prepare_storage(
+        storage: Storage,
+        namespace: Option<String>,
+        retry_policy: Option<RetryPolicy>,
+        instrument_storage: bool,
+        storage_circuit_breaker: Option<(u32, Duration)>,
+    ) -> Result<Storage, Error> {
+        let storage = match namespace {
+            Some(namespace) => Storage::from(Namespaced::new(namespace, Box::new(storage))),
+            None => storage,
+        };
+        let storage = if instrument_storage {
+            storage.instrumented()
+        } else {
+            storage
+        };
+        let storage = match storage_circuit_breaker {
+            Some((failure_threshold, cooldown)) => {
+                storage.with_circuit_breaker(failure_threshold, cooldown)
+            }
+            None => storage,
+        };
+
+        if let Some(retry_policy) = retry_policy {
+            // Errors that retrying cannot fix, e.g., a denied permission, end the retries at once
+            let is_permanent = |error: &aptos_secure_storage::Error| {
+                matches!(
+                    error,
+                    aptos_secure_storage::Error::AccessDenied(_)
+                        | aptos_secure_storage::Error::PermissionDenied(_)
+                        | aptos_secure_storage::Error::Unavailable {
+                            retryable: false,
+                            ..
+                        }
+                )
+            };
+            aptos_retrier::retry(
+                aptos_retrier::fixed_retry_strategy(retry_policy.delay_ms, retry_policy.tries),
+                || match storage.available() {
+                    Err(error) if is_permanent(&error) => Ok(Err(error)),
+                    result => result.map(Ok),
+                },
+            )??;
+        } else {
+            storage.available()?;
+        }
+        Ok(storage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{PrivateKey, Signature, Uniform};
+    use aptos_global_constants::{CONSENSUS_KEY, OWNER_ACCOUNT};
+    use aptos_secure_storage::{CircuitState, InMemoryStorage};
+    use aptos_types::{account_address::AccountAddress, validator_signer::ValidatorSigner};
+    use consensus_types::{safety_data::SafetyData, timeout::Timeout};
+
+    fn builder(signer: &ValidatorSigner) -> PersistentSafetyStorageBuilder {
+        PersistentSafetyStorageBuilder::new()
+            .author(signer.author())
+            .consensus_key(signer.private_key().clone())
+            .execution_key(Ed25519PrivateKey::generate_for_testing())
+            .waypoint(crate::test_utils::validator_signers_to_waypoint(&[signer]))
+    }
+
+    #[test]
+    fn test_build_missing_waypoint() {
+        let signer = ValidatorSigner::from_int(0);
+        let error = PersistentSafetyStorageBuilder::new()
+            .author(signer.author())
+            .consensus_key(signer.private_key().clone())
+            .execution_key(Ed25519PrivateKey::generate_for_testing())
+            .build(Storage::from(InMemoryStorage::new()))
+            .err()
+            .unwrap();
+        assert_eq!(error, Error::MissingStorageConfig("waypoint".into()));
+    }
+
+    #[test]
+    fn test_build_missing_author() {
+        let error = PersistentSafetyStorageBuilder::new()
+            .build(Storage::from(InMemoryStorage::new()))
+            .err()
+            .unwrap();
+        assert_eq!(error, Error::MissingStorageConfig("author".into()));
+    }
+
+    #[test]
+    fn test_open_rejects_initialization_values() {
+        let signer = ValidatorSigner::from_int(0);
+        let error = builder(&signer)
+            .open(Storage::from(InMemoryStorage::new()))
+            .err()
+            .unwrap();
+        assert!(matches!(error, Error::InvalidStorageConfig(_)));
+    }
+
+    #[test]
+    fn test_build_and_open_namespaced() {
+        let signer = ValidatorSigner::from_int(0);
+        let waypoint = crate::test_utils::validator_signers_to_waypoint(&[&signer]);
+        let storage = builder(&signer)
+            .namespace("safety_rules")
+            .retry_policy(RetryPolicy::new(0, 1))
+            .max_safety_data_backups(2)
+            .build(Storage::from(InMemoryStorage::new()))
+            .unwrap();
+        assert_eq!(storage.author().unwrap(), signer.author());
+        assert_eq!(storage.waypoint().unwrap(), waypoint);
+
+        // Keys are only visible through the namespace
+        let mut storage = storage;
+        match storage.internal_store() {
+            Storage::NamespacedStorage(namespaced) => {
+                assert_eq!(namespaced.namespace(), "safety_rules");
+                namespaced
+                    .inner()
+                    .get::<Author>(aptos_global_constants::OWNER_ACCOUNT)
+                    .unwrap_err();
+            }
+            _ => panic!("Expected namespaced storage"),
+        }
+    }
+
+    #[test]
+    fn test_build_instrumented() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut storage = builder(&signer)
+            .instrument_storage(true)
+            .build(Storage::from(InMemoryStorage::new()))
+            .unwrap();
+        assert_eq!(storage.author().unwrap(), signer.author());
+        assert!(matches!(
+            storage.internal_store(),
+            Storage::InstrumentedStorage(_)
+        ));
+        assert_eq!(storage.internal_store().backend(), "in_memory");
+    }
+
+    #[test]
+    fn test_build_storage_circuit_breaker() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut storage = builder(&signer)
+            .instrument_storage(true)
+            .storage_circuit_breaker(3, Duration::from_secs(1))
+            .build(Storage::from(InMemoryStorage::new()))
+            .unwrap();
+        assert_eq!(storage.author().unwrap(), signer.author());
+        match storage.internal_store() {
+            Storage::CircuitBreakerStorage(circuit_breaker) => {
+                assert_eq!(circuit_breaker.state(), CircuitState::Closed);
+                assert!(matches!(
+                    **circuit_breaker.inner(),
+                    Storage::InstrumentedStorage(_)
+                ));
+            }
+            _ => panic!("Expected circuit breaker storage"),
+        }
+        assert_eq!(storage.internal_store().backend(), "in_memory");
+    }
+
+    #[test]
+    fn test_build_key_policy() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut storage = builder(&signer)
+            .key_policy(PersistentSafetyStorage::key_policy())
+            .build(Storage::from(InMemoryStorage::new()))
+            .unwrap();
+        assert!(matches!(
+            storage.internal_store(),
+            Storage::PolicyStorage(_)
+        ));
+
+        // Normal operation is unaffected
+        storage
+            .set_safety_data(SafetyData::new(1, 2, 1, 0, None))
+            .unwrap();
+        assert_eq!(storage.safety_data().unwrap().last_voted_round, 2);
+        let consensus_key = signer.private_key().public_key();
+        let message = Timeout::new(1, 2);
+        storage.renew_signing_lease();
+        let signature = storage
+            .sign(CONSENSUS_KEY.into(), consensus_key.clone(), &message)
+            .unwrap();
+        signature.verify(&message, &consensus_key).unwrap();
+
+        // The owner account cannot be overwritten once initialized
+        assert_eq!(
+            storage
+                .internal_store()
+                .set(OWNER_ACCOUNT, AccountAddress::random())
+                .unwrap_err(),
+            aptos_secure_storage::Error::PolicyDenied(format!("Write access to {}", OWNER_ACCOUNT))
+        );
+        assert_eq!(storage.author().unwrap(), signer.author());
+    }
+}
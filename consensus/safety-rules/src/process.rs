@@ -4,18 +4,17 @@
 use crate::{
     persistent_safety_storage::PersistentSafetyStorage,
     remote_service::{self, RemoteService},
-    safety_rules_manager,
+    safety_rules_manager, Error,
 };
 use aptos_config::config::{SafetyRulesConfig, SafetyRulesService};
-
-use std::net::SocketAddr;
+use aptos_secure_net::{ClientTlsConfig, ServerAddress, TlsConfig};
 
 pub struct Process {
     data: Option<ProcessData>,
 }
 
 impl Process {
-    pub fn new(config: SafetyRulesConfig) -> Self {
+    pub fn new(config: SafetyRulesConfig) -> Result<Self, Error> {
         let storage = safety_rules_manager::storage(&config);
 
         let verify_vote_proposal_signature = config.verify_vote_proposal_signature;
@@ -24,60 +23,89 @@ impl Process {
             SafetyRulesService::Process(service) => service,
             _ => panic!("Unexpected SafetyRules service: {:?}", config.service),
         };
-        let server_addr = service.server_address();
+        let server_addr = remote_service::server_address(service);
+        let tls_config = remote_service::tls_config(&config, service)?;
 
-        Self {
+        Ok(Self {
             data: Some(ProcessData {
                 server_addr,
+                tls_config,
                 storage,
                 verify_vote_proposal_signature,
                 export_consensus_key,
                 network_timeout: config.network_timeout_ms,
+                max_frame_size: config.max_frame_size,
             }),
-        }
+        })
     }
 
-    pub fn start(&mut self) {
+    /// Serves requests until the process exits, unless the service cannot be started.
+    pub fn start(&mut self) -> Result<(), Error> {
         let data = self.data.take().expect("Unable to retrieve ProcessData");
         remote_service::execute(
             data.storage,
             data.server_addr,
+            data.tls_config,
             data.verify_vote_proposal_signature,
             data.export_consensus_key,
             data.network_timeout,
-        );
+            data.max_frame_size,
+        )
     }
 }
 
 struct ProcessData {
-    server_addr: SocketAddr,
+    server_addr: ServerAddress,
+    tls_config: Option<TlsConfig>,
     storage: PersistentSafetyStorage,
     verify_vote_proposal_signature: bool,
     export_consensus_key: bool,
     // Timeout in Seconds for network operations
     network_timeout: u64,
+    max_frame_size: usize,
 }
 
 pub struct ProcessService {
-    server_addr: SocketAddr,
+    server_addr: ServerAddress,
+    tls_config: Option<ClientTlsConfig>,
     network_timeout_ms: u64,
+    max_frame_size: usize,
 }
 
 impl ProcessService {
-    pub fn new(server_addr: SocketAddr, network_timeout: u64) -> Self {
-        Self {
+    pub fn new(
+        server_addr: ServerAddress,
+        tls_config: Option<TlsConfig>,
+        network_timeout: u64,
+        max_frame_size: usize,
+    ) -> Result<Self, Error> {
+        let tls_config = match tls_config {
+            Some(tls_config) => Some(tls_config.client_config()?),
+            None => None,
+        };
+        Ok(Self {
             server_addr,
+            tls_config,
             network_timeout_ms: network_timeout,
-        }
+            max_frame_size,
+        })
     }
 }
 
 impl RemoteService for ProcessService {
-    fn server_address(&self) -> SocketAddr {
-        self.server_addr
+    fn server_address(&self) -> ServerAddress {
+        self.server_addr.clone()
+    }
+
+    fn tls_config(&self) -> Option<&ClientTlsConfig> {
+        self.tls_config.as_ref()
     }
 
     fn network_timeout_ms(&self) -> u64 {
         self.network_timeout_ms
     }
+
+    fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
 }
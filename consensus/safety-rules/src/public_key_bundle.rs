@@ -0,0 +1,104 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Error;
+use aptos_crypto::{
+    ed25519::{Ed25519PublicKey, Ed25519Signature},
+    Signature,
+};
+use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use consensus_types::common::Author;
+use serde::{Deserialize, Serialize};
+
+/// The public keys of a validator, exported for key transparency auditing. The bundle is signed
+/// with its own execution key, so that auditors can check that the keys were exported by their
+/// holder and not altered since.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PublicKeyBundle {
+    pub consensus_pub_key: Ed25519PublicKey,
+    /// The consensus keys derived for recent epochs, by ascending epoch, if per-epoch consensus
+    /// keys are enabled.
+    pub epoch_consensus_pub_keys: Vec<(u64, Ed25519PublicKey)>,
+    pub execution_pub_key: Ed25519PublicKey,
+    pub author: Author,
+    /// When the bundle was exported, in Unix ms.
+    pub created_at: u64,
+    pub signature: Ed25519Signature,
+}
+
+/// The content of a PublicKeyBundle that is signed, i.e., everything but the signature. As the
+/// type has its own CryptoHasher, its signature can never be mistaken for that of a consensus
+/// message.
+#[derive(Deserialize, Serialize, CryptoHasher, BCSCryptoHash)]
+pub struct PublicKeyBundleSigningRepr {
+    pub consensus_pub_key: Ed25519PublicKey,
+    pub epoch_consensus_pub_keys: Vec<(u64, Ed25519PublicKey)>,
+    pub execution_pub_key: Ed25519PublicKey,
+    pub author: Author,
+    pub created_at: u64,
+}
+
+impl PublicKeyBundle {
+    pub fn signing_format(&self) -> PublicKeyBundleSigningRepr {
+        PublicKeyBundleSigningRepr {
+            consensus_pub_key: self.consensus_pub_key.clone(),
+            epoch_consensus_pub_keys: self.epoch_consensus_pub_keys.clone(),
+            execution_pub_key: self.execution_pub_key.clone(),
+            author: self.author,
+            created_at: self.created_at,
+        }
+    }
+
+    /// Verifies that the bundle was signed by the holder of its execution key.
+    pub fn verify(&self) -> Result<(), Error> {
+        self.signature
+            .verify(&self.signing_format(), &self.execution_pub_key)
+            .map_err(|error| Error::InvalidPublicKeyBundle(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, SigningKey, Uniform};
+
+    #[test]
+    fn test_verify() {
+        let execution_key = Ed25519PrivateKey::generate_for_testing();
+        let mut bundle = PublicKeyBundle {
+            consensus_pub_key: Ed25519PrivateKey::generate_for_testing().public_key(),
+            epoch_consensus_pub_keys: vec![],
+            execution_pub_key: execution_key.public_key(),
+            author: Author::random(),
+            created_at: 42,
+            signature: Ed25519Signature::dummy_signature(),
+        };
+        bundle.signature = execution_key.sign(&bundle.signing_format());
+        bundle.verify().unwrap();
+
+        // The bundle no longer verifies once altered
+        let mut altered = bundle.clone();
+        altered.consensus_pub_key = Ed25519PrivateKey::generate_for_testing().public_key();
+        assert!(matches!(
+            altered.verify(),
+            Err(Error::InvalidPublicKeyBundle(_))
+        ));
+
+        let mut altered = bundle.clone();
+        altered.epoch_consensus_pub_keys =
+            vec![(1, Ed25519PrivateKey::generate_for_testing().public_key())];
+        assert!(matches!(
+            altered.verify(),
+            Err(Error::InvalidPublicKeyBundle(_))
+        ));
+
+        // Nor if it is signed by a key other than its execution key
+        let mut resigned = bundle;
+        resigned.signature =
+            Ed25519PrivateKey::generate_for_testing().sign(&resigned.signing_format());
+        assert!(matches!(
+            resigned.verify(),
+            Err(Error::InvalidPublicKeyBundle(_))
+        ));
+    }
+}
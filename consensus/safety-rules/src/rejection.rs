@@ -0,0 +1,95 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Error;
+use serde::{Deserialize, Serialize};
+
+/// The safety rule a rejected request violated, as reported by the rejection counters.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum RejectionReason {
+    /// The round does not advance the last voted round.
+    OldRound,
+    /// The round, or the parent of the proposal, is below the preferred round.
+    PreferredRound,
+    /// The request is for another epoch.
+    EpochMismatch,
+    /// The epoch change proof does not extend the waypoint.
+    WaypointMismatch,
+    /// The quorum certificate does not verify.
+    InvalidQuorumCert,
+    /// The proposal timestamp is too far in the future.
+    Timestamp,
+}
+
+impl RejectionReason {
+    /// Returns the reason for the error, if it is a violation of a safety rule rather than, e.g.,
+    /// a storage failure.
+    pub fn from_error(error: &Error) -> Option<Self> {
+        match error {
+            Error::IncorrectLastVotedRound(..) | Error::InvalidRound(..) => Some(Self::OldRound),
+            Error::IncorrectPreferredRound(..) => Some(Self::PreferredRound),
+            Error::IncorrectEpoch(..) => Some(Self::EpochMismatch),
+            Error::WaypointOutOfDate(..) | Error::InvalidEpochChangeProof(_) => {
+                Some(Self::WaypointMismatch)
+            }
+            Error::InvalidQuorumCertificate(_) => Some(Self::InvalidQuorumCert),
+            Error::ProposalTimestampTooFarInFuture(..) => Some(Self::Timestamp),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectionReason::OldRound => "old_round",
+            RejectionReason::PreferredRound => "preferred_round",
+            RejectionReason::EpochMismatch => "epoch_mismatch",
+            RejectionReason::WaypointMismatch => "waypoint_mismatch",
+            RejectionReason::InvalidQuorumCert => "invalid_qc",
+            RejectionReason::Timestamp => "timestamp",
+        }
+    }
+}
+
+/// The most recent request rejected by a safety rule, for debugging a validator that stopped
+/// voting.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RejectionInfo {
+    reason: RejectionReason,
+    request: String,
+    error: String,
+    timestamp_usecs: u64,
+}
+
+impl RejectionInfo {
+    pub fn new(
+        reason: RejectionReason,
+        request: String,
+        error: String,
+        timestamp_usecs: u64,
+    ) -> Self {
+        Self {
+            reason,
+            request,
+            error,
+            timestamp_usecs,
+        }
+    }
+
+    pub fn reason(&self) -> RejectionReason {
+        self.reason
+    }
+
+    /// The name of the rejected request, e.g., "sign_proposal".
+    pub fn request(&self) -> &str {
+        &self.request
+    }
+
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+
+    /// When the request was rejected, according to the safety rules time service.
+    pub fn timestamp_usecs(&self) -> u64 {
+        self.timestamp_usecs
+    }
+}
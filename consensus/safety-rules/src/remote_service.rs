@@ -6,34 +6,95 @@ use crate::{
     serializer::{SafetyRulesInput, SerializerClient, SerializerService, TSerializerClient},
     Error, SafetyRules, TSafetyRules,
 };
+use aptos_config::config::{RemoteService as RemoteServiceConfig, SafetyRulesConfig, TlsMaterial};
 use aptos_logger::warn;
-use aptos_secure_net::{NetworkClient, NetworkServer};
-use std::net::SocketAddr;
+use aptos_secure_net::{
+    ClientTlsConfig, NetworkClient, NetworkServer, ServerAddress, TlsConfig, DEFAULT_MAX_FRAME_SIZE,
+};
+use aptos_secure_storage::{KVStorage, Storage};
 
 pub trait RemoteService {
     fn client(&self) -> SerializerClient {
-        let network_client = NetworkClient::new(
+        let mut network_client = NetworkClient::new(
             "safety-rules",
             self.server_address(),
             self.network_timeout_ms(),
-        );
+        )
+        .with_max_frame_size(self.max_frame_size());
+        if let Some(tls_config) = self.tls_config() {
+            network_client = network_client.with_client_tls_config(tls_config.clone());
+        }
         let service = Box::new(RemoteClient::new(network_client));
         SerializerClient::new_client(service)
     }
 
-    fn server_address(&self) -> SocketAddr;
+    fn server_address(&self) -> ServerAddress;
+
+    /// Connect over mutually authenticated TLS, if set.
+    fn tls_config(&self) -> Option<&ClientTlsConfig> {
+        None
+    }
 
     /// Network Timeout in milliseconds.
     fn network_timeout_ms(&self) -> u64;
+
+    /// The largest message, in bytes, sent to or accepted from the service.
+    fn max_frame_size(&self) -> usize {
+        DEFAULT_MAX_FRAME_SIZE
+    }
+}
+
+/// The address of the service described by the config, a Unix domain socket if one is set.
+pub fn server_address(config: &RemoteServiceConfig) -> ServerAddress {
+    match &config.socket_path {
+        Some(socket_path) => ServerAddress::Unix(socket_path.clone()),
+        None => ServerAddress::Tcp(config.server_address()),
+    }
+}
+
+/// Loads the TLS certificates and key of the service described by the config, if it has any,
+/// from disk or from the safety rules secure backend.
+pub fn tls_config(
+    config: &SafetyRulesConfig,
+    service: &RemoteServiceConfig,
+) -> Result<Option<TlsConfig>, Error> {
+    let tls = match &service.tls {
+        Some(tls) => tls,
+        None => return Ok(None),
+    };
+    if service.socket_path.is_some() {
+        return Err(Error::InvalidTlsConfig(
+            "TLS is only supported over TCP, not with a socket path".into(),
+        ));
+    }
+
+    let storage = Storage::from(&config.backend);
+    let load = |material: &TlsMaterial| -> Result<Vec<u8>, Error> {
+        match material {
+            TlsMaterial::FromDisk(path) => std::fs::read(path).map_err(|e| {
+                Error::InvalidTlsConfig(format!("Unable to read {}: {}", path.display(), e))
+            }),
+            TlsMaterial::FromStorage(name) => Ok(storage.get::<String>(name)?.value.into_bytes()),
+        }
+    };
+
+    Ok(Some(TlsConfig {
+        certificate_chain: load(&tls.certificate)?,
+        private_key: load(&tls.private_key)?,
+        ca_certificates: load(&tls.ca_certificate)?,
+        server_name: tls.server_name.clone(),
+    }))
 }
 
 pub fn execute(
     storage: PersistentSafetyStorage,
-    listen_addr: SocketAddr,
+    listen_addr: ServerAddress,
+    tls_config: Option<TlsConfig>,
     verify_vote_proposal_signature: bool,
     export_consensus_key: bool,
     network_timeout_ms: u64,
-) {
+    max_frame_size: usize,
+) -> Result<(), Error> {
     let mut safety_rules = SafetyRules::new(
         storage,
         verify_vote_proposal_signature,
@@ -43,8 +104,13 @@ pub fn execute(
         warn!("Unable to print consensus state: {}", e);
     }
 
-    let mut serializer_service = SerializerService::new(safety_rules);
-    let mut network_server = NetworkServer::new("safety-rules", listen_addr, network_timeout_ms);
+    let mut serializer_service =
+        SerializerService::new(safety_rules).with_max_frame_size(max_frame_size);
+    let mut network_server = NetworkServer::new("safety-rules", listen_addr, network_timeout_ms)
+        .with_max_frame_size(max_frame_size);
+    if let Some(tls_config) = tls_config {
+        network_server = network_server.with_tls(&tls_config)?;
+    }
 
     loop {
         if let Err(e) = process_one_message(&mut network_server, &mut serializer_service) {
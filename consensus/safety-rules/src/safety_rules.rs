@@ -2,12 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    attestation::{SafetyStateAttestation, SignedSafetyStateAttestation},
     configurable_validator_signer::ConfigurableValidatorSigner,
     consensus_state::ConsensusState,
     counters,
     error::Error,
     logging::{LogEntry, LogEvent, SafetyLogSchema},
     persistent_safety_storage::PersistentSafetyStorage,
+    rejection::{RejectionInfo, RejectionReason},
+    signing_lease::SigningLease,
     t_safety_rules::TSafetyRules,
 };
 use aptos_crypto::{
@@ -15,7 +18,9 @@ use aptos_crypto::{
     hash::{CryptoHash, HashValue},
     traits::Signature,
 };
+use aptos_global_constants::CONSENSUS_KEY;
 use aptos_logger::prelude::*;
+use aptos_time_service::TimeServiceTrait;
 use aptos_types::{
     block_info::BlockInfo,
     epoch_change::EpochChangeProof,
@@ -38,6 +43,11 @@ use consensus_types::{
 use serde::Serialize;
 use std::cmp::Ordering;
 
+/// How far ahead of the local clock a proposal timestamp may be, unless configured otherwise.
+/// Proposals beyond that are neither signed nor voted for, so that a leader with a fast (or
+/// malicious) clock cannot push the block time into the future.
+pub(crate) const DEFAULT_MAX_PROPOSAL_TIMESTAMP_SKEW_MS: u64 = 500;
+
 pub(crate) fn next_round(round: Round) -> Result<Round, Error> {
     u64::checked_add(round, 1).ok_or(Error::IncorrectRound(round))
 }
@@ -49,13 +59,15 @@ pub struct SafetyRules {
     pub(crate) export_consensus_key: bool,
     pub(crate) validator_signer: Option<ConfigurableValidatorSigner>,
     pub(crate) epoch_state: Option<EpochState>,
+    pub(crate) last_rejection: Option<RejectionInfo>,
 }
 
 impl SafetyRules {
     /// Constructs a new instance of SafetyRules with the given persistent storage and the
-    /// consensus private keys
+    /// consensus private keys. Unless the storage is in standby mode, this acquires the signing
+    /// lease.
     pub fn new(
-        persistent_storage: PersistentSafetyStorage,
+        mut persistent_storage: PersistentSafetyStorage,
         verify_vote_proposal_signature: bool,
         export_consensus_key: bool,
     ) -> Self {
@@ -68,12 +80,14 @@ impl SafetyRules {
         } else {
             None
         };
+        persistent_storage.renew_signing_lease();
         Self {
             persistent_storage,
             execution_public_key,
             export_consensus_key,
             validator_signer: None,
             epoch_state: None,
+            last_rejection: None,
         }
     }
 
@@ -104,6 +118,7 @@ impl SafetyRules {
         proposed_block
             .verify_well_formed()
             .map_err(|error| Error::InvalidProposal(error.to_string()))?;
+        self.verify_timestamp(proposed_block.block_data())?;
 
         vote_proposal
             .gen_vote_data()
@@ -111,13 +126,44 @@ impl SafetyRules {
     }
 
     pub(crate) fn sign<T: Serialize + CryptoHash>(
-        &self,
+        &mut self,
         message: &T,
     ) -> Result<Ed25519Signature, Error> {
+        // An exported consensus key does not go through storage, so check for standby and the
+        // signing lease here too
+        self.persistent_storage.renew_signing_lease();
+        self.persistent_storage.check_can_sign()?;
         let signer = self.signer()?;
         signer.sign(message, &self.persistent_storage)
     }
 
+    /// Rejects timestamps more than the configured skew ahead of the local clock. The genesis
+    /// block carries the timestamp of the ledger info it was derived from and is exempt.
+    pub(crate) fn verify_timestamp(&self, block_data: &BlockData) -> Result<(), Error> {
+        if block_data.is_genesis_block() {
+            return Ok(());
+        }
+
+        let timestamp_usecs = block_data.timestamp_usecs();
+        let now_usecs = self
+            .persistent_storage
+            .time_service()
+            .now_unix_time()
+            .as_micros() as u64;
+        let max_skew_usecs = self
+            .persistent_storage
+            .max_proposal_timestamp_skew_ms()
+            .saturating_mul(1000);
+        if timestamp_usecs > now_usecs.saturating_add(max_skew_usecs) {
+            counters::increment_proposal_timestamp_rejections();
+            return Err(Error::ProposalTimestampTooFarInFuture(
+                timestamp_usecs,
+                now_usecs,
+            ));
+        }
+        Ok(())
+    }
+
     pub(crate) fn signer(&self) -> Result<&ConfigurableValidatorSigner, Error> {
         self.validator_signer
             .as_ref()
@@ -130,6 +176,62 @@ impl SafetyRules {
             .ok_or_else(|| Error::NotInitialized("epoch_state".into()))
     }
 
+    /// Leaves standby mode once the signing lease is acquired, see
+    /// PersistentSafetyStorage::promote.
+    pub fn promote(&mut self) -> Result<SigningLease, Error> {
+        self.persistent_storage.promote()
+    }
+
+    /// Enters standby mode and releases the signing lease, see PersistentSafetyStorage::demote.
+    pub fn demote(&mut self) -> Result<(), Error> {
+        self.persistent_storage.demote()
+    }
+
+    /// Returns the most recent request rejected by a safety rule, if any.
+    pub fn last_rejection(&self) -> Option<RejectionInfo> {
+        self.last_rejection.clone()
+    }
+
+    /// Logs and counts the request. Requests rejected by a safety rule are also counted by reason
+    /// and recorded as the last rejection.
+    fn run_and_log<F, L, R>(
+        &mut self,
+        callback: F,
+        log_cb: L,
+        log_entry: LogEntry,
+    ) -> Result<R, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Error>,
+        L: for<'a> Fn(SafetyLogSchema<'a>) -> SafetyLogSchema<'a>,
+    {
+        let _timer = counters::start_timer("internal", log_entry.as_str());
+        debug!(log_cb(SafetyLogSchema::new(log_entry, LogEvent::Request)));
+        counters::increment_query(log_entry.as_str(), "request");
+        callback(self)
+            .map(|v| {
+                info!(log_cb(SafetyLogSchema::new(log_entry, LogEvent::Success)));
+                counters::increment_query(log_entry.as_str(), "success");
+                v
+            })
+            .map_err(|err| {
+                error!(log_cb(SafetyLogSchema::new(log_entry, LogEvent::Error)).error(&err));
+                counters::increment_query(log_entry.as_str(), "error");
+                if let Some(reason) = RejectionReason::from_error(&err) {
+                    counters::increment_rejection(reason.as_str());
+                    self.last_rejection = Some(RejectionInfo::new(
+                        reason,
+                        log_entry.as_str().into(),
+                        err.to_string(),
+                        self.persistent_storage
+                            .time_service()
+                            .now_unix_time()
+                            .as_micros() as u64,
+                    ));
+                }
+                err
+            })
+    }
+
     pub(crate) fn observe_qc(&self, qc: &QuorumCert, safety_data: &mut SafetyData) -> bool {
         let mut updated = false;
         let one_chain = qc.certified_block().round();
@@ -255,23 +357,46 @@ impl SafetyRules {
     // Internal functions mapped to the public interface to enable exhaustive logging and metrics
 
     fn guarded_consensus_state(&mut self) -> Result<ConsensusState, Error> {
-        let waypoint = self.persistent_storage.waypoint()?;
-        let safety_data = self.persistent_storage.safety_data()?;
+        let (author, safety_data, waypoint) =
+            self.persistent_storage.author_safety_data_and_waypoint()?;
 
         info!(SafetyLogSchema::new(LogEntry::State, LogEvent::Update)
-            .author(self.persistent_storage.author()?)
+            .author(author)
             .epoch(safety_data.epoch)
             .last_voted_round(safety_data.last_voted_round)
             .preferred_round(safety_data.preferred_round)
-            .waypoint(waypoint));
+            .waypoint(waypoint.to_string()));
 
         Ok(ConsensusState::new(
-            self.persistent_storage.safety_data()?,
-            self.persistent_storage.waypoint()?,
+            safety_data,
+            waypoint,
             self.signer().is_ok(),
+            self.last_rejection.clone(),
         ))
     }
 
+    /// Signs with the storage directly, rather than through the validator signer, so that the
+    /// consensus key is proven to be held by storage even if it has been exported.
+    fn guarded_attest_safety_state(
+        &mut self,
+        nonce: HashValue,
+    ) -> Result<SignedSafetyStateAttestation, Error> {
+        let key_version = self.signer()?.public_key();
+        let safety_data = self.persistent_storage.safety_data()?;
+        let attestation = SafetyStateAttestation::new(
+            self.persistent_storage.author()?,
+            safety_data.epoch,
+            safety_data.last_voted_round,
+            self.persistent_storage.waypoint()?.version(),
+            nonce,
+        );
+        self.persistent_storage.renew_signing_lease();
+        let signature =
+            self.persistent_storage
+                .sign(CONSENSUS_KEY.into(), key_version, &attestation)?;
+        Ok(SignedSafetyStateAttestation::new(attestation, signature))
+    }
+
     fn guarded_initialize(&mut self, proof: &EpochChangeProof) -> Result<(), Error> {
         let waypoint = self.persistent_storage.waypoint()?;
         let last_li = proof
@@ -290,7 +415,7 @@ impl SafetyRules {
             self.persistent_storage.set_waypoint(new_waypoint)?;
         }
 
-        let current_epoch = self.persistent_storage.safety_data()?.epoch;
+        let current_epoch = self.persistent_storage.current_epoch()?;
         match current_epoch.cmp(&epoch_state.epoch) {
             Ordering::Greater => {
                 // waypoint is not up to the current epoch.
@@ -318,6 +443,12 @@ impl SafetyRules {
         };
         self.epoch_state = Some(epoch_state.clone());
 
+        // With per-epoch consensus keys, the expected key is the one derived for this epoch
+        if self.persistent_storage.has_consensus_key_master_seed()? {
+            self.persistent_storage
+                .derive_epoch_consensus_key(epoch_state.epoch)?;
+        }
+
         let author = self.persistent_storage.author()?;
         let expected_key = epoch_state.verifier.get_public_key(&author);
         let initialize_result = match expected_key {
@@ -330,6 +461,14 @@ impl SafetyRules {
                         "in set",
                     );
                     Ok(())
+                } else if self.persistent_storage.is_standby() {
+                    // Standby cannot sign or export the key, so trust that storage holds it.
+                    // Its availability is verified when signing after promotion.
+                    self.validator_signer = Some(ConfigurableValidatorSigner::new_handle(
+                        author,
+                        expected_key,
+                    ));
+                    Ok(())
                 } else if self.export_consensus_key {
                     // Try to export the consensus key directly from storage.
                     match self
@@ -414,6 +553,7 @@ impl SafetyRules {
 
         let mut safety_data = self.persistent_storage.safety_data()?;
         self.verify_epoch(block_data.epoch(), &safety_data)?;
+        self.verify_timestamp(block_data)?;
 
         if block_data.round() <= safety_data.last_voted_round {
             return Err(Error::InvalidProposal(format!(
@@ -434,7 +574,7 @@ impl SafetyRules {
     fn guarded_sign_timeout(&mut self, timeout: &Timeout) -> Result<Ed25519Signature, Error> {
         self.signer()?;
 
-        let mut safety_data = self.persistent_storage.safety_data()?;
+        let safety_data = self.persistent_storage.safety_data()?;
         self.verify_epoch(timeout.epoch(), &safety_data)?;
 
         if timeout.round() <= safety_data.preferred_round {
@@ -450,8 +590,7 @@ impl SafetyRules {
             ));
         }
         if timeout.round() > safety_data.last_voted_round {
-            self.verify_and_update_last_vote_round(timeout.round(), &mut safety_data)?;
-            self.persistent_storage.set_safety_data(safety_data)?;
+            self.persistent_storage.try_advance_round(timeout.round())?;
         }
 
         let signature = self.sign(timeout)?;
@@ -497,13 +636,13 @@ impl SafetyRules {
 
 impl TSafetyRules for SafetyRules {
     fn consensus_state(&mut self) -> Result<ConsensusState, Error> {
-        let cb = || self.guarded_consensus_state();
-        run_and_log(cb, |log| log, LogEntry::ConsensusState)
+        let cb = |safety_rules: &mut Self| safety_rules.guarded_consensus_state();
+        self.run_and_log(cb, |log| log, LogEntry::ConsensusState)
     }
 
     fn initialize(&mut self, proof: &EpochChangeProof) -> Result<(), Error> {
-        let cb = || self.guarded_initialize(proof);
-        run_and_log(cb, |log| log, LogEntry::Initialize)
+        let cb = |safety_rules: &mut Self| safety_rules.guarded_initialize(proof);
+        self.run_and_log(cb, |log| log, LogEntry::Initialize)
     }
 
     fn construct_and_sign_vote(
@@ -511,19 +650,21 @@ impl TSafetyRules for SafetyRules {
         maybe_signed_vote_proposal: &MaybeSignedVoteProposal,
     ) -> Result<Vote, Error> {
         let round = maybe_signed_vote_proposal.vote_proposal.block().round();
-        let cb = || self.guarded_construct_and_sign_vote(maybe_signed_vote_proposal);
-        run_and_log(cb, |log| log.round(round), LogEntry::ConstructAndSignVote)
+        let cb = |safety_rules: &mut Self| {
+            safety_rules.guarded_construct_and_sign_vote(maybe_signed_vote_proposal)
+        };
+        self.run_and_log(cb, |log| log.round(round), LogEntry::ConstructAndSignVote)
     }
 
     fn sign_proposal(&mut self, block_data: &BlockData) -> Result<Ed25519Signature, Error> {
         let round = block_data.round();
-        let cb = || self.guarded_sign_proposal(block_data);
-        run_and_log(cb, |log| log.round(round), LogEntry::SignProposal)
+        let cb = |safety_rules: &mut Self| safety_rules.guarded_sign_proposal(block_data);
+        self.run_and_log(cb, |log| log.round(round), LogEntry::SignProposal)
     }
 
     fn sign_timeout(&mut self, timeout: &Timeout) -> Result<Ed25519Signature, Error> {
-        let cb = || self.guarded_sign_timeout(timeout);
-        run_and_log(cb, |log| log.round(timeout.round()), LogEntry::SignTimeout)
+        let cb = |safety_rules: &mut Self| safety_rules.guarded_sign_timeout(timeout);
+        self.run_and_log(cb, |log| log.round(timeout.round()), LogEntry::SignTimeout)
     }
 
     fn sign_timeout_with_qc(
@@ -531,8 +672,10 @@ impl TSafetyRules for SafetyRules {
         timeout: &TwoChainTimeout,
         timeout_cert: Option<&TwoChainTimeoutCertificate>,
     ) -> Result<Ed25519Signature, Error> {
-        let cb = || self.guarded_sign_timeout_with_qc(timeout, timeout_cert);
-        run_and_log(
+        let cb = |safety_rules: &mut Self| {
+            safety_rules.guarded_sign_timeout_with_qc(timeout, timeout_cert)
+        };
+        self.run_and_log(
             cb,
             |log| log.round(timeout.round()),
             LogEntry::SignTimeoutWithQC,
@@ -545,10 +688,11 @@ impl TSafetyRules for SafetyRules {
         timeout_cert: Option<&TwoChainTimeoutCertificate>,
     ) -> Result<Vote, Error> {
         let round = maybe_signed_vote_proposal.vote_proposal.block().round();
-        let cb = || {
-            self.guarded_construct_and_sign_vote_two_chain(maybe_signed_vote_proposal, timeout_cert)
+        let cb = |safety_rules: &mut Self| {
+            safety_rules
+                .guarded_construct_and_sign_vote_two_chain(maybe_signed_vote_proposal, timeout_cert)
         };
-        run_and_log(
+        self.run_and_log(
             cb,
             |log| log.round(round),
             LogEntry::ConstructAndSignVoteTwoChain,
@@ -560,28 +704,17 @@ impl TSafetyRules for SafetyRules {
         ledger_info: LedgerInfoWithSignatures,
         new_ledger_info: LedgerInfo,
     ) -> Result<Ed25519Signature, Error> {
-        let cb = || self.guarded_sign_commit_vote(ledger_info, new_ledger_info);
-        run_and_log(cb, |log| log, LogEntry::SignCommitVote)
+        let cb = |safety_rules: &mut Self| {
+            safety_rules.guarded_sign_commit_vote(ledger_info, new_ledger_info)
+        };
+        self.run_and_log(cb, |log| log, LogEntry::SignCommitVote)
     }
-}
 
-fn run_and_log<F, L, R>(callback: F, log_cb: L, log_entry: LogEntry) -> Result<R, Error>
-where
-    F: FnOnce() -> Result<R, Error>,
-    L: for<'a> Fn(SafetyLogSchema<'a>) -> SafetyLogSchema<'a>,
-{
-    let _timer = counters::start_timer("internal", log_entry.as_str());
-    debug!(log_cb(SafetyLogSchema::new(log_entry, LogEvent::Request)));
-    counters::increment_query(log_entry.as_str(), "request");
-    callback()
-        .map(|v| {
-            info!(log_cb(SafetyLogSchema::new(log_entry, LogEvent::Success)));
-            counters::increment_query(log_entry.as_str(), "success");
-            v
-        })
-        .map_err(|err| {
-            error!(log_cb(SafetyLogSchema::new(log_entry, LogEvent::Error)).error(&err));
-            counters::increment_query(log_entry.as_str(), "error");
-            err
-        })
+    fn attest_safety_state(
+        &mut self,
+        nonce: HashValue,
+    ) -> Result<SignedSafetyStateAttestation, Error> {
+        let cb = |safety_rules: &mut Self| safety_rules.guarded_attest_safety_state(nonce);
+        self.run_and_log(cb, |log| log, LogEntry::AttestSafetyState)
+    }
 }
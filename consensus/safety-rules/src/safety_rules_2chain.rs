@@ -20,7 +20,7 @@ impl SafetyRules {
         timeout_cert: Option<&TwoChainTimeoutCertificate>,
     ) -> Result<Ed25519Signature, Error> {
         self.signer()?;
-        let mut safety_data = self.persistent_storage.safety_data()?;
+        let safety_data = self.persistent_storage.safety_data()?;
         self.verify_epoch(timeout.epoch(), &safety_data)?;
         timeout
             .verify(&self.epoch_state()?.verifier)
@@ -37,8 +37,7 @@ impl SafetyRules {
             ));
         }
         if timeout.round() > safety_data.last_voted_round {
-            self.verify_and_update_last_vote_round(timeout.round(), &mut safety_data)?;
-            self.persistent_storage.set_safety_data(safety_data)?;
+            self.persistent_storage.try_advance_round(timeout.round())?;
         }
 
         let signature = self.sign(&timeout.signing_format())?;
@@ -4,16 +4,18 @@
 use crate::{
     local_client::LocalClient,
     persistent_safety_storage::PersistentSafetyStorage,
+    persistent_safety_storage_builder::PersistentSafetyStorageBuilder,
     process::ProcessService,
-    remote_service::RemoteService,
+    remote_service::{self, RemoteService},
     serializer::{SerializerClient, SerializerService},
     thread::ThreadService,
-    SafetyRules, TSafetyRules,
+    CircuitBreaker, Error, SafetyRules, TSafetyRules,
 };
 use aptos_config::config::{SafetyRulesConfig, SafetyRulesService};
 use aptos_infallible::RwLock;
+use aptos_secure_net::{ServerAddress, TlsConfig};
 use aptos_secure_storage::{KVStorage, Storage};
-use std::{convert::TryInto, net::SocketAddr, sync::Arc};
+use std::{convert::TryInto, sync::Arc, time::Duration};
 
 pub fn storage(config: &SafetyRulesConfig) -> PersistentSafetyStorage {
     let backend = &config.backend;
@@ -22,8 +24,39 @@ pub fn storage(config: &SafetyRulesConfig) -> PersistentSafetyStorage {
         panic!("Storage is not available: {:?}", error);
     }
 
+    let signing_lease_store = config.signing_lease.backend.as_ref().map(|backend| {
+        let signing_lease_store: Storage = backend
+            .try_into()
+            .expect("Unable to initialize signing lease storage");
+        if let Err(error) = signing_lease_store.available() {
+            panic!("Signing lease storage is not available: {:?}", error);
+        }
+        signing_lease_store
+    });
+
+    let mut builder = PersistentSafetyStorageBuilder::new()
+        .enable_caching(config.enable_cached_safety_data)
+        .instrument_storage(true)
+        .consensus_key_max_age_secs(config.consensus_key_max_age_secs)
+        .max_safety_data_backups(config.max_safety_data_backups)
+        .standby(config.standby)
+        .signing_lease(
+            signing_lease_store,
+            config.signing_lease.holder.clone(),
+            Duration::from_millis(config.signing_lease.duration_ms),
+        )
+        .max_proposal_timestamp_skew_ms(config.max_proposal_timestamp_skew_ms);
+    if let Some(failure_threshold) = config.storage_circuit_failure_threshold {
+        builder = builder.circuit_breaker(CircuitBreaker::new(
+            failure_threshold,
+            Duration::from_millis(config.storage_circuit_reset_timeout_ms),
+        ));
+    }
+    if config.enforce_storage_key_policy {
+        builder = builder.key_policy(PersistentSafetyStorage::key_policy());
+    }
+
     if let Some(test_config) = &config.test {
-        let author = test_config.author;
         let consensus_private_key = test_config
             .consensus_key
             .as_ref()
@@ -36,16 +69,17 @@ pub fn storage(config: &SafetyRulesConfig) -> PersistentSafetyStorage {
             .private_key();
         let waypoint = test_config.waypoint.expect("No waypoint in config");
 
-        PersistentSafetyStorage::initialize(
-            internal_storage,
-            author,
-            consensus_private_key,
-            execution_private_key,
-            waypoint,
-            config.enable_cached_safety_data,
-        )
+        builder
+            .author(test_config.author)
+            .consensus_key(consensus_private_key)
+            .execution_key(execution_private_key)
+            .waypoint(waypoint)
+            .build(internal_storage)
+            .expect("Unable to initialize safety rules storage")
     } else {
-        PersistentSafetyStorage::new(internal_storage, config.enable_cached_safety_data)
+        builder
+            .open(internal_storage)
+            .expect("Unable to open safety rules storage")
     }
 }
 
@@ -63,7 +97,16 @@ pub struct SafetyRulesManager {
 impl SafetyRulesManager {
     pub fn new(config: &SafetyRulesConfig) -> Self {
         if let SafetyRulesService::Process(conf) = &config.service {
-            return Self::new_process(conf.server_address(), config.network_timeout_ms);
+            // Without a valid TLS config the safety rules process cannot be reached
+            let manager = remote_service::tls_config(config, conf).and_then(|tls_config| {
+                Self::new_process(
+                    remote_service::server_address(conf),
+                    tls_config,
+                    config.network_timeout_ms,
+                    config.max_frame_size,
+                )
+            });
+            return manager.expect("Invalid safety rules TLS config");
         }
 
         let storage = storage(config);
@@ -105,11 +148,17 @@ impl SafetyRulesManager {
         }
     }
 
-    pub fn new_process(server_addr: SocketAddr, timeout_ms: u64) -> Self {
-        let process_service = ProcessService::new(server_addr, timeout_ms);
-        Self {
+    pub fn new_process(
+        server_addr: ServerAddress,
+        tls_config: Option<TlsConfig>,
+        timeout_ms: u64,
+        max_frame_size: usize,
+    ) -> Result<Self, Error> {
+        let process_service =
+            ProcessService::new(server_addr, tls_config, timeout_ms, max_frame_size)?;
+        Ok(Self {
             internal_safety_rules: SafetyRulesWrapper::Process(process_service),
-        }
+        })
     }
 
     pub fn new_serializer(
@@ -1,9 +1,13 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{counters, logging::LogEntry, ConsensusState, Error, SafetyRules, TSafetyRules};
-use aptos_crypto::ed25519::Ed25519Signature;
+use crate::{
+    counters, logging::LogEntry, ConsensusState, Error, SafetyRules, SignedSafetyStateAttestation,
+    TSafetyRules,
+};
+use aptos_crypto::{ed25519::Ed25519Signature, HashValue};
 use aptos_infallible::RwLock;
+use aptos_secure_net::DEFAULT_MAX_FRAME_SIZE;
 use aptos_types::{
     epoch_change::EpochChangeProof,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
@@ -34,18 +38,37 @@ pub enum SafetyRulesInput {
         Box<Option<TwoChainTimeoutCertificate>>,
     ),
     SignCommitVote(Box<LedgerInfoWithSignatures>, Box<LedgerInfo>),
+    AttestSafetyState(HashValue),
 }
 
 pub struct SerializerService {
     internal: SafetyRules,
+    /// The largest request or response, in bytes.
+    max_frame_size: usize,
 }
 
 impl SerializerService {
     pub fn new(internal: SafetyRules) -> Self {
-        Self { internal }
+        Self {
+            internal,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+
+    /// Limits the size of the requests that are decoded and the responses that are returned, in
+    /// bytes, e.g., to the maximum frame size of the network transport.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
     }
 
     pub fn handle_message(&mut self, input_message: Vec<u8>) -> Result<Vec<u8>, Error> {
+        if input_message.len() > self.max_frame_size {
+            return Err(Error::FrameTooLarge(
+                input_message.len(),
+                self.max_frame_size,
+            ));
+        }
         let input = serde_json::from_slice(&input_message)?;
 
         let output = match input {
@@ -80,9 +103,15 @@ impl SerializerService {
                     .internal
                     .sign_commit_vote(*ledger_info, *new_ledger_info),
             ),
-        };
+            SafetyRulesInput::AttestSafetyState(nonce) => {
+                serde_json::to_vec(&self.internal.attest_safety_state(nonce))
+            }
+        }?;
 
-        Ok(output?)
+        if output.len() > self.max_frame_size {
+            return Err(Error::FrameTooLarge(output.len(), self.max_frame_size));
+        }
+        Ok(output)
     }
 }
 
@@ -181,6 +210,15 @@ impl TSafetyRules for SerializerClient {
         ))?;
         serde_json::from_slice(&response)?
     }
+
+    fn attest_safety_state(
+        &mut self,
+        nonce: HashValue,
+    ) -> Result<SignedSafetyStateAttestation, Error> {
+        let _timer = counters::start_timer("external", LogEntry::AttestSafetyState.as_str());
+        let response = self.request(SafetyRulesInput::AttestSafetyState(nonce))?;
+        serde_json::from_slice(&response)?
+    }
 }
 
 pub trait TSerializerClient: Send + Sync {
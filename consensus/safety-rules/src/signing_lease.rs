@@ -0,0 +1,258 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Error;
+use aptos_secure_storage::KVStorage;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The storage key under which the signing lease is held.
+pub const SIGNING_LEASE: &str = "safety_rules_signing_lease";
+
+/// How long the signing lease is held without being renewed, unless configured otherwise.
+pub const DEFAULT_SIGNING_LEASE_DURATION: Duration = Duration::from_secs(30);
+
+/// Fences signing between a primary validator and its hot standbys: only the holder of an
+/// unexpired lease may sign. The lease must live in a backend shared by all of them, and the
+/// active holder is expected to re-acquire it before it expires.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SigningLease {
+    pub holder: String,
+    /// Unix time in milliseconds after which the lease can be taken over.
+    pub expiration_ms: u64,
+}
+
+impl SigningLease {
+    /// Acquires (or renews) the lease for `holder`. Fails if another holder has a lease that has
+    /// not yet expired according to `time_service`. The lease is only written if it did not
+    /// change since it was read, so of several nodes racing for an expired lease only one wins.
+    pub fn acquire<S: KVStorage>(
+        lease_store: &mut S,
+        holder: &str,
+        duration: Duration,
+        time_service: &TimeService,
+    ) -> Result<Self, Error> {
+        let now = time_service.now_unix_time().as_millis() as u64;
+        let version = match lease_store.get::<SigningLease>(SIGNING_LEASE) {
+            Ok(response) => {
+                let current = response.value;
+                if current.holder != holder && !current.is_expired(now) {
+                    return Err(Error::SigningLeaseHeld(
+                        current.holder,
+                        current.expiration_ms,
+                    ));
+                }
+                response.version
+            }
+            Err(aptos_secure_storage::Error::KeyNotSet(_)) => 0,
+            Err(error) => return Err(error.into()),
+        };
+
+        let lease = SigningLease {
+            holder: holder.to_string(),
+            expiration_ms: now.saturating_add(duration.as_millis() as u64),
+        };
+        match lease_store.set_if_version(SIGNING_LEASE, lease.clone(), version) {
+            Ok(()) => Ok(lease),
+            // Another node wrote the lease in the meantime, report whoever holds it now
+            Err(aptos_secure_storage::Error::VersionMismatch { .. }) => {
+                let current = lease_store.get::<SigningLease>(SIGNING_LEASE)?.value;
+                Err(Error::SigningLeaseHeld(
+                    current.holder,
+                    current.expiration_ms,
+                ))
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Releases the lease if `holder` holds it, by expiring it. Releasing a lease that is not
+    /// held, or held by another holder, is a no-op. Like `acquire`, the lease is only written if
+    /// it did not change since it was read, so a lease taken over in the meantime is kept.
+    pub fn release<S: KVStorage>(lease_store: &mut S, holder: &str) -> Result<(), Error> {
+        let version = match lease_store.get::<SigningLease>(SIGNING_LEASE) {
+            Ok(response) if response.value.holder == holder => response.version,
+            Ok(_) | Err(aptos_secure_storage::Error::KeyNotSet(_)) => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let lease = SigningLease {
+            holder: holder.to_string(),
+            expiration_ms: 0,
+        };
+        match lease_store.set_if_version(SIGNING_LEASE, lease, version) {
+            Ok(()) | Err(aptos_secure_storage::Error::VersionMismatch { .. }) => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        now_ms >= self.expiration_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mock_time_service;
+    use aptos_secure_storage::{GetResponse, InMemoryStorage};
+    use serde::de::DeserializeOwned;
+
+    #[test]
+    fn test_acquire() {
+        let mock_time = mock_time_service(Duration::from_secs(1_000));
+        let time = TimeService::from(mock_time.clone());
+        let mut lease_store = InMemoryStorage::new();
+        let lease =
+            SigningLease::acquire(&mut lease_store, "primary", Duration::from_secs(60), &time)
+                .unwrap();
+        assert_eq!(lease.holder, "primary");
+        assert_eq!(lease.expiration_ms, 1_060_000);
+
+        // The holder can renew, but nobody else can take over an unexpired lease
+        mock_time.advance(Duration::from_secs(30));
+        let lease =
+            SigningLease::acquire(&mut lease_store, "primary", Duration::from_secs(60), &time)
+                .unwrap();
+        assert_eq!(lease.expiration_ms, 1_090_000);
+        mock_time.advance(Duration::from_millis(59_999));
+        let error =
+            SigningLease::acquire(&mut lease_store, "standby", Duration::from_secs(60), &time)
+                .unwrap_err();
+        assert!(matches!(error, Error::SigningLeaseHeld(holder, _) if holder == "primary"));
+
+        // A lease expires at its expiration time, after which it can be taken over
+        mock_time.advance(Duration::from_millis(1));
+        let lease =
+            SigningLease::acquire(&mut lease_store, "standby", Duration::from_secs(60), &time)
+                .unwrap();
+        assert_eq!(lease.holder, "standby");
+    }
+
+    #[test]
+    fn test_acquire_race() {
+        let time = TimeService::from(mock_time_service(Duration::from_secs(1_000)));
+        let mut lease_store = RacingStorage {
+            inner: InMemoryStorage::new(),
+            interloper: None,
+        };
+        SigningLease::acquire(&mut lease_store, "primary", Duration::from_secs(0), &time).unwrap();
+
+        // Another standby takes over the expired lease between the read and the write
+        lease_store.interloper = Some(SigningLease {
+            holder: "other".into(),
+            expiration_ms: 1_060_000,
+        });
+        let error =
+            SigningLease::acquire(&mut lease_store, "standby", Duration::from_secs(60), &time)
+                .unwrap_err();
+        assert_eq!(error, Error::SigningLeaseHeld("other".into(), 1_060_000));
+        let lease = lease_store
+            .get::<SigningLease>(SIGNING_LEASE)
+            .unwrap()
+            .value;
+        assert_eq!(lease.holder, "other");
+    }
+
+    /// Writes the interloper's lease right after the lease is read, once.
+    struct RacingStorage {
+        inner: InMemoryStorage,
+        interloper: Option<SigningLease>,
+    }
+
+    impl KVStorage for RacingStorage {
+        fn available(&self) -> Result<(), aptos_secure_storage::Error> {
+            self.inner.available()
+        }
+
+        fn get<T: DeserializeOwned>(
+            &self,
+            key: &str,
+        ) -> Result<GetResponse<T>, aptos_secure_storage::Error> {
+            self.inner.get(key)
+        }
+
+        fn set<T: Serialize>(
+            &mut self,
+            key: &str,
+            value: T,
+        ) -> Result<(), aptos_secure_storage::Error> {
+            self.inner.set(key, value)
+        }
+
+        fn set_if_version<T: Serialize>(
+            &mut self,
+            key: &str,
+            value: T,
+            expected_version: u64,
+        ) -> Result<(), aptos_secure_storage::Error> {
+            if let Some(lease) = self.interloper.take() {
+                self.inner.set(key, lease)?;
+            }
+            self.inner.set_if_version(key, value, expected_version)
+        }
+
+        fn delete(&mut self, key: &str) -> Result<(), aptos_secure_storage::Error> {
+            self.inner.delete(key)
+        }
+
+        fn list_keys(
+            &self,
+            prefix: Option<&str>,
+        ) -> Result<Vec<String>, aptos_secure_storage::Error> {
+            self.inner.list_keys(prefix)
+        }
+
+        fn reset_and_clear(&mut self) -> Result<(), aptos_secure_storage::Error> {
+            self.inner.reset_and_clear()
+        }
+    }
+
+    #[test]
+    fn test_release() {
+        let time = TimeService::from(mock_time_service(Duration::from_secs(1_000)));
+        let mut lease_store = InMemoryStorage::new();
+        SigningLease::release(&mut lease_store, "primary").unwrap();
+        SigningLease::acquire(&mut lease_store, "primary", Duration::from_secs(60), &time).unwrap();
+
+        // Only the holder releases the lease, after which anybody can take it over at once
+        SigningLease::release(&mut lease_store, "standby").unwrap();
+        assert!(
+            SigningLease::acquire(&mut lease_store, "standby", Duration::from_secs(60), &time)
+                .is_err()
+        );
+        SigningLease::release(&mut lease_store, "primary").unwrap();
+        SigningLease::release(&mut lease_store, "primary").unwrap();
+        let lease =
+            SigningLease::acquire(&mut lease_store, "standby", Duration::from_secs(60), &time)
+                .unwrap();
+        assert_eq!(lease.holder, "standby");
+    }
+
+    #[test]
+    fn test_release_race() {
+        let time = TimeService::from(mock_time_service(Duration::from_secs(1_000)));
+        let mut lease_store = RacingStorage {
+            inner: InMemoryStorage::new(),
+            interloper: None,
+        };
+        SigningLease::acquire(&mut lease_store, "primary", Duration::from_secs(0), &time).unwrap();
+
+        // A standby takes over the expired lease between the read and the release
+        lease_store.interloper = Some(SigningLease {
+            holder: "standby".into(),
+            expiration_ms: 1_060_000,
+        });
+        SigningLease::release(&mut lease_store, "primary").unwrap();
+        let lease = lease_store
+            .get::<SigningLease>(SIGNING_LEASE)
+            .unwrap()
+            .value;
+        assert_eq!(lease.holder, "standby");
+        assert!(
+            SigningLease::acquire(&mut lease_store, "primary", Duration::from_secs(60), &time)
+                .is_err()
+        );
+    }
+}
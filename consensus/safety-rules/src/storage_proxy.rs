@@ -0,0 +1,141 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, PersistentSafetyStorage};
+use aptos_crypto::ed25519::Ed25519PublicKey;
+use aptos_infallible::Mutex;
+use aptos_secure_storage::TokenBucket;
+use aptos_time_service::TimeServiceTrait;
+use aptos_types::waypoint::Waypoint;
+use consensus_types::{common::Author, safety_data::SafetyData};
+
+/// Rate-limits the reads and writes issued to a PersistentSafetyStorage, e.g., to protect a
+/// remote storage backend from a misbehaving caller. Reads and writes draw from separate token
+/// buckets, each given as its capacity and refill rate per second, and calls in excess of either
+/// fail with `Error::RateLimited` without reaching the storage. Time is read from the time service
+/// of the storage.
+pub struct StorageProxy {
+    storage: PersistentSafetyStorage,
+    reads: Mutex<TokenBucket>,
+    writes: Mutex<TokenBucket>,
+}
+
+impl StorageProxy {
+    pub fn new(
+        storage: PersistentSafetyStorage,
+        (read_capacity, reads_per_sec): (u64, u64),
+        (write_capacity, writes_per_sec): (u64, u64),
+    ) -> Self {
+        let now = storage.time_service().now();
+        Self {
+            reads: Mutex::new(TokenBucket::new(read_capacity, reads_per_sec, now)),
+            writes: Mutex::new(TokenBucket::new(write_capacity, writes_per_sec, now)),
+            storage,
+        }
+    }
+
+    pub fn inner(&self) -> &PersistentSafetyStorage {
+        &self.storage
+    }
+
+    /// Gives access to the storage without rate limiting.
+    pub fn inner_mut(&mut self) -> &mut PersistentSafetyStorage {
+        &mut self.storage
+    }
+
+    pub fn into_inner(self) -> PersistentSafetyStorage {
+        self.storage
+    }
+
+    pub fn author(&self) -> Result<Author, Error> {
+        self.acquire_read()?;
+        self.storage.author()
+    }
+
+    pub fn execution_public_key(&self) -> Result<Ed25519PublicKey, Error> {
+        self.acquire_read()?;
+        self.storage.execution_public_key()
+    }
+
+    pub fn safety_data(&mut self) -> Result<SafetyData, Error> {
+        self.acquire_read()?;
+        self.storage.safety_data()
+    }
+
+    pub fn waypoint(&self) -> Result<Waypoint, Error> {
+        self.acquire_read()?;
+        self.storage.waypoint()
+    }
+
+    pub fn set_safety_data(&mut self, data: SafetyData) -> Result<(), Error> {
+        self.acquire_write()?;
+        self.storage.set_safety_data(data)
+    }
+
+    pub fn set_waypoint(&mut self, waypoint: &Waypoint) -> Result<(), Error> {
+        self.acquire_write()?;
+        self.storage.set_waypoint(waypoint)
+    }
+
+    fn acquire_read(&self) -> Result<(), Error> {
+        self.acquire(&self.reads, "read")
+    }
+
+    fn acquire_write(&self) -> Result<(), Error> {
+        self.acquire(&self.writes, "write")
+    }
+
+    fn acquire(&self, bucket: &Mutex<TokenBucket>, operation: &str) -> Result<(), Error> {
+        bucket
+            .lock()
+            .try_acquire(self.storage.time_service().now())
+            .map_err(|_| Error::RateLimited(operation.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mock_time_service;
+    use aptos_crypto::{ed25519::Ed25519PrivateKey, Uniform};
+    use aptos_secure_storage::{InMemoryStorage, Storage};
+    use aptos_types::validator_signer::ValidatorSigner;
+    use std::time::Duration;
+
+    #[test]
+    fn test_storage_proxy() {
+        let signer = ValidatorSigner::from_int(0);
+        let mut storage = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            Waypoint::default(),
+            true,
+        );
+        let time = mock_time_service(Duration::from_secs(1));
+        storage.set_time_service(time.clone().into());
+        let mut proxy = StorageProxy::new(storage, (3, 1), (2, 1));
+
+        // Bursts up to the capacity of each bucket succeed, and excess calls are rejected
+        assert_eq!(proxy.author().unwrap(), signer.author());
+        let safety_data = proxy.safety_data().unwrap();
+        assert_eq!(proxy.waypoint().unwrap(), Waypoint::default());
+        assert_eq!(proxy.author(), Err(Error::RateLimited("read".into())));
+        proxy.set_safety_data(safety_data.clone()).unwrap();
+        proxy.set_waypoint(&Waypoint::default()).unwrap();
+        assert_eq!(
+            proxy.set_safety_data(safety_data.clone()),
+            Err(Error::RateLimited("write".into()))
+        );
+
+        // The inner storage is not rate limited
+        assert_eq!(proxy.inner().author().unwrap(), signer.author());
+
+        // Tokens are refilled over time
+        time.advance(Duration::from_secs(1));
+        proxy.author().unwrap();
+        proxy.set_safety_data(safety_data).unwrap();
+        assert!(proxy.author().is_err());
+    }
+}
@@ -1,8 +1,8 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{ConsensusState, Error};
-use aptos_crypto::ed25519::Ed25519Signature;
+use crate::{ConsensusState, Error, SignedSafetyStateAttestation};
+use aptos_crypto::{ed25519::Ed25519Signature, HashValue};
 use aptos_types::{
     epoch_change::EpochChangeProof,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
@@ -62,4 +62,12 @@ pub trait TSafetyRules {
         ledger_info: LedgerInfoWithSignatures,
         new_ledger_info: LedgerInfo,
     ) -> Result<Ed25519Signature, Error>;
+
+    /// Attests to the current epoch, last voted round and waypoint version for external
+    /// monitoring. The attestation covers the caller-provided nonce and is signed with the
+    /// consensus key, but cannot be used in place of any consensus message.
+    fn attest_safety_state(
+        &mut self,
+        nonce: HashValue,
+    ) -> Result<SignedSafetyStateAttestation, Error>;
 }
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    persistent_safety_storage::PersistentSafetyStorage, serializer::SerializerService, SafetyRules,
-    TSafetyRules,
+    persistent_safety_storage::PersistentSafetyStorage,
+    persistent_safety_storage_builder::PersistentSafetyStorageBuilder,
+    serializer::SerializerService, SafetyRules, TSafetyRules,
 };
 use aptos_crypto::{
     ed25519::Ed25519PrivateKey,
@@ -12,6 +13,7 @@ use aptos_crypto::{
     Uniform,
 };
 use aptos_secure_storage::{InMemoryStorage, Storage};
+use aptos_time_service::MockTimeService;
 use aptos_types::{
     block_info::BlockInfo,
     epoch_change::EpochChangeProof,
@@ -33,10 +35,18 @@ use consensus_types::{
     vote_data::VoteData,
     vote_proposal::{MaybeSignedVoteProposal, VoteProposal},
 };
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, time::Duration};
 
 pub type Proof = AccumulatorExtensionProof<TransactionAccumulatorHasher>;
 
+/// Returns a mock time service whose clock starts at the given Unix time. Clones share the same
+/// time, so a test can keep a clone to advance the clock of the safety rules it hands one to.
+pub fn mock_time_service(now: Duration) -> MockTimeService {
+    let time = MockTimeService::new();
+    time.advance(now);
+    time
+}
+
 pub fn empty_proof() -> Proof {
     Proof::new(vec![], 0, vec![])
 }
@@ -227,14 +237,13 @@ pub fn validator_signers_to_waypoint(signers: &[&ValidatorSigner]) -> Waypoint {
 pub fn test_storage(signer: &ValidatorSigner) -> PersistentSafetyStorage {
     let waypoint = validator_signers_to_waypoint(&[signer]);
     let storage = Storage::from(InMemoryStorage::new());
-    PersistentSafetyStorage::initialize(
-        storage,
-        signer.author(),
-        signer.private_key().clone(),
-        Ed25519PrivateKey::generate_for_testing(),
-        waypoint,
-        true,
-    )
+    PersistentSafetyStorageBuilder::new()
+        .author(signer.author())
+        .consensus_key(signer.private_key().clone())
+        .execution_key(Ed25519PrivateKey::generate_for_testing())
+        .waypoint(waypoint)
+        .build(storage)
+        .expect("Unable to initialize test storage")
 }
 
 /// Returns a safety rules instance for testing purposes.
@@ -1,7 +1,13 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{test_utils, SafetyRulesManager};
+use crate::{remote_service, test_utils, Error, SafetyRulesManager};
+use aptos_config::config::{
+    OnDiskStorageConfig, RemoteService, RemoteServiceTlsConfig, SafetyRulesConfig, SecureBackend,
+    TlsMaterial,
+};
+use aptos_secure_net::DEFAULT_MAX_FRAME_SIZE;
+use aptos_secure_storage::{KVStorage, Storage};
 use aptos_types::validator_signer::ValidatorSigner;
 
 #[test]
@@ -18,3 +24,72 @@ fn test_reconnect() {
     let state1 = safety_rules_manager.client().consensus_state().unwrap();
     assert_eq!(state0, state1);
 }
+
+#[test]
+fn test_load_tls_config() {
+    let storage_path = aptos_temppath::TempPath::new();
+    let mut backend = OnDiskStorageConfig::default();
+    backend.path = storage_path.path().to_path_buf();
+    let config = SafetyRulesConfig {
+        backend: SecureBackend::OnDiskStorage(backend),
+        ..Default::default()
+    };
+    let mut storage = Storage::from(&config.backend);
+    storage
+        .set("safety_rules_tls_key", "private key".to_string())
+        .unwrap();
+    storage
+        .set("safety_rules_tls_ca", "ca".to_string())
+        .unwrap();
+
+    let certificate_path = aptos_temppath::TempPath::new();
+    std::fs::write(certificate_path.path(), "certificate").unwrap();
+
+    let mut service = RemoteService {
+        server_address: "/ip4/127.0.0.1/tcp/5555".parse().unwrap(),
+        socket_path: None,
+        tls: Some(RemoteServiceTlsConfig {
+            certificate: TlsMaterial::FromDisk(certificate_path.path().to_path_buf()),
+            private_key: TlsMaterial::FromStorage("safety_rules_tls_key".into()),
+            ca_certificate: TlsMaterial::FromStorage("safety_rules_tls_ca".into()),
+            server_name: "safety-rules".into(),
+        }),
+    };
+    let tls_config = remote_service::tls_config(&config, &service)
+        .unwrap()
+        .unwrap();
+    assert_eq!(tls_config.certificate_chain, b"certificate");
+    assert_eq!(tls_config.private_key, b"private key");
+    assert_eq!(tls_config.ca_certificates, b"ca");
+    assert_eq!(tls_config.server_name, "safety-rules");
+
+    // The material is not valid PEM, which is reported when connecting rather than a panic
+    let error = SafetyRulesManager::new_process(
+        remote_service::server_address(&service),
+        Some(tls_config),
+        1_000,
+        DEFAULT_MAX_FRAME_SIZE,
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error, Error::InvalidTlsConfig(_)));
+
+    // TLS is only supported over TCP
+    service.socket_path = Some(storage_path.path().with_extension("sock"));
+    assert!(matches!(
+        remote_service::tls_config(&config, &service).unwrap_err(),
+        Error::InvalidTlsConfig(_)
+    ));
+    service.socket_path = None;
+
+    service.tls.as_mut().unwrap().ca_certificate = TlsMaterial::FromStorage("missing".into());
+    assert!(matches!(
+        remote_service::tls_config(&config, &service).unwrap_err(),
+        Error::SecureStorageMissingDataError(_)
+    ));
+
+    service.tls = None;
+    assert!(remote_service::tls_config(&config, &service)
+        .unwrap()
+        .is_none());
+}
@@ -1,9 +1,22 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{test_utils, tests::suite, SafetyRules};
+use crate::{
+    counters, key_derivation, safety_rules::DEFAULT_MAX_PROPOSAL_TIMESTAMP_SKEW_MS, test_utils,
+    tests::suite, Error, RejectionReason, SafetyRules, TSafetyRules,
+};
 use aptos_crypto::{ed25519::Ed25519PrivateKey, Uniform};
+use aptos_time_service::{MockTimeService, TimeServiceTrait};
 use aptos_types::validator_signer::ValidatorSigner;
+use consensus_types::{
+    block::Block,
+    block_data::BlockData,
+    quorum_cert::QuorumCert,
+    timeout::Timeout,
+    vote_proposal::{MaybeSignedVoteProposal, VoteProposal},
+};
+use serial_test::serial;
+use std::time::Duration;
 
 #[test]
 fn test() {
@@ -41,3 +54,244 @@ fn safety_rules(
         )
     })
 }
+
+#[test]
+fn test_proposal_too_far_in_the_future() {
+    let signer = ValidatorSigner::from_int(0);
+    let time = test_utils::mock_time_service(Duration::from_secs(1_000));
+    let (mut safety_rules, genesis_qc) = initialized_safety_rules(&signer, &time);
+    let max_timestamp_usecs = max_timestamp_usecs(&time);
+
+    let proposal = make_proposal_at(max_timestamp_usecs + 1, genesis_qc.clone(), &signer);
+    assert_eq!(
+        safety_rules
+            .sign_proposal(proposal.block().block_data())
+            .unwrap_err(),
+        Error::ProposalTimestampTooFarInFuture(max_timestamp_usecs + 1, now_usecs(&time))
+    );
+    assert_eq!(
+        safety_rules
+            .construct_and_sign_vote_two_chain(&proposal, None)
+            .unwrap_err(),
+        Error::ProposalTimestampTooFarInFuture(max_timestamp_usecs + 1, now_usecs(&time))
+    );
+
+    // A timestamp exactly at the bound is accepted
+    let proposal = make_proposal_at(max_timestamp_usecs, genesis_qc, &signer);
+    safety_rules
+        .sign_proposal(proposal.block().block_data())
+        .unwrap();
+    safety_rules
+        .construct_and_sign_vote_two_chain(&proposal, None)
+        .unwrap();
+}
+
+#[test]
+fn test_proposal_timestamp_clock_skew() {
+    let signer = ValidatorSigner::from_int(0);
+    let time = test_utils::mock_time_service(Duration::from_secs(1_000));
+    let (mut safety_rules, genesis_qc) = initialized_safety_rules(&signer, &time);
+    let proposal = make_proposal_at(max_timestamp_usecs(&time) + 1, genesis_qc, &signer);
+    let block_data = proposal.block().block_data();
+
+    // The leader's clock is ahead of ours, until ours catches up
+    safety_rules.sign_proposal(block_data).unwrap_err();
+    time.advance(Duration::from_micros(1));
+    safety_rules.sign_proposal(block_data).unwrap();
+
+    // Our clock is corrected backwards, the same proposal is too far ahead again
+    let time = test_utils::mock_time_service(Duration::from_secs(999));
+    safety_rules
+        .persistent_storage
+        .set_time_service(time.clone().into());
+    assert!(matches!(
+        safety_rules.sign_proposal(block_data).unwrap_err(),
+        Error::ProposalTimestampTooFarInFuture(..)
+    ));
+
+    // A clock far ahead of the proposal neither rejects it nor overflows the bound
+    time.advance(Duration::from_secs(u64::MAX / 1_000_000 - 999));
+    safety_rules.sign_proposal(block_data).unwrap();
+    safety_rules
+        .construct_and_sign_vote_two_chain(&proposal, None)
+        .unwrap();
+}
+
+#[test]
+#[serial]
+fn test_configured_proposal_timestamp_skew() {
+    counters::reset_all();
+    let signer = ValidatorSigner::from_int(0);
+    let time = test_utils::mock_time_service(Duration::from_secs(1_000));
+    let (mut safety_rules, genesis_qc) = initialized_safety_rules(&signer, &time);
+    safety_rules
+        .persistent_storage
+        .set_max_proposal_timestamp_skew_ms(0);
+
+    let rejections = counters::get_proposal_timestamp_rejections();
+    let proposal = make_proposal_at(now_usecs(&time) + 1, genesis_qc.clone(), &signer);
+    safety_rules
+        .sign_proposal(proposal.block().block_data())
+        .unwrap_err();
+    safety_rules
+        .construct_and_sign_vote_two_chain(&proposal, None)
+        .unwrap_err();
+    assert!(counters::get_proposal_timestamp_rejections() >= rejections + 2);
+
+    let proposal = make_proposal_at(now_usecs(&time), genesis_qc, &signer);
+    safety_rules
+        .construct_and_sign_vote_two_chain(&proposal, None)
+        .unwrap();
+}
+
+#[test]
+fn test_genesis_timestamp_in_the_future() {
+    let signer = ValidatorSigner::from_int(0);
+    let time = test_utils::mock_time_service(Duration::from_secs(1_000));
+    let (safety_rules, genesis_qc) = initialized_safety_rules(&signer, &time);
+
+    // The genesis block is never proposed, its timestamp is that of a ledger info
+    let genesis = BlockData::new_genesis(max_timestamp_usecs(&time) + 1, genesis_qc);
+    safety_rules.verify_timestamp(&genesis).unwrap();
+}
+
+#[test]
+#[serial]
+fn test_rejections() {
+    counters::reset_all();
+    let signer = ValidatorSigner::from_int(0);
+    let time = test_utils::mock_time_service(Duration::from_secs(1_000));
+    let (mut safety_rules, genesis_qc) = initialized_safety_rules(&signer, &time);
+    assert_eq!(safety_rules.last_rejection(), None);
+
+    let reasons = [
+        RejectionReason::OldRound,
+        RejectionReason::PreferredRound,
+        RejectionReason::EpochMismatch,
+        RejectionReason::WaypointMismatch,
+        RejectionReason::InvalidQuorumCert,
+        RejectionReason::Timestamp,
+    ];
+    let rejections: Vec<_> = reasons
+        .iter()
+        .map(|reason| counters::get_rejections(reason.as_str()))
+        .collect();
+
+    // Vote up to round 3, with a preferred round of 1
+    let round = genesis_qc.certified_block().round();
+    let epoch = genesis_qc.certified_block().epoch();
+    let p0 = test_utils::make_proposal_with_qc(round + 1, genesis_qc.clone(), &signer, None);
+    let p1 = test_utils::make_proposal_with_parent(vec![], round + 2, &p0, None, &signer, None);
+    let p2 = test_utils::make_proposal_with_parent(vec![], round + 3, &p1, None, &signer, None);
+    safety_rules.construct_and_sign_vote(&p0).unwrap();
+    safety_rules.construct_and_sign_vote(&p1).unwrap();
+    safety_rules.construct_and_sign_vote(&p2).unwrap();
+
+    safety_rules
+        .sign_timeout(&Timeout::new(epoch, round + 2))
+        .unwrap_err();
+    safety_rules
+        .sign_timeout(&Timeout::new(epoch, round + 1))
+        .unwrap_err();
+    safety_rules
+        .sign_timeout(&Timeout::new(epoch + 1, round + 4))
+        .unwrap_err();
+
+    let bad_signer = ValidatorSigner::from_int(0xef);
+    let bad =
+        test_utils::make_proposal_with_parent(vec![], round + 4, &p2, None, &bad_signer, None);
+    let bad_qc = bad.block().quorum_cert().clone();
+    let proposal = test_utils::make_proposal_with_qc(round + 5, bad_qc, &signer, None);
+    safety_rules
+        .sign_proposal(proposal.block().block_data())
+        .unwrap_err();
+
+    let proposal = make_proposal_at(max_timestamp_usecs(&time) + 1, genesis_qc, &signer);
+    safety_rules
+        .sign_proposal(proposal.block().block_data())
+        .unwrap_err();
+    let last_rejection = safety_rules.last_rejection().unwrap();
+    assert_eq!(last_rejection.reason(), RejectionReason::Timestamp);
+    assert_eq!(last_rejection.request(), "sign_proposal");
+    assert_eq!(last_rejection.timestamp_usecs(), now_usecs(&time));
+
+    let (bad_proof, _) = test_utils::make_genesis(&ValidatorSigner::from_int(1));
+    safety_rules.initialize(&bad_proof).unwrap_err();
+
+    for (reason, rejections) in reasons.iter().zip(rejections) {
+        assert!(counters::get_rejections(reason.as_str()) > rejections);
+    }
+
+    // The last rejection is also part of the consensus state
+    let last_rejection = safety_rules
+        .consensus_state()
+        .unwrap()
+        .last_rejection()
+        .cloned();
+    assert_eq!(last_rejection, safety_rules.last_rejection());
+    let last_rejection = last_rejection.unwrap();
+    assert_eq!(last_rejection.reason(), RejectionReason::WaypointMismatch);
+    assert_eq!(last_rejection.request(), "initialize");
+}
+
+#[test]
+fn test_derived_epoch_consensus_key() {
+    let master_seed = vec![42; 32];
+    let stored_signer = ValidatorSigner::from_int(0);
+    let derived_key = key_derivation::derive_consensus_key(&master_seed, 1).unwrap();
+    let signer = ValidatorSigner::new(stored_signer.author(), derived_key);
+    let (proof, genesis_qc) = test_utils::make_genesis(&signer);
+
+    for export_consensus_key in [false, true] {
+        // Storage only holds another consensus key, the one of the validator set is derived
+        let mut storage = test_utils::test_storage(&stored_signer);
+        storage
+            .set_consensus_key_master_seed(master_seed.clone())
+            .unwrap();
+        let mut safety_rules = SafetyRules::new(storage, false, export_consensus_key);
+        safety_rules.initialize(&proof).unwrap();
+        assert_eq!(
+            safety_rules.signer().unwrap().public_key(),
+            signer.public_key()
+        );
+
+        let proposal = test_utils::make_proposal_with_qc(1, genesis_qc.clone(), &signer, None);
+        safety_rules
+            .sign_proposal(proposal.block().block_data())
+            .unwrap();
+        safety_rules.construct_and_sign_vote(&proposal).unwrap();
+    }
+}
+
+fn initialized_safety_rules(
+    signer: &ValidatorSigner,
+    time: &MockTimeService,
+) -> (SafetyRules, QuorumCert) {
+    let mut storage = test_utils::test_storage(signer);
+    storage.set_time_service(time.clone().into());
+    let mut safety_rules = SafetyRules::new(storage, false, false);
+    let (proof, genesis_qc) = test_utils::make_genesis(signer);
+    safety_rules.initialize(&proof).unwrap();
+    (safety_rules, genesis_qc)
+}
+
+fn now_usecs(time: &MockTimeService) -> u64 {
+    time.now_unix_time().as_micros() as u64
+}
+
+fn max_timestamp_usecs(time: &MockTimeService) -> u64 {
+    now_usecs(time) + DEFAULT_MAX_PROPOSAL_TIMESTAMP_SKEW_MS * 1000
+}
+
+fn make_proposal_at(
+    timestamp_usecs: u64,
+    qc: QuorumCert,
+    signer: &ValidatorSigner,
+) -> MaybeSignedVoteProposal {
+    let round = qc.certified_block().round() + 1;
+    let block = Block::new_proposal(vec![], round, timestamp_usecs, qc, signer);
+    MaybeSignedVoteProposal {
+        vote_proposal: VoteProposal::new(test_utils::empty_proof(), block, None, false),
+        signature: None,
+    }
+}
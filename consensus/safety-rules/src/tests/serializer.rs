@@ -1,7 +1,7 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{test_utils, tests::suite, SafetyRulesManager};
+use crate::{test_utils, tests::suite, Error, SafetyRulesManager};
 use aptos_crypto::{ed25519::Ed25519PrivateKey, Uniform};
 use aptos_types::validator_signer::ValidatorSigner;
 
@@ -18,6 +18,25 @@ fn test() {
     }
 }
 
+#[test]
+fn test_max_frame_size() {
+    // Oversized requests are rejected before they are decoded
+    let mut serializer_service = test_utils::test_serializer().with_max_frame_size(16);
+    assert_eq!(
+        serializer_service.handle_message(vec![b' '; 17]),
+        Err(Error::FrameTooLarge(17, 16))
+    );
+
+    // So are responses larger than the transport accepts
+    let mut serializer_service = test_utils::test_serializer().with_max_frame_size(18);
+    let request = b"\"ConsensusState\"".to_vec();
+    assert_eq!(request.len(), 16);
+    assert!(matches!(
+        serializer_service.handle_message(request),
+        Err(Error::FrameTooLarge(_, 18))
+    ));
+}
+
 fn safety_rules(
     verify_vote_proposal_signature: bool,
     export_consensus_key: bool,
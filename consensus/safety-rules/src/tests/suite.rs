@@ -77,6 +77,7 @@ pub fn run_test_suite(safety_rules: &Callback) {
     test_2chain_timeout(safety_rules);
     test_sign_commit_vote(safety_rules);
     test_bad_execution_output(safety_rules);
+    test_attest_safety_state(safety_rules);
 }
 
 fn test_bad_execution_output(safety_rules: &Callback) {
@@ -692,7 +693,7 @@ fn test_reconcile_key(_safety_rules: &Callback) {
     let signer = ValidatorSigner::from_int(0);
     let mut storage = test_utils::test_storage(&signer);
 
-    let new_pub_key = storage.internal_store().rotate_key(CONSENSUS_KEY).unwrap();
+    let new_pub_key = storage.rotate_consensus_key().unwrap();
     let mut safety_rules = Box::new(SafetyRules::new(storage, false, false));
 
     let (mut proof, genesis_qc) = test_utils::make_genesis(&signer);
@@ -1064,3 +1065,34 @@ fn test_sign_commit_vote(constructor: &Callback) {
         Error::InconsistentExecutionResult(_, _)
     ));
 }
+
+fn test_attest_safety_state(safety_rules: &Callback) {
+    let (mut safety_rules, signer, key) = safety_rules();
+    let (proof, genesis_qc) = test_utils::make_genesis(&signer);
+    let round = genesis_qc.certified_block().round();
+
+    // Nothing can be attested before the consensus key for the epoch is known
+    let nonce = HashValue::random();
+    assert_eq!(
+        safety_rules.attest_safety_state(nonce).unwrap_err(),
+        Error::NotInitialized("validator_signer".into())
+    );
+
+    safety_rules.initialize(&proof).unwrap();
+    let a1 = test_utils::make_proposal_with_qc(round + 1, genesis_qc, &signer, key.as_ref());
+    safety_rules
+        .construct_and_sign_vote_two_chain(&a1, None)
+        .unwrap();
+
+    let signed = safety_rules.attest_safety_state(nonce).unwrap();
+    signed.verify(&signer.public_key(), nonce).unwrap();
+    let attestation = signed.attestation();
+    let consensus_state = safety_rules.consensus_state().unwrap();
+    assert_eq!(attestation.author(), signer.author());
+    assert_eq!(attestation.epoch(), consensus_state.epoch());
+    assert_eq!(attestation.last_voted_round(), round + 1);
+    assert_eq!(
+        attestation.waypoint_version(),
+        consensus_state.waypoint().version()
+    );
+}
@@ -10,8 +10,10 @@
 use crate::{
     persistent_safety_storage::PersistentSafetyStorage,
     remote_service::{self, RemoteService},
+    Error,
 };
 use aptos_config::utils;
+use aptos_secure_net::{ServerAddress, DEFAULT_MAX_FRAME_SIZE};
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
     thread::{self, JoinHandle},
@@ -20,7 +22,7 @@ use std::{
 /// ThreadClient is the actual owner of the thread but in the context of Consenus and SafetyRules
 /// is on the client side of the operations as it makes queries / requests to SafetyRules.
 pub struct ThreadService {
-    _child: JoinHandle<()>,
+    _child: JoinHandle<Result<(), Error>>,
     server_addr: SocketAddr,
     network_timeout: u64,
 }
@@ -39,10 +41,12 @@ impl ThreadService {
         let child = thread::spawn(move || {
             remote_service::execute(
                 storage,
-                listen_addr,
+                listen_addr.into(),
+                None,
                 verify_vote_proposal_signature,
                 export_consensus_key,
                 timeout,
+                DEFAULT_MAX_FRAME_SIZE,
             )
         });
 
@@ -55,8 +59,8 @@ impl ThreadService {
 }
 
 impl RemoteService for ThreadService {
-    fn server_address(&self) -> SocketAddr {
-        self.server_addr
+    fn server_address(&self) -> ServerAddress {
+        self.server_addr.into()
     }
     fn network_timeout_ms(&self) -> u64 {
         self.network_timeout
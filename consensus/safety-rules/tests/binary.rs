@@ -2,28 +2,100 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aptos_config::{
-    config::{NodeConfig, PersistableConfig, RemoteService, SafetyRulesService},
+    config::{NodeConfig, PersistableConfig, RemoteService, SafetyRulesConfig, SafetyRulesService},
     utils,
 };
 use aptos_types::validator_signer::ValidatorSigner;
-use safety_rules::{test_utils, SafetyRulesManager};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use safety_rules::{test_utils, Error, SafetyRulesManager};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    os::unix::fs::PermissionsExt,
+};
 
 const BINARY: &str = env!("CARGO_BIN_EXE_safety-rules");
 
 #[test]
 fn test_consensus_state() {
+    let (mut config, _signer) = test_config();
+    config.service = SafetyRulesService::Process(RemoteService {
+        server_address: server_address(),
+        socket_path: None,
+        tls: None,
+    });
+
+    let (mut child, _config_path) = spawn(&config);
+    let safety_rules_manager = SafetyRulesManager::new(&config);
+    let mut safety_rules = safety_rules_manager.client();
+    let consensus_state = safety_rules.consensus_state();
+
+    // Ensure the safety-rules subprocess is killed whether the test passes or fails.
+    // Not doing this would result in a zombie process.
+    child.kill().expect("could not kill safety-rules process");
+    child
+        .wait()
+        .expect("could not wait on safety-rules process");
+    consensus_state.unwrap();
+}
+
+#[test]
+fn test_vote_over_unix_socket() {
+    let (mut config, signer) = test_config();
+    let socket_dir = aptos_temppath::TempPath::new();
+    socket_dir.create_as_dir().unwrap();
+    let socket_path = socket_dir.path().join("safety-rules.sock");
+    config.service = SafetyRulesService::Process(RemoteService {
+        server_address: server_address(),
+        socket_path: Some(socket_path.clone()),
+        tls: None,
+    });
+
+    let (mut child, _config_path) = spawn(&config);
+    let safety_rules_manager = SafetyRulesManager::new(&config);
+    let mut safety_rules = safety_rules_manager.client();
+
+    let result = (|| {
+        let (proof, genesis_qc) = test_utils::make_genesis(&signer);
+        let round = genesis_qc.certified_block().round();
+        let a1 = test_utils::make_proposal_with_qc(round + 1, genesis_qc, &signer, None);
+        let a2 = test_utils::make_proposal_with_parent(vec![], round + 2, &a1, None, &signer, None);
+
+        safety_rules.initialize(&proof)?;
+        safety_rules.construct_and_sign_vote_two_chain(&a1, None)?;
+        let vote = safety_rules.construct_and_sign_vote_two_chain(&a2, None)?;
+        let consensus_state = safety_rules.consensus_state()?;
+        let permissions = std::fs::metadata(&socket_path).unwrap().permissions();
+        Ok::<_, Error>((vote, consensus_state, permissions))
+    })();
+
+    // Ensure the safety-rules subprocess is killed whether the test passes or fails.
+    child.kill().expect("could not kill safety-rules process");
+    child
+        .wait()
+        .expect("could not wait on safety-rules process");
+
+    let (vote, consensus_state, permissions) = result.unwrap();
+    assert_eq!(vote.author(), signer.author());
+    assert_eq!(consensus_state.last_voted_round(), 2);
+    assert_eq!(permissions.mode() & 0o777, 0o600);
+}
+
+fn test_config() -> (SafetyRulesConfig, ValidatorSigner) {
     let mut config = NodeConfig::random().consensus.safety_rules;
     let test_config = config.test.as_mut().unwrap();
     let private_key = test_config.consensus_key.as_ref().unwrap().private_key();
     let signer = ValidatorSigner::new(test_config.author, private_key);
     let waypoint = test_utils::validator_signers_to_waypoint(&[&signer]);
     test_config.waypoint = Some(waypoint);
+    (config, signer)
+}
 
+fn server_address() -> aptos_types::network_address::NetworkAddress {
     let server_port = utils::get_available_port();
-    let server_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port).into();
-    config.service = SafetyRulesService::Process(RemoteService { server_address });
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port).into()
+}
 
+/// Starts the safety-rules binary, the returned config path must be kept until it is running.
+fn spawn(config: &SafetyRulesConfig) -> (std::process::Child, aptos_temppath::TempPath) {
     let config_path = aptos_temppath::TempPath::new();
     config_path.create_as_file().unwrap();
     config.save_config(config_path.path()).unwrap();
@@ -34,17 +106,5 @@ fn test_consensus_state() {
         .stdin(std::process::Stdio::inherit())
         .stdout(std::process::Stdio::inherit())
         .stderr(std::process::Stdio::inherit());
-    let mut child = command.spawn().unwrap();
-
-    let safety_rules_manager = SafetyRulesManager::new(&config);
-    let mut safety_rules = safety_rules_manager.client();
-    let consensus_state = safety_rules.consensus_state();
-
-    // Ensure the safety-rules subprocess is killed whether the test passes or fails.
-    // Not doing this would result in a zombie process.
-    child.kill().expect("could not kill safety-rules process");
-    child
-        .wait()
-        .expect("could not wait on safety-rules process");
-    consensus_state.unwrap();
+    (command.spawn().unwrap(), config_path)
 }
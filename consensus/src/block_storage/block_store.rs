@@ -196,6 +196,7 @@ impl BlockStore {
             vec![],                   /* compute_status */
             vec![],                   /* txn_infos */
             vec![],                   /* reconfig_events */
+            0,                        /* total_gas_used */
         );
 
         let executed_root_block = ExecutedBlock::new(
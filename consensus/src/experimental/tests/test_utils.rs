@@ -97,6 +97,7 @@ pub fn prepare_executed_blocks_with_ledger_info(
         vec![],
         vec![],
         vec![],
+        0,
     );
 
     let li = LedgerInfo::new(
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::persistent_liveness_storage::PersistentLivenessStorage;
-use aptos_crypto::ed25519::Ed25519Signature;
+use aptos_crypto::{ed25519::Ed25519Signature, HashValue};
 use aptos_logger::prelude::info;
 use aptos_metrics::monitor;
 use aptos_types::{
@@ -16,7 +16,7 @@ use consensus_types::{
     vote::Vote,
     vote_proposal::MaybeSignedVoteProposal,
 };
-use safety_rules::{ConsensusState, Error, TSafetyRules};
+use safety_rules::{ConsensusState, Error, SignedSafetyStateAttestation, TSafetyRules};
 use std::sync::Arc;
 
 /// Wrap safety rules with counters.
@@ -143,12 +143,19 @@ impl TSafetyRules for MetricsSafetyRules {
             )
         })
     }
+
+    fn attest_safety_state(
+        &mut self,
+        nonce: HashValue,
+    ) -> Result<SignedSafetyStateAttestation, Error> {
+        monitor!("safety_rules", self.inner.attest_safety_state(nonce))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{metrics_safety_rules::MetricsSafetyRules, test_utils::EmptyStorage};
-    use aptos_crypto::ed25519::Ed25519Signature;
+    use aptos_crypto::{ed25519::Ed25519Signature, HashValue};
     use aptos_types::{
         epoch_change::EpochChangeProof,
         ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
@@ -161,7 +168,7 @@ mod tests {
         vote::Vote,
         vote_proposal::MaybeSignedVoteProposal,
     };
-    use safety_rules::{ConsensusState, Error, TSafetyRules};
+    use safety_rules::{ConsensusState, Error, SignedSafetyStateAttestation, TSafetyRules};
 
     pub struct MockSafetyRules {
         // number of initialize() calls
@@ -241,6 +248,13 @@ mod tests {
         ) -> Result<Ed25519Signature, Error> {
             unimplemented!()
         }
+
+        fn attest_safety_state(
+            &mut self,
+            _: HashValue,
+        ) -> Result<SignedSafetyStateAttestation, Error> {
+            unimplemented!()
+        }
     }
 
     #[test]
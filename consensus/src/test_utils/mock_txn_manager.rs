@@ -72,6 +72,7 @@ impl TxnManager for MockTransactionManager {
                 mock_transaction_status(block.payload().map_or(0, |txns| txns.len())),
                 compute_results.transaction_info_hashes().clone(),
                 compute_results.reconfig_events().to_vec(),
+                compute_results.total_gas_used(),
             );
             assert!(self
                 .mempool_proxy
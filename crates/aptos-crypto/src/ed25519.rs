@@ -37,6 +37,7 @@ use crate::{
 };
 use anyhow::{anyhow, Result};
 use aptos_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
+use bech32::{FromBase32, ToBase32, Variant};
 use core::convert::TryFrom;
 use mirai_annotations::*;
 use serde::Serialize;
@@ -122,6 +123,28 @@ impl Ed25519PublicKey {
         self.0.to_bytes()
     }
 
+    /// Encode an Ed25519PublicKey as a bech32 string with the human-readable part `hrp`, e.g.,
+    /// `aptos1...`. Unlike hex, typos are detected by the checksum of the encoding.
+    ///
+    /// Panics if `hrp` is not a valid bech32 human-readable part, i.e., 1 to 83 printable
+    /// ASCII characters of a single case.
+    pub fn to_bech32(&self, hrp: &str) -> String {
+        bech32::encode(hrp, self.to_bytes().to_base32(), Variant::Bech32)
+            .expect("Invalid bech32 human-readable part")
+    }
+
+    /// Decode an Ed25519PublicKey encoded by `to_bech32`, with any human-readable part.
+    pub fn from_bech32(encoded: &str) -> std::result::Result<Self, CryptoMaterialError> {
+        let (_hrp, data, variant) =
+            bech32::decode(encoded).map_err(|_| CryptoMaterialError::DeserializationError)?;
+        if variant != Variant::Bech32 {
+            return Err(CryptoMaterialError::DeserializationError);
+        }
+        let bytes =
+            Vec::<u8>::from_base32(&data).map_err(|_| CryptoMaterialError::DeserializationError)?;
+        Ed25519PublicKey::try_from(bytes.as_slice())
+    }
+
     /// Deserialize an Ed25519PublicKey without any validation checks apart from expected key size.
     pub(crate) fn from_bytes_unchecked(
         bytes: &[u8],
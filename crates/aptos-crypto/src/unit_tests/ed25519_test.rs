@@ -19,6 +19,7 @@ use core::{
 use ed25519_dalek::ed25519::signature::Verifier as _;
 
 use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
+use bech32::ToBase32;
 use digest::Digest;
 use proptest::{collection::vec, prelude::*};
 use serde::{Deserialize, Serialize};
@@ -438,6 +439,73 @@ proptest! {
     }
 }
 
+#[test]
+fn test_bech32_round_trip() {
+    let public_key = Ed25519PrivateKey::generate_for_testing().public_key();
+    for hrp in ["aptos", "aptosvalcons", "A"] {
+        let encoded = public_key.to_bech32(hrp);
+        assert!(encoded.starts_with(&format!("{}1", hrp.to_lowercase())));
+        assert_eq!(Ed25519PublicKey::from_bech32(&encoded).unwrap(), public_key);
+        assert_eq!(
+            Ed25519PublicKey::from_bech32(&encoded.to_uppercase()).unwrap(),
+            public_key
+        );
+    }
+}
+
+#[test]
+fn test_bech32_invalid() {
+    let public_key = Ed25519PrivateKey::generate_for_testing().public_key();
+    let encoded = public_key.to_bech32("aptos");
+    let data_part = encoded.strip_prefix("aptos").unwrap();
+
+    // Missing, out of range, or altered human-readable parts
+    for hrp in ["", "apt\u{7f}s", "apt s", "aptoz"] {
+        assert_eq!(
+            Ed25519PublicKey::from_bech32(&format!("{}{}", hrp, data_part)),
+            Err(CryptoMaterialError::DeserializationError)
+        );
+    }
+
+    // Malformed strings: a typo, mixed case, no separator, or a truncated checksum
+    let mut typo = encoded.clone().into_bytes();
+    let last = typo.len() - 1;
+    typo[last] = if typo[last] == b'q' { b'p' } else { b'q' };
+    for malformed in [
+        String::from_utf8(typo).unwrap(),
+        format!("APTOS{}", data_part),
+        encoded.replace('1', ""),
+        encoded[..encoded.len() - 1].to_string(),
+        String::new(),
+    ] {
+        assert_eq!(
+            Ed25519PublicKey::from_bech32(&malformed),
+            Err(CryptoMaterialError::DeserializationError)
+        );
+    }
+
+    // A valid encoding of a payload that is not a public key
+    let short = bech32::encode("aptos", [0u8; 16].to_base32(), bech32::Variant::Bech32).unwrap();
+    assert!(Ed25519PublicKey::from_bech32(&short).is_err());
+    let bech32m = bech32::encode(
+        "aptos",
+        public_key.to_bytes().to_base32(),
+        bech32::Variant::Bech32m,
+    )
+    .unwrap();
+    assert_eq!(
+        Ed25519PublicKey::from_bech32(&bech32m),
+        Err(CryptoMaterialError::DeserializationError)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Invalid bech32 human-readable part")]
+fn test_bech32_invalid_hrp_encoding() {
+    let public_key = Ed25519PrivateKey::generate_for_testing().public_key();
+    public_key.to_bech32("Aptos");
+}
+
 // Test against known small subgroup public keys.
 #[ignore]
 #[test]
@@ -2,9 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use criterion::{criterion_group, criterion_main, measurement::Measurement, BatchSize, Criterion};
+use executor::components::account_state_cache::ACCOUNT_STATE_CACHE_SIZE;
 use executor_benchmark::{
-    init_db_and_executor, transaction_executor::TransactionExecutor,
-    transaction_generator::TransactionGenerator,
+    init_db_and_executor, init_db_and_executor_with_account_state_cache,
+    transaction_executor::TransactionExecutor, transaction_generator::TransactionGenerator,
 };
 use executor_types::BlockExecutorTrait;
 use std::sync::Arc;
@@ -64,10 +65,37 @@ fn executor_benchmark<M: Measurement + 'static>(c: &mut Criterion<M>) {
     });
 }
 
+/// Compares blocks dominated by faucet transactions with and without the account state cache.
+fn faucet_benchmark<M: Measurement + 'static>(c: &mut Criterion<M>) {
+    let mut group = c.benchmark_group("bench_faucet");
+    for &(name, capacity) in &[("uncached", 0), ("cached", ACCOUNT_STATE_CACHE_SIZE)] {
+        let (config, genesis_key) = aptos_genesis_tool::test_config();
+        let (_db, executor) = init_db_and_executor_with_account_state_cache(&config, capacity);
+        let parent_block_id = executor.committed_block_id();
+        let executor = Arc::new(executor);
+
+        // Blocks are committed as they are executed, as the cache serves committed state
+        let mut generator = TransactionGenerator::new(genesis_key, NUM_ACCOUNTS);
+        let mut executor = TransactionExecutor::new(executor, parent_block_id, 0, None);
+        for txn_block in generator.gen_account_creations(SMALL_BLOCK_SIZE) {
+            executor.execute_block(txn_block);
+        }
+
+        group.bench_function(name, |bencher| {
+            bencher.iter_batched(
+                || generator.gen_faucet_transactions(1, SMALL_BLOCK_SIZE, 1),
+                |mut txn_block| executor.execute_block(txn_block.pop().unwrap()),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
 criterion_group!(
     name = txn_benches;
     config = Criterion::default().sample_size(10);
-    targets = executor_benchmark
+    targets = executor_benchmark, faucet_benchmark
 );
 
 criterion_main!(txn_benches);
@@ -15,7 +15,9 @@ use aptos_logger::prelude::*;
 
 use aptos_vm::AptosVM;
 use aptosdb::AptosDB;
-use executor::block_executor::BlockExecutor;
+use executor::{
+    block_executor::BlockExecutor, components::account_state_cache::ACCOUNT_STATE_CACHE_SIZE,
+};
 use executor_types::BlockExecutorTrait;
 use std::{
     fs,
@@ -25,6 +27,14 @@ use std::{
 use storage_interface::{DbReader, DbReaderWriter};
 
 pub fn init_db_and_executor(config: &NodeConfig) -> (Arc<dyn DbReader>, BlockExecutor<AptosVM>) {
+    init_db_and_executor_with_account_state_cache(config, ACCOUNT_STATE_CACHE_SIZE)
+}
+
+/// Like `init_db_and_executor`, with an account state cache of the given capacity.
+pub fn init_db_and_executor_with_account_state_cache(
+    config: &NodeConfig,
+    account_state_cache_capacity: usize,
+) -> (Arc<dyn DbReader>, BlockExecutor<AptosVM>) {
     let (db, dbrw) = DbReaderWriter::wrap(
         AptosDB::open(
             &config.storage.dir(),
@@ -35,7 +45,8 @@ pub fn init_db_and_executor(config: &NodeConfig) -> (Arc<dyn DbReader>, BlockExe
         .expect("DB should open."),
     );
 
-    let executor = BlockExecutor::new(dbrw);
+    let executor =
+        BlockExecutor::new_with_account_state_cache_capacity(dbrw, account_state_cache_capacity);
 
     (db, executor)
 }
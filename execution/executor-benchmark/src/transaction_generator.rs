@@ -17,7 +17,7 @@ use aptos_types::{
 };
 use chrono::Local;
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::{rngs::StdRng, SeedableRng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use std::{
     convert::TryFrom,
@@ -81,6 +81,9 @@ pub struct TransactionGenerator {
     /// Record the number of txns generated.
     version: Version,
 
+    /// The next sequence number of the root account, which creates and mints to accounts.
+    root_sequence_number: u64,
+
     /// For deterministic transaction generation.
     rng: StdRng,
 
@@ -114,6 +117,7 @@ impl TransactionGenerator {
             num_accounts,
             genesis_key,
             version: 0,
+            root_sequence_number: 0,
             rng,
             block_sender,
         }
@@ -175,6 +179,7 @@ impl TransactionGenerator {
             num_accounts,
             genesis_key,
             version: 2 * num_accounts as Version,
+            root_sequence_number: 2 * num_accounts as u64,
             rng,
             block_sender: Some(block_sender),
         }
@@ -238,6 +243,7 @@ impl TransactionGenerator {
                 transactions.push(txn);
             }
             self.version += transactions.len() as Version;
+            self.root_sequence_number += transactions.len() as u64;
             if let Some(sender) = &self.block_sender {
                 sender.send(transactions).unwrap();
             } else {
@@ -277,6 +283,7 @@ impl TransactionGenerator {
                 transactions.push(txn);
             }
             self.version += transactions.len() as Version;
+            self.root_sequence_number += transactions.len() as u64;
 
             if let Some(sender) = &self.block_sender {
                 sender.send(transactions).unwrap();
@@ -330,6 +337,44 @@ impl TransactionGenerator {
         txn_block
     }
 
+    /// Generates blocks of mints from the root account to random accounts, i.e., blocks dominated
+    /// by faucet transactions. The accounts must have been created.
+    pub fn gen_faucet_transactions(
+        &mut self,
+        amount: u64,
+        block_size: usize,
+        num_blocks: usize,
+    ) -> Vec<Vec<Transaction>> {
+        let root_address = aptos_root_address();
+        let mut txn_block = vec![];
+
+        for _i in 0..num_blocks {
+            let mut transactions = Vec::with_capacity(block_size);
+            for _j in 0..block_size {
+                let receiver_idx = self.rng.gen_range(0..self.accounts_cache.len());
+                let txn = create_transaction(
+                    &self.genesis_key,
+                    self.genesis_key.public_key(),
+                    Self::transaction_factory()
+                        .mint(self.accounts_cache[receiver_idx].address, amount)
+                        .sender(root_address)
+                        .sequence_number(self.root_sequence_number)
+                        .build(),
+                );
+                transactions.push(txn);
+                self.root_sequence_number += 1;
+            }
+            self.version += transactions.len() as Version;
+
+            if let Some(sender) = &self.block_sender {
+                sender.send(transactions).unwrap();
+            } else {
+                txn_block.push(transactions);
+            }
+        }
+        txn_block
+    }
+
     /// Verifies the sequence numbers in storage match what we have locally.
     pub fn verify_sequence_number(&self, db: &dyn DbReader) {
         println!(
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod integration_test_impl;
+pub mod mock_storage;
 
 use aptos_config::{config::NodeConfig, utils};
 use aptos_crypto::{
@@ -13,7 +14,7 @@ use aptos_types::{
     block_info::BlockInfo,
     block_metadata::BlockMetadata,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
-    test_helpers::transaction_test_helpers::get_test_signed_txn,
+    test_helpers::transaction_test_helpers::{self, get_test_signed_txn},
     transaction::{Transaction, TransactionPayload},
     validator_signer::ValidatorSigner,
     waypoint::Waypoint,
@@ -25,6 +26,7 @@ use executor_types::StateComputeResult;
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
     thread::JoinHandle,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use storage_interface::DbReaderWriter;
 use storage_service::start_storage_service_with_db;
@@ -71,11 +73,7 @@ pub fn gen_ledger_info_with_sigs(
         ),
         HashValue::zero(),
     );
-    let signatures = signer
-        .iter()
-        .map(|s| (s.author(), s.sign(&ledger_info)))
-        .collect();
-    LedgerInfoWithSignatures::new(ledger_info, signatures)
+    ValidatorSigner::sign_ledger_info_multi(&ledger_info, &signer)
 }
 
 pub fn extract_signer(config: &mut NodeConfig) -> ValidatorSigner {
@@ -111,3 +109,30 @@ pub fn get_test_signed_transaction(
         payload,
     ))
 }
+
+/// Like `get_test_signed_transaction`, but with the given gas unit price and max gas amount, e.g.,
+/// to exercise the gas checks of the VM.
+pub fn get_test_signed_transaction_with_gas(
+    sender: AccountAddress,
+    sequence_number: u64,
+    private_key: Ed25519PrivateKey,
+    public_key: Ed25519PublicKey,
+    payload: Option<TransactionPayload>,
+    gas_unit_price: u64,
+    max_gas_amount: u64,
+) -> Transaction {
+    let expiration_timestamp_secs = (SystemTime::now() + Duration::from_secs(10))
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the UNIX_EPOCH")
+        .as_secs();
+    Transaction::UserTransaction(transaction_test_helpers::get_test_signed_transaction(
+        sender,
+        sequence_number,
+        &private_key,
+        public_key,
+        payload,
+        expiration_timestamp_secs,
+        gas_unit_price,
+        Some(max_gas_amount),
+    ))
+}
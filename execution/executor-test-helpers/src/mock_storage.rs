@@ -0,0 +1,185 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides a storage mock that records calls and injects faults, so that the
+//! executor's error handling can be tested without a RocksDB backed store.
+
+use anyhow::{bail, Result};
+use aptos_crypto::HashValue;
+use aptos_infallible::Mutex;
+use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures,
+    proof::SparseMerkleProof,
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::{TransactionToCommit, Version},
+};
+use std::collections::HashMap;
+use storage_interface::{DbReader, DbReaderWriter, DbWriter, StartupInfo, TreeState};
+
+/// The storage operations intercepted by `MockStorage`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum StorageOp {
+    GetStartupInfo,
+    GetLatestLedgerInfo,
+    GetLatestVersion,
+    GetLatestCommitMetadata,
+    GetLatestTreeState,
+    GetStateValueWithProofByVersion,
+    GetAccumulatorRootHash,
+    SaveLedgerInfos,
+    SaveTransactions,
+}
+
+/// A DbReader and DbWriter that records every intercepted call and can be configured to fail
+/// an operation after a number of successful calls. Calls are forwarded to the wrapped store if
+/// there is one, otherwise reads observe an empty, freshly bootstrapped ledger and writes are
+/// dropped.
+#[derive(Default)]
+pub struct MockStorage {
+    inner: Option<DbReaderWriter>,
+    calls: Mutex<Vec<StorageOp>>,
+    // The number of calls of an operation that succeed before every later call fails
+    faults: Mutex<HashMap<StorageOp, usize>>,
+}
+
+impl MockStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forwards all calls that are not failed to the given store.
+    pub fn wrap(inner: DbReaderWriter) -> Self {
+        Self {
+            inner: Some(inner),
+            ..Self::default()
+        }
+    }
+
+    /// Fails every call of `op` after the first `successful_calls` calls.
+    pub fn fail_after(self, op: StorageOp, successful_calls: usize) -> Self {
+        self.faults.lock().insert(op, successful_calls);
+        self
+    }
+
+    /// Every intercepted call, in the order they were made, including failed ones.
+    pub fn calls(&self) -> Vec<StorageOp> {
+        self.calls.lock().clone()
+    }
+
+    pub fn call_count(&self, op: StorageOp) -> usize {
+        self.calls.lock().iter().filter(|call| **call == op).count()
+    }
+
+    fn record(&self, op: StorageOp) -> Result<()> {
+        self.calls.lock().push(op);
+        if let Some(remaining) = self.faults.lock().get_mut(&op) {
+            if *remaining == 0 {
+                bail!("Injected failure in MockStorage: {:?}", op);
+            }
+            *remaining -= 1;
+        }
+        Ok(())
+    }
+
+    fn reader(&self) -> Option<&dyn DbReader> {
+        self.inner.as_ref().map(|inner| inner.reader.as_ref())
+    }
+
+    fn writer(&self) -> Option<&dyn DbWriter> {
+        self.inner.as_ref().map(|inner| inner.writer.as_ref())
+    }
+}
+
+impl DbReader for MockStorage {
+    fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
+        self.record(StorageOp::GetStartupInfo)?;
+        match self.reader() {
+            Some(reader) => reader.get_startup_info(),
+            None => Ok(Some(StartupInfo::new_for_testing())),
+        }
+    }
+
+    fn get_latest_ledger_info_option(&self) -> Result<Option<LedgerInfoWithSignatures>> {
+        self.record(StorageOp::GetLatestLedgerInfo)?;
+        match self.reader() {
+            Some(reader) => reader.get_latest_ledger_info_option(),
+            None => Ok(Some(StartupInfo::new_for_testing().latest_ledger_info)),
+        }
+    }
+
+    fn get_latest_version(&self) -> Result<Version> {
+        self.record(StorageOp::GetLatestVersion)?;
+        match self.reader() {
+            Some(reader) => reader.get_latest_version(),
+            None => Ok(StartupInfo::new_for_testing()
+                .latest_ledger_info
+                .ledger_info()
+                .version()),
+        }
+    }
+
+    fn get_latest_commit_metadata(&self) -> Result<(Version, u64)> {
+        self.record(StorageOp::GetLatestCommitMetadata)?;
+        match self.reader() {
+            Some(reader) => reader.get_latest_commit_metadata(),
+            None => {
+                let ledger_info_with_sigs = StartupInfo::new_for_testing().latest_ledger_info;
+                let ledger_info = ledger_info_with_sigs.ledger_info();
+                Ok((ledger_info.version(), ledger_info.timestamp_usecs()))
+            }
+        }
+    }
+
+    fn get_latest_tree_state(&self) -> Result<TreeState> {
+        self.record(StorageOp::GetLatestTreeState)?;
+        match self.reader() {
+            Some(reader) => reader.get_latest_tree_state(),
+            None => Ok(StartupInfo::new_for_testing().committed_tree_state),
+        }
+    }
+
+    fn get_state_value_with_proof_by_version(
+        &self,
+        state_key: &StateKey,
+        version: Version,
+    ) -> Result<(Option<StateValue>, SparseMerkleProof<StateValue>)> {
+        self.record(StorageOp::GetStateValueWithProofByVersion)?;
+        match self.reader() {
+            Some(reader) => reader.get_state_value_with_proof_by_version(state_key, version),
+            None => bail!("MockStorage has no state at version {}", version),
+        }
+    }
+
+    fn get_accumulator_root_hash(&self, version: Version) -> Result<HashValue> {
+        self.record(StorageOp::GetAccumulatorRootHash)?;
+        match self.reader() {
+            Some(reader) => reader.get_accumulator_root_hash(version),
+            None => bail!("MockStorage has no accumulator at version {}", version),
+        }
+    }
+}
+
+impl DbWriter for MockStorage {
+    fn save_ledger_infos(&self, ledger_infos: &[LedgerInfoWithSignatures]) -> Result<()> {
+        self.record(StorageOp::SaveLedgerInfos)?;
+        match self.writer() {
+            Some(writer) => writer.save_ledger_infos(ledger_infos),
+            None => Ok(()),
+        }
+    }
+
+    fn save_transactions(
+        &self,
+        txns_to_commit: &[TransactionToCommit],
+        first_version: Version,
+        ledger_info_with_sigs: Option<&LedgerInfoWithSignatures>,
+    ) -> Result<()> {
+        self.record(StorageOp::SaveTransactions)?;
+        match self.writer() {
+            Some(writer) => {
+                writer.save_transactions(txns_to_commit, first_version, ledger_info_with_sigs)
+            }
+            None => Ok(()),
+        }
+    }
+}
@@ -12,6 +12,7 @@ use aptos_types::{
     ledger_info::LedgerInfoWithSignatures,
     proof::accumulator::InMemoryAccumulator,
     transaction::{Transaction, TransactionInfo, TransactionStatus, TransactionToCommit},
+    vm_status::KeptVMStatus,
 };
 use std::sync::Arc;
 
@@ -171,10 +172,14 @@ impl ExecutedChunk {
 
         let mut transaction_info_hashes = Vec::new();
         let mut reconfig_events = Vec::new();
+        let mut total_gas_used = 0;
 
         for (_, txn_data) in &self.to_commit {
             transaction_info_hashes.push(txn_data.txn_info_hash());
-            reconfig_events.extend(txn_data.reconfig_events.iter().cloned())
+            reconfig_events.extend(txn_data.reconfig_events.iter().cloned());
+            if txn_data.status() == &TransactionStatus::Keep(KeptVMStatus::Executed) {
+                total_gas_used += txn_data.gas_used();
+            }
         }
 
         StateComputeResult::new(
@@ -187,6 +192,7 @@ impl ExecutedChunk {
             self.status.clone(),
             transaction_info_hashes,
             reconfig_events,
+            total_gas_used,
         )
     }
 }
@@ -99,6 +99,11 @@ pub trait BlockExecutorTrait: Send + Sync {
         parent_block_id: HashValue,
     ) -> Result<StateComputeResult, Error>;
 
+    /// Returns the speculative trees of a block that has been executed but not necessarily
+    /// committed. Their accumulator root hash (`state_id`) and `version` allow light clients to
+    /// request a consistency proof.
+    fn get_executed_trees(&self, block_id: HashValue) -> Result<ExecutedTrees, Error>;
+
     /// Saves eligible blocks to persistent storage.
     /// If we have multiple blocks and not all of them have signatures, we may send them to storage
     /// in a few batches. For example, if we have
@@ -113,6 +118,22 @@ pub trait BlockExecutorTrait: Send + Sync {
         block_ids: Vec<HashValue>,
         ledger_info_with_sigs: LedgerInfoWithSignatures,
     ) -> Result<(), Error>;
+
+    /// Reverts the in-memory state of the executor to the committed block `block_id`, dropping the
+    /// speculative results of the blocks executed on top of it, e.g., to execute another fork
+    /// after a reorganization. The persisted state is not modified, so the block must be the
+    /// latest committed block. Execution then continues from it.
+    fn revert_to(&self, block_id: HashValue) -> Result<(), Error>;
+
+    /// Executes a block and commits it, without executing or committing other blocks in
+    /// between. The parent must be the latest committed block, and the ledger info must commit
+    /// the block at the state resulting from its execution, otherwise nothing is committed.
+    fn execute_and_commit(
+        &self,
+        block: (HashValue, Vec<Transaction>),
+        parent_block_id: HashValue,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<(), Error>;
 }
 
 pub trait TransactionReplayer: Send {
@@ -165,6 +186,9 @@ pub struct StateComputeResult {
     signature: Option<Ed25519Signature>,
 
     reconfig_events: Vec<ContractEvent>,
+
+    /// The gas used by all successfully executed transactions.
+    total_gas_used: u64,
 }
 
 impl StateComputeResult {
@@ -178,6 +202,7 @@ impl StateComputeResult {
         compute_status: Vec<TransactionStatus>,
         transaction_info_hashes: Vec<HashValue>,
         reconfig_events: Vec<ContractEvent>,
+        total_gas_used: u64,
     ) -> Self {
         Self {
             root_hash,
@@ -189,6 +214,7 @@ impl StateComputeResult {
             compute_status,
             transaction_info_hashes,
             reconfig_events,
+            total_gas_used,
             signature: None,
         }
     }
@@ -207,6 +233,7 @@ impl StateComputeResult {
             compute_status: vec![],
             transaction_info_hashes: vec![],
             reconfig_events: vec![],
+            total_gas_used: 0,
             signature: None,
         }
     }
@@ -275,6 +302,10 @@ impl StateComputeResult {
         &self.reconfig_events
     }
 
+    pub fn total_gas_used(&self) -> u64 {
+        self.total_gas_used
+    }
+
     pub fn signature(&self) -> &Option<Ed25519Signature> {
         &self.signature
     }
@@ -6,20 +6,28 @@
 use crate::logging::{LogEntry, LogSchema};
 use anyhow::Result;
 use aptos_crypto::HashValue;
+use aptos_infallible::RwLock;
 use aptos_logger::prelude::*;
 use aptos_state_view::StateViewId;
-use aptos_types::{ledger_info::LedgerInfoWithSignatures, transaction::Transaction};
+use aptos_types::{
+    ledger_info::LedgerInfoWithSignatures, state_store::state_key::StateKey,
+    transaction::Transaction, vm_status::KeptVMStatus,
+};
 use aptos_vm::VMExecutor;
-use executor_types::{BlockExecutorTrait, Error, StateComputeResult};
+use executor_types::{BlockExecutorTrait, Error, ExecutedTrees, StateComputeResult};
 use fail::fail_point;
-use std::marker::PhantomData;
+use std::{marker::PhantomData, sync::Arc};
 
 use crate::{
-    components::{block_tree::BlockTree, chunk_output::ChunkOutput},
+    components::{
+        account_state_cache::{AccountStateCache, CachedStateReader, ACCOUNT_STATE_CACHE_SIZE},
+        block_tree::BlockTree,
+        chunk_output::ChunkOutput,
+    },
     metrics::{
-        APTOS_EXECUTOR_COMMIT_BLOCKS_SECONDS, APTOS_EXECUTOR_EXECUTE_BLOCK_SECONDS,
-        APTOS_EXECUTOR_SAVE_TRANSACTIONS_SECONDS, APTOS_EXECUTOR_TRANSACTIONS_SAVED,
-        APTOS_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS,
+        APTOS_EXECUTOR_BLOCK_GAS_USED, APTOS_EXECUTOR_COMMIT_BLOCKS_SECONDS,
+        APTOS_EXECUTOR_EXECUTE_BLOCK_SECONDS, APTOS_EXECUTOR_SAVE_TRANSACTIONS_SECONDS,
+        APTOS_EXECUTOR_TRANSACTIONS_SAVED, APTOS_EXECUTOR_VM_EXECUTE_BLOCK_SECONDS,
     },
 };
 use storage_interface::DbReaderWriter;
@@ -27,6 +35,10 @@ use storage_interface::DbReaderWriter;
 pub struct BlockExecutor<V> {
     pub db: DbReaderWriter,
     block_tree: BlockTree,
+    account_state_cache: Arc<AccountStateCache>,
+    // Executions, commits and resets share the lock, which `execute_and_commit` holds
+    // exclusively, so that other calls are not interleaved with it
+    execution_lock: RwLock<()>,
     phantom: PhantomData<V>,
 }
 
@@ -35,28 +47,23 @@ where
     V: VMExecutor,
 {
     pub fn new(db: DbReaderWriter) -> Self {
+        Self::new_with_account_state_cache_capacity(db, ACCOUNT_STATE_CACHE_SIZE)
+    }
+
+    /// Creates an executor whose account state cache holds at most `capacity` accounts, or
+    /// that caches nothing if it is 0.
+    pub fn new_with_account_state_cache_capacity(db: DbReaderWriter, capacity: usize) -> Self {
         let block_tree = BlockTree::new(&db.reader).expect("Block tree failed to init.");
         Self {
             db,
             block_tree,
+            account_state_cache: Arc::new(AccountStateCache::new(capacity)),
+            execution_lock: RwLock::new(()),
             phantom: PhantomData,
         }
     }
-}
-
-impl<V> BlockExecutorTrait for BlockExecutor<V>
-where
-    V: VMExecutor,
-{
-    fn committed_block_id(&self) -> HashValue {
-        self.block_tree.root_block().id
-    }
-
-    fn reset(&self) -> Result<(), Error> {
-        Ok(self.block_tree.reset(&self.db.reader)?)
-    }
 
-    fn execute_block(
+    fn execute_block_impl(
         &self,
         block: (HashValue, Vec<Transaction>),
         parent_block_id: HashValue,
@@ -95,7 +102,10 @@ where
             let state_view = parent_view.state_view(
                 &committed_block.output.result_view,
                 StateViewId::BlockExecution { block_id },
-                self.db.reader.clone(),
+                Arc::new(CachedStateReader::new(
+                    self.db.reader.clone(),
+                    self.account_state_cache.clone(),
+                )),
             );
 
             let chunk_output = {
@@ -119,7 +129,7 @@ where
         Ok(block.output.as_state_compute_result(parent_accumulator))
     }
 
-    fn commit_blocks(
+    fn commit_blocks_impl(
         &self,
         block_ids: Vec<HashValue>,
         ledger_info_with_sigs: LedgerInfoWithSignatures,
@@ -174,6 +184,22 @@ where
                 first_version,
                 Some(&ledger_info_with_sigs),
             )?;
+            APTOS_EXECUTOR_BLOCK_GAS_USED.inc_by(
+                txns_to_commit
+                    .iter()
+                    .filter(|txn| txn.status() == &KeptVMStatus::Executed)
+                    .map(|txn| txn.gas_used())
+                    .sum(),
+            );
+            self.account_state_cache.invalidate(
+                txns_to_commit
+                    .iter()
+                    .flat_map(|txn| txn.state_updates().keys())
+                    .filter_map(|state_key| match state_key {
+                        StateKey::AccountAddressKey(address) => Some(address),
+                        _ => None,
+                    }),
+            );
             self.block_tree
                 .prune(ledger_info_with_sigs.ledger_info())
                 .expect("Failure pruning block tree.");
@@ -181,3 +207,84 @@ where
         Ok(())
     }
 }
+
+impl<V> BlockExecutorTrait for BlockExecutor<V>
+where
+    V: VMExecutor,
+{
+    fn committed_block_id(&self) -> HashValue {
+        self.block_tree.root_block().id
+    }
+
+    fn reset(&self) -> Result<(), Error> {
+        let _guard = self.execution_lock.read();
+        self.account_state_cache.clear();
+        Ok(self.block_tree.reset(&self.db.reader)?)
+    }
+
+    fn execute_block(
+        &self,
+        block: (HashValue, Vec<Transaction>),
+        parent_block_id: HashValue,
+    ) -> Result<StateComputeResult, Error> {
+        let _guard = self.execution_lock.read();
+        self.execute_block_impl(block, parent_block_id)
+    }
+
+    fn get_executed_trees(&self, block_id: HashValue) -> Result<ExecutedTrees, Error> {
+        let block = self
+            .block_tree
+            .get_blocks_opt(&[block_id])?
+            .pop()
+            .expect("Must exist.")
+            .ok_or(Error::BlockNotFound(block_id))?;
+        Ok(block.output.result_view.clone())
+    }
+
+    fn commit_blocks(
+        &self,
+        block_ids: Vec<HashValue>,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        let _guard = self.execution_lock.read();
+        self.commit_blocks_impl(block_ids, ledger_info_with_sigs)
+    }
+
+    fn revert_to(&self, block_id: HashValue) -> Result<(), Error> {
+        let _guard = self.execution_lock.write();
+        let committed_block_id = self.committed_block_id();
+        if block_id != committed_block_id {
+            return Err(Error::InternalError {
+                error: format!(
+                    "Cannot revert to block {}, which is not the latest committed block {}",
+                    block_id, committed_block_id
+                ),
+            });
+        }
+        self.block_tree.revert_to_root();
+        Ok(())
+    }
+
+    fn execute_and_commit(
+        &self,
+        block: (HashValue, Vec<Transaction>),
+        parent_block_id: HashValue,
+        ledger_info_with_sigs: LedgerInfoWithSignatures,
+    ) -> Result<(), Error> {
+        let _guard = self.execution_lock.write();
+        let block_id = block.0;
+        let output = self.execute_block_impl(block, parent_block_id)?;
+        let ledger_info = ledger_info_with_sigs.ledger_info();
+        if output.root_hash() != ledger_info.transaction_accumulator_hash() {
+            return Err(Error::InternalError {
+                error: format!(
+                    "Ledger info does not match the execution of block {}: {} != {}",
+                    block_id,
+                    output.root_hash(),
+                    ledger_info.transaction_accumulator_hash()
+                ),
+            });
+        }
+        self.commit_blocks_impl(vec![block_id], ledger_info_with_sigs)
+    }
+}
@@ -0,0 +1,205 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::metrics::APTOS_EXECUTOR_ACCOUNT_STATE_CACHE;
+use anyhow::Result;
+use aptos_infallible::Mutex;
+use aptos_types::{
+    account_address::AccountAddress,
+    proof::SparseMerkleProof,
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::Version,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+use storage_interface::DbReader;
+
+/// The maximum number of accounts held in the `AccountStateCache`.
+pub const ACCOUNT_STATE_CACHE_SIZE: usize = 1024;
+
+type StateValueWithProof = (Option<StateValue>, SparseMerkleProof<StateValue>);
+
+struct CachedAccountState {
+    version: Version,
+    value: StateValueWithProof,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    accounts: HashMap<AccountAddress, CachedAccountState>,
+    // Least recently used first
+    lru: BTreeMap<u64, AccountAddress>,
+    tick: u64,
+}
+
+/// Caches account states read from storage, together with their proofs, across blocks. The
+/// per-block state view already caches reads within a block; this saves the storage reads of
+/// hot accounts (e.g., the faucet) for every block executed on top of the same persisted state.
+/// Entries are only served for the version they were read at, and are dropped when a write to
+/// the account is committed. The least recently used account is evicted when full.
+pub struct AccountStateCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+impl AccountStateCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub fn get(&self, address: &AccountAddress, version: Version) -> Option<StateValueWithProof> {
+        let mut inner = self.inner.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let Inner { accounts, lru, .. } = &mut *inner;
+        match accounts.get_mut(address) {
+            Some(cached) if cached.version == version => {
+                lru.remove(&cached.last_used);
+                lru.insert(tick, *address);
+                cached.last_used = tick;
+                Some(cached.value.clone())
+            }
+            _ => None,
+        }
+    }
+
+    pub fn insert(&self, address: AccountAddress, version: Version, value: StateValueWithProof) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let Inner { accounts, lru, .. } = &mut *inner;
+        if let Some(previous) = accounts.insert(
+            address,
+            CachedAccountState {
+                version,
+                value,
+                last_used: tick,
+            },
+        ) {
+            lru.remove(&previous.last_used);
+        }
+        lru.insert(tick, address);
+
+        while accounts.len() > self.capacity {
+            let (&oldest, _) = lru.iter().next().expect("Must exist.");
+            let evicted = lru.remove(&oldest).expect("Must exist.");
+            accounts.remove(&evicted);
+        }
+    }
+
+    /// Drops the entries of accounts that have been written to.
+    pub fn invalidate<'a>(&self, addresses: impl IntoIterator<Item = &'a AccountAddress>) {
+        let mut inner = self.inner.lock();
+        let Inner { accounts, lru, .. } = &mut *inner;
+        for address in addresses {
+            if let Some(removed) = accounts.remove(address) {
+                lru.remove(&removed.last_used);
+            }
+        }
+    }
+
+    pub fn clear(&self) {
+        *self.inner.lock() = Inner::default();
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Serves the account state reads of a state view from an `AccountStateCache`. This is only
+/// meant to back state views, so only the reads they issue are supported.
+pub struct CachedStateReader {
+    reader: Arc<dyn DbReader>,
+    cache: Arc<AccountStateCache>,
+}
+
+impl CachedStateReader {
+    pub fn new(reader: Arc<dyn DbReader>, cache: Arc<AccountStateCache>) -> Self {
+        Self { reader, cache }
+    }
+}
+
+impl DbReader for CachedStateReader {
+    fn get_state_value_with_proof_by_version(
+        &self,
+        state_key: &StateKey,
+        version: Version,
+    ) -> Result<StateValueWithProof> {
+        let address = match state_key {
+            StateKey::AccountAddressKey(address) => address,
+            _ => {
+                return self
+                    .reader
+                    .get_state_value_with_proof_by_version(state_key, version)
+            }
+        };
+
+        if let Some(value) = self.cache.get(address, version) {
+            APTOS_EXECUTOR_ACCOUNT_STATE_CACHE
+                .with_label_values(&["hit"])
+                .inc();
+            return Ok(value);
+        }
+        APTOS_EXECUTOR_ACCOUNT_STATE_CACHE
+            .with_label_values(&["miss"])
+            .inc();
+        let value = self
+            .reader
+            .get_state_value_with_proof_by_version(state_key, version)?;
+        self.cache.insert(*address, version, value.clone());
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value() -> StateValueWithProof {
+        (None, SparseMerkleProof::new(None, vec![]))
+    }
+
+    #[test]
+    fn test_version_and_invalidation() {
+        let cache = AccountStateCache::new(ACCOUNT_STATE_CACHE_SIZE);
+        let address = AccountAddress::random();
+        cache.insert(address, 1, value());
+
+        assert!(cache.get(&address, 1).is_some());
+        assert!(cache.get(&address, 2).is_none());
+        cache.invalidate(&[address]);
+        assert!(cache.get(&address, 1).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let cache = AccountStateCache::new(2);
+        let addresses: Vec<_> = (0..3).map(|_| AccountAddress::random()).collect();
+        cache.insert(addresses[0], 1, value());
+        cache.insert(addresses[1], 1, value());
+
+        // Touch the first account so that the second one is the least recently used
+        assert!(cache.get(&addresses[0], 1).is_some());
+        cache.insert(addresses[2], 1, value());
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&addresses[0], 1).is_some());
+        assert!(cache.get(&addresses[1], 1).is_none());
+        assert!(cache.get(&addresses[2], 1).is_some());
+    }
+}
@@ -191,6 +191,17 @@ impl BlockTree {
         Ok(())
     }
 
+    /// Drops the descendants of the root, i.e., the speculative results of the blocks executed on
+    /// top of the committed block.
+    pub fn revert_to_root(&self) {
+        let root = self.root_block();
+        info!(
+            LogSchema::new(LogEntry::SpeculationCache).root_block_id(root.id),
+            "Reverted to the root block"
+        );
+        root.children.lock().clear();
+    }
+
     pub fn get_block(&self, id: HashValue) -> Result<Arc<Block>> {
         Ok(self.get_blocks(&[id])?.pop().expect("Must exist."))
     }
@@ -121,6 +121,21 @@ fn test_reconfig_id_update() {
     );
 }
 
+#[test]
+fn test_revert_to_root() {
+    let block_tree = create_tree();
+    block_tree.revert_to_root();
+    assert_eq!(block_tree.size(), 1);
+    assert_eq!(block_tree.root_block().id, *PRE_GENESIS_BLOCK_ID);
+
+    // Blocks can be added again, including on another branch
+    block_tree
+        .add_block(*PRE_GENESIS_BLOCK_ID, id(1), empty_chunk())
+        .unwrap();
+    block_tree.add_block(id(1), id(3), empty_chunk()).unwrap();
+    assert_eq!(block_tree.size(), 3);
+}
+
 #[test]
 fn test_add_duplicate_block() {
     let block_tree = create_tree();
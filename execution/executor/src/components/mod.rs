@@ -3,6 +3,7 @@
 
 #![forbid(unsafe_code)]
 
+pub mod account_state_cache;
 pub mod apply_chunk_output;
 pub mod block_tree;
 pub mod chunk_commit_queue;
@@ -1,7 +1,10 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use aptos_metrics::{register_histogram, register_int_counter, Histogram, IntCounter};
+use aptos_metrics::{
+    register_histogram, register_int_counter, register_int_counter_vec, Histogram, IntCounter,
+    IntCounterVec,
+};
 use once_cell::sync::Lazy;
 
 pub static APTOS_EXECUTOR_EXECUTE_CHUNK_SECONDS: Lazy<Histogram> = Lazy::new(|| {
@@ -88,6 +91,16 @@ pub static APTOS_EXECUTOR_SAVE_TRANSACTIONS_SECONDS: Lazy<Histogram> = Lazy::new
     .unwrap()
 });
 
+pub static APTOS_EXECUTOR_BLOCK_GAS_USED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "aptos_executor_block_gas_used_total",
+        // metric description
+        "Cumulative gas used by the successful transactions of committed blocks"
+    )
+    .unwrap()
+});
+
 pub static APTOS_EXECUTOR_TRANSACTIONS_SAVED: Lazy<Histogram> = Lazy::new(|| {
     register_histogram!(
         // metric name
@@ -97,3 +110,15 @@ pub static APTOS_EXECUTOR_TRANSACTIONS_SAVED: Lazy<Histogram> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static APTOS_EXECUTOR_ACCOUNT_STATE_CACHE: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "aptos_executor_account_state_cache",
+        // metric description
+        "Hits and misses of the account state cache in Aptos executor",
+        // metric labels
+        &["result"]
+    )
+    .unwrap()
+});
@@ -172,6 +172,30 @@ fn test_executor_one_block() {
     executor.commit_blocks(vec![block_id], ledger_info).unwrap();
 }
 
+#[test]
+fn test_executor_get_executed_trees() {
+    let executor = TestExecutor::new();
+    let parent_block_id = executor.committed_block_id();
+    let block_id = gen_block_id(1);
+
+    let txns = (0..10)
+        .map(|i| encode_mint_transaction(gen_address(i), 100))
+        .collect::<Vec<_>>();
+    let output = executor
+        .execute_block((block_id, txns), parent_block_id)
+        .unwrap();
+
+    // The block is available before being committed
+    let executed_trees = executor.get_executed_trees(block_id).unwrap();
+    assert_eq!(executed_trees.version(), Some(output.version()));
+    assert_eq!(executed_trees.state_id(), output.root_hash());
+
+    assert_eq!(
+        executor.get_executed_trees(gen_block_id(2)).unwrap_err(),
+        executor_types::Error::BlockNotFound(gen_block_id(2))
+    );
+}
+
 #[test]
 fn test_executor_multiple_blocks() {
     let executor = TestExecutor::new();
@@ -235,6 +259,86 @@ fn test_executor_commit_twice() {
         .unwrap();
 }
 
+#[test]
+fn test_executor_execute_and_commit() {
+    let block_id = gen_block_id(1);
+    let txns = (0..5)
+        .map(|i| encode_mint_transaction(gen_address(i), 100))
+        .collect::<Vec<_>>();
+
+    // The root hash of the block is known from an execution on another executor
+    let root_hash = {
+        let executor = TestExecutor::new();
+        let parent_block_id = executor.committed_block_id();
+        executor
+            .execute_block((block_id, txns.clone()), parent_block_id)
+            .unwrap()
+            .root_hash()
+    };
+
+    let executor = TestExecutor::new();
+    let parent_block_id = executor.committed_block_id();
+    let ledger_info = gen_ledger_info(5, HashValue::zero(), block_id, 1);
+    assert!(matches!(
+        executor.execute_and_commit((block_id, txns.clone()), parent_block_id, ledger_info),
+        Err(executor_types::Error::InternalError { .. })
+    ));
+    assert_eq!(executor.committed_block_id(), parent_block_id);
+
+    let ledger_info = gen_ledger_info(5, root_hash, block_id, 1);
+    executor
+        .execute_and_commit((block_id, txns), parent_block_id, ledger_info)
+        .unwrap();
+    assert_eq!(executor.committed_block_id(), block_id);
+    assert_eq!(executor.db.reader.get_latest_version().unwrap(), 5);
+}
+
+#[test]
+fn test_executor_revert_to() {
+    let executor = TestExecutor::new();
+    let committed_block_id = executor.committed_block_id();
+    let mint_txns = |range: std::ops::Range<u64>| {
+        range
+            .map(|i| encode_mint_transaction(gen_address(i), 100))
+            .collect::<Vec<_>>()
+    };
+
+    // Executes a fork of two blocks, then reverts it
+    let block1_id = gen_block_id(1);
+    let block2_id = gen_block_id(2);
+    executor
+        .execute_block((block1_id, mint_txns(0..5)), committed_block_id)
+        .unwrap();
+    executor
+        .execute_block((block2_id, mint_txns(5..10)), block1_id)
+        .unwrap();
+    assert!(matches!(
+        executor.revert_to(block1_id),
+        Err(executor_types::Error::InternalError { .. })
+    ));
+    executor.revert_to(committed_block_id).unwrap();
+    assert_eq!(executor.committed_block_id(), committed_block_id);
+    for block_id in [block1_id, block2_id] {
+        assert!(matches!(
+            executor.get_executed_trees(block_id),
+            Err(executor_types::Error::BlockNotFound(id)) if id == block_id
+        ));
+    }
+
+    // Execution continues from the committed block, on another branch
+    let block3_id = gen_block_id(3);
+    let output = executor
+        .execute_block((block3_id, mint_txns(10..13)), committed_block_id)
+        .unwrap();
+    assert_eq!(output.version(), 3);
+    let ledger_info = gen_ledger_info(3, output.root_hash(), block3_id, 1);
+    executor
+        .commit_blocks(vec![block3_id], ledger_info)
+        .unwrap();
+    assert_eq!(executor.committed_block_id(), block3_id);
+    assert_eq!(executor.db.reader.get_latest_version().unwrap(), 3);
+}
+
 #[test]
 fn test_executor_execute_same_block_multiple_times() {
     let executor = TestExecutor::new();
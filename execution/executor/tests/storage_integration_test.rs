@@ -1,27 +1,39 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use aptos_crypto::PrivateKey;
+use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
 use aptos_transaction_builder::aptos_stdlib::{
-    encode_mint_script_function, encode_set_version_script_function,
+    encode_create_account_script_function, encode_mint_script_function,
+    encode_set_version_script_function,
 };
 use aptos_types::{
-    account_config::aptos_root_address,
+    account_config::{aptos_root_address, CORE_CODE_ADDRESS},
     account_state::AccountState,
     block_metadata::BlockMetadata,
     state_store::state_key::StateKey,
-    transaction::{Transaction, WriteSetPayload},
+    transaction::{
+        authenticator::AuthenticationKey, Transaction, TransactionStatus, WriteSetPayload,
+    },
     trusted_state::TrustedState,
     validator_signer::ValidatorSigner,
+    vm_status::{AbortLocation, KeptVMStatus, StatusCode},
 };
+use aptos_vm::AptosVM;
+use executor::block_executor::BlockExecutor;
 use executor_test_helpers::{
     gen_block_id, gen_ledger_info_with_sigs, get_test_signed_transaction,
+    get_test_signed_transaction_with_gas,
     integration_test_impl::{
-        create_db_and_executor, test_execution_with_storage_impl, verify_committed_txn_status,
+        create_db_and_executor, test_execution_with_storage_impl, verify_account_balance,
+        verify_committed_txn_status,
     },
+    mock_storage::{MockStorage, StorageOp},
 };
 use executor_types::BlockExecutorTrait;
-use std::convert::TryFrom;
+use move_core_types::{ident_str, language_storage::ModuleId};
+use rand::SeedableRng;
+use std::{convert::TryFrom, sync::Arc};
+use storage_interface::DbReaderWriter;
 
 #[test]
 fn test_genesis() {
@@ -41,7 +53,8 @@ fn test_genesis() {
         .verify_and_ratchet(&state_proof, Some(&initial_accumulator))
         .unwrap();
     let li = state_proof.latest_ledger_info();
-    assert_eq!(li.version(), 0);
+    li.verify_genesis().unwrap();
+    assert!(li.matches_waypoint(&waypoint));
 
     let aptos_root_account = db
         .reader
@@ -68,8 +81,8 @@ fn test_reconfiguration() {
     let validator_account = signer.author();
 
     // test the current keys in the validator's account equals to the key in the validator set
-    let state_proof = db.reader.get_state_proof(0).unwrap();
-    let current_version = state_proof.latest_ledger_info().version();
+    let current_version = db.reader.get_latest_version().unwrap();
+    let epoch_state = db.reader.get_latest_epoch_state().unwrap();
     let validator_account_state_with_proof = db
         .reader
         .get_state_value_with_proof(
@@ -78,30 +91,16 @@ fn test_reconfiguration() {
             current_version,
         )
         .unwrap();
-    let aptos_root_account_state_with_proof = db
-        .reader
-        .get_state_value_with_proof(
-            StateKey::AccountAddressKey(aptos_root_address()),
-            current_version,
-            current_version,
-        )
-        .unwrap();
     assert_eq!(
-        AccountState::try_from(&aptos_root_account_state_with_proof.value.unwrap())
-            .unwrap()
-            .get_validator_set()
-            .unwrap()
-            .unwrap()
-            .payload()
-            .next()
-            .unwrap()
-            .consensus_public_key(),
-        &AccountState::try_from(&validator_account_state_with_proof.value.unwrap())
-            .unwrap()
-            .get_validator_config_resource()
-            .unwrap()
-            .unwrap()
-            .consensus_public_key
+        epoch_state.verifier.get_public_key(&validator_account),
+        Some(
+            AccountState::try_from(&validator_account_state_with_proof.value.unwrap())
+                .unwrap()
+                .get_validator_config_resource()
+                .unwrap()
+                .unwrap()
+                .consensus_public_key
+        )
     );
 
     // txn1 = give the validator some money so they can send a tx
@@ -148,6 +147,8 @@ fn test_reconfiguration() {
 
     let state_proof = db.reader.get_state_proof(0).unwrap();
     let current_version = state_proof.latest_ledger_info().version();
+    let next_epoch_state = db.reader.get_latest_epoch_state().unwrap();
+    assert_eq!(next_epoch_state.epoch, epoch_state.epoch + 1);
 
     let t3 = db
         .reader
@@ -155,6 +156,140 @@ fn test_reconfiguration() {
         .unwrap();
     verify_committed_txn_status(t3.as_ref(), &txn_block[2]).unwrap();
 
+    // The gas usage matches the committed transaction infos, and only user transactions pay gas
+    let t3 = t3.unwrap();
+    assert_eq!(
+        db.reader.get_gas_usage_by_transaction(t3.version).unwrap(),
+        t3.proof.transaction_info().gas_used()
+    );
+    assert!(db.reader.get_gas_usage_by_transaction(1).unwrap() > 0);
+    assert_eq!(db.reader.get_gas_usage_by_transaction(2).unwrap(), 0);
+    assert!(db
+        .reader
+        .get_gas_usage_by_transaction(current_version + 1)
+        .is_err());
+
+    let aptos_root_account_state_with_proof = db
+        .reader
+        .get_state_value_with_proof(
+            StateKey::AccountAddressKey(aptos_root_address()),
+            current_version,
+            current_version,
+        )
+        .unwrap();
+    assert_eq!(
+        AccountState::try_from(&aptos_root_account_state_with_proof.value.unwrap())
+            .unwrap()
+            .get_version()
+            .unwrap()
+            .unwrap()
+            .major,
+        42
+    );
+
+    // txn4 = give the validator some more money, which does not end the epoch
+    let txn4 = get_test_signed_transaction(
+        aptos_root_address(),
+        /* sequence_number = */ 2,
+        genesis_key.clone(),
+        genesis_key.public_key(),
+        Some(encode_mint_script_function(validator_account, 1_000)),
+    );
+    let block2_id = gen_block_id(2);
+    let vm_output = executor
+        .execute_block((block2_id, vec![txn4]), block_id)
+        .unwrap();
+    assert!(!vm_output.has_reconfiguration());
+    let ledger_info_with_sigs = gen_ledger_info_with_sigs(2, &vm_output, block2_id, vec![&signer]);
+    executor
+        .commit_blocks(vec![block2_id], ledger_info_with_sigs)
+        .unwrap();
+
+    // Mid-epoch, the epoch state is the one carried by the ledger info that ended the last epoch
+    let latest_ledger_info = db.reader.get_latest_ledger_info().unwrap();
+    assert!(latest_ledger_info
+        .ledger_info()
+        .next_epoch_state()
+        .is_none());
+    assert_eq!(
+        db.reader.get_latest_epoch_state().unwrap(),
+        next_epoch_state
+    );
+}
+
+#[test]
+fn test_set_version_downgrade() {
+    // Once the aptos version is set, setting a lower version must abort in the VM and leave the
+    // version unchanged
+
+    let path = aptos_temppath::TempPath::new();
+    path.create_as_dir().unwrap();
+    let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+    let genesis_key = &vm_genesis::GENESIS_KEYPAIR.0;
+    let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+    let (_, db, executor, _waypoint) = create_db_and_executor(path.path(), &genesis_txn);
+    let parent_block_id = executor.committed_block_id();
+    let signer = ValidatorSigner::new(validators[0].data.address, validators[0].key.clone());
+
+    // block1 = bump the timer and set the aptos version, which ends the epoch
+    let txn1 = Transaction::BlockMetadata(BlockMetadata::new(
+        gen_block_id(1),
+        1,
+        300000001,
+        vec![],
+        signer.author(),
+    ));
+    let txn2 = get_test_signed_transaction(
+        aptos_root_address(),
+        /* sequence_number = */ 0,
+        genesis_key.clone(),
+        genesis_key.public_key(),
+        Some(encode_set_version_script_function(42)),
+    );
+    let block1_id = gen_block_id(1);
+    let output = executor
+        .execute_block((block1_id, vec![txn1, txn2]), parent_block_id)
+        .unwrap();
+    assert!(output.has_reconfiguration());
+    let ledger_info_with_sigs = gen_ledger_info_with_sigs(1, &output, block1_id, vec![&signer]);
+    executor
+        .commit_blocks(vec![block1_id], ledger_info_with_sigs)
+        .unwrap();
+
+    // block2 = attempt to decrease the aptos version
+    let txn3 = get_test_signed_transaction(
+        aptos_root_address(),
+        /* sequence_number = */ 1,
+        genesis_key.clone(),
+        genesis_key.public_key(),
+        Some(encode_set_version_script_function(1)),
+    );
+    let block2_id = gen_block_id(2);
+    let output = executor
+        .execute_block((block2_id, vec![txn3.clone()]), block1_id)
+        .unwrap();
+    assert!(!output.has_reconfiguration());
+    // Errors::invalid_argument(EINVALID_MAJOR_VERSION_NUMBER) in AptosFramework::Version
+    let version_module = ModuleId::new(CORE_CODE_ADDRESS, ident_str!("Version").to_owned());
+    assert_eq!(
+        output.compute_status(),
+        &vec![TransactionStatus::Keep(KeptVMStatus::MoveAbort(
+            AbortLocation::Module(version_module),
+            (1 << 8) + 7,
+        ))]
+    );
+    let ledger_info_with_sigs = gen_ledger_info_with_sigs(2, &output, block2_id, vec![&signer]);
+    executor
+        .commit_blocks(vec![block2_id], ledger_info_with_sigs)
+        .unwrap();
+
+    // The aborted transaction is committed, but the version is unchanged
+    let current_version = db.reader.get_latest_version().unwrap();
+    let t3 = db
+        .reader
+        .get_account_transaction(aptos_root_address(), 1, false, current_version)
+        .unwrap();
+    verify_committed_txn_status(t3.as_ref(), &txn3).unwrap();
     let aptos_root_account_state_with_proof = db
         .reader
         .get_state_value_with_proof(
@@ -174,6 +309,261 @@ fn test_reconfiguration() {
     );
 }
 
+#[test]
+fn test_create_account() {
+    let path = aptos_temppath::TempPath::new();
+    path.create_as_dir().unwrap();
+    let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+    let genesis_key = &vm_genesis::GENESIS_KEYPAIR.0;
+    let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+    let (_, db, executor, _waypoint) = create_db_and_executor(path.path(), &genesis_txn);
+    let parent_block_id = executor.committed_block_id();
+    let signer = ValidatorSigner::new(validators[0].data.address, validators[0].key.clone());
+
+    // This generates an account that does not overlap with genesis
+    let mut rng = ::rand::rngs::StdRng::from_seed([5u8; 32]);
+    let new_account_key = Ed25519PrivateKey::generate(&mut rng).public_key();
+    let new_account = AuthenticationKey::ed25519(&new_account_key).derived_address();
+
+    let txn1 = get_test_signed_transaction(
+        aptos_root_address(),
+        /* sequence_number = */ 0,
+        genesis_key.clone(),
+        genesis_key.public_key(),
+        Some(encode_create_account_script_function(new_account)),
+    );
+    let txn2 = get_test_signed_transaction(
+        aptos_root_address(),
+        /* sequence_number = */ 1,
+        genesis_key.clone(),
+        genesis_key.public_key(),
+        Some(encode_mint_script_function(new_account, 1_000)),
+    );
+
+    let txn_block = vec![txn1, txn2];
+    let block_id = gen_block_id(1);
+    let output = executor
+        .execute_block((block_id, txn_block.clone()), parent_block_id)
+        .unwrap();
+    let ledger_info_with_sigs = gen_ledger_info_with_sigs(1, &output, block_id, vec![&signer]);
+    executor
+        .commit_blocks(vec![block_id], ledger_info_with_sigs)
+        .unwrap();
+
+    let current_version = db.reader.get_latest_version().unwrap();
+    for (sequence_number, txn) in txn_block.iter().enumerate() {
+        let committed_txn = db
+            .reader
+            .get_account_transaction(
+                aptos_root_address(),
+                sequence_number as u64,
+                false,
+                current_version,
+            )
+            .unwrap();
+        verify_committed_txn_status(committed_txn.as_ref(), txn).unwrap();
+    }
+
+    // Both transactions succeed, so the block accounts for the gas of both
+    let gas_used: u64 = (1..=current_version)
+        .map(|version| db.reader.get_gas_usage_by_transaction(version).unwrap())
+        .sum();
+    assert!(gas_used > 0);
+    assert_eq!(output.total_gas_used(), gas_used);
+
+    let new_account_state_with_proof = db
+        .reader
+        .get_state_value_with_proof(
+            StateKey::AccountAddressKey(new_account),
+            current_version,
+            current_version,
+        )
+        .unwrap();
+    let new_account_state =
+        AccountState::try_from(new_account_state_with_proof.value.as_ref().unwrap()).unwrap();
+    assert!(new_account_state.get_account_resource().unwrap().is_some());
+    verify_account_balance(&new_account_state_with_proof, |balance| balance == 1_000).unwrap();
+}
+
+#[test]
+fn test_insufficient_balance_for_gas() {
+    // A transaction whose max gas fee exceeds the balance of its sender must be discarded by the
+    // prologue, without being committed
+
+    let path = aptos_temppath::TempPath::new();
+    path.create_as_dir().unwrap();
+    let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+    let genesis_key = &vm_genesis::GENESIS_KEYPAIR.0;
+    let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+    let (_, db, executor, _waypoint) = create_db_and_executor(path.path(), &genesis_txn);
+    let parent_block_id = executor.committed_block_id();
+    let signer = ValidatorSigner::new(validators[0].data.address, validators[0].key.clone());
+
+    let mut rng = ::rand::rngs::StdRng::from_seed([6u8; 32]);
+    let new_account_private_key = Ed25519PrivateKey::generate(&mut rng);
+    let new_account_key = new_account_private_key.public_key();
+    let new_account = AuthenticationKey::ed25519(&new_account_key).derived_address();
+
+    // The new account holds no coins, so it cannot pay for any gas
+    let txn1 = get_test_signed_transaction(
+        aptos_root_address(),
+        /* sequence_number = */ 0,
+        genesis_key.clone(),
+        genesis_key.public_key(),
+        Some(encode_create_account_script_function(new_account)),
+    );
+    let txn2 = get_test_signed_transaction_with_gas(
+        new_account,
+        /* sequence_number = */ 0,
+        new_account_private_key,
+        new_account_key,
+        None,
+        /* gas_unit_price = */ 1,
+        /* max_gas_amount = */ 1_000,
+    );
+
+    let block_id = gen_block_id(1);
+    let output = executor
+        .execute_block((block_id, vec![txn1, txn2]), parent_block_id)
+        .unwrap();
+    assert_eq!(
+        output.compute_status()[0],
+        TransactionStatus::Keep(KeptVMStatus::Executed)
+    );
+    assert_eq!(
+        output.compute_status()[1],
+        TransactionStatus::Discard(StatusCode::INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE)
+    );
+    let ledger_info_with_sigs = gen_ledger_info_with_sigs(1, &output, block_id, vec![&signer]);
+    executor
+        .commit_blocks(vec![block_id], ledger_info_with_sigs)
+        .unwrap();
+
+    let current_version = db.reader.get_latest_version().unwrap();
+    assert!(db
+        .reader
+        .get_account_transaction(new_account, 0, false, current_version)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_get_account_transactions_paginated() {
+    let path = aptos_temppath::TempPath::new();
+    path.create_as_dir().unwrap();
+    let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+    let genesis_key = &vm_genesis::GENESIS_KEYPAIR.0;
+    let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+    let (_, db, executor, _waypoint) = create_db_and_executor(path.path(), &genesis_txn);
+    let parent_block_id = executor.committed_block_id();
+    let signer = ValidatorSigner::new(validators[0].data.address, validators[0].key.clone());
+
+    let num_txns = 100;
+    let txn_block: Vec<_> = (0..num_txns)
+        .map(|sequence_number| {
+            get_test_signed_transaction(
+                aptos_root_address(),
+                sequence_number,
+                genesis_key.clone(),
+                genesis_key.public_key(),
+                Some(encode_mint_script_function(signer.author(), 1_000)),
+            )
+        })
+        .collect();
+    let block_id = gen_block_id(1);
+    let output = executor
+        .execute_block((block_id, txn_block.clone()), parent_block_id)
+        .unwrap();
+    let ledger_info_with_sigs = gen_ledger_info_with_sigs(1, &output, block_id, vec![&signer]);
+    executor
+        .commit_blocks(vec![block_id], ledger_info_with_sigs)
+        .unwrap();
+
+    // Page through all transactions, following the cursor of each page
+    let current_version = db.reader.get_latest_version().unwrap();
+    let limit = 30;
+    let mut cursor = None;
+    let mut committed_txns = vec![];
+    loop {
+        let page = db
+            .reader
+            .get_account_transactions_paginated(
+                aptos_root_address(),
+                cursor,
+                limit,
+                false,
+                current_version,
+            )
+            .unwrap();
+        let page_len = page.len() as u64;
+        assert!(page_len <= limit);
+        if let Some(last_seq_num) = page.last_seq_num() {
+            assert!(cursor.map_or(true, |cursor| last_seq_num > cursor));
+            cursor = Some(last_seq_num);
+        }
+        committed_txns.extend(page.into_inner());
+        if page_len < limit {
+            assert_eq!(page_len, num_txns % limit);
+            break;
+        }
+    }
+
+    assert_eq!(committed_txns.len(), txn_block.len());
+    for (committed_txn, txn) in committed_txns.iter().zip(&txn_block) {
+        verify_committed_txn_status(Some(committed_txn), txn).unwrap();
+    }
+
+    // Past the last page, there are no more transactions
+    let page = db
+        .reader
+        .get_account_transactions_paginated(
+            aptos_root_address(),
+            cursor,
+            limit,
+            false,
+            current_version,
+        )
+        .unwrap();
+    assert!(page.is_empty());
+    assert_eq!(page.last_seq_num(), None);
+}
+
+#[test]
+fn test_commit_fails_on_storage_error() {
+    let path = aptos_temppath::TempPath::new();
+    path.create_as_dir().unwrap();
+    let (genesis, validators) = vm_genesis::test_genesis_change_set_and_validators(Some(1));
+    let genesis_txn = Transaction::GenesisTransaction(WriteSetPayload::Direct(genesis));
+    let (_, db, _executor, _waypoint) = create_db_and_executor(path.path(), &genesis_txn);
+    let signer = ValidatorSigner::new(validators[0].data.address, validators[0].key.clone());
+
+    let storage =
+        Arc::new(MockStorage::wrap(db.clone()).fail_after(StorageOp::SaveTransactions, 0));
+    let executor = BlockExecutor::<AptosVM>::new(DbReaderWriter::from_arc(storage.clone()));
+    let parent_block_id = executor.committed_block_id();
+
+    let txn = Transaction::BlockMetadata(BlockMetadata::new(
+        gen_block_id(1),
+        1,
+        300000001,
+        vec![],
+        signer.author(),
+    ));
+    let block_id = gen_block_id(1);
+    let output = executor
+        .execute_block((block_id, vec![txn]), parent_block_id)
+        .unwrap();
+    let ledger_info_with_sigs = gen_ledger_info_with_sigs(1, &output, block_id, vec![&signer]);
+
+    // The failed write is surfaced to the caller and nothing is committed
+    executor
+        .commit_blocks(vec![block_id], ledger_info_with_sigs)
+        .unwrap_err();
+    assert_eq!(storage.call_count(StorageOp::SaveTransactions), 1);
+    assert_eq!(db.reader.get_latest_version().unwrap(), 0);
+    assert_eq!(executor.committed_block_id(), parent_block_id);
+}
+
 #[test]
 fn test_execution_with_storage() {
     test_execution_with_storage_impl();
@@ -13,26 +13,49 @@
 //! server.
 //!
 //! Internally both the client and server leverage a NetworkStream that communications in blocks
-//! where a block is a length prefixed array of bytes.
+//! where a block is a length prefixed array of bytes. The stream is either a TCP connection,
+//! optionally secured with mutually authenticated TLS, or, for services that should only be
+//! reachable from the local host, a Unix domain socket.
 
 use aptos_logger::{info, trace, warn, Schema};
 use aptos_secure_push_metrics::{register_int_counter_vec, IntCounterVec};
+#[cfg(unix)]
+use nix::sys::stat::{umask, Mode};
 use once_cell::sync::Lazy;
+use rustls::{ClientConnection, ServerConfig, ServerConnection, StreamOwned};
 use serde::Serialize;
 use std::{
+    fmt,
     io::{Read, Write},
     net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::Arc,
     thread, time,
 };
+#[cfg(unix)]
+use std::{
+    fs,
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+};
 use thiserror::Error;
 
+mod tls;
+
+pub use tls::{ClientTlsConfig, TlsConfig};
+
+/// By default messages are only limited by their u32 length prefix.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = u32::MAX as usize;
+
 #[derive(Schema)]
 struct SecureNetLogSchema<'a> {
     service: &'static str,
     mode: NetworkMode,
     event: LogEvent,
     #[schema(debug)]
-    remote_peer: Option<&'a SocketAddr>,
+    remote_peer: Option<&'a ServerAddress>,
     #[schema(debug)]
     error: Option<&'a Error>,
 }
@@ -138,6 +161,10 @@ pub enum Error {
     AlreadyShutdown,
     #[error("Found data that is too large to decode: {0}")]
     DataTooLarge(usize),
+    #[error("Frame of {0} bytes exceeds the maximum frame size of {1} bytes")]
+    FrameTooLarge(usize, usize),
+    #[error("Invalid TLS config: {0}")]
+    InvalidTlsConfig(String),
     #[error("Internal network error:")]
     NetworkError(#[from] std::io::Error),
     #[error("No active stream")]
@@ -146,24 +173,82 @@ pub enum Error {
     OverflowError(String),
     #[error("Remote stream cleanly closed")]
     RemoteStreamClosed,
+    #[error("TLS handshake failed: {0}")]
+    TlsHandshakeFailed(String),
+    #[error("TLS peer is not authorized: {0}")]
+    TlsUnauthorized(String),
+    #[error("Unix domain sockets are not supported on this platform: {0}")]
+    UnixSocketUnsupported(String),
+}
+
+/// The address a NetworkServer listens on and a NetworkClient connects to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ServerAddress {
+    Tcp(SocketAddr),
+    /// The path of a Unix domain socket, which is only accessible to the owner of the server.
+    /// Binding or connecting to one fails on platforms other than Unix.
+    Unix(PathBuf),
+}
+
+impl From<SocketAddr> for ServerAddress {
+    fn from(address: SocketAddr) -> Self {
+        ServerAddress::Tcp(address)
+    }
+}
+
+impl fmt::Display for ServerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ServerAddress::Tcp(address) => write!(f, "{}", address),
+            ServerAddress::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
 }
 
 pub struct NetworkClient {
     service: &'static str,
-    server: SocketAddr,
+    server: ServerAddress,
     stream: Option<NetworkStream>,
+    tls: Option<ClientTlsConfig>,
     /// Read, Write, Connect timeout in milliseconds.
     timeout_ms: u64,
+    /// The largest message that is sent or accepted, in bytes.
+    max_frame_size: usize,
 }
 
 impl NetworkClient {
-    pub fn new(service: &'static str, server: SocketAddr, timeout_ms: u64) -> Self {
+    pub fn new<A: Into<ServerAddress>>(service: &'static str, server: A, timeout_ms: u64) -> Self {
         Self {
             service,
-            server,
+            server: server.into(),
             stream: None,
+            tls: None,
             timeout_ms,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+        }
+    }
+
+    /// Connects over mutually authenticated TLS. Only supported for TCP servers.
+    pub fn with_tls(mut self, config: &TlsConfig) -> Result<Self, Error> {
+        if let ServerAddress::Unix(_) = self.server {
+            return Err(Error::InvalidTlsConfig(
+                "TLS is only supported over TCP".into(),
+            ));
         }
+        Ok(self.with_client_tls_config(config.client_config()?))
+    }
+
+    /// Connects over mutually authenticated TLS with a configuration that was already built,
+    /// e.g., shared by several clients. Connecting to a Unix domain socket then fails.
+    pub fn with_client_tls_config(mut self, config: ClientTlsConfig) -> Self {
+        self.tls = Some(config);
+        self
+    }
+
+    /// Limits the size of the messages sent and accepted, in bytes.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
     }
 
     fn increment_counter(&self, method: Method, result: MethodResult) {
@@ -200,7 +285,7 @@ impl NetworkClient {
             LogEvent::Shutdown,
         ));
 
-        let stream = self.stream.take().ok_or(Error::NoActiveStream)?;
+        let mut stream = self.stream.take().ok_or(Error::NoActiveStream)?;
         stream.shutdown()?;
         Ok(())
     }
@@ -237,27 +322,34 @@ impl NetworkClient {
             )
             .remote_peer(&self.server));
 
-            let timeout = std::time::Duration::from_millis(self.timeout_ms);
-            let mut stream = TcpStream::connect_timeout(&self.server, timeout);
+            let mut stream = self.connect();
 
             let sleeptime = time::Duration::from_millis(100);
-            while let Err(err) = stream {
+            while let Err(err) = &stream {
                 self.increment_counter(Method::Connect, MethodResult::Failure);
                 warn!(SecureNetLogSchema::new(
                     self.service,
                     NetworkMode::Client,
                     LogEvent::ConnectionFailed,
                 )
-                .error(&err.into())
+                .error(err)
                 .remote_peer(&self.server));
 
+                // Unlike an unavailable server, TLS failures do not resolve themselves
+                if !matches!(err, Error::NetworkError(_)) {
+                    break;
+                }
                 thread::sleep(sleeptime);
-                stream = TcpStream::connect_timeout(&self.server, timeout);
+                stream = self.connect();
             }
 
             let stream = stream?;
-            stream.set_nodelay(true)?;
-            self.stream = Some(NetworkStream::new(stream, self.server, self.timeout_ms));
+            self.stream = Some(NetworkStream::new(
+                stream,
+                self.server.clone(),
+                self.timeout_ms,
+                self.max_frame_size,
+            ));
             self.increment_counter(Method::Connect, MethodResult::Success);
             info!(SecureNetLogSchema::new(
                 self.service,
@@ -269,27 +361,79 @@ impl NetworkClient {
 
         self.stream.as_mut().ok_or(Error::NoActiveStream)
     }
+
+    fn connect(&self) -> Result<Stream, Error> {
+        match &self.server {
+            ServerAddress::Tcp(address) => {
+                let timeout = std::time::Duration::from_millis(self.timeout_ms);
+                let stream = TcpStream::connect_timeout(address, timeout)?;
+                stream.set_nodelay(true)?;
+                match &self.tls {
+                    Some(tls) => {
+                        tls::connect(&tls.config, tls.server_name.clone(), stream, timeout)
+                    }
+                    None => Ok(Stream::Tcp(stream)),
+                }
+            }
+            ServerAddress::Unix(_) if self.tls.is_some() => Err(Error::InvalidTlsConfig(
+                "TLS is only supported over TCP".into(),
+            )),
+            #[cfg(unix)]
+            ServerAddress::Unix(path) => Ok(Stream::Unix(UnixStream::connect(path)?)),
+            #[cfg(not(unix))]
+            ServerAddress::Unix(path) => {
+                Err(Error::UnixSocketUnsupported(path.display().to_string()))
+            }
+        }
+    }
 }
 
 pub struct NetworkServer {
     service: &'static str,
-    listener: Option<TcpListener>,
+    listener: Option<Listener>,
     stream: Option<NetworkStream>,
+    tls: Option<Arc<ServerConfig>>,
     /// Read, Write, Connect timeout in milliseconds.
     timeout_ms: u64,
+    /// The largest message that is sent or accepted, in bytes.
+    max_frame_size: usize,
 }
 
 impl NetworkServer {
-    pub fn new(service: &'static str, listen: SocketAddr, timeout_ms: u64) -> Self {
-        let listener = TcpListener::bind(listen);
+    /// Binds to the given address. A Unix domain socket is created with permissions that only
+    /// allow the current user to connect, replacing any socket left behind at that path. It is
+    /// removed when the server is shutdown or dropped.
+    pub fn new<A: Into<ServerAddress>>(service: &'static str, listen: A, timeout_ms: u64) -> Self {
+        let listener = Listener::bind(listen.into());
         Self {
             service,
             listener: Some(listener.unwrap()),
             stream: None,
+            tls: None,
             timeout_ms,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
         }
     }
 
+    /// Requires clients to connect over mutually authenticated TLS. Only supported for TCP.
+    pub fn with_tls(mut self, config: &TlsConfig) -> Result<Self, Error> {
+        #[cfg(unix)]
+        if let Some(Listener::Unix(_)) = self.listener {
+            return Err(Error::InvalidTlsConfig(
+                "TLS is only supported over TCP".into(),
+            ));
+        }
+        self.tls = Some(config.server_config()?);
+        Ok(self)
+    }
+
+    /// Limits the size of the messages sent and accepted, in bytes. A client that sends a larger
+    /// message is disconnected without the message being read.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
     fn increment_counter(&self, method: Method, result: MethodResult) {
         increment_counter(self.service, NetworkMode::Server, method, result)
     }
@@ -301,7 +445,7 @@ impl NetworkServer {
 
         let result = {
             let stream = self.client()?;
-            stream.read().map_err(|e| (stream.remote, e))
+            stream.read().map_err(|e| (stream.remote.clone(), e))
         };
 
         if let Err((remote, err)) = &result {
@@ -331,7 +475,7 @@ impl NetworkServer {
         ));
 
         self.listener.take().ok_or(Error::AlreadyShutdown)?;
-        let stream = self.stream.take().ok_or(Error::NoActiveStream)?;
+        let mut stream = self.stream.take().ok_or(Error::NoActiveStream)?;
         stream.shutdown()?;
         Ok(())
     }
@@ -343,7 +487,7 @@ impl NetworkServer {
 
         let result = {
             let stream = self.client()?;
-            stream.write(data).map_err(|e| (stream.remote, e))
+            stream.write(data).map_err(|e| (stream.remote.clone(), e))
         };
 
         if let Err((remote, err)) = &result {
@@ -390,6 +534,27 @@ impl NetworkServer {
                 }
             };
 
+            let stream = match (&self.tls, stream) {
+                (Some(config), Stream::Tcp(stream)) => {
+                    let timeout = time::Duration::from_millis(self.timeout_ms);
+                    match tls::accept(config, stream, timeout) {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            self.increment_counter(Method::Connect, MethodResult::Failure);
+                            warn!(SecureNetLogSchema::new(
+                                self.service,
+                                NetworkMode::Server,
+                                LogEvent::ConnectionFailed,
+                            )
+                            .error(&err)
+                            .remote_peer(&stream_addr));
+                            return Err(err);
+                        }
+                    }
+                }
+                (_, stream) => stream,
+            };
+
             self.increment_counter(Method::Connect, MethodResult::Success);
             info!(SecureNetLogSchema::new(
                 self.service,
@@ -398,53 +563,231 @@ impl NetworkServer {
             )
             .remote_peer(&stream_addr));
 
-            stream.set_nodelay(true)?;
-            self.stream = Some(NetworkStream::new(stream, stream_addr, self.timeout_ms));
+            self.stream = Some(NetworkStream::new(
+                stream,
+                stream_addr,
+                self.timeout_ms,
+                self.max_frame_size,
+            ));
         }
 
         self.stream.as_mut().ok_or(Error::NoActiveStream)
     }
 }
 
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixSocketListener),
+}
+
+impl Listener {
+    fn bind(address: ServerAddress) -> std::io::Result<Self> {
+        match address {
+            ServerAddress::Tcp(address) => Ok(Listener::Tcp(TcpListener::bind(address)?)),
+            #[cfg(unix)]
+            ServerAddress::Unix(path) => Ok(Listener::Unix(UnixSocketListener::bind(path)?)),
+            #[cfg(not(unix))]
+            ServerAddress::Unix(_) => Err(unix_sockets_unsupported()),
+        }
+    }
+
+    fn accept(&self) -> std::io::Result<(Stream, ServerAddress)> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, address) = listener.accept()?;
+                stream.set_nodelay(true)?;
+                Ok((Stream::Tcp(stream), ServerAddress::Tcp(address)))
+            }
+            // Peers of a Unix domain socket are unnamed, so they are identified by the socket
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                let (stream, _) = listener.listener.accept()?;
+                Ok((
+                    Stream::Unix(stream),
+                    ServerAddress::Unix(listener.path.clone()),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn unix_sockets_unsupported() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Unix domain sockets are not supported on this platform",
+    )
+}
+
+#[cfg(unix)]
+struct UnixSocketListener {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl UnixSocketListener {
+    fn bind(path: PathBuf) -> std::io::Result<Self> {
+        // A previous server that did not shutdown cleanly leaves its socket behind
+        if let Ok(metadata) = fs::symlink_metadata(&path) {
+            if metadata.file_type().is_socket() {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        // The socket is created with permissions for the owner only, so that no other user can
+        // connect before they could be restricted. The umask is process wide, so it is restored
+        // right away.
+        let previous_umask = umask(Mode::from_bits_truncate(0o177));
+        let listener = UnixListener::bind(&path);
+        umask(previous_umask);
+        Ok(Self {
+            listener: listener?,
+            path,
+        })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for UnixSocketListener {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!("Unable to remove socket {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+enum Stream {
+    Tcp(TcpStream),
+    TlsClient(Box<StreamOwned<ClientConnection, TcpStream>>),
+    TlsServer(Box<StreamOwned<ServerConnection, TcpStream>>),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Stream {
+    fn set_timeout(&self, timeout: Option<time::Duration>) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => {
+                stream.set_read_timeout(timeout)?;
+                stream.set_write_timeout(timeout)
+            }
+            Stream::TlsClient(stream) => {
+                stream.sock.set_read_timeout(timeout)?;
+                stream.sock.set_write_timeout(timeout)
+            }
+            Stream::TlsServer(stream) => {
+                stream.sock.set_read_timeout(timeout)?;
+                stream.sock.set_write_timeout(timeout)
+            }
+            #[cfg(unix)]
+            Stream::Unix(stream) => {
+                stream.set_read_timeout(timeout)?;
+                stream.set_write_timeout(timeout)
+            }
+        }
+    }
+
+    fn shutdown(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.shutdown(Shutdown::Both),
+            Stream::TlsClient(stream) => {
+                stream.conn.send_close_notify();
+                stream.flush()?;
+                stream.sock.shutdown(Shutdown::Both)
+            }
+            Stream::TlsServer(stream) => {
+                stream.conn.send_close_notify();
+                stream.flush()?;
+                stream.sock.shutdown(Shutdown::Both)
+            }
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.shutdown(Shutdown::Both),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            Stream::TlsClient(stream) => stream.read(buf),
+            Stream::TlsServer(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            Stream::TlsClient(stream) => stream.write(buf),
+            Stream::TlsServer(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            Stream::TlsClient(stream) => stream.flush(),
+            Stream::TlsServer(stream) => stream.flush(),
+            #[cfg(unix)]
+            Stream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
 struct NetworkStream {
-    stream: TcpStream,
-    remote: SocketAddr,
+    stream: Stream,
+    remote: ServerAddress,
     buffer: Vec<u8>,
     temp_buffer: [u8; 1024],
+    max_frame_size: usize,
 }
 
 impl NetworkStream {
-    pub fn new(stream: TcpStream, remote: SocketAddr, timeout_ms: u64) -> Self {
+    pub fn new(
+        stream: Stream,
+        remote: ServerAddress,
+        timeout_ms: u64,
+        max_frame_size: usize,
+    ) -> Self {
         let timeout = Some(std::time::Duration::from_millis(timeout_ms));
         // These only fail if a duration of 0 is passed in.
-        stream.set_read_timeout(timeout).unwrap();
-        stream.set_write_timeout(timeout).unwrap();
+        stream.set_timeout(timeout).unwrap();
 
         Self {
             stream,
             remote,
             buffer: Vec::new(),
             temp_buffer: [0; 1024],
+            max_frame_size,
         }
     }
 
     /// Blocking read until able to successfully read an entire message
     pub fn read(&mut self) -> Result<Vec<u8>, Error> {
-        let result = self.read_buffer();
-        if !result.is_empty() {
+        if let Some(result) = self.read_buffer()? {
             return Ok(result);
         }
 
         loop {
             trace!("Attempting to read from stream");
-            let read = self.stream.read(&mut self.temp_buffer)?;
+            let read = self
+                .stream
+                .read(&mut self.temp_buffer)
+                .map_err(tls::from_io_error)?;
             trace!("Read {} bytes from stream", read);
             if read == 0 {
                 return Err(Error::RemoteStreamClosed);
             }
             self.buffer.extend(self.temp_buffer[..read].to_vec());
-            let result = self.read_buffer();
-            if !result.is_empty() {
+            if let Some(result) = self.read_buffer()? {
                 trace!("Found a message in the stream");
                 return Ok(result);
             }
@@ -453,12 +796,15 @@ impl NetworkStream {
     }
 
     /// Terminate the socket
-    pub fn shutdown(&self) -> Result<(), Error> {
-        Ok(self.stream.shutdown(Shutdown::Both)?)
+    pub fn shutdown(&mut self) -> Result<(), Error> {
+        Ok(self.stream.shutdown()?)
     }
 
     /// Blocking write until able to successfully send an entire message
     pub fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        if data.len() > self.max_frame_size {
+            return Err(Error::FrameTooLarge(data.len(), self.max_frame_size));
+        }
         let u32_max = u32::max_value() as usize;
         if u32_max <= data.len() {
             return Err(Error::DataTooLarge(data.len()));
@@ -477,24 +823,28 @@ impl NetworkStream {
 
     /// Data sent on a TCP socket may not necessarily be delivered at the exact time. So a read may
     /// only include a subset of what was sent. This wraps around the TCP read buffer to ensure
-    /// that only full messages are received.
-    fn read_buffer(&mut self) -> Vec<u8> {
+    /// that only full messages are received. The length prefix is checked against the maximum
+    /// frame size as soon as it arrives, so an oversized message is never buffered.
+    fn read_buffer(&mut self) -> Result<Option<Vec<u8>>, Error> {
         if self.buffer.len() < 4 {
-            return Vec::new();
+            return Ok(None);
         }
 
         let mut u32_bytes = [0; 4];
         u32_bytes.copy_from_slice(&self.buffer[..4]);
         let data_size = u32::from_le_bytes(u32_bytes) as usize;
+        if data_size > self.max_frame_size {
+            return Err(Error::FrameTooLarge(data_size, self.max_frame_size));
+        }
 
         let remaining_data = &self.buffer[4..];
         if remaining_data.len() < data_size {
-            return Vec::new();
+            return Ok(None);
         }
 
         let returnable_data = remaining_data[..data_size].to_vec();
         self.buffer = remaining_data[data_size..].to_vec();
-        returnable_data
+        Ok(Some(returnable_data))
     }
 
     /// Writing to a TCP socket will take in as much data as the underlying buffer has space for.
@@ -504,7 +854,7 @@ impl NetworkStream {
         let mut total_written: u64 = 0;
 
         while !unwritten.is_empty() {
-            let written = self.stream.write(unwritten)?;
+            let written = self.stream.write(unwritten).map_err(tls::from_io_error)?;
             total_written = total_written
                 .checked_add(written as u64)
                 .ok_or_else(|| Error::OverflowError("write_all::total_written".into()))?;
@@ -541,6 +891,33 @@ mod test {
         assert_eq!(data, result);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_ping_unix() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let socket_path = aptos_temppath::TempPath::new();
+        let server_addr = ServerAddress::Unix(socket_path.path().to_path_buf());
+        let mut server = NetworkServer::new("test", server_addr.clone(), TIMEOUT);
+        let mut client = NetworkClient::new("test", server_addr, TIMEOUT);
+
+        let permissions = fs::metadata(socket_path.path()).unwrap().permissions();
+        assert_eq!(permissions.mode() & 0o777, 0o600);
+
+        let data = vec![0, 1, 2, 3];
+        client.write(&data).unwrap();
+        let result = server.read().unwrap();
+        assert_eq!(data, result);
+
+        let data = vec![4, 5, 6, 7];
+        server.write(&data).unwrap();
+        let result = client.read().unwrap();
+        assert_eq!(data, result);
+
+        server.shutdown().unwrap();
+        assert!(!socket_path.path().exists());
+    }
+
     #[test]
     fn test_client_shutdown() {
         let server_port = utils::get_available_port();
@@ -604,6 +981,62 @@ mod test {
         assert_eq!(data2, result2);
     }
 
+    #[test]
+    fn test_oversized_frame() {
+        let server_port = utils::get_available_port();
+        let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+        let mut server = NetworkServer::new("test", server_addr, TIMEOUT).with_max_frame_size(4);
+
+        // Readers reject oversized messages by their length prefix, before the payload arrives
+        let mut stream = TcpStream::connect(server_addr).unwrap();
+        stream.write_all(&u32::MAX.to_le_bytes()).unwrap();
+        assert!(matches!(
+            server.read().unwrap_err(),
+            Error::FrameTooLarge(size, 4) if size == u32::MAX as usize
+        ));
+
+        // The server dropped the offending connection and serves the next client
+        let mut client = NetworkClient::new("test", server_addr, TIMEOUT).with_max_frame_size(4);
+        let data = vec![0, 1, 2, 3];
+        client.write(&data).unwrap();
+        assert_eq!(server.read().unwrap(), data);
+
+        // Writers refuse to send oversized messages
+        assert!(matches!(
+            client.write(&[0, 1, 2, 3, 4]).unwrap_err(),
+            Error::FrameTooLarge(5, 4)
+        ));
+    }
+
+    #[test]
+    fn test_truncated_frame() {
+        let server_port = utils::get_available_port();
+        let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+        let mut server = NetworkServer::new("test", server_addr, TIMEOUT);
+
+        let mut stream = TcpStream::connect(server_addr).unwrap();
+        stream.write_all(&4u32.to_le_bytes()).unwrap();
+        stream.write_all(&[0, 1]).unwrap();
+        stream.shutdown(Shutdown::Both).unwrap();
+        assert!(matches!(
+            server.read().unwrap_err(),
+            Error::RemoteStreamClosed
+        ));
+    }
+
+    #[test]
+    fn test_empty_message() {
+        let server_port = utils::get_available_port();
+        let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+        let mut server = NetworkServer::new("test", server_addr, TIMEOUT);
+        let mut client = NetworkClient::new("test", server_addr, TIMEOUT);
+
+        client.write(&[]).unwrap();
+        client.write(&[0, 1, 2, 3]).unwrap();
+        assert!(server.read().unwrap().is_empty());
+        assert_eq!(server.read().unwrap(), vec![0, 1, 2, 3]);
+    }
+
     #[test]
     fn test_server_timeout() {
         let server_port = utils::get_available_port();
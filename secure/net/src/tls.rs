@@ -0,0 +1,193 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Mutually authenticated TLS for TCP connections between a NetworkClient and a NetworkServer.
+//! Both ends present a certificate and reject peers whose certificate is not signed by one of
+//! their trusted certificate authorities.
+
+use crate::{Error, Stream};
+use rustls::{
+    server::AllowAnyAuthenticatedClient, AlertDescription, Certificate, ClientConfig,
+    ClientConnection, ConnectionCommon, PrivateKey, RootCertStore, ServerConfig, ServerConnection,
+    ServerName, StreamOwned,
+};
+use std::{convert::TryFrom, io, net::TcpStream, sync::Arc, time::Duration};
+
+/// In TLS 1.3 the server verifies the client certificate after the client has completed its side
+/// of the handshake, so a rejected client only finds out on its next read. With TLS 1.2, which is
+/// still accepted from peers that do not support TLS 1.3, it fails its handshake.
+const PROTOCOL_VERSIONS: &[&rustls::SupportedProtocolVersion] =
+    &[&rustls::version::TLS13, &rustls::version::TLS12];
+
+/// The PEM encoded identity of one end of a connection and the authorities it trusts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TlsConfig {
+    /// The certificate chain presented to the peer, starting with the end-entity certificate.
+    pub certificate_chain: Vec<u8>,
+    /// The PKCS#8 private key of the end-entity certificate.
+    pub private_key: Vec<u8>,
+    /// The certificates of the authorities that sign the certificates of accepted peers.
+    pub ca_certificates: Vec<u8>,
+    /// The DNS name the server certificate is issued for, checked by clients.
+    pub server_name: String,
+}
+
+/// The TLS configuration of a NetworkClient, built from a TlsConfig by `TlsConfig::client_config`
+/// so that it can be checked once and shared by many clients.
+#[derive(Clone)]
+pub struct ClientTlsConfig {
+    pub(crate) config: Arc<ClientConfig>,
+    pub(crate) server_name: ServerName,
+}
+
+impl TlsConfig {
+    pub(crate) fn server_config(&self) -> Result<Arc<ServerConfig>, Error> {
+        let client_verifier = AllowAnyAuthenticatedClient::new(self.root_store()?);
+        let config = ServerConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(PROTOCOL_VERSIONS)
+            .map_err(|e| Error::InvalidTlsConfig(e.to_string()))?
+            .with_client_cert_verifier(client_verifier)
+            .with_single_cert(self.certificate_chain()?, self.private_key()?)
+            .map_err(|e| Error::InvalidTlsConfig(e.to_string()))?;
+        Ok(Arc::new(config))
+    }
+
+    pub fn client_config(&self) -> Result<ClientTlsConfig, Error> {
+        let config = ClientConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(PROTOCOL_VERSIONS)
+            .map_err(|e| Error::InvalidTlsConfig(e.to_string()))?
+            .with_root_certificates(self.root_store()?)
+            .with_single_cert(self.certificate_chain()?, self.private_key()?)
+            .map_err(|e| Error::InvalidTlsConfig(e.to_string()))?;
+        let server_name = ServerName::try_from(self.server_name.as_str()).map_err(|_| {
+            Error::InvalidTlsConfig(format!("Invalid server name: {}", self.server_name))
+        })?;
+        Ok(ClientTlsConfig {
+            config: Arc::new(config),
+            server_name,
+        })
+    }
+
+    fn certificate_chain(&self) -> Result<Vec<Certificate>, Error> {
+        let certificates = parse_certificates(&self.certificate_chain)?;
+        if certificates.is_empty() {
+            return Err(Error::InvalidTlsConfig("No certificate found".into()));
+        }
+        Ok(certificates)
+    }
+
+    fn private_key(&self) -> Result<PrivateKey, Error> {
+        let mut keys = rustls_pemfile::pkcs8_private_keys(&mut self.private_key.as_slice())
+            .map_err(|e| Error::InvalidTlsConfig(format!("Unable to parse private key: {}", e)))?;
+        match keys.len() {
+            1 => Ok(PrivateKey(keys.remove(0))),
+            count => Err(Error::InvalidTlsConfig(format!(
+                "Expected one PKCS#8 private key, found {}",
+                count
+            ))),
+        }
+    }
+
+    fn root_store(&self) -> Result<RootCertStore, Error> {
+        let mut root_store = RootCertStore::empty();
+        for certificate in parse_certificates(&self.ca_certificates)? {
+            root_store
+                .add(&certificate)
+                .map_err(|e| Error::InvalidTlsConfig(format!("Invalid CA certificate: {:?}", e)))?;
+        }
+        if root_store.is_empty() {
+            return Err(Error::InvalidTlsConfig("No CA certificate found".into()));
+        }
+        Ok(root_store)
+    }
+}
+
+fn parse_certificates(pem: &[u8]) -> Result<Vec<Certificate>, Error> {
+    let certificates = rustls_pemfile::certs(&mut &pem[..])
+        .map_err(|e| Error::InvalidTlsConfig(format!("Unable to parse certificates: {}", e)))?;
+    Ok(certificates.into_iter().map(Certificate).collect())
+}
+
+/// Performs the server side of the handshake on an accepted connection.
+pub(crate) fn accept(
+    config: &Arc<ServerConfig>,
+    mut sock: TcpStream,
+    timeout: Duration,
+) -> Result<Stream, Error> {
+    let mut conn = ServerConnection::new(config.clone()).map_err(from_tls_error)?;
+    complete_handshake(&mut conn, &mut sock, timeout)?;
+    Ok(Stream::TlsServer(Box::new(StreamOwned::new(conn, sock))))
+}
+
+/// Performs the client side of the handshake on a new connection.
+pub(crate) fn connect(
+    config: &Arc<ClientConfig>,
+    server_name: ServerName,
+    mut sock: TcpStream,
+    timeout: Duration,
+) -> Result<Stream, Error> {
+    let mut conn = ClientConnection::new(config.clone(), server_name).map_err(from_tls_error)?;
+    complete_handshake(&mut conn, &mut sock, timeout)?;
+    Ok(Stream::TlsClient(Box::new(StreamOwned::new(conn, sock))))
+}
+
+fn complete_handshake<T>(
+    conn: &mut ConnectionCommon<T>,
+    sock: &mut TcpStream,
+    timeout: Duration,
+) -> Result<(), Error> {
+    // A peer that stalls the handshake must not block the caller indefinitely
+    sock.set_read_timeout(Some(timeout))?;
+    sock.set_write_timeout(Some(timeout))?;
+    while conn.is_handshaking() {
+        conn.complete_io(sock).map_err(from_io_error)?;
+    }
+    Ok(())
+}
+
+/// Surfaces TLS failures, which the TLS streams report as I/O errors, as their own errors.
+pub(crate) fn from_io_error(error: io::Error) -> Error {
+    let tls_error = error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+        .cloned();
+    match tls_error {
+        Some(tls_error) => from_tls_error(tls_error),
+        None => Error::NetworkError(error),
+    }
+}
+
+/// Distinguishes a peer that failed to authenticate from a handshake that failed for any other
+/// reason (e.g., a peer that does not speak TLS). Note that a server does not tell a client why
+/// its certificate was rejected, so only the server reports the authorization failure.
+fn from_tls_error(error: rustls::Error) -> Error {
+    let unauthorized = match &error {
+        rustls::Error::NoCertificatesPresented
+        | rustls::Error::InvalidCertificateEncoding
+        | rustls::Error::InvalidCertificateSignatureType
+        | rustls::Error::InvalidCertificateSignature
+        | rustls::Error::InvalidCertificateData(_) => true,
+        rustls::Error::AlertReceived(alert) => matches!(
+            alert,
+            AlertDescription::BadCertificate
+                | AlertDescription::UnsupportedCertificate
+                | AlertDescription::CertificateRevoked
+                | AlertDescription::CertificateExpired
+                | AlertDescription::CertificateUnknown
+                | AlertDescription::UnknownCA
+                | AlertDescription::AccessDenied
+                | AlertDescription::CertificateRequired
+        ),
+        _ => false,
+    };
+
+    if unauthorized {
+        Error::TlsUnauthorized(error.to_string())
+    } else {
+        Error::TlsHandshakeFailed(error.to_string())
+    }
+}
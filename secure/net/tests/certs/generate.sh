@@ -0,0 +1,33 @@
+#!/bin/sh
+# Copyright (c) Aptos
+# SPDX-License-Identifier: Apache-2.0
+
+# Regenerates the test certificates used by the TLS tests. The trusted CA signs the server and
+# client certificates, the untrusted CA signs a client certificate that the server must reject.
+set -e
+cd "$(dirname "$0")"
+DAYS=36500
+
+ca() {
+    openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out "$1.key"
+    openssl req -x509 -new -key "$1.key" -subj "/CN=$1" -days $DAYS -out "$1.pem" \
+        -addext "basicConstraints=critical,CA:TRUE" \
+        -addext "keyUsage=critical,keyCertSign,cRLSign"
+}
+
+leaf() {
+    openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out "$1.key"
+    openssl req -new -key "$1.key" -subj "/CN=$1" -out "$1.csr"
+    printf "basicConstraints=critical,CA:FALSE\nkeyUsage=critical,digitalSignature\nextendedKeyUsage=%s\nsubjectAltName=DNS:%s\n" \
+        "$3" "$4" > "$1.ext"
+    openssl x509 -req -in "$1.csr" -CA "$2.pem" -CAkey "$2.key" -CAcreateserial -days $DAYS \
+        -extfile "$1.ext" -out "$1.pem"
+    rm "$1.csr" "$1.ext"
+}
+
+ca ca
+ca untrusted_ca
+leaf server ca serverAuth safety-rules
+leaf client ca clientAuth client
+leaf untrusted_client untrusted_ca clientAuth client
+rm -f ./*.srl ca.key untrusted_ca.key
@@ -0,0 +1,110 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Exercises mutually authenticated TLS between a NetworkClient and a NetworkServer. The test
+//! certificates are regenerated by certs/generate.sh.
+
+use aptos_config::utils;
+use aptos_secure_net::{Error, NetworkClient, NetworkServer, TlsConfig};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    thread,
+};
+
+/// Read, Write, Connect timeout in milliseconds.
+const TIMEOUT: u64 = 5_000;
+
+const CA: &[u8] = include_bytes!("certs/ca.pem");
+const SERVER_CERT: &[u8] = include_bytes!("certs/server.pem");
+const SERVER_KEY: &[u8] = include_bytes!("certs/server.key");
+const CLIENT_CERT: &[u8] = include_bytes!("certs/client.pem");
+const CLIENT_KEY: &[u8] = include_bytes!("certs/client.key");
+const UNTRUSTED_CLIENT_CERT: &[u8] = include_bytes!("certs/untrusted_client.pem");
+const UNTRUSTED_CLIENT_KEY: &[u8] = include_bytes!("certs/untrusted_client.key");
+
+fn tls_config(certificate_chain: &[u8], private_key: &[u8]) -> TlsConfig {
+    TlsConfig {
+        certificate_chain: certificate_chain.to_vec(),
+        private_key: private_key.to_vec(),
+        ca_certificates: CA.to_vec(),
+        server_name: "safety-rules".into(),
+    }
+}
+
+fn server() -> (NetworkServer, SocketAddr) {
+    let server_port = utils::get_available_port();
+    let server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), server_port);
+    let server = NetworkServer::new("test", server_addr, TIMEOUT)
+        .with_tls(&tls_config(SERVER_CERT, SERVER_KEY))
+        .unwrap();
+    (server, server_addr)
+}
+
+#[test]
+fn test_accepted_client() {
+    let (mut server, server_addr) = server();
+    let server = thread::spawn(move || {
+        let request = server.read().unwrap();
+        server.write(&request).unwrap();
+    });
+
+    let mut client = NetworkClient::new("test", server_addr, TIMEOUT)
+        .with_tls(&tls_config(CLIENT_CERT, CLIENT_KEY))
+        .unwrap();
+    let data = vec![0, 1, 2, 3];
+    client.write(&data).unwrap();
+    assert_eq!(client.read().unwrap(), data);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_rejected_client() {
+    let (mut server, server_addr) = server();
+    let server = thread::spawn(move || server.read().unwrap_err());
+
+    let mut client = NetworkClient::new("test", server_addr, TIMEOUT)
+        .with_tls(&tls_config(UNTRUSTED_CLIENT_CERT, UNTRUSTED_CLIENT_KEY))
+        .unwrap();
+    // With TLS 1.3 the client completes its side of the handshake, and only finds out on its
+    // next read that the server rejected its certificate
+    let error = client
+        .write(&[0, 1, 2, 3])
+        .and_then(|_| client.read())
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        Error::TlsHandshakeFailed(_) | Error::TlsUnauthorized(_)
+    ));
+    assert!(matches!(server.join().unwrap(), Error::TlsUnauthorized(_)));
+}
+
+#[test]
+fn test_plaintext_client() {
+    let (mut server, server_addr) = server();
+    let server = thread::spawn(move || server.read().unwrap_err());
+
+    let mut client = NetworkClient::new("test", server_addr, TIMEOUT);
+    client.write(&[0, 1, 2, 3]).unwrap();
+    assert!(matches!(
+        server.join().unwrap(),
+        Error::TlsHandshakeFailed(_)
+    ));
+}
+
+#[test]
+fn test_untrusted_server() {
+    let (mut server, server_addr) = server();
+    let server = thread::spawn(move || server.read().unwrap_err());
+
+    // The client does not trust the authority that signed the server certificate
+    let mut config = tls_config(CLIENT_CERT, CLIENT_KEY);
+    config.ca_certificates = include_bytes!("certs/untrusted_ca.pem").to_vec();
+    let mut client = NetworkClient::new("test", server_addr, TIMEOUT)
+        .with_tls(&config)
+        .unwrap();
+    assert!(matches!(
+        client.write(&[0, 1, 2, 3]).unwrap_err(),
+        Error::TlsUnauthorized(_)
+    ));
+    server.join().unwrap();
+}
@@ -0,0 +1,264 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Error;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time,
+};
+
+const ACCESS_KEY_ID_VAR: &str = "AWS_ACCESS_KEY_ID";
+const SECRET_ACCESS_KEY_VAR: &str = "AWS_SECRET_ACCESS_KEY";
+const SESSION_TOKEN_VAR: &str = "AWS_SESSION_TOKEN";
+const PROFILE_VAR: &str = "AWS_PROFILE";
+const SHARED_CREDENTIALS_FILE_VAR: &str = "AWS_SHARED_CREDENTIALS_FILE";
+const DEFAULT_PROFILE: &str = "default";
+
+const INSTANCE_METADATA_HOST: &str = "http://169.254.169.254";
+const INSTANCE_METADATA_TIMEOUT_MS: u64 = 1_000;
+const INSTANCE_METADATA_TOKEN_TTL_SECS: &str = "21600";
+
+/// Temporary credentials are refreshed this long before they expire.
+const EXPIRATION_MARGIN_SECS: i64 = 300;
+
+/// Credentials for signing requests to AWS.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+    /// When temporary credentials expire, after which they must be resolved again.
+    pub expiration: Option<DateTime<Utc>>,
+}
+
+impl Credentials {
+    pub fn new(
+        access_key_id: String,
+        secret_access_key: String,
+        session_token: Option<String>,
+    ) -> Self {
+        Self {
+            access_key_id,
+            secret_access_key,
+            session_token,
+            expiration: None,
+        }
+    }
+
+    /// Resolves credentials like the standard AWS credential chain does, from the first of:
+    /// * the `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN` variables,
+    /// * the `AWS_PROFILE` (or default) profile of the shared credentials file, which is
+    /// `AWS_SHARED_CREDENTIALS_FILE` or `~/.aws/credentials`,
+    /// * the role of the EC2 instance, through the instance metadata service (IMDSv2).
+    pub fn from_chain() -> Result<Self, Error> {
+        if let Some(credentials) = Self::from_env() {
+            return Ok(credentials);
+        }
+        if let Some(path) = shared_credentials_file() {
+            let profile = env::var(PROFILE_VAR).unwrap_or_else(|_| DEFAULT_PROFILE.into());
+            if let Some(credentials) = Self::from_profile(&path, &profile)? {
+                return Ok(credentials);
+            }
+        }
+        Self::from_instance_metadata()
+    }
+
+    pub fn from_env() -> Option<Self> {
+        let access_key_id = env::var(ACCESS_KEY_ID_VAR).ok()?;
+        let secret_access_key = env::var(SECRET_ACCESS_KEY_VAR).ok()?;
+        Some(Self::new(
+            access_key_id,
+            secret_access_key,
+            env::var(SESSION_TOKEN_VAR).ok(),
+        ))
+    }
+
+    /// Reads the credentials of the profile from a shared credentials file, if both exist.
+    pub fn from_profile(path: &Path, profile: &str) -> Result<Option<Self>, Error> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(Error::CredentialsError(error.to_string())),
+        };
+        parse_profile(&contents, profile)
+    }
+
+    pub fn from_instance_metadata() -> Result<Self, Error> {
+        let token = instance_metadata_request("PUT", "/latest/api/token", None)?;
+        let role = instance_metadata_request(
+            "GET",
+            "/latest/meta-data/iam/security-credentials/",
+            Some(&token),
+        )?;
+        let role = role.lines().next().unwrap_or_default().trim();
+        let response = instance_metadata_request(
+            "GET",
+            &format!("/latest/meta-data/iam/security-credentials/{}", role),
+            Some(&token),
+        )?;
+        let response: InstanceMetadataCredentials = serde_json::from_str(&response)?;
+        let expiration = DateTime::parse_from_rfc3339(&response.expiration)
+            .map_err(|error| Error::CredentialsError(error.to_string()))?;
+        Ok(Self {
+            access_key_id: response.access_key_id,
+            secret_access_key: response.secret_access_key,
+            session_token: Some(response.token),
+            expiration: Some(expiration.with_timezone(&Utc)),
+        })
+    }
+
+    /// Returns whether the credentials expire within the refresh margin of `now`.
+    pub fn is_expiring(&self, now: DateTime<Utc>) -> bool {
+        self.expiration.map_or(false, |expiration| {
+            now + Duration::seconds(EXPIRATION_MARGIN_SECS) >= expiration
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct InstanceMetadataCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    token: String,
+    expiration: String,
+}
+
+fn shared_credentials_file() -> Option<PathBuf> {
+    match env::var(SHARED_CREDENTIALS_FILE_VAR) {
+        Ok(path) => Some(PathBuf::from(path)),
+        Err(_) => env::var("HOME")
+            .ok()
+            .map(|home| PathBuf::from(home).join(".aws").join("credentials")),
+    }
+}
+
+/// Parses the INI formatted shared credentials file, returning the credentials of the profile if
+/// it is present.
+fn parse_profile(contents: &str, profile: &str) -> Result<Option<Credentials>, Error> {
+    let mut in_profile = false;
+    let mut access_key_id = None;
+    let mut secret_access_key = None;
+    let mut session_token = None;
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            in_profile = line[1..line.len() - 1].trim() == profile;
+            continue;
+        }
+        if !in_profile {
+            continue;
+        }
+        if let Some((name, value)) = line.split_once('=') {
+            let value = Some(value.trim().to_string());
+            match name.trim() {
+                "aws_access_key_id" => access_key_id = value,
+                "aws_secret_access_key" => secret_access_key = value,
+                "aws_session_token" => session_token = value,
+                _ => (),
+            }
+        }
+    }
+
+    match (access_key_id, secret_access_key) {
+        (Some(access_key_id), Some(secret_access_key)) => Ok(Some(Credentials::new(
+            access_key_id,
+            secret_access_key,
+            session_token,
+        ))),
+        (None, None) => Ok(None),
+        _ => Err(Error::CredentialsError(format!(
+            "Profile {} is missing an access key id or secret access key",
+            profile
+        ))),
+    }
+}
+
+fn instance_metadata_request(
+    method: &str,
+    path: &str,
+    token: Option<&str>,
+) -> Result<String, Error> {
+    let mut request = ureq::request(method, &format!("{}{}", INSTANCE_METADATA_HOST, path));
+    request.timeout_connect(INSTANCE_METADATA_TIMEOUT_MS);
+    request.timeout(time::Duration::from_millis(INSTANCE_METADATA_TIMEOUT_MS));
+    match token {
+        Some(token) => request.set("X-aws-ec2-metadata-token", token),
+        None => request.set(
+            "X-aws-ec2-metadata-token-ttl-seconds",
+            INSTANCE_METADATA_TOKEN_TTL_SECS,
+        ),
+    };
+    let response = request.call();
+    if response.ok() {
+        Ok(response.into_string()?)
+    } else {
+        Err(Error::CredentialsError(format!(
+            "Instance metadata {} returned {}",
+            path,
+            response.status()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CREDENTIALS_FILE: &str = "
+# Comments and unrelated profiles are skipped
+[default]
+aws_access_key_id = default_id
+aws_secret_access_key = default_secret
+
+[validator]
+aws_access_key_id=validator_id
+aws_secret_access_key=validator_secret
+aws_session_token = validator_token
+region = us-west-2
+
+[incomplete]
+aws_access_key_id = incomplete_id
+";
+
+    #[test]
+    fn test_parse_profile() {
+        assert_eq!(
+            parse_profile(CREDENTIALS_FILE, "default").unwrap(),
+            Some(Credentials::new(
+                "default_id".into(),
+                "default_secret".into(),
+                None
+            ))
+        );
+        assert_eq!(
+            parse_profile(CREDENTIALS_FILE, "validator").unwrap(),
+            Some(Credentials::new(
+                "validator_id".into(),
+                "validator_secret".into(),
+                Some("validator_token".into())
+            ))
+        );
+        assert_eq!(parse_profile(CREDENTIALS_FILE, "missing").unwrap(), None);
+        assert!(matches!(
+            parse_profile(CREDENTIALS_FILE, "incomplete"),
+            Err(Error::CredentialsError(_))
+        ));
+    }
+
+    #[test]
+    fn test_is_expiring() {
+        let now = Utc::now();
+        let mut credentials = Credentials::new("id".into(), "secret".into(), None);
+        assert!(!credentials.is_expiring(now));
+        credentials.expiration = Some(now + Duration::seconds(EXPIRATION_MARGIN_SECS + 1));
+        assert!(!credentials.is_expiring(now));
+        credentials.expiration = Some(now + Duration::seconds(EXPIRATION_MARGIN_SECS));
+        assert!(credentials.is_expiring(now));
+    }
+}
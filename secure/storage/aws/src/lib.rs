@@ -0,0 +1,324 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+pub mod credentials;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock;
+pub mod signature;
+
+pub use crate::credentials::Credentials;
+
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+use thiserror::Error;
+
+/// Default request timeouts for Secrets Manager operations. As for the vault client, the
+/// connection timeout of ureq 1.5.4 overrides all other timeouts.
+const DEFAULT_CONNECTION_TIMEOUT_MS: u64 = 1_000;
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 1_000;
+
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+const SERVICE: &str = "secretsmanager";
+const TARGET_PREFIX: &str = "secretsmanager";
+
+/// The staging labels Secrets Manager maintains for the current and previous versions of a
+/// secret. Writing a new value moves the current label to it, and the previous label to the
+/// version that was current until then.
+pub const CURRENT_STAGE: &str = "AWSCURRENT";
+pub const PREVIOUS_STAGE: &str = "AWSPREVIOUS";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("AWS error, status code: {0}, type: {1}, message: {2}")]
+    ServiceError(u16, String, String),
+    #[error("Unable to resolve AWS credentials: {0}")]
+    CredentialsError(String),
+    #[error("Internal error: {0}")]
+    InternalError(String),
+    #[error("404: Not Found: {0}")]
+    NotFound(String),
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::SerializationError(format!("{}", error))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::SerializationError(format!("{}", error))
+    }
+}
+
+impl From<ureq::Response> for Error {
+    fn from(resp: ureq::Response) -> Self {
+        if let Some(error) = resp.synthetic_error() {
+            return Error::InternalError(error.to_string());
+        }
+
+        let status = resp.status();
+        let body = match resp.into_string() {
+            Ok(body) => body,
+            Err(error) => return Error::InternalError(error.to_string()),
+        };
+        match serde_json::from_str::<ErrorResponse>(&body) {
+            // The type may be qualified, e.g., "com.amazonaws.secretsmanager#ResourceNotFound"
+            Ok(error) => Error::ServiceError(
+                status,
+                error
+                    .error_type
+                    .rsplit('#')
+                    .next()
+                    .unwrap_or_default()
+                    .into(),
+                error.message.unwrap_or_default(),
+            ),
+            Err(_) => Error::ServiceError(status, String::new(), body),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    #[serde(rename = "__type")]
+    error_type: String,
+    #[serde(alias = "Message")]
+    message: Option<String>,
+}
+
+/// Client provides a client around the JSON interface to AWS Secrets Manager. Learn more here:
+/// https://docs.aws.amazon.com/secretsmanager/latest/apireference/Welcome.html
+///
+/// Requests are signed with AWS Signature Version 4. Credentials are either provided up front, or
+/// resolved through the standard credential chain on first use and whenever they expire.
+pub struct Client {
+    agent: ureq::Agent,
+    /// The Secrets Manager endpoint, e.g., https://secretsmanager.us-west-2.amazonaws.com.
+    endpoint: String,
+    /// The authority of the endpoint, which is signed as the host header.
+    host: String,
+    region: String,
+    credentials: Mutex<Option<Credentials>>,
+
+    /// Timeout for new socket connections to AWS.
+    connection_timeout_ms: u64,
+    /// Timeout for generic AWS responses (e.g., reads and writes).
+    response_timeout_ms: u64,
+}
+
+impl Client {
+    /// Creates a client for the region. The endpoint defaults to the regional Secrets Manager
+    /// endpoint, but can be overridden, e.g., to use a VPC endpoint or localstack.
+    pub fn new(
+        region: String,
+        endpoint: Option<String>,
+        credentials: Option<Credentials>,
+        connection_timeout_ms: Option<u64>,
+        response_timeout_ms: Option<u64>,
+    ) -> Self {
+        let endpoint = endpoint
+            .map(|endpoint| endpoint.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| format!("https://{}.{}.amazonaws.com", SERVICE, region));
+        let host = endpoint
+            .split("://")
+            .last()
+            .unwrap_or_default()
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        Self {
+            agent: ureq::Agent::new().set("connection", "keep-alive").build(),
+            endpoint,
+            host,
+            region,
+            credentials: Mutex::new(credentials),
+            connection_timeout_ms: connection_timeout_ms.unwrap_or(DEFAULT_CONNECTION_TIMEOUT_MS),
+            response_timeout_ms: response_timeout_ms.unwrap_or(DEFAULT_RESPONSE_TIMEOUT_MS),
+        }
+    }
+
+    /// Creates a new secret with the given initial value.
+    pub fn create_secret(&self, name: &str, secret_string: &str) -> Result<(), Error> {
+        self.call::<Value>(
+            "CreateSecret",
+            json!({ "Name": name, "SecretString": secret_string }),
+        )?;
+        Ok(())
+    }
+
+    /// Deletes the secret and all of its versions at once, without a recovery window.
+    pub fn delete_secret(&self, secret_id: &str) -> Result<(), Error> {
+        self.call::<Value>(
+            "DeleteSecret",
+            json!({ "SecretId": secret_id, "ForceDeleteWithoutRecovery": true }),
+        )
+        .map_err(|error| not_found(error, secret_id))?;
+        Ok(())
+    }
+
+    /// Reads the version of the secret with the given staging label, or the current version.
+    pub fn get_secret_value(
+        &self,
+        secret_id: &str,
+        version_stage: Option<&str>,
+    ) -> Result<SecretValue, Error> {
+        let payload = match version_stage {
+            Some(stage) => json!({ "SecretId": secret_id, "VersionStage": stage }),
+            None => json!({ "SecretId": secret_id }),
+        };
+        self.call("GetSecretValue", payload)
+            .map_err(|error| not_found(error, secret_id))
+    }
+
+    /// Lists the names of all secrets that start with the given prefix.
+    pub fn list_secrets(&self, name_prefix: &str) -> Result<Vec<String>, Error> {
+        let mut names = vec![];
+        let mut next_token: Option<String> = None;
+        loop {
+            let mut payload = json!({});
+            // The name filter is a case-insensitive prefix match, so names are filtered again
+            if !name_prefix.is_empty() {
+                payload["Filters"] = json!([{ "Key": "name", "Values": [name_prefix] }]);
+            }
+            if let Some(token) = next_token {
+                payload["NextToken"] = json!(token);
+            }
+            let response: ListSecretsResponse = self.call("ListSecrets", payload)?;
+            names.extend(
+                response
+                    .secret_list
+                    .into_iter()
+                    .map(|secret| secret.name)
+                    .filter(|name| name.starts_with(name_prefix)),
+            );
+            next_token = response.next_token;
+            if next_token.is_none() {
+                return Ok(names);
+            }
+        }
+    }
+
+    /// Writes a new version of the secret, which becomes its current version. Returns the id of
+    /// the new version.
+    pub fn put_secret_value(&self, secret_id: &str, secret_string: &str) -> Result<String, Error> {
+        let response: PutSecretValueResponse = self
+            .call(
+                "PutSecretValue",
+                json!({ "SecretId": secret_id, "SecretString": secret_string }),
+            )
+            .map_err(|error| not_found(error, secret_id))?;
+        Ok(response.version_id)
+    }
+
+    fn call<T: DeserializeOwned>(&self, action: &str, payload: Value) -> Result<T, Error> {
+        let payload = serde_json::to_vec(&payload)?;
+        let credentials = self.credentials()?;
+        let now = Utc::now();
+
+        let mut headers = BTreeMap::new();
+        headers.insert("content-type".to_string(), CONTENT_TYPE.to_string());
+        headers.insert("host".to_string(), self.host.clone());
+        headers.insert(
+            signature::AMZ_DATE_HEADER.to_string(),
+            signature::amz_date(now),
+        );
+        headers.insert(
+            "x-amz-target".to_string(),
+            format!("{}.{}", TARGET_PREFIX, action),
+        );
+        if let Some(token) = &credentials.session_token {
+            headers.insert(signature::SECURITY_TOKEN_HEADER.to_string(), token.clone());
+        }
+        let authorization = signature::authorization(
+            &credentials,
+            &self.region,
+            SERVICE,
+            now,
+            "POST",
+            "/",
+            &headers,
+            &payload,
+        );
+
+        let mut request = self.agent.post(&format!("{}/", self.endpoint));
+        for (name, value) in &headers {
+            request.set(name, value);
+        }
+        request.set("authorization", &authorization);
+        request.timeout_connect(self.connection_timeout_ms);
+        request.timeout(Duration::from_millis(self.response_timeout_ms));
+        let resp = request.send_bytes(&payload);
+
+        if resp.ok() {
+            Ok(serde_json::from_str(&resp.into_string()?)?)
+        } else {
+            Err(resp.into())
+        }
+    }
+
+    /// Returns the credentials, first resolving them if they are missing or about to expire.
+    fn credentials(&self) -> Result<Credentials, Error> {
+        let mut credentials = self
+            .credentials
+            .lock()
+            .map_err(|error| Error::InternalError(error.to_string()))?;
+        match &*credentials {
+            Some(current) if !current.is_expiring(Utc::now()) => Ok(current.clone()),
+            _ => {
+                let resolved = Credentials::from_chain()?;
+                *credentials = Some(resolved.clone());
+                Ok(resolved)
+            }
+        }
+    }
+}
+
+/// Replaces a ResourceNotFoundException with NotFound for the secret.
+fn not_found(error: Error, secret_id: &str) -> Error {
+    match error {
+        Error::ServiceError(_, error_type, _) if error_type == "ResourceNotFoundException" => {
+            Error::NotFound(secret_id.into())
+        }
+        error => error,
+    }
+}
+
+/// A version of a secret.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct SecretValue {
+    pub name: String,
+    pub version_id: String,
+    pub secret_string: String,
+    /// Seconds since the Unix epoch at which the version was created.
+    pub created_date: f64,
+    pub version_stages: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListSecretsResponse {
+    secret_list: Vec<SecretListEntry>,
+    next_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SecretListEntry {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PutSecretValueResponse {
+    version_id: String,
+}
@@ -0,0 +1,362 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! An in-process stand-in for AWS Secrets Manager, for testing clients without an AWS account or
+//! localstack. It serves the JSON protocol over HTTP on a local port, checks the signature of
+//! every request against the credentials it was created with, and keeps secrets in memory.
+
+use crate::{signature, Credentials, CURRENT_STAGE, PREVIOUS_STAGE};
+use aptos_http_mock::{HttpMock, Request, Response};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub const REGION: &str = "us-west-2";
+
+/// ListSecrets returns at most this many secrets per page, so that pagination is exercised.
+const PAGE_SIZE: usize = 2;
+
+struct SecretVersion {
+    version_id: String,
+    secret_string: String,
+    created_date: f64,
+    stages: Vec<String>,
+}
+
+#[derive(Default)]
+struct State {
+    secrets: BTreeMap<String, Vec<SecretVersion>>,
+    next_version: u64,
+}
+
+/// A mock Secrets Manager that runs until the process exits.
+pub struct MockSecretsManager {
+    server: HttpMock,
+    credentials: Credentials,
+}
+
+impl MockSecretsManager {
+    pub fn start() -> Self {
+        let credentials = Credentials::new("mock_access_key".into(), "mock_secret".into(), None);
+        let state = Mutex::new(State::default());
+        let server_credentials = credentials.clone();
+        let server = HttpMock::start(
+            "mock-secrets-manager",
+            move |request| match check_signature(&request, &server_credentials) {
+                Ok(()) => handle(&request, &mut state.lock().unwrap()),
+                Err(response) => response,
+            },
+        )
+        .expect("Unable to start the mock");
+
+        Self {
+            server,
+            credentials,
+        }
+    }
+
+    pub fn endpoint(&self) -> String {
+        self.server.url().into()
+    }
+
+    /// The credentials that requests must be signed with.
+    pub fn credentials(&self) -> Credentials {
+        self.credentials.clone()
+    }
+}
+
+fn check_signature(request: &Request, credentials: &Credentials) -> Result<(), Response> {
+    let invalid = |message: &str| {
+        Err((
+            400,
+            Some(json!({ "__type": "InvalidSignatureException", "message": message })),
+        ))
+    };
+    let authorization = match request.header("authorization") {
+        Some(authorization) => authorization,
+        None => return invalid("Missing authorization"),
+    };
+    let time = match request
+        .header(signature::AMZ_DATE_HEADER)
+        .and_then(|date| NaiveDateTime::parse_from_str(date, "%Y%m%dT%H%M%SZ").ok())
+    {
+        Some(time) => DateTime::<Utc>::from_utc(time, Utc),
+        None => return invalid("Missing or invalid x-amz-date"),
+    };
+    let signed_headers = match authorization
+        .split("SignedHeaders=")
+        .nth(1)
+        .and_then(|rest| rest.split(',').next())
+    {
+        Some(signed_headers) => signed_headers,
+        None => return invalid("Missing signed headers"),
+    };
+    let mut signed = BTreeMap::new();
+    for name in signed_headers.split(';') {
+        match request.header(name) {
+            Some(value) => signed.insert(name.to_string(), value.to_string()),
+            None => return invalid("Missing signed header"),
+        };
+    }
+
+    let expected = signature::authorization(
+        credentials,
+        REGION,
+        "secretsmanager",
+        time,
+        "POST",
+        "/",
+        &signed,
+        &request.body,
+    );
+    if authorization != expected {
+        return invalid("The request signature does not match");
+    }
+    Ok(())
+}
+
+fn handle(request: &Request, state: &mut State) -> Response {
+    let action = request
+        .header("x-amz-target")
+        .and_then(|target| target.strip_prefix("secretsmanager."))
+        .unwrap_or_default();
+    let request = request.json();
+    let result = match action {
+        "CreateSecret" => create_secret(state, &request),
+        "DeleteSecret" => delete_secret(state, &request),
+        "GetSecretValue" => get_secret_value(state, &request),
+        "ListSecrets" => list_secrets(state, &request),
+        "PutSecretValue" => put_secret_value(state, &request),
+        _ => Err(("InvalidAction", format!("Unknown action {}", action))),
+    };
+    match result {
+        Ok(response) => (200, Some(response)),
+        Err((error_type, message)) => (
+            400,
+            Some(json!({
+                "__type": format!("com.amazonaws.secretsmanager#{}", error_type),
+                "Message": message
+            })),
+        ),
+    }
+}
+
+type ActionResult = Result<Value, (&'static str, String)>;
+
+fn field<'a>(request: &'a Value, name: &str) -> Result<&'a str, (&'static str, String)> {
+    request[name]
+        .as_str()
+        .ok_or_else(|| ("InvalidParameterException", format!("Missing {}", name)))
+}
+
+fn not_found(secret_id: &str) -> (&'static str, String) {
+    (
+        "ResourceNotFoundException",
+        format!(
+            "Secrets Manager can't find the specified secret: {}",
+            secret_id
+        ),
+    )
+}
+
+fn new_version(state: &mut State, secret_string: &str) -> SecretVersion {
+    state.next_version += 1;
+    let created_date = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64();
+    SecretVersion {
+        version_id: format!("version-{}", state.next_version),
+        secret_string: secret_string.into(),
+        created_date,
+        stages: vec![CURRENT_STAGE.into()],
+    }
+}
+
+fn create_secret(state: &mut State, request: &Value) -> ActionResult {
+    let name = field(request, "Name")?;
+    if state.secrets.contains_key(name) {
+        return Err((
+            "ResourceExistsException",
+            format!("The secret {} already exists", name),
+        ));
+    }
+    let version = new_version(state, field(request, "SecretString")?);
+    let version_id = version.version_id.clone();
+    state.secrets.insert(name.into(), vec![version]);
+    Ok(json!({ "Name": name, "VersionId": version_id }))
+}
+
+fn delete_secret(state: &mut State, request: &Value) -> ActionResult {
+    let name = field(request, "SecretId")?;
+    state.secrets.remove(name).ok_or_else(|| not_found(name))?;
+    Ok(json!({ "Name": name }))
+}
+
+fn get_secret_value(state: &mut State, request: &Value) -> ActionResult {
+    let name = field(request, "SecretId")?;
+    let stage = request["VersionStage"].as_str().unwrap_or(CURRENT_STAGE);
+    let version = state
+        .secrets
+        .get(name)
+        .and_then(|versions| {
+            versions
+                .iter()
+                .find(|version| version.stages.iter().any(|s| s == stage))
+        })
+        .ok_or_else(|| not_found(name))?;
+    Ok(json!({
+        "Name": name,
+        "VersionId": version.version_id,
+        "SecretString": version.secret_string,
+        "CreatedDate": version.created_date,
+        "VersionStages": version.stages,
+    }))
+}
+
+fn list_secrets(state: &mut State, request: &Value) -> ActionResult {
+    // Like Secrets Manager, the name filter is a case-insensitive prefix match
+    let prefix = request["Filters"][0]["Values"][0]
+        .as_str()
+        .unwrap_or_default()
+        .to_lowercase();
+    let start: usize = request["NextToken"]
+        .as_str()
+        .and_then(|token| token.parse().ok())
+        .unwrap_or(0);
+    let names: Vec<_> = state
+        .secrets
+        .keys()
+        .filter(|name| name.to_lowercase().starts_with(&prefix))
+        .collect();
+    let page: Vec<_> = names
+        .iter()
+        .skip(start)
+        .take(PAGE_SIZE)
+        .map(|name| json!({ "Name": name }))
+        .collect();
+    let mut response = json!({ "SecretList": page });
+    if start + PAGE_SIZE < names.len() {
+        response["NextToken"] = json!((start + PAGE_SIZE).to_string());
+    }
+    Ok(response)
+}
+
+fn put_secret_value(state: &mut State, request: &Value) -> ActionResult {
+    let name = field(request, "SecretId")?;
+    let secret_string = field(request, "SecretString")?;
+    if !state.secrets.contains_key(name) {
+        return Err(not_found(name));
+    }
+    let version = new_version(state, secret_string);
+    let version_id = version.version_id.clone();
+
+    // The current version becomes the previous one, and versions without a stage are dropped
+    let versions = state.secrets.get_mut(name).unwrap();
+    for version in versions.iter_mut() {
+        version.stages.retain(|stage| stage == CURRENT_STAGE);
+        if !version.stages.is_empty() {
+            version.stages = vec![PREVIOUS_STAGE.into()];
+        }
+    }
+    versions.retain(|version| !version.stages.is_empty());
+    versions.push(version);
+    Ok(json!({ "Name": name, "VersionId": version_id }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Client, Error};
+
+    fn client(mock: &MockSecretsManager) -> Client {
+        Client::new(
+            REGION.into(),
+            Some(mock.endpoint()),
+            Some(mock.credentials()),
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_versions() {
+        let mock = MockSecretsManager::start();
+        let client = client(&mock);
+        assert_eq!(
+            client.get_secret_value("secret", None).unwrap_err(),
+            Error::NotFound("secret".into())
+        );
+        assert_eq!(
+            client.put_secret_value("secret", "0").unwrap_err(),
+            Error::NotFound("secret".into())
+        );
+
+        client.create_secret("secret", "0").unwrap();
+        assert!(matches!(
+            client.create_secret("secret", "0"),
+            Err(Error::ServiceError(400, error_type, _)) if error_type == "ResourceExistsException"
+        ));
+        assert_eq!(
+            client
+                .get_secret_value("secret", Some(PREVIOUS_STAGE))
+                .unwrap_err(),
+            Error::NotFound("secret".into())
+        );
+
+        for value in 1..3 {
+            client
+                .put_secret_value("secret", &value.to_string())
+                .unwrap();
+            let current = client.get_secret_value("secret", None).unwrap();
+            assert_eq!(current.secret_string, value.to_string());
+            assert_eq!(current.version_stages, vec![CURRENT_STAGE]);
+            let previous = client
+                .get_secret_value("secret", Some(PREVIOUS_STAGE))
+                .unwrap();
+            assert_eq!(previous.secret_string, (value - 1).to_string());
+        }
+
+        client.delete_secret("secret").unwrap();
+        assert_eq!(
+            client.delete_secret("secret").unwrap_err(),
+            Error::NotFound("secret".into())
+        );
+    }
+
+    #[test]
+    fn test_list_secrets() {
+        let mock = MockSecretsManager::start();
+        let client = client(&mock);
+        for name in ["a/1", "a/2", "A/3", "a/4", "a/5", "b/1"] {
+            client.create_secret(name, "").unwrap();
+        }
+        assert_eq!(
+            client.list_secrets("a/").unwrap(),
+            vec!["a/1", "a/2", "a/4", "a/5"]
+        );
+        assert_eq!(client.list_secrets("").unwrap().len(), 6);
+        assert!(client.list_secrets("c/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        let mock = MockSecretsManager::start();
+        let credentials = Credentials::new("mock_access_key".into(), "wrong".into(), None);
+        let client = Client::new(
+            REGION.into(),
+            Some(mock.endpoint()),
+            Some(credentials),
+            None,
+            None,
+        );
+        assert!(matches!(
+            client.get_secret_value("secret", None),
+            Err(Error::ServiceError(400, error_type, _)) if error_type == "InvalidSignatureException"
+        ));
+    }
+}
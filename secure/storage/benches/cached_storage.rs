@@ -0,0 +1,55 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_secure_storage::{CachedStorage, ChaosPolicy, ChaosStorage, InMemoryStorage, KVStorage};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::time::Duration;
+
+/// Simulated round trip to a remote backend
+const LATENCY: Duration = Duration::from_micros(200);
+const KEYS: [&str; 4] = ["safety_data", "waypoint", "owner_account", "execution"];
+
+fn remote_storage() -> ChaosStorage<InMemoryStorage> {
+    let mut storage = InMemoryStorage::new();
+    for key in KEYS {
+        storage.set(key, key).unwrap();
+    }
+    ChaosStorage::new(storage, ChaosPolicy::default().latency(LATENCY))
+}
+
+/// Reads every key `n` times
+fn read<S: KVStorage>(storage: &S, n: u64) {
+    for _ in 0..n {
+        for key in KEYS {
+            black_box(storage.get::<String>(key).unwrap());
+        }
+    }
+}
+
+pub fn benchmark(c: &mut Criterion) {
+    let count = 100;
+    let duration_secs = 5;
+    let samples = 10;
+
+    let uncached = remote_storage();
+    let cached = CachedStorage::new(remote_storage(), Duration::from_secs(60));
+
+    let mut group = c.benchmark_group("CachedStorage");
+    group
+        .measurement_time(Duration::from_secs(duration_secs))
+        .sample_size(samples);
+    group.bench_function("Uncached", |b| b.iter(|| read(&uncached, black_box(count))));
+    group.bench_function("Cached", |b| b.iter(|| read(&cached, black_box(count))));
+    group.finish();
+
+    println!(
+        "Backend calls: {} uncached, {} cached ({} hits, {} misses)",
+        uncached.operations(),
+        cached.bypass().operations(),
+        cached.hits(),
+        cached.misses()
+    );
+}
+
+criterion_group!(benches, benchmark);
+criterion_main!(benches);
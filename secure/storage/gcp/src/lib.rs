@@ -0,0 +1,322 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+pub mod credentials;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock;
+
+pub use crate::credentials::{Credentials, ServiceAccountKey};
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use std::{sync::Mutex, time::Duration};
+use thiserror::Error;
+
+/// Default request timeouts for Secret Manager operations. As for the vault client, the
+/// connection timeout of ureq 1.5.4 overrides all other timeouts.
+const DEFAULT_CONNECTION_TIMEOUT_MS: u64 = 1_000;
+const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 1_000;
+
+const DEFAULT_ENDPOINT: &str = "https://secretmanager.googleapis.com";
+/// Access tokens are refreshed this long before they expire.
+const TOKEN_EXPIRATION_MARGIN_SECS: u64 = 300;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum Error {
+    #[error("GCP error, status code: {0}, status: {1}, message: {2}")]
+    HttpError(u16, String, String),
+    #[error("Unable to obtain GCP credentials: {0}")]
+    CredentialsError(String),
+    #[error("Internal error: {0}")]
+    InternalError(String),
+    #[error("404: Not Found: {0}")]
+    NotFound(String),
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+}
+
+impl From<base64::DecodeError> for Error {
+    fn from(error: base64::DecodeError) -> Self {
+        Self::SerializationError(format!("{}", error))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::SerializationError(format!("{}", error))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::SerializationError(format!("{}", error))
+    }
+}
+
+impl From<ureq::Response> for Error {
+    fn from(resp: ureq::Response) -> Self {
+        if let Some(error) = resp.synthetic_error() {
+            return Error::InternalError(error.to_string());
+        }
+
+        let status = resp.status();
+        let body = match resp.into_string() {
+            Ok(body) => body,
+            Err(error) => return Error::InternalError(error.to_string()),
+        };
+        match serde_json::from_str::<ErrorResponse>(&body) {
+            Ok(response) => Error::HttpError(status, response.error.status, response.error.message),
+            Err(_) => Error::HttpError(status, String::new(), body),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ErrorStatus,
+}
+
+#[derive(Deserialize)]
+struct ErrorStatus {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    message: String,
+}
+
+/// Client provides a client around the REST interface to GCP Secret Manager. Learn more here:
+/// https://cloud.google.com/secret-manager/docs/reference/rest
+///
+/// Secrets hold a sequence of versions, numbered from 1, each of which holds an immutable value.
+pub struct Client {
+    agent: ureq::Agent,
+    /// The Secret Manager endpoint, e.g., https://secretmanager.googleapis.com.
+    endpoint: String,
+    project: String,
+    credentials: Credentials,
+    /// The current access token, and when it expires.
+    token: Mutex<Option<(String, Option<u64>)>>,
+
+    /// Timeout for new socket connections to GCP.
+    connection_timeout_ms: u64,
+    /// Timeout for generic GCP responses (e.g., reads and writes).
+    response_timeout_ms: u64,
+}
+
+impl Client {
+    /// Creates a client for the secrets of the project. The endpoint defaults to the global
+    /// Secret Manager endpoint, but can be overridden, e.g., to use a regional endpoint.
+    pub fn new(
+        project: String,
+        endpoint: Option<String>,
+        credentials: Credentials,
+        connection_timeout_ms: Option<u64>,
+        response_timeout_ms: Option<u64>,
+    ) -> Self {
+        Self {
+            agent: ureq::Agent::new().set("connection", "keep-alive").build(),
+            endpoint: endpoint
+                .map(|endpoint| endpoint.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| DEFAULT_ENDPOINT.into()),
+            project,
+            credentials,
+            token: Mutex::new(None),
+            connection_timeout_ms: connection_timeout_ms.unwrap_or(DEFAULT_CONNECTION_TIMEOUT_MS),
+            response_timeout_ms: response_timeout_ms.unwrap_or(DEFAULT_RESPONSE_TIMEOUT_MS),
+        }
+    }
+
+    /// Creates a new secret without any versions.
+    pub fn create_secret(&self, secret_id: &str) -> Result<(), Error> {
+        self.call::<Value>(
+            "POST",
+            &format!("{}?secretId={}", self.secrets_path(), secret_id),
+            Some(json!({ "replication": { "automatic": {} } })),
+        )?;
+        Ok(())
+    }
+
+    /// Deletes the secret and all of its versions.
+    pub fn delete_secret(&self, secret_id: &str) -> Result<(), Error> {
+        self.call::<Value>("DELETE", &self.secret_path(secret_id), None)
+            .map_err(|error| not_found(error, secret_id))?;
+        Ok(())
+    }
+
+    /// Reads the given version of the secret, or the latest version.
+    pub fn access_secret_version(
+        &self,
+        secret_id: &str,
+        version: Option<u64>,
+    ) -> Result<SecretVersion, Error> {
+        let version = version.map_or_else(|| "latest".into(), |version| version.to_string());
+        let path = format!(
+            "{}/versions/{}:access",
+            self.secret_path(secret_id),
+            version
+        );
+        let response: AccessSecretVersionResponse = self
+            .call("GET", &path, None)
+            .map_err(|error| not_found(error, secret_id))?;
+        Ok(SecretVersion {
+            version: version_number(&response.name)?,
+            data: base64::decode(&response.payload.data)?,
+        })
+    }
+
+    /// Adds a new version to the secret, which becomes its latest version. Returns the number of
+    /// the new version.
+    pub fn add_secret_version(&self, secret_id: &str, data: &[u8]) -> Result<u64, Error> {
+        let path = format!("{}:addVersion", self.secret_path(secret_id));
+        let payload = json!({ "payload": { "data": base64::encode(data) } });
+        let response: VersionResponse = self
+            .call("POST", &path, Some(payload))
+            .map_err(|error| not_found(error, secret_id))?;
+        version_number(&response.name)
+    }
+
+    /// Irrevocably destroys the value of the given version of the secret.
+    pub fn destroy_secret_version(&self, secret_id: &str, version: u64) -> Result<(), Error> {
+        let path = format!(
+            "{}/versions/{}:destroy",
+            self.secret_path(secret_id),
+            version
+        );
+        self.call::<Value>("POST", &path, Some(json!({})))
+            .map_err(|error| not_found(error, secret_id))?;
+        Ok(())
+    }
+
+    /// Lists the ids of all secrets in the project.
+    pub fn list_secrets(&self) -> Result<Vec<String>, Error> {
+        let mut secret_ids = vec![];
+        let mut page_token: Option<String> = None;
+        loop {
+            let path = match &page_token {
+                Some(token) => format!("{}?pageToken={}", self.secrets_path(), token),
+                None => self.secrets_path(),
+            };
+            let response: ListSecretsResponse = self.call("GET", &path, None)?;
+            for secret in response.secrets {
+                // Names are of the form projects/{project}/secrets/{secret_id}
+                let secret_id = secret.name.rsplit('/').next().unwrap_or_default();
+                secret_ids.push(secret_id.to_string());
+            }
+            page_token = response.next_page_token.filter(|token| !token.is_empty());
+            if page_token.is_none() {
+                return Ok(secret_ids);
+            }
+        }
+    }
+
+    fn secrets_path(&self) -> String {
+        format!("/v1/projects/{}/secrets", self.project)
+    }
+
+    fn secret_path(&self, secret_id: &str) -> String {
+        format!("{}/{}", self.secrets_path(), secret_id)
+    }
+
+    fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        payload: Option<Value>,
+    ) -> Result<T, Error> {
+        let token = self.access_token()?;
+        let mut request = self
+            .agent
+            .request(method, &format!("{}{}", self.endpoint, path));
+        request.set("authorization", &format!("Bearer {}", token));
+        request.timeout_connect(self.connection_timeout_ms);
+        request.timeout(Duration::from_millis(self.response_timeout_ms));
+        let resp = match payload {
+            Some(payload) => request.send_json(payload),
+            None => request.call(),
+        };
+
+        if resp.ok() {
+            Ok(serde_json::from_str(&resp.into_string()?)?)
+        } else {
+            Err(resp.into())
+        }
+    }
+
+    /// Returns the access token, first obtaining a new one if there is none or it is about to
+    /// expire.
+    fn access_token(&self) -> Result<String, Error> {
+        let mut token = self
+            .token
+            .lock()
+            .map_err(|error| Error::InternalError(error.to_string()))?;
+        let now = credentials::now_secs();
+        match &*token {
+            Some((current, expiration))
+                if expiration.map_or(true, |expiration| {
+                    now + TOKEN_EXPIRATION_MARGIN_SECS < expiration
+                }) =>
+            {
+                Ok(current.clone())
+            }
+            _ => {
+                let (new_token, expiration) = self.credentials.access_token()?;
+                *token = Some((new_token.clone(), expiration));
+                Ok(new_token)
+            }
+        }
+    }
+}
+
+/// Replaces a NOT_FOUND error with NotFound for the secret.
+fn not_found(error: Error, secret_id: &str) -> Error {
+    match error {
+        Error::HttpError(404, _, _) => Error::NotFound(secret_id.into()),
+        error => error,
+    }
+}
+
+/// Parses the number of a version from its name, projects/{project}/secrets/{id}/versions/{n}.
+fn version_number(name: &str) -> Result<u64, Error> {
+    name.rsplit('/')
+        .next()
+        .and_then(|version| version.parse().ok())
+        .ok_or_else(|| Error::SerializationError(format!("Invalid version name: {}", name)))
+}
+
+/// The value of a version of a secret.
+#[derive(Debug, PartialEq)]
+pub struct SecretVersion {
+    pub version: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct AccessSecretVersionResponse {
+    name: String,
+    payload: Payload,
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    data: String,
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListSecretsResponse {
+    #[serde(default)]
+    secrets: Vec<SecretResponse>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SecretResponse {
+    name: String,
+}
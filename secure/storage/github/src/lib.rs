@@ -3,6 +3,9 @@
 
 #![forbid(unsafe_code)]
 
+#[cfg(any(test, feature = "testing"))]
+pub mod mock;
+
 use proxy::Proxy;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -13,6 +16,8 @@ const ACCEPT_HEADER: &str = "Accept";
 const ACCEPT_VALUE: &str = "Accept: application/vnd.github.v3+json";
 const TIMEOUT: u64 = 10_000;
 const URL: &str = "https://api.github.com";
+/// The number of times a write is attempted while it conflicts with concurrent writes
+const WRITE_ATTEMPTS: usize = 3;
 
 #[derive(Debug, Error, PartialEq)]
 pub enum Error {
@@ -26,6 +31,8 @@ pub enum Error {
     NotFound(String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
+    #[error("Write conflicted with concurrent writes: {0}")]
+    WriteConflict(String),
 }
 
 impl From<std::io::Error> for Error {
@@ -68,6 +75,7 @@ pub struct Client {
     owner: String,
     repository: String,
     token: String,
+    url: String,
 }
 
 impl Client {
@@ -77,28 +85,42 @@ impl Client {
             owner,
             repository,
             token,
+            url: URL.into(),
         }
     }
 
-    /// Delete a file from a GitHub repository
-    pub fn delete_file(&self, path: &str) -> Result<(), Error> {
-        // Occasionally GitHub sends us back delayed results and the file is already deleted.
-        let hash = match self.get_sha(path) {
-            Ok(hash) => hash,
-            Err(Error::NotFound(_)) => return Ok(()),
-            Err(e) => return Err(e),
-        };
+    /// The branch that is read and written.
+    pub fn branch(&self) -> &str {
+        &self.branch
+    }
 
-        let resp = self
-            .upgrade_request(ureq::delete(&self.post_url(path)))
-            .send_json(
-                json!({ "branch": self.branch.to_string(), "message": "aptos-secure", "sha": hash }),
-            );
+    /// Sends requests to another API server than api.github.com, e.g., a GitHub Enterprise server
+    /// or a mock.
+    pub fn set_url(&mut self, url: String) {
+        self.url = url;
+    }
 
-        match resp.status() {
-            200 => Ok(()),
-            _ => Err(resp.into()),
-        }
+    /// Delete a file from a GitHub repository
+    pub fn delete_file(&self, path: &str) -> Result<(), Error> {
+        self.retry_on_conflict(path, || {
+            // Occasionally GitHub sends us back delayed results and the file is already deleted.
+            let hash = match self.get_sha(path) {
+                Ok(hash) => hash,
+                Err(Error::NotFound(_)) => return Ok(()),
+                Err(e) => return Err(e),
+            };
+
+            let resp = self
+                .upgrade_request(ureq::delete(&self.post_url(path)))
+                .send_json(
+                    json!({ "branch": self.branch.to_string(), "message": "aptos-secure", "sha": hash }),
+                );
+
+            match resp.status() {
+                200 => Ok(()),
+                _ => Err(resp.into()),
+            }
+        })
     }
 
     /// Recursively delete all files, which as a by product will delete all folders
@@ -116,7 +138,10 @@ impl Client {
 
     /// Retrieve a list of branches, this is effectively a status check on the repository
     pub fn get_branches(&self) -> Result<Vec<String>, Error> {
-        let url = format!("{}/repos/{}/{}/branches", URL, self.owner, self.repository);
+        let url = format!(
+            "{}/repos/{}/{}/branches",
+            self.url, self.owner, self.repository
+        );
         let resp = self.upgrade_request(ureq::get(&url)).call();
 
         match resp.status() {
@@ -147,33 +172,47 @@ impl Client {
 
     /// Retrieve the contents of a file.
     pub fn get_file(&self, path: &str) -> Result<String, Error> {
-        let value = self.get_internal(path)?;
-        if value.len() == 1 && value[0].path == path {
-            let content = value[0]
-                .content
-                .as_ref()
-                .ok_or_else(|| Error::InternalError("No content found".into()))?;
-            // Apparently GitHub introduces newlines every 60 characters and at the end of content,
-            // this strips those characters out.
-            Ok(content.lines().collect::<Vec<_>>().join(""))
-        } else {
-            Err(Error::InternalError(format!(
-                "get mismatch, found {} entries",
-                value.len()
-            )))
-        }
+        self.get_file_and_sha(path).map(|(content, _)| content)
     }
 
     /// Create or update a file.
     pub fn put(&self, path: &str, content: &str) -> Result<(), Error> {
-        let json = match self.get_sha(path) {
-            Ok(hash) => {
+        self.retry_on_conflict(path, || {
+            let sha = match self.get_sha(path) {
+                Ok(sha) => Some(sha),
+                Err(Error::NotFound(_)) => None,
+                Err(e) => return Err(e),
+            };
+            self.put_internal(path, content, sha)
+        })
+    }
+
+    /// Create or update a file with the content computed from its current content, if any. The
+    /// content is computed again whenever the write is retried after a conflict, so a concurrent
+    /// write is never overwritten without being seen.
+    pub fn update<F>(&self, path: &str, mut update: F) -> Result<(), Error>
+    where
+        F: FnMut(Option<&str>) -> Result<String, Error>,
+    {
+        self.retry_on_conflict(path, || {
+            let (content, sha) = match self.get_file_and_sha(path) {
+                Ok((content, sha)) => (update(Some(&content))?, Some(sha)),
+                Err(Error::NotFound(_)) => (update(None)?, None),
+                Err(e) => return Err(e),
+            };
+            self.put_internal(path, &content, sha)
+        })
+    }
+
+    /// Writes a file, conditioned on the sha of the file it replaces, if any.
+    fn put_internal(&self, path: &str, content: &str, sha: Option<String>) -> Result<(), Error> {
+        let json = match sha {
+            Some(hash) => {
                 json!({ "branch": self.branch.to_string(), "content": content, "message": format!("[aptos-management] {}", path), "sha": hash })
             }
-            Err(Error::NotFound(_)) => {
+            None => {
                 json!({ "branch": self.branch.to_string(), "content": content, "message": format!("[aptos-management] {}", path) })
             }
-            Err(e) => return Err(e),
         };
 
         let resp = self
@@ -187,6 +226,24 @@ impl Client {
         }
     }
 
+    /// Writes are conditioned on the sha of the file read beforehand, and GitHub answers with a
+    /// 409 if a concurrent write changed the file in between. The write, which reads the sha
+    /// again, is then retried, up to WRITE_ATTEMPTS times in total.
+    fn retry_on_conflict<F>(&self, path: &str, mut write: F) -> Result<(), Error>
+    where
+        F: FnMut() -> Result<(), Error>,
+    {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match write() {
+                Err(Error::HttpError(409, _, _)) if attempts < WRITE_ATTEMPTS => continue,
+                Err(Error::HttpError(409, _, _)) => return Err(Error::WriteConflict(path.into())),
+                result => return result,
+            }
+        }
+    }
+
     /// Simple wrapper around requests to add default parameters to the request
     fn upgrade_request(&self, mut request: ureq::Request) -> ureq::Request {
         request
@@ -229,6 +286,26 @@ impl Client {
         }
     }
 
+    /// Returns the contents of a file and its sha hash, read together
+    fn get_file_and_sha(&self, path: &str) -> Result<(String, String), Error> {
+        let value = self.get_internal(path)?;
+        if value.len() == 1 && value[0].path == path {
+            let content = value[0]
+                .content
+                .as_ref()
+                .ok_or_else(|| Error::InternalError("No content found".into()))?;
+            // Apparently GitHub introduces newlines every 60 characters and at the end of content,
+            // this strips those characters out.
+            let content = content.lines().collect::<Vec<_>>().join("");
+            Ok((content, value[0].sha.clone()))
+        } else {
+            Err(Error::InternalError(format!(
+                "get mismatch, found {} entries",
+                value.len()
+            )))
+        }
+    }
+
     /// Returns the sha hash of a file according to GitHub
     fn get_sha(&self, path: &str) -> Result<String, Error> {
         let value = self.get_internal(path)?;
@@ -246,14 +323,14 @@ impl Client {
     fn post_url(&self, path: &str) -> String {
         format!(
             "{}/repos/{}/{}/contents/{}",
-            URL, self.owner, self.repository, path
+            self.url, self.owner, self.repository, path
         )
     }
 
     fn get_url(&self, path: &str) -> String {
         format!(
             "{}/repos/{}/{}/contents/{}?ref={}",
-            URL, self.owner, self.repository, path, self.branch
+            self.url, self.owner, self.repository, path, self.branch
         )
     }
 }
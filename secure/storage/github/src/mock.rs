@@ -0,0 +1,272 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Client;
+use aptos_http_mock::{HttpMock, Request, Response};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    sync::{Arc, Mutex},
+};
+
+const OWNER: &str = "mock_owner";
+const REPOSITORY: &str = "mock_repository";
+const TOKEN: &str = "mock_token";
+
+/// A minimal GitHub API server running within the process, for tests that do not require a
+/// repository on GitHub. It serves the branches and the contents of a single repository, checks
+/// the sha of each write like GitHub does, and can make writes conflict as if another writer
+/// committed to the file concurrently. The owner, repository and token are not checked, but all
+/// requests are recorded. The server runs until the process exits.
+pub struct MockGitHub {
+    server: HttpMock,
+    state: Arc<Mutex<MockState>>,
+}
+
+/// A request received by a MockGitHub.
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    /// The branch that was read or written.
+    pub branch: String,
+}
+
+/// The sha and content of each file, by branch and path.
+struct MockState {
+    default_branch: String,
+    branches: BTreeSet<String>,
+    files: BTreeMap<(String, String), (String, String)>,
+    conflicts: usize,
+    /// The content committed by the concurrent writer of the next conflict, if any.
+    concurrent_content: Option<String>,
+    commits: u64,
+    requests: Vec<MockRequest>,
+}
+
+impl MockGitHub {
+    /// Starts a server for a repository with the given branches, the first one being the default.
+    pub fn start(branches: &[&str]) -> Result<Self, io::Error> {
+        let state = Arc::new(Mutex::new(MockState {
+            default_branch: branches.first().copied().unwrap_or("main").into(),
+            branches: branches.iter().map(|branch| branch.to_string()).collect(),
+            files: BTreeMap::new(),
+            conflicts: 0,
+            concurrent_content: None,
+            commits: 0,
+            requests: vec![],
+        }));
+
+        let server_state = state.clone();
+        let server = HttpMock::start("mock-github", move |request| {
+            handle(&mut server_state.lock().unwrap(), &request)
+        })?;
+        Ok(Self { server, state })
+    }
+
+    /// Makes the next `conflicts` writes fail with a 409, as if another writer committed to the
+    /// file after its sha was read. The sha of an existing file changes with each conflict.
+    pub fn inject_conflicts(&self, conflicts: usize) {
+        self.state.lock().unwrap().conflicts = conflicts;
+    }
+
+    /// Makes the next write fail with a 409, as if another writer committed the given content to
+    /// the file after its sha was read.
+    pub fn inject_concurrent_write(&self, content: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.conflicts = 1;
+        state.concurrent_content = Some(content.into());
+    }
+
+    /// The requests received so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn host(&self) -> &str {
+        self.server.url()
+    }
+
+    pub fn owner(&self) -> &str {
+        OWNER
+    }
+
+    pub fn repository(&self) -> &str {
+        REPOSITORY
+    }
+
+    pub fn token(&self) -> &str {
+        TOKEN
+    }
+
+    /// A client of the given branch of the repository.
+    pub fn client(&self, branch: &str) -> Client {
+        let mut client = Client::new(OWNER.into(), REPOSITORY.into(), branch.into(), TOKEN.into());
+        client.set_url(self.host().into());
+        client
+    }
+}
+
+impl MockState {
+    fn next_sha(&mut self) -> String {
+        self.commits += 1;
+        format!("{:040x}", self.commits)
+    }
+}
+
+fn message(status: u16, message: &str) -> Response {
+    (status, Some(json!({ "message": message })))
+}
+
+fn handle(state: &mut MockState, request: &Request) -> Response {
+    let (method, path) = (request.method.as_str(), request.path.as_str());
+    let body = request.json();
+    let branch = request
+        .query_param("ref")
+        .or_else(|| body["branch"].as_str())
+        .unwrap_or(&state.default_branch)
+        .to_string();
+    state.requests.push(MockRequest {
+        method: method.into(),
+        path: path.into(),
+        branch: branch.clone(),
+    });
+
+    // Paths are /repos/{owner}/{repository}/{resource}
+    let resource = match path.strip_prefix("/repos/") {
+        Some(path) => path.splitn(3, '/').nth(2).unwrap_or(""),
+        None => return message(404, "Not Found"),
+    };
+    if resource == "branches" && method == "GET" {
+        let branches: Vec<_> = state
+            .branches
+            .iter()
+            .map(|name| json!({ "name": name }))
+            .collect();
+        return (200, Some(json!(branches)));
+    }
+    let path = match resource.strip_prefix("contents") {
+        Some(path) => path.trim_start_matches('/').to_string(),
+        None => return message(404, "Not Found"),
+    };
+    if !state.branches.contains(&branch) {
+        return message(404, &format!("No commit found for the ref {}", branch));
+    }
+
+    match method {
+        "GET" => get(state, &branch, &path),
+        "PUT" | "DELETE" => {
+            let key = (branch, path);
+            let current_sha = state.files.get(&key).map(|(sha, _)| sha.clone());
+            if state.conflicts > 0 {
+                state.conflicts -= 1;
+                let concurrent_content = state.concurrent_content.take();
+                if current_sha.is_some() || concurrent_content.is_some() {
+                    let sha = state.next_sha();
+                    let file = state.files.entry(key.clone()).or_default();
+                    file.0 = sha;
+                    if let Some(content) = concurrent_content {
+                        file.1 = content;
+                    }
+                }
+                return message(409, &format!("{} does not match", key.1));
+            }
+            match (current_sha, body["sha"].as_str()) {
+                (Some(current_sha), Some(sha)) if current_sha != sha => {
+                    message(409, &format!("{} does not match {}", key.1, sha))
+                }
+                (Some(_), None) => message(422, "Invalid request. \"sha\" wasn't supplied."),
+                (None, _) if method == "DELETE" => message(404, "Not Found"),
+                (current_sha, _) => {
+                    if method == "DELETE" {
+                        state.files.remove(&key);
+                        return (200, Some(json!({ "content": null })));
+                    }
+                    let sha = state.next_sha();
+                    let content = body["content"].as_str().unwrap_or("").to_string();
+                    state.files.insert(key.clone(), (sha.clone(), content));
+                    let status = if current_sha.is_some() { 200 } else { 201 };
+                    (
+                        status,
+                        Some(json!({ "content": { "path": key.1, "sha": sha } })),
+                    )
+                }
+            }
+        }
+        _ => message(405, "Method Not Allowed"),
+    }
+}
+
+/// Reads a file, or lists the files and directories directly within a directory.
+fn get(state: &MockState, branch: &str, path: &str) -> Response {
+    if let Some((sha, content)) = state.files.get(&(branch.to_string(), path.to_string())) {
+        let file = json!({ "type": "file", "path": path, "sha": sha, "content": content });
+        return (200, Some(file));
+    }
+
+    let directory = if path.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", path)
+    };
+    let entries: BTreeMap<_, _> = state
+        .files
+        .iter()
+        .filter(|((file_branch, _), _)| file_branch == branch)
+        .filter_map(|((_, file), (sha, _))| {
+            let name = file.strip_prefix(&directory)?;
+            Some(match name.split_once('/') {
+                Some((subdirectory, _)) => {
+                    let path = format!("{}{}", directory, subdirectory);
+                    (path, ("dir", "0".repeat(40)))
+                }
+                None => (file.clone(), ("file", sha.clone())),
+            })
+        })
+        .collect();
+    if entries.is_empty() {
+        return message(404, "Not Found");
+    }
+    let entries: Vec<_> = entries
+        .into_iter()
+        .map(
+            |(path, (content_type, sha))| json!({ "type": content_type, "path": path, "sha": sha }),
+        )
+        .collect();
+    (200, Some(json!(entries)))
+}
+
+#[test]
+fn mock_github_branches() {
+    let github = MockGitHub::start(&["main", "storage"]).unwrap();
+    let main = github.client("main");
+    let storage = github.client("storage");
+    assert_eq!(main.get_branches().unwrap(), vec!["main", "storage"]);
+
+    // Each branch has files of its own
+    main.put("a/b", "main").unwrap();
+    storage.put("a/b", "storage").unwrap();
+    storage.put("a/c/d", "storage").unwrap();
+    assert_eq!(main.get_file("a/b").unwrap(), "main");
+    assert_eq!(storage.get_file("a/b").unwrap(), "storage");
+    assert_eq!(main.get_directory("a").unwrap(), vec!["a/b"]);
+    assert_eq!(storage.get_directory("a").unwrap(), vec!["a/b", "a/c/"]);
+
+    storage.delete_directory("a").unwrap();
+    storage.get_directory("a").unwrap_err();
+    assert_eq!(main.get_file("a/b").unwrap(), "main");
+    assert!(matches!(
+        github.client("missing").put("a/b", "missing"),
+        Err(crate::Error::HttpError(404, _, _))
+    ));
+
+    // Writes name the branch of the client
+    let branches: Vec<_> = github
+        .requests()
+        .into_iter()
+        .filter(|request| request.method == "PUT")
+        .map(|request| request.branch)
+        .collect();
+    assert_eq!(branches, vec!["main", "storage", "storage", "missing"]);
+}
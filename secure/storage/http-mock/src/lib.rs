@@ -0,0 +1,184 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+//! A minimal HTTP/1.1 server running within the process, on which the clients of secure storage
+//! backends are tested against stand-ins of their services. The server parses requests and writes
+//! JSON responses, while each stand-in only handles the requests, e.g., `aptos_vault_client::mock`.
+
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+};
+
+/// A request received by an HttpMock. Header names are lowercase.
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    /// The path of the target, without its query.
+    pub path: String,
+    /// The query of the target, without the leading `?`, or empty if there is none.
+    pub query: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// The value of a parameter of the query, as sent, i.e., not percent-decoded.
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(param, _)| *param == name)
+            .map(|(_, value)| value)
+    }
+
+    /// The body as JSON, or null if it is not JSON.
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or(Value::Null)
+    }
+}
+
+/// The status and JSON body, if any, of a response.
+pub type Response = (u16, Option<Value>);
+
+/// A server that runs until the process exits, handling the requests of each connection, which
+/// is kept alive, on a thread of its own.
+pub struct HttpMock {
+    url: String,
+}
+
+impl HttpMock {
+    /// Starts a server on a free local port, whose threads are named after `name`.
+    pub fn start<H>(name: &str, handler: H) -> Result<Self, io::Error>
+    where
+        H: Fn(Request) -> Response + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        let url = format!("http://127.0.0.1:{}", listener.local_addr()?.port());
+        let handler = Arc::new(handler);
+        thread::Builder::new().name(name.into()).spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let handler = handler.clone();
+                thread::spawn(move || serve(stream, handler.as_ref()));
+            }
+        })?;
+        Ok(Self { url })
+    }
+
+    /// The URL of the server, e.g., `http://127.0.0.1:8200`, without a trailing slash.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+/// Serves the requests of a connection until the client closes it.
+fn serve<H: Fn(Request) -> Response>(stream: TcpStream, handler: &H) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    let mut reader = BufReader::new(stream);
+    while let Some(request) = read_request(&mut reader) {
+        let (status, body) = handler(request);
+        if write_response(&mut writer, status, body).is_err() {
+            return;
+        }
+    }
+}
+
+fn read_request(reader: &mut BufReader<TcpStream>) -> Option<Request> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).ok()? == 0 {
+        return None;
+    }
+    let mut request_line = line.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let (path, query) = (path.to_string(), query.to_string());
+
+    let mut headers = BTreeMap::new();
+    let mut content_length = 0;
+    let mut chunked = false;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).ok()?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let (name, value) = (name.trim().to_ascii_lowercase(), value.trim());
+            match name.as_str() {
+                "content-length" => content_length = value.parse().ok()?,
+                "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+                _ => (),
+            }
+            headers.insert(name, value.to_string());
+        }
+    }
+
+    let mut body = vec![];
+    if chunked {
+        loop {
+            let mut size = String::new();
+            reader.read_line(&mut size).ok()?;
+            let size = usize::from_str_radix(size.trim(), 16).ok()?;
+            // Each chunk, including the last empty one, is followed by a line break
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk).ok()?;
+            if size == 0 {
+                break;
+            }
+            body.extend_from_slice(&chunk[..size]);
+        }
+    } else {
+        body.resize(content_length, 0);
+        reader.read_exact(&mut body).ok()?;
+    }
+    Some(Request {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    })
+}
+
+fn write_response(writer: &mut TcpStream, status: u16, body: Option<Value>) -> io::Result<()> {
+    let body = body.map(|body| body.to_string()).unwrap_or_default();
+    let reason = match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        422 => "Unprocessable Entity",
+        _ => "Error",
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
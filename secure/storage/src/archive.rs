@@ -0,0 +1,168 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    crypto_kv_storage,
+    encrypted_on_disk::FileCipher,
+    from_base64,
+    migrate::{self, Entry},
+    namespaced::NAMESPACE_SEPARATOR,
+    to_base64, CryptoStorage, Error, KVStorage, Passphrase, Storage,
+};
+use aptos_crypto::ed25519::Ed25519PrivateKey;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use zeroize::Zeroizing;
+
+/// The version of the archives written by `export_namespace`.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// The entries of an archive, by name within the namespace.
+#[derive(Debug, Default, PartialEq)]
+pub struct ArchiveReport {
+    /// Values and keys held by the archive.
+    pub entries: Vec<String>,
+    /// Keys that are not exportable, which the archive does not hold.
+    pub skipped: Vec<String>,
+}
+
+/// An archive, as written by `export_namespace`.
+#[derive(Deserialize, Serialize)]
+struct Archive {
+    version: u32,
+    /// The ArchiveContents, encrypted by a FileCipher.
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    encrypted: Vec<u8>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct ArchiveContents {
+    values: BTreeMap<String, Value>,
+    /// The current version of each key, followed by its previous version, if any.
+    keys: BTreeMap<String, Vec<Ed25519PrivateKey>>,
+    skipped: Vec<String>,
+}
+
+/// Exports the values and keys of `namespace`, or of the whole storage if None, into a single
+/// archive encrypted with a key derived from the passphrase, e.g., for disaster recovery. Entries
+/// are enumerated with `KVStorage::list_keys`. The entries at `key_names`, within the namespace,
+/// are keys, which are exported along with their previous version; other entries are values. Keys
+/// that are not exportable cannot be archived, and are recorded as skipped.
+pub fn export_namespace(
+    storage: &Storage,
+    namespace: Option<&str>,
+    key_names: &[&str],
+    passphrase: &Passphrase,
+) -> Result<Vec<u8>, Error> {
+    let prefix = namespace.map(|namespace| format!("{}{}", namespace, NAMESPACE_SEPARATOR));
+    let names = storage.list_keys(prefix.as_deref())?;
+    let archived_name = |name: &str| match &prefix {
+        Some(prefix) => name
+            .strip_prefix(prefix.as_str())
+            .unwrap_or(name)
+            .to_string(),
+        None => name.to_string(),
+    };
+
+    let mut entries = Vec::new();
+    for name in names {
+        let is_key = key_names.contains(&archived_name(&name).as_str());
+        if let Some(entry) = migrate::entry(storage, &name, is_key)? {
+            entries.push((name, entry));
+        }
+    }
+
+    let key_names: Vec<_> = entries
+        .iter()
+        .filter(|(_, entry)| matches!(entry, Entry::Key(_)))
+        .map(|(name, _)| name.clone())
+        .collect();
+    let mut contents = ArchiveContents::default();
+    for (name, entry) in entries {
+        // Previous versions are archived along with their key
+        if key_names
+            .iter()
+            .any(|key_name| crypto_kv_storage::is_key_pair_entry(&name, key_name))
+        {
+            continue;
+        }
+        let archived_name = archived_name(&name);
+        match entry {
+            Entry::Key(public_key) => {
+                match storage.export_private_key_for_version(&name, public_key) {
+                    Ok(private_key) => {
+                        let mut versions = vec![private_key];
+                        match storage.get_public_key_previous_version(&name) {
+                            Ok(previous_version) => versions.push(
+                                storage.export_private_key_for_version(&name, previous_version)?,
+                            ),
+                            Err(Error::KeyVersionNotFound(_, _)) => (),
+                            Err(error) => return Err(error),
+                        }
+                        contents.keys.insert(archived_name, versions);
+                    }
+                    Err(Error::KeyNotExportable(_)) => contents.skipped.push(archived_name),
+                    Err(error) => return Err(error),
+                }
+            }
+            Entry::Value(value) => {
+                contents.values.insert(archived_name, value);
+            }
+        }
+    }
+
+    let plaintext = Zeroizing::new(serde_json::to_vec(&contents)?);
+    let cipher = FileCipher::generate(passphrase.read()?.as_bytes())?;
+    Ok(serde_json::to_vec(&Archive {
+        version: ARCHIVE_VERSION,
+        encrypted: cipher.encrypt(&plaintext)?,
+    })?)
+}
+
+/// Imports an archive written by `export_namespace` into `namespace`, or at the root of the
+/// storage if None, overwriting existing entries of the same names. Fails with
+/// `Error::DecryptionError` if the passphrase does not match that of the archive, and with
+/// `Error::TamperError` if the archive was altered after it was exported. Nothing is written
+/// unless the archive could be decrypted.
+///
+/// Warning: the archived values are written as is, without checking them against the entries
+/// they overwrite. This must not be used on a namespace in use, e.g., an older archive would move
+/// the safety data of a running validator backwards and let it sign twice in a round. Safety rules
+/// restores archives with `PersistentSafetyStorage::import_archive`, which checks them first.
+pub fn import_namespace(
+    storage: &mut Storage,
+    namespace: Option<&str>,
+    archive: &[u8],
+    passphrase: &Passphrase,
+) -> Result<ArchiveReport, Error> {
+    let archive: Archive = serde_json::from_slice(archive)?;
+    if archive.version != ARCHIVE_VERSION {
+        return Err(Error::SerializationError(format!(
+            "Unsupported archive version: {}",
+            archive.version
+        )));
+    }
+    let cipher = FileCipher::for_contents(passphrase.read()?.as_bytes(), &archive.encrypted)?;
+    let plaintext: Zeroizing<Vec<u8>> = cipher.decrypt(&archive.encrypted)?;
+    let contents: ArchiveContents = serde_json::from_slice(&plaintext)?;
+
+    let namespaced = |name: &str| match namespace {
+        Some(namespace) => format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, name),
+        None => name.to_string(),
+    };
+    let mut report = ArchiveReport {
+        entries: Vec::new(),
+        skipped: contents.skipped,
+    };
+    for (name, value) in contents.values {
+        storage.set(&namespaced(&name), value)?;
+        report.entries.push(name);
+    }
+    for (name, versions) in contents.keys {
+        storage.import_private_key_versions(&namespaced(&name), versions)?;
+        report.entries.push(name);
+    }
+    report.entries.sort();
+    Ok(report)
+}
@@ -0,0 +1,305 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    kv_storage::filter_keys, logging, CryptoStorage, Error, GetResponse, KVStorage,
+    PublicKeyResponse,
+};
+use aptos_aws_client::{Client, Credentials, PREVIOUS_STAGE};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+    PrivateKey, SigningKey, Uniform,
+};
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use rand::{rngs::OsRng, Rng, SeedableRng};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// AwsStorage keeps each key in its own secret within AWS Secrets Manager, serialized like the
+/// values of OnDiskStorage. Private keys are generated locally and stored like any other value.
+/// Rotating a key writes a new version of its secret, so the key it replaces remains available
+/// through the AWSPREVIOUS staging label that Secrets Manager maintains.
+///
+/// Version ids of secrets are not ordered, so each write reads the version of the value first and
+/// records the next one along with it. This costs a round trip per write, and concurrent writers
+/// may record the same version. Secrets Manager offers no conditional writes, so `set_if_version`
+/// is not supported.
+pub struct AwsStorage {
+    client: Client,
+    /// Prepended to every key to form the name of its secret, e.g., "validator/".
+    prefix: String,
+    time_service: TimeService,
+}
+
+impl AwsStorage {
+    /// Creates a storage for the region. Credentials are resolved through the standard AWS
+    /// credential chain unless provided.
+    pub fn new(
+        region: String,
+        prefix: Option<String>,
+        endpoint: Option<String>,
+        credentials: Option<Credentials>,
+        connection_timeout_ms: Option<u64>,
+        response_timeout_ms: Option<u64>,
+    ) -> Self {
+        Self {
+            client: Client::new(
+                region,
+                endpoint,
+                credentials,
+                connection_timeout_ms,
+                response_timeout_ms,
+            ),
+            prefix: prefix.unwrap_or_default(),
+            time_service: TimeService::real(),
+        }
+    }
+
+    fn secret_name(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    fn get_stage<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        version_stage: Option<&str>,
+    ) -> Result<GetResponse<T>, Error> {
+        let secret = self
+            .client
+            .get_secret_value(&self.secret_name(key), version_stage)
+            .map_err(|e| match e {
+                aptos_aws_client::Error::NotFound(_) => Error::KeyNotSet(key.into()),
+                e => e.into(),
+            })?;
+        serde_json::from_str(&secret.secret_string).map_err(|e| e.into())
+    }
+
+    /// Returns the version of the value stored at the key, or 0 if it is not set. Version ids of
+    /// secrets are not ordered, so versions are counted by the storage.
+    fn version(&self, key: &str) -> Result<u64, Error> {
+        match self.get_stage::<Value>(key, None) {
+            Ok(response) => Ok(response.version),
+            Err(Error::KeyNotSet(_)) => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads the private key the last rotation replaced.
+    fn export_previous_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        match self.get_stage(name, Some(PREVIOUS_STAGE)) {
+            Ok(response) => Ok(response.value),
+            Err(Error::KeyNotSet(_)) => Err(Error::KeyVersionNotFound(
+                name.into(),
+                "previous version".into(),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl KVStorage for AwsStorage {
+    fn available(&self) -> Result<(), Error> {
+        self.client.list_secrets(&self.prefix)?;
+        Ok(())
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        self.get_stage(key, None)
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        let now = self.time_service.now_secs();
+        let version = self.version(key)? + 1;
+        let data = serde_json::to_string(&GetResponse::new_with_version(value, now, version))?;
+        let name = self.secret_name(key);
+        match self.client.put_secret_value(&name, &data) {
+            Ok(_) => Ok(()),
+            Err(aptos_aws_client::Error::NotFound(_)) => self
+                .client
+                .create_secret(&name, &data)
+                .map_err(|e| e.into()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        _key: &str,
+        _value: T,
+        _expected_version: u64,
+    ) -> Result<(), Error> {
+        Err(Error::InternalError(
+            "AwsStorage does not support conditional writes".into(),
+        ))
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        match self.client.delete_secret(&self.secret_name(key)) {
+            Ok(()) | Err(aptos_aws_client::Error::NotFound(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        // Secrets Manager matches names without regard to case
+        let names = self
+            .client
+            .list_secrets(&self.secret_name(prefix.unwrap_or("")))?;
+        let keys = names
+            .into_iter()
+            .filter_map(|name| name.strip_prefix(&self.prefix).map(str::to_string));
+        Ok(filter_keys(keys, prefix))
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        for name in self.client.list_secrets(&self.prefix)? {
+            self.client.delete_secret(&name)?;
+        }
+        Ok(())
+    }
+}
+
+impl CryptoStorage for AwsStorage {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let (private_key, public_key) = new_ed25519_key_pair();
+
+        // Creating the secret fails if it exists, so an existing key is never replaced
+        let now = self.time_service.now_secs();
+        let data = serde_json::to_string(&GetResponse::new_with_version(private_key, now, 1))?;
+        let secret_name = self.secret_name(name);
+        match self.client.create_secret(&secret_name, &data) {
+            Ok(()) => Ok(public_key),
+            Err(aptos_aws_client::Error::ServiceError(_, error_type, _))
+                if error_type == "ResourceExistsException" =>
+            {
+                Err(Error::KeyAlreadyExists(secret_name))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn generate_key(&mut self, _name: &str) -> Result<Ed25519PublicKey, Error> {
+        Err(Error::InternalError(
+            "AWS Secrets Manager does not support non-exportable keys".into(),
+        ))
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        false
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        self.get(name).map(|v| v.value)
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        let current_private_key = self.export_private_key(name)?;
+        if current_private_key.public_key() == version {
+            return Ok(current_private_key);
+        }
+
+        match self.export_previous_private_key(name) {
+            Ok(previous_private_key) if previous_private_key.public_key() == version => {
+                Ok(previous_private_key)
+            }
+            Ok(_) | Err(Error::KeyVersionNotFound(_, _)) => {
+                Err(Error::KeyVersionNotFound(name.into(), version.to_string()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        let public_key = key.public_key();
+        self.set(name, key)?;
+        logging::log_key_import(name, &public_key.to_bytes());
+        Ok(())
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        let response = self.get::<Ed25519PrivateKey>(name)?;
+        Ok(PublicKeyResponse {
+            last_update: response.last_update,
+            public_key: response.value.public_key(),
+        })
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.export_previous_private_key(name)
+            .map(|key| key.public_key())
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        // Only existing keys can be rotated
+        self.export_private_key(name)?;
+
+        let (private_key, public_key) = new_ed25519_key_pair();
+        self.set(name, private_key)?;
+        Ok(public_key)
+    }
+
+    /// Only the current and previous versions of a key are ever retained, and the previous one
+    /// cannot be deleted.
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        _in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        // Fail as for any other operation on a key that does not exist
+        self.get_public_key(name)?;
+        if keep_latest_n >= 2 {
+            return Ok(());
+        }
+        Err(Error::InternalError(
+            "AWS Secrets Manager does not support pruning the previous key version".into(),
+        ))
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        let private_key = self.export_private_key(name)?;
+        Ok(private_key.sign(message))
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        let private_key = self.export_private_key_for_version(name, version)?;
+        Ok(private_key.sign(message))
+    }
+
+    fn sign_batch_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        let private_key = self.export_private_key_for_version(name, version)?;
+        Ok(messages
+            .iter()
+            .map(|message| private_key.sign(message))
+            .collect())
+    }
+}
+
+/// Private helper method to generate a new ed25519 key pair using entropy from the OS.
+fn new_ed25519_key_pair() -> (Ed25519PrivateKey, Ed25519PublicKey) {
+    let mut seed_rng = OsRng;
+    let mut rng = rand::rngs::StdRng::from_seed(seed_rng.gen());
+    let private_key = Ed25519PrivateKey::generate(&mut rng);
+    let public_key = private_key.public_key();
+    (private_key, public_key)
+}
@@ -0,0 +1,410 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    CryptoStorage, Error, GetResponse, KVStorage, KeyWatch, PublicKeyResponse, SchemeSigningKey,
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+};
+use aptos_infallible::Mutex;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+/// CachedStorage is a read-through and write-through cache in front of a storage, e.g., a remote
+/// backend, that serves reads of recently read or written keys from memory. Entries expire after
+/// the TTL of their key, which defaults to the TTL given on creation, and a zero TTL disables
+/// caching of a key. Writes reach the storage before updating the cache, and a failed write
+/// invalidates the key, as the value held by the storage is then unknown.
+///
+/// The cache assumes that it is the only writer of its keys: writes by others are only observed
+/// once entries expire or are invalidated, and `bypass` gives access to the storage for reads
+/// that must not be stale, e.g., consistency checks. The `last_update` of a written value is
+/// taken from the local clock. Crypto operations are not cached, and those that modify a key
+/// invalidate all entries starting with its name.
+pub struct CachedStorage<S> {
+    inner: S,
+    default_ttl: Duration,
+    ttls: HashMap<String, Duration>,
+    cache: Mutex<HashMap<String, CacheEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    time_service: TimeService,
+}
+
+struct CacheEntry {
+    value: Value,
+    last_update: u64,
+    version: u64,
+    expires_at: Instant,
+}
+
+impl<S> CachedStorage<S> {
+    pub fn new(inner: S, default_ttl: Duration) -> Self {
+        Self::new_with_time_service(inner, default_ttl, TimeService::real())
+    }
+
+    pub(crate) fn new_with_time_service(
+        inner: S,
+        default_ttl: Duration,
+        time_service: TimeService,
+    ) -> Self {
+        Self {
+            inner,
+            default_ttl,
+            ttls: HashMap::new(),
+            cache: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            time_service,
+        }
+    }
+
+    /// Overrides the TTL of a key. Entries cached before keep their expiration time.
+    pub fn set_ttl(&mut self, key: &str, ttl: Duration) {
+        self.ttls.insert(key.into(), ttl);
+    }
+
+    /// Gives access to the storage for uncached reads. Writing through it would leave the cache
+    /// stale, hence only shared access is provided.
+    pub fn bypass(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        self.cache.lock().remove(key);
+    }
+
+    pub fn invalidate_all(&self) {
+        self.cache.lock().clear();
+    }
+
+    /// The number of values served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of values read from the storage.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    fn invalidate_prefix(&self, prefix: &str) {
+        self.cache.lock().retain(|key, _| !key.starts_with(prefix));
+    }
+
+    fn ttl(&self, key: &str) -> Duration {
+        self.ttls.get(key).copied().unwrap_or(self.default_ttl)
+    }
+
+    fn cached(&self, key: &str) -> Option<GetResponse<Value>> {
+        let mut cache = self.cache.lock();
+        match cache.get(key) {
+            Some(entry) if entry.expires_at > self.time_service.now() => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(GetResponse::new_with_version(
+                    entry.value.clone(),
+                    entry.last_update,
+                    entry.version,
+                ))
+            }
+            Some(_) => {
+                cache.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, key: &str, value: Value, last_update: u64, version: u64) {
+        let ttl = self.ttl(key);
+        if ttl == Duration::ZERO {
+            return;
+        }
+        let entry = CacheEntry {
+            value,
+            last_update,
+            version,
+            expires_at: self.time_service.now() + ttl,
+        };
+        self.cache.lock().insert(key.into(), entry);
+    }
+
+    /// Updates the cache after a successful write. The version of the written value is known
+    /// for conditional writes, and otherwise follows that of a cached value, if any.
+    fn written(&self, key: &str, value: Value, version: Option<u64>) {
+        let now = self.time_service.now();
+        let cached_version = self
+            .cache
+            .lock()
+            .get(key)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| match entry.version {
+                // The storage does not track versions
+                0 => 0,
+                version => version + 1,
+            });
+        match version.or(cached_version) {
+            Some(version) => self.insert(key, value, self.time_service.now_secs(), version),
+            None => self.invalidate(key),
+        }
+    }
+}
+
+fn typed<T: DeserializeOwned>(response: GetResponse<Value>) -> Result<GetResponse<T>, Error> {
+    Ok(GetResponse::new_with_version(
+        serde_json::from_value(response.value)?,
+        response.last_update,
+        response.version,
+    ))
+}
+
+impl<S: KVStorage> KVStorage for CachedStorage<S> {
+    fn available(&self) -> Result<(), Error> {
+        self.inner.available()
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        if let Some(response) = self.cached(key) {
+            return typed(response);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let response = self.inner.get::<Value>(key)?;
+        self.insert(
+            key,
+            response.value.clone(),
+            response.last_update,
+            response.version,
+        );
+        typed(response)
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        let value = serde_json::to_value(value)?;
+        if let Err(error) = self.inner.set(key, &value) {
+            self.invalidate(key);
+            return Err(error);
+        }
+        self.written(key, value, None);
+        Ok(())
+    }
+
+    /// Only reads the keys that are not cached from the storage.
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        let mut responses = BTreeMap::new();
+        let mut missing = Vec::new();
+        for key in keys {
+            match self.cached(key) {
+                Some(response) => {
+                    responses.insert(key.to_string(), response);
+                }
+                None => missing.push(*key),
+            }
+        }
+        if missing.is_empty() {
+            return Ok(responses);
+        }
+
+        self.misses
+            .fetch_add(missing.len() as u64, Ordering::Relaxed);
+        for (key, response) in self.inner.get_batch(&missing)? {
+            self.insert(
+                &key,
+                response.value.clone(),
+                response.last_update,
+                response.version,
+            );
+            responses.insert(key, response);
+        }
+        Ok(responses)
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        if let Err(error) = self.inner.set_batch(entries) {
+            for (key, _) in entries {
+                self.invalidate(key);
+            }
+            return Err(error);
+        }
+        for (key, value) in entries {
+            self.written(key, value.clone(), None);
+        }
+        Ok(())
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        let value = serde_json::to_value(value)?;
+        if let Err(error) = self.inner.set_if_version(key, &value, expected_version) {
+            self.invalidate(key);
+            return Err(error);
+        }
+        self.written(key, value, Some(expected_version + 1));
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        self.invalidate(key);
+        self.inner.delete(key)
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        self.inner.list_keys(prefix)
+    }
+
+    /// Events are not applied to the cache, so cached values only reflect the changes watched
+    /// once they expire or are invalidated.
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        self.inner.watch(key)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.invalidate_all();
+        self.inner.reset_and_clear()
+    }
+}
+
+impl<S: CryptoStorage> CryptoStorage for CachedStorage<S> {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.invalidate_prefix(name);
+        self.inner.create_key(name)
+    }
+
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.invalidate_prefix(name);
+        self.inner.generate_key(name)
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        self.inner.supports_non_exportable_keys()
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        self.inner.export_private_key(name)
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        self.invalidate_prefix(name);
+        self.inner.import_private_key(name, key)
+    }
+
+    fn import_private_key_versions(
+        &mut self,
+        name: &str,
+        versions: Vec<Ed25519PrivateKey>,
+    ) -> Result<usize, Error> {
+        self.invalidate_prefix(name);
+        self.inner.import_private_key_versions(name, versions)
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        self.inner.export_private_key_for_version(name, version)
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.inner.get_public_key(name)
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.inner.get_public_key_previous_version(name)
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.invalidate_prefix(name);
+        self.inner.rotate_key(name)
+    }
+
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        self.invalidate_prefix(name);
+        self.inner
+            .prune_key_versions(name, keep_latest_n, in_use_floor)
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.inner.sign(name, message)
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.inner.sign_using_version(name, version, message)
+    }
+
+    fn sign_batch_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        self.inner.sign_batch_using_version(name, version, messages)
+    }
+
+    fn create_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.invalidate_prefix(name);
+        self.inner.create_key_as::<K>(name)
+    }
+
+    fn import_private_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+        key: K,
+    ) -> Result<(), Error> {
+        self.invalidate_prefix(name);
+        self.inner.import_private_key_as(name, key)
+    }
+
+    fn export_private_key_as<K: SchemeSigningKey>(&self, name: &str) -> Result<K, Error> {
+        self.inner.export_private_key_as(name)
+    }
+
+    fn get_public_key_as<K: SchemeSigningKey>(
+        &self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.inner.get_public_key_as::<K>(name)
+    }
+
+    fn sign_as<K: SchemeSigningKey, T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<K::SignatureMaterial, Error> {
+        self.inner.sign_as::<K, T>(name, message)
+    }
+}
@@ -0,0 +1,405 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    CryptoStorage, Error, GetResponse, KVStorage, KeyWatch, PublicKeyResponse, SchemeSigningKey,
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+};
+use aptos_infallible::Mutex;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    thread,
+    time::Duration,
+};
+
+/// The kinds of operations a ChaosPolicy can target. Key creation, rotation and pruning are
+/// writes, while exports and signing are reads.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChaosOperation {
+    Read,
+    Write,
+}
+
+/// The faults injected by a ChaosStorage. Every operation first waits for the latency, if any,
+/// then consumes the next entry of the script: a scripted error is returned as is, while a `None`
+/// entry lets the operation through to the remaining rules. Once the script is exhausted, an
+/// operation fails if it is the Nth one, or if it targets a key matching one of the failing
+/// patterns. Patterns may contain `*` wildcards.
+#[derive(Debug, Default)]
+pub struct ChaosPolicy {
+    latency: Option<Duration>,
+    script: VecDeque<Option<Error>>,
+    fail_every_nth: Option<u64>,
+    failing_keys: Vec<(String, Option<ChaosOperation>)>,
+    max_failures: Option<u64>,
+}
+
+impl ChaosPolicy {
+    /// Delays every operation, including those that fail.
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Appends entries to the script, in order.
+    pub fn script<I: IntoIterator<Item = Option<Error>>>(mut self, script: I) -> Self {
+        self.script.extend(script);
+        self
+    }
+
+    /// Fails the Nth, 2Nth, ... operation, counting from the creation of the storage.
+    pub fn fail_every_nth(mut self, n: u64) -> Self {
+        assert!(n > 0, "Operations are counted from 1");
+        self.fail_every_nth = Some(n);
+        self
+    }
+
+    /// Fails operations on keys matching `pattern`, restricted to `operation` if set.
+    pub fn fail_keys_matching(mut self, pattern: &str, operation: Option<ChaosOperation>) -> Self {
+        self.failing_keys.push((pattern.into(), operation));
+        self
+    }
+
+    /// Stops injecting failures, scripted ones included, once `max_failures` were injected.
+    pub fn max_failures(mut self, max_failures: u64) -> Self {
+        self.max_failures = Some(max_failures);
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct ChaosState {
+    operations: u64,
+    failures: u64,
+}
+
+/// ChaosStorage wraps any storage and injects faults into its operations according to a
+/// ChaosPolicy, so that consumers can test their error handling against a shared fault model.
+/// Failures injected by the rules of the policy are `Error::InternalError`s, and the wrapped
+/// storage is left untouched by failed operations.
+pub struct ChaosStorage<S> {
+    inner: S,
+    policy: Mutex<ChaosPolicy>,
+    state: Mutex<ChaosState>,
+}
+
+impl<S> ChaosStorage<S> {
+    pub fn new(inner: S, policy: ChaosPolicy) -> Self {
+        Self {
+            inner,
+            policy: Mutex::new(policy),
+            state: Mutex::new(ChaosState::default()),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Gives access to the storage without injecting faults.
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Replaces the policy, e.g., to heal the storage. The operation count is kept, but the
+    /// count of injected failures is reset.
+    pub fn set_policy(&mut self, policy: ChaosPolicy) {
+        *self.policy.lock() = policy;
+        self.state.lock().failures = 0;
+    }
+
+    /// The number of operations issued so far, failed ones included.
+    pub fn operations(&self) -> u64 {
+        self.state.lock().operations
+    }
+
+    /// The number of failures injected under the current policy.
+    pub fn injected_failures(&self) -> u64 {
+        self.state.lock().failures
+    }
+
+    fn inject(&self, operation: ChaosOperation, keys: &[&str]) -> Result<(), Error> {
+        let mut policy = self.policy.lock();
+        if let Some(latency) = policy.latency {
+            thread::sleep(latency);
+        }
+
+        let mut state = self.state.lock();
+        state.operations += 1;
+        if policy
+            .max_failures
+            .map_or(false, |max_failures| state.failures >= max_failures)
+        {
+            return Ok(());
+        }
+
+        if let Some(Some(error)) = policy.script.pop_front() {
+            state.failures += 1;
+            return Err(error);
+        }
+
+        let nth = policy
+            .fail_every_nth
+            .map_or(false, |n| state.operations % n == 0);
+        let failing_key = keys.iter().find(|key| {
+            policy.failing_keys.iter().any(|(pattern, target)| {
+                target.map_or(true, |target| target == operation) && matches(pattern, key)
+            })
+        });
+        if nth || failing_key.is_some() {
+            state.failures += 1;
+            return Err(Error::InternalError(format!(
+                "Injected failure of {:?} operation {}{}",
+                operation,
+                state.operations,
+                failing_key.map_or(String::new(), |key| format!(" on key {}", key))
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if `key` matches `pattern`, in which `*` matches any sequence of characters.
+fn matches(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let mut rest = match key.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<_> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some((last, middle)) => (*last, middle),
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+impl<S: KVStorage> KVStorage for ChaosStorage<S> {
+    fn available(&self) -> Result<(), Error> {
+        self.inject(ChaosOperation::Read, &[])?;
+        self.inner.available()
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        self.inject(ChaosOperation::Read, &[key])?;
+        self.inner.get(key)
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        self.inject(ChaosOperation::Write, &[key])?;
+        self.inner.set(key, value)
+    }
+
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        self.inject(ChaosOperation::Read, keys)?;
+        self.inner.get_batch(keys)
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        let keys: Vec<_> = entries.iter().map(|(key, _)| *key).collect();
+        self.inject(ChaosOperation::Write, &keys)?;
+        self.inner.set_batch(entries)
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        self.inject(ChaosOperation::Write, &[key])?;
+        self.inner.set_if_version(key, value, expected_version)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        self.inject(ChaosOperation::Write, &[key])?;
+        self.inner.delete(key)
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        self.inject(ChaosOperation::Read, &[])?;
+        self.inner.list_keys(prefix)
+    }
+
+    /// Only subscribing is subject to faults, not the events of the watch.
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        self.inject(ChaosOperation::Read, &[key])?;
+        self.inner.watch(key)
+    }
+
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.inject(ChaosOperation::Write, &[])?;
+        self.inner.reset_and_clear()
+    }
+}
+
+impl<S: CryptoStorage> CryptoStorage for ChaosStorage<S> {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.inject(ChaosOperation::Write, &[name])?;
+        self.inner.create_key(name)
+    }
+
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.inject(ChaosOperation::Write, &[name])?;
+        self.inner.generate_key(name)
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        self.inner.supports_non_exportable_keys()
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        self.inject(ChaosOperation::Read, &[name])?;
+        self.inner.export_private_key(name)
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        self.inject(ChaosOperation::Write, &[name])?;
+        self.inner.import_private_key(name, key)
+    }
+
+    fn import_private_key_versions(
+        &mut self,
+        name: &str,
+        versions: Vec<Ed25519PrivateKey>,
+    ) -> Result<usize, Error> {
+        self.inject(ChaosOperation::Write, &[name])?;
+        self.inner.import_private_key_versions(name, versions)
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        self.inject(ChaosOperation::Read, &[name])?;
+        self.inner.export_private_key_for_version(name, version)
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.inject(ChaosOperation::Read, &[name])?;
+        self.inner.get_public_key(name)
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.inject(ChaosOperation::Read, &[name])?;
+        self.inner.get_public_key_previous_version(name)
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.inject(ChaosOperation::Write, &[name])?;
+        self.inner.rotate_key(name)
+    }
+
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        self.inject(ChaosOperation::Write, &[name])?;
+        self.inner
+            .prune_key_versions(name, keep_latest_n, in_use_floor)
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.inject(ChaosOperation::Read, &[name])?;
+        self.inner.sign(name, message)
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.inject(ChaosOperation::Read, &[name])?;
+        self.inner.sign_using_version(name, version, message)
+    }
+
+    fn sign_batch_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        self.inject(ChaosOperation::Read, &[name])?;
+        self.inner.sign_batch_using_version(name, version, messages)
+    }
+
+    fn create_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.inject(ChaosOperation::Write, &[name])?;
+        self.inner.create_key_as::<K>(name)
+    }
+
+    fn import_private_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+        key: K,
+    ) -> Result<(), Error> {
+        self.inject(ChaosOperation::Write, &[name])?;
+        self.inner.import_private_key_as(name, key)
+    }
+
+    fn export_private_key_as<K: SchemeSigningKey>(&self, name: &str) -> Result<K, Error> {
+        self.inject(ChaosOperation::Read, &[name])?;
+        self.inner.export_private_key_as(name)
+    }
+
+    fn get_public_key_as<K: SchemeSigningKey>(
+        &self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.inject(ChaosOperation::Read, &[name])?;
+        self.inner.get_public_key_as::<K>(name)
+    }
+
+    fn sign_as<K: SchemeSigningKey, T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<K::SignatureMaterial, Error> {
+        self.inject(ChaosOperation::Read, &[name])?;
+        self.inner.sign_as::<K, T>(name, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("consensus", "consensus"));
+        assert!(!matches("consensus", "consensus_key"));
+        assert!(matches("consensus*", "consensus_key"));
+        assert!(matches("*_key", "consensus_key"));
+        assert!(matches("c*s*y", "consensus_key"));
+        assert!(!matches("c*s*z", "consensus_key"));
+        assert!(matches("*", ""));
+        assert!(!matches("ab*ba", "aba"));
+    }
+}
@@ -0,0 +1,434 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    metrics, CryptoStorage, Error, GetResponse, KVStorage, KeyWatch, PublicKeyResponse,
+    SchemeSigningKey,
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+};
+use aptos_infallible::Mutex;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The states of a CircuitBreaker.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CircuitState {
+    /// Operations reach the storage.
+    Closed,
+    /// Operations fail fast with `Error::CircuitOpen`, until the cooldown elapsed.
+    Open,
+    /// The cooldown elapsed, and a single operation probes whether the storage recovered. Other
+    /// operations fail fast until its outcome is known.
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open => write!(f, "open"),
+            CircuitState::HalfOpen => write!(f, "half_open"),
+        }
+    }
+}
+
+/// The state machine of a circuit breaker, which stops issuing operations to a storage that keeps
+/// failing. After `failure_threshold` consecutive failures, the circuit opens and operations are
+/// refused for `cooldown`. The circuit then half-opens, and the next operation probes the storage:
+/// the circuit closes if it succeeds, and opens again otherwise.
+///
+/// The caller decides which outcomes are failures, and reports transitions by comparing `state`
+/// before and after `check` and `record`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: CircuitState,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        assert!(
+            failure_threshold > 0,
+            "The failure threshold must be positive"
+        );
+        Self {
+            failure_threshold,
+            cooldown,
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            open_until: None,
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Returns whether an operation may be issued at `now`, half-opening the circuit once the
+    /// cooldown elapsed.
+    pub fn check(&mut self, now: Instant) -> bool {
+        match self.state {
+            CircuitState::Closed => true,
+            CircuitState::Open if self.open_until.map_or(true, |until| now >= until) => {
+                self.state = CircuitState::HalfOpen;
+                true
+            }
+            CircuitState::Open | CircuitState::HalfOpen => false,
+        }
+    }
+
+    /// Records the outcome of an operation let through by `check`.
+    pub fn record(&mut self, failed: bool, now: Instant) {
+        if !failed {
+            self.consecutive_failures = 0;
+            self.state = CircuitState::Closed;
+            return;
+        }
+
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.state == CircuitState::HalfOpen
+            || self.consecutive_failures >= self.failure_threshold
+        {
+            self.open_until = Some(now + self.cooldown);
+            self.state = CircuitState::Open;
+        }
+    }
+}
+
+/// CircuitBreakerStorage guards a storage that keeps failing, e.g., a remote backend that is down,
+/// with a CircuitBreaker, so that callers fail fast with `Error::CircuitOpen` instead of waiting
+/// for a timeout on every operation.
+///
+/// Only `Error::Unavailable`s and `Error::InternalError`s count as failures: other errors, e.g.,
+/// `KeyNotSet`, `VersionMismatch` or `PermissionDenied`, show that the storage is reachable.
+/// Transitions are counted in `STORAGE_CIRCUIT_TRANSITIONS`, labeled by the backend of the
+/// storage. It is usually created through `Storage::with_circuit_breaker`.
+pub struct CircuitBreakerStorage<S> {
+    backend: &'static str,
+    inner: S,
+    circuit: Mutex<CircuitBreaker>,
+    time_service: TimeService,
+}
+
+impl<S> CircuitBreakerStorage<S> {
+    pub fn new(
+        backend: &'static str,
+        inner: S,
+        failure_threshold: u32,
+        cooldown: Duration,
+    ) -> Self {
+        Self::new_with_time_service(
+            backend,
+            inner,
+            failure_threshold,
+            cooldown,
+            TimeService::real(),
+        )
+    }
+
+    pub(crate) fn new_with_time_service(
+        backend: &'static str,
+        inner: S,
+        failure_threshold: u32,
+        cooldown: Duration,
+        time_service: TimeService,
+    ) -> Self {
+        Self {
+            backend,
+            inner,
+            circuit: Mutex::new(CircuitBreaker::new(failure_threshold, cooldown)),
+            time_service,
+        }
+    }
+
+    pub fn backend(&self) -> &'static str {
+        self.backend
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.circuit.lock().state()
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Fails fast unless the circuit lets an operation through.
+    fn check(&self) -> Result<(), Error> {
+        let mut circuit = self.circuit.lock();
+        let previous = circuit.state();
+        let allowed = circuit.check(self.time_service.now());
+        self.report(&circuit, previous);
+        if allowed {
+            Ok(())
+        } else {
+            Err(Error::CircuitOpen(self.backend.into()))
+        }
+    }
+
+    /// Records the outcome of an operation let through by `check`, and returns it.
+    fn observe<T>(&self, result: Result<T, Error>) -> Result<T, Error> {
+        let mut circuit = self.circuit.lock();
+        let previous = circuit.state();
+        let failed = matches!(
+            result,
+            Err(Error::Unavailable { .. } | Error::InternalError(_))
+        );
+        circuit.record(failed, self.time_service.now());
+        self.report(&circuit, previous);
+        result
+    }
+
+    fn report(&self, circuit: &CircuitBreaker, previous: CircuitState) {
+        let state = circuit.state();
+        if previous == state {
+            return;
+        }
+
+        metrics::STORAGE_CIRCUIT_TRANSITIONS
+            .with_label_values(&[self.backend, &state.to_string()])
+            .inc();
+        if state == CircuitState::Open {
+            aptos_logger::warn!(
+                "Circuit of the {} storage {} -> {} after {} consecutive failures, failing fast for {} ms",
+                self.backend,
+                previous,
+                state,
+                circuit.consecutive_failures(),
+                circuit.cooldown().as_millis(),
+            );
+        } else {
+            aptos_logger::info!(
+                "Circuit of the {} storage {} -> {}",
+                self.backend,
+                previous,
+                state
+            );
+        }
+    }
+}
+
+impl<S: KVStorage> KVStorage for CircuitBreakerStorage<S> {
+    fn available(&self) -> Result<(), Error> {
+        self.check()?;
+        self.observe(self.inner.available())
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        self.check()?;
+        self.observe(self.inner.get(key))
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        self.check()?;
+        self.observe(self.inner.set(key, value))
+    }
+
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        self.check()?;
+        self.observe(self.inner.get_batch(keys))
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        self.check()?;
+        self.observe(self.inner.set_batch(entries))
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        self.check()?;
+        self.observe(self.inner.set_if_version(key, value, expected_version))
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        self.check()?;
+        self.observe(self.inner.delete(key))
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        self.check()?;
+        self.observe(self.inner.list_keys(prefix))
+    }
+
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        self.check()?;
+        self.observe(self.inner.watch(key))
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.inner.reset_and_clear()
+    }
+}
+
+impl<S: CryptoStorage> CryptoStorage for CircuitBreakerStorage<S> {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.check()?;
+        self.observe(self.inner.create_key(name))
+    }
+
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.check()?;
+        self.observe(self.inner.generate_key(name))
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        self.inner.supports_non_exportable_keys()
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        self.check()?;
+        self.observe(self.inner.export_private_key(name))
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        self.check()?;
+        self.observe(self.inner.import_private_key(name, key))
+    }
+
+    fn import_private_key_versions(
+        &mut self,
+        name: &str,
+        versions: Vec<Ed25519PrivateKey>,
+    ) -> Result<usize, Error> {
+        self.check()?;
+        self.observe(self.inner.import_private_key_versions(name, versions))
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        self.check()?;
+        self.observe(self.inner.export_private_key_for_version(name, version))
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.check()?;
+        self.observe(self.inner.get_public_key(name))
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.check()?;
+        self.observe(self.inner.get_public_key_previous_version(name))
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.check()?;
+        self.observe(self.inner.rotate_key(name))
+    }
+
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        self.check()?;
+        self.observe(
+            self.inner
+                .prune_key_versions(name, keep_latest_n, in_use_floor),
+        )
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.check()?;
+        self.observe(self.inner.sign(name, message))
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.check()?;
+        self.observe(self.inner.sign_using_version(name, version, message))
+    }
+
+    fn sign_batch_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        self.check()?;
+        self.observe(self.inner.sign_batch_using_version(name, version, messages))
+    }
+
+    fn create_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.check()?;
+        self.observe(self.inner.create_key_as::<K>(name))
+    }
+
+    fn import_private_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+        key: K,
+    ) -> Result<(), Error> {
+        self.check()?;
+        self.observe(self.inner.import_private_key_as(name, key))
+    }
+
+    fn export_private_key_as<K: SchemeSigningKey>(&self, name: &str) -> Result<K, Error> {
+        self.check()?;
+        self.observe(self.inner.export_private_key_as(name))
+    }
+
+    fn get_public_key_as<K: SchemeSigningKey>(
+        &self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.check()?;
+        self.observe(self.inner.get_public_key_as::<K>(name))
+    }
+
+    fn sign_as<K: SchemeSigningKey, T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<K::SignatureMaterial, Error> {
+        self.check()?;
+        self.observe(self.inner.sign_as::<K, T>(name, message))
+    }
+}
@@ -1,30 +1,78 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{CryptoStorage, Error, KVStorage, PublicKeyResponse};
+use crate::{
+    from_base64, logging, to_base64, CryptoStorage, Error, KVStorage, KeyScheme, PublicKeyResponse,
+    SchemeSigningKey,
+};
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
-    PrivateKey, SigningKey, Uniform,
+    PrivateKey, SigningKey, ValidCryptoMaterial,
 };
 use rand::{rngs::OsRng, Rng, SeedableRng};
-use serde::ser::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::TryFrom;
+use zeroize::Zeroizing;
 
 /// CryptoKVStorage offers a CryptoStorage implementation by extending a key value store (KVStorage)
 /// to create and manage cryptographic keys. This is useful for providing a simple CryptoStorage
 /// implementation based upon an existing KVStorage engine (e.g. for test purposes).
-pub trait CryptoKVStorage: KVStorage {}
+///
+/// Keys created by `generate_key` are stored like any other key, alongside a marker that makes
+/// the storage refuse to export them. Signing still reads the key into this process, so
+/// `supports_non_exportable_keys` returns false.
+///
+/// Keys of schemes other than Ed25519 are stored along with their scheme, see `KeyScheme`, while
+/// Ed25519 keys are stored as is, so that existing storages remain readable.
+///
+/// Rotations keep the `max_key_versions` most recent versions of each key: the current version at
+/// 'name', the previous one at 'name_previous' and older ones at 'name_previous_N'. The public
+/// keys of the `MAX_PRUNED_VERSIONS` most recently pruned versions are recorded, so that their use
+/// fails with `Error::KeyVersionPruned`.
+pub trait CryptoKVStorage: KVStorage {
+    /// The number of versions of each key retained on rotation, including the current version.
+    fn max_key_versions(&self) -> usize {
+        DEFAULT_MAX_KEY_VERSIONS
+    }
+
+    /// Fails if the key at 'name' may not be rotated. All keys may be rotated by default.
+    fn check_rotatable(&self, _name: &str) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// The current and previous versions of each key are retained by default.
+pub const DEFAULT_MAX_KEY_VERSIONS: usize = 2;
+
+/// The number of pruned versions of each key whose public keys are recorded. Older pruned versions
+/// are forgotten, and reported as not found.
+pub const MAX_PRUNED_VERSIONS: usize = 64;
 
 impl<T: CryptoKVStorage> CryptoStorage for T {
     fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
         // Generate and store the new named key pair
-        let (private_key, public_key) = new_ed25519_key_pair();
+        let (private_key, public_key) = new_key_pair::<Ed25519PrivateKey>();
         self.import_private_key(name, private_key)?;
         Ok(public_key)
     }
 
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        // The marker is written first, so the key is never exportable, even if a write fails
+        let (private_key, public_key) = new_key_pair::<Ed25519PrivateKey>();
+        self.set(&get_non_exportable_name(name), true)?;
+        self.set(name, private_key)?;
+        Ok(public_key)
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        false
+    }
+
     fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
-        self.get(name).map(|v| v.value)
+        check_exportable(self, name)?;
+        read_private_key(self, name)
     }
 
     fn export_private_key_for_version(
@@ -32,33 +80,28 @@ impl<T: CryptoKVStorage> CryptoStorage for T {
         name: &str,
         version: Ed25519PublicKey,
     ) -> Result<Ed25519PrivateKey, Error> {
-        let current_private_key = self.export_private_key(name)?;
-        if current_private_key.public_key().eq(&version) {
-            return Ok(current_private_key);
-        }
+        check_exportable(self, name)?;
+        read_private_key_for_version(self, name, version)
+    }
 
-        match self.export_private_key(&get_previous_version_name(name)) {
-            Ok(previous_private_key) => {
-                if previous_private_key.public_key().eq(&version) {
-                    Ok(previous_private_key)
-                } else {
-                    Err(Error::KeyVersionNotFound(name.into(), version.to_string()))
-                }
-            }
-            Err(Error::KeyNotSet(_)) => {
-                Err(Error::KeyVersionNotFound(name.into(), version.to_string()))
-            }
-            Err(e) => Err(e),
-        }
+    fn import_private_key_versions(
+        &mut self,
+        name: &str,
+        versions: Vec<Ed25519PrivateKey>,
+    ) -> Result<usize, Error> {
+        import_key_versions(self, name, versions)
     }
 
     fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
-        self.set(name, key)
+        let public_key = key.public_key();
+        self.set(name, key)?;
+        logging::log_key_import(name, &public_key.to_bytes());
+        Ok(())
     }
 
     fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
         let response = self.get(name)?;
-        let key: Ed25519PrivateKey = response.value;
+        let key: Ed25519PrivateKey = decode_private_key(name, response.value)?;
 
         Ok(PublicKeyResponse {
             last_update: response.last_update,
@@ -67,7 +110,7 @@ impl<T: CryptoKVStorage> CryptoStorage for T {
     }
 
     fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
-        match self.export_private_key(&get_previous_version_name(name)) {
+        match read_private_key::<_, Ed25519PrivateKey>(self, &get_previous_version_name(name)) {
             Ok(previous_private_key) => Ok(previous_private_key.public_key()),
             Err(Error::KeyNotSet(_)) => Err(Error::KeyVersionNotFound(
                 name.into(),
@@ -78,19 +121,51 @@ impl<T: CryptoKVStorage> CryptoStorage for T {
     }
 
     fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
-        let private_key: Ed25519PrivateKey = self.get(name)?.value;
-        let (new_private_key, new_public_key) = new_ed25519_key_pair();
-        self.set(&get_previous_version_name(name), private_key)?;
-        self.set(name, new_private_key)?;
+        self.check_rotatable(name)?;
+        let mut versions = read_key_versions(self, name)?;
+        let (new_private_key, new_public_key) = new_key_pair::<Ed25519PrivateKey>();
+        versions.insert(0, new_private_key);
+        let pruned = versions.split_off(versions.len().min(self.max_key_versions().max(1)));
+
+        // Versions are shifted from the oldest, so the current version is replaced last
+        record_pruned_versions(self, name, &public_keys(&pruned))?;
+        for (depth, private_key) in versions.iter().enumerate().rev() {
+            self.set(&get_version_name(name, depth), private_key)?;
+        }
+        delete_key_versions(self, name, versions.len(), pruned.len())?;
         Ok(new_public_key)
     }
 
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        let mut versions = read_key_versions(self, name)?;
+        let mut keep = keep_latest_n.max(1);
+        if let Some(in_use_floor) = in_use_floor {
+            let depth = versions
+                .iter()
+                .position(|private_key| private_key.public_key() == in_use_floor)
+                .ok_or_else(|| missing_version_error(self, name, &in_use_floor))?;
+            keep = keep.max(depth + 1);
+        }
+        if keep >= versions.len() {
+            return Ok(());
+        }
+
+        let pruned = versions.split_off(keep);
+        record_pruned_versions(self, name, &public_keys(&pruned))?;
+        delete_key_versions(self, name, keep, pruned.len())
+    }
+
     fn sign<U: CryptoHash + Serialize>(
         &self,
         name: &str,
         message: &U,
     ) -> Result<Ed25519Signature, Error> {
-        let private_key = self.export_private_key(name)?;
+        let private_key: Ed25519PrivateKey = read_private_key(self, name)?;
         Ok(private_key.sign(message))
     }
 
@@ -100,16 +175,295 @@ impl<T: CryptoKVStorage> CryptoStorage for T {
         version: Ed25519PublicKey,
         message: &U,
     ) -> Result<Ed25519Signature, Error> {
-        let private_key = self.export_private_key_for_version(name, version)?;
+        let private_key = read_private_key_for_version(self, name, version)?;
+        Ok(private_key.sign(message))
+    }
+
+    fn sign_batch_using_version<U: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[U],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        let private_key = read_private_key_for_version(self, name, version)?;
+        Ok(messages
+            .iter()
+            .map(|message| private_key.sign(message))
+            .collect())
+    }
+
+    fn create_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        let (private_key, public_key) = new_key_pair::<K>();
+        write_private_key(self, name, &private_key)?;
+        Ok(public_key)
+    }
+
+    fn import_private_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+        key: K,
+    ) -> Result<(), Error> {
+        import_key_versions(self, name, vec![key])?;
+        Ok(())
+    }
+
+    fn export_private_key_as<K: SchemeSigningKey>(&self, name: &str) -> Result<K, Error> {
+        check_exportable(self, name)?;
+        read_private_key(self, name)
+    }
+
+    fn get_public_key_as<K: SchemeSigningKey>(
+        &self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        let private_key: K = read_private_key(self, name)?;
+        Ok(private_key.public_key())
+    }
+
+    fn sign_as<K: SchemeSigningKey, U: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &U,
+    ) -> Result<K::SignatureMaterial, Error> {
+        let private_key: K = read_private_key(self, name)?;
         Ok(private_key.sign(message))
     }
 }
 
-/// Private helper method to generate a new ed25519 key pair using entropy from the OS.
-fn new_ed25519_key_pair() -> (Ed25519PrivateKey, Ed25519PublicKey) {
+/// The stored representation of the private keys of schemes other than Ed25519.
+#[derive(Deserialize, Serialize)]
+struct StoredPrivateKey {
+    scheme: KeyScheme,
+    #[serde(
+        serialize_with = "to_base64",
+        deserialize_with = "from_base64_zeroizing"
+    )]
+    key: Zeroizing<Vec<u8>>,
+}
+
+fn from_base64_zeroizing<'de, D>(deserializer: D) -> Result<Zeroizing<Vec<u8>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    from_base64(deserializer).map(Zeroizing::new)
+}
+
+/// Private helper method to write the private key of the scheme of `K` at 'name'.
+fn write_private_key<S: KVStorage + ?Sized, K: SchemeSigningKey>(
+    storage: &mut S,
+    name: &str,
+    key: &K,
+) -> Result<(), Error> {
+    match K::SCHEME {
+        KeyScheme::Ed25519 => storage.set(name, key),
+        scheme => storage.set(
+            name,
+            StoredPrivateKey {
+                scheme,
+                key: Zeroizing::new(key.to_bytes()),
+            },
+        ),
+    }
+}
+
+/// Private helper method to decode the private key of the scheme of `K` read from 'name'. Ed25519
+/// keys are stored as strings.
+fn decode_private_key<K: SchemeSigningKey>(name: &str, value: Value) -> Result<K, Error> {
+    let (scheme, key) = if value.is_string() {
+        (KeyScheme::Ed25519, None)
+    } else {
+        let stored: StoredPrivateKey = serde_json::from_value(value.clone())?;
+        (stored.scheme, Some(stored.key))
+    };
+    if scheme != K::SCHEME {
+        return Err(Error::KeySchemeMismatch(
+            name.into(),
+            K::SCHEME.to_string(),
+            scheme.to_string(),
+        ));
+    }
+    match key {
+        Some(key) => {
+            K::try_from(&key[..]).map_err(|error| Error::SerializationError(error.to_string()))
+        }
+        None => Ok(serde_json::from_value(value)?),
+    }
+}
+
+/// Private helper method to read the private key of the scheme of `K` stored at 'name', whether or
+/// not it is exportable.
+fn read_private_key<S: KVStorage + ?Sized, K: SchemeSigningKey>(
+    storage: &S,
+    name: &str,
+) -> Result<K, Error> {
+    decode_private_key(name, storage.get(name)?.value)
+}
+
+/// Private helper method to read the Ed25519 private key stored at 'name' and identified by
+/// 'version', whether or not it is exportable. Versions are read from the most recent.
+fn read_private_key_for_version<S: KVStorage + ?Sized>(
+    storage: &S,
+    name: &str,
+    version: Ed25519PublicKey,
+) -> Result<Ed25519PrivateKey, Error> {
+    let mut depth = 0;
+    loop {
+        match read_private_key::<_, Ed25519PrivateKey>(storage, &get_version_name(name, depth)) {
+            Ok(private_key) if private_key.public_key() == version => return Ok(private_key),
+            Ok(_) => depth += 1,
+            Err(Error::KeyNotSet(_)) if depth > 0 => {
+                return Err(missing_version_error(storage, name, &version))
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Private helper method to read all versions of the key pair at 'name', from the most recent.
+fn read_key_versions<S: KVStorage + ?Sized>(
+    storage: &S,
+    name: &str,
+) -> Result<Vec<Ed25519PrivateKey>, Error> {
+    let mut versions: Vec<Ed25519PrivateKey> = vec![read_private_key(storage, name)?];
+    loop {
+        match read_private_key(storage, &get_version_name(name, versions.len())) {
+            Ok(private_key) => versions.push(private_key),
+            Err(Error::KeyNotSet(_)) => return Ok(versions),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Private helper method to import the given versions of a key pair of the scheme of `K` at 'name',
+/// from the most recent, replacing all existing versions. Returns the number of versions retained.
+fn import_key_versions<S: CryptoKVStorage + ?Sized, K: SchemeSigningKey>(
+    storage: &mut S,
+    name: &str,
+    mut versions: Vec<K>,
+) -> Result<usize, Error> {
+    if versions.is_empty() {
+        return Err(Error::InternalError(format!(
+            "No version of {} to import",
+            name
+        )));
+    }
+    let existing_versions = count_key_versions(storage, name)?;
+    versions.truncate(storage.max_key_versions().max(1));
+
+    // Versions are written from the oldest, so the current version is replaced last
+    for (depth, private_key) in versions.iter().enumerate().rev() {
+        let version_name = get_version_name(name, depth);
+        write_private_key(storage, &version_name, private_key)?;
+        logging::log_key_import(&version_name, &private_key.verifying_key().to_bytes());
+    }
+    if existing_versions > versions.len() {
+        delete_key_versions(
+            storage,
+            name,
+            versions.len(),
+            existing_versions - versions.len(),
+        )?;
+    }
+    Ok(versions.len())
+}
+
+/// Private helper method to count the versions of the key pair at 'name', of any scheme.
+fn count_key_versions<S: KVStorage + ?Sized>(storage: &S, name: &str) -> Result<usize, Error> {
+    let mut count = 0;
+    loop {
+        match storage.get::<Value>(&get_version_name(name, count)) {
+            Ok(_) => count += 1,
+            Err(Error::KeyNotSet(_)) => return Ok(count),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Private helper method to delete `count` versions of the key pair at 'name', starting at the
+/// given depth.
+fn delete_key_versions<S: KVStorage + ?Sized>(
+    storage: &mut S,
+    name: &str,
+    depth: usize,
+    count: usize,
+) -> Result<(), Error> {
+    for depth in depth..depth + count {
+        storage.delete(&get_version_name(name, depth))?;
+    }
+    Ok(())
+}
+
+/// Private helper method to read the public keys of the pruned versions of the key pair at 'name'.
+fn read_pruned_versions<S: KVStorage + ?Sized>(
+    storage: &S,
+    name: &str,
+) -> Result<Vec<Ed25519PublicKey>, Error> {
+    match storage.get(&get_pruned_versions_name(name)) {
+        Ok(response) => Ok(response.value),
+        Err(Error::KeyNotSet(_)) => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+/// Helper method to record the given versions of the key pair at 'name' as pruned, keeping the
+/// `MAX_PRUNED_VERSIONS` most recent ones. This happens before they are deleted, so that a version
+/// is never silently lost.
+pub(crate) fn record_pruned_versions<S: KVStorage + ?Sized>(
+    storage: &mut S,
+    name: &str,
+    pruned: &[Ed25519PublicKey],
+) -> Result<(), Error> {
+    if pruned.is_empty() {
+        return Ok(());
+    }
+    let mut pruned_versions = read_pruned_versions(storage, name)?;
+    pruned_versions.extend_from_slice(pruned);
+    let forgotten = pruned_versions.len().saturating_sub(MAX_PRUNED_VERSIONS);
+    pruned_versions.drain(..forgotten);
+    storage.set(&get_pruned_versions_name(name), pruned_versions)
+}
+
+/// Private helper method to get the public keys of the given versions.
+fn public_keys(versions: &[Ed25519PrivateKey]) -> Vec<Ed25519PublicKey> {
+    versions
+        .iter()
+        .map(|private_key| private_key.public_key())
+        .collect()
+}
+
+/// Helper method to tell a pruned version of the key pair at 'name' apart from one that never
+/// existed.
+pub(crate) fn missing_version_error<S: KVStorage + ?Sized>(
+    storage: &S,
+    name: &str,
+    version: &Ed25519PublicKey,
+) -> Error {
+    match read_pruned_versions(storage, name) {
+        Ok(pruned_versions) if pruned_versions.contains(version) => {
+            Error::KeyVersionPruned(name.into(), version.to_string())
+        }
+        Ok(_) => Error::KeyVersionNotFound(name.into(), version.to_string()),
+        Err(e) => e,
+    }
+}
+
+/// Private helper method that fails if the key pair at 'name' was created by `generate_key`.
+fn check_exportable<S: KVStorage + ?Sized>(storage: &S, name: &str) -> Result<(), Error> {
+    match storage.get::<bool>(&get_non_exportable_name(name)) {
+        Ok(response) if response.value => Err(Error::KeyNotExportable(name.into())),
+        Ok(_) | Err(Error::KeyNotSet(_)) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Private helper method to generate a new key pair of the scheme of `K` using entropy from the OS.
+fn new_key_pair<K: SchemeSigningKey>() -> (K, K::VerifyingKeyMaterial) {
     let mut seed_rng = OsRng;
     let mut rng = rand::rngs::StdRng::from_seed(seed_rng.gen());
-    let private_key = Ed25519PrivateKey::generate(&mut rng);
+    let private_key = K::generate(&mut rng);
     let public_key = private_key.public_key();
     (private_key, public_key)
 }
@@ -119,3 +473,36 @@ fn new_ed25519_key_pair() -> (Ed25519PrivateKey, Ed25519PublicKey) {
 fn get_previous_version_name(name: &str) -> String {
     format!("{}_previous", name)
 }
+
+/// Private helper method to get the name of the version of the given key pair at the given depth,
+/// where 0 is the current version and 1 the previous one.
+fn get_version_name(name: &str, depth: usize) -> String {
+    match depth {
+        0 => name.into(),
+        1 => get_previous_version_name(name),
+        depth => format!("{}_previous_{}", name, depth),
+    }
+}
+
+/// Private helper method to get the name of the public keys of the pruned versions of the given
+/// key pair.
+fn get_pruned_versions_name(name: &str) -> String {
+    format!("{}_pruned", name)
+}
+
+/// Private helper method to get the name of the marker of a key pair created by `generate_key`.
+fn get_non_exportable_name(name: &str) -> String {
+    format!("{}_non_exportable", name)
+}
+
+/// Returns true if 'name' holds a previous version, the pruned versions or the marker of the key
+/// pair at 'key_name', rather than a value of its own.
+pub(crate) fn is_key_pair_entry(name: &str, key_name: &str) -> bool {
+    match name.strip_prefix(key_name) {
+        Some("_previous") | Some("_pruned") | Some("_non_exportable") => true,
+        Some(suffix) => suffix
+            .strip_prefix("_previous_")
+            .map_or(false, |depth| depth.parse::<usize>().is_ok()),
+        None => false,
+    }
+}
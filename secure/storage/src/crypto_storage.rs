@@ -2,9 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::Error;
-use aptos_crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    SigningKey, Uniform, ValidCryptoMaterial,
+};
 use enum_dispatch::enum_dispatch;
 use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt};
 
 /// CryptoStorage provides an abstraction for secure generation and handling of cryptographic keys.
 #[enum_dispatch]
@@ -13,6 +17,17 @@ pub trait CryptoStorage {
     /// multiple times with the same name is implementation specific.
     fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error>;
 
+    /// Generates a new named Ed25519 private key that cannot be exported: exporting any version
+    /// of it fails with `Error::KeyNotExportable`, and it can only be used through `sign` and
+    /// `sign_using_version`. Whether the key material ever leaves the backend is reported by
+    /// `supports_non_exportable_keys`.
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error>;
+
+    /// Returns true if keys created by `generate_key` are held and used for signing inside the
+    /// backend, so that their key material is never available to this process. Otherwise, the
+    /// backend only refuses to export them.
+    fn supports_non_exportable_keys(&self) -> bool;
+
     /// Returns the Ed25519 private key stored at 'name'.
     fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error>;
 
@@ -23,6 +38,43 @@ pub trait CryptoStorage {
     /// running in test environments where a set of deterministic keys must be generated.
     fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error>;
 
+    /// Imports the private key at the provided name unless a key is already stored there, in
+    /// which case the existing key is kept. Unlike `import_private_key`, the behavior is the same
+    /// for all backends.
+    fn import_private_key_if_absent(
+        &mut self,
+        name: &str,
+        key: Ed25519PrivateKey,
+    ) -> Result<ImportResult, Error> {
+        match self.get_public_key(name) {
+            Ok(_) => return Ok(ImportResult::AlreadyExisted),
+            Err(Error::KeyNotSet(_)) => (),
+            Err(error) => return Err(error),
+        }
+        match self.import_private_key(name, key) {
+            Ok(()) => Ok(ImportResult::Fresh),
+            Err(Error::KeyAlreadyExists(_)) => Ok(ImportResult::AlreadyExisted),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Imports the given versions of a key at the provided name, from the most recent, so that the
+    /// older versions can still be used through their version. Returns the number of versions
+    /// retained, as storages may not support importing previous versions: by default, only the
+    /// most recent version is imported.
+    fn import_private_key_versions(
+        &mut self,
+        name: &str,
+        versions: Vec<Ed25519PrivateKey>,
+    ) -> Result<usize, Error> {
+        let key = versions
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::InternalError(format!("No version of {} to import", name)))?;
+        self.import_private_key(name, key)?;
+        Ok(1)
+    }
+
     /// Returns the Ed25519 private key stored at 'name' and identified by 'version', which is the
     /// corresponding public key. This may fail even if the 'named' key exists but the version is
     /// not present.
@@ -44,6 +96,18 @@ pub trait CryptoStorage {
     /// the version. At most two versions are expected to be retained.
     fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error>;
 
+    /// Deletes the older versions of the key at 'name', keeping its `keep_latest_n` most recent
+    /// versions. The current version is always kept, and so is every version at least as recent
+    /// as `in_use_floor`, the oldest version still in use, which must be a version of the key.
+    /// Backends that record pruned versions fail with `Error::KeyVersionPruned` when they are
+    /// used; others fail with `Error::KeyVersionNotFound`.
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error>;
+
     /// Signs the provided securely-hashable struct, using the 'named' private
     /// key.
     // The FQDNs on the next line help macros don't remove them
@@ -62,6 +126,121 @@ pub trait CryptoStorage {
         version: Ed25519PublicKey,
         message: &T,
     ) -> Result<Ed25519Signature, Error>;
+
+    /// Signs each of the provided securely-hashable structs using the 'named' and 'versioned'
+    /// private key, in a single interaction with the backend.
+    /// Signatures are returned in the order of the messages, and `Error::BatchSigningFailed`
+    /// holds the index of a message that could not be signed.
+    fn sign_batch_using_version<T: aptos_crypto::hash::CryptoHash + serde::Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error>;
+
+    /// Securely generates a new named private key of the scheme of `K`, see `create_key`. By
+    /// default, only Ed25519 keys are supported, through the Ed25519 interfaces above; other
+    /// schemes fail with `Error::UnsupportedKeyScheme`.
+    fn create_key_as<K: crate::SchemeSigningKey>(
+        &mut self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        check_ed25519::<K>()?;
+        convert(&self.create_key(name)?)
+    }
+
+    /// Imports a private key of the scheme of `K` at the provided name, see `import_private_key`.
+    fn import_private_key_as<K: crate::SchemeSigningKey>(
+        &mut self,
+        name: &str,
+        key: K,
+    ) -> Result<(), Error> {
+        check_ed25519::<K>()?;
+        self.import_private_key(name, convert(&key)?)
+    }
+
+    /// Returns the private key of the scheme of `K` stored at 'name'. Keys of another scheme fail
+    /// with `Error::KeySchemeMismatch`, if the storage records schemes.
+    fn export_private_key_as<K: crate::SchemeSigningKey>(&self, name: &str) -> Result<K, Error> {
+        check_ed25519::<K>()?;
+        convert(&self.export_private_key(name)?)
+    }
+
+    /// Returns the public key of the scheme of `K` stored at 'name'.
+    fn get_public_key_as<K: crate::SchemeSigningKey>(
+        &self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        check_ed25519::<K>()?;
+        convert(&self.get_public_key(name)?.public_key)
+    }
+
+    /// Signs the provided securely-hashable struct using the 'named' private key of the scheme
+    /// of `K`.
+    // The FQDNs on the next line help macros, don't remove them
+    fn sign_as<K: crate::SchemeSigningKey, T: aptos_crypto::hash::CryptoHash + serde::Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<K::SignatureMaterial, Error> {
+        check_ed25519::<K>()?;
+        let signature = self.sign(name, message)?;
+        K::SignatureMaterial::try_from(&signature.to_bytes()[..])
+            .map_err(|error| Error::SerializationError(error.to_string()))
+    }
+}
+
+/// The signature schemes of the keys held by a CryptoStorage. Storages that record the scheme of
+/// their keys refuse to use a key as one of another scheme, e.g., a BLS12-381 private key, which
+/// has the length of an Ed25519 one, as an Ed25519 key.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyScheme {
+    Ed25519,
+    Bls12381,
+}
+
+impl fmt::Display for KeyScheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyScheme::Ed25519 => write!(f, "ed25519"),
+            KeyScheme::Bls12381 => write!(f, "bls12381"),
+        }
+    }
+}
+
+/// A private key that can be held by a CryptoStorage, i.e., that knows its scheme.
+pub trait SchemeSigningKey: SigningKey + Uniform {
+    const SCHEME: KeyScheme;
+}
+
+impl SchemeSigningKey for Ed25519PrivateKey {
+    const SCHEME: KeyScheme = KeyScheme::Ed25519;
+}
+
+/// Private helper method that fails unless `K` is of the Ed25519 scheme, the only one supported by
+/// the Ed25519 interfaces.
+fn check_ed25519<K: SchemeSigningKey>() -> Result<(), Error> {
+    match K::SCHEME {
+        KeyScheme::Ed25519 => Ok(()),
+        scheme => Err(Error::UnsupportedKeyScheme(scheme.to_string())),
+    }
+}
+
+/// Private helper method to convert between the Ed25519 types and those of a scheme that was
+/// checked to be Ed25519.
+fn convert<A: ValidCryptoMaterial, B: ValidCryptoMaterial>(material: &A) -> Result<B, Error> {
+    B::try_from(&material.to_bytes()[..])
+        .map_err(|error| Error::SerializationError(error.to_string()))
+}
+
+/// The outcome of `CryptoStorage::import_private_key_if_absent`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImportResult {
+    /// The key was imported.
+    Fresh,
+    /// A key was already stored at the name, and was kept.
+    AlreadyExisted,
 }
 
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
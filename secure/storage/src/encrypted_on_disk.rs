@@ -0,0 +1,336 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    from_base64, on_disk, to_base64, CryptoKVStorage, Error, GetResponse, KVStorage, KeyWatch,
+    OnDiskStorage,
+};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, NewAead, Payload},
+    Aes256Gcm,
+};
+use aptos_crypto::HashValue;
+use argon2::Argon2;
+use rand::{rngs::OsRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    path::{Path, PathBuf},
+};
+use zeroize::Zeroizing;
+
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+const SALT_SIZE: usize = 16;
+
+/// Where the passphrase of an EncryptedOnDiskStorage is read from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Passphrase {
+    /// The name of an environment variable holding the passphrase.
+    FromEnv(String),
+    /// A key file holding the passphrase. Trailing newlines are ignored.
+    FromDisk(PathBuf),
+}
+
+impl Passphrase {
+    pub fn read(&self) -> Result<Zeroizing<String>, Error> {
+        let mut passphrase = Zeroizing::new(match self {
+            Passphrase::FromEnv(var) => env::var(var).map_err(|e| {
+                Error::InternalError(format!("Unable to read passphrase from {}: {}", var, e))
+            })?,
+            Passphrase::FromDisk(path) => fs::read_to_string(path)?,
+        });
+        while passphrase.ends_with('\n') || passphrase.ends_with('\r') {
+            passphrase.pop();
+        }
+        if passphrase.is_empty() {
+            return Err(Error::InternalError("The passphrase is empty".into()));
+        }
+        Ok(passphrase)
+    }
+}
+
+/// EncryptedOnDiskStorage is an OnDiskStorage whose file is encrypted with AES-256-GCM, so that
+/// secrets are not written to disk as plaintext. The key is derived from a passphrase with
+/// Argon2id. The file holds the salt of the key derivation, a check value of the key, the nonce
+/// and the ciphertext, so a wrong passphrase is told apart from a file that was tampered with.
+/// Only a file that does not exist starts a new storage: an empty file is reported as tampered
+/// with, rather than silently replacing the keys it held.
+///
+/// The key is derived once, when the storage is opened. Every write encrypts the file with a
+/// fresh nonce. Like OnDiskStorage, this should not be used in production.
+pub struct EncryptedOnDiskStorage {
+    inner: OnDiskStorage,
+}
+
+impl EncryptedOnDiskStorage {
+    /// Opens the storage at `file_path`, creating it if it does not exist. Fails with
+    /// `Error::DecryptionError` if the passphrase does not match that of an existing file, and
+    /// with `Error::TamperError` if the file is empty or its salt or key check was altered.
+    pub fn new(file_path: PathBuf, passphrase: &Passphrase) -> Result<Self, Error> {
+        let passphrase = passphrase.read()?;
+        if !file_path.exists() {
+            // The file holds the salt from the start, so that it is never empty
+            let storage = Self {
+                inner: OnDiskStorage::new_with_cipher(
+                    file_path,
+                    FileCipher::generate(passphrase.as_bytes())?,
+                ),
+            };
+            storage.inner.write(&HashMap::new())?;
+            return Ok(storage);
+        }
+
+        let contents = on_disk::read_file(&file_path)?;
+        if contents.is_empty() {
+            return Err(Error::TamperError(format!(
+                "The storage {} is empty",
+                file_path.display()
+            )));
+        }
+        let cipher = FileCipher::for_contents(passphrase.as_bytes(), &contents).map_err(
+            |error| match error {
+                Error::DecryptionError(_) => {
+                    Error::DecryptionError(format!("Wrong passphrase for {}", file_path.display()))
+                }
+                error => error,
+            },
+        )?;
+
+        Ok(Self {
+            inner: OnDiskStorage::new_with_cipher(file_path, cipher),
+        })
+    }
+
+    /// Encrypts the plaintext OnDiskStorage at `plaintext_path` into a new storage at
+    /// `file_path`, which may be the same path to encrypt the storage in place. An existing
+    /// storage at `file_path` is never overwritten, unless it is the plaintext storage.
+    ///
+    /// Warning: migrating into another file leaves the plaintext storage and its `.bak` backup
+    /// behind, with every secret in the clear. The operator must delete them securely.
+    pub fn migrate_from_plaintext(
+        plaintext_path: &Path,
+        file_path: PathBuf,
+        passphrase: &Passphrase,
+    ) -> Result<Self, Error> {
+        let data = on_disk::decode_data(on_disk::read_file(plaintext_path)?, None)?;
+
+        let in_place = file_path.exists()
+            && fs::canonicalize(&file_path)? == fs::canonicalize(plaintext_path)?;
+        let overwrites_storage =
+            file_path.exists() && fs::metadata(&file_path)?.len() > 0 && !in_place;
+        if overwrites_storage {
+            return Err(Error::InternalError(format!(
+                "Refusing to overwrite existing storage: {}",
+                file_path.display()
+            )));
+        }
+
+        // The file is replaced atomically, so the plaintext is kept should encryption fail
+        let cipher = FileCipher::generate(passphrase.read()?.as_bytes())?;
+        let storage = Self {
+            inner: OnDiskStorage::new_with_cipher(file_path, cipher),
+        };
+        storage.inner.write(&data)?;
+        if !in_place {
+            aptos_logger::warn!(
+                "Migrated {} to an encrypted storage, but the plaintext storage and its backup \
+                 remain on disk and must be deleted securely",
+                plaintext_path.display()
+            );
+        }
+        Ok(storage)
+    }
+
+    /// See `OnDiskStorage::lock`.
+    pub fn lock(&mut self) -> Result<(), Error> {
+        self.inner.lock()
+    }
+
+    /// See `OnDiskStorage::set_max_key_versions`.
+    pub fn set_max_key_versions(&mut self, max_key_versions: usize) {
+        self.inner.set_max_key_versions(max_key_versions)
+    }
+}
+
+impl KVStorage for EncryptedOnDiskStorage {
+    fn available(&self) -> Result<(), Error> {
+        self.inner.available()
+    }
+
+    fn get<V: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<V>, Error> {
+        self.inner.get(key)
+    }
+
+    fn set<V: Serialize>(&mut self, key: &str, value: V) -> Result<(), Error> {
+        self.inner.set(key, value)
+    }
+
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        self.inner.get_batch(keys)
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        self.inner.set_batch(entries)
+    }
+
+    fn set_if_version<V: Serialize>(
+        &mut self,
+        key: &str,
+        value: V,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        self.inner.set_if_version(key, value, expected_version)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        self.inner.delete(key)
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        self.inner.list_keys(prefix)
+    }
+
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        self.inner.watch(key)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.inner.reset_and_clear()
+    }
+}
+
+impl CryptoKVStorage for EncryptedOnDiskStorage {
+    fn max_key_versions(&self) -> usize {
+        self.inner.max_key_versions()
+    }
+}
+
+/// The contents of the file of an EncryptedOnDiskStorage.
+#[derive(Deserialize, Serialize)]
+struct EncryptedFile {
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    salt: Vec<u8>,
+    /// The hash of the key, to tell a wrong passphrase apart from a tampered file.
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    key_check: Vec<u8>,
+    /// The hash of the salt and the key check, so that altering either is reported as tampering
+    /// rather than as a wrong passphrase.
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    header_check: Vec<u8>,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    nonce: Vec<u8>,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    ciphertext: Vec<u8>,
+}
+
+/// Encrypts and decrypts the contents of the file of an EncryptedOnDiskStorage.
+pub(crate) struct FileCipher {
+    salt: Vec<u8>,
+    key: Zeroizing<Vec<u8>>,
+    key_check: Vec<u8>,
+}
+
+impl FileCipher {
+    /// Derives the key from the passphrase with a new random salt.
+    pub(crate) fn generate(passphrase: &[u8]) -> Result<Self, Error> {
+        let mut salt = vec![0; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        Self::new(passphrase, salt)
+    }
+
+    fn new(passphrase: &[u8], salt: Vec<u8>) -> Result<Self, Error> {
+        let mut key = Zeroizing::new(vec![0; KEY_SIZE]);
+        Argon2::default()
+            .hash_password_into(passphrase, &salt, &mut key)
+            .map_err(|e| Error::InternalError(format!("Unable to derive the key: {}", e)))?;
+        let key_check = HashValue::sha3_256_of(&key).to_vec();
+        Ok(Self {
+            salt,
+            key,
+            key_check,
+        })
+    }
+
+    /// Derives the key from the passphrase with the salt of contents encrypted by a FileCipher.
+    /// Fails with `Error::DecryptionError` if the passphrase does not match that of the contents,
+    /// and with `Error::TamperError` if their salt or key check was altered.
+    pub(crate) fn for_contents(passphrase: &[u8], contents: &[u8]) -> Result<Self, Error> {
+        let file: EncryptedFile = serde_json::from_slice(contents)?;
+        check_header(&file)?;
+        let cipher = Self::new(passphrase, file.salt)?;
+        if cipher.key_check != file.key_check {
+            return Err(Error::DecryptionError("Wrong passphrase".into()));
+        }
+        Ok(cipher)
+    }
+
+    pub(crate) fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut nonce = vec![0; NONCE_SIZE];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = Aes256Gcm::new(GenericArray::from_slice(&self.key))
+            .encrypt(
+                GenericArray::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &self.salt,
+                },
+            )
+            .map_err(|_| Error::InternalError("Unable to encrypt the storage".into()))?;
+
+        Ok(serde_json::to_vec(&EncryptedFile {
+            salt: self.salt.clone(),
+            key_check: self.key_check.clone(),
+            header_check: header_check(&self.salt, &self.key_check),
+            nonce,
+            ciphertext,
+        })?)
+    }
+
+    /// Fails with `Error::TamperError` if the file was altered after it was encrypted, including
+    /// if it was replaced by a file encrypted with another key.
+    pub(crate) fn decrypt(&self, contents: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let file: EncryptedFile = serde_json::from_slice(contents)?;
+        check_header(&file)?;
+        if file.salt != self.salt || file.key_check != self.key_check {
+            return Err(Error::TamperError(
+                "The storage was encrypted with another key".into(),
+            ));
+        }
+        if file.nonce.len() != NONCE_SIZE {
+            return Err(Error::TamperError(format!(
+                "Invalid nonce length: {}",
+                file.nonce.len()
+            )));
+        }
+
+        Aes256Gcm::new(GenericArray::from_slice(&self.key))
+            .decrypt(
+                GenericArray::from_slice(&file.nonce),
+                Payload {
+                    msg: &file.ciphertext,
+                    aad: &file.salt,
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| Error::TamperError("The storage failed authentication".into()))
+    }
+}
+
+fn header_check(salt: &[u8], key_check: &[u8]) -> Vec<u8> {
+    let mut header = salt.to_vec();
+    header.extend_from_slice(key_check);
+    HashValue::sha3_256_of(&header).to_vec()
+}
+
+fn check_header(file: &EncryptedFile) -> Result<(), Error> {
+    if file.header_check != header_check(&file.salt, &file.key_check) {
+        return Err(Error::TamperError(
+            "The salt or the key check of the storage was altered".into(),
+        ));
+    }
+    Ok(())
+}
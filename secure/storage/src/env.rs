@@ -0,0 +1,135 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{kv_storage::filter_keys, CryptoKVStorage, Error, GetResponse, KVStorage};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::env;
+
+/// The default prefix of the environment variables read by EnvStorage.
+pub const DEFAULT_ENV_PREFIX: &str = "APTOS_SECURE_";
+
+/// EnvStorage is a read-only storage that reads every key from an environment variable, e.g., as
+/// injected into a container from a secret. The variable of a key is the prefix followed by the
+/// key in upper case, with every character other than letters and digits replaced by an
+/// underscore, so that the consensus key is read from APTOS_SECURE_CONSENSUS.
+///
+/// Values are decoded as JSON (e.g., numbers), as strings (e.g., hex encoded keys and accounts),
+/// or from base64 into hex encoded strings, whichever matches the expected type. All writes fail
+/// with `Error::ReadOnly`, so values that safety rules must update (e.g., safety data and the
+/// waypoint) are to be held by a writable storage within an `Overlay`.
+pub struct EnvStorage {
+    prefix: String,
+}
+
+impl EnvStorage {
+    pub fn new(prefix: Option<String>) -> Self {
+        Self {
+            prefix: prefix.unwrap_or_else(|| DEFAULT_ENV_PREFIX.into()),
+        }
+    }
+
+    fn var_name(&self, key: &str) -> String {
+        let key: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+impl KVStorage for EnvStorage {
+    fn available(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        let var_name = self.var_name(key);
+        let value = match env::var(&var_name) {
+            Ok(value) => value,
+            Err(env::VarError::NotPresent) => return Err(Error::KeyNotSet(key.into())),
+            Err(env::VarError::NotUnicode(_)) => {
+                return Err(Error::SerializationError(format!(
+                    "{} is not valid unicode",
+                    var_name
+                )))
+            }
+        };
+        // Environment variables carry no update time, and are only written once
+        Ok(GetResponse::new_with_version(
+            decode(&var_name, &value)?,
+            0,
+            1,
+        ))
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, _value: T) -> Result<(), Error> {
+        Err(Error::ReadOnly(key.into()))
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        _value: T,
+        _expected_version: u64,
+    ) -> Result<(), Error> {
+        Err(Error::ReadOnly(key.into()))
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        let keys: Vec<_> = entries.iter().map(|(key, _)| *key).collect();
+        Err(Error::ReadOnly(keys.join(", ")))
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        Err(Error::ReadOnly(key.into()))
+    }
+
+    /// Lists the keys of all variables with the prefix, in lower case. As variable names are
+    /// upper case, the listed keys only match the keys that were set if those are lower case.
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        let keys = env::vars_os().filter_map(|(name, _)| {
+            let name = name.into_string().ok()?;
+            Some(name.strip_prefix(&self.prefix)?.to_ascii_lowercase())
+        });
+        Ok(filter_keys(keys, prefix))
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        Err(Error::ReadOnly(self.prefix.clone()))
+    }
+}
+
+impl CryptoKVStorage for EnvStorage {}
+
+/// Decodes the value of the variable into the expected type, trying in turn the value as JSON, as
+/// a string, and as base64 encoded bytes, which are read as a hex encoded string.
+fn decode<T: DeserializeOwned>(var_name: &str, value: &str) -> Result<T, Error> {
+    let value = value.trim();
+    let mut candidates = vec![];
+    if let Ok(json) = serde_json::from_str::<Value>(value) {
+        candidates.push(json);
+    }
+    candidates.push(Value::String(value.into()));
+    if let Ok(bytes) = base64::decode(value) {
+        candidates.push(Value::String(hex::encode(bytes)));
+    }
+
+    candidates
+        .into_iter()
+        .find_map(|candidate| serde_json::from_value(candidate).ok())
+        .ok_or_else(|| {
+            Error::SerializationError(format!(
+                "Unable to decode {} into {}",
+                var_name,
+                std::any::type_name::<T>()
+            ))
+        })
+}
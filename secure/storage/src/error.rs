@@ -2,25 +2,106 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use serde::{Deserialize, Serialize};
-use std::io;
+use std::{io, time::Duration};
 use thiserror::Error;
 
-#[derive(Debug, Deserialize, Error, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Error, PartialEq, Serialize)]
 pub enum Error {
+    #[error("Access to the storage denied: {0}")]
+    AccessDenied(String),
+    #[error("Failed to sign message {0} of the batch: {1}")]
+    BatchSigningFailed(usize, String),
+    #[error("Storage circuit is open after repeated failures, failing fast: {0}")]
+    CircuitOpen(String),
+    #[error("Storage is corrupted: {0}")]
+    CorruptedStorage(String),
+    #[error("Decryption error: {0}")]
+    DecryptionError(String),
     #[error("Entropy error: {0}")]
     EntropyError(String),
     #[error("Internal error: {0}")]
     InternalError(String),
     #[error("Key already exists: {0}")]
     KeyAlreadyExists(String),
+    #[error("Key is not exportable: {0}")]
+    KeyNotExportable(String),
     #[error("Key not set: {0}")]
     KeyNotSet(String),
-    #[error("Permission denied")]
-    PermissionDenied,
+    #[error("Key scheme mismatch, key name: {0}, expected: {1}, found: {2}")]
+    KeySchemeMismatch(String, String, String),
+    #[error("Migrated entry does not match the source: {0}")]
+    MigrationMismatch(String),
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("Denied by the storage key policy: {0}")]
+    PolicyDenied(String),
+    #[error("Storage operation rate limited, retry after {retry_after:?}")]
+    RateLimited { retry_after: Duration },
+    #[error("Storage is read-only, unable to write: {0}")]
+    ReadOnly(String),
     #[error("Serialization error: {0}")]
     SerializationError(String),
     #[error("Key version not found, key name: {0}, version: {1}")]
     KeyVersionNotFound(String, String),
+    #[error("Key version pruned, key name: {0}, version: {1}")]
+    KeyVersionPruned(String, String),
+    #[error("Storage {0} already in use by PID {1}")]
+    StorageInUse(String, String),
+    #[error("Version mismatch, expected: {expected}, actual: {actual}")]
+    VersionMismatch { expected: u64, actual: u64 },
+    #[error("Storage was tampered with: {0}")]
+    TamperError(String),
+    #[error("Key scheme is not supported by the storage: {0}")]
+    UnsupportedKeyScheme(String),
+    #[error("Operation is not supported by the storage: {0}")]
+    UnsupportedOperation(String),
+    #[error("Storage is unavailable: {reason}, retryable: {retryable}")]
+    Unavailable { retryable: bool, reason: String },
+    #[error("Write conflicted with concurrent writes: {0}")]
+    WriteConflict(String),
+}
+
+impl Error {
+    /// Whether the operation may succeed if retried, e.g., after a timeout or throttling.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited { .. }
+                | Self::Unavailable {
+                    retryable: true,
+                    ..
+                }
+        )
+    }
+
+    /// Classifies the failure of a request to a remote backend by its HTTP status: throttling and
+    /// server errors mean that the backend is unavailable, while other statuses are unexpected.
+    /// A 403 is what backends return once a token expired, while a 401 may be a misconfigured or
+    /// not yet valid credential, so only the former is reported as `PermissionDenied`.
+    fn from_http_status(status: u16, reason: String) -> Self {
+        match status {
+            401 => Self::AccessDenied(reason),
+            403 => Self::PermissionDenied(reason),
+            429 | 500 | 502 | 503 | 504 => Self::Unavailable {
+                retryable: true,
+                reason,
+            },
+            500..=599 => Self::Unavailable {
+                retryable: false,
+                reason,
+            },
+            _ => Self::InternalError(reason),
+        }
+    }
+
+    /// A request that did not reach the backend, e.g., as the connection was refused or timed
+    /// out.
+    fn unreachable(reason: String) -> Self {
+        Self::Unavailable {
+            retryable: true,
+            reason,
+        }
+    }
 }
 
 impl From<base64::DecodeError> for Error {
@@ -37,7 +118,17 @@ impl From<chrono::format::ParseError> for Error {
 
 impl From<io::Error> for Error {
     fn from(error: io::Error) -> Self {
-        Self::InternalError(format!("{}", error))
+        match error.kind() {
+            // A local file the process may not access, rather than an expired token
+            io::ErrorKind::PermissionDenied => Self::AccessDenied(format!("{}", error)),
+            io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted => Self::unreachable(format!("{}", error)),
+            _ => Self::InternalError(format!("{}", error)),
+        }
     }
 }
 
@@ -57,7 +148,13 @@ impl From<aptos_vault_client::Error> for Error {
     fn from(error: aptos_vault_client::Error) -> Self {
         match error {
             aptos_vault_client::Error::NotFound(_, key) => Self::KeyNotSet(key),
-            aptos_vault_client::Error::HttpError(403, _, _) => Self::PermissionDenied,
+            aptos_vault_client::Error::HttpError(status, _, _) => {
+                Self::from_http_status(status, format!("{}", error))
+            }
+            aptos_vault_client::Error::SyntheticError(_) => Self::unreachable(format!("{}", error)),
+            aptos_vault_client::Error::SerializationError(_) => {
+                Self::SerializationError(format!("{}", error))
+            }
             _ => Self::InternalError(format!("{}", error)),
         }
     }
@@ -67,7 +164,72 @@ impl From<aptos_github_client::Error> for Error {
     fn from(error: aptos_github_client::Error) -> Self {
         match error {
             aptos_github_client::Error::NotFound(key) => Self::KeyNotSet(key),
-            aptos_github_client::Error::HttpError(403, _, _) => Self::PermissionDenied,
+            aptos_github_client::Error::HttpError(status, _, _) => {
+                Self::from_http_status(status, format!("{}", error))
+            }
+            aptos_github_client::Error::InternalError(_) => Self::unreachable(format!("{}", error)),
+            aptos_github_client::Error::SerializationError(_) => {
+                Self::SerializationError(format!("{}", error))
+            }
+            aptos_github_client::Error::WriteConflict(key) => Self::WriteConflict(key),
+            _ => Self::InternalError(format!("{}", error)),
+        }
+    }
+}
+
+impl From<aptos_aws_client::Error> for Error {
+    fn from(error: aptos_aws_client::Error) -> Self {
+        match error {
+            aptos_aws_client::Error::NotFound(key) => Self::KeyNotSet(key),
+            aptos_aws_client::Error::ServiceError(403, _, _) => {
+                Self::PermissionDenied(format!("{}", error))
+            }
+            aptos_aws_client::Error::ServiceError(_, ref error_type, _)
+                if error_type == "ExpiredTokenException" =>
+            {
+                Self::PermissionDenied(format!("{}", error))
+            }
+            aptos_aws_client::Error::ServiceError(_, ref error_type, _)
+                if matches!(
+                    error_type.as_str(),
+                    "AccessDeniedException"
+                        | "InvalidSignatureException"
+                        | "UnrecognizedClientException"
+                ) =>
+            {
+                Self::AccessDenied(format!("{}", error))
+            }
+            aptos_aws_client::Error::ServiceError(_, ref error_type, _)
+                if matches!(
+                    error_type.as_str(),
+                    "ThrottlingException" | "RequestLimitExceeded" | "TooManyRequestsException"
+                ) =>
+            {
+                Self::unreachable(format!("{}", error))
+            }
+            aptos_aws_client::Error::ServiceError(status, _, _) => {
+                Self::from_http_status(status, format!("{}", error))
+            }
+            aptos_aws_client::Error::InternalError(_) => Self::unreachable(format!("{}", error)),
+            aptos_aws_client::Error::SerializationError(_) => {
+                Self::SerializationError(format!("{}", error))
+            }
+            _ => Self::InternalError(format!("{}", error)),
+        }
+    }
+}
+
+impl From<aptos_gcp_client::Error> for Error {
+    fn from(error: aptos_gcp_client::Error) -> Self {
+        match error {
+            aptos_gcp_client::Error::NotFound(key) => Self::KeyNotSet(key),
+            aptos_gcp_client::Error::HttpError(status, _, _) => {
+                Self::from_http_status(status, format!("{}", error))
+            }
+            aptos_gcp_client::Error::InternalError(_) => Self::unreachable(format!("{}", error)),
+            aptos_gcp_client::Error::SerializationError(_) => {
+                Self::SerializationError(format!("{}", error))
+            }
             _ => Self::InternalError(format!("{}", error)),
         }
     }
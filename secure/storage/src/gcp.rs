@@ -0,0 +1,397 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    kv_storage::filter_keys, logging, CryptoStorage, Error, GetResponse, KVStorage,
+    PublicKeyResponse,
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+    PrivateKey, SigningKey, Uniform,
+};
+use aptos_gcp_client::{Client, Credentials, SecretVersion};
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use rand::{rngs::OsRng, Rng, SeedableRng};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt::Write, path::Path};
+
+/// GcsSecretManagerStorage keeps each key in its own secret within GCP Secret Manager,
+/// serialized like the values of OnDiskStorage. Private keys are generated locally and stored like
+/// any other value. Every write adds a new version to the secret of a key, so after a rotation
+/// the previous key is the version before the latest one. Older versions are destroyed, so that
+/// at most two versions of a key are retained.
+///
+/// Secret ids may only contain letters, digits, underscores and hyphens, so other characters of
+/// keys, such as the separator of namespaces, are escaped as a hyphen and their hex value.
+///
+/// The version of a value is that of its secret version, which Secret Manager assigns on each
+/// write. Secret Manager offers no conditional writes, so `set_if_version` is not supported.
+pub struct GcsSecretManagerStorage {
+    client: Client,
+    /// Prepended to every escaped key to form the id of its secret, e.g., "validator_". Unlike
+    /// keys, it is not escaped, so it may only contain characters allowed in secret ids.
+    prefix: String,
+    time_service: TimeService,
+}
+
+impl GcsSecretManagerStorage {
+    pub fn new(
+        project: String,
+        prefix: Option<String>,
+        endpoint: Option<String>,
+        credentials: Credentials,
+        connection_timeout_ms: Option<u64>,
+        response_timeout_ms: Option<u64>,
+    ) -> Self {
+        Self {
+            client: Client::new(
+                project,
+                endpoint,
+                credentials,
+                connection_timeout_ms,
+                response_timeout_ms,
+            ),
+            prefix: prefix.unwrap_or_default(),
+            time_service: TimeService::real(),
+        }
+    }
+
+    /// Creates a storage whose credentials are read from the service account key file at
+    /// `credentials_path` or, without a path, resolved like Google's application default
+    /// credentials.
+    pub fn from_credentials_path(
+        project: String,
+        prefix: Option<String>,
+        endpoint: Option<String>,
+        credentials_path: Option<&Path>,
+        connection_timeout_ms: Option<u64>,
+        response_timeout_ms: Option<u64>,
+    ) -> Result<Self, Error> {
+        let credentials = Credentials::from_path_or_default(credentials_path)?;
+        Ok(Self::new(
+            project,
+            prefix,
+            endpoint,
+            credentials,
+            connection_timeout_ms,
+            response_timeout_ms,
+        ))
+    }
+
+    fn secret_id(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, escape(key))
+    }
+
+    fn get_version<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        version: Option<u64>,
+    ) -> Result<(u64, GetResponse<T>), Error> {
+        let SecretVersion { version, data } = self
+            .client
+            .access_secret_version(&self.secret_id(key), version)
+            .map_err(|e| match e {
+                aptos_gcp_client::Error::NotFound(_) => Error::KeyNotSet(key.into()),
+                e if is_destroyed(&e) => Error::KeyNotSet(key.into()),
+                e => e.into(),
+            })?;
+        // Secret versions are numbered from 1 and increase with every write
+        let mut response: GetResponse<T> = serde_json::from_slice(&data)?;
+        response.version = version;
+        Ok((version, response))
+    }
+
+    /// Adds a version to the secret of the key, creating the secret if needed, and destroys the
+    /// version before the one it replaces.
+    fn add_version<T: Serialize>(&self, key: &str, value: T) -> Result<(), Error> {
+        let now = self.time_service.now_secs();
+        // The version is that of the secret, which is only known once it is added
+        let data = serde_json::to_vec(&GetResponse::new_with_version(value, now, 0))?;
+        let secret_id = self.secret_id(key);
+        let version = match self.client.add_secret_version(&secret_id, &data) {
+            Err(aptos_gcp_client::Error::NotFound(_)) => {
+                self.client.create_secret(&secret_id)?;
+                self.client.add_secret_version(&secret_id, &data)?
+            }
+            result => result?,
+        };
+
+        if version > 2 {
+            match self.client.destroy_secret_version(&secret_id, version - 2) {
+                Ok(()) => (),
+                Err(e) if is_destroyed(&e) => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the private key the last rotation replaced.
+    fn export_previous_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        let (version, _) = self.get_version::<Ed25519PrivateKey>(name, None)?;
+        let not_found = || Error::KeyVersionNotFound(name.into(), "previous version".into());
+        if version < 2 {
+            return Err(not_found());
+        }
+        match self.get_version(name, Some(version - 1)) {
+            Ok((_, response)) => Ok(response.value),
+            Err(Error::KeyNotSet(_)) => Err(not_found()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl KVStorage for GcsSecretManagerStorage {
+    fn available(&self) -> Result<(), Error> {
+        self.client.list_secrets()?;
+        Ok(())
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        self.get_version(key, None).map(|(_, response)| response)
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        self.add_version(key, value)
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        _key: &str,
+        _value: T,
+        _expected_version: u64,
+    ) -> Result<(), Error> {
+        Err(Error::InternalError(
+            "GcsSecretManagerStorage does not support conditional writes".into(),
+        ))
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        match self.client.delete_secret(&self.secret_id(key)) {
+            Ok(()) | Err(aptos_gcp_client::Error::NotFound(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        // Keys are matched once unescaped, as an escaped prefix may end within an escape
+        let keys = self
+            .client
+            .list_secrets()?
+            .into_iter()
+            .filter(|secret_id| secret_id.starts_with(&self.prefix))
+            .map(|secret_id| unescape(&secret_id[self.prefix.len()..]))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(filter_keys(keys, prefix))
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        for secret_id in self.client.list_secrets()? {
+            if secret_id.starts_with(&self.prefix) {
+                self.client.delete_secret(&secret_id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CryptoStorage for GcsSecretManagerStorage {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        // Creating the secret fails if it exists, so an existing key is never replaced
+        let secret_id = self.secret_id(name);
+        match self.client.create_secret(&secret_id) {
+            Ok(()) => (),
+            Err(aptos_gcp_client::Error::HttpError(409, _, _)) => {
+                return Err(Error::KeyAlreadyExists(secret_id))
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        let (private_key, public_key) = new_ed25519_key_pair();
+        self.add_version(name, private_key)?;
+        Ok(public_key)
+    }
+
+    fn generate_key(&mut self, _name: &str) -> Result<Ed25519PublicKey, Error> {
+        Err(Error::InternalError(
+            "GCP Secret Manager does not support non-exportable keys".into(),
+        ))
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        false
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        self.get(name).map(|v| v.value)
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        let current_private_key = self.export_private_key(name)?;
+        if current_private_key.public_key() == version {
+            return Ok(current_private_key);
+        }
+
+        match self.export_previous_private_key(name) {
+            Ok(previous_private_key) if previous_private_key.public_key() == version => {
+                Ok(previous_private_key)
+            }
+            Ok(_) | Err(Error::KeyVersionNotFound(_, _)) => {
+                Err(Error::KeyVersionNotFound(name.into(), version.to_string()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        let public_key = key.public_key();
+        self.set(name, key)?;
+        logging::log_key_import(name, &public_key.to_bytes());
+        Ok(())
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        let response = self.get::<Ed25519PrivateKey>(name)?;
+        Ok(PublicKeyResponse {
+            last_update: response.last_update,
+            public_key: response.value.public_key(),
+        })
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.export_previous_private_key(name)
+            .map(|key| key.public_key())
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        // Only existing keys can be rotated
+        self.export_private_key(name)?;
+
+        let (private_key, public_key) = new_ed25519_key_pair();
+        self.add_version(name, private_key)?;
+        Ok(public_key)
+    }
+
+    /// Only the current and previous versions of a key are ever retained, and the previous one
+    /// cannot be deleted.
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        _in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        // Fail as for any other operation on a key that does not exist
+        self.get_public_key(name)?;
+        if keep_latest_n >= 2 {
+            return Ok(());
+        }
+        Err(Error::InternalError(
+            "GCP Secret Manager does not support pruning the previous key version".into(),
+        ))
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        let private_key = self.export_private_key(name)?;
+        Ok(private_key.sign(message))
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        let private_key = self.export_private_key_for_version(name, version)?;
+        Ok(private_key.sign(message))
+    }
+
+    fn sign_batch_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        let private_key = self.export_private_key_for_version(name, version)?;
+        Ok(messages
+            .iter()
+            .map(|message| private_key.sign(message))
+            .collect())
+    }
+}
+
+/// Whether the error reports that a version was destroyed, and so holds no value.
+fn is_destroyed(error: &aptos_gcp_client::Error) -> bool {
+    matches!(
+        error,
+        aptos_gcp_client::Error::HttpError(400, status, _) if status == "FAILED_PRECONDITION"
+    )
+}
+
+/// Escapes every character of the key that is not allowed in secret ids, as well as hyphens, as a
+/// hyphen followed by the two digit hex value of each of its bytes.
+fn escape(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for c in key.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            escaped.push(c);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                write!(escaped, "-{:02x}", byte).expect("Writing to a String cannot fail");
+            }
+        }
+    }
+    escaped
+}
+
+fn unescape(secret_id: &str) -> Result<String, Error> {
+    let invalid = || Error::SerializationError(format!("Invalid secret id: {}", secret_id));
+    let mut bytes = Vec::with_capacity(secret_id.len());
+    let mut parts = secret_id.split('-');
+    bytes.extend_from_slice(parts.next().unwrap_or_default().as_bytes());
+    for part in parts {
+        let byte = part.get(..2).ok_or_else(invalid)?;
+        bytes.push(u8::from_str_radix(byte, 16).map_err(|_| invalid())?);
+        bytes.extend_from_slice(part[2..].as_bytes());
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Private helper method to generate a new ed25519 key pair using entropy from the OS.
+fn new_ed25519_key_pair() -> (Ed25519PrivateKey, Ed25519PublicKey) {
+    let mut seed_rng = OsRng;
+    let mut rng = rand::rngs::StdRng::from_seed(seed_rng.gen());
+    let private_key = Ed25519PrivateKey::generate(&mut rng);
+    let public_key = private_key.public_key();
+    (private_key, public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        for key in ["key", "", "namespace/key", "a-b", "-", "a_b/c.d", "ключ"] {
+            let escaped = escape(key);
+            assert!(escaped
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'));
+            assert_eq!(unescape(&escaped).unwrap(), key);
+        }
+        assert_eq!(escape("namespace/key"), "namespace-2fkey");
+        assert!(escape("namespace/key").starts_with(&escape("namespace/")));
+        assert!(unescape("a-zz").is_err());
+        assert!(unescape("a-2").is_err());
+    }
+}
@@ -1,13 +1,18 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{CryptoKVStorage, Error, GetResponse, KVStorage};
+use crate::{kv_storage::filter_keys, CryptoKVStorage, Error, GetResponse, KVStorage};
 use aptos_github_client::Client;
 use aptos_time_service::{TimeService, TimeServiceTrait};
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 
 /// GitHubStorage leverages a GitHub repository to provide a file system approach to key / value
 /// storage.  This is not intended for storing private data but for organizing public data.
+///
+/// Keys are read from and written to the given branch, which must exist. Writes that conflict
+/// with concurrent writers are retried a few times, after which they fail with
+/// `Error::WriteConflict`.
 pub struct GitHubStorage {
     client: Client,
     time_service: TimeService,
@@ -20,38 +25,114 @@ impl GitHubStorage {
             time_service: TimeService::real(),
         }
     }
+
+    pub fn branch(&self) -> &str {
+        self.client.branch()
+    }
+
+    /// Sends requests to another API server than api.github.com, e.g., a GitHub Enterprise server.
+    pub fn set_url(&mut self, url: String) {
+        self.client.set_url(url);
+    }
+}
+
+impl GitHubStorage {
+    /// Recursively lists the files within the given directory.
+    fn list_files(&self, directory: &str, files: &mut Vec<String>) -> Result<(), Error> {
+        for entry in self.client.get_directory(directory)? {
+            match entry.strip_suffix('/') {
+                Some(subdirectory) => self.list_files(subdirectory, files)?,
+                None => files.push(entry),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a value written by `set`, as stored in a file.
+fn decode<T: DeserializeOwned>(data: &str) -> Result<GetResponse<T>, Error> {
+    let data = base64::decode(data)?;
+    let data = std::str::from_utf8(&data).map_err(|e| {
+        Error::InternalError(format!(
+            "Unparseable data: {:?}\n returned from Github KV Storage, met Error:{}",
+            data, e
+        ))
+    })?;
+    serde_json::from_str(data).map_err(|e| e.into())
+}
+
+/// The directory that holds all keys with the given prefix, without its trailing separator.
+fn directory_of(prefix: &str) -> &str {
+    prefix
+        .rsplit_once('/')
+        .map_or("", |(directory, _)| directory)
 }
 
 impl KVStorage for GitHubStorage {
     fn available(&self) -> Result<(), Error> {
-        if !self.client.get_branches()?.is_empty() {
+        let branches = self.client.get_branches()?;
+        if branches.iter().any(|branch| branch == self.client.branch()) {
             Ok(())
-        } else {
+        } else if branches.is_empty() {
             Err(Error::InternalError("No branches found.".into()))
+        } else {
+            Err(Error::InternalError(format!(
+                "Branch {} not found.",
+                self.client.branch()
+            )))
         }
     }
 
     fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
-        let data = self.client.get_file(key)?;
-        let data = base64::decode(&data)?;
-        let data = std::str::from_utf8(&data).map_err(|e| {
-            Error::InternalError(format!(
-                "Unparseable data: {:?}\n returned from Github KV Storage, met Error:{}",
-                data, e
-            ))
-        })?;
-        serde_json::from_str(data).map_err(|e| e.into())
+        decode(&self.client.get_file(key)?)
     }
 
     fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
         let now = self.time_service.now_secs();
-        let data = GetResponse::new(value, now);
-        let data = serde_json::to_string(&data)?;
-        let data = base64::encode(&data);
-        self.client.put(key, &data)?;
+        let value = serde_json::to_value(value)?;
+        // Commits are not ordered by a number, so versions are counted by the storage. The version
+        // is read again whenever the write is retried after a conflict, so a concurrent write is
+        // never overwritten with the same version.
+        self.client.update(key, |current| {
+            let version = match current {
+                Some(data) => {
+                    decode::<Value>(data)
+                        .map_err(|e| aptos_github_client::Error::SerializationError(e.to_string()))?
+                        .version
+                        + 1
+                }
+                None => 1,
+            };
+            let data = GetResponse::new_with_version(&value, now, version);
+            Ok(base64::encode(&serde_json::to_string(&data)?))
+        })?;
         Ok(())
     }
 
+    /// Versions are counted by `set`, from the value read along with the sha of the file before
+    /// each write. The sha only guards the write against a concurrent commit, and is not exposed
+    /// as a version, so conditional writes are not supported.
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        _key: &str,
+        _value: T,
+        _expected_version: u64,
+    ) -> Result<(), Error> {
+        Err(Error::InternalError(
+            "GitHubStorage does not support conditional writes".into(),
+        ))
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        self.client.delete_file(key).map_err(|e| e.into())
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        let mut keys = vec![];
+        self.list_files(directory_of(prefix.unwrap_or("")), &mut keys)?;
+        Ok(filter_keys(keys, prefix))
+    }
+
     #[cfg(any(test, feature = "testing"))]
     fn reset_and_clear(&mut self) -> Result<(), Error> {
         self.client.delete_directory("/").map_err(|e| e.into())
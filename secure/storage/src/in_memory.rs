@@ -1,21 +1,37 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{CryptoKVStorage, Error, GetResponse, KVStorage};
+use crate::{
+    kv_storage::filter_keys, watch::Watchers, CryptoKVStorage, Error, GetResponse, KVStorage,
+    KeyWatch, ValueMetadata, DEFAULT_MAX_KEY_VERSIONS,
+};
 use aptos_time_service::{TimeService, TimeServiceTrait};
 use serde::{de::DeserializeOwned, Serialize};
 use std::collections::HashMap;
 
+/// The entries of an InMemoryStorage at a point in time, see `InMemoryStorage::snapshot`. Each
+/// value is a serialized `GetResponse`, so the versions and update times are captured too.
+/// Private keys are included in plain text, so snapshots are only available to tests.
+#[cfg(any(test, feature = "testing"))]
+pub type StorageSnapshot = HashMap<String, serde_json::Value>;
+
 /// InMemoryStorage represents a key value store that is purely in memory and intended for single
 /// threads (or must be wrapped by a Arc<RwLock<>>). This provides no permission checks and simply
 /// is a proof of concept to unblock building of applications without more complex data stores.
 /// Internally, it retains all data, which means that it must make copies of all key material which
 /// violates the code base. It violates it because the anticipation is that data stores would
 /// securely handle key material. This should not be used in production.
+///
+/// Watches are notified by every write, as all writes go through the storage.
 #[derive(Default)]
 pub struct InMemoryStorage {
     data: HashMap<String, Vec<u8>>,
+    /// The latest version of every key ever set. It is kept when a key is deleted, so that a key
+    /// set again never reuses a version that a writer may still hold.
+    versions: HashMap<String, u64>,
     time_service: TimeService,
+    max_key_versions: Option<usize>,
+    watchers: Watchers,
 }
 
 impl InMemoryStorage {
@@ -28,7 +44,69 @@ impl InMemoryStorage {
     pub fn new_with_time_service(time_service: TimeService) -> Self {
         Self {
             data: HashMap::new(),
+            versions: HashMap::new(),
             time_service,
+            max_key_versions: None,
+            watchers: Watchers::default(),
+        }
+    }
+
+    /// Sets the number of versions of each key retained on rotation, including the current one.
+    pub fn set_max_key_versions(&mut self, max_key_versions: usize) {
+        self.max_key_versions = Some(max_key_versions);
+    }
+
+    /// Returns the version of the value stored at the key, or 0 if it is not set.
+    fn version(&self, key: &str) -> u64 {
+        if self.data.contains_key(key) {
+            self.versions.get(key).copied().unwrap_or(0)
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl InMemoryStorage {
+    /// Captures all entries, including keys, e.g., to run several test cases from the same state
+    /// without setting it up again.
+    pub fn snapshot(&self) -> StorageSnapshot {
+        self.data
+            .iter()
+            .map(|(key, response)| {
+                let response =
+                    serde_json::from_slice(response).expect("Entries are serialized as JSON");
+                (key.clone(), response)
+            })
+            .collect()
+    }
+
+    /// Replaces all entries with those of a snapshot taken by `snapshot`. Watches of the keys
+    /// whose entry changed are notified, as if the changes had been written.
+    pub fn restore(&mut self, snapshot: StorageSnapshot) {
+        use std::{collections::BTreeSet, mem};
+
+        let mut data = HashMap::new();
+        for (key, response) in snapshot {
+            let version = response["version"].as_u64().unwrap_or(0);
+            self.versions.insert(key.clone(), version);
+            let response = serde_json::to_vec(&response).expect("Values serialize as JSON");
+            data.insert(key, response);
+        }
+        let previous = mem::replace(&mut self.data, data);
+
+        let changed: BTreeSet<_> = previous
+            .keys()
+            .chain(self.data.keys())
+            .filter(|key| previous.get(*key) != self.data.get(*key))
+            .collect();
+        for key in changed {
+            let metadata = self.data.get(key).and_then(|response| {
+                serde_json::from_slice::<GetResponse<serde_json::Value>>(response)
+                    .ok()
+                    .map(|response| response.metadata())
+            });
+            self.watchers.notify(key, metadata);
         }
     }
 }
@@ -49,18 +127,66 @@ impl KVStorage for InMemoryStorage {
 
     fn set<V: Serialize>(&mut self, key: &str, value: V) -> Result<(), Error> {
         let now = self.time_service.now_secs();
+        let version = self.versions.get(key).copied().unwrap_or(0) + 1;
+        self.versions.insert(key.to_string(), version);
         self.data.insert(
             key.to_string(),
-            serde_json::to_vec(&GetResponse::new(value, now))?,
+            serde_json::to_vec(&GetResponse::new_with_version(value, now, version))?,
         );
+        let metadata = ValueMetadata {
+            last_update: now,
+            version,
+        };
+        self.watchers.notify(key, Some(metadata));
+        Ok(())
+    }
+
+    fn set_if_version<V: Serialize>(
+        &mut self,
+        key: &str,
+        value: V,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        let version = self.version(key);
+        if version != expected_version {
+            return Err(Error::VersionMismatch {
+                expected: expected_version,
+                actual: version,
+            });
+        }
+        self.set(key, value)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        if self.data.remove(key).is_some() {
+            self.watchers.notify(key, None);
+        }
         Ok(())
     }
 
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        Ok(filter_keys(self.data.keys().cloned(), prefix))
+    }
+
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        Ok(self.watchers.subscribe(key))
+    }
+
     #[cfg(any(test, feature = "testing"))]
     fn reset_and_clear(&mut self) -> Result<(), Error> {
+        for key in self.watchers.keys() {
+            if self.data.contains_key(&key) {
+                self.watchers.notify(&key, None);
+            }
+        }
         self.data.clear();
+        self.versions.clear();
         Ok(())
     }
 }
 
-impl CryptoKVStorage for InMemoryStorage {}
+impl CryptoKVStorage for InMemoryStorage {
+    fn max_key_versions(&self) -> usize {
+        self.max_key_versions.unwrap_or(DEFAULT_MAX_KEY_VERSIONS)
+    }
+}
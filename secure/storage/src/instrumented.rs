@@ -0,0 +1,299 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    metrics, CryptoStorage, Error, GetResponse, KVStorage, KeyWatch, PublicKeyResponse,
+    SchemeSigningKey,
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Instrumented records the latency of every operation of a storage in `STORAGE_LATENCY`, and
+/// failed operations in `STORAGE_ERRORS`, both labeled by the backend of the storage and the
+/// name of the operation. It is usually created through `Storage::instrumented`.
+pub struct Instrumented<S> {
+    backend: &'static str,
+    inner: S,
+}
+
+impl<S> Instrumented<S> {
+    pub fn new(backend: &'static str, inner: S) -> Self {
+        Self { backend, inner }
+    }
+
+    pub fn backend(&self) -> &'static str {
+        self.backend
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: KVStorage> KVStorage for Instrumented<S> {
+    fn available(&self) -> Result<(), Error> {
+        let _timer = metrics::start_timer(self.backend, "available");
+        metrics::observe(self.backend, "available", self.inner.available())
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        let _timer = metrics::start_timer(self.backend, "get");
+        metrics::observe(self.backend, "get", self.inner.get(key))
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        let _timer = metrics::start_timer(self.backend, "set");
+        metrics::observe(self.backend, "set", self.inner.set(key, value))
+    }
+
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        let _timer = metrics::start_timer(self.backend, "get_batch");
+        metrics::observe(self.backend, "get_batch", self.inner.get_batch(keys))
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        let _timer = metrics::start_timer(self.backend, "set_batch");
+        metrics::observe(self.backend, "set_batch", self.inner.set_batch(entries))
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        let _timer = metrics::start_timer(self.backend, "set_if_version");
+        metrics::observe(
+            self.backend,
+            "set_if_version",
+            self.inner.set_if_version(key, value, expected_version),
+        )
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        let _timer = metrics::start_timer(self.backend, "delete");
+        metrics::observe(self.backend, "delete", self.inner.delete(key))
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        let _timer = metrics::start_timer(self.backend, "list_keys");
+        metrics::observe(self.backend, "list_keys", self.inner.list_keys(prefix))
+    }
+
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        let _timer = metrics::start_timer(self.backend, "watch");
+        metrics::observe(self.backend, "watch", self.inner.watch(key))
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.inner.reset_and_clear()
+    }
+}
+
+impl<S: CryptoStorage> CryptoStorage for Instrumented<S> {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let _timer = metrics::start_timer(self.backend, "create_key");
+        metrics::observe(self.backend, "create_key", self.inner.create_key(name))
+    }
+
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let _timer = metrics::start_timer(self.backend, "generate_key");
+        metrics::observe(self.backend, "generate_key", self.inner.generate_key(name))
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        self.inner.supports_non_exportable_keys()
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        let _timer = metrics::start_timer(self.backend, "export_private_key");
+        metrics::observe(
+            self.backend,
+            "export_private_key",
+            self.inner.export_private_key(name),
+        )
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        let _timer = metrics::start_timer(self.backend, "import_private_key");
+        metrics::observe(
+            self.backend,
+            "import_private_key",
+            self.inner.import_private_key(name, key),
+        )
+    }
+
+    fn import_private_key_versions(
+        &mut self,
+        name: &str,
+        versions: Vec<Ed25519PrivateKey>,
+    ) -> Result<usize, Error> {
+        let _timer = metrics::start_timer(self.backend, "import_private_key_versions");
+        metrics::observe(
+            self.backend,
+            "import_private_key_versions",
+            self.inner.import_private_key_versions(name, versions),
+        )
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        let _timer = metrics::start_timer(self.backend, "export_private_key_for_version");
+        metrics::observe(
+            self.backend,
+            "export_private_key_for_version",
+            self.inner.export_private_key_for_version(name, version),
+        )
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        let _timer = metrics::start_timer(self.backend, "get_public_key");
+        metrics::observe(
+            self.backend,
+            "get_public_key",
+            self.inner.get_public_key(name),
+        )
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let _timer = metrics::start_timer(self.backend, "get_public_key_previous_version");
+        metrics::observe(
+            self.backend,
+            "get_public_key_previous_version",
+            self.inner.get_public_key_previous_version(name),
+        )
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        let _timer = metrics::start_timer(self.backend, "rotate_key");
+        metrics::observe(self.backend, "rotate_key", self.inner.rotate_key(name))
+    }
+
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        let _timer = metrics::start_timer(self.backend, "prune_key_versions");
+        metrics::observe(
+            self.backend,
+            "prune_key_versions",
+            self.inner
+                .prune_key_versions(name, keep_latest_n, in_use_floor),
+        )
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        let _timer = metrics::start_timer(self.backend, "sign");
+        metrics::observe(self.backend, "sign", self.inner.sign(name, message))
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        let _timer = metrics::start_timer(self.backend, "sign_using_version");
+        metrics::observe(
+            self.backend,
+            "sign_using_version",
+            self.inner.sign_using_version(name, version, message),
+        )
+    }
+
+    fn sign_batch_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        let _timer = metrics::start_timer(self.backend, "sign_batch_using_version");
+        metrics::observe(
+            self.backend,
+            "sign_batch_using_version",
+            self.inner.sign_batch_using_version(name, version, messages),
+        )
+    }
+
+    fn create_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        let _timer = metrics::start_timer(self.backend, "create_key_as");
+        metrics::observe(
+            self.backend,
+            "create_key_as",
+            self.inner.create_key_as::<K>(name),
+        )
+    }
+
+    fn import_private_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+        key: K,
+    ) -> Result<(), Error> {
+        let _timer = metrics::start_timer(self.backend, "import_private_key_as");
+        metrics::observe(
+            self.backend,
+            "import_private_key_as",
+            self.inner.import_private_key_as(name, key),
+        )
+    }
+
+    fn export_private_key_as<K: SchemeSigningKey>(&self, name: &str) -> Result<K, Error> {
+        let _timer = metrics::start_timer(self.backend, "export_private_key_as");
+        metrics::observe(
+            self.backend,
+            "export_private_key_as",
+            self.inner.export_private_key_as(name),
+        )
+    }
+
+    fn get_public_key_as<K: SchemeSigningKey>(
+        &self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        let _timer = metrics::start_timer(self.backend, "get_public_key_as");
+        metrics::observe(
+            self.backend,
+            "get_public_key_as",
+            self.inner.get_public_key_as::<K>(name),
+        )
+    }
+
+    fn sign_as<K: SchemeSigningKey, T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<K::SignatureMaterial, Error> {
+        let _timer = metrics::start_timer(self.backend, "sign_as");
+        metrics::observe(
+            self.backend,
+            "sign_as",
+            self.inner.sign_as::<K, T>(name, message),
+        )
+    }
+}
@@ -1,9 +1,26 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::Error;
+use crate::{Error, KeyWatch};
 use enum_dispatch::enum_dispatch;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Applies the prefix semantics of `KVStorage::list_keys` to the keys of a backend, including
+/// backends that filter keys themselves, e.g., by directory or without regard to case.
+pub(crate) fn filter_keys<I: IntoIterator<Item = String>>(
+    keys: I,
+    prefix: Option<&str>,
+) -> Vec<String> {
+    let prefix = prefix.unwrap_or("");
+    let mut keys: Vec<_> = keys
+        .into_iter()
+        .filter(|key| key.starts_with(prefix))
+        .collect();
+    keys.sort();
+    keys
+}
 
 /// A secure key/value storage engine. Create takes a policy that is enforced internally by the
 /// actual backend. The policy contains public identities that the backend can translate into a
@@ -22,6 +39,56 @@ pub trait KVStorage {
     /// invalid permissions.
     fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error>;
 
+    /// Retrieves the values of several keys, and fails like `get` if any of them is not set.
+    /// Backends that can serve several keys in fewer round trips than one per key override this.
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        keys.iter()
+            .map(|key| Ok((key.to_string(), self.get::<Value>(key)?)))
+            .collect()
+    }
+
+    /// Sets the values of several keys. Backends that can write several keys in fewer round trips
+    /// than one per key override this. The writes are not guaranteed to be atomic.
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        for (key, value) in entries {
+            self.set(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Sets a value in storage only if the key is at `expected_version`, as returned by `get`, or
+    /// 0 to require that the key is not set. The check and the write are atomic, so concurrent
+    /// writers cannot overwrite each other's updates: all but one fail with `VersionMismatch`.
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error>;
+
+    /// Removes the key and its value from storage. Deleting a key that is not set succeeds, so
+    /// that a delete can safely be retried.
+    fn delete(&mut self, key: &str) -> Result<(), Error>;
+
+    /// Lists, in ascending order, the keys set in storage that start with the given prefix, or
+    /// all of them if there is none. The prefix is compared with keys byte for byte: it need not
+    /// end at a separator (`a` matches both `ab` and `a/b`), and case matters. Only the keys are
+    /// returned, never the values, so listing requires no access to the values themselves.
+    /// Backends that cannot enumerate their keys fail with `Error::UnsupportedOperation`.
+    fn list_keys(&self, _prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        Err(Error::UnsupportedOperation("list_keys".into()))
+    }
+
+    /// Watches the key for changes, including those made through other instances or processes
+    /// where the backend allows it, so that consumers need not poll storage. Watching a key that
+    /// is not set succeeds, and its creation is reported. Backends that cannot watch keys fail.
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        Err(Error::InternalError(format!(
+            "Unable to watch {}: the storage does not support watching keys",
+            key
+        )))
+    }
+
     /// Resets and clears all data held in the storage engine.
     /// Note: this should only be exposed and used for testing. Resetting the storage engine is not
     /// something that should be supported in production.
@@ -45,6 +112,35 @@ where
         S::set(self, key, value)
     }
 
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        S::get_batch(self, keys)
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        S::set_batch(self, entries)
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        S::set_if_version(self, key, value, expected_version)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        S::delete(self, key)
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        S::list_keys(self, prefix)
+    }
+
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        S::watch(self, key)
+    }
+
     #[cfg(any(test, feature = "testing"))]
     fn reset_and_clear(&mut self) -> Result<(), Error> {
         S::reset_and_clear(self)
@@ -60,11 +156,38 @@ pub struct GetResponse<T> {
     pub last_update: u64,
     /// Value stored at the provided key
     pub value: T,
+    /// Increases with every write of the key, from 1 for backends that count writes, or as the
+    /// native version of the backend, e.g., of a Vault secret. Values written before versions
+    /// were recorded read as version 0.
+    #[serde(default)]
+    pub version: u64,
 }
 
 impl<T> GetResponse<T> {
-    /// Creates a GetResponse
-    pub fn new(value: T, last_update: u64) -> Self {
-        Self { last_update, value }
+    /// Creates a GetResponse for the given version of the value
+    pub fn new_with_version(value: T, last_update: u64, version: u64) -> Self {
+        Self {
+            last_update,
+            value,
+            version,
+        }
+    }
+
+    /// Returns when and by which write the value was last updated, e.g., to detect a value that
+    /// changed since it was read.
+    pub fn metadata(&self) -> ValueMetadata {
+        ValueMetadata {
+            last_update: self.last_update,
+            version: self.version,
+        }
     }
 }
+
+/// The metadata of a value in storage, see `GetResponse`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ValueMetadata {
+    /// Time since Unix Epoch in seconds.
+    pub last_update: u64,
+    /// The version of the value, see `GetResponse::version`.
+    pub version: u64,
+}
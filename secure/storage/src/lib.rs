@@ -3,30 +3,71 @@
 
 #![forbid(unsafe_code)]
 
+mod archive;
+mod aws;
+mod cached;
+#[cfg(any(test, feature = "testing"))]
+mod chaos;
+mod circuit_breaker;
 mod crypto_kv_storage;
 mod crypto_storage;
+mod encrypted_on_disk;
+mod env;
 mod error;
+mod gcp;
 mod github;
 mod in_memory;
+mod instrumented;
 mod kv_storage;
+mod logging;
+mod metrics;
+mod migrate;
 mod namespaced;
 mod on_disk;
+mod overlay;
 mod policy;
+mod policy_storage;
+mod rate_limited;
 mod storage;
+#[cfg(any(test, feature = "testing"))]
+pub mod storage_conformance_tests;
 mod vault;
+mod watch;
 
 pub use crate::{
-    crypto_kv_storage::CryptoKVStorage,
-    crypto_storage::{CryptoStorage, PublicKeyResponse},
+    archive::{export_namespace, import_namespace, ArchiveReport, ARCHIVE_VERSION},
+    aws::AwsStorage,
+    cached::CachedStorage,
+    circuit_breaker::{CircuitBreaker, CircuitBreakerStorage, CircuitState},
+    crypto_kv_storage::{CryptoKVStorage, DEFAULT_MAX_KEY_VERSIONS, MAX_PRUNED_VERSIONS},
+    crypto_storage::{CryptoStorage, ImportResult, KeyScheme, PublicKeyResponse, SchemeSigningKey},
+    encrypted_on_disk::{EncryptedOnDiskStorage, Passphrase},
+    env::{EnvStorage, DEFAULT_ENV_PREFIX},
     error::Error,
+    gcp::GcsSecretManagerStorage,
     github::GitHubStorage,
     in_memory::InMemoryStorage,
-    kv_storage::{GetResponse, KVStorage},
+    instrumented::Instrumented,
+    kv_storage::{GetResponse, KVStorage, ValueMetadata},
+    metrics::{STORAGE_CIRCUIT_TRANSITIONS, STORAGE_ERRORS, STORAGE_LATENCY, STORAGE_RATE_LIMITED},
+    migrate::{migrate, MigrationReport},
     namespaced::Namespaced,
     on_disk::OnDiskStorage,
+    overlay::Overlay,
     policy::{Capability, Identity, Permission, Policy},
+    policy_storage::{KeyPolicy, PolicyStorage},
+    rate_limited::{RateLimitedOperation, RateLimitedStorage, RateLimits, TokenBucket},
     storage::Storage,
     vault::VaultStorage,
+    watch::{KeyChangeEvent, KeyWatch, WATCH_CAPACITY},
+};
+
+pub use aptos_vault_client::{KvVersion, DEFAULT_KV_MOUNT};
+
+#[cfg(any(test, feature = "testing"))]
+pub use crate::{
+    chaos::{ChaosOperation, ChaosPolicy, ChaosStorage},
+    in_memory::StorageSnapshot,
 };
 
 // Some common serializations for interacting with bytes these must be manually added to types via:
@@ -0,0 +1,49 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use aptos_logger::Schema;
+use serde::Serialize;
+
+#[derive(Schema)]
+pub struct StorageLogSchema<'a> {
+    event: LogEvent,
+    key_name: Option<&'a str>,
+    key_fingerprint: Option<String>,
+}
+
+impl<'a> StorageLogSchema<'a> {
+    pub fn new(event: LogEvent) -> Self {
+        Self {
+            event,
+            key_name: None,
+            key_fingerprint: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogEvent {
+    KeyImport,
+}
+
+/// Records the import of the private key at 'name'. Only a short fingerprint of its public key is
+/// logged, so that imports can be correlated with audit logs without exposing keys.
+pub(crate) fn log_key_import(name: &str, public_key: &[u8]) {
+    aptos_logger::info!(key_import_log_schema(name, public_key));
+}
+
+pub(crate) fn key_import_log_schema<'a>(name: &'a str, public_key: &[u8]) -> StorageLogSchema<'a> {
+    StorageLogSchema::new(LogEvent::KeyImport)
+        .key_name(name)
+        .key_fingerprint(key_fingerprint(public_key))
+}
+
+/// The first 8 bytes of the public key, in hex, enough to tell keys apart.
+pub(crate) fn key_fingerprint(public_key: &[u8]) -> String {
+    public_key
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
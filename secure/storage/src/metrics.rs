@@ -0,0 +1,72 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Error;
+use aptos_metrics_core::{
+    register_histogram_vec, register_int_counter_vec, HistogramTimer, HistogramVec, IntCounterVec,
+};
+use once_cell::sync::Lazy;
+
+/// Time to perform each operation of an instrumented storage, labeled by backend and operation.
+/// It is registered with the default registry, and thus exported by the metrics endpoint of the
+/// node.
+pub static STORAGE_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "aptos_secure_storage_latency",
+        "Time to perform a secure storage operation",
+        &["backend", "operation"]
+    )
+    .unwrap()
+});
+
+/// Number of failed operations of an instrumented storage, labeled by backend and operation.
+pub static STORAGE_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_secure_storage_errors",
+        "Number of failed secure storage operations",
+        &["backend", "operation"]
+    )
+    .unwrap()
+});
+
+/// Number of transitions of the circuit of a CircuitBreakerStorage into each state, labeled by
+/// backend and the state entered.
+pub static STORAGE_CIRCUIT_TRANSITIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_secure_storage_circuit_transitions",
+        "Number of transitions of a secure storage circuit breaker",
+        &["backend", "state"]
+    )
+    .unwrap()
+});
+
+/// Number of operations of a RateLimitedStorage that were throttled, labeled by backend and the
+/// class of the operation.
+pub static STORAGE_RATE_LIMITED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "aptos_secure_storage_rate_limited",
+        "Number of throttled secure storage operations",
+        &["backend", "operation"]
+    )
+    .unwrap()
+});
+
+pub(crate) fn start_timer(backend: &str, operation: &str) -> HistogramTimer {
+    STORAGE_LATENCY
+        .with_label_values(&[backend, operation])
+        .start_timer()
+}
+
+/// Counts the result of an operation if it failed, and returns it.
+pub(crate) fn observe<T>(
+    backend: &str,
+    operation: &str,
+    result: Result<T, Error>,
+) -> Result<T, Error> {
+    if result.is_err() {
+        STORAGE_ERRORS
+            .with_label_values(&[backend, operation])
+            .inc();
+    }
+    result
+}
@@ -0,0 +1,144 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{crypto_kv_storage, CryptoStorage, Error, KVStorage, Storage};
+use aptos_crypto::{ed25519::Ed25519PublicKey, PrivateKey};
+use serde_json::Value;
+
+/// The outcome of `migrate`, listing the entries considered by name.
+#[derive(Debug, Default, PartialEq)]
+pub struct MigrationReport {
+    /// Entries copied to the destination, where they were read back and found identical.
+    pub copied: Vec<String>,
+    /// Entries that are not set in the source.
+    pub skipped: Vec<String>,
+    /// Entries that could not be copied or read back identical, along with the error.
+    pub failed: Vec<(String, Error)>,
+}
+
+impl MigrationReport {
+    /// Returns true if every entry set in the source was copied.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Copies the entries at the given names, or else all entries listed by the source, from `src` to
+/// `dst`, e.g., to move a validator from on-disk storage to Vault. The entries at `key_names` are
+/// keys, which are exported and imported along with their previous version, which is retained if
+/// the destination supports it; other entries are values, which are copied as is, whatever they
+/// hold. Every copied entry is read back from the destination and compared to the source. The
+/// entries that hold the previous versions of a key are copied as part of the key, rather than as
+/// entries of their own.
+///
+/// A failure to copy an entry does not stop the migration, and is recorded in the report. Keys
+/// that are not exportable cannot be migrated.
+pub fn migrate(
+    src: &mut Storage,
+    dst: &mut Storage,
+    names: Option<&[&str]>,
+    key_names: &[&str],
+) -> Result<MigrationReport, Error> {
+    let names = match names {
+        Some(names) => names.iter().map(|name| name.to_string()).collect(),
+        None => src.list_keys(None)?,
+    };
+
+    let mut entries = Vec::new();
+    let mut report = MigrationReport::default();
+    for name in names {
+        let is_key = key_names.contains(&name.as_str());
+        match entry(src, &name, is_key) {
+            Ok(Some(entry)) => entries.push((name, entry)),
+            Ok(None) => report.skipped.push(name),
+            Err(error) => report.failed.push((name, error)),
+        }
+    }
+
+    let key_names: Vec<_> = entries
+        .iter()
+        .filter(|(_, entry)| matches!(entry, Entry::Key(_)))
+        .map(|(name, _)| name.clone())
+        .collect();
+    for (name, entry) in entries {
+        // Previous versions are copied along with their key
+        if key_names
+            .iter()
+            .any(|key_name| crypto_kv_storage::is_key_pair_entry(&name, key_name))
+        {
+            continue;
+        }
+        let result = match entry {
+            Entry::Key(public_key) => migrate_key(src, dst, &name, public_key),
+            Entry::Value(value) => migrate_value(dst, &name, value),
+        };
+        match result {
+            Ok(()) => report.copied.push(name),
+            Err(error) => report.failed.push((name, error)),
+        }
+    }
+    Ok(report)
+}
+
+/// An entry of the source, i.e., a key, identified by its current version, or a value.
+pub(crate) enum Entry {
+    Key(Ed25519PublicKey),
+    Value(Value),
+}
+
+/// Reads the entry at 'name', a key if `is_key` and a value otherwise, if it is set.
+pub(crate) fn entry(src: &Storage, name: &str, is_key: bool) -> Result<Option<Entry>, Error> {
+    if is_key {
+        return match src.get_public_key(name) {
+            Ok(response) => Ok(Some(Entry::Key(response.public_key))),
+            Err(Error::KeyNotSet(_)) => Ok(None),
+            Err(error) => Err(error),
+        };
+    }
+    match src.get::<Value>(name) {
+        Ok(response) => Ok(Some(Entry::Value(response.value))),
+        Err(Error::KeyNotSet(_)) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Private helper method to copy the current and previous versions of the key at 'name'.
+fn migrate_key(
+    src: &Storage,
+    dst: &mut Storage,
+    name: &str,
+    public_key: Ed25519PublicKey,
+) -> Result<(), Error> {
+    let mut versions = vec![src.export_private_key_for_version(name, public_key.clone())?];
+    let previous_version = match src.get_public_key_previous_version(name) {
+        Ok(previous_version) => {
+            versions.push(src.export_private_key_for_version(name, previous_version.clone())?);
+            Some(previous_version)
+        }
+        Err(Error::KeyVersionNotFound(_, _)) => None,
+        Err(error) => return Err(error),
+    };
+
+    let retained_versions = dst.import_private_key_versions(name, versions)?;
+    let copied_public_key = dst.get_public_key(name)?.public_key;
+    if copied_public_key != public_key {
+        return Err(Error::MigrationMismatch(name.into()));
+    }
+    if let Some(previous_version) = previous_version.filter(|_| retained_versions > 1) {
+        let copied_private_key =
+            dst.export_private_key_for_version(name, previous_version.clone())?;
+        if copied_private_key.public_key() != previous_version {
+            return Err(Error::MigrationMismatch(name.into()));
+        }
+    }
+    Ok(())
+}
+
+/// Private helper method to copy the value at 'name'.
+fn migrate_value(dst: &mut Storage, name: &str, value: Value) -> Result<(), Error> {
+    dst.set(name, &value)?;
+    if dst.get::<Value>(name)?.value != value {
+        return Err(Error::MigrationMismatch(name.into()));
+    }
+    Ok(())
+}
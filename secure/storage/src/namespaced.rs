@@ -1,12 +1,16 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{CryptoStorage, Error, GetResponse, KVStorage, PublicKeyResponse};
+use crate::{
+    CryptoStorage, Error, GetResponse, KVStorage, KeyWatch, PublicKeyResponse, SchemeSigningKey,
+};
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
 };
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 
 pub const NAMESPACE_SEPARATOR: &str = "/";
 
@@ -71,6 +75,62 @@ impl<S: KVStorage> KVStorage for Namespaced<S> {
         self.inner.set(&self.namespaced(key), value)
     }
 
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        let namespaced_keys: Vec<_> = keys.iter().map(|key| self.namespaced(key)).collect();
+        let namespaced_keys: Vec<_> = namespaced_keys.iter().map(String::as_str).collect();
+        let prefix_len = self.namespaced("").len();
+        Ok(self
+            .inner
+            .get_batch(&namespaced_keys)?
+            .into_iter()
+            .map(|(key, value)| (key[prefix_len..].to_string(), value))
+            .collect())
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        let namespaced_keys: Vec<_> = entries
+            .iter()
+            .map(|(key, _)| self.namespaced(key))
+            .collect();
+        let entries: Vec<_> = namespaced_keys
+            .iter()
+            .zip(entries)
+            .map(|(key, (_, value))| (key.as_str(), value.clone()))
+            .collect();
+        self.inner.set_batch(&entries)
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        self.inner
+            .set_if_version(&self.namespaced(key), value, expected_version)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        self.inner.delete(&self.namespaced(key))
+    }
+
+    /// Only lists keys within the namespace, without the namespace prefix.
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        let namespace_len = self.namespaced("").len();
+        let prefix = self.namespaced(prefix.unwrap_or(""));
+        Ok(self
+            .inner
+            .list_keys(Some(&prefix))?
+            .into_iter()
+            .map(|key| key[namespace_len..].to_string())
+            .collect())
+    }
+
+    /// Events name the key without the namespace prefix.
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        Ok(self.inner.watch(&self.namespaced(key))?.with_key(key))
+    }
+
     /// Note: This is not a namespace function
     #[cfg(any(test, feature = "testing"))]
     fn reset_and_clear(&mut self) -> Result<(), Error> {
@@ -83,6 +143,14 @@ impl<S: CryptoStorage> CryptoStorage for Namespaced<S> {
         self.inner.create_key(&self.namespaced(name))
     }
 
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.inner.generate_key(&self.namespaced(name))
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        self.inner.supports_non_exportable_keys()
+    }
+
     fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
         self.inner.export_private_key(&self.namespaced(name))
     }
@@ -91,6 +159,15 @@ impl<S: CryptoStorage> CryptoStorage for Namespaced<S> {
         self.inner.import_private_key(&self.namespaced(name), key)
     }
 
+    fn import_private_key_versions(
+        &mut self,
+        name: &str,
+        versions: Vec<Ed25519PrivateKey>,
+    ) -> Result<usize, Error> {
+        self.inner
+            .import_private_key_versions(&self.namespaced(name), versions)
+    }
+
     fn export_private_key_for_version(
         &self,
         name: &str,
@@ -113,6 +190,16 @@ impl<S: CryptoStorage> CryptoStorage for Namespaced<S> {
         self.inner.rotate_key(&self.namespaced(name))
     }
 
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        self.inner
+            .prune_key_versions(&self.namespaced(name), keep_latest_n, in_use_floor)
+    }
+
     fn sign<T: CryptoHash + Serialize>(
         &self,
         name: &str,
@@ -130,6 +217,51 @@ impl<S: CryptoStorage> CryptoStorage for Namespaced<S> {
         self.inner
             .sign_using_version(&self.namespaced(name), version, message)
     }
+
+    fn sign_batch_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        self.inner
+            .sign_batch_using_version(&self.namespaced(name), version, messages)
+    }
+
+    fn create_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.inner.create_key_as::<K>(&self.namespaced(name))
+    }
+
+    fn import_private_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+        key: K,
+    ) -> Result<(), Error> {
+        self.inner
+            .import_private_key_as(&self.namespaced(name), key)
+    }
+
+    fn export_private_key_as<K: SchemeSigningKey>(&self, name: &str) -> Result<K, Error> {
+        self.inner.export_private_key_as(&self.namespaced(name))
+    }
+
+    fn get_public_key_as<K: SchemeSigningKey>(
+        &self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.inner.get_public_key_as::<K>(&self.namespaced(name))
+    }
+
+    fn sign_as<K: SchemeSigningKey, T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<K::SignatureMaterial, Error> {
+        self.inner.sign_as::<K, T>(&self.namespaced(name), message)
+    }
 }
 
 #[cfg(test)]
@@ -1,17 +1,33 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{CryptoKVStorage, Error, GetResponse, KVStorage};
+use crate::{
+    encrypted_on_disk::FileCipher, kv_storage::filter_keys, watch, CryptoKVStorage, Error,
+    GetResponse, KVStorage, KeyWatch, DEFAULT_MAX_KEY_VERSIONS,
+};
+use aptos_crypto::HashValue;
 use aptos_temppath::TempPath;
 use aptos_time_service::{TimeService, TimeServiceTrait};
-use serde::{de::DeserializeOwned, Serialize};
-use serde_json::Value;
+use fs2::FileExt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::{
-    collections::HashMap,
-    fs::{self, File},
-    io::{Read, Write},
-    path::PathBuf,
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+    time::Duration,
 };
+use zeroize::{Zeroize, Zeroizing};
+
+/// The extension of the sibling file holding the hex encoded SHA3-256 checksum of the file.
+const CHECKSUM_EXTENSION: &str = "sha3";
+
+/// How often watches check whether the file was modified.
+const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_millis(100);
 
 /// OnDiskStorage represents a key value store that is persisted to the local filesystem and is
 /// intended for single threads (or must be wrapped by a Arc<RwLock<>>). This provides no permission
@@ -20,53 +36,289 @@ use std::{
 /// must make copies of all key material which violates the code base. It violates it because
 /// the anticipation is that data stores would securely handle key material. This should not be used
 /// in production.
+///
+/// Writers that must be exclusive can call `lock`, which takes an advisory lock on a sibling
+/// `.lock` file that is held until the storage is dropped.
+///
+/// Writes are crash-safe: the file is replaced by an atomic rename of a synced temporary file,
+/// and its checksum is kept in a sibling `.sha3` file to detect partial files, e.g., on
+/// filesystems without atomic renames. The file itself remains plain JSON, readable by older
+/// versions and by external tools. Every write is mirrored to a sibling `.bak` file, from which
+/// a file that does not match its checksum is restored when it is read. So a file edited or
+/// emptied by hand is restored, unless its `.sha3` file is deleted along with it.
+///
+/// Watches compare the checksum of the file, so that writes by other processes are observed as
+/// well, however close together, and only decode the file once it changed.
+///
+/// A deleted key leaves a tombstone holding its latest version, so that a key set again never
+/// reuses a version that a writer may still hold.
 pub struct OnDiskStorage {
     file_path: PathBuf,
     temp_path: TempPath,
     time_service: TimeService,
+    lock_file: Option<File>,
+    /// Encrypts the file, for an EncryptedOnDiskStorage.
+    cipher: Option<Arc<FileCipher>>,
+    max_key_versions: Option<usize>,
+    watch_interval: Duration,
 }
 
 impl OnDiskStorage {
     pub fn new(file_path: PathBuf) -> Self {
-        Self::new_with_time_service(file_path, TimeService::real())
+        Self::new_with_time_service(file_path, TimeService::real(), None)
     }
 
-    fn new_with_time_service(file_path: PathBuf, time_service: TimeService) -> Self {
+    pub(crate) fn new_with_cipher(file_path: PathBuf, cipher: FileCipher) -> Self {
+        Self::new_with_time_service(file_path, TimeService::real(), Some(cipher))
+    }
+
+    fn new_with_time_service(
+        file_path: PathBuf,
+        time_service: TimeService,
+        cipher: Option<FileCipher>,
+    ) -> Self {
         if !file_path.exists() {
             File::create(&file_path).expect("Unable to create storage");
         }
 
-        // The parent will be one when only a filename is supplied. Therefore use the current
-        // working directory provided by PathBuf::new().
-        let file_dir = file_path
-            .parent()
-            .map_or(PathBuf::new(), |p| p.to_path_buf());
-
         Self {
+            temp_path: TempPath::new_with_temp_dir(parent_dir(&file_path)),
             file_path,
-            temp_path: TempPath::new_with_temp_dir(file_dir),
             time_service,
+            lock_file: None,
+            cipher: cipher.map(Arc::new),
+            max_key_versions: None,
+            watch_interval: DEFAULT_WATCH_INTERVAL,
         }
     }
 
-    fn read(&self) -> Result<HashMap<String, Value>, Error> {
-        let mut file = File::open(&self.file_path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        if contents.is_empty() {
-            return Ok(HashMap::new());
-        }
-        let data = serde_json::from_str(&contents)?;
-        Ok(data)
+    /// Sets the number of versions of each key retained on rotation, including the current one.
+    pub fn set_max_key_versions(&mut self, max_key_versions: usize) {
+        self.max_key_versions = Some(max_key_versions);
     }
 
-    fn write(&self, data: &HashMap<String, Value>) -> Result<(), Error> {
-        let contents = serde_json::to_vec(data)?;
-        let mut file = File::create(self.temp_path.path())?;
-        file.write_all(&contents)?;
-        fs::rename(&self.temp_path, &self.file_path)?;
+    /// Sets how often watches check whether the file was modified.
+    pub fn set_watch_interval(&mut self, watch_interval: Duration) {
+        self.watch_interval = watch_interval;
+    }
+
+    /// Acquires an exclusive advisory lock on the storage, so that no other process (or
+    /// OnDiskStorage instance) can lock it concurrently. The lock is released when this storage
+    /// is dropped, including during a panic unwind. Locking an already locked instance is a no-op.
+    pub fn lock(&mut self) -> Result<(), Error> {
+        if self.lock_file.is_some() {
+            return Ok(());
+        }
+
+        let lock_path = sibling_path(&self.file_path, "lock");
+        let mut lock_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&lock_path)?;
+
+        if lock_file.try_lock_exclusive().is_err() {
+            let mut pid = String::new();
+            lock_file.read_to_string(&mut pid)?;
+            return Err(Error::StorageInUse(
+                self.file_path.display().to_string(),
+                pid.trim().to_string(),
+            ));
+        }
+
+        // Record the owner to help operators diagnose contention
+        lock_file.set_len(0)?;
+        lock_file.seek(SeekFrom::Start(0))?;
+        write!(lock_file, "{}", process::id())?;
+        lock_file.sync_all()?;
+
+        self.lock_file = Some(lock_file);
         Ok(())
     }
+
+    fn read(&self) -> Result<StorageData, Error> {
+        read_data(&self.file_path, self.cipher.as_deref())
+    }
+
+    pub(crate) fn write(&self, data: &HashMap<String, Value>) -> Result<(), Error> {
+        let contents = self.serialize(data)?;
+        write_file(&self.temp_path, &self.file_path, &contents)?;
+        write_atomically(
+            &self.temp_path,
+            &sibling_path(&self.file_path, "bak"),
+            &contents,
+        )
+    }
+
+    /// Serializes the data into the contents of the file, encrypted if there is a cipher. The
+    /// buffers hold key material, so they are zeroized once dropped.
+    pub(crate) fn serialize(
+        &self,
+        data: &HashMap<String, Value>,
+    ) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let mut contents = Zeroizing::new(serde_json::to_vec(data)?);
+        if let Some(cipher) = &self.cipher {
+            contents = Zeroizing::new(cipher.encrypt(&contents)?);
+        }
+        Ok(contents)
+    }
+}
+
+/// The parsed data of a storage file. Its values hold key material, so their strings are zeroized
+/// once dropped.
+#[derive(Default)]
+pub(crate) struct StorageData(HashMap<String, Value>);
+
+impl Deref for StorageData {
+    type Target = HashMap<String, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for StorageData {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for StorageData {
+    fn drop(&mut self) {
+        self.0.values_mut().for_each(zeroize_value);
+    }
+}
+
+fn zeroize_value(value: &mut Value) {
+    match value {
+        Value::String(string) => string.zeroize(),
+        Value::Array(values) => values.iter_mut().for_each(zeroize_value),
+        Value::Object(values) => values.values_mut().for_each(zeroize_value),
+        Value::Null | Value::Bool(_) | Value::Number(_) => (),
+    }
+}
+
+/// Reads and, if there is a cipher, decrypts the data of the storage file at `file_path`.
+fn read_data(file_path: &Path, cipher: Option<&FileCipher>) -> Result<StorageData, Error> {
+    decode_data(read_file(file_path)?, cipher)
+}
+
+/// Decrypts, if there is a cipher, and parses the contents of a storage file.
+pub(crate) fn decode_data(
+    contents: Zeroizing<Vec<u8>>,
+    cipher: Option<&FileCipher>,
+) -> Result<StorageData, Error> {
+    if contents.is_empty() {
+        // An encrypted file always holds at least its salt
+        return match cipher {
+            Some(_) => Err(Error::TamperError("The storage is empty".into())),
+            None => Ok(StorageData::default()),
+        };
+    }
+    let data = match cipher {
+        Some(cipher) => serde_json::from_slice(&cipher.decrypt(&contents)?)?,
+        None => serde_json::from_slice(&contents)?,
+    };
+    Ok(StorageData(data))
+}
+
+/// Returns the contents of the storage file at `file_path`, once checked against its checksum.
+/// A file that does not match its checksum, e.g., after a write torn by a crash, is replaced by
+/// its backup if the backup matches, and fails with `Error::CorruptedStorage` otherwise. A file
+/// without a checksum, e.g., written by an older version, is returned as is.
+pub(crate) fn read_file(file_path: &Path) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let contents = Zeroizing::new(fs::read(file_path)?);
+    let expected = match fs::read_to_string(sibling_path(file_path, CHECKSUM_EXTENSION)) {
+        Ok(checksum) => checksum,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(contents),
+        Err(error) => return Err(error.into()),
+    };
+    if checksum(&contents) == expected.trim() {
+        return Ok(contents);
+    }
+
+    let backup_path = sibling_path(file_path, "bak");
+    let backup = match fs::read(&backup_path) {
+        Ok(backup) => Zeroizing::new(backup),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Zeroizing::new(vec![]),
+        Err(error) => return Err(error.into()),
+    };
+    if checksum(&backup) != expected.trim() {
+        return Err(Error::CorruptedStorage(format!(
+            "neither {} nor its backup match their checksum",
+            file_path.display()
+        )));
+    }
+
+    aptos_logger::warn!(
+        "Restoring storage {} from its backup {}, as it does not match its checksum",
+        file_path.display(),
+        backup_path.display()
+    );
+    let temp_path = TempPath::new_with_temp_dir(parent_dir(file_path));
+    write_atomically(&temp_path, file_path, &backup)?;
+    Ok(backup)
+}
+
+/// Replaces the storage file at `file_path`, then its checksum. A crash in between leaves a file
+/// that does not match its checksum, while the backup, which is written last, still does.
+fn write_file(temp_path: &TempPath, file_path: &Path, contents: &[u8]) -> Result<(), Error> {
+    write_atomically(temp_path, file_path, contents)?;
+    write_atomically(
+        temp_path,
+        &sibling_path(file_path, CHECKSUM_EXTENSION),
+        checksum(contents).as_bytes(),
+    )
+}
+
+/// Replaces the storage file at `file_path` and its checksum, as a storage writing it would, for
+/// tests that alter the file without it being restored.
+#[cfg(test)]
+pub(crate) fn overwrite_file(file_path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let temp_path = TempPath::new_with_temp_dir(parent_dir(file_path));
+    write_file(&temp_path, file_path, contents)
+}
+
+/// Replaces the file at `file_path` by renaming a synced temporary file over it, then syncs the
+/// directory so that the rename itself is persisted.
+fn write_atomically(temp_path: &TempPath, file_path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let mut file = File::create(temp_path.path())?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(temp_path.path(), file_path)?;
+    sync_dir(&parent_dir(file_path))
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<(), Error> {
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+fn checksum(contents: &[u8]) -> String {
+    HashValue::sha3_256_of(contents).to_hex()
+}
+
+/// Returns the directory of the file. The parent is empty when only a filename is supplied, in
+/// which case the current working directory is used.
+fn parent_dir(file_path: &Path) -> PathBuf {
+    match file_path.parent() {
+        Some(parent) if parent != Path::new("") => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Returns the path of `file_path` with `extension` appended, e.g., `storage.json.bak`.
+fn sibling_path(file_path: &Path, extension: &str) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
 }
 
 impl KVStorage for OnDiskStorage {
@@ -75,26 +327,149 @@ impl KVStorage for OnDiskStorage {
     }
 
     fn get<V: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<V>, Error> {
-        let mut data = self.read()?;
-        data.remove(key)
-            .ok_or_else(|| Error::KeyNotSet(key.to_string()))
-            .and_then(|value| serde_json::from_value(value).map_err(|e| e.into()))
+        let data = self.read()?;
+        let value = data
+            .get(key)
+            .filter(|entry| is_set(entry))
+            .ok_or_else(|| Error::KeyNotSet(key.to_string()))?;
+        Ok(GetResponse::deserialize(value)?)
     }
 
     fn set<V: Serialize>(&mut self, key: &str, value: V) -> Result<(), Error> {
         let now = self.time_service.now_secs();
         let mut data = self.read()?;
+        let version = latest_version(&data, key) + 1;
+        data.insert(
+            key.to_string(),
+            serde_json::to_value(&GetResponse::new_with_version(value, now, version))?,
+        );
+        self.write(&data)
+    }
+
+    /// The check is atomic with respect to other users of this instance. Other processes writing
+    /// to the same file must be excluded with `lock`.
+    fn set_if_version<V: Serialize>(
+        &mut self,
+        key: &str,
+        value: V,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        let now = self.time_service.now_secs();
+        let mut data = self.read()?;
+        let version = version(&data, key);
+        if version != expected_version {
+            return Err(Error::VersionMismatch {
+                expected: expected_version,
+                actual: version,
+            });
+        }
+        let version = latest_version(&data, key) + 1;
         data.insert(
             key.to_string(),
-            serde_json::to_value(&GetResponse::new(value, now))?,
+            serde_json::to_value(&GetResponse::new_with_version(value, now, version))?,
         );
         self.write(&data)
     }
 
+    /// Reads the file once for all keys.
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        let data = self.read()?;
+        keys.iter()
+            .map(|key| {
+                let value = data
+                    .get(*key)
+                    .filter(|entry| is_set(entry))
+                    .ok_or_else(|| Error::KeyNotSet(key.to_string()))?;
+                Ok((key.to_string(), GetResponse::deserialize(value)?))
+            })
+            .collect()
+    }
+
+    /// Writes the file once for all entries, so that either all or none of them are persisted.
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        let now = self.time_service.now_secs();
+        let mut data = self.read()?;
+        for (key, value) in entries {
+            let version = latest_version(&data, key) + 1;
+            data.insert(
+                key.to_string(),
+                serde_json::to_value(&GetResponse::new_with_version(value, now, version))?,
+            );
+        }
+        self.write(&data)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        let mut data = self.read()?;
+        if data.get(key).map_or(false, is_set) {
+            let version = latest_version(&data, key);
+            data.insert(key.to_string(), json!({ "version": version }));
+            self.write(&data)?;
+        }
+        Ok(())
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        let data = self.read()?;
+        let keys = data
+            .iter()
+            .filter(|(_, entry)| is_set(entry))
+            .map(|(key, _)| key.clone());
+        Ok(filter_keys(keys, prefix))
+    }
+
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        let file_path = self.file_path.clone();
+        let cipher = self.cipher.clone();
+        let watched_key = key.to_string();
+        let mut file_checksum = None;
+        let mut metadata = None;
+        watch::poll(key, self.watch_interval, move || {
+            // Every write changes the contents of the file, as it bumps a version, even within the
+            // granularity of its modification time
+            let contents = read_file(&file_path)?;
+            let contents_checksum = checksum(&contents);
+            if file_checksum.as_ref() != Some(&contents_checksum) {
+                let data = decode_data(contents, cipher.as_deref())?;
+                metadata = match data.get(&watched_key).filter(|entry| is_set(entry)) {
+                    Some(value) => Some(GetResponse::<Value>::deserialize(value)?.metadata()),
+                    None => None,
+                };
+                file_checksum = Some(contents_checksum);
+            }
+            Ok(metadata)
+        })
+    }
+
     #[cfg(any(test, feature = "testing"))]
     fn reset_and_clear(&mut self) -> Result<(), Error> {
         self.write(&HashMap::new())
     }
 }
 
-impl CryptoKVStorage for OnDiskStorage {}
+impl CryptoKVStorage for OnDiskStorage {
+    fn max_key_versions(&self) -> usize {
+        self.max_key_versions.unwrap_or(DEFAULT_MAX_KEY_VERSIONS)
+    }
+}
+
+/// Returns the version of the value stored at the key, or 0 if it is not set.
+fn version(data: &HashMap<String, Value>, key: &str) -> u64 {
+    match data.get(key) {
+        Some(entry) if is_set(entry) => latest_version(data, key),
+        _ => 0,
+    }
+}
+
+/// Returns the version of the last write of the key, including the delete of a deleted key, or 0
+/// if it was never set.
+fn latest_version(data: &HashMap<String, Value>, key: &str) -> u64 {
+    data.get(key)
+        .and_then(|entry| entry["version"].as_u64())
+        .unwrap_or(0)
+}
+
+/// Returns whether the entry holds a value, rather than being the tombstone of a deleted key.
+fn is_set(entry: &Value) -> bool {
+    entry.get("value").is_some()
+}
@@ -0,0 +1,107 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{CryptoKVStorage, Error, GetResponse, KVStorage, KeyWatch};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Overlay pairs a read-only storage, e.g., an EnvStorage holding the keys of a validator, with a
+/// writable storage that holds all writes, e.g., safety data and the waypoint. Reads prefer the
+/// writable storage, and fall back to the read-only storage for keys it does not hold. As nothing
+/// is ever removed from the read-only storage, a deleted key reverts to its read-only value, if
+/// any.
+///
+/// Keys held by the read-only storage cannot be rotated through an overlay, as the rotated key
+/// would be written to the writable storage and silently shadow the read-only one, e.g., the
+/// consensus key injected from a secret. Such keys fail to rotate with `Error::ReadOnly`, and are
+/// to be rotated where the read-only storage is provisioned.
+pub struct Overlay<R, W> {
+    read_only: R,
+    writable: W,
+}
+
+impl<R, W> Overlay<R, W> {
+    pub fn new(read_only: R, writable: W) -> Self {
+        Self {
+            read_only,
+            writable,
+        }
+    }
+
+    pub fn read_only(&self) -> &R {
+        &self.read_only
+    }
+
+    pub fn writable(&self) -> &W {
+        &self.writable
+    }
+
+    pub fn writable_mut(&mut self) -> &mut W {
+        &mut self.writable
+    }
+}
+
+impl<R: KVStorage, W: KVStorage> KVStorage for Overlay<R, W> {
+    fn available(&self) -> Result<(), Error> {
+        self.read_only.available()?;
+        self.writable.available()
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        match self.writable.get(key) {
+            Err(Error::KeyNotSet(_)) => self.read_only.get(key),
+            response => response,
+        }
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        self.writable.set(key, value)
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        self.writable.set_batch(entries)
+    }
+
+    /// Versions are those of the writable storage, where a key only held by the read-only storage
+    /// is not set.
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        self.writable.set_if_version(key, value, expected_version)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        self.writable.delete(key)
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        let mut keys: BTreeSet<_> = self.read_only.list_keys(prefix)?.into_iter().collect();
+        keys.extend(self.writable.list_keys(prefix)?);
+        Ok(keys.into_iter().collect())
+    }
+
+    /// Only watches the writable storage, as the read-only storage does not change.
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        self.writable.watch(key)
+    }
+
+    /// Only clears the writable storage.
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.writable.reset_and_clear()
+    }
+}
+
+impl<R: KVStorage, W: KVStorage> CryptoKVStorage for Overlay<R, W> {
+    fn check_rotatable(&self, name: &str) -> Result<(), Error> {
+        match self.read_only.get::<Value>(name) {
+            Ok(_) => Err(Error::ReadOnly(name.into())),
+            Err(Error::KeyNotSet(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
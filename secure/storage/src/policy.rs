@@ -48,7 +48,7 @@ pub enum Identity {
 }
 
 /// Represents actions
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Capability {
     Export,
     Read,
@@ -0,0 +1,302 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    Capability, CryptoStorage, Error, GetResponse, KVStorage, KeyWatch, PublicKeyResponse,
+    SchemeSigningKey,
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The capabilities granted on the keys of a PolicyStorage: a key is granted those of its own
+/// rule, or the default ones if it has none.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyPolicy {
+    default: Vec<Capability>,
+    keys: BTreeMap<String, Vec<Capability>>,
+}
+
+impl KeyPolicy {
+    pub fn new(default: Vec<Capability>) -> Self {
+        Self {
+            default,
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Grants every capability on keys without a rule.
+    pub fn allow_all() -> Self {
+        Self::new(vec![
+            Capability::Export,
+            Capability::Read,
+            Capability::Rotate,
+            Capability::Sign,
+            Capability::Write,
+        ])
+    }
+
+    /// Only grants reading keys without a rule, e.g., for monitoring tooling.
+    pub fn read_only() -> Self {
+        Self::new(vec![Capability::Read])
+    }
+
+    /// Grants exactly the given capabilities on the key, overriding the default ones.
+    pub fn key(mut self, key: &str, capabilities: Vec<Capability>) -> Self {
+        self.keys.insert(key.into(), capabilities);
+        self
+    }
+
+    pub fn allows(&self, key: &str, capability: Capability) -> bool {
+        self.keys
+            .get(key)
+            .unwrap_or(&self.default)
+            .contains(&capability)
+    }
+}
+
+/// PolicyStorage enforces a KeyPolicy on the operations of a storage: an operation on a key that
+/// the policy does not grant the required capability fails with `Error::PolicyDenied`, without
+/// reaching the storage. Reads, including public keys and watches, require `Read`;
+/// writes, deletes, and the creation and import of keys require `Write`; rotations and prunes
+/// require `Rotate`, exports `Export`, and signatures `Sign`. Listing keys requires `Read` on the
+/// prefix. It is usually created through `Storage::with_key_policy`.
+pub struct PolicyStorage<S> {
+    inner: S,
+    policy: KeyPolicy,
+}
+
+impl<S> PolicyStorage<S> {
+    pub fn new(inner: S, policy: KeyPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    pub fn policy(&self) -> &KeyPolicy {
+        &self.policy
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Fails unless the policy grants the capability on the key.
+    fn check(&self, key: &str, capability: Capability) -> Result<(), Error> {
+        if self.policy.allows(key, capability) {
+            Ok(())
+        } else {
+            let access = format!("{:?} access to {}", capability, key);
+            aptos_logger::warn!("Denied {} by the storage key policy", access);
+            Err(Error::PolicyDenied(access))
+        }
+    }
+}
+
+impl<S: KVStorage> KVStorage for PolicyStorage<S> {
+    fn available(&self) -> Result<(), Error> {
+        self.inner.available()
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        self.check(key, Capability::Read)?;
+        self.inner.get(key)
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        self.check(key, Capability::Write)?;
+        self.inner.set(key, value)
+    }
+
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        for key in keys {
+            self.check(key, Capability::Read)?;
+        }
+        self.inner.get_batch(keys)
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        for (key, _) in entries {
+            self.check(key, Capability::Write)?;
+        }
+        self.inner.set_batch(entries)
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        self.check(key, Capability::Write)?;
+        self.inner.set_if_version(key, value, expected_version)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        self.check(key, Capability::Write)?;
+        self.inner.delete(key)
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        self.check(prefix.unwrap_or_default(), Capability::Read)?;
+        self.inner.list_keys(prefix)
+    }
+
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        self.check(key, Capability::Read)?;
+        self.inner.watch(key)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.inner.reset_and_clear()
+    }
+}
+
+impl<S: CryptoStorage> CryptoStorage for PolicyStorage<S> {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.check(name, Capability::Write)?;
+        self.inner.create_key(name)
+    }
+
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.check(name, Capability::Write)?;
+        self.inner.generate_key(name)
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        self.inner.supports_non_exportable_keys()
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        self.check(name, Capability::Export)?;
+        self.inner.export_private_key(name)
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        self.check(name, Capability::Write)?;
+        self.inner.import_private_key(name, key)
+    }
+
+    fn import_private_key_versions(
+        &mut self,
+        name: &str,
+        versions: Vec<Ed25519PrivateKey>,
+    ) -> Result<usize, Error> {
+        self.check(name, Capability::Write)?;
+        self.inner.import_private_key_versions(name, versions)
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        self.check(name, Capability::Export)?;
+        self.inner.export_private_key_for_version(name, version)
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.check(name, Capability::Read)?;
+        self.inner.get_public_key(name)
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.check(name, Capability::Read)?;
+        self.inner.get_public_key_previous_version(name)
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.check(name, Capability::Rotate)?;
+        self.inner.rotate_key(name)
+    }
+
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        self.check(name, Capability::Rotate)?;
+        self.inner
+            .prune_key_versions(name, keep_latest_n, in_use_floor)
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.check(name, Capability::Sign)?;
+        self.inner.sign(name, message)
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.check(name, Capability::Sign)?;
+        self.inner.sign_using_version(name, version, message)
+    }
+
+    fn sign_batch_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        self.check(name, Capability::Sign)?;
+        self.inner.sign_batch_using_version(name, version, messages)
+    }
+
+    fn create_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.check(name, Capability::Write)?;
+        self.inner.create_key_as::<K>(name)
+    }
+
+    fn import_private_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+        key: K,
+    ) -> Result<(), Error> {
+        self.check(name, Capability::Write)?;
+        self.inner.import_private_key_as(name, key)
+    }
+
+    fn export_private_key_as<K: SchemeSigningKey>(&self, name: &str) -> Result<K, Error> {
+        self.check(name, Capability::Export)?;
+        self.inner.export_private_key_as(name)
+    }
+
+    fn get_public_key_as<K: SchemeSigningKey>(
+        &self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.check(name, Capability::Read)?;
+        self.inner.get_public_key_as::<K>(name)
+    }
+
+    fn sign_as<K: SchemeSigningKey, T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<K::SignatureMaterial, Error> {
+        self.check(name, Capability::Sign)?;
+        self.inner.sign_as::<K, T>(name, message)
+    }
+}
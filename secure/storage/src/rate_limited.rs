@@ -0,0 +1,392 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    metrics, CryptoStorage, Error, GetResponse, KVStorage, KeyWatch, PublicKeyResponse,
+    SchemeSigningKey,
+};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
+    hash::CryptoHash,
+};
+use aptos_infallible::Mutex;
+use aptos_time_service::{TimeService, TimeServiceTrait};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The classes of operations that a RateLimitedStorage limits separately. Key creation,
+/// rotation and pruning are writes, while checking availability and exporting keys are reads.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RateLimitedOperation {
+    Read,
+    Write,
+    Sign,
+}
+
+impl fmt::Display for RateLimitedOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RateLimitedOperation::Read => write!(f, "read"),
+            RateLimitedOperation::Write => write!(f, "write"),
+            RateLimitedOperation::Sign => write!(f, "sign"),
+        }
+    }
+}
+
+/// The limits of a RateLimitedStorage: each class of operations may burst up to `capacity`
+/// operations, and is then allowed `refill_per_sec` operations per second. Classes without a
+/// limit are not rate limited.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RateLimits {
+    reads: Option<(u64, u64)>,
+    writes: Option<(u64, u64)>,
+    signs: Option<(u64, u64)>,
+}
+
+impl RateLimits {
+    pub fn reads(mut self, capacity: u64, refill_per_sec: u64) -> Self {
+        self.reads = Some((capacity, refill_per_sec));
+        self
+    }
+
+    pub fn writes(mut self, capacity: u64, refill_per_sec: u64) -> Self {
+        self.writes = Some((capacity, refill_per_sec));
+        self
+    }
+
+    pub fn signs(mut self, capacity: u64, refill_per_sec: u64) -> Self {
+        self.signs = Some((capacity, refill_per_sec));
+        self
+    }
+}
+
+/// A token bucket holding up to `capacity` tokens, refilled at `refill_per_sec` tokens per
+/// second. Tokens are tracked in thousandths so that refills are exact at millisecond precision.
+pub struct TokenBucket {
+    capacity: u64,
+    refill_per_sec: u64,
+    milli_tokens: u64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    pub fn new(capacity: u64, refill_per_sec: u64, now: Instant) -> Self {
+        assert!(capacity > 0, "The bucket capacity must be positive");
+        assert!(
+            refill_per_sec > 0,
+            "The bucket refill rate must be positive"
+        );
+        Self {
+            capacity,
+            refill_per_sec,
+            milli_tokens: capacity.saturating_mul(1_000),
+            last_refill: now,
+        }
+    }
+
+    /// Takes a token, or returns how long until the next one is refilled if the bucket is empty.
+    pub fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        // Whole milliseconds are refilled, and the remainder counts towards the next refill
+        let elapsed_ms = now.saturating_duration_since(self.last_refill).as_millis() as u64;
+        self.last_refill += Duration::from_millis(elapsed_ms);
+        self.milli_tokens = self
+            .milli_tokens
+            .saturating_add(elapsed_ms.saturating_mul(self.refill_per_sec))
+            .min(self.capacity.saturating_mul(1_000));
+
+        if self.milli_tokens < 1_000 {
+            let missing = 1_000 - self.milli_tokens;
+            let retry_after_ms = (missing + self.refill_per_sec - 1) / self.refill_per_sec;
+            return Err(Duration::from_millis(retry_after_ms));
+        }
+        self.milli_tokens -= 1_000;
+        Ok(())
+    }
+}
+
+/// RateLimitedStorage limits the rate of the operations issued to a storage, e.g., so that a
+/// caller stuck in a retry loop cannot exhaust the rate limits that a shared Vault applies to all
+/// of its tenants. Reads, writes and signatures draw from separate token buckets (see
+/// `RateLimits`), and operations in excess fail with `Error::RateLimited` without reaching the
+/// storage. Throttled operations are counted in `STORAGE_RATE_LIMITED`, labeled by the backend
+/// of the storage and the class of the operation.
+///
+/// It is usually created through `Storage::with_rate_limits`, and composes with the other
+/// wrappers: wrapped in a CachedStorage, only cache misses are limited, and throttled operations
+/// are not failures for a CircuitBreakerStorage.
+pub struct RateLimitedStorage<S> {
+    backend: &'static str,
+    inner: S,
+    reads: Option<Mutex<TokenBucket>>,
+    writes: Option<Mutex<TokenBucket>>,
+    signs: Option<Mutex<TokenBucket>>,
+    time_service: TimeService,
+}
+
+impl<S> RateLimitedStorage<S> {
+    pub fn new(backend: &'static str, inner: S, limits: RateLimits) -> Self {
+        Self::new_with_time_service(backend, inner, limits, TimeService::real())
+    }
+
+    pub(crate) fn new_with_time_service(
+        backend: &'static str,
+        inner: S,
+        limits: RateLimits,
+        time_service: TimeService,
+    ) -> Self {
+        let now = time_service.now();
+        let bucket = |limit: Option<(u64, u64)>| {
+            limit.map(|(capacity, refill_per_sec)| {
+                Mutex::new(TokenBucket::new(capacity, refill_per_sec, now))
+            })
+        };
+        Self {
+            backend,
+            inner,
+            reads: bucket(limits.reads),
+            writes: bucket(limits.writes),
+            signs: bucket(limits.signs),
+            time_service,
+        }
+    }
+
+    pub fn backend(&self) -> &'static str {
+        self.backend
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Fails unless the bucket of the operation holds a token.
+    fn acquire(&self, operation: RateLimitedOperation) -> Result<(), Error> {
+        let bucket = match operation {
+            RateLimitedOperation::Read => &self.reads,
+            RateLimitedOperation::Write => &self.writes,
+            RateLimitedOperation::Sign => &self.signs,
+        };
+        let bucket = match bucket {
+            Some(bucket) => bucket,
+            None => return Ok(()),
+        };
+
+        bucket
+            .lock()
+            .try_acquire(self.time_service.now())
+            .map_err(|retry_after| {
+                metrics::STORAGE_RATE_LIMITED
+                    .with_label_values(&[self.backend, &operation.to_string()])
+                    .inc();
+                Error::RateLimited { retry_after }
+            })
+    }
+}
+
+impl<S: KVStorage> KVStorage for RateLimitedStorage<S> {
+    fn available(&self) -> Result<(), Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.available()
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<GetResponse<T>, Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.get(key)
+    }
+
+    fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.set(key, value)
+    }
+
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.get_batch(keys)
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.set_batch(entries)
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.set_if_version(key, value, expected_version)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.delete(key)
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.list_keys(prefix)
+    }
+
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.watch(key)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn reset_and_clear(&mut self) -> Result<(), Error> {
+        self.inner.reset_and_clear()
+    }
+}
+
+impl<S: CryptoStorage> CryptoStorage for RateLimitedStorage<S> {
+    fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.create_key(name)
+    }
+
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.generate_key(name)
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        self.inner.supports_non_exportable_keys()
+    }
+
+    fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.export_private_key(name)
+    }
+
+    fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.import_private_key(name, key)
+    }
+
+    fn import_private_key_versions(
+        &mut self,
+        name: &str,
+        versions: Vec<Ed25519PrivateKey>,
+    ) -> Result<usize, Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.import_private_key_versions(name, versions)
+    }
+
+    fn export_private_key_for_version(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+    ) -> Result<Ed25519PrivateKey, Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.export_private_key_for_version(name, version)
+    }
+
+    fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.get_public_key(name)
+    }
+
+    fn get_public_key_previous_version(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.get_public_key_previous_version(name)
+    }
+
+    fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.rotate_key(name)
+    }
+
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner
+            .prune_key_versions(name, keep_latest_n, in_use_floor)
+    }
+
+    fn sign<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.acquire(RateLimitedOperation::Sign)?;
+        self.inner.sign(name, message)
+    }
+
+    fn sign_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        message: &T,
+    ) -> Result<Ed25519Signature, Error> {
+        self.acquire(RateLimitedOperation::Sign)?;
+        self.inner.sign_using_version(name, version, message)
+    }
+
+    fn sign_batch_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        self.acquire(RateLimitedOperation::Sign)?;
+        self.inner.sign_batch_using_version(name, version, messages)
+    }
+
+    fn create_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.create_key_as::<K>(name)
+    }
+
+    fn import_private_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+        key: K,
+    ) -> Result<(), Error> {
+        self.acquire(RateLimitedOperation::Write)?;
+        self.inner.import_private_key_as(name, key)
+    }
+
+    fn export_private_key_as<K: SchemeSigningKey>(&self, name: &str) -> Result<K, Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.export_private_key_as(name)
+    }
+
+    fn get_public_key_as<K: SchemeSigningKey>(
+        &self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        self.acquire(RateLimitedOperation::Read)?;
+        self.inner.get_public_key_as::<K>(name)
+    }
+
+    fn sign_as<K: SchemeSigningKey, T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<K::SignatureMaterial, Error> {
+        self.acquire(RateLimitedOperation::Sign)?;
+        self.inner.sign_as::<K, T>(name, message)
+    }
+}
@@ -1,12 +1,17 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
-    CryptoStorage, Error, GetResponse, GitHubStorage, InMemoryStorage, KVStorage, Namespaced,
-    OnDiskStorage, PublicKeyResponse, VaultStorage,
+    AwsStorage, CachedStorage, CircuitBreakerStorage, CryptoStorage, EncryptedOnDiskStorage,
+    EnvStorage, Error, GcsSecretManagerStorage, GetResponse, GitHubStorage, InMemoryStorage,
+    Instrumented, KVStorage, KeyPolicy, KeyWatch, Namespaced, OnDiskStorage, Overlay,
+    PolicyStorage, PublicKeyResponse, RateLimitedStorage, RateLimits, SchemeSigningKey,
+    VaultStorage,
 };
 use aptos_crypto::ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature};
 use enum_dispatch::enum_dispatch;
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, time::Duration};
 
 /// This is the interface into secure storage. Any storage engine implementing this trait
 /// should support both key/value operations (e.g., get, set and create) and cryptographic key
@@ -16,11 +21,79 @@ use serde::{de::DeserializeOwned, Serialize};
 /// T: Storage. This boilerplate can be 100% generated by a proc macro.
 #[enum_dispatch(KVStorage, CryptoStorage)]
 pub enum Storage {
+    AwsStorage(AwsStorage),
+    GcsSecretManagerStorage(GcsSecretManagerStorage),
     GitHubStorage(GitHubStorage),
     VaultStorage(VaultStorage),
     InMemoryStorage(InMemoryStorage),
     NamespacedStorage(Namespaced<Box<Storage>>),
     OnDiskStorage(OnDiskStorage),
+    EncryptedOnDiskStorage(EncryptedOnDiskStorage),
+    EnvStorage(EnvStorage),
+    OverlayStorage(Overlay<Box<Storage>, Box<Storage>>),
+    CachedStorage(CachedStorage<Box<Storage>>),
+    InstrumentedStorage(Instrumented<Box<Storage>>),
+    CircuitBreakerStorage(CircuitBreakerStorage<Box<Storage>>),
+    PolicyStorage(PolicyStorage<Box<Storage>>),
+    RateLimitedStorage(RateLimitedStorage<Box<Storage>>),
+}
+
+impl Storage {
+    /// The kind of backend holding the data, e.g., "vault", which labels storage metrics.
+    /// Wrappers report the backend they wrap, except for overlays, which span two backends.
+    pub fn backend(&self) -> &'static str {
+        match self {
+            Storage::AwsStorage(_) => "aws",
+            Storage::GcsSecretManagerStorage(_) => "gcs",
+            Storage::GitHubStorage(_) => "github",
+            Storage::VaultStorage(_) => "vault",
+            Storage::InMemoryStorage(_) => "in_memory",
+            Storage::NamespacedStorage(storage) => storage.inner().backend(),
+            Storage::OnDiskStorage(_) => "on_disk",
+            Storage::EncryptedOnDiskStorage(_) => "encrypted_on_disk",
+            Storage::EnvStorage(_) => "env",
+            Storage::OverlayStorage(_) => "overlay",
+            Storage::CachedStorage(storage) => storage.bypass().backend(),
+            Storage::InstrumentedStorage(storage) => storage.backend(),
+            Storage::CircuitBreakerStorage(storage) => storage.backend(),
+            Storage::PolicyStorage(storage) => storage.inner().backend(),
+            Storage::RateLimitedStorage(storage) => storage.backend(),
+        }
+    }
+
+    /// Records the latency and failures of every operation of this storage, labeled by its
+    /// backend (see `Instrumented`). Instrumenting a storage twice has no effect.
+    pub fn instrumented(self) -> Self {
+        match self {
+            Storage::InstrumentedStorage(_) => self,
+            storage => Storage::from(Instrumented::new(storage.backend(), Box::new(storage))),
+        }
+    }
+
+    /// Fails fast once this storage failed `failure_threshold` consecutive times, until
+    /// `cooldown` elapsed (see `CircuitBreakerStorage`).
+    pub fn with_circuit_breaker(self, failure_threshold: u32, cooldown: Duration) -> Self {
+        let backend = self.backend();
+        Storage::from(CircuitBreakerStorage::new(
+            backend,
+            Box::new(self),
+            failure_threshold,
+            cooldown,
+        ))
+    }
+
+    /// Limits the rate of the reads, writes and signatures issued to this storage (see
+    /// `RateLimitedStorage`).
+    pub fn with_rate_limits(self, limits: RateLimits) -> Self {
+        let backend = self.backend();
+        Storage::from(RateLimitedStorage::new(backend, Box::new(self), limits))
+    }
+
+    /// Rejects the operations of this storage that the policy does not grant (see
+    /// `PolicyStorage`).
+    pub fn with_key_policy(self, policy: KeyPolicy) -> Self {
+        Storage::from(PolicyStorage::new(Box::new(self), policy))
+    }
 }
 
 impl KVStorage for Box<Storage> {
@@ -36,6 +109,35 @@ impl KVStorage for Box<Storage> {
         Storage::set(self, key, value)
     }
 
+    fn get_batch(&self, keys: &[&str]) -> Result<BTreeMap<String, GetResponse<Value>>, Error> {
+        Storage::get_batch(self, keys)
+    }
+
+    fn set_batch(&mut self, entries: &[(&str, Value)]) -> Result<(), Error> {
+        Storage::set_batch(self, entries)
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        Storage::set_if_version(self, key, value, expected_version)
+    }
+
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        Storage::delete(self, key)
+    }
+
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        Storage::list_keys(self, prefix)
+    }
+
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        Storage::watch(self, key)
+    }
+
     #[cfg(any(test, feature = "testing"))]
     fn reset_and_clear(&mut self) -> Result<(), Error> {
         Storage::reset_and_clear(self)
@@ -47,6 +149,14 @@ impl CryptoStorage for Box<Storage> {
         Storage::create_key(self, name)
     }
 
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        Storage::generate_key(self, name)
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        Storage::supports_non_exportable_keys(self)
+    }
+
     fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
         Storage::export_private_key(self, name)
     }
@@ -55,6 +165,14 @@ impl CryptoStorage for Box<Storage> {
         Storage::import_private_key(self, name, key)
     }
 
+    fn import_private_key_versions(
+        &mut self,
+        name: &str,
+        versions: Vec<Ed25519PrivateKey>,
+    ) -> Result<usize, Error> {
+        Storage::import_private_key_versions(self, name, versions)
+    }
+
     fn export_private_key_for_version(
         &self,
         name: &str,
@@ -75,6 +193,15 @@ impl CryptoStorage for Box<Storage> {
         Storage::rotate_key(self, name)
     }
 
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        Storage::prune_key_versions(self, name, keep_latest_n, in_use_floor)
+    }
+
     fn sign<T: aptos_crypto::hash::CryptoHash + Serialize>(
         &self,
         name: &str,
@@ -91,4 +218,47 @@ impl CryptoStorage for Box<Storage> {
     ) -> Result<Ed25519Signature, Error> {
         Storage::sign_using_version(self, name, version, message)
     }
+
+    fn sign_batch_using_version<T: aptos_crypto::hash::CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        Storage::sign_batch_using_version(self, name, version, messages)
+    }
+
+    fn create_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        Storage::create_key_as::<K>(self, name)
+    }
+
+    fn import_private_key_as<K: SchemeSigningKey>(
+        &mut self,
+        name: &str,
+        key: K,
+    ) -> Result<(), Error> {
+        Storage::import_private_key_as(self, name, key)
+    }
+
+    fn export_private_key_as<K: SchemeSigningKey>(&self, name: &str) -> Result<K, Error> {
+        Storage::export_private_key_as(self, name)
+    }
+
+    fn get_public_key_as<K: SchemeSigningKey>(
+        &self,
+        name: &str,
+    ) -> Result<K::VerifyingKeyMaterial, Error> {
+        Storage::get_public_key_as::<K>(self, name)
+    }
+
+    fn sign_as<K: SchemeSigningKey, T: aptos_crypto::hash::CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        message: &T,
+    ) -> Result<K::SignatureMaterial, Error> {
+        Storage::sign_as::<K, T>(self, name, message)
+    }
 }
@@ -0,0 +1,843 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! The conformance tests of secure storage backends, which check the key/value and cryptographic
+//! operations of a storage, including the errors returned. All backends should pass `run_all`,
+//! including those implemented outside of this crate, which can call it from their own tests
+//! through the `testing` feature. The other public tests cover behaviors that not all backends
+//! support, e.g., versioned writes or non-exportable keys.
+//!
+//! Tests may leave keys behind, and are run on a storage that is cleared by `reset_and_clear`.
+//! They cannot be run concurrently on storages that share a backend.
+
+use crate::{
+    CryptoStorage, Error, GetResponse, ImportResult, KVStorage, Namespaced, SchemeSigningKey,
+};
+
+use aptos_crypto::{
+    ed25519::Ed25519PrivateKey, test_utils::TestAptosCrypto, HashValue, PrivateKey, Signature,
+    Uniform,
+};
+
+/// Storage data constants for testing purposes.
+const CRYPTO_KEY: &str = "Private_Key";
+const U64_KEY: &str = "U64_Key";
+const CRYPTO_NAME: &str = "Test_Key_Name";
+
+/// Runs all conformance tests on the storage, clearing it before each test. This holds the
+/// canonical list of tests, so that all callers run the same battery.
+pub fn run_all<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let tests: &[fn(&mut S)] = &[
+        test_set_reset_get,
+        test_create_and_get_non_existent_version,
+        test_create_get_key_pair,
+        test_create_key_pair_and_perform_rotations,
+        test_create_sign_rotate_sign,
+        test_sign_batch_using_version,
+        test_ensure_storage_is_available,
+        test_get_non_existent,
+        test_get_batch_non_existent,
+        test_get_public_key_previous_version,
+        test_get_set,
+        test_get_set_batch,
+        test_get_uncreated_key_pair,
+        test_hash_value,
+        test_incremental_timestamp,
+        test_import_key,
+        test_import_key_if_absent,
+        test_key_scheme_ed25519,
+        test_list_keys,
+        test_delete,
+        test_namespaces,
+        test_verify_incorrect_value_types,
+    ];
+
+    storage.reset_and_clear().unwrap();
+    for test in tests {
+        test(storage);
+        storage.reset_and_clear().unwrap();
+    }
+}
+
+/// This test tries to set a key, reset the storage and then retrieve its
+// value, checking that the reset is indeed performed in testing mode. It
+/// should be performed first, as other tests will depend on it.
+fn test_set_reset_get<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let u64_1 = 10;
+    storage.set(U64_KEY, u64_1).unwrap();
+    storage.reset_and_clear().unwrap();
+    assert_eq!(
+        storage.get::<u64>(U64_KEY).unwrap_err(),
+        Error::KeyNotSet(U64_KEY.to_string())
+    );
+}
+
+/// This test tries to get and set non-existent keys in storage and asserts that the correct
+/// errors are returned on these operations.
+fn test_get_non_existent<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    assert_eq!(
+        storage.get::<Ed25519PrivateKey>(CRYPTO_KEY).unwrap_err(),
+        Error::KeyNotSet(CRYPTO_KEY.to_string())
+    );
+    assert_eq!(
+        storage.get::<u64>(U64_KEY).unwrap_err(),
+        Error::KeyNotSet(U64_KEY.to_string())
+    );
+}
+
+/// This test tries to get a batch of keys that includes a non-existent key, and asserts that the
+/// batch fails as a get of that key would.
+fn test_get_batch_non_existent<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    storage.set(U64_KEY, 10).unwrap();
+    assert_eq!(
+        storage.get_batch(&[U64_KEY, CRYPTO_KEY]).unwrap_err(),
+        Error::KeyNotSet(CRYPTO_KEY.to_string())
+    );
+    assert!(storage.get_batch(&[]).unwrap().is_empty());
+}
+
+/// This test tries to get previous versions of the public key after multiple rotations have
+/// occurred. It also checks that the previous versions returned can be used to fetch the correct
+/// private keys.
+fn test_get_public_key_previous_version<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let num_rotations = 10;
+
+    let mut public_key = storage.create_key(CRYPTO_NAME).unwrap();
+    let mut private_key = storage.export_private_key(CRYPTO_NAME).unwrap();
+
+    // Verify no previous version exists
+    assert!(storage
+        .get_public_key_previous_version(CRYPTO_NAME)
+        .is_err());
+
+    for _ in 0..num_rotations {
+        let new_public_key = storage.rotate_key(CRYPTO_NAME).unwrap();
+        let new_private_key = storage.export_private_key(CRYPTO_NAME).unwrap();
+
+        // Verify the correct previous public key is returned
+        let public_key_previous_version = storage
+            .get_public_key_previous_version(CRYPTO_NAME)
+            .unwrap();
+        assert_eq!(public_key, public_key_previous_version);
+        assert_eq!(private_key.public_key(), public_key_previous_version);
+
+        // Verify the previous public key can be used to fetch the corresponding public key
+        let private_key_previous_version = storage
+            .export_private_key_for_version(CRYPTO_NAME, public_key_previous_version)
+            .unwrap();
+        assert_eq!(private_key, private_key_previous_version);
+
+        public_key = new_public_key;
+        private_key = new_private_key;
+    }
+}
+
+/// This test stores various key/value pairs in storage, updates them, retrieves the values to
+/// ensure the correct value types are returned.
+fn test_get_set<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let crypto_private_1 = Ed25519PrivateKey::generate_for_testing();
+    let crypto_private_2 = Ed25519PrivateKey::generate_for_testing();
+    let u64_1 = 10;
+    let u64_2 = 647;
+
+    storage.set(U64_KEY, u64_1).unwrap();
+    storage.set(CRYPTO_KEY, crypto_private_1.clone()).unwrap();
+
+    assert_eq!(storage.get::<u64>(U64_KEY).unwrap().value, u64_1);
+    assert_eq!(
+        storage.get::<Ed25519PrivateKey>(CRYPTO_KEY).unwrap().value,
+        crypto_private_1
+    );
+
+    storage.set(U64_KEY, u64_2).unwrap();
+    storage.set(CRYPTO_KEY, crypto_private_2.clone()).unwrap();
+
+    assert_eq!(storage.get::<u64>(U64_KEY).unwrap().value, u64_2);
+    assert_eq!(
+        storage.get::<Ed25519PrivateKey>(CRYPTO_KEY).unwrap().value,
+        crypto_private_2
+    );
+}
+
+/// This test stores and updates key/value pairs in batches, and checks that batches and single
+/// key operations observe the same values.
+fn test_get_set_batch<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let crypto_private = Ed25519PrivateKey::generate_for_testing();
+    let u64_1 = 10;
+    let u64_2 = 647;
+
+    storage
+        .set_batch(&[
+            (U64_KEY, serde_json::to_value(u64_1).unwrap()),
+            (CRYPTO_KEY, serde_json::to_value(&crypto_private).unwrap()),
+        ])
+        .unwrap();
+    assert_eq!(storage.get::<u64>(U64_KEY).unwrap().value, u64_1);
+    assert_eq!(
+        storage.get::<Ed25519PrivateKey>(CRYPTO_KEY).unwrap().value,
+        crypto_private
+    );
+
+    storage.set(U64_KEY, u64_2).unwrap();
+    let values = storage.get_batch(&[CRYPTO_KEY, U64_KEY]).unwrap();
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[U64_KEY].value, serde_json::to_value(u64_2).unwrap());
+    let crypto_response: GetResponse<Ed25519PrivateKey> = GetResponse::new_with_version(
+        serde_json::from_value(values[CRYPTO_KEY].value.clone()).unwrap(),
+        values[CRYPTO_KEY].last_update,
+        values[CRYPTO_KEY].version,
+    );
+    assert_eq!(crypto_response, storage.get(CRYPTO_KEY).unwrap());
+}
+
+/// This test sets keys under nested names and checks that listing returns, in order, exactly the
+/// keys that start with the given prefix, byte for byte.
+fn test_list_keys<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    storage.set("list/b/c", 2).unwrap();
+    storage.set("list/a", 1).unwrap();
+    storage.set("listed", 3).unwrap();
+    storage.set("LIST/d", 5).unwrap();
+    storage.set(U64_KEY, 4).unwrap();
+
+    assert_eq!(
+        storage.list_keys(Some("list/")).unwrap(),
+        vec!["list/a", "list/b/c"]
+    );
+    assert_eq!(
+        storage.list_keys(Some("list")).unwrap(),
+        vec!["list/a", "list/b/c", "listed"]
+    );
+    assert_eq!(
+        storage.list_keys(Some("list/b/")).unwrap(),
+        vec!["list/b/c"]
+    );
+    assert_eq!(storage.list_keys(Some("lis")).unwrap().len(), 3);
+    assert_eq!(storage.list_keys(Some("LIST")).unwrap(), vec!["LIST/d"]);
+    assert!(storage.list_keys(Some("missing/")).unwrap().is_empty());
+    assert_eq!(
+        storage.list_keys(None).unwrap(),
+        vec!["LIST/d", U64_KEY, "list/a", "list/b/c", "listed"]
+    );
+}
+
+/// This test checks that a deleted key is no longer set, and that deleting a key that is not set
+/// succeeds, whether it was never set or has already been deleted.
+fn test_delete<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    storage.set(U64_KEY, 5).unwrap();
+    storage.delete(U64_KEY).unwrap();
+    assert_eq!(
+        storage.get::<u64>(U64_KEY).unwrap_err(),
+        Error::KeyNotSet(U64_KEY.to_string())
+    );
+    storage.delete(U64_KEY).unwrap();
+    storage.delete(CRYPTO_NAME).unwrap();
+
+    // A deleted key can be set again
+    storage.set(U64_KEY, 5).unwrap();
+    assert_eq!(storage.get::<u64>(U64_KEY).unwrap().value, 5u64);
+}
+
+/// Checks that conditional writes only succeed at the current version of the key, which is 0 until
+/// the key is set. This is not part of `run_all` as not all backends track versions.
+pub fn test_set_if_version<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    storage.reset_and_clear().unwrap();
+    assert_eq!(
+        storage.set_if_version(U64_KEY, 1, 1).unwrap_err(),
+        Error::VersionMismatch {
+            expected: 1,
+            actual: 0
+        }
+    );
+    storage.set_if_version(U64_KEY, 1, 0).unwrap();
+    let response = storage.get::<u64>(U64_KEY).unwrap();
+    assert_eq!(response.value, 1);
+    assert_eq!(response.version, 1);
+
+    // A writer holding a stale version cannot overwrite a newer value
+    storage.set(U64_KEY, 2).unwrap();
+    assert_eq!(
+        storage.set_if_version(U64_KEY, 3, 1).unwrap_err(),
+        Error::VersionMismatch {
+            expected: 1,
+            actual: 2
+        }
+    );
+    assert_eq!(storage.get::<u64>(U64_KEY).unwrap().value, 2);
+    storage.set_if_version(U64_KEY, 3, 2).unwrap();
+    let response = storage.get::<u64>(U64_KEY).unwrap();
+    assert_eq!(response.value, 3);
+    assert_eq!(response.version, 3);
+    storage.reset_and_clear().unwrap();
+}
+
+/// Checks that a key set again after a delete continues from the version it had, so that a writer
+/// holding a version from before the delete cannot overwrite the new value. This is not part of
+/// `run_all` as not all backends keep the versions of deleted keys.
+pub fn test_delete_keeps_version<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    storage.reset_and_clear().unwrap();
+    storage.set(U64_KEY, 1).unwrap();
+    storage.set(U64_KEY, 2).unwrap();
+    storage.delete(U64_KEY).unwrap();
+    assert!(storage.get::<u64>(U64_KEY).is_err());
+    assert!(storage.list_keys(None).unwrap().is_empty());
+
+    // A deleted key is not set, so only a write that requires as much succeeds
+    assert_eq!(
+        storage.set_if_version(U64_KEY, 3, 2).unwrap_err(),
+        Error::VersionMismatch {
+            expected: 2,
+            actual: 0
+        }
+    );
+    storage.set_if_version(U64_KEY, 3, 0).unwrap();
+    assert_eq!(storage.get::<u64>(U64_KEY).unwrap().version, 3);
+    storage.set(U64_KEY, 4).unwrap();
+    storage.delete(U64_KEY).unwrap();
+    storage.set(U64_KEY, 5).unwrap();
+
+    // The key is set again as many times as before, but a stale writer still fails
+    assert_eq!(
+        storage.set_if_version(U64_KEY, 6, 3).unwrap_err(),
+        Error::VersionMismatch {
+            expected: 3,
+            actual: 5
+        }
+    );
+    storage.reset_and_clear().unwrap();
+}
+
+/// Generates a non-exportable key, and checks that it signs across rotations but that none of its
+/// versions can be exported. This is not part of `run_all` as not all backends support
+/// non-exportable keys.
+pub fn test_non_exportable_key<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    storage.reset_and_clear().unwrap();
+    let public_key = storage.generate_key(CRYPTO_NAME).unwrap();
+    assert_eq!(
+        storage.get_public_key(CRYPTO_NAME).unwrap().public_key,
+        public_key
+    );
+
+    let message = TestAptosCrypto("Hello, World".to_string());
+    let signature = storage.sign(CRYPTO_NAME, &message).unwrap();
+    assert!(signature.verify(&message, &public_key).is_ok());
+    assert!(matches!(
+        storage.export_private_key(CRYPTO_NAME),
+        Err(Error::KeyNotExportable(_))
+    ));
+    assert!(matches!(
+        storage.export_private_key_for_version(CRYPTO_NAME, public_key.clone()),
+        Err(Error::KeyNotExportable(_))
+    ));
+
+    // The previous version still signs after a rotation, and neither version is exportable
+    let new_public_key = storage.rotate_key(CRYPTO_NAME).unwrap();
+    assert_eq!(
+        storage
+            .sign_using_version(CRYPTO_NAME, public_key.clone(), &message)
+            .unwrap(),
+        signature
+    );
+    assert!(matches!(
+        storage.export_private_key_for_version(CRYPTO_NAME, public_key),
+        Err(Error::KeyNotExportable(_))
+    ));
+    assert!(matches!(
+        storage.export_private_key_for_version(CRYPTO_NAME, new_public_key),
+        Err(Error::KeyNotExportable(_))
+    ));
+
+    // Keys created otherwise remain exportable
+    storage.create_key(CRYPTO_KEY).unwrap();
+    storage.export_private_key(CRYPTO_KEY).unwrap();
+    storage.reset_and_clear().unwrap();
+}
+
+/// Rotates a key five times, prunes its history to two versions and checks that only those can
+/// still be used. The storage must retain at least six versions of each key, and record pruned
+/// versions. This is not part of `run_all` as not all backends retain as many versions.
+pub fn test_prune_key_versions<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    storage.reset_and_clear().unwrap();
+    let mut versions = vec![storage.create_key(CRYPTO_NAME).unwrap()];
+    for _ in 0..5 {
+        versions.push(storage.rotate_key(CRYPTO_NAME).unwrap());
+    }
+    let message = TestAptosCrypto("Hello, World".to_string());
+    for version in &versions {
+        let signature = storage
+            .sign_using_version(CRYPTO_NAME, version.clone(), &message)
+            .unwrap();
+        assert!(signature.verify(&message, version).is_ok());
+    }
+
+    // Versions at least as recent as the oldest version in use are never pruned
+    storage
+        .prune_key_versions(CRYPTO_NAME, 2, Some(versions[2].clone()))
+        .unwrap();
+    for version in &versions[2..] {
+        storage
+            .export_private_key_for_version(CRYPTO_NAME, version.clone())
+            .unwrap();
+    }
+
+    storage.prune_key_versions(CRYPTO_NAME, 2, None).unwrap();
+    for version in &versions[..4] {
+        assert!(matches!(
+            storage.sign_using_version(CRYPTO_NAME, version.clone(), &message),
+            Err(Error::KeyVersionPruned(_, _))
+        ));
+        assert!(matches!(
+            storage.export_private_key_for_version(CRYPTO_NAME, version.clone()),
+            Err(Error::KeyVersionPruned(_, _))
+        ));
+    }
+    for version in &versions[4..] {
+        storage
+            .sign_using_version(CRYPTO_NAME, version.clone(), &message)
+            .unwrap();
+    }
+    assert_eq!(
+        storage.get_public_key(CRYPTO_NAME).unwrap().public_key,
+        versions[5]
+    );
+    assert_eq!(
+        storage
+            .get_public_key_previous_version(CRYPTO_NAME)
+            .unwrap(),
+        versions[4]
+    );
+
+    // A pruned version cannot be the oldest version in use, and unknown versions are not pruned
+    assert!(matches!(
+        storage.prune_key_versions(CRYPTO_NAME, 1, Some(versions[0].clone())),
+        Err(Error::KeyVersionPruned(_, _))
+    ));
+    let unknown_version = Ed25519PrivateKey::generate_for_testing().public_key();
+    assert!(matches!(
+        storage.export_private_key_for_version(CRYPTO_NAME, unknown_version),
+        Err(Error::KeyVersionNotFound(_, _))
+    ));
+
+    // Rotations after pruning retain the remaining versions
+    let new_version = storage.rotate_key(CRYPTO_NAME).unwrap();
+    for version in [&versions[4], &versions[5], &new_version] {
+        storage
+            .sign_using_version(CRYPTO_NAME, version.clone(), &message)
+            .unwrap();
+    }
+    storage.reset_and_clear().unwrap();
+}
+
+/// This test creates, imports, exports and signs with keys of the scheme of `K`, through the
+/// interfaces that are generic over the scheme. The storage must support the scheme.
+pub fn test_key_scheme<K: SchemeSigningKey, S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let message = TestAptosCrypto("Hello, World".to_string());
+
+    let public_key = storage.create_key_as::<K>(CRYPTO_NAME).unwrap();
+    let private_key = storage.export_private_key_as::<K>(CRYPTO_NAME).unwrap();
+    assert!(private_key.public_key() == public_key);
+    assert!(storage.get_public_key_as::<K>(CRYPTO_NAME).unwrap() == public_key);
+    assert_eq!(
+        storage.sign_as::<K, _>(CRYPTO_NAME, &message).unwrap(),
+        private_key.sign(&message)
+    );
+
+    let imported_key = K::generate_for_testing();
+    let imported_bytes = imported_key.to_bytes();
+    let imported_public_key = imported_key.public_key();
+    let imported_signature = imported_key.sign(&message);
+    storage
+        .import_private_key_as(CRYPTO_KEY, imported_key)
+        .unwrap();
+    assert_eq!(
+        storage
+            .export_private_key_as::<K>(CRYPTO_KEY)
+            .unwrap()
+            .to_bytes(),
+        imported_bytes
+    );
+    assert!(storage.get_public_key_as::<K>(CRYPTO_KEY).unwrap() == imported_public_key);
+    assert_eq!(
+        storage.sign_as::<K, _>(CRYPTO_KEY, &message).unwrap(),
+        imported_signature
+    );
+}
+
+/// This test checks that Ed25519 keys are the same through the Ed25519 interfaces and those
+/// generic over the scheme.
+fn test_key_scheme_ed25519<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    test_key_scheme::<Ed25519PrivateKey, _>(storage);
+
+    let public_key = storage.get_public_key(CRYPTO_NAME).unwrap().public_key;
+    assert_eq!(
+        storage
+            .get_public_key_as::<Ed25519PrivateKey>(CRYPTO_NAME)
+            .unwrap(),
+        public_key
+    );
+    let public_key = storage.create_key("key").unwrap();
+    let message = TestAptosCrypto("Hello, World".to_string());
+    assert_eq!(
+        storage
+            .sign_as::<Ed25519PrivateKey, _>("key", &message)
+            .unwrap(),
+        storage.sign("key", &message).unwrap()
+    );
+    assert_eq!(
+        storage
+            .export_private_key_as::<Ed25519PrivateKey>("key")
+            .unwrap()
+            .public_key(),
+        public_key
+    );
+}
+
+/// This test checks that a storage that records the scheme of its keys refuses to use a key of
+/// another scheme as an Ed25519 key.
+pub fn test_key_scheme_mismatch<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let stored_key = serde_json::json!({
+        "scheme": "bls12381",
+        "key": base64::encode(Ed25519PrivateKey::generate_for_testing().to_bytes()),
+    });
+    storage.set(CRYPTO_NAME, stored_key).unwrap();
+
+    let message = TestAptosCrypto("Hello, World".to_string());
+    assert!(matches!(
+        storage.export_private_key(CRYPTO_NAME),
+        Err(Error::KeySchemeMismatch(_, expected, found)) if expected == "ed25519" && found == "bls12381"
+    ));
+    assert!(matches!(
+        storage.get_public_key(CRYPTO_NAME),
+        Err(Error::KeySchemeMismatch(_, _, _))
+    ));
+    assert!(matches!(
+        storage.sign(CRYPTO_NAME, &message),
+        Err(Error::KeySchemeMismatch(_, _, _))
+    ));
+    assert!(matches!(
+        storage.sign_as::<Ed25519PrivateKey, _>(CRYPTO_NAME, &message),
+        Err(Error::KeySchemeMismatch(_, _, _))
+    ));
+    storage.reset_and_clear().unwrap();
+}
+
+/// Lists keys of two storages that share a backend under different namespaces, and checks that
+/// each only observes its own keys. Both storages must be empty.
+pub fn test_list_keys_across_namespaces<S: KVStorage>(storage_1: &mut S, storage_2: &mut S) {
+    storage_1.set("shared/a", 1).unwrap();
+    storage_1.set("own_1", 1).unwrap();
+    storage_2.set("shared/b", 2).unwrap();
+
+    assert_eq!(
+        storage_1.list_keys(Some("shared/")).unwrap(),
+        vec!["shared/a"]
+    );
+    assert_eq!(
+        storage_2.list_keys(Some("shared/")).unwrap(),
+        vec!["shared/b"]
+    );
+    assert_eq!(
+        storage_1.list_keys(None).unwrap(),
+        vec!["own_1", "shared/a"]
+    );
+    assert_eq!(storage_2.list_keys(None).unwrap(), vec!["shared/b"]);
+}
+
+/// Sets a key under two namespaces of the storage, and checks that each namespace only observes
+/// its own value, and that the key itself remains unset.
+fn test_namespaces<S: KVStorage>(storage: &mut S) {
+    Namespaced::new("namespace_1", &mut *storage)
+        .set(U64_KEY, 1)
+        .unwrap();
+    Namespaced::new("namespace_2", &mut *storage)
+        .set(U64_KEY, 2)
+        .unwrap();
+    let get =
+        |storage: &mut S, namespace: &str| Namespaced::new(namespace, storage).get::<u64>(U64_KEY);
+    assert_eq!(get(storage, "namespace_1").unwrap().value, 1);
+    assert_eq!(get(storage, "namespace_2").unwrap().value, 2);
+    assert_eq!(
+        storage.get::<u64>(U64_KEY).unwrap_err(),
+        Error::KeyNotSet(U64_KEY.to_string())
+    );
+
+    Namespaced::new("namespace_1", &mut *storage)
+        .delete(U64_KEY)
+        .unwrap();
+    assert!(matches!(
+        get(storage, "namespace_1"),
+        Err(Error::KeyNotSet(_))
+    ));
+    assert_eq!(get(storage, "namespace_2").unwrap().value, 2);
+}
+
+/// Watches a key with two subscribers, and checks that both observe its creation, update and
+/// deletion, and that dropping one subscriber does not affect the other. Each change is received
+/// before the next write, as storages that poll for changes observe writes within an interval as
+/// one.
+pub fn test_watch<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let timeout = std::time::Duration::from_secs(10);
+    let watch_1 = storage.watch(U64_KEY).unwrap();
+    let watch_2 = storage.watch(U64_KEY).unwrap();
+    assert_eq!(watch_1.key(), U64_KEY);
+
+    storage.set(U64_KEY, 1).unwrap();
+    let metadata = storage.get::<u64>(U64_KEY).unwrap().metadata();
+    for watch in [&watch_1, &watch_2] {
+        let event = watch.recv_timeout(timeout).unwrap();
+        assert_eq!(event.key, U64_KEY);
+        assert_eq!(event.metadata, Some(metadata));
+    }
+
+    // A dropped subscriber no longer receives events, and the others are unaffected
+    drop(watch_1);
+    storage.set(U64_KEY, 2).unwrap();
+    let metadata = storage.get::<u64>(U64_KEY).unwrap().metadata();
+    assert_eq!(
+        watch_2.recv_timeout(timeout).unwrap().metadata,
+        Some(metadata)
+    );
+
+    storage.delete(U64_KEY).unwrap();
+    assert_eq!(watch_2.recv_timeout(timeout).unwrap().metadata, None);
+    assert!(watch_2.try_recv().is_err());
+}
+
+/// This test verifies that importing a key if absent never overwrites an existing key.
+fn test_import_key_if_absent<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let key = Ed25519PrivateKey::generate_for_testing();
+    let other_key = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng);
+
+    assert_eq!(
+        storage
+            .import_private_key_if_absent(CRYPTO_NAME, key.clone())
+            .unwrap(),
+        ImportResult::Fresh
+    );
+    assert_eq!(
+        storage
+            .import_private_key_if_absent(CRYPTO_NAME, other_key)
+            .unwrap(),
+        ImportResult::AlreadyExisted
+    );
+    assert_eq!(
+        storage.get_public_key(CRYPTO_NAME).unwrap().public_key,
+        key.public_key()
+    );
+
+    // Keys created by the backend are kept as well
+    storage.create_key(CRYPTO_KEY).unwrap();
+    assert_eq!(
+        storage
+            .import_private_key_if_absent(CRYPTO_KEY, key)
+            .unwrap(),
+        ImportResult::AlreadyExisted
+    );
+}
+
+/// This test ensures that a key can reasonably be imported.
+fn test_import_key<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let key_name = "key";
+    let imported_key_name = "imported_key";
+
+    // Prepare key
+
+    storage.create_key(key_name).unwrap();
+    let key = storage.export_private_key(key_name).unwrap();
+    let public_key = storage.get_public_key(key_name).unwrap().public_key;
+
+    // Restore and verify key
+
+    storage
+        .import_private_key(imported_key_name, key.clone())
+        .unwrap();
+    let imported_key = storage.export_private_key(imported_key_name).unwrap();
+    let imported_public_key = storage
+        .get_public_key(imported_key_name)
+        .unwrap()
+        .public_key;
+
+    assert_eq!(key, imported_key);
+    assert_eq!(public_key, imported_public_key);
+
+    // Verify valid keys
+
+    let message = TestAptosCrypto("Hello, World".to_string());
+    let message_signature = storage.sign(imported_key_name, &message).unwrap();
+    message_signature
+        .verify(&message, &imported_public_key)
+        .unwrap();
+
+    // Ensure rotation still works
+
+    storage.rotate_key(imported_key_name).unwrap();
+    let rotated_imported_key = storage.export_private_key(imported_key_name).unwrap();
+    let rotated_imported_public_key = storage
+        .get_public_key(imported_key_name)
+        .unwrap()
+        .public_key;
+
+    let rotated_message_signature = storage.sign(imported_key_name, &message).unwrap();
+    rotated_message_signature
+        .verify(&message, &rotated_imported_public_key)
+        .unwrap();
+
+    assert_ne!(imported_key, rotated_imported_key);
+    assert_ne!(imported_public_key, rotated_imported_public_key);
+    assert_ne!(message_signature, rotated_message_signature);
+}
+
+/// This test stores different types of values into storage, retrieves them, and asserts
+/// that the value unwrap functions return an unexpected type error on an incorrect unwrap.
+fn test_verify_incorrect_value_types<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    storage.set(U64_KEY, 10).unwrap();
+    storage
+        .set(CRYPTO_KEY, Ed25519PrivateKey::generate_for_testing())
+        .unwrap();
+
+    storage.get::<Ed25519PrivateKey>(U64_KEY).unwrap_err();
+    storage.get::<u64>(CRYPTO_KEY).unwrap_err();
+}
+
+/// This test: (i) creates a new named test key pair; (ii) retrieves the public key for
+/// the created key pair; (iii) compares the public keys returned by the create call and the
+/// retrieval call.
+fn test_create_get_key_pair<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let public_key = storage.create_key(CRYPTO_NAME).unwrap();
+    let retrieved_public_key_response = storage.get_public_key(CRYPTO_NAME).unwrap();
+    assert_eq!(public_key, retrieved_public_key_response.public_key);
+}
+
+/// This test tries to get the public key of a key pair that has not yet been created. As
+/// such, it asserts that this attempt fails.
+fn test_get_uncreated_key_pair<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let key_pair_name = "Non-existent Key";
+    assert!(
+        storage.get_public_key(key_pair_name).is_err(),
+        "Accessing a key that has not yet been created should have failed!"
+    );
+}
+
+/// Verify HashValues work correctly
+fn test_hash_value<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let hash_value_key = "HashValue";
+    let hash_value_value = HashValue::random();
+
+    storage.set(hash_value_key, hash_value_value).unwrap();
+    let out_value = storage.get(hash_value_key).unwrap().value;
+    assert_eq!(hash_value_value, out_value);
+}
+
+/// This test verifies the storage engine is up and running.
+fn test_ensure_storage_is_available<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    storage.available().unwrap();
+}
+
+/// This test creates a new named key pair and attempts to get a non-existent version of the public
+/// and private keys. As such, these calls should fail.
+fn test_create_and_get_non_existent_version<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    // Create new named key pair
+    let _ = storage.create_key(CRYPTO_NAME).unwrap();
+
+    // Get a non-existent version of the new key pair and verify failure
+    let non_existent_public_key = Ed25519PrivateKey::generate_for_testing().public_key();
+    assert!(
+        storage.export_private_key_for_version(CRYPTO_NAME, non_existent_public_key).is_err(),
+        "We have tried to retrieve a non-existent private key version -- the call should have failed!",
+    );
+}
+
+/// This test creates a new key pair and performs multiple key rotations, ensuring that
+/// storage updates key pair versions appropriately.
+fn test_create_key_pair_and_perform_rotations<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let num_rotations = 10;
+
+    let mut public_key = storage.create_key(CRYPTO_NAME).unwrap();
+    let mut private_key = storage.export_private_key(CRYPTO_NAME).unwrap();
+
+    for _ in 0..num_rotations {
+        let new_public_key = storage.rotate_key(CRYPTO_NAME).unwrap();
+        let new_private_key = storage.export_private_key(CRYPTO_NAME).unwrap();
+
+        let exported_key = storage
+            .export_private_key_for_version(CRYPTO_NAME, public_key)
+            .unwrap();
+        assert_eq!(exported_key, private_key);
+        assert_eq!(new_public_key, new_private_key.public_key());
+
+        public_key = new_public_key;
+        private_key = new_private_key;
+    }
+}
+
+/// This test creates a new key pair, signs a message using the key pair, rotates the key pair,
+/// re-signs the message using the previous key pair version, and asserts the same signature is
+/// produced.
+fn test_create_sign_rotate_sign<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    // Generate new key pair
+    let public_key = storage.create_key(CRYPTO_NAME).unwrap();
+
+    // Create then sign message and verify correct signature
+    let message = TestAptosCrypto("Hello, World".to_string());
+    let message_signature = storage.sign(CRYPTO_NAME, &message).unwrap();
+    assert!(message_signature.verify(&message, &public_key).is_ok());
+
+    // Rotate the key pair and sign the message again using the previous key pair version
+    let _ = storage.rotate_key(CRYPTO_NAME).unwrap();
+    let message_signature_previous = storage
+        .sign_using_version(CRYPTO_NAME, public_key, &message)
+        .unwrap();
+
+    // Verify signatures match and are valid
+    assert_eq!(message_signature, message_signature_previous);
+}
+
+/// This test signs a batch of messages using the previous version of a rotated key pair, and
+/// asserts that the signatures are those produced by signing each message in turn.
+fn test_sign_batch_using_version<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let public_key = storage.create_key(CRYPTO_NAME).unwrap();
+    storage.rotate_key(CRYPTO_NAME).unwrap();
+
+    let messages: Vec<_> = ["Hello", "World", "Hello"]
+        .iter()
+        .map(|message| TestAptosCrypto(message.to_string()))
+        .collect();
+    let signatures = storage
+        .sign_batch_using_version(CRYPTO_NAME, public_key.clone(), &messages)
+        .unwrap();
+    assert_eq!(signatures.len(), messages.len());
+    for (message, signature) in messages.iter().zip(signatures) {
+        let expected = storage
+            .sign_using_version(CRYPTO_NAME, public_key.clone(), message)
+            .unwrap();
+        assert_eq!(signature, expected);
+        assert!(signature.verify(message, &public_key).is_ok());
+    }
+
+    assert!(storage
+        .sign_batch_using_version::<TestAptosCrypto>(CRYPTO_NAME, public_key, &[])
+        .unwrap()
+        .is_empty());
+}
+
+/// This test verifies that timestamps and versions increase with successive writes, and that
+/// reads do not change them.
+fn test_incremental_timestamp<S: KVStorage + CryptoStorage>(storage: &mut S) {
+    let key = "timestamp_u64";
+    let value0 = 442;
+    let value1 = 450;
+
+    storage.set(key, value0).unwrap();
+    let first = storage.get::<u64>(key).unwrap();
+    assert!(first.version > 0);
+    assert_eq!(
+        storage.get::<u64>(key).unwrap().metadata(),
+        first.metadata()
+    );
+    std::thread::sleep(std::time::Duration::from_secs(1));
+    storage.set(key, value1).unwrap();
+    let second = storage.get::<u64>(key).unwrap();
+
+    assert_ne!(first.value, second.value);
+    assert!(first.last_update < second.last_update);
+    assert!(first.version < second.version);
+}
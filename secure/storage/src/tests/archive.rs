@@ -0,0 +1,140 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    export_namespace, import_namespace, ArchiveReport, CryptoStorage, Error, InMemoryStorage,
+    KVStorage, Passphrase, Storage,
+};
+use aptos_temppath::TempPath;
+use serde_json::Value;
+use std::fs;
+
+const KEY_NAMES: &[&str] = &["consensus", "generated"];
+
+fn passphrase_file(passphrase: &str) -> (TempPath, Passphrase) {
+    let path = TempPath::new();
+    fs::write(path.path(), passphrase).unwrap();
+    let passphrase = Passphrase::FromDisk(path.path().to_path_buf());
+    (path, passphrase)
+}
+
+fn source_storage() -> Storage {
+    let mut storage = Storage::from(InMemoryStorage::new());
+    storage.set("safety/waypoint", "0:abcd").unwrap();
+    storage.create_key("safety/consensus").unwrap();
+    storage.rotate_key("safety/consensus").unwrap();
+    storage.generate_key("safety/generated").unwrap();
+    storage.set("other/value", 1).unwrap();
+    storage
+}
+
+#[test]
+fn archive_round_trip() {
+    let (_path, passphrase) = passphrase_file("correct horse battery staple");
+    let source = source_storage();
+    let archive = export_namespace(&source, Some("safety"), KEY_NAMES, &passphrase).unwrap();
+
+    let mut storage = Storage::from(InMemoryStorage::new());
+    let report = import_namespace(&mut storage, Some("restored"), &archive, &passphrase).unwrap();
+    assert_eq!(
+        report,
+        ArchiveReport {
+            entries: vec!["consensus".into(), "waypoint".into()],
+            skipped: vec!["generated".into()],
+        }
+    );
+
+    // Keys are restored along with their previous version, and other namespaces are left out
+    assert_eq!(
+        storage.get::<String>("restored/waypoint").unwrap().value,
+        "0:abcd"
+    );
+    assert_eq!(
+        storage
+            .get_public_key("restored/consensus")
+            .unwrap()
+            .public_key,
+        source
+            .get_public_key("safety/consensus")
+            .unwrap()
+            .public_key
+    );
+    assert_eq!(
+        storage
+            .get_public_key_previous_version("restored/consensus")
+            .unwrap(),
+        source
+            .get_public_key_previous_version("safety/consensus")
+            .unwrap()
+    );
+    let mut keys = storage.list_keys(None).unwrap();
+    keys.sort();
+    assert_eq!(
+        keys,
+        vec![
+            "restored/consensus",
+            "restored/consensus_previous",
+            "restored/waypoint"
+        ]
+    );
+
+    // Without a namespace, the whole storage is archived
+    let archive = export_namespace(
+        &source,
+        None,
+        &["safety/consensus", "safety/generated"],
+        &passphrase,
+    )
+    .unwrap();
+    let mut storage = Storage::from(InMemoryStorage::new());
+    let report = import_namespace(&mut storage, None, &archive, &passphrase).unwrap();
+    assert_eq!(
+        report.entries,
+        vec!["other/value", "safety/consensus", "safety/waypoint"]
+    );
+    assert_eq!(report.skipped, vec!["safety/generated"]);
+    assert_eq!(storage.get::<u64>("other/value").unwrap().value, 1);
+}
+
+#[test]
+fn archive_tamper_detection() {
+    let (_path, passphrase) = passphrase_file("correct horse battery staple");
+    let archive =
+        export_namespace(&source_storage(), Some("safety"), KEY_NAMES, &passphrase).unwrap();
+    let mut storage = Storage::from(InMemoryStorage::new());
+
+    // Flips a bit of the ciphertext
+    let mut tampered: Value = serde_json::from_slice(&archive).unwrap();
+    let encrypted = base64::decode(tampered["encrypted"].as_str().unwrap()).unwrap();
+    let mut encrypted: Value = serde_json::from_slice(&encrypted).unwrap();
+    let mut ciphertext = base64::decode(encrypted["ciphertext"].as_str().unwrap()).unwrap();
+    ciphertext[0] ^= 1;
+    encrypted["ciphertext"] = base64::encode(&ciphertext).into();
+    tampered["encrypted"] = base64::encode(&serde_json::to_vec(&encrypted).unwrap()).into();
+    let tampered = serde_json::to_vec(&tampered).unwrap();
+    assert!(matches!(
+        import_namespace(&mut storage, None, &tampered, &passphrase),
+        Err(Error::TamperError(_))
+    ));
+
+    // A wrong passphrase is told apart from tampering
+    let (_path, wrong_passphrase) = passphrase_file("wrong");
+    assert!(matches!(
+        import_namespace(&mut storage, None, &archive, &wrong_passphrase),
+        Err(Error::DecryptionError(_))
+    ));
+
+    // Archives of unknown versions are rejected
+    let mut future: Value = serde_json::from_slice(&archive).unwrap();
+    future["version"] = 2.into();
+    let future = serde_json::to_vec(&future).unwrap();
+    assert_eq!(
+        import_namespace(&mut storage, None, &future, &passphrase).unwrap_err(),
+        Error::SerializationError("Unsupported archive version: 2".into())
+    );
+
+    // Nothing was written
+    assert!(storage.list_keys(None).unwrap().is_empty());
+    import_namespace(&mut storage, None, &archive, &passphrase).unwrap();
+    assert!(!storage.list_keys(None).unwrap().is_empty());
+}
@@ -0,0 +1,106 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    storage_conformance_tests, AwsStorage, CryptoStorage, Error, KVStorage, Namespaced, Storage,
+};
+use aptos_aws_client::{mock::MockSecretsManager, Credentials};
+
+/// The default endpoint of localstack, e.g., as run by `docker run -p 4566:4566 localstack/localstack`.
+const LOCALSTACK_ENDPOINT: &str = "http://localhost:4566";
+
+fn create_aws(mock: &MockSecretsManager, prefix: Option<&str>) -> AwsStorage {
+    AwsStorage::new(
+        aptos_aws_client::mock::REGION.into(),
+        prefix.map(str::to_string),
+        Some(mock.endpoint()),
+        Some(mock.credentials()),
+        None,
+        None,
+    )
+}
+
+#[test]
+fn aws() {
+    let mock = MockSecretsManager::start();
+    let mut storage = Storage::from(create_aws(&mock, None));
+    storage_conformance_tests::run_all(&mut storage);
+}
+
+/// Storages with distinct prefixes share an account without seeing each other's secrets.
+#[test]
+fn aws_prefixes() {
+    let mock = MockSecretsManager::start();
+    let mut storage_1 = Storage::from(create_aws(&mock, Some("validator_1/")));
+    let mut storage_2 = Storage::from(create_aws(&mock, Some("validator_2/")));
+    storage_conformance_tests::run_all(&mut storage_1);
+
+    storage_1.set("key", 1).unwrap();
+    storage_2.set("key", 2).unwrap();
+    storage_2.set("other", 2).unwrap();
+    assert_eq!(storage_1.get::<u64>("key").unwrap().value, 1);
+    assert_eq!(storage_1.list_keys(None).unwrap(), vec!["key"]);
+    storage_1.reset_and_clear().unwrap();
+    assert_eq!(storage_2.list_keys(None).unwrap(), vec!["key", "other"]);
+}
+
+#[test]
+fn aws_list_keys_across_namespaces() {
+    let mock = MockSecretsManager::start();
+    let namespaced = |namespace| {
+        let inner = Box::new(Storage::from(create_aws(&mock, None)));
+        Storage::from(Namespaced::new(namespace, inner))
+    };
+    let mut storage_1 = namespaced("namespace_1");
+    let mut storage_2 = namespaced("namespace_2");
+    storage_conformance_tests::test_list_keys_across_namespaces(&mut storage_1, &mut storage_2);
+}
+
+#[test]
+fn aws_create_existing_key() {
+    let mock = MockSecretsManager::start();
+    let mut storage = create_aws(&mock, Some("prefix/"));
+    storage.create_key("key").unwrap();
+    assert_eq!(
+        storage.create_key("key").unwrap_err(),
+        Error::KeyAlreadyExists("prefix/key".into())
+    );
+}
+
+#[test]
+fn aws_invalid_credentials() {
+    let mock = MockSecretsManager::start();
+    let credentials = Credentials::new("mock_access_key".into(), "wrong".into(), None);
+    let storage = AwsStorage::new(
+        aptos_aws_client::mock::REGION.into(),
+        None,
+        Some(mock.endpoint()),
+        Some(credentials),
+        None,
+        None,
+    );
+    // Unlike an expired token, a wrong secret key is not reported as PermissionDenied
+    assert!(matches!(
+        storage.available().unwrap_err(),
+        Error::AccessDenied(_)
+    ));
+    assert!(matches!(
+        storage.get::<u64>("key").unwrap_err(),
+        Error::AccessDenied(_)
+    ));
+}
+
+// This test requires localstack and must be run via: `cargo xtest -- --ignored`
+#[ignore]
+#[test]
+fn aws_localstack() {
+    let mut storage = Storage::from(AwsStorage::new(
+        "us-east-1".into(),
+        Some("aptos_secure_storage_test/".into()),
+        Some(LOCALSTACK_ENDPOINT.into()),
+        Some(Credentials::new("test".into(), "test".into(), None)),
+        None,
+        None,
+    ));
+    storage_conformance_tests::run_all(&mut storage);
+}
@@ -0,0 +1,142 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    storage_conformance_tests, CachedStorage, ChaosPolicy, ChaosStorage, CryptoStorage, Error,
+    InMemoryStorage, KVStorage, Storage,
+};
+use aptos_time_service::TimeService;
+use serde_json::Value;
+use std::time::Duration;
+
+const TTL: Duration = Duration::from_secs(60);
+
+fn cached_storage() -> Storage {
+    let inner = Box::new(Storage::from(InMemoryStorage::new()));
+    Storage::from(CachedStorage::new(inner, TTL))
+}
+
+#[test]
+fn cached() {
+    storage_conformance_tests::run_all(&mut cached_storage());
+}
+
+#[test]
+fn cached_set_if_version() {
+    storage_conformance_tests::test_set_if_version(&mut cached_storage());
+}
+
+#[test]
+fn cached_non_exportable_key() {
+    storage_conformance_tests::test_non_exportable_key(&mut cached_storage());
+}
+
+#[test]
+fn cached_read_through() {
+    let mut storage = CachedStorage::new(
+        ChaosStorage::new(InMemoryStorage::new(), ChaosPolicy::default()),
+        TTL,
+    );
+    storage.set("key", 1).unwrap();
+    assert_eq!(storage.bypass().operations(), 1);
+
+    // Only the first read reaches the storage, and cached reads keep its metadata
+    let response = storage.get::<u64>("key").unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap(), response);
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+    assert_eq!(storage.bypass().operations(), 2);
+    assert_eq!((storage.hits(), storage.misses()), (2, 1));
+
+    // Missing keys are not cached
+    assert_eq!(
+        storage.get::<u64>("missing").unwrap_err(),
+        Error::KeyNotSet("missing".into())
+    );
+    storage.get::<u64>("missing").unwrap_err();
+    assert_eq!(storage.misses(), 3);
+
+    // Batches only read missing keys
+    storage.set("other", 2).unwrap();
+    let responses = storage.get_batch(&["key", "other"]).unwrap();
+    assert_eq!(responses["key"].value, Value::from(1));
+    assert_eq!(responses["other"].value, Value::from(2));
+    assert_eq!(storage.bypass().operations(), 6);
+    assert_eq!((storage.hits(), storage.misses()), (3, 4));
+    storage.get::<u64>("other").unwrap();
+    assert_eq!(storage.bypass().operations(), 6);
+}
+
+#[test]
+fn cached_write_through() {
+    // The sixth operation, i.e., the write of 4, fails
+    let policy = ChaosPolicy::default().fail_every_nth(6).max_failures(1);
+    let mut storage = CachedStorage::new(ChaosStorage::new(InMemoryStorage::new(), policy), TTL);
+    storage.set("key", 1).unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().version, 1);
+
+    // Writes update cached values, which then match the storage
+    storage.set("key", 2).unwrap();
+    let response = storage.get::<u64>("key").unwrap();
+    assert_eq!((response.value, response.version), (2, 2));
+    let response = storage.bypass().get::<u64>("key").unwrap();
+    assert_eq!((response.value, response.version), (2, 2));
+    storage.set_if_version("key", 3, 2).unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().version, 3);
+    assert_eq!(storage.misses(), 1);
+
+    // Failed writes invalidate the key
+    storage.set("key", 4).unwrap_err();
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 3);
+    assert_eq!(storage.misses(), 2);
+
+    storage.delete("key").unwrap();
+    storage.get::<u64>("key").unwrap_err();
+}
+
+#[test]
+fn cached_ttl() {
+    let time_service = TimeService::mock();
+    let mut storage =
+        CachedStorage::new_with_time_service(InMemoryStorage::new(), TTL, time_service.clone());
+    storage.set_ttl("uncached", Duration::ZERO);
+    storage.set("key", 1).unwrap();
+    storage.set("uncached", 1).unwrap();
+
+    storage.get::<u64>("key").unwrap();
+    storage.get::<u64>("key").unwrap();
+    storage.get::<u64>("uncached").unwrap();
+    storage.get::<u64>("uncached").unwrap();
+    assert_eq!((storage.hits(), storage.misses()), (1, 3));
+
+    // Entries expire after their TTL
+    let time_service = time_service.into_mock();
+    time_service.advance(TTL - Duration::from_secs(1));
+    storage.get::<u64>("key").unwrap();
+    assert_eq!(storage.misses(), 3);
+    time_service.advance(Duration::from_secs(1));
+    storage.get::<u64>("key").unwrap();
+    assert_eq!(storage.misses(), 4);
+}
+
+#[test]
+fn cached_invalidate() {
+    let mut storage = CachedStorage::new(InMemoryStorage::new(), TTL);
+    storage.set("key", 1).unwrap();
+    storage.get::<u64>("key").unwrap();
+    storage.invalidate("key");
+    storage.get::<u64>("key").unwrap();
+    storage.invalidate_all();
+    storage.get::<u64>("key").unwrap();
+    assert_eq!((storage.hits(), storage.misses()), (0, 3));
+
+    // Crypto operations invalidate the entries of their key
+    storage.create_key("consensus").unwrap();
+    storage.get::<Value>("consensus").unwrap();
+    storage.rotate_key("consensus").unwrap();
+    let response = storage.get::<Value>("consensus").unwrap();
+    assert_eq!(
+        response,
+        storage.bypass().get::<Value>("consensus").unwrap()
+    );
+    assert_eq!(storage.misses(), 5);
+}
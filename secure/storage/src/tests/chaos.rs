@@ -0,0 +1,142 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    ChaosOperation, ChaosPolicy, ChaosStorage, CryptoStorage, Error, InMemoryStorage, KVStorage,
+};
+use aptos_crypto::test_utils::TestAptosCrypto;
+use std::time::{Duration, Instant};
+
+fn is_injected<T>(result: Result<T, Error>) -> bool {
+    matches!(result, Err(Error::InternalError(error)) if error.starts_with("Injected failure"))
+}
+
+#[test]
+fn chaos_default_policy() {
+    let mut storage = ChaosStorage::new(InMemoryStorage::new(), ChaosPolicy::default());
+    storage.set("key", 1).unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+    storage.create_key("consensus").unwrap();
+    storage.rotate_key("consensus").unwrap();
+    assert_eq!(storage.operations(), 4);
+    assert_eq!(storage.injected_failures(), 0);
+}
+
+#[test]
+fn chaos_fail_every_nth() {
+    let mut storage = ChaosStorage::new(
+        InMemoryStorage::new(),
+        ChaosPolicy::default().fail_every_nth(3),
+    );
+    storage.set("key", 1).unwrap();
+    storage.set("key", 2).unwrap();
+    assert!(is_injected(storage.set("key", 3)));
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 2);
+    storage.available().unwrap();
+    assert!(is_injected(storage.available()));
+    assert_eq!(storage.injected_failures(), 2);
+
+    // Failed writes never reach the wrapped storage
+    assert_eq!(storage.inner().get::<u64>("key").unwrap().version, 2);
+}
+
+#[test]
+fn chaos_fail_keys_matching() {
+    let mut storage = ChaosStorage::new(
+        InMemoryStorage::new(),
+        ChaosPolicy::default()
+            .fail_keys_matching("safety_*", Some(ChaosOperation::Write))
+            .fail_keys_matching("consensus", None),
+    );
+    storage.inner_mut().set("safety_data", 1).unwrap();
+    assert_eq!(storage.get::<u64>("safety_data").unwrap().value, 1);
+    assert!(is_injected(storage.set("safety_data", 2)));
+    assert!(is_injected(
+        storage.set_batch(&[("other", 1.into()), ("safety_backup", 1.into())])
+    ));
+    assert!(matches!(
+        storage.get::<u64>("other"),
+        Err(Error::KeyNotSet(_))
+    ));
+    storage.set("safety", 1).unwrap();
+
+    // Crypto operations are matched by key name
+    assert!(is_injected(storage.create_key("consensus")));
+    storage.create_key("execution").unwrap();
+    storage.inner_mut().create_key("consensus").unwrap();
+    assert!(is_injected(storage.get_public_key("consensus")));
+    let message = TestAptosCrypto("message".to_string());
+    assert!(is_injected(storage.sign("consensus", &message)));
+}
+
+#[test]
+fn chaos_script() {
+    let mut storage = ChaosStorage::new(
+        InMemoryStorage::new(),
+        ChaosPolicy::default().script(vec![
+            Some(Error::PermissionDenied("denied".into())),
+            None,
+            Some(Error::VersionMismatch {
+                expected: 1,
+                actual: 2,
+            }),
+        ]),
+    );
+    assert_eq!(
+        storage.set("key", 1),
+        Err(Error::PermissionDenied("denied".into()))
+    );
+    storage.set("key", 1).unwrap();
+    assert_eq!(
+        storage.get::<u64>("key").map(|response| response.value),
+        Err(Error::VersionMismatch {
+            expected: 1,
+            actual: 2,
+        })
+    );
+
+    // The script is exhausted
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+}
+
+#[test]
+fn chaos_max_failures_and_set_policy() {
+    let mut storage = ChaosStorage::new(
+        InMemoryStorage::new(),
+        ChaosPolicy::default()
+            .fail_keys_matching("*", Some(ChaosOperation::Write))
+            .max_failures(2),
+    );
+    assert!(is_injected(storage.set("key", 1)));
+    assert!(is_injected(storage.set("key", 1)));
+    storage.set("key", 1).unwrap();
+
+    // A new policy resets the count of failures, but not of operations
+    storage.set_policy(
+        ChaosPolicy::default()
+            .fail_keys_matching("*", Some(ChaosOperation::Write))
+            .max_failures(1),
+    );
+    assert_eq!(storage.injected_failures(), 0);
+    assert!(is_injected(storage.delete("key")));
+    storage.delete("key").unwrap();
+    assert_eq!(storage.operations(), 5);
+
+    storage.set_policy(ChaosPolicy::default());
+    storage.set("key", 2).unwrap();
+}
+
+#[test]
+fn chaos_latency() {
+    let latency = Duration::from_millis(20);
+    let mut storage = ChaosStorage::new(
+        InMemoryStorage::new(),
+        ChaosPolicy::default()
+            .latency(latency)
+            .script(vec![Some(Error::PermissionDenied("denied".into()))]),
+    );
+    let start = Instant::now();
+    storage.set("key", 1).unwrap_err();
+    storage.set("key", 1).unwrap();
+    assert!(start.elapsed() >= 2 * latency);
+}
@@ -0,0 +1,110 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    storage_conformance_tests, ChaosPolicy, ChaosStorage, CircuitBreakerStorage, CircuitState,
+    Error, InMemoryStorage, KVStorage, Storage, STORAGE_CIRCUIT_TRANSITIONS,
+};
+use aptos_time_service::TimeService;
+use std::time::Duration;
+
+const COOLDOWN: Duration = Duration::from_secs(10);
+
+fn timed_out() -> Option<Error> {
+    Some(Error::InternalError("Connection timed out".into()))
+}
+
+#[test]
+fn circuit_breaker() {
+    let mut storage = Storage::from(InMemoryStorage::new()).with_circuit_breaker(3, COOLDOWN);
+    assert_eq!(storage.backend(), "in_memory");
+    storage_conformance_tests::run_all(&mut storage);
+}
+
+#[test]
+fn circuit_breaker_transitions() {
+    let time_service = TimeService::mock();
+    let script = vec![timed_out(), timed_out(), None, timed_out(), timed_out()];
+    let mut storage = CircuitBreakerStorage::new_with_time_service(
+        "scripted",
+        ChaosStorage::new(
+            InMemoryStorage::new(),
+            ChaosPolicy::default().script(script),
+        ),
+        3,
+        COOLDOWN,
+        time_service.clone(),
+    );
+    let transitions = |state: &str| {
+        STORAGE_CIRCUIT_TRANSITIONS
+            .with_label_values(&["scripted", state])
+            .get()
+    };
+
+    // Failures must be consecutive to open the circuit
+    storage.set("key", 1).unwrap_err();
+    storage.set("key", 1).unwrap_err();
+    storage.set("key", 1).unwrap();
+    storage.get::<u64>("key").unwrap_err();
+    storage.get::<u64>("key").unwrap_err();
+    assert_eq!(storage.state(), CircuitState::Closed);
+
+    // Errors that show the storage is reachable are not failures
+    storage
+        .inner_mut()
+        .set_policy(ChaosPolicy::default().script(vec![
+            Some(Error::KeyNotSet("key".into())),
+            timed_out(),
+            timed_out(),
+        ]));
+    storage.get::<u64>("key").unwrap_err();
+    storage.get::<u64>("key").unwrap_err();
+    storage.get::<u64>("key").unwrap_err();
+    assert_eq!(storage.state(), CircuitState::Closed);
+    storage.get::<u64>("key").unwrap();
+
+    // Once open, operations fail fast without reaching the storage
+    storage
+        .inner_mut()
+        .set_policy(ChaosPolicy::default().script(vec![
+            timed_out(),
+            timed_out(),
+            timed_out(),
+            timed_out(),
+        ]));
+    for _ in 0..3 {
+        storage.get::<u64>("key").unwrap_err();
+    }
+    assert_eq!(storage.state(), CircuitState::Open);
+    assert_eq!(transitions("open"), 1);
+    let operations = storage.inner().operations();
+    assert_eq!(
+        storage.get::<u64>("key").unwrap_err(),
+        Error::CircuitOpen("scripted".into())
+    );
+    storage.set("key", 2).unwrap_err();
+    assert_eq!(storage.inner().operations(), operations);
+
+    // After the cooldown, a failed probe opens the circuit again
+    let mock = time_service.into_mock();
+    mock.advance(COOLDOWN - Duration::from_secs(1));
+    storage.get::<u64>("key").unwrap_err();
+    assert_eq!(storage.inner().operations(), operations);
+    mock.advance(Duration::from_secs(1));
+    assert_eq!(
+        storage.get::<u64>("key").unwrap_err(),
+        Error::InternalError("Connection timed out".into())
+    );
+    assert_eq!(storage.state(), CircuitState::Open);
+    assert_eq!(transitions("half_open"), 1);
+    assert_eq!(transitions("open"), 2);
+
+    // A successful probe closes it
+    mock.advance(COOLDOWN);
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+    assert_eq!(storage.state(), CircuitState::Closed);
+    assert_eq!(transitions("half_open"), 2);
+    assert_eq!(transitions("closed"), 1);
+    storage.set("key", 2).unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 2);
+}
@@ -0,0 +1,237 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    on_disk, storage_conformance_tests, CryptoStorage, EncryptedOnDiskStorage, Error, KVStorage,
+    OnDiskStorage, Passphrase, Storage,
+};
+use aptos_temppath::TempPath;
+use serde_json::Value;
+use std::{fs, path::PathBuf};
+
+const PASSPHRASE: &str = "correct horse battery staple";
+
+fn passphrase_file(passphrase: &str) -> (TempPath, Passphrase) {
+    let path = TempPath::new();
+    fs::write(path.path(), format!("{}\n", passphrase)).unwrap();
+    let passphrase = Passphrase::FromDisk(path.path().to_path_buf());
+    (path, passphrase)
+}
+
+fn storage_path() -> (TempPath, PathBuf) {
+    let temp_dir = TempPath::new();
+    temp_dir.create_as_dir().unwrap();
+    let path_buf = temp_dir.path().join("storage");
+    (temp_dir, path_buf)
+}
+
+#[test]
+fn encrypted_on_disk() {
+    let (_passphrase_path, passphrase) = passphrase_file(PASSPHRASE);
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = Storage::from(EncryptedOnDiskStorage::new(path_buf, &passphrase).unwrap());
+    storage_conformance_tests::run_all(&mut storage);
+}
+
+#[test]
+fn encrypted_on_disk_set_if_version() {
+    let (_passphrase_path, passphrase) = passphrase_file(PASSPHRASE);
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = Storage::from(EncryptedOnDiskStorage::new(path_buf, &passphrase).unwrap());
+    storage_conformance_tests::test_set_if_version(&mut storage);
+}
+
+#[test]
+fn encrypted_on_disk_no_plaintext() {
+    let (_passphrase_path, passphrase) = passphrase_file(PASSPHRASE);
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = EncryptedOnDiskStorage::new(path_buf.clone(), &passphrase).unwrap();
+    storage.set("secret_key", "secret_value").unwrap();
+    storage.create_key("consensus").unwrap();
+
+    let contents = String::from_utf8_lossy(&fs::read(&path_buf).unwrap()).to_string();
+    assert!(!contents.contains("secret_key"));
+    assert!(!contents.contains("secret_value"));
+    assert!(!contents.contains("consensus"));
+
+    // The storage can be opened again with the same passphrase
+    let storage = EncryptedOnDiskStorage::new(path_buf, &passphrase).unwrap();
+    assert_eq!(
+        storage.get::<String>("secret_key").unwrap().value,
+        "secret_value"
+    );
+}
+
+#[test]
+fn encrypted_on_disk_passphrase_from_env() {
+    let var = "APTOS_ENCRYPTED_ON_DISK_STORAGE_TEST_PASSPHRASE";
+    std::env::set_var(var, PASSPHRASE);
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage =
+        EncryptedOnDiskStorage::new(path_buf.clone(), &Passphrase::FromEnv(var.into())).unwrap();
+    storage.set("key", 1).unwrap();
+
+    // The same passphrase read from a key file opens the storage
+    let (_passphrase_path, passphrase) = passphrase_file(PASSPHRASE);
+    let storage = EncryptedOnDiskStorage::new(path_buf, &passphrase).unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+
+    assert!(matches!(
+        Passphrase::FromEnv("APTOS_UNSET_TEST_PASSPHRASE".into()).read(),
+        Err(Error::InternalError(_))
+    ));
+}
+
+#[test]
+fn encrypted_on_disk_wrong_passphrase() {
+    let (_passphrase_path, passphrase) = passphrase_file(PASSPHRASE);
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = EncryptedOnDiskStorage::new(path_buf.clone(), &passphrase).unwrap();
+    storage.set("key", 1).unwrap();
+
+    let (_wrong_path, wrong_passphrase) = passphrase_file("wrong passphrase");
+    assert!(matches!(
+        EncryptedOnDiskStorage::new(path_buf, &wrong_passphrase),
+        Err(Error::DecryptionError(_))
+    ));
+
+    let (_empty_path, empty_passphrase) = passphrase_file("");
+    assert!(matches!(
+        empty_passphrase.read(),
+        Err(Error::InternalError(_))
+    ));
+}
+
+#[test]
+fn encrypted_on_disk_bit_flip() {
+    let (_passphrase_path, passphrase) = passphrase_file(PASSPHRASE);
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = EncryptedOnDiskStorage::new(path_buf.clone(), &passphrase).unwrap();
+    storage.set("key", 1).unwrap();
+    let contents: Value = serde_json::from_slice(&on_disk::read_file(&path_buf).unwrap()).unwrap();
+
+    // Flipping a single bit of the ciphertext or the nonce fails authentication. Anyone able to
+    // alter the file can also recompute its checksum, so the file is written along with it.
+    for field in ["ciphertext", "nonce"] {
+        let mut bytes = base64::decode(contents[field].as_str().unwrap()).unwrap();
+        bytes[0] ^= 1;
+        let mut tampered = contents.clone();
+        tampered[field] = Value::String(base64::encode(&bytes));
+        on_disk::overwrite_file(&path_buf, &serde_json::to_vec(&tampered).unwrap()).unwrap();
+
+        assert!(matches!(
+            storage.get::<u64>("key"),
+            Err(Error::TamperError(_))
+        ));
+        let reopened = EncryptedOnDiskStorage::new(path_buf.clone(), &passphrase).unwrap();
+        assert!(matches!(
+            reopened.get::<u64>("key"),
+            Err(Error::TamperError(_))
+        ));
+    }
+
+    // Altering the salt or the key check is tampering too, not a wrong passphrase
+    for field in ["salt", "key_check"] {
+        let mut bytes = base64::decode(contents[field].as_str().unwrap()).unwrap();
+        bytes[0] ^= 1;
+        let mut tampered = contents.clone();
+        tampered[field] = Value::String(base64::encode(&bytes));
+        on_disk::overwrite_file(&path_buf, &serde_json::to_vec(&tampered).unwrap()).unwrap();
+
+        assert!(matches!(
+            storage.get::<u64>("key"),
+            Err(Error::TamperError(_))
+        ));
+        assert!(matches!(
+            EncryptedOnDiskStorage::new(path_buf.clone(), &passphrase),
+            Err(Error::TamperError(_))
+        ));
+    }
+
+    on_disk::overwrite_file(&path_buf, &serde_json::to_vec(&contents).unwrap()).unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+}
+
+#[test]
+fn encrypted_on_disk_empty_file() {
+    let (_passphrase_path, passphrase) = passphrase_file(PASSPHRASE);
+    let (_temp_dir, path_buf) = storage_path();
+
+    // A new storage is never empty, even before anything is written to it
+    EncryptedOnDiskStorage::new(path_buf.clone(), &passphrase).unwrap();
+    assert!(!on_disk::read_file(&path_buf).unwrap().is_empty());
+
+    // An emptied file without a backup does not start a new storage
+    let (_other_dir, other_path) = storage_path();
+    fs::write(&other_path, b"").unwrap();
+    assert!(matches!(
+        EncryptedOnDiskStorage::new(other_path, &passphrase),
+        Err(Error::TamperError(_))
+    ));
+}
+
+#[test]
+fn encrypted_on_disk_migrate_from_plaintext() {
+    let (_passphrase_path, passphrase) = passphrase_file(PASSPHRASE);
+    let (_temp_dir, plaintext_path) = storage_path();
+    let mut plaintext = OnDiskStorage::new(plaintext_path.clone());
+    plaintext.set("key", 1).unwrap();
+    plaintext.set("key", 2).unwrap();
+    let public_key = plaintext.create_key("consensus").unwrap();
+
+    // Migrating into another file leaves the plaintext storage untouched
+    let (_encrypted_dir, encrypted_path) = storage_path();
+    let storage = EncryptedOnDiskStorage::migrate_from_plaintext(
+        &plaintext_path,
+        encrypted_path.clone(),
+        &passphrase,
+    )
+    .unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 2);
+    assert_eq!(storage.get::<u64>("key").unwrap().version, 2);
+    assert_eq!(
+        storage.get_public_key("consensus").unwrap().public_key,
+        public_key
+    );
+    assert_eq!(plaintext.get::<u64>("key").unwrap().value, 2);
+
+    // An existing storage is not overwritten
+    assert!(matches!(
+        EncryptedOnDiskStorage::migrate_from_plaintext(
+            &plaintext_path,
+            encrypted_path,
+            &passphrase
+        ),
+        Err(Error::InternalError(_))
+    ));
+
+    // Migrating in place encrypts the plaintext storage
+    let storage = EncryptedOnDiskStorage::migrate_from_plaintext(
+        &plaintext_path,
+        plaintext_path.clone(),
+        &passphrase,
+    )
+    .unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 2);
+    assert!(!String::from_utf8_lossy(&fs::read(&plaintext_path).unwrap()).contains("consensus"));
+    let storage = EncryptedOnDiskStorage::new(plaintext_path, &passphrase).unwrap();
+    assert_eq!(
+        storage.get_public_key("consensus").unwrap().public_key,
+        public_key
+    );
+}
+
+#[test]
+fn encrypted_on_disk_torn_write() {
+    let (_passphrase_path, passphrase) = passphrase_file(PASSPHRASE);
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = EncryptedOnDiskStorage::new(path_buf.clone(), &passphrase).unwrap();
+    storage.set("key", 1).unwrap();
+    let contents = fs::read(&path_buf).unwrap();
+
+    for len in [0, contents.len() / 2, contents.len() - 1] {
+        fs::write(&path_buf, &contents[..len]).unwrap();
+        let storage = EncryptedOnDiskStorage::new(path_buf.clone(), &passphrase).unwrap();
+        assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+    }
+}
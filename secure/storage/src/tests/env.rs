@@ -0,0 +1,198 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    CryptoStorage, EnvStorage, Error, InMemoryStorage, KVStorage, Overlay, Storage,
+    DEFAULT_ENV_PREFIX,
+};
+use aptos_crypto::{
+    ed25519::Ed25519PrivateKey, test_utils::TestAptosCrypto, PrivateKey, Uniform,
+    ValidCryptoMaterial, ValidCryptoMaterialStringExt,
+};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+// Every test uses its own prefix, as the environment is shared by tests that run in parallel
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Data {
+    epoch: u64,
+}
+
+#[test]
+fn env_get() {
+    let prefix = "APTOS_TEST_ENV_GET_";
+    let storage = EnvStorage::new(Some(prefix.into()));
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+
+    env::set_var(
+        "APTOS_TEST_ENV_GET_CONSENSUS",
+        private_key.to_encoded_string().unwrap(),
+    );
+    env::set_var(
+        "APTOS_TEST_ENV_GET_EXECUTION",
+        format!("0x{}", private_key.to_encoded_string().unwrap()),
+    );
+    env::set_var(
+        "APTOS_TEST_ENV_GET_KEY_BASE64",
+        base64::encode(private_key.to_bytes()),
+    );
+    env::set_var("APTOS_TEST_ENV_GET_NUMBER", "42\n");
+    env::set_var("APTOS_TEST_ENV_GET_STRING", "a string");
+    env::set_var("APTOS_TEST_ENV_GET_NAMESPACE_DATA", r#"{"epoch": 5}"#);
+
+    for key in ["consensus", "execution", "key_base64"] {
+        assert_eq!(storage.export_private_key(key).unwrap(), private_key);
+    }
+    assert_eq!(
+        storage.get_public_key("CONSENSUS").unwrap().public_key,
+        private_key.public_key()
+    );
+    assert_eq!(storage.get::<u64>("number").unwrap().value, 42);
+    assert_eq!(storage.get::<String>("string").unwrap().value, "a string");
+    assert_eq!(
+        storage.get::<Data>("namespace/data").unwrap().value,
+        Data { epoch: 5 }
+    );
+    assert_eq!(
+        storage.get::<u64>("unset").unwrap_err(),
+        Error::KeyNotSet("unset".into())
+    );
+    assert_eq!(
+        storage.list_keys(Some("n")).unwrap(),
+        vec!["namespace_data", "number"]
+    );
+
+    let message = TestAptosCrypto("Hello, World".to_string());
+    storage.sign("consensus", &message).unwrap();
+}
+
+#[test]
+fn env_default_prefix() {
+    env::set_var(
+        format!("{}ENV_DEFAULT_PREFIX_TEST", DEFAULT_ENV_PREFIX),
+        "7",
+    );
+    let storage = EnvStorage::new(None);
+    assert_eq!(
+        storage.get::<u64>("env_default_prefix_test").unwrap().value,
+        7
+    );
+}
+
+#[test]
+fn env_malformed() {
+    let prefix = "APTOS_TEST_ENV_MALFORMED_";
+    let storage = EnvStorage::new(Some(prefix.into()));
+    env::set_var("APTOS_TEST_ENV_MALFORMED_CONSENSUS", "not a key");
+    env::set_var("APTOS_TEST_ENV_MALFORMED_SHORT_KEY", "0x0123");
+    env::set_var("APTOS_TEST_ENV_MALFORMED_NUMBER", "forty two");
+    env::set_var("APTOS_TEST_ENV_MALFORMED_DATA", r#"{"epoch": "five"}"#);
+
+    assert!(matches!(
+        storage.export_private_key("consensus"),
+        Err(Error::SerializationError(_))
+    ));
+    assert!(matches!(
+        storage.get_public_key("short_key"),
+        Err(Error::SerializationError(_))
+    ));
+    assert!(matches!(
+        storage.get::<u64>("number"),
+        Err(Error::SerializationError(_))
+    ));
+    assert!(matches!(
+        storage.get::<Data>("data"),
+        Err(Error::SerializationError(_))
+    ));
+}
+
+#[test]
+fn env_read_only() {
+    let prefix = "APTOS_TEST_ENV_READ_ONLY_";
+    env::set_var("APTOS_TEST_ENV_READ_ONLY_CONSENSUS", "1");
+    let mut storage = Storage::from(EnvStorage::new(Some(prefix.into())));
+
+    assert_eq!(
+        storage.set("consensus", 2).unwrap_err(),
+        Error::ReadOnly("consensus".into())
+    );
+    assert_eq!(
+        storage.set_if_version("consensus", 2, 0).unwrap_err(),
+        Error::ReadOnly("consensus".into())
+    );
+    assert_eq!(
+        storage.delete("consensus").unwrap_err(),
+        Error::ReadOnly("consensus".into())
+    );
+    assert!(matches!(
+        storage.create_key("execution"),
+        Err(Error::ReadOnly(_))
+    ));
+    assert_eq!(storage.get::<u64>("consensus").unwrap().value, 1);
+}
+
+#[test]
+fn env_overlay() {
+    let prefix = "APTOS_TEST_ENV_OVERLAY_";
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    env::set_var(
+        "APTOS_TEST_ENV_OVERLAY_CONSENSUS",
+        private_key.to_encoded_string().unwrap(),
+    );
+    env::set_var("APTOS_TEST_ENV_OVERLAY_SHADOWED", "1");
+
+    let mut storage = Storage::from(Overlay::new(
+        Box::new(Storage::from(EnvStorage::new(Some(prefix.into())))),
+        Box::new(Storage::from(InMemoryStorage::new())),
+    ));
+
+    // Keys are read from the environment, while writes go to the in-memory storage
+    assert_eq!(
+        storage.export_private_key("consensus").unwrap(),
+        private_key
+    );
+    storage.set("safety_data", Data { epoch: 1 }).unwrap();
+    storage.set("safety_data", Data { epoch: 2 }).unwrap();
+    assert_eq!(
+        storage.get::<Data>("safety_data").unwrap().value,
+        Data { epoch: 2 }
+    );
+    assert_eq!(
+        storage.list_keys(None).unwrap(),
+        vec!["consensus", "safety_data", "shadowed"]
+    );
+
+    // Writes shadow the read-only value, until they are deleted
+    storage.set("shadowed", 2).unwrap();
+    assert_eq!(storage.get::<u64>("shadowed").unwrap().value, 2);
+    storage.delete("shadowed").unwrap();
+    assert_eq!(storage.get::<u64>("shadowed").unwrap().value, 1);
+
+    // Keys held by the read-only storage cannot be rotated, as the rotated key would shadow them
+    assert_eq!(
+        storage.rotate_key("consensus").unwrap_err(),
+        Error::ReadOnly("consensus".into())
+    );
+    assert_eq!(
+        storage.export_private_key("consensus").unwrap(),
+        private_key
+    );
+    storage
+        .get_public_key_previous_version("consensus")
+        .unwrap_err();
+
+    // Keys only held by the writable storage are rotated as usual
+    let created = storage.create_key("owner").unwrap();
+    let rotated = storage.rotate_key("owner").unwrap();
+    assert_eq!(storage.get_public_key("owner").unwrap().public_key, rotated);
+    assert_eq!(
+        storage.get_public_key_previous_version("owner").unwrap(),
+        created
+    );
+
+    assert_eq!(
+        storage.get::<u64>("unset").unwrap_err(),
+        Error::KeyNotSet("unset".into())
+    );
+}
@@ -0,0 +1,234 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::Error;
+use std::io;
+
+fn retryable(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Unavailable {
+            retryable: true,
+            ..
+        }
+    )
+}
+
+fn not_retryable(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::Unavailable {
+            retryable: false,
+            ..
+        }
+    )
+}
+
+fn permission_denied(error: &Error) -> bool {
+    matches!(error, Error::PermissionDenied(_))
+}
+
+fn access_denied(error: &Error) -> bool {
+    matches!(error, Error::AccessDenied(_))
+}
+
+fn key_not_set(error: &Error) -> bool {
+    matches!(error, Error::KeyNotSet(_))
+}
+
+fn serialization_error(error: &Error) -> bool {
+    matches!(error, Error::SerializationError(_))
+}
+
+fn internal_error(error: &Error) -> bool {
+    matches!(error, Error::InternalError(_))
+}
+
+fn http(status: u16) -> (u16, String, String) {
+    (status, "status".into(), "body".into())
+}
+
+#[test]
+fn vault_error_mapping() {
+    use aptos_vault_client::Error as VaultError;
+
+    let http_error = |status| {
+        let (status, status_text, body) = http(status);
+        VaultError::HttpError(status, status_text, body)
+    };
+    let matrix: Vec<(VaultError, &dyn Fn(&Error) -> bool)> = vec![
+        (http_error(401), &access_denied),
+        (http_error(403), &permission_denied),
+        (
+            VaultError::NotFound("secret".into(), "key".into()),
+            &key_not_set,
+        ),
+        (http_error(429), &retryable),
+        (http_error(500), &retryable),
+        (http_error(503), &retryable),
+        (http_error(501), &not_retryable),
+        (VaultError::SyntheticError("timed out".into()), &retryable),
+        (
+            VaultError::SerializationError("eof".into()),
+            &serialization_error,
+        ),
+        (http_error(400), &internal_error),
+        (VaultError::MissingField("data".into()), &internal_error),
+    ];
+    for (vault_error, expected) in matrix {
+        let message = format!("{:?}", vault_error);
+        let error = Error::from(vault_error);
+        assert!(expected(&error), "{} -> {:?}", message, error);
+    }
+}
+
+#[test]
+fn github_error_mapping() {
+    use aptos_github_client::Error as GitHubError;
+
+    let http_error = |status| {
+        let (status, status_text, body) = http(status);
+        GitHubError::HttpError(status, status_text, body)
+    };
+    let matrix: Vec<(GitHubError, &dyn Fn(&Error) -> bool)> = vec![
+        (http_error(401), &access_denied),
+        (http_error(403), &permission_denied),
+        (GitHubError::NotFound("key".into()), &key_not_set),
+        (http_error(502), &retryable),
+        (http_error(505), &not_retryable),
+        (
+            GitHubError::InternalError("connection refused".into()),
+            &retryable,
+        ),
+        (
+            GitHubError::SerializationError("eof".into()),
+            &serialization_error,
+        ),
+        (http_error(422), &internal_error),
+    ];
+    for (github_error, expected) in matrix {
+        let message = format!("{:?}", github_error);
+        let error = Error::from(github_error);
+        assert!(expected(&error), "{} -> {:?}", message, error);
+    }
+}
+
+#[test]
+fn aws_error_mapping() {
+    use aptos_aws_client::Error as AwsError;
+
+    let service_error = |status, error_type: &str| {
+        AwsError::ServiceError(status, error_type.into(), "message".into())
+    };
+    let matrix: Vec<(AwsError, &dyn Fn(&Error) -> bool)> = vec![
+        (service_error(403, "Forbidden"), &permission_denied),
+        (
+            service_error(400, "ExpiredTokenException"),
+            &permission_denied,
+        ),
+        (
+            service_error(400, "UnrecognizedClientException"),
+            &access_denied,
+        ),
+        (AwsError::NotFound("key".into()), &key_not_set),
+        (service_error(400, "ThrottlingException"), &retryable),
+        (service_error(500, "InternalServiceError"), &retryable),
+        (AwsError::InternalError("timed out".into()), &retryable),
+        (
+            AwsError::SerializationError("eof".into()),
+            &serialization_error,
+        ),
+        (
+            service_error(400, "ResourceExistsException"),
+            &internal_error,
+        ),
+        (
+            AwsError::CredentialsError("no profile".into()),
+            &internal_error,
+        ),
+    ];
+    for (aws_error, expected) in matrix {
+        let message = format!("{:?}", aws_error);
+        let error = Error::from(aws_error);
+        assert!(expected(&error), "{} -> {:?}", message, error);
+    }
+}
+
+#[test]
+fn gcp_error_mapping() {
+    use aptos_gcp_client::Error as GcpError;
+
+    let http_error = |status| {
+        let (status, status_text, body) = http(status);
+        GcpError::HttpError(status, status_text, body)
+    };
+    let matrix: Vec<(GcpError, &dyn Fn(&Error) -> bool)> = vec![
+        (http_error(401), &access_denied),
+        (http_error(403), &permission_denied),
+        (GcpError::NotFound("key".into()), &key_not_set),
+        (http_error(429), &retryable),
+        (http_error(504), &retryable),
+        (GcpError::InternalError("timed out".into()), &retryable),
+        (
+            GcpError::SerializationError("eof".into()),
+            &serialization_error,
+        ),
+        (http_error(409), &internal_error),
+        (
+            GcpError::CredentialsError("no token".into()),
+            &internal_error,
+        ),
+    ];
+    for (gcp_error, expected) in matrix {
+        let message = format!("{:?}", gcp_error);
+        let error = Error::from(gcp_error);
+        assert!(expected(&error), "{} -> {:?}", message, error);
+    }
+}
+
+/// The errors of on-disk storages.
+#[test]
+fn io_error_mapping() {
+    let matrix: Vec<(io::ErrorKind, &dyn Fn(&Error) -> bool)> = vec![
+        (io::ErrorKind::PermissionDenied, &access_denied),
+        (io::ErrorKind::TimedOut, &retryable),
+        (io::ErrorKind::ConnectionRefused, &retryable),
+        (io::ErrorKind::NotFound, &internal_error),
+        (io::ErrorKind::InvalidData, &internal_error),
+    ];
+    for (kind, expected) in matrix {
+        let error = Error::from(io::Error::new(kind, "error"));
+        assert!(expected(&error), "{:?} -> {:?}", kind, error);
+    }
+}
+
+#[test]
+fn error_display_and_retries() {
+    let error = Error::from(aptos_vault_client::Error::SyntheticError(
+        "timed out".into(),
+    ));
+    assert!(error.is_retryable());
+    assert_eq!(
+        error.to_string(),
+        "Storage is unavailable: Synthetic error returned: timed out, retryable: true"
+    );
+    assert!(!Error::from(aptos_vault_client::Error::HttpError(
+        501,
+        "Not Implemented".into(),
+        "".into()
+    ))
+    .is_retryable());
+    assert!(!Error::PermissionDenied("error".into()).is_retryable());
+    assert!(!Error::AccessDenied("error".into()).is_retryable());
+    assert!(!Error::InternalError("error".into()).is_retryable());
+
+    // Other crates match on these
+    assert_eq!(
+        Error::PermissionDenied("error".into()).to_string(),
+        "Permission denied: error"
+    );
+    assert_eq!(
+        Error::KeyNotSet("key".into()).to_string(),
+        "Key not set: key"
+    );
+}
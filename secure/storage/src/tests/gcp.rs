@@ -0,0 +1,152 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    storage_conformance_tests, CryptoStorage, Error, GcsSecretManagerStorage, KVStorage,
+    Namespaced, Storage,
+};
+use aptos_crypto::{
+    ed25519::Ed25519PrivateKey, test_utils::TestAptosCrypto, PrivateKey, SigningKey, Uniform,
+};
+use aptos_gcp_client::{mock::MockSecretManager, Credentials};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn create_gcp(mock: &MockSecretManager, prefix: Option<&str>) -> GcsSecretManagerStorage {
+    GcsSecretManagerStorage::new(
+        aptos_gcp_client::mock::PROJECT.into(),
+        prefix.map(str::to_string),
+        Some(mock.endpoint()),
+        mock.credentials(),
+        None,
+        None,
+    )
+}
+
+#[test]
+fn gcp() {
+    let mock = MockSecretManager::start();
+    let mut storage = Storage::from(create_gcp(&mock, None));
+    storage_conformance_tests::run_all(&mut storage);
+}
+
+/// Storages with distinct prefixes share a project without seeing each other's secrets.
+#[test]
+fn gcp_prefixes() {
+    let mock = MockSecretManager::start();
+    let mut storage_1 = Storage::from(create_gcp(&mock, Some("validator_1_")));
+    let mut storage_2 = Storage::from(create_gcp(&mock, Some("validator_2_")));
+    storage_conformance_tests::run_all(&mut storage_1);
+
+    storage_1.set("key", 1).unwrap();
+    storage_2.set("key", 2).unwrap();
+    storage_2.set("other", 2).unwrap();
+    assert_eq!(storage_1.get::<u64>("key").unwrap().value, 1);
+    assert_eq!(storage_1.list_keys(None).unwrap(), vec!["key"]);
+    storage_1.reset_and_clear().unwrap();
+    assert_eq!(storage_2.list_keys(None).unwrap(), vec!["key", "other"]);
+}
+
+#[test]
+fn gcp_list_keys_across_namespaces() {
+    let mock = MockSecretManager::start();
+    let namespaced = |namespace| {
+        let inner = Box::new(Storage::from(create_gcp(&mock, None)));
+        Storage::from(Namespaced::new(namespace, inner))
+    };
+    let mut storage_1 = namespaced("namespace_1");
+    let mut storage_2 = namespaced("namespace_2");
+    storage_conformance_tests::test_list_keys_across_namespaces(&mut storage_1, &mut storage_2);
+}
+
+/// Keys are kept across secret versions: imports and rotations add versions, and only the latest
+/// two are readable.
+#[test]
+fn gcp_key_versions() {
+    let mock = MockSecretManager::start();
+    let mut storage = create_gcp(&mock, None);
+    let message = TestAptosCrypto("Hello, World".to_string());
+
+    let imported = Ed25519PrivateKey::generate(&mut StdRng::from_seed([7; 32]));
+    storage.import_private_key("key", imported.clone()).unwrap();
+    assert_eq!(storage.export_private_key("key").unwrap(), imported);
+    assert_eq!(
+        storage.get_public_key_previous_version("key").unwrap_err(),
+        Error::KeyVersionNotFound("key".into(), "previous version".into())
+    );
+
+    let rotated = storage.rotate_key("key").unwrap();
+    assert_eq!(storage.get_public_key("key").unwrap().public_key, rotated);
+    assert_eq!(
+        storage.get_public_key_previous_version("key").unwrap(),
+        imported.public_key()
+    );
+    assert_eq!(
+        storage
+            .sign_using_version("key", imported.public_key(), &message)
+            .unwrap(),
+        imported.sign(&message)
+    );
+
+    // A second rotation destroys the imported key
+    let rotated_again = storage.rotate_key("key").unwrap();
+    assert_eq!(
+        storage.get_public_key_previous_version("key").unwrap(),
+        rotated
+    );
+    assert_eq!(
+        storage.export_private_key("key").unwrap().public_key(),
+        rotated_again
+    );
+    assert_eq!(
+        storage
+            .sign_using_version("key", imported.public_key(), &message)
+            .unwrap_err(),
+        Error::KeyVersionNotFound("key".into(), imported.public_key().to_string())
+    );
+}
+
+#[test]
+fn gcp_create_existing_key() {
+    let mock = MockSecretManager::start();
+    let mut storage = create_gcp(&mock, Some("prefix_"));
+    storage.create_key("key").unwrap();
+    assert_eq!(
+        storage.create_key("key").unwrap_err(),
+        Error::KeyAlreadyExists("prefix_key".into())
+    );
+}
+
+#[test]
+fn gcp_invalid_credentials() {
+    let mock = MockSecretManager::start();
+    let storage = GcsSecretManagerStorage::new(
+        aptos_gcp_client::mock::PROJECT.into(),
+        None,
+        Some(mock.endpoint()),
+        Credentials::AccessToken("invalid".into()),
+        None,
+        None,
+    );
+    assert!(matches!(
+        storage.available().unwrap_err(),
+        Error::AccessDenied(_)
+    ));
+    assert!(matches!(
+        storage.get::<u64>("key").unwrap_err(),
+        Error::AccessDenied(_)
+    ));
+
+    // A token without access to the project is denied
+    let other_project = GcsSecretManagerStorage::new(
+        "other-project".into(),
+        None,
+        Some(mock.endpoint()),
+        mock.static_credentials(),
+        None,
+        None,
+    );
+    assert!(matches!(
+        other_project.available().unwrap_err(),
+        Error::PermissionDenied(_)
+    ));
+}
@@ -1,7 +1,8 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{tests::suite, GitHubStorage, Storage};
+use crate::{storage_conformance_tests, Error, GitHubStorage, KVStorage, Storage};
+use aptos_github_client::mock::MockGitHub;
 
 const OWNER: &str = "OWNER";
 const REPOSITORY: &str = "REPOSITORY";
@@ -21,5 +22,108 @@ fn github_storage() {
         BRANCH.into(),
         TOKEN.into(),
     ));
-    suite::execute_all_storage_tests(&mut storage);
+    storage_conformance_tests::run_all(&mut storage);
+}
+
+fn create_mock_github(github: &MockGitHub, branch: &str) -> GitHubStorage {
+    let mut storage = GitHubStorage::new(
+        github.owner().into(),
+        github.repository().into(),
+        branch.into(),
+        github.token().into(),
+    );
+    storage.set_url(github.host().into());
+    storage
+}
+
+/// Keys are read from and written to the configured branch, which must exist.
+#[test]
+fn github_storage_branch_mock() {
+    let github = MockGitHub::start(&["main", "storage"]).unwrap();
+    let mut main = create_mock_github(&github, "main");
+    let mut storage = create_mock_github(&github, "storage");
+    main.available().unwrap();
+    storage.available().unwrap();
+    assert_eq!(
+        create_mock_github(&github, "missing").available(),
+        Err(Error::InternalError("Branch missing not found.".into()))
+    );
+
+    main.set("key", 1).unwrap();
+    storage.set("key", 2).unwrap();
+    storage.set("other/key", 3).unwrap();
+    assert_eq!(main.get::<u64>("key").unwrap().value, 1);
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 2);
+    assert_eq!(main.list_keys(None).unwrap(), vec!["key"]);
+    assert_eq!(storage.list_keys(None).unwrap(), vec!["key", "other/key"]);
+    assert!(github
+        .requests()
+        .iter()
+        .filter(|request| request.method == "PUT")
+        .all(|request| request.branch != "missing"));
+}
+
+/// Writes that conflict with a concurrent writer are retried with the new sha of the file, until
+/// they fail with a WriteConflict.
+#[test]
+fn github_storage_write_conflicts_mock() {
+    let github = MockGitHub::start(&["main"]).unwrap();
+    let mut storage = create_mock_github(&github, "main");
+    let writes = || {
+        github
+            .requests()
+            .iter()
+            .filter(|request| request.method == "PUT" || request.method == "DELETE")
+            .count()
+    };
+    storage.set("key", 1).unwrap();
+
+    // Conflicts followed by a successful write
+    github.inject_conflicts(2);
+    let previous_writes = writes();
+    storage.set("key", 2).unwrap();
+    assert_eq!(writes() - previous_writes, 3);
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 2);
+
+    // Persistent conflicts
+    github.inject_conflicts(3);
+    let error = storage.set("key", 3).unwrap_err();
+    assert_eq!(error, Error::WriteConflict("key".into()));
+    assert!(!error.is_retryable());
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 2);
+    github.inject_conflicts(3);
+    assert_eq!(
+        storage.delete("key"),
+        Err(Error::WriteConflict("key".into()))
+    );
+    storage.delete("key").unwrap();
+    assert_eq!(
+        storage.get::<u64>("key").unwrap_err(),
+        Error::KeyNotSet("key".into())
+    );
+}
+
+/// A write retried after a conflict counts its version from the concurrent write.
+#[test]
+fn github_storage_concurrent_write_mock() {
+    let github = MockGitHub::start(&["main"]).unwrap();
+    let mut storage = create_mock_github(&github, "main");
+    let mut concurrent = create_mock_github(&github, "main");
+    storage.set("key", 1).unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().version, 1);
+
+    // The concurrent writer sets the value to 2, which the write of 3 then replaces
+    concurrent.set("other", 2).unwrap();
+    concurrent.set("other", 2).unwrap();
+    let content = github.client("main").get_file("other").unwrap();
+    github.inject_concurrent_write(&content);
+    storage.set("key", 3).unwrap();
+    let response = storage.get::<u64>("key").unwrap();
+    assert_eq!((response.value, response.version), (3, 3));
+
+    // A key created concurrently is not written as new
+    github.inject_concurrent_write(&content);
+    storage.set("new", 4).unwrap();
+    let response = storage.get::<u64>("new").unwrap();
+    assert_eq!((response.value, response.version), (4, 3));
 }
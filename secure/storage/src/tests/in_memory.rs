@@ -1,10 +1,227 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{tests::suite, InMemoryStorage, Storage};
+use crate::{
+    storage_conformance_tests, CryptoStorage, Error, InMemoryStorage, KVStorage, Namespaced,
+    Storage, MAX_PRUNED_VERSIONS, WATCH_CAPACITY,
+};
+use aptos_crypto::ed25519::Ed25519PublicKey;
+use std::{
+    sync::{mpsc::TryRecvError, Arc, Mutex},
+    thread,
+};
 
 #[test]
 fn in_memory() {
     let mut storage = Storage::from(InMemoryStorage::new());
-    suite::execute_all_storage_tests(&mut storage);
+    storage_conformance_tests::run_all(&mut storage);
+}
+
+#[test]
+fn in_memory_unwrapped() {
+    // Backends implemented outside of this crate are not variants of Storage
+    storage_conformance_tests::run_all(&mut InMemoryStorage::new());
+}
+
+#[test]
+fn in_memory_namespaced() {
+    let inner = Box::new(Storage::from(InMemoryStorage::new()));
+    let mut storage = Storage::from(Namespaced::new("namespace", inner));
+    storage_conformance_tests::run_all(&mut storage);
+}
+
+#[test]
+fn in_memory_non_exportable_key() {
+    let mut storage = Storage::from(InMemoryStorage::new());
+    assert!(!storage.supports_non_exportable_keys());
+    storage_conformance_tests::test_non_exportable_key(&mut storage);
+
+    let inner = Box::new(Storage::from(InMemoryStorage::new()));
+    let mut storage = Storage::from(Namespaced::new("namespace", inner));
+    storage_conformance_tests::test_non_exportable_key(&mut storage);
+}
+
+#[test]
+fn in_memory_key_scheme_mismatch() {
+    let mut storage = Storage::from(InMemoryStorage::new());
+    storage_conformance_tests::test_key_scheme_mismatch(&mut storage);
+
+    let inner = Box::new(Storage::from(InMemoryStorage::new()));
+    let mut storage = Storage::from(Namespaced::new("namespace", inner));
+    storage_conformance_tests::test_key_scheme_mismatch(&mut storage);
+}
+
+#[test]
+fn in_memory_prune_key_versions() {
+    let mut storage = InMemoryStorage::new();
+    storage.set_max_key_versions(6);
+    let mut storage = Storage::from(storage);
+    storage_conformance_tests::test_prune_key_versions(&mut storage);
+
+    let mut inner = InMemoryStorage::new();
+    inner.set_max_key_versions(6);
+    let mut storage = Storage::from(Namespaced::new("namespace", Box::new(Storage::from(inner))));
+    storage_conformance_tests::test_prune_key_versions(&mut storage);
+}
+
+#[test]
+fn in_memory_rotation_history() {
+    // Only the current and previous versions are retained by default
+    let mut storage = Storage::from(InMemoryStorage::new());
+    let first_version = storage.create_key("key").unwrap();
+    storage.rotate_key("key").unwrap();
+    storage
+        .export_private_key_for_version("key", first_version.clone())
+        .unwrap();
+    storage.rotate_key("key").unwrap();
+    assert!(matches!(
+        storage.export_private_key_for_version("key", first_version),
+        Err(Error::KeyVersionPruned(_, _))
+    ));
+}
+
+#[test]
+fn in_memory_pruned_versions_capped() {
+    // Each rotation prunes a version, and only the most recently pruned versions are recorded
+    let mut storage = Storage::from(InMemoryStorage::new());
+    let mut versions = vec![storage.create_key("key").unwrap()];
+    for _ in 0..=MAX_PRUNED_VERSIONS {
+        versions.push(storage.rotate_key("key").unwrap());
+    }
+    assert!(matches!(
+        storage.export_private_key_for_version("key", versions[0].clone()),
+        Err(Error::KeyVersionNotFound(_, _))
+    ));
+    assert!(matches!(
+        storage.export_private_key_for_version("key", versions[1].clone()),
+        Err(Error::KeyVersionPruned(_, _))
+    ));
+    let pruned_versions: Vec<Ed25519PublicKey> = storage.get("key_pruned").unwrap().value;
+    assert_eq!(pruned_versions.len(), MAX_PRUNED_VERSIONS);
+}
+
+#[test]
+fn in_memory_watch() {
+    let mut storage = Storage::from(InMemoryStorage::new());
+    storage_conformance_tests::test_watch(&mut storage);
+
+    let inner = Box::new(Storage::from(InMemoryStorage::new()));
+    let mut storage = Storage::from(Namespaced::new("namespace", inner));
+    storage_conformance_tests::test_watch(&mut storage);
+}
+
+#[test]
+fn in_memory_watch_bounded() {
+    let mut storage = InMemoryStorage::new();
+    let lagging = storage.watch("key").unwrap();
+    let other_key = storage.watch("other").unwrap();
+    for value in 0..=WATCH_CAPACITY {
+        storage.set("key", value).unwrap();
+    }
+
+    // The subscriber that fell behind receives the buffered events, then is unsubscribed
+    for version in 1..=WATCH_CAPACITY {
+        let event = lagging.try_recv().unwrap();
+        assert_eq!(event.metadata.unwrap().version, version as u64);
+    }
+    assert_eq!(lagging.try_recv().unwrap_err(), TryRecvError::Disconnected);
+
+    // Subscribers of other keys only observe their key
+    assert_eq!(other_key.try_recv().unwrap_err(), TryRecvError::Empty);
+    storage.set("other", 1).unwrap();
+    assert_eq!(other_key.try_recv().unwrap().key, "other");
+}
+
+#[test]
+fn in_memory_snapshot() {
+    let mut storage = InMemoryStorage::new();
+    storage.set("key", 1).unwrap();
+    storage.set("key", 2).unwrap();
+    storage.set("unchanged", 1).unwrap();
+    let public_key = storage.create_key("crypto").unwrap();
+    let snapshot = storage.snapshot();
+
+    storage.set("key", 3).unwrap();
+    storage.set("added", 1).unwrap();
+    storage.rotate_key("crypto").unwrap();
+    let key = storage.watch("key").unwrap();
+    let added = storage.watch("added").unwrap();
+    let unchanged = storage.watch("unchanged").unwrap();
+    storage.restore(snapshot.clone());
+
+    // Values, versions and keys are restored
+    let response = storage.get::<u64>("key").unwrap();
+    assert_eq!((response.value, response.version), (2, 2));
+    assert_eq!(
+        storage.get::<u64>("added").unwrap_err(),
+        Error::KeyNotSet("added".into())
+    );
+    assert_eq!(
+        storage.get_public_key("crypto").unwrap().public_key,
+        public_key
+    );
+    assert_eq!(storage.snapshot(), snapshot);
+
+    // Only the watches of the keys that changed are notified
+    assert_eq!(key.try_recv().unwrap().metadata.unwrap().version, 2);
+    assert_eq!(added.try_recv().unwrap().metadata, None);
+    assert_eq!(unchanged.try_recv().unwrap_err(), TryRecvError::Empty);
+
+    // A snapshot can be restored again
+    storage.set("key", 4).unwrap();
+    storage.restore(snapshot);
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 2);
+}
+
+#[test]
+fn in_memory_set_if_version() {
+    let mut storage = Storage::from(InMemoryStorage::new());
+    storage_conformance_tests::test_set_if_version(&mut storage);
+}
+
+#[test]
+fn in_memory_delete_keeps_version() {
+    let mut storage = Storage::from(InMemoryStorage::new());
+    storage_conformance_tests::test_delete_keeps_version(&mut storage);
+}
+
+#[test]
+fn in_memory_concurrent_writers() {
+    const COUNTER: &str = "counter";
+    const WRITERS: u64 = 8;
+    const INCREMENTS: u64 = 100;
+
+    let storage = Arc::new(Mutex::new(InMemoryStorage::new()));
+    storage.lock().unwrap().set(COUNTER, 0u64).unwrap();
+
+    // Each writer increments the counter with a read-modify-write, and the lock is released
+    // between the read and the write. Only conditional writes keep increments from being lost.
+    let writers: Vec<_> = (0..WRITERS)
+        .map(|_| {
+            let storage = storage.clone();
+            thread::spawn(move || {
+                for _ in 0..INCREMENTS {
+                    loop {
+                        let response = storage.lock().unwrap().get::<u64>(COUNTER).unwrap();
+                        match storage.lock().unwrap().set_if_version(
+                            COUNTER,
+                            response.value + 1,
+                            response.version,
+                        ) {
+                            Ok(()) => break,
+                            Err(Error::VersionMismatch { .. }) => thread::yield_now(),
+                            Err(error) => panic!("Unexpected error: {:?}", error),
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let response = storage.lock().unwrap().get::<u64>(COUNTER).unwrap();
+    assert_eq!(response.value, WRITERS * INCREMENTS);
+    assert_eq!(response.version, WRITERS * INCREMENTS + 1);
 }
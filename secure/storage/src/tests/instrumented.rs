@@ -0,0 +1,58 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    storage_conformance_tests, CryptoStorage, InMemoryStorage, Instrumented, KVStorage, Namespaced,
+    Storage, STORAGE_ERRORS, STORAGE_LATENCY,
+};
+
+#[test]
+fn instrumented() {
+    let mut storage = Storage::from(InMemoryStorage::new()).instrumented();
+    storage_conformance_tests::run_all(&mut storage);
+    storage_conformance_tests::test_set_if_version(&mut storage);
+}
+
+#[test]
+fn instrumented_backend() {
+    let storage = Storage::from(InMemoryStorage::new());
+    assert_eq!(storage.backend(), "in_memory");
+    let storage = Storage::from(Namespaced::new("namespace", Box::new(storage))).instrumented();
+    assert_eq!(storage.backend(), "in_memory");
+
+    // Instrumenting twice does not record operations twice
+    match storage.instrumented() {
+        Storage::InstrumentedStorage(storage) => {
+            assert!(matches!(**storage.inner(), Storage::NamespacedStorage(_)))
+        }
+        _ => panic!("Storage is not instrumented"),
+    }
+}
+
+#[test]
+fn instrumented_metrics() {
+    // The backend label is unique to this test, so that concurrent tests do not interfere
+    const BACKEND: &str = "instrumented_metrics";
+    let latency = |operation| {
+        STORAGE_LATENCY
+            .with_label_values(&[BACKEND, operation])
+            .get_sample_count()
+    };
+    let errors = |operation| {
+        STORAGE_ERRORS
+            .with_label_values(&[BACKEND, operation])
+            .get()
+    };
+
+    let mut storage = Instrumented::new(BACKEND, InMemoryStorage::new());
+    storage.set("key", 1).unwrap();
+    storage.get::<u64>("key").unwrap();
+    storage.get::<u64>("missing").unwrap_err();
+    storage.create_key("consensus").unwrap();
+    storage.rotate_key("missing").unwrap_err();
+
+    assert_eq!((latency("set"), errors("set")), (1, 0));
+    assert_eq!((latency("get"), errors("get")), (2, 1));
+    assert_eq!((latency("create_key"), errors("create_key")), (1, 0));
+    assert_eq!((latency("rotate_key"), errors("rotate_key")), (1, 1));
+}
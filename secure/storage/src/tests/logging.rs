@@ -0,0 +1,34 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::logging::{key_fingerprint, key_import_log_schema};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+use aptos_logger::{Key, Schema, Value, Visitor};
+
+struct Collector(Vec<String>);
+
+impl Visitor for Collector {
+    fn visit_pair(&mut self, key: Key, value: Value<'_>) {
+        self.0.push(format!("{:?}={:?}", key, value));
+    }
+}
+
+#[test]
+fn key_import_logs_fingerprint() {
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let public_key = private_key.public_key().to_bytes();
+    let mut collector = Collector(Vec::new());
+    key_import_log_schema("consensus", &public_key).visit(&mut collector);
+    let output = collector.0.join(" ");
+
+    let fingerprint = key_fingerprint(&public_key);
+    assert_eq!(fingerprint.len(), 16);
+    assert!(output.contains(&fingerprint));
+    assert!(output.contains("consensus"));
+    assert!(output.contains("key_import"));
+
+    // Neither the full public key nor the private key is logged
+    assert!(!output.contains(&hex::encode(public_key)));
+    assert!(!output.contains(&hex::encode(private_key.to_bytes())));
+    assert!(!output.contains(&base64::encode(private_key.to_bytes())));
+}
@@ -0,0 +1,108 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{migrate, CryptoStorage, Error, InMemoryStorage, KVStorage, MigrationReport, Storage};
+use aptos_crypto::{test_utils::TestAptosCrypto, HashValue};
+
+const CONSENSUS_KEY: &str = "consensus";
+
+#[test]
+fn migrate_all() {
+    let mut src = Storage::from(InMemoryStorage::new());
+    let mut dst = Storage::from(InMemoryStorage::new());
+    src.set("owner_account", "owner".to_string()).unwrap();
+    src.set("waypoint", 7u64).unwrap();
+    // A value that would also parse as a private key
+    let hash = HashValue::random().to_hex();
+    src.set("hash", hash.clone()).unwrap();
+    let previous_version = src.create_key(CONSENSUS_KEY).unwrap();
+    let current_version = src.rotate_key(CONSENSUS_KEY).unwrap();
+
+    // The previous version of the key is copied along with it
+    let report = migrate(&mut src, &mut dst, None, &[CONSENSUS_KEY]).unwrap();
+    assert_eq!(
+        report,
+        MigrationReport {
+            copied: vec![
+                CONSENSUS_KEY.to_string(),
+                "hash".to_string(),
+                "owner_account".to_string(),
+                "waypoint".to_string(),
+            ],
+            skipped: vec![],
+            failed: vec![],
+        }
+    );
+    assert!(report.is_complete());
+    assert_eq!(dst.list_keys(None).unwrap(), src.list_keys(None).unwrap());
+    assert_eq!(dst.get::<u64>("waypoint").unwrap().value, 7);
+    assert_eq!(dst.get::<String>("hash").unwrap().value, hash);
+    assert_eq!(
+        dst.get::<String>("owner_account").unwrap().value,
+        "owner".to_string()
+    );
+
+    // Both versions of the consensus key sign as in the source
+    let message = TestAptosCrypto("Hello, World".to_string());
+    assert_eq!(
+        dst.get_public_key(CONSENSUS_KEY).unwrap().public_key,
+        current_version
+    );
+    assert_eq!(
+        dst.get_public_key_previous_version(CONSENSUS_KEY).unwrap(),
+        previous_version
+    );
+    for version in [current_version, previous_version] {
+        assert_eq!(
+            dst.sign_using_version(CONSENSUS_KEY, version.clone(), &message)
+                .unwrap(),
+            src.sign_using_version(CONSENSUS_KEY, version, &message)
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn migrate_keys() {
+    let mut src = Storage::from(InMemoryStorage::new());
+    let mut dst = Storage::from(InMemoryStorage::new());
+    src.set("waypoint", 7u64).unwrap();
+    src.set("unlisted", 1u64).unwrap();
+    src.create_key(CONSENSUS_KEY).unwrap();
+    src.generate_key("execution").unwrap();
+
+    // Existing versions in the destination are replaced
+    dst.create_key(CONSENSUS_KEY).unwrap();
+    dst.rotate_key(CONSENSUS_KEY).unwrap();
+
+    let report = migrate(
+        &mut src,
+        &mut dst,
+        Some(&[CONSENSUS_KEY, "execution", "missing", "waypoint"]),
+        &[CONSENSUS_KEY, "execution"],
+    )
+    .unwrap();
+    assert_eq!(
+        report.copied,
+        vec![CONSENSUS_KEY.to_string(), "waypoint".to_string()]
+    );
+    assert_eq!(report.skipped, vec!["missing".to_string()]);
+    assert_eq!(
+        report.failed,
+        vec![(
+            "execution".to_string(),
+            Error::KeyNotExportable("execution".into())
+        )]
+    );
+    assert!(!report.is_complete());
+
+    assert_eq!(
+        dst.get_public_key(CONSENSUS_KEY).unwrap().public_key,
+        src.get_public_key(CONSENSUS_KEY).unwrap().public_key
+    );
+    assert!(matches!(
+        dst.get_public_key_previous_version(CONSENSUS_KEY),
+        Err(Error::KeyVersionNotFound(_, _))
+    ));
+    dst.get::<u64>("unlisted").unwrap_err();
+}
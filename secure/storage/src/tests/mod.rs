@@ -1,8 +1,21 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+mod archive;
+mod aws;
+mod cached;
+mod chaos;
+mod circuit_breaker;
+mod encrypted_on_disk;
+mod env;
+mod error;
+mod gcp;
 mod github;
 mod in_memory;
+mod instrumented;
+mod logging;
+mod migrate;
 mod on_disk;
-mod suite;
+mod policy_storage;
+mod rate_limited;
 mod vault;
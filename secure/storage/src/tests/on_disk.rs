@@ -1,12 +1,212 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{tests::suite, OnDiskStorage, Storage};
+use crate::{
+    on_disk, storage_conformance_tests, Error, KVStorage, Namespaced, OnDiskStorage, Storage,
+};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, Uniform};
 use aptos_temppath::TempPath;
+use std::{collections::HashMap, fs, path::PathBuf, time::Duration};
+use zeroize::Zeroizing;
+
+fn storage_path() -> (TempPath, PathBuf) {
+    let temp_dir = TempPath::new();
+    temp_dir.create_as_dir().unwrap();
+    let path_buf = temp_dir.path().join("storage");
+    (temp_dir, path_buf)
+}
 
 #[test]
 fn on_disk() {
     let path_buf = TempPath::new().path().to_path_buf();
     let mut storage = Storage::from(OnDiskStorage::new(path_buf));
-    suite::execute_all_storage_tests(&mut storage);
+    storage_conformance_tests::run_all(&mut storage);
+}
+
+#[test]
+fn on_disk_key_scheme_mismatch() {
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = Storage::from(OnDiskStorage::new(path_buf));
+    storage_conformance_tests::test_key_scheme_mismatch(&mut storage);
+}
+
+#[test]
+fn on_disk_watch() {
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = OnDiskStorage::new(path_buf.clone());
+    storage.set_watch_interval(Duration::from_millis(10));
+    let mut storage = Storage::from(storage);
+    storage_conformance_tests::test_watch(&mut storage);
+
+    // Writes by another instance, e.g., of another process, are observed as well
+    let watch = storage.watch("key").unwrap();
+    let mut other_storage = OnDiskStorage::new(path_buf);
+    other_storage.set("key", 1).unwrap();
+    let event = watch.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(event.metadata.unwrap().version, 1);
+
+    // A write right after the previous one is observed, even if the file keeps its modification
+    // time
+    other_storage.set("key", 2).unwrap();
+    let event = watch.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(event.metadata.unwrap().version, 2);
+}
+
+#[test]
+fn on_disk_lock() {
+    let temp_dir = TempPath::new();
+    temp_dir.create_as_dir().unwrap();
+    let path_buf = temp_dir.path().join("storage");
+    let mut storage = OnDiskStorage::new(path_buf.clone());
+    storage.lock().unwrap();
+    storage.lock().unwrap();
+
+    let mut second_storage = OnDiskStorage::new(path_buf.clone());
+    let pid = std::process::id().to_string();
+    assert_eq!(
+        second_storage.lock(),
+        Err(Error::StorageInUse(path_buf.display().to_string(), pid))
+    );
+
+    // Dropping the storage releases the lock
+    drop(storage);
+    second_storage.lock().unwrap();
+}
+
+#[test]
+fn on_disk_list_keys_across_namespaces() {
+    let path_buf = TempPath::new().path().to_path_buf();
+    let namespaced = |namespace| {
+        let inner = Box::new(Storage::from(OnDiskStorage::new(path_buf.clone())));
+        Storage::from(Namespaced::new(namespace, inner))
+    };
+    let mut storage_1 = namespaced("namespace_1");
+    let mut storage_2 = namespaced("namespace_2");
+    storage_conformance_tests::test_list_keys_across_namespaces(&mut storage_1, &mut storage_2);
+}
+
+#[test]
+fn on_disk_set_if_version() {
+    let path_buf = TempPath::new().path().to_path_buf();
+    let mut storage = Storage::from(OnDiskStorage::new(path_buf));
+    storage_conformance_tests::test_set_if_version(&mut storage);
+}
+
+#[test]
+fn on_disk_delete_keeps_version() {
+    let path_buf = TempPath::new().path().to_path_buf();
+    let mut storage = Storage::from(OnDiskStorage::new(path_buf));
+    storage_conformance_tests::test_delete_keeps_version(&mut storage);
+}
+
+#[test]
+fn on_disk_non_exportable_key() {
+    let path_buf = TempPath::new().path().to_path_buf();
+    let mut storage = Storage::from(OnDiskStorage::new(path_buf));
+    storage_conformance_tests::test_non_exportable_key(&mut storage);
+}
+
+#[test]
+fn on_disk_prune_key_versions() {
+    let path_buf = TempPath::new().path().to_path_buf();
+    let mut storage = OnDiskStorage::new(path_buf);
+    storage.set_max_key_versions(6);
+    storage_conformance_tests::test_prune_key_versions(&mut Storage::from(storage));
+}
+
+#[test]
+fn on_disk_torn_writes() {
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = OnDiskStorage::new(path_buf.clone());
+    storage.set("key", 1).unwrap();
+    storage.set("key", 2).unwrap();
+    let contents = fs::read(&path_buf).unwrap();
+
+    // A file truncated at any offset is restored from its backup when read
+    for len in 0..contents.len() {
+        fs::write(&path_buf, &contents[..len]).unwrap();
+        let storage = OnDiskStorage::new(path_buf.clone());
+        assert_eq!(storage.get::<u64>("key").unwrap().value, 2);
+    }
+
+    // So is a file whose checksum does not match
+    let mut flipped = contents.clone();
+    flipped[0] ^= 1;
+    fs::write(&path_buf, &flipped).unwrap();
+    let storage = OnDiskStorage::new(path_buf.clone());
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 2);
+
+    // Without a valid backup, the corruption surfaces on reads, rather than when opened
+    let mut backup_path = path_buf.clone().into_os_string();
+    backup_path.push(".bak");
+    fs::write(&backup_path, &contents[..contents.len() / 2]).unwrap();
+    fs::write(&path_buf, &contents[..contents.len() - 1]).unwrap();
+    let storage = OnDiskStorage::new(path_buf);
+    assert!(matches!(
+        storage.get::<u64>("key"),
+        Err(Error::CorruptedStorage(_))
+    ));
+}
+
+#[test]
+fn on_disk_emptied_file() {
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = OnDiskStorage::new(path_buf.clone());
+    storage.set("key", 1).unwrap();
+
+    // An emptied file no longer matches its checksum, so it is restored
+    fs::write(&path_buf, b"").unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+
+    // Unless its checksum is deleted along with it, which clears the storage
+    fs::write(&path_buf, b"").unwrap();
+    let mut checksum_path = path_buf.into_os_string();
+    checksum_path.push(".sha3");
+    fs::remove_file(checksum_path).unwrap();
+    assert_eq!(
+        storage.get::<u64>("key").unwrap_err(),
+        Error::KeyNotSet("key".into())
+    );
+}
+
+#[test]
+fn on_disk_without_checksum() {
+    // Files are plain JSON, and files written before checksums were introduced remain readable
+    let (_temp_dir, path_buf) = storage_path();
+    let mut storage = OnDiskStorage::new(path_buf.clone());
+    storage.set("key", 1).unwrap();
+    let contents: serde_json::Value =
+        serde_json::from_slice(&fs::read(&path_buf).unwrap()).unwrap();
+    assert_eq!(contents["key"]["value"], 1);
+    let mut checksum_path = path_buf.clone().into_os_string();
+    checksum_path.push(".sha3");
+    fs::remove_file(checksum_path).unwrap();
+
+    let mut storage = OnDiskStorage::new(path_buf.clone());
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+    storage.set("key", 2).unwrap();
+    assert_eq!(
+        OnDiskStorage::new(path_buf)
+            .get::<u64>("key")
+            .unwrap()
+            .value,
+        2
+    );
+}
+
+#[test]
+fn on_disk_key_buffers_zeroizing() {
+    let (_temp_dir, path_buf) = storage_path();
+    let storage = OnDiskStorage::new(path_buf.clone());
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+    let data = HashMap::from([(
+        "key".to_string(),
+        serde_json::to_value(&private_key).unwrap(),
+    )]);
+
+    // The buffers holding serialized keys are zeroized once dropped
+    let contents: Zeroizing<Vec<u8>> = storage.serialize(&data).unwrap();
+    storage.write(&data).unwrap();
+    let read: Zeroizing<Vec<u8>> = on_disk::read_file(&path_buf).unwrap();
+    assert_eq!(read, contents);
 }
@@ -0,0 +1,87 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    storage_conformance_tests, Capability, ChaosPolicy, ChaosStorage, CryptoStorage, Error,
+    InMemoryStorage, KVStorage, KeyPolicy, PolicyStorage, Storage,
+};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, test_utils::TestAptosCrypto, Uniform};
+
+#[test]
+fn policy_storage() {
+    let mut storage = Storage::from(InMemoryStorage::new()).with_key_policy(KeyPolicy::allow_all());
+    assert_eq!(storage.backend(), "in_memory");
+    storage_conformance_tests::run_all(&mut storage);
+}
+
+#[test]
+fn key_policy_allows() {
+    let policy = KeyPolicy::read_only().key("key", vec![Capability::Sign]);
+    assert!(policy.allows("other", Capability::Read));
+    assert!(!policy.allows("other", Capability::Write));
+    assert!(policy.allows("key", Capability::Sign));
+    assert!(!policy.allows("key", Capability::Read));
+}
+
+#[test]
+fn policy_storage_denies_before_backend() {
+    let policy = KeyPolicy::allow_all()
+        .key("owner", vec![Capability::Read])
+        .key("consensus", vec![Capability::Read, Capability::Sign]);
+    let mut storage = PolicyStorage::new(
+        ChaosStorage::new(InMemoryStorage::new(), ChaosPolicy::default()),
+        policy,
+    );
+    storage.inner_mut().set("owner", 1).unwrap();
+    storage
+        .inner_mut()
+        .import_private_key("consensus", Ed25519PrivateKey::generate_for_testing())
+        .unwrap();
+    let operations = storage.inner().operations();
+
+    // Out-of-policy operations fail without reaching the backend
+    assert_eq!(
+        storage.set("owner", 2).unwrap_err(),
+        Error::PolicyDenied("Write access to owner".into())
+    );
+    assert_eq!(
+        storage
+            .set_batch(&[("key", 2.into()), ("owner", 2.into())])
+            .unwrap_err(),
+        Error::PolicyDenied("Write access to owner".into())
+    );
+    assert_eq!(
+        storage.delete("owner").unwrap_err(),
+        Error::PolicyDenied("Write access to owner".into())
+    );
+    assert_eq!(
+        storage.rotate_key("consensus").unwrap_err(),
+        Error::PolicyDenied("Rotate access to consensus".into())
+    );
+    assert_eq!(
+        storage.export_private_key("consensus").unwrap_err(),
+        Error::PolicyDenied("Export access to consensus".into())
+    );
+    assert_eq!(storage.inner().operations(), operations);
+    assert_eq!(storage.get::<u64>("owner").unwrap().value, 1);
+
+    // Other operations are unaffected
+    storage
+        .sign("consensus", &TestAptosCrypto("Hello, World".to_string()))
+        .unwrap();
+    storage.get_public_key("consensus").unwrap();
+    storage.set("key", 3).unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 3);
+
+    // A read-only policy, e.g., for monitoring, never writes
+    let mut storage = PolicyStorage::new(storage.into_inner(), KeyPolicy::read_only());
+    assert_eq!(
+        storage.set("key", 4).unwrap_err(),
+        Error::PolicyDenied("Write access to key".into())
+    );
+    assert_eq!(
+        storage.create_key("other").unwrap_err(),
+        Error::PolicyDenied("Write access to other".into())
+    );
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 3);
+}
@@ -0,0 +1,109 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    storage_conformance_tests, ChaosPolicy, ChaosStorage, CircuitState, CryptoStorage, Error,
+    InMemoryStorage, KVStorage, RateLimitedStorage, RateLimits, Storage, STORAGE_RATE_LIMITED,
+};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, test_utils::TestAptosCrypto, Uniform};
+use aptos_time_service::TimeService;
+use std::time::Duration;
+
+fn rate_limited(retry_after_ms: u64) -> Error {
+    Error::RateLimited {
+        retry_after: Duration::from_millis(retry_after_ms),
+    }
+}
+
+#[test]
+fn rate_limited_storage() {
+    let limits = RateLimits::default()
+        .reads(10_000, 1)
+        .writes(10_000, 1)
+        .signs(10_000, 1);
+    let mut storage = Storage::from(InMemoryStorage::new()).with_rate_limits(limits);
+    assert_eq!(storage.backend(), "in_memory");
+    storage_conformance_tests::run_all(&mut storage);
+}
+
+#[test]
+fn rate_limited_refill() {
+    let time_service = TimeService::mock();
+    let mut storage = RateLimitedStorage::new_with_time_service(
+        "throttled",
+        ChaosStorage::new(InMemoryStorage::new(), ChaosPolicy::default()),
+        RateLimits::default().reads(2, 10).writes(1, 1),
+        time_service.clone(),
+    );
+    let throttled = |operation: &str| {
+        STORAGE_RATE_LIMITED
+            .with_label_values(&["throttled", operation])
+            .get()
+    };
+    storage
+        .inner_mut()
+        .import_private_key("key", Ed25519PrivateKey::generate_for_testing())
+        .unwrap();
+
+    // Bursts up to the capacity succeed, and operations in excess do not reach the storage
+    storage.set("key", 1).unwrap();
+    storage.get::<u64>("key").unwrap();
+    storage.get::<u64>("key").unwrap();
+    let operations = storage.inner().operations();
+    assert_eq!(storage.get::<u64>("key").unwrap_err(), rate_limited(100));
+    assert_eq!(storage.set("key", 2).unwrap_err(), rate_limited(1_000));
+    assert_eq!(storage.inner().operations(), operations);
+    assert_eq!(throttled("read"), 1);
+    assert_eq!(throttled("write"), 1);
+
+    // Classes without a limit are not rate limited
+    let message = TestAptosCrypto("Hello, World".to_string());
+    for _ in 0..10 {
+        storage.sign("key", &message).unwrap();
+    }
+    assert_eq!(throttled("sign"), 0);
+
+    // Tokens are refilled over time, and the retry delay shrinks accordingly
+    let mock = time_service.into_mock();
+    mock.advance(Duration::from_millis(40));
+    assert_eq!(storage.get::<u64>("key").unwrap_err(), rate_limited(60));
+    mock.advance(Duration::from_millis(60));
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+    assert_eq!(storage.get::<u64>("key").unwrap_err(), rate_limited(100));
+
+    // Refills are capped at the capacity of the bucket
+    mock.advance(Duration::from_secs(60));
+    storage.set("key", 2).unwrap();
+    assert_eq!(storage.set("key", 3).unwrap_err(), rate_limited(1_000));
+    storage.get::<u64>("key").unwrap();
+    storage.get::<u64>("key").unwrap();
+    assert_eq!(storage.get::<u64>("key").unwrap_err(), rate_limited(100));
+    assert_eq!(throttled("read"), 4);
+    assert_eq!(throttled("write"), 2);
+}
+
+#[test]
+fn rate_limited_composes() {
+    let limits = RateLimits::default().writes(1, 1);
+    let mut storage = Storage::from(InMemoryStorage::new())
+        .with_rate_limits(limits)
+        .with_circuit_breaker(1, Duration::from_secs(10));
+    assert_eq!(storage.backend(), "in_memory");
+
+    // Throttled operations are not failures of the storage
+    storage.set("key", 1).unwrap();
+    let error = storage.set("key", 2).unwrap_err();
+    assert!(matches!(error, Error::RateLimited { .. }));
+    assert!(error.is_retryable());
+    match &storage {
+        Storage::CircuitBreakerStorage(circuit_breaker) => {
+            assert_eq!(circuit_breaker.state(), CircuitState::Closed);
+            assert!(matches!(
+                **circuit_breaker.inner(),
+                Storage::RateLimitedStorage(_)
+            ));
+        }
+        _ => panic!("Expected circuit breaker storage"),
+    }
+    assert_eq!(storage.get::<u64>("key").unwrap().value, 1);
+}
@@ -2,15 +2,21 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    tests::suite,
+    storage_conformance_tests,
     vault::{
         policy::{VaultEngine, VaultPolicy},
         VaultStorage,
     },
     Capability, CryptoStorage, Error, Identity, KVStorage, Namespaced, Permission, Policy, Storage,
 };
-use aptos_crypto::{test_utils::TestAptosCrypto, Signature};
-use aptos_vault_client::dev::{self, ROOT_TOKEN};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, test_utils::TestAptosCrypto, Signature, Uniform};
+use aptos_vault_client::{
+    dev::{self, ROOT_TOKEN},
+    mock::MockVault,
+    KeyBackup, KvVersion, NAMESPACE_HEADER,
+};
+use std::time::Duration;
+use zeroize::Zeroizing;
 
 /// VaultStorage namespace constants
 const VAULT_NAMESPACE_1: &str = "namespace_1";
@@ -68,7 +74,15 @@ fn execute_storage_tests_vault() {
 /// Runs the test suite on a VaultStorage instance that does not use distinct namespaces
 fn test_suite_no_namespaces() {
     let mut storage = Storage::from(create_vault());
-    suite::execute_all_storage_tests(&mut storage);
+    storage_conformance_tests::run_all(&mut storage);
+    storage_conformance_tests::test_set_if_version(&mut storage);
+    storage_conformance_tests::test_watch(&mut storage);
+    assert!(storage.supports_non_exportable_keys());
+    storage_conformance_tests::test_non_exportable_key(&mut storage);
+
+    let mut vault = create_vault();
+    vault.set_max_key_versions(6);
+    storage_conformance_tests::test_prune_key_versions(&mut Storage::from(vault));
 }
 
 /// Runs the test suite on a VaultStorage instance that supports multiple distinct namespaces.
@@ -78,9 +92,11 @@ fn test_suite_multiple_namespaces() {
     let mut storage_2 = Storage::from(create_vault_with_namespace(VAULT_NAMESPACE_2));
     let mut storage_3 = Storage::from(create_vault_with_namespace(VAULT_NAMESPACE_3));
 
-    suite::execute_all_storage_tests(&mut storage_1);
-    suite::execute_all_storage_tests(&mut storage_2);
-    suite::execute_all_storage_tests(&mut storage_3);
+    storage_conformance_tests::run_all(&mut storage_1);
+    storage_conformance_tests::run_all(&mut storage_2);
+    storage_conformance_tests::run_all(&mut storage_3);
+    storage_conformance_tests::test_non_exportable_key(&mut storage_1);
+    storage_conformance_tests::test_list_keys_across_namespaces(&mut storage_1, &mut storage_2);
 }
 
 /// Creates and initializes a VaultStorage instance for testing. If a namespace is specified, the
@@ -167,20 +183,29 @@ fn test_vault_key_value_policies() {
     let writer_token = storage.create_token(vec![WRITER]).unwrap();
     let mut writer = create_vault_storage(writer_token.clone(), ttl, false);
     assert_eq!(writer.get::<u64>(ANYONE).unwrap().value, 1);
-    assert_eq!(writer.get::<u64>(ROOT), Err(Error::PermissionDenied));
+    assert!(matches!(
+        writer.get::<u64>(ROOT),
+        Err(Error::PermissionDenied(_))
+    ));
     assert_eq!(writer.get::<u64>(PARTIAL).unwrap().value, 3);
     assert_eq!(writer.get::<u64>(FULL).unwrap().value, 4);
 
     let reader_token = storage.create_token(vec![READER]).unwrap();
     let mut reader = create_vault_storage(reader_token.clone(), ttl, false);
     assert_eq!(reader.get::<u64>(ANYONE).unwrap().value, 1);
-    assert_eq!(reader.get::<u64>(ROOT), Err(Error::PermissionDenied));
+    assert!(matches!(
+        reader.get::<u64>(ROOT),
+        Err(Error::PermissionDenied(_))
+    ));
     assert_eq!(reader.get::<u64>(PARTIAL).unwrap().value, 3);
     assert_eq!(reader.get::<u64>(FULL).unwrap().value, 4);
 
     // Attempt writes followed by reads for correctness
     writer.set(ANYONE, 5).unwrap();
-    assert_eq!(writer.set(ROOT, 6), Err(Error::PermissionDenied));
+    assert!(matches!(
+        writer.set(ROOT, 6),
+        Err(Error::PermissionDenied(_))
+    ));
     writer.set(PARTIAL, 7).unwrap();
     writer.set(FULL, 8).unwrap();
 
@@ -190,8 +215,14 @@ fn test_vault_key_value_policies() {
     assert_eq!(storage.get::<u64>(FULL).unwrap().value, 8);
 
     reader.set(ANYONE, 9).unwrap();
-    assert_eq!(reader.set(ROOT, 10), Err(Error::PermissionDenied));
-    assert_eq!(reader.set(PARTIAL, 11), Err(Error::PermissionDenied));
+    assert!(matches!(
+        reader.set(ROOT, 10),
+        Err(Error::PermissionDenied(_))
+    ));
+    assert!(matches!(
+        reader.set(PARTIAL, 11),
+        Err(Error::PermissionDenied(_))
+    ));
     reader.set(FULL, 12).unwrap();
 
     assert_eq!(storage.get::<u64>(ANYONE).unwrap().value, 9);
@@ -204,28 +235,28 @@ fn test_vault_key_value_policies() {
         VAULT_NAMESPACE_1,
         create_vault_storage(reader_token, ttl, false),
     );
-    assert_eq!(
+    assert!(matches!(
         reader_with_namespace.get::<u64>(ANYONE),
-        Err(Error::PermissionDenied)
-    );
-    assert_eq!(
+        Err(Error::PermissionDenied(_))
+    ));
+    assert!(matches!(
         reader_with_namespace.set(ANYONE, 5),
-        Err(Error::PermissionDenied)
-    );
+        Err(Error::PermissionDenied(_))
+    ));
 
     // Verify a writer with another namespace has no permission for the operations
     let mut writer_with_namespace = Namespaced::new(
         VAULT_NAMESPACE_1,
         create_vault_storage(writer_token, ttl, false),
     );
-    assert_eq!(
+    assert!(matches!(
         writer_with_namespace.get::<u64>(ANYONE),
-        Err(Error::PermissionDenied)
-    );
-    assert_eq!(
+        Err(Error::PermissionDenied(_))
+    ));
+    assert!(matches!(
         writer_with_namespace.set(ANYONE, 5),
-        Err(Error::PermissionDenied)
-    );
+        Err(Error::PermissionDenied(_))
+    ));
 }
 
 fn test_vault_crypto_policies() {
@@ -362,25 +393,31 @@ fn test_vault_tokens() {
 
     // Verify a writer without a namespace has no permission for the operations
     let mut writer_without_namespace = create_vault_storage(writer_token, None, true);
-    assert_eq!(
+    assert!(matches!(
         writer_without_namespace.get::<u64>(PARTIAL),
-        Err(Error::PermissionDenied)
-    );
-    assert_eq!(
+        Err(Error::PermissionDenied(_))
+    ));
+    assert!(matches!(
         writer_without_namespace.set::<u64>(PARTIAL, 5),
-        Err(Error::PermissionDenied)
-    );
+        Err(Error::PermissionDenied(_))
+    ));
 
     // Revoke the token and verify failure
     writer.inner().revoke_token_self().unwrap();
-    assert_eq!(writer.get::<u64>(PARTIAL), Err(Error::PermissionDenied));
+    assert!(matches!(
+        writer.get::<u64>(PARTIAL),
+        Err(Error::PermissionDenied(_))
+    ));
 
     // Try to use an invalid token and verify failure
     let writer = Namespaced::new(
         VAULT_NAMESPACE_1,
         create_vault_storage("INVALID TOKEN".into(), None, true),
     );
-    assert_eq!(writer.get::<u64>(PARTIAL), Err(Error::PermissionDenied));
+    assert!(matches!(
+        writer.get::<u64>(PARTIAL),
+        Err(Error::PermissionDenied(_))
+    ));
 }
 
 fn test_vault_cas() {
@@ -469,3 +506,173 @@ fn test_vault_key_trimming() {
         );
     }
 }
+
+/// Runs the key-value tests of the suite against a mock Vault server, which serves KV engines of
+/// both versions, with a storage for each mount. Unlike the tests above, it does not require Vault.
+#[test]
+fn vault_kv_versions_mock() {
+    let vault = MockVault::start(&[
+        ("secret", KvVersion::V2),
+        ("kv_v1", KvVersion::V1),
+        ("kv_v2", KvVersion::V2),
+    ])
+    .unwrap();
+    let create_mock_vault = |mount: Option<&str>| {
+        let mut storage = VaultStorage::new(
+            vault.host().into(),
+            vault.token().into(),
+            None,
+            None,
+            true,
+            None,
+            None,
+        );
+        if let Some(mount) = mount {
+            storage.set_kv_mount(mount, None);
+        }
+        storage.set_watch_interval(Duration::from_millis(10));
+        storage
+    };
+
+    let mut storages = [
+        Storage::from(create_mock_vault(None)),
+        Storage::from(create_mock_vault(Some("kv_v1"))),
+        Storage::from(create_mock_vault(Some("kv_v2"))),
+    ];
+    for storage in storages.iter_mut() {
+        storage_conformance_tests::test_set_if_version(storage);
+        storage_conformance_tests::test_watch(storage);
+    }
+
+    // Each storage only sees the secrets of its mount, with versions of their own
+    for (value, storage) in storages.iter_mut().enumerate() {
+        for _ in 0..=value {
+            storage.set("key", value).unwrap();
+        }
+        storage.set(&format!("key_{}", value), value).unwrap();
+    }
+    for (value, storage) in storages.iter().enumerate() {
+        let response = storage.get::<usize>("key").unwrap();
+        assert_eq!(response.value, value);
+        assert_eq!(response.version, value as u64 + 1);
+        assert_eq!(
+            storage.list_keys(None).unwrap(),
+            vec!["key".to_string(), format!("key_{}", value)]
+        );
+    }
+}
+
+/// Reads previous versions of a secret, which only KV v2 engines retain.
+#[test]
+fn vault_get_version_mock() {
+    let vault = MockVault::start(&[("kv_v1", KvVersion::V1), ("kv_v2", KvVersion::V2)]).unwrap();
+    for (mount, kv_version) in [("kv_v1", KvVersion::V1), ("kv_v2", KvVersion::V2)] {
+        let mut storage = VaultStorage::new(
+            vault.host().into(),
+            vault.token().into(),
+            None,
+            None,
+            true,
+            None,
+            None,
+        );
+        storage.set_kv_mount(mount, Some(kv_version));
+        storage.set("key", 1).unwrap();
+        storage.set("key", 2).unwrap();
+
+        let current = storage.get::<u64>("key").unwrap();
+        assert_eq!(storage.get_version::<u64>("key", 2).unwrap(), current);
+        match kv_version {
+            KvVersion::V1 => assert_eq!(
+                storage.get_version::<u64>("key", 1).unwrap_err(),
+                Error::KeyVersionNotFound("key".into(), "1".into())
+            ),
+            KvVersion::V2 => {
+                let previous = storage.get_version::<u64>("key", 1).unwrap();
+                assert_eq!((previous.value, previous.version), (1, 1));
+            }
+        }
+        assert_eq!(
+            storage.get_version::<u64>("key", 3).unwrap_err(),
+            Error::KeyVersionNotFound("key".into(), "3".into())
+        );
+    }
+}
+
+/// Checks that the requests of all kinds of operations, including token renewals and transit
+/// operations, which the mock does not serve, are sent to the Vault Enterprise namespace.
+#[test]
+fn vault_enterprise_namespace_mock() {
+    let vault = MockVault::start(&[("secret", KvVersion::V2)]).unwrap();
+    let mut storage = VaultStorage::new(
+        vault.host().into(),
+        vault.token().into(),
+        None,
+        Some(3600),
+        true,
+        None,
+        None,
+    );
+    storage.set_enterprise_namespace(Some("team".into()));
+    storage.set_kv_mount("secret", None);
+    storage.set_watch_interval(Duration::from_millis(10));
+    let mut storage = Storage::from(storage);
+
+    storage_conformance_tests::test_set_if_version(&mut storage);
+    storage_conformance_tests::test_watch(&mut storage);
+    storage.create_key("key").unwrap_err();
+    storage
+        .sign("key", &TestAptosCrypto("message".into()))
+        .unwrap_err();
+
+    let requests = vault.requests();
+    for path in [
+        "/v1/auth/token/renew-self",
+        "/v1/sys/internal/ui/mounts/secret",
+        "/v1/secret/data/key",
+        "/v1/secret/metadata/key",
+        "/v1/transit/keys/key",
+        "/v1/transit/sign/key",
+    ] {
+        assert!(
+            requests.iter().any(|request| request.path == path),
+            "{}",
+            path
+        );
+    }
+    for request in requests {
+        assert_eq!(request.header(NAMESPACE_HEADER), Some("team"));
+    }
+}
+
+/// Checks that a 403, e.g., for a token of another namespace, names the namespace.
+#[test]
+fn vault_enterprise_namespace_permission_denied_mock() {
+    let vault = MockVault::start(&[("secret", KvVersion::V2)]).unwrap();
+    let mut storage = VaultStorage::new(
+        vault.host().into(),
+        "other_token".into(),
+        None,
+        None,
+        true,
+        None,
+        None,
+    );
+    storage.set_enterprise_namespace(Some("team".into()));
+    storage.set_kv_mount("secret", Some(KvVersion::V2));
+
+    match storage.get::<u64>("key") {
+        Err(Error::PermissionDenied(reason)) => assert!(reason.contains("(namespace: team)")),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn vault_key_backup_zeroizing() {
+    let private_key = Ed25519PrivateKey::generate_for_testing();
+
+    // The backup holds the private key, so it is zeroized once dropped
+    let backup: Zeroizing<String> = KeyBackup::encode(&private_key).unwrap();
+    let decoded = base64::decode(backup.as_bytes()).unwrap();
+    serde_json::from_slice::<KeyBackup>(&decoded).unwrap();
+}
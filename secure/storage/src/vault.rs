@@ -2,21 +2,32 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    namespaced::NAMESPACE_SEPARATOR, CryptoStorage, Error, GetResponse, KVStorage,
-    PublicKeyResponse,
+    crypto_kv_storage::{missing_version_error, record_pruned_versions},
+    kv_storage::filter_keys,
+    logging,
+    namespaced::NAMESPACE_SEPARATOR,
+    watch, CryptoStorage, Error, GetResponse, KVStorage, KeyWatch, PublicKeyResponse,
+    ValueMetadata,
 };
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
+    traits::signing_message,
+    PrivateKey,
 };
 use aptos_infallible::RwLock;
 use aptos_time_service::{TimeService, TimeServiceTrait};
-use aptos_vault_client::Client;
+use aptos_vault_client::{Client, KvVersion, DEFAULT_MAX_KEY_VERSIONS};
 use chrono::DateTime;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{
     collections::HashMap,
-    sync::atomic::{AtomicU64, Ordering},
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Once,
+    },
+    time::Duration,
 };
 
 #[cfg(any(test, feature = "testing"))]
@@ -24,13 +35,24 @@ use aptos_vault_client::ReadResponse;
 
 const TRANSIT_NAMESPACE_SEPARATOR: &str = "__";
 
+/// How often watches read the metadata of their secret.
+const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
 /// VaultStorage utilizes Vault for maintaining encrypted, authenticated data. This
 /// version currently matches the behavior of OnDiskStorage and InMemoryStorage. In the future,
 /// Vault will be able to create keys, sign messages, and handle permissions across different
-/// services. The specific vault service leveraged herein is called KV (Key Value) Secrets Engine -
-/// Version 2 (https://www.vaultproject.io/api/secret/kv/kv-v2.html). So while Secure Storage
-/// calls pointers to data keys, Vault has actually a secret that contains multiple key value
-/// pairs.
+/// services. The specific vault service leveraged herein is called KV (Key Value) Secrets Engine,
+/// by default Version 2 (https://www.vaultproject.io/api/secret/kv/kv-v2.html) mounted at
+/// `secret`, see `set_kv_mount` for other mounts and Version 1. So while Secure Storage calls
+/// pointers to data keys, Vault has actually a secret that contains multiple key value pairs.
+///
+/// Version 1 keeps neither versions nor creation times, so they are recorded alongside each value
+/// by the client, and check-and-set writes are not atomic: a warning is logged on the first
+/// check-and-set write to a KV v1 mount.
+///
+/// Vault does not notify clients of changes, so watches poll the metadata of their secret every
+/// watch interval, with a client that shares the token of the storage, on the thread shared by all
+/// polled watches. The value is not read, except on KV v1, which keeps no metadata.
 pub struct VaultStorage {
     client: Client,
     time_service: TimeService,
@@ -38,6 +60,9 @@ pub struct VaultStorage {
     next_renewal: AtomicU64,
     use_cas: bool,
     secret_versions: RwLock<HashMap<String, u32>>,
+    max_key_versions: u32,
+    watch_interval: Duration,
+    cas_warning: Once,
 }
 
 impl VaultStorage {
@@ -63,9 +88,85 @@ impl VaultStorage {
             next_renewal: AtomicU64::new(0),
             use_cas,
             secret_versions: RwLock::new(HashMap::new()),
+            max_key_versions: DEFAULT_MAX_KEY_VERSIONS,
+            watch_interval: DEFAULT_WATCH_INTERVAL,
+            cas_warning: Once::new(),
+        }
+    }
+
+    /// Sets the number of versions of each key retained on rotation, including the current one.
+    pub fn set_max_key_versions(&mut self, max_key_versions: u32) {
+        self.max_key_versions = max_key_versions.max(1);
+    }
+
+    /// Holds secrets in the KV secrets engine mounted at the given path, whose version is read
+    /// from Vault on first use if not given.
+    pub fn set_kv_mount(&mut self, mount: &str, kv_version: Option<KvVersion>) {
+        self.client.set_kv_mount(mount, kv_version);
+        self.secret_versions.write().clear();
+        self.cas_warning = Once::new();
+        if self.use_cas && kv_version == Some(KvVersion::V1) {
+            self.warn_if_cas_not_atomic();
+        }
+    }
+
+    /// Warns once that check-and-set writes are not atomic if secrets are held in a KV v1 mount.
+    /// Errors reading the version of the mount are left to the write itself.
+    fn warn_if_cas_not_atomic(&self) {
+        if let Ok(KvVersion::V1) = self.client.kv_version() {
+            self.cas_warning.call_once(|| {
+                aptos_logger::warn!(
+                    "Check-and-set writes to the KV v1 mount {} are a read followed by a write, \
+                    so concurrent writers may both succeed. Use a KV v2 mount instead.",
+                    self.client.kv_mount()
+                )
+            });
         }
     }
 
+    /// Sends all requests to the given Vault Enterprise namespace, which isolates the mounts,
+    /// policies and tokens of each tenant of a shared cluster.
+    pub fn set_enterprise_namespace(&mut self, enterprise_namespace: Option<String>) {
+        self.client.set_enterprise_namespace(enterprise_namespace);
+        self.secret_versions.write().clear();
+    }
+
+    /// Returns the value of the key at the given version, which is only retained by KV v2
+    /// engines: KV v1 engines only hold the current version. The metadata of the response is
+    /// that of the requested version.
+    pub fn get_version<T: DeserializeOwned>(
+        &self,
+        key: &str,
+        version: u64,
+    ) -> Result<GetResponse<T>, Error> {
+        let secret = key;
+        let key = self.unnamespaced(key);
+        let version = u32::try_from(version)
+            .map_err(|_| Error::KeyVersionNotFound(secret.into(), version.to_string()))?;
+        let resp = match self.client().read_secret_version(secret, key, version) {
+            Err(aptos_vault_client::Error::NotFound(_, _)) => {
+                return Err(Error::KeyVersionNotFound(
+                    secret.into(),
+                    version.to_string(),
+                ))
+            }
+            resp => resp?,
+        };
+        let last_update = DateTime::parse_from_rfc3339(&resp.creation_time)?.timestamp() as u64;
+        let value: T = serde_json::from_value(resp.value)?;
+        Ok(GetResponse::new_with_version(
+            value,
+            last_update,
+            resp.version as u64,
+        ))
+    }
+
+    /// Sets how often watches read the metadata of their secret, which bounds both the delay
+    /// before a change is observed and the load added to Vault by each watch.
+    pub fn set_watch_interval(&mut self, watch_interval: Duration) {
+        self.watch_interval = watch_interval;
+    }
+
     // Made into an accessor so we can get auto-renewal
     fn client(&self) -> &Client {
         if self.renew_ttl_secs.is_some() {
@@ -125,12 +226,57 @@ impl VaultStorage {
         Ok(self.client().read_ed25519_key(name)?)
     }
 
+    /// Versions pruned by Vault are told apart from unknown versions by the public keys recorded
+    /// before they were pruned, see `record_pruned_versions`.
     fn key_version(&self, name: &str, version: &Ed25519PublicKey) -> Result<u32, Error> {
-        let pubkeys = self.client().read_ed25519_key(name)?;
-        let pubkey = pubkeys.iter().find(|pubkey| version == &pubkey.value);
-        Ok(pubkey
-            .ok_or_else(|| Error::KeyVersionNotFound(name.into(), version.to_string()))?
-            .version)
+        let pubkeys = self.client().read_ed25519_key(&self.crypto_name(name))?;
+        match pubkeys.iter().find(|pubkey| version == &pubkey.value) {
+            Some(pubkey) => Ok(pubkey.version),
+            None => Err(missing_version_error(self, name, version)),
+        }
+    }
+
+    /// Records the public keys of the versions of the key at 'name' older than
+    /// `min_available_version`, before Vault deletes them, like `CryptoKVStorage` does.
+    fn record_pruned_key_versions(
+        &mut self,
+        name: &str,
+        min_available_version: u32,
+    ) -> Result<(), Error> {
+        let pruned: Vec<_> = self
+            .client()
+            .read_ed25519_key(&self.crypto_name(name))?
+            .into_iter()
+            .filter(|key| key.version < min_available_version)
+            .map(|key| key.value)
+            .collect();
+        record_pruned_versions(self, name, &pruned)
+    }
+
+    /// Recursively lists the secrets within the given path, which is either empty or ends with
+    /// the namespace separator.
+    fn list_secrets(&self, path: &str, secrets: &mut Vec<String>) -> Result<(), Error> {
+        for secret in self.client().list_secrets(path)? {
+            let secret = format!("{}{}", path, secret);
+            if secret.ends_with(NAMESPACE_SEPARATOR) {
+                self.list_secrets(&secret, secrets)?;
+            } else {
+                secrets.push(secret);
+            }
+        }
+        Ok(())
+    }
+
+    fn create_transit_key(&self, name: &str, exportable: bool) -> Result<Ed25519PublicKey, Error> {
+        let ns_name = self.crypto_name(name);
+        match self.get_public_key(name) {
+            Ok(_) => return Err(Error::KeyAlreadyExists(ns_name)),
+            Err(Error::KeyNotSet(_)) => (/* Expected this for new keys! */),
+            Err(e) => return Err(e),
+        }
+
+        self.client().create_ed25519_key(&ns_name, exportable)?;
+        self.get_public_key(name).map(|v| v.public_key)
     }
 
     fn crypto_name(&self, name: &str) -> String {
@@ -144,10 +290,16 @@ impl VaultStorage {
     }
 }
 
+/// `get_batch` and `set_batch` keep their default implementations: every key is a secret of its
+/// own, and the KV engine has no endpoint that reads or writes several secrets in one request.
 impl KVStorage for VaultStorage {
     fn available(&self) -> Result<(), Error> {
         if !self.client().unsealed()? {
-            Err(Error::InternalError("Vault is not unsealed".into()))
+            // An operator may unseal it
+            Err(Error::Unavailable {
+                retryable: true,
+                reason: "Vault is not unsealed".into(),
+            })
         } else {
             Ok(())
         }
@@ -162,13 +314,18 @@ impl KVStorage for VaultStorage {
         self.secret_versions
             .write()
             .insert(key.to_string(), resp.version);
-        Ok(GetResponse { last_update, value })
+        Ok(GetResponse::new_with_version(
+            value,
+            last_update,
+            resp.version as u64,
+        ))
     }
 
     fn set<T: Serialize>(&mut self, key: &str, value: T) -> Result<(), Error> {
         let secret = key;
         let key = self.unnamespaced(key);
         let version = if self.use_cas {
+            self.warn_if_cas_not_atomic();
             self.secret_versions.read().get(key).copied()
         } else {
             None
@@ -182,6 +339,83 @@ impl KVStorage for VaultStorage {
         Ok(())
     }
 
+    /// Relies on the check-and-set of the KV engine, whether or not `use_cas` is enabled.
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: T,
+        expected_version: u64,
+    ) -> Result<(), Error> {
+        let secret = key;
+        let key = self.unnamespaced(key);
+        self.warn_if_cas_not_atomic();
+        // Vault versions are u32, so a larger version can never match
+        let cas = u32::try_from(expected_version).unwrap_or(u32::MAX);
+        match self
+            .client()
+            .write_secret(secret, key, &serde_json::to_value(&value)?, Some(cas))
+        {
+            Ok(new_version) => {
+                self.secret_versions
+                    .write()
+                    .insert(key.to_string(), new_version);
+                Ok(())
+            }
+            Err(aptos_vault_client::Error::HttpError(400, _, body))
+                if body.contains("check-and-set") =>
+            {
+                let actual = match self.client().read_secret(secret, key) {
+                    Ok(resp) => resp.version as u64,
+                    Err(aptos_vault_client::Error::NotFound(_, _)) => 0,
+                    Err(error) => return Err(error.into()),
+                };
+                Err(Error::VersionMismatch {
+                    expected: expected_version,
+                    actual,
+                })
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Deletes every version of the secret, along with its metadata.
+    fn delete(&mut self, key: &str) -> Result<(), Error> {
+        let secret = key;
+        let key = self.unnamespaced(key);
+        self.client().delete_secret(secret)?;
+        self.secret_versions.write().remove(key);
+        Ok(())
+    }
+
+    /// Only lists the secrets of the KV engine, not the keys held by the transit engine.
+    fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        // Vault lists directories, so the keys are listed from that of the prefix
+        let path = prefix.unwrap_or("");
+        let path = path
+            .rfind(NAMESPACE_SEPARATOR)
+            .map_or("", |index| &path[..index + NAMESPACE_SEPARATOR.len()]);
+        let mut keys = vec![];
+        self.list_secrets(path, &mut keys)?;
+        Ok(filter_keys(keys, prefix))
+    }
+
+    fn watch(&self, key: &str) -> Result<KeyWatch, Error> {
+        let client = self.client().clone();
+        let secret = key.to_string();
+        let unnamespaced = self.unnamespaced(key).to_string();
+        watch::poll(key, self.watch_interval, move || {
+            match client.read_secret_metadata(&secret, &unnamespaced) {
+                Ok(resp) => Ok(Some(ValueMetadata {
+                    last_update: DateTime::parse_from_rfc3339(&resp.creation_time)?.timestamp()
+                        as u64,
+                    version: resp.version as u64,
+                })),
+                Err(aptos_vault_client::Error::NotFound(_, _)) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
     #[cfg(any(test, feature = "testing"))]
     fn reset_and_clear(&mut self) -> Result<(), Error> {
         self.secret_versions.write().clear();
@@ -193,20 +427,23 @@ impl KVStorage for VaultStorage {
 
 impl CryptoStorage for VaultStorage {
     fn create_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
-        let ns_name = self.crypto_name(name);
-        match self.get_public_key(name) {
-            Ok(_) => return Err(Error::KeyAlreadyExists(ns_name)),
-            Err(Error::KeyNotSet(_)) => (/* Expected this for new keys! */),
-            Err(e) => return Err(e),
-        }
+        self.create_transit_key(name, true)
+    }
 
-        self.client().create_ed25519_key(&ns_name, true)?;
-        self.get_public_key(name).map(|v| v.public_key)
+    /// The key is created in the transit engine with exporting disabled, and Vault signs with it.
+    fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+        self.create_transit_key(name, false)
+    }
+
+    fn supports_non_exportable_keys(&self) -> bool {
+        true
     }
 
     fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
         let name = self.crypto_name(name);
-        Ok(self.client().export_ed25519_key(&name, None)?)
+        self.client()
+            .export_ed25519_key(&name, None)
+            .map_err(|e| export_error(&name, e))
     }
 
     fn export_private_key_for_version(
@@ -214,9 +451,11 @@ impl CryptoStorage for VaultStorage {
         name: &str,
         version: Ed25519PublicKey,
     ) -> Result<Ed25519PrivateKey, Error> {
+        let vers = self.key_version(name, &version)?;
         let name = self.crypto_name(name);
-        let vers = self.key_version(&name, &version)?;
-        Ok(self.client().export_ed25519_key(&name, Some(vers))?)
+        self.client()
+            .export_ed25519_key(&name, Some(vers))
+            .map_err(|e| export_error(&name, e))
     }
 
     fn import_private_key(&mut self, name: &str, key: Ed25519PrivateKey) -> Result<(), Error> {
@@ -227,9 +466,9 @@ impl CryptoStorage for VaultStorage {
             Err(e) => return Err(e),
         }
 
-        self.client()
-            .import_ed25519_key(&ns_name, &key)
-            .map_err(|e| e.into())
+        self.client().import_ed25519_key(&ns_name, &key)?;
+        logging::log_key_import(name, &key.public_key().to_bytes());
+        Ok(())
     }
 
     fn get_public_key(&self, name: &str) -> Result<PublicKeyResponse, Error> {
@@ -269,7 +508,53 @@ impl CryptoStorage for VaultStorage {
     fn rotate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
         let ns_name = self.crypto_name(name);
         self.client().rotate_key(&ns_name)?;
-        Ok(self.client().trim_key_versions(&ns_name)?)
+        let max_version = self
+            .client()
+            .read_ed25519_key(&ns_name)?
+            .iter()
+            .map(|key| key.version)
+            .max()
+            .unwrap_or_default();
+        self.record_pruned_key_versions(
+            name,
+            (max_version + 1).saturating_sub(self.max_key_versions),
+        )?;
+        Ok(self
+            .client()
+            .trim_key_versions(&ns_name, self.max_key_versions)?)
+    }
+
+    /// Vault deletes the pruned versions, so their public keys are recorded beforehand.
+    fn prune_key_versions(
+        &mut self,
+        name: &str,
+        keep_latest_n: usize,
+        in_use_floor: Option<Ed25519PublicKey>,
+    ) -> Result<(), Error> {
+        let ns_name = self.crypto_name(name);
+        let versions: Vec<_> = self
+            .client()
+            .read_ed25519_key(&ns_name)?
+            .iter()
+            .map(|key| key.version)
+            .collect();
+        let (min_version, max_version) = match (versions.iter().min(), versions.iter().max()) {
+            (Some(min_version), Some(max_version)) => (*min_version, *max_version),
+            _ => return Err(Error::KeyNotSet(ns_name)),
+        };
+
+        let keep = u32::try_from(keep_latest_n).unwrap_or(u32::MAX).max(1);
+        let mut min_available_version = max_version.saturating_sub(keep - 1);
+        if let Some(in_use_floor) = in_use_floor {
+            min_available_version =
+                min_available_version.min(self.key_version(name, &in_use_floor)?);
+        }
+        if min_available_version > min_version {
+            self.record_pruned_key_versions(name, min_available_version)?;
+            self.client()
+                .prune_key_versions(&ns_name, min_available_version)?;
+        }
+        Ok(())
     }
 
     fn sign<T: CryptoHash + Serialize>(
@@ -294,8 +579,8 @@ impl CryptoStorage for VaultStorage {
         version: Ed25519PublicKey,
         message: &T,
     ) -> Result<Ed25519Signature, Error> {
+        let vers = self.key_version(name, &version)?;
         let name = self.crypto_name(name);
-        let vers = self.key_version(&name, &version)?;
         let mut bytes = <T::Hasher as aptos_crypto::hash::CryptoHasher>::seed().to_vec();
         bcs::serialize_into(&mut bytes, &message).map_err(|e| {
             Error::InternalError(format!(
@@ -305,6 +590,40 @@ impl CryptoStorage for VaultStorage {
         })?;
         Ok(self.client().sign_ed25519(&name, &bytes, Some(vers))?)
     }
+
+    /// Signs all messages with a single transit request.
+    fn sign_batch_using_version<T: CryptoHash + Serialize>(
+        &self,
+        name: &str,
+        version: Ed25519PublicKey,
+        messages: &[T],
+    ) -> Result<Vec<Ed25519Signature>, Error> {
+        if messages.is_empty() {
+            return Ok(Vec::new());
+        }
+        let vers = self.key_version(name, &version)?;
+        let name = self.crypto_name(name);
+        let signing_messages: Vec<_> = messages.iter().map(signing_message).collect();
+        let signing_messages: Vec<_> = signing_messages.iter().map(Vec::as_slice).collect();
+        self.client()
+            .sign_ed25519_batch(&name, &signing_messages, Some(vers))?
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| {
+                result.map_err(|error| Error::BatchSigningFailed(index, error.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Vault refuses to export transit keys created with exporting disabled.
+fn export_error(name: &str, error: aptos_vault_client::Error) -> Error {
+    match error {
+        aptos_vault_client::Error::HttpError(400, _, body) if body.contains("not exportable") => {
+            Error::KeyNotExportable(name.into())
+        }
+        e => e.into(),
+    }
 }
 
 #[cfg(test)]
@@ -460,6 +779,32 @@ pub mod policy {
             self.vault.set(&secret, value)
         }
 
+        fn set_if_version<T: Serialize>(
+            &mut self,
+            key: &str,
+            value: T,
+            expected_version: u64,
+        ) -> Result<(), Error> {
+            let secret = self.secret_name(key);
+            self.vault.set_if_version(&secret, value, expected_version)
+        }
+
+        fn delete(&mut self, key: &str) -> Result<(), Error> {
+            let secret = self.secret_name(key);
+            self.vault.delete(&secret)
+        }
+
+        fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+            let namespace_len = self.secret_name("").len();
+            let prefix = self.secret_name(prefix.unwrap_or(""));
+            Ok(self
+                .vault
+                .list_keys(Some(&prefix))?
+                .into_iter()
+                .map(|key| key[namespace_len..].to_string())
+                .collect())
+        }
+
         fn reset_and_clear(&mut self) -> Result<(), Error> {
             self.vault.reset_and_clear()?;
             self.reset_policies()
@@ -472,6 +817,15 @@ pub mod policy {
             self.vault.create_key(&ns_name)
         }
 
+        fn generate_key(&mut self, name: &str) -> Result<Ed25519PublicKey, Error> {
+            let ns_name = self.crypto_name(name);
+            self.vault.generate_key(&ns_name)
+        }
+
+        fn supports_non_exportable_keys(&self) -> bool {
+            self.vault.supports_non_exportable_keys()
+        }
+
         fn export_private_key(&self, name: &str) -> Result<Ed25519PrivateKey, Error> {
             let name = self.crypto_name(name);
             self.vault.export_private_key(&name)
@@ -506,6 +860,17 @@ pub mod policy {
             self.vault.rotate_key(&ns_name)
         }
 
+        fn prune_key_versions(
+            &mut self,
+            name: &str,
+            keep_latest_n: usize,
+            in_use_floor: Option<Ed25519PublicKey>,
+        ) -> Result<(), Error> {
+            let ns_name = self.crypto_name(name);
+            self.vault
+                .prune_key_versions(&ns_name, keep_latest_n, in_use_floor)
+        }
+
         fn sign<T: CryptoHash + Serialize>(
             &self,
             name: &str,
@@ -524,6 +889,17 @@ pub mod policy {
             let name = self.crypto_name(name);
             self.vault.sign_using_version(&name, version, message)
         }
+
+        fn sign_batch_using_version<T: CryptoHash + Serialize>(
+            &self,
+            name: &str,
+            version: Ed25519PublicKey,
+            messages: &[T],
+        ) -> Result<Vec<Ed25519Signature>, Error> {
+            let name = self.crypto_name(name);
+            self.vault
+                .sign_batch_using_version(&name, version, messages)
+        }
     }
 
     pub enum VaultEngine {
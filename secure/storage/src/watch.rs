@@ -0,0 +1,254 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Error, ValueMetadata};
+use aptos_infallible::Mutex;
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvError, RecvTimeoutError, SyncSender, TryRecvError},
+        Arc, Condvar, Mutex as StdMutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The number of events a subscriber may fall behind before it is unsubscribed.
+pub const WATCH_CAPACITY: usize = 64;
+
+/// A change of a watched key, see `KVStorage::watch`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyChangeEvent {
+    pub key: String,
+    /// The metadata of the new value, or None if the key was deleted.
+    pub metadata: Option<ValueMetadata>,
+}
+
+/// Receives the changes of a watched key, in order. Events are buffered in a bounded channel, and
+/// a subscriber that falls `WATCH_CAPACITY` events behind is unsubscribed: its channel is closed
+/// once the buffered events are received, and the key should be read and watched again.
+/// Dropping the watch unsubscribes, and stops the polling of storages that poll for changes.
+pub struct KeyWatch {
+    key: String,
+    receiver: Receiver<Option<ValueMetadata>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl KeyWatch {
+    fn new(key: &str) -> (Self, SyncSender<Option<ValueMetadata>>, Arc<AtomicBool>) {
+        let (sender, receiver) = mpsc::sync_channel(WATCH_CAPACITY);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let watch = Self {
+            key: key.into(),
+            receiver,
+            cancelled: cancelled.clone(),
+        };
+        (watch, sender, cancelled)
+    }
+
+    /// The key as named by the subscriber, which is reported in events.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Reports events under the given key, e.g., without the namespace added by a wrapper.
+    pub(crate) fn with_key(mut self, key: &str) -> Self {
+        self.key = key.into();
+        self
+    }
+
+    /// Blocks until the next change, or fails once the subscriber is unsubscribed.
+    pub fn recv(&self) -> Result<KeyChangeEvent, RecvError> {
+        self.receiver.recv().map(|metadata| self.event(metadata))
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<KeyChangeEvent, RecvTimeoutError> {
+        self.receiver
+            .recv_timeout(timeout)
+            .map(|metadata| self.event(metadata))
+    }
+
+    pub fn try_recv(&self) -> Result<KeyChangeEvent, TryRecvError> {
+        self.receiver
+            .try_recv()
+            .map(|metadata| self.event(metadata))
+    }
+
+    fn event(&self, metadata: Option<ValueMetadata>) -> KeyChangeEvent {
+        KeyChangeEvent {
+            key: self.key.clone(),
+            metadata,
+        }
+    }
+}
+
+impl Drop for KeyWatch {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The subscribers of the keys of a storage that observes its own writes, e.g., InMemoryStorage.
+#[derive(Default)]
+pub(crate) struct Watchers {
+    subscribers: Mutex<HashMap<String, Vec<Subscriber>>>,
+}
+
+struct Subscriber {
+    sender: SyncSender<Option<ValueMetadata>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Watchers {
+    pub fn subscribe(&self, key: &str) -> KeyWatch {
+        let (watch, sender, cancelled) = KeyWatch::new(key);
+        self.subscribers
+            .lock()
+            .entry(key.into())
+            .or_default()
+            .push(Subscriber { sender, cancelled });
+        watch
+    }
+
+    /// Notifies the subscribers of the key of a change, and unsubscribes those that dropped their
+    /// watch or fell behind.
+    pub fn notify(&self, key: &str, metadata: Option<ValueMetadata>) {
+        let mut subscribers = self.subscribers.lock();
+        if let Some(key_subscribers) = subscribers.get_mut(key) {
+            key_subscribers.retain(|subscriber| {
+                !subscriber.cancelled.load(Ordering::Relaxed)
+                    && subscriber.sender.try_send(metadata).is_ok()
+            });
+            if key_subscribers.is_empty() {
+                subscribers.remove(key);
+            }
+        }
+    }
+
+    /// The keys that have subscribers.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn keys(&self) -> Vec<String> {
+        self.subscribers.lock().keys().cloned().collect()
+    }
+}
+
+/// Watches a key of a storage that cannot observe all writes, e.g., as other processes write to
+/// it, by reading the metadata of the key every `interval`. Changes made within an interval are
+/// observed as one. All watches are polled in turn by a single thread, shared by all storages, so
+/// a slow read delays the polling of other watches.
+pub(crate) fn poll<F>(key: &str, interval: Duration, mut read: F) -> Result<KeyWatch, Error>
+where
+    F: FnMut() -> Result<Option<ValueMetadata>, Error> + Send + 'static,
+{
+    let last = read()?;
+    let (watch, sender, cancelled) = KeyWatch::new(key);
+    let poll = Poll {
+        key: key.into(),
+        interval,
+        next: Instant::now() + interval,
+        last,
+        read: Box::new(read),
+        subscriber: Subscriber { sender, cancelled },
+    };
+    POLLER.add(poll)?;
+    Ok(watch)
+}
+
+const POISONED: &str = "Cannot currently handle a poisoned lock";
+
+/// The watches polled by the thread of the poller, which is started by the first watch.
+static POLLER: Lazy<Poller> = Lazy::new(Poller::default);
+
+#[derive(Default)]
+struct Poller {
+    /// Watches added since the thread last looked, or None until the thread is started.
+    added: StdMutex<Option<Vec<Poll>>>,
+    wakeup: Condvar,
+}
+
+struct Poll {
+    key: String,
+    interval: Duration,
+    next: Instant,
+    last: Option<ValueMetadata>,
+    read: Box<dyn FnMut() -> Result<Option<ValueMetadata>, Error> + Send>,
+    subscriber: Subscriber,
+}
+
+impl Poller {
+    fn add(&'static self, poll: Poll) -> Result<(), Error> {
+        let mut added = self.added.lock().expect(POISONED);
+        match added.as_mut() {
+            Some(added) => added.push(poll),
+            None => {
+                thread::Builder::new()
+                    .name("storage-watch".into())
+                    .spawn(move || self.run())?;
+                *added = Some(vec![poll]);
+            }
+        }
+        self.wakeup.notify_one();
+        Ok(())
+    }
+
+    fn run(&self) {
+        let mut polls: Vec<Poll> = Vec::new();
+        loop {
+            let mut added = self.added.lock().expect(POISONED);
+            // Waits until a watch is due, or until a watch is added
+            loop {
+                if let Some(added) = added.as_mut().filter(|added| !added.is_empty()) {
+                    polls.append(added);
+                    break;
+                }
+                let now = Instant::now();
+                added = match polls.iter().map(|poll| poll.next).min() {
+                    Some(next) if next <= now => break,
+                    Some(next) => {
+                        self.wakeup
+                            .wait_timeout(added, next - now)
+                            .expect(POISONED)
+                            .0
+                    }
+                    None => self.wakeup.wait(added).expect(POISONED),
+                };
+            }
+            drop(added);
+
+            let now = Instant::now();
+            polls = polls
+                .into_iter()
+                .filter_map(|mut poll| if poll.poll(now) { Some(poll) } else { None })
+                .collect();
+        }
+    }
+}
+
+impl Poll {
+    /// Reads the metadata of the key if it is due, and notifies the subscriber of a change.
+    /// Returns false once the subscriber is unsubscribed.
+    fn poll(&mut self, now: Instant) -> bool {
+        if self.subscriber.cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+        if self.next > now {
+            return true;
+        }
+        self.next = now + self.interval;
+        let metadata = match (self.read)() {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                aptos_logger::warn!("Unable to poll {} for changes: {}", self.key, error);
+                return true;
+            }
+        };
+        if metadata == self.last {
+            return true;
+        }
+        self.last = metadata;
+        // Ends the subscription if the watch was dropped or fell behind
+        self.subscriber.sender.try_send(metadata).is_ok()
+    }
+}
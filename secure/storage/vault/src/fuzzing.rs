@@ -2,10 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    CreateTokenAuth, CreateTokenResponse, ExportKey, ExportKeyResponse, ListKeys, ListKeysResponse,
-    ListPoliciesResponse, ReadKey, ReadKeyResponse, ReadKeys, ReadSecretData, ReadSecretListData,
-    ReadSecretListResponse, ReadSecretMetadata, ReadSecretResponse, RenewTokenAuth,
-    RenewTokenResponse, SealStatusResponse, Signature, SignatureResponse,
+    BatchSignature, CreateTokenAuth, CreateTokenResponse, ExportKey, ExportKeyResponse, ListKeys,
+    ListKeysResponse, ListPoliciesResponse, ReadKey, ReadKeyResponse, ReadKeys, ReadSecretData,
+    ReadSecretListData, ReadSecretListResponse, ReadSecretMetadata, ReadSecretResponse,
+    RenewTokenAuth, RenewTokenResponse, SealStatusResponse, Signature, SignatureBatch,
+    SignatureBatchResponse, SignatureResponse,
 };
 use aptos_types::proptest_types::arb_json_value;
 use proptest::prelude::*;
@@ -273,6 +274,31 @@ prop_compose! {
     }
 }
 
+// This generates an arbitrary batched transit sign response returned by vault.
+prop_compose! {
+    pub fn arb_transit_sign_batch_response(
+    )(
+        status in any::<u16>(),
+        status_text in any::<String>(),
+        batch_results in prop::collection::vec(
+            (any::<Option<String>>(), any::<Option<String>>()),
+            0..MAX_COLLECTION_SIZE
+        ),
+    ) -> Response {
+        let batch_results = batch_results
+            .into_iter()
+            .map(|(signature, error)| BatchSignature { signature, error })
+            .collect();
+        let signature_response = SignatureBatchResponse {
+            data: SignatureBatch { batch_results },
+        };
+
+        let signature_response =
+            serde_json::to_string::<SignatureBatchResponse>(&signature_response).unwrap();
+        Response::new(status, &status_text, &signature_response)
+    }
+}
+
 // This generates an arbitrary unsealed response returned by vault.
 prop_compose! {
     pub fn arb_unsealed_response(
@@ -300,14 +326,16 @@ mod tests {
             arb_generic_response, arb_policy_list_response, arb_secret_list_response,
             arb_secret_read_response, arb_token_create_response, arb_token_renew_response,
             arb_transit_create_response, arb_transit_export_response, arb_transit_list_response,
-            arb_transit_read_response, arb_transit_sign_response, arb_unsealed_response,
+            arb_transit_read_response, arb_transit_sign_batch_response, arb_transit_sign_response,
+            arb_unsealed_response,
         },
         process_generic_response, process_policy_list_response, process_policy_read_response,
         process_secret_list_response, process_secret_read_response, process_token_create_response,
         process_token_renew_response, process_transit_create_response,
         process_transit_export_response, process_transit_list_response,
         process_transit_read_response, process_transit_restore_response,
-        process_transit_sign_response, process_unsealed_response,
+        process_transit_sign_batch_response, process_transit_sign_response,
+        process_unsealed_response,
     };
     use proptest::prelude::*;
 
@@ -379,6 +407,13 @@ mod tests {
             let _ = process_transit_sign_response(response);
         }
 
+        #[test]
+        fn process_transit_sign_batch_response_proptest(
+            response in arb_transit_sign_batch_response()
+        ) {
+            let _ = process_transit_sign_batch_response(response);
+        }
+
         #[test]
         fn process_unsealed_response_proptest(response in arb_unsealed_response()) {
             let _ = process_unsealed_response(response);
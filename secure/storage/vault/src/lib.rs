@@ -4,11 +4,15 @@
 #![forbid(unsafe_code)]
 
 pub mod dev;
+#[cfg(any(test, feature = "testing"))]
+#[cfg(any(test, feature = "testing"))]
+pub mod mock;
 
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature, ED25519_PRIVATE_KEY_LENGTH},
     PrivateKey,
 };
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::{
@@ -19,13 +23,14 @@ use std::{
 };
 use thiserror::Error;
 use ureq::Response;
+use zeroize::{Zeroize, Zeroizing};
 
 #[cfg(any(test, feature = "fuzzing"))]
 pub mod fuzzing;
 
-/// The max number of key versions held in vault at any one time.
+/// The default max number of key versions held in vault at any one time.
 /// Keys are trimmed in FIFO order.
-const MAX_NUM_KEY_VERSIONS: u32 = 4;
+pub const DEFAULT_MAX_KEY_VERSIONS: u32 = 4;
 
 /// Default request timeouts for vault operations.
 /// Note: there is a bug in ureq v 1.5.4 where it's not currently possible to set
@@ -36,6 +41,30 @@ const MAX_NUM_KEY_VERSIONS: u32 = 4;
 const DEFAULT_CONNECTION_TIMEOUT_MS: u64 = 1_000;
 const DEFAULT_RESPONSE_TIMEOUT_MS: u64 = 1_000;
 
+/// The path at which the KV secrets engine is mounted by default.
+pub const DEFAULT_KV_MOUNT: &str = "secret";
+
+/// The field of a KV v1 secret that records the creation time and version of its value, which the
+/// engine does not keep.
+const KV_V1_METADATA: &str = "__metadata";
+
+/// The header naming the Vault Enterprise namespace of a request.
+pub const NAMESPACE_HEADER: &str = "X-Vault-Namespace";
+
+/// The error returned by Vault when the check-and-set version of a write does not match.
+const CAS_MISMATCH: &str = "check-and-set parameter did not match the current version";
+
+/// The versions of the KV secrets engine (https://www.vaultproject.io/docs/secrets/kv). Version 1
+/// holds a single value per secret under `<mount>/<secret>`, while version 2 retains previous
+/// versions, with values under `<mount>/data/<secret>` and metadata under
+/// `<mount>/metadata/<secret>`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KvVersion {
+    V1,
+    V2,
+}
+
 #[derive(Debug, Error, PartialEq)]
 pub enum Error {
     #[error("Http error, status code: {0}, status text: {1}, body: {2}")]
@@ -110,6 +139,7 @@ impl From<serde_json::Error> for Error {
 /// * Data is accessed in Vault via tokens. Policies can only be granted during creation of a
 /// token, but policies can be amended afterward. So you cannot add new policies to a token, but
 /// you can increase the tokens abilities by modifying the underlying policies.
+#[derive(Clone)]
 pub struct Client {
     agent: ureq::Agent,
     host: String,
@@ -120,6 +150,13 @@ pub struct Client {
     connection_timeout_ms: u64,
     /// Timeout for generic vault responses (e.g., reads and writes).
     response_timeout_ms: u64,
+
+    /// The path at which the KV secrets engine holding secrets is mounted.
+    kv_mount: String,
+    /// The version of the KV secrets engine, detected on first use if not configured.
+    kv_version: Arc<OnceCell<KvVersion>>,
+    /// The Vault Enterprise namespace of all requests, if any.
+    enterprise_namespace: Option<String>,
 }
 
 impl Client {
@@ -152,23 +189,86 @@ impl Client {
             tls_connector,
             connection_timeout_ms,
             response_timeout_ms,
+            kv_mount: DEFAULT_KV_MOUNT.into(),
+            kv_version: Arc::new(OnceCell::with_value(KvVersion::V2)),
+            enterprise_namespace: None,
         }
     }
 
+    /// Sends all requests, including token renewals and transit operations, to the given Vault
+    /// Enterprise namespace through the `X-Vault-Namespace` header. Paths are then relative to
+    /// the namespace, e.g., the KV mount and the transit engine must be enabled within it.
+    pub fn set_enterprise_namespace(&mut self, enterprise_namespace: Option<String>) {
+        self.enterprise_namespace = enterprise_namespace;
+    }
+
+    pub fn enterprise_namespace(&self) -> Option<&str> {
+        self.enterprise_namespace.as_deref()
+    }
+
+    /// Holds secrets in the KV secrets engine mounted at the given path, instead of a KV v2
+    /// engine mounted at `DEFAULT_KV_MOUNT`. If the version of the engine is not given, it is read
+    /// from Vault on first use.
+    pub fn set_kv_mount(&mut self, mount: &str, kv_version: Option<KvVersion>) {
+        self.kv_mount = mount.trim_matches('/').into();
+        self.kv_version = Arc::new(kv_version.map_or_else(OnceCell::new, OnceCell::with_value));
+    }
+
+    pub fn kv_mount(&self) -> &str {
+        &self.kv_mount
+    }
+
+    /// Returns the version of the KV secrets engine holding secrets, reading it from Vault if it
+    /// was not configured.
+    pub fn kv_version(&self) -> Result<KvVersion, Error> {
+        self.kv_version
+            .get_or_try_init(|| self.read_kv_version(&self.kv_mount))
+            .map(|kv_version| *kv_version)
+    }
+
+    /// Reads the version of the KV secrets engine mounted at the given path.
+    pub fn read_kv_version(&self, mount: &str) -> Result<KvVersion, Error> {
+        let request = self.agent.get(&format!(
+            "{}/v1/sys/internal/ui/mounts/{}",
+            self.host,
+            mount.trim_matches('/')
+        ));
+        let resp = self.upgrade_request(request).call();
+
+        self.in_namespace(process_kv_version_response(mount, resp))
+    }
+
+    /// The URL of the value of a secret in the KV secrets engine.
+    fn kv_data_url(&self, secret: &str) -> Result<String, Error> {
+        Ok(match self.kv_version()? {
+            KvVersion::V1 => format!("{}/v1/{}/{}", self.host, self.kv_mount, secret),
+            KvVersion::V2 => format!("{}/v1/{}/data/{}", self.host, self.kv_mount, secret),
+        })
+    }
+
+    /// The URL of the metadata of a secret in the KV secrets engine, which lists and deletes
+    /// secrets. KV v1 has no metadata, and uses the path of the secret instead.
+    fn kv_metadata_url(&self, secret: &str) -> Result<String, Error> {
+        Ok(match self.kv_version()? {
+            KvVersion::V1 => format!("{}/v1/{}/{}", self.host, self.kv_mount, secret),
+            KvVersion::V2 => format!("{}/v1/{}/metadata/{}", self.host, self.kv_mount, secret),
+        })
+    }
+
     pub fn delete_policy(&self, policy_name: &str) -> Result<(), Error> {
         let request = self
             .agent
             .delete(&format!("{}/v1/sys/policy/{}", self.host, policy_name));
         let resp = self.upgrade_request(request).call();
 
-        process_generic_response(resp)
+        self.in_namespace(process_generic_response(resp))
     }
 
     pub fn list_policies(&self) -> Result<Vec<String>, Error> {
         let request = self.agent.get(&format!("{}/v1/sys/policy", self.host));
         let resp = self.upgrade_request(request).call();
 
-        process_policy_list_response(resp)
+        self.in_namespace(process_policy_list_response(resp))
     }
 
     /// Retrieves the policy at the given policy name.
@@ -178,7 +278,7 @@ impl Client {
             .get(&format!("{}/v1/sys/policy/{}", self.host, policy_name));
         let resp = self.upgrade_request(request).call();
 
-        process_policy_read_response(resp)
+        self.in_namespace(process_policy_read_response(resp))
     }
 
     /// Create a new policy in Vault, see the explanation for Policy for how the data is
@@ -190,7 +290,7 @@ impl Client {
             .post(&format!("{}/v1/sys/policy/{}", self.host, policy_name));
         let resp = self.upgrade_request(request).send_json(policy.try_into()?);
 
-        process_generic_response(resp)
+        self.in_namespace(process_generic_response(resp))
     }
 
     /// Creates a new token or identity for accessing Vault. The token will have access to anything
@@ -203,7 +303,7 @@ impl Client {
             .upgrade_request(request)
             .send_json(json!({ "policies": policies }));
 
-        process_token_create_response(resp)
+        self.in_namespace(process_token_create_response(resp))
     }
 
     pub fn renew_token_self(&self, increment: Option<u32>) -> Result<u32, Error> {
@@ -217,7 +317,7 @@ impl Client {
             request.call()
         };
 
-        process_token_renew_response(resp)
+        self.in_namespace(process_token_renew_response(resp))
     }
 
     pub fn revoke_token_self(&self) -> Result<(), Error> {
@@ -227,38 +327,78 @@ impl Client {
         let mut request = self.upgrade_request(request);
         let resp = request.call();
 
-        process_generic_response(resp)
+        self.in_namespace(process_generic_response(resp))
     }
 
     /// List all stored secrets
     pub fn list_secrets(&self, secret: &str) -> Result<Vec<String>, Error> {
-        let request = self.agent.request(
-            "LIST",
-            &format!("{}/v1/secret/metadata/{}", self.host, secret),
-        );
+        let request = self.agent.request("LIST", &self.kv_metadata_url(secret)?);
         let resp = self.upgrade_request(request).call();
 
-        process_secret_list_response(resp)
+        self.in_namespace(process_secret_list_response(resp))
     }
 
-    /// Delete a specific secret store
+    /// Delete a specific secret store, including all of its versions
     pub fn delete_secret(&self, secret: &str) -> Result<(), Error> {
-        let request = self
-            .agent
-            .delete(&format!("{}/v1/secret/metadata/{}", self.host, secret));
+        let request = self.agent.delete(&self.kv_metadata_url(secret)?);
         let resp = self.upgrade_request(request).call();
 
-        process_generic_response(resp)
+        self.in_namespace(process_generic_response(resp))
     }
 
     /// Read a key/value pair from a given secret store.
     pub fn read_secret(&self, secret: &str, key: &str) -> Result<ReadResponse<Value>, Error> {
-        let request = self
-            .agent
-            .get(&format!("{}/v1/secret/data/{}", self.host, secret));
+        let request = self.agent.get(&self.kv_data_url(secret)?);
         let resp = self.upgrade_request(request).call();
 
-        process_secret_read_response(secret, key, resp)
+        match self.kv_version()? {
+            KvVersion::V1 => self.in_namespace(process_secret_read_response_v1(secret, key, resp)),
+            KvVersion::V2 => self.in_namespace(process_secret_read_response(secret, key, resp)),
+        }
+    }
+
+    /// Read the creation time and version of the current version of a secret store, without its
+    /// value. KV v1 keeps no metadata, so the secret itself is read, see `read_secret`.
+    pub fn read_secret_metadata(&self, secret: &str, key: &str) -> Result<ReadResponse<()>, Error> {
+        match self.kv_version()? {
+            KvVersion::V1 => {
+                let resp = self.read_secret(secret, key)?;
+                Ok(ReadResponse::new(resp.creation_time, (), resp.version))
+            }
+            KvVersion::V2 => {
+                let request = self.agent.get(&self.kv_metadata_url(secret)?);
+                let resp = self.upgrade_request(request).call();
+
+                self.in_namespace(process_secret_metadata_response(secret, key, resp))
+            }
+        }
+    }
+
+    /// Read a key/value pair from the given version of a secret store. KV v1 only retains the
+    /// current version, so any other version is not found.
+    pub fn read_secret_version(
+        &self,
+        secret: &str,
+        key: &str,
+        version: u32,
+    ) -> Result<ReadResponse<Value>, Error> {
+        match self.kv_version()? {
+            KvVersion::V1 => {
+                let resp = self.read_secret(secret, key)?;
+                if resp.version == version {
+                    Ok(resp)
+                } else {
+                    Err(Error::NotFound(secret.into(), key.into()))
+                }
+            }
+            KvVersion::V2 => {
+                let mut request = self.agent.get(&self.kv_data_url(secret)?);
+                request.query("version", &version.to_string());
+                let resp = self.upgrade_request(request).call();
+
+                self.in_namespace(process_secret_read_response(secret, key, resp))
+            }
+        }
     }
 
     pub fn create_ed25519_key(&self, name: &str, exportable: bool) -> Result<(), Error> {
@@ -269,7 +409,7 @@ impl Client {
             .upgrade_request(request)
             .send_json(json!({ "type": "ed25519", "exportable": exportable }));
 
-        process_transit_create_response(name, resp)
+        self.in_namespace(process_transit_create_response(name, resp))
     }
 
     pub fn delete_key(&self, name: &str) -> Result<(), Error> {
@@ -280,14 +420,14 @@ impl Client {
             .upgrade_request(request)
             .send_json(json!({ "deletion_allowed": true }));
 
-        process_generic_response(resp)?;
+        self.in_namespace(process_generic_response(resp))?;
 
         let request = self
             .agent
             .delete(&format!("{}/v1/transit/keys/{}", self.host, name));
         let resp = self.upgrade_request(request).call();
 
-        process_generic_response(resp)
+        self.in_namespace(process_generic_response(resp))
     }
 
     pub fn export_ed25519_key(
@@ -301,19 +441,21 @@ impl Client {
         ));
         let resp = self.upgrade_request(request).call();
 
-        process_transit_export_response(name, version, resp)
+        self.in_namespace(process_transit_export_response(name, version, resp))
     }
 
     pub fn import_ed25519_key(&self, name: &str, key: &Ed25519PrivateKey) -> Result<(), Error> {
-        let backup = base64::encode(serde_json::to_string(&KeyBackup::new(key))?);
+        let backup = KeyBackup::encode(key)?;
+        let body = Zeroizing::new(serde_json::to_string(&RestoreRequest { backup: &backup })?);
         let request = self
             .agent
             .post(&format!("{}/v1/transit/restore/{}", self.host, name));
         let resp = self
             .upgrade_request(request)
-            .send_json(json!({ "backup": backup }));
+            .set("Content-Type", "application/json")
+            .send_string(&body);
 
-        process_transit_restore_response(resp)
+        self.in_namespace(process_transit_restore_response(resp))
     }
 
     pub fn list_keys(&self) -> Result<Vec<String>, Error> {
@@ -322,7 +464,7 @@ impl Client {
             .request("LIST", &format!("{}/v1/transit/keys", self.host));
         let resp = self.upgrade_request(request).call();
 
-        process_transit_list_response(resp)
+        self.in_namespace(process_transit_list_response(resp))
     }
 
     pub fn read_ed25519_key(
@@ -334,7 +476,7 @@ impl Client {
             .get(&format!("{}/v1/transit/keys/{}", self.host, name));
         let resp = self.upgrade_request(request).call();
 
-        process_transit_read_response(name, resp)
+        self.in_namespace(process_transit_read_response(name, resp))
     }
 
     pub fn rotate_key(&self, name: &str) -> Result<(), Error> {
@@ -343,7 +485,7 @@ impl Client {
             .post(&format!("{}/v1/transit/keys/{}/rotate", self.host, name));
         let resp = self.upgrade_request(request).call();
 
-        process_generic_response(resp)
+        self.in_namespace(process_generic_response(resp))
     }
 
     /// Trims the number of key versions held in vault storage. This prevents stale
@@ -353,7 +495,11 @@ impl Client {
     /// Once the key versions have been trimmed, this method returns the most
     /// recent (i.e., highest versioned) public key for the given cryptographic
     /// key name.
-    pub fn trim_key_versions(&self, name: &str) -> Result<Ed25519PublicKey, Error> {
+    pub fn trim_key_versions(
+        &self,
+        name: &str,
+        max_key_versions: u32,
+    ) -> Result<Ed25519PublicKey, Error> {
         // Read all keys and versions
         let all_pub_keys = self.read_ed25519_key(name)?;
 
@@ -370,16 +516,15 @@ impl Client {
             .ok_or_else(|| Error::NotFound("transit/".into(), name.into()))?;
 
         // Trim keys if too many versions exist
-        if (max_version - min_version) >= MAX_NUM_KEY_VERSIONS {
-            // let min_available_version = max_version - MAX_NUM_KEY_VERSIONS + 1;
+        if (max_version - min_version) >= max_key_versions {
+            // let min_available_version = max_version - max_key_versions + 1;
             let min_available_version = max_version
-                .checked_sub(MAX_NUM_KEY_VERSIONS)
+                .checked_sub(max_key_versions)
                 .and_then(|n| n.checked_add(1))
                 .ok_or_else(|| {
                     Error::OverflowError("trim_key_versions::min_available_version".into())
                 })?;
-            self.set_minimum_encrypt_decrypt_version(name, min_available_version)?;
-            self.set_minimum_available_version(name, min_available_version)?;
+            self.prune_key_versions(name, min_available_version)?;
         };
 
         let newest_pub_key = all_pub_keys
@@ -389,6 +534,13 @@ impl Client {
         Ok(newest_pub_key.value.clone())
     }
 
+    /// Deletes the key versions older than the given version, which can no longer be used.
+    /// This operation cannot be undone.
+    pub fn prune_key_versions(&self, name: &str, min_available_version: u32) -> Result<(), Error> {
+        self.set_minimum_encrypt_decrypt_version(name, min_available_version)?;
+        self.set_minimum_available_version(name, min_available_version)
+    }
+
     /// Trims the key versions according to the minimum available version specified.
     /// This operation deletes any older keys and cannot be undone.
     fn set_minimum_available_version(
@@ -403,7 +555,7 @@ impl Client {
             .upgrade_request(request)
             .send_json(json!({ "min_available_version": min_available_version }));
 
-        process_generic_response(resp)
+        self.in_namespace(process_generic_response(resp))
     }
 
     /// Sets the minimum encryption and decryption versions for a named cryptographic key.
@@ -419,7 +571,7 @@ impl Client {
             json!({ "min_encryption_version": min_version, "min_decryption_version": min_version }),
         );
 
-        process_generic_response(resp)
+        self.in_namespace(process_generic_response(resp))
     }
 
     pub fn sign_ed25519(
@@ -439,10 +591,43 @@ impl Client {
             .post(&format!("{}/v1/transit/sign/{}", self.host, name));
         let resp = self.upgrade_request(request).send_json(data);
 
-        process_transit_sign_response(resp)
+        self.in_namespace(process_transit_sign_response(resp))
     }
 
-    /// Create or update a key/value pair in a given secret store.
+    /// Signs each of the provided inputs with a single request. The results are returned in the
+    /// order of the inputs, and a failure to sign an input only fails its result.
+    pub fn sign_ed25519_batch(
+        &self,
+        name: &str,
+        data: &[&[u8]],
+        version: Option<u32>,
+    ) -> Result<Vec<Result<Ed25519Signature, Error>>, Error> {
+        let expected = data.len();
+        let batch_input: Vec<_> = data
+            .iter()
+            .map(|data| json!({ "input": base64::encode(data) }))
+            .collect();
+        let data = if let Some(version) = version {
+            json!({ "batch_input": batch_input, "key_version": version })
+        } else {
+            json!({ "batch_input": batch_input })
+        };
+
+        let request = self
+            .agent
+            .post(&format!("{}/v1/transit/sign/{}", self.host, name));
+        let resp = self.upgrade_request(request).send_json(data);
+
+        self.in_namespace(process_transit_sign_batch_response(resp, expected))
+    }
+
+    /// Create or update a key/value pair in a given secret store, and returns its new version.
+    /// If a version is given, the write only succeeds if it is the current version of the secret,
+    /// or 0 if the secret does not exist.
+    ///
+    /// KV v1 keeps no versions, so the creation time and version of the value are recorded
+    /// alongside it, and the current version is read before writing. The check-and-set version
+    /// is thus not atomic on KV v1: concurrent writers may both succeed.
     pub fn write_secret(
         &self,
         secret: &str,
@@ -450,23 +635,60 @@ impl Client {
         value: &Value,
         version: Option<u32>,
     ) -> Result<u32, Error> {
+        if let KvVersion::V1 = self.kv_version()? {
+            return self.write_secret_v1(secret, key, value, version);
+        }
+
         let payload = if let Some(version) = version {
             json!({ "data": { key: value }, "options": {"cas": version} })
         } else {
             json!({ "data": { key: value } })
         };
 
-        let request = self
-            .agent
-            .put(&format!("{}/v1/secret/data/{}", self.host, secret));
+        let request = self.agent.put(&self.kv_data_url(secret)?);
         let resp = self.upgrade_request(request).send_json(payload);
 
         if resp.ok() {
             let resp: WriteSecretResponse = serde_json::from_str(&resp.into_string()?)?;
             Ok(resp.data.version)
         } else {
-            Err(resp.into())
+            self.in_namespace(Err(resp.into()))
+        }
+    }
+
+    fn write_secret_v1(
+        &self,
+        secret: &str,
+        key: &str,
+        value: &Value,
+        version: Option<u32>,
+    ) -> Result<u32, Error> {
+        let current_version = match self.read_secret(secret, key) {
+            Ok(resp) => resp.version,
+            Err(Error::NotFound(_, _)) => 0,
+            Err(error) => return Err(error),
+        };
+        if version.map_or(false, |version| version != current_version) {
+            return Err(Error::HttpError(
+                400,
+                "Bad Request".into(),
+                json!({ "errors": [CAS_MISMATCH] }).to_string(),
+            ));
         }
+
+        let new_version = current_version
+            .checked_add(1)
+            .ok_or_else(|| Error::OverflowError(format!("version of {}", secret)))?;
+        let metadata = ReadSecretMetadata {
+            created_time: chrono::Utc::now().to_rfc3339(),
+            version: new_version,
+        };
+        let payload = json!({ key: value, KV_V1_METADATA: metadata });
+        let request = self.agent.put(&self.kv_data_url(secret)?);
+        let resp = self.upgrade_request(request).send_json(payload);
+
+        self.in_namespace(process_generic_response(resp))?;
+        Ok(new_version)
     }
 
     /// Returns whether or not the vault is unsealed (can be read from / written to). This can be
@@ -475,7 +697,7 @@ impl Client {
         let request = self.agent.get(&format!("{}/v1/sys/seal-status", self.host));
         let resp = self.upgrade_request_without_token(request).call();
 
-        process_unsealed_response(resp)
+        self.in_namespace(process_unsealed_response(resp))
     }
 
     fn upgrade_request(&self, request: ureq::Request) -> ureq::Request {
@@ -488,8 +710,26 @@ impl Client {
         request.timeout_connect(self.connection_timeout_ms);
         request.timeout(Duration::from_millis(self.response_timeout_ms));
         request.set_tls_connector(self.tls_connector.clone());
+        if let Some(enterprise_namespace) = &self.enterprise_namespace {
+            request.set(NAMESPACE_HEADER, enterprise_namespace);
+        }
         request
     }
+
+    /// Names the namespace in the status text of HTTP errors, as Vault does not, e.g., in the
+    /// 403s returned to a token or a policy of another namespace.
+    fn in_namespace<T>(&self, result: Result<T, Error>) -> Result<T, Error> {
+        match (result, &self.enterprise_namespace) {
+            (Err(Error::HttpError(status, status_text, body)), Some(enterprise_namespace)) => {
+                Err(Error::HttpError(
+                    status,
+                    format!("{} (namespace: {})", status_text, enterprise_namespace),
+                    body,
+                ))
+            }
+            (result, _) => result,
+        }
+    }
 }
 
 /// Processes a generic response returned by a vault request. This function simply just checks
@@ -573,6 +813,89 @@ pub fn process_secret_read_response(
     }
 }
 
+/// Processes the response returned by a secret metadata read vault request. A secret whose
+/// current version is deleted or destroyed is not found.
+pub fn process_secret_metadata_response(
+    secret: &str,
+    key: &str,
+    resp: Response,
+) -> Result<ReadResponse<()>, Error> {
+    match resp.status() {
+        200 => {
+            let mut resp: ReadSecretMetadataResponse = serde_json::from_str(&resp.into_string()?)?;
+            let version = resp.data.current_version;
+            match resp.data.versions.remove(&version.to_string()) {
+                Some(metadata) if metadata.deletion_time.is_empty() && !metadata.destroyed => {
+                    Ok(ReadResponse::new(metadata.created_time, (), version))
+                }
+                _ => Err(Error::NotFound(secret.into(), key.into())),
+            }
+        }
+        404 => {
+            // Explicitly clear buffer so the stream can be re-used.
+            resp.into_string()?;
+            Err(Error::NotFound(secret.into(), key.into()))
+        }
+        _ => Err(resp.into()),
+    }
+}
+
+/// Processes the response returned by a secret read vault request to a KV v1 engine. Secrets that
+/// were not written by this client have no recorded metadata, and are reported as the first
+/// version, created at the Unix epoch.
+pub fn process_secret_read_response_v1(
+    secret: &str,
+    key: &str,
+    resp: Response,
+) -> Result<ReadResponse<Value>, Error> {
+    match resp.status() {
+        200 => {
+            let mut resp: ReadSecretResponseV1 = serde_json::from_str(&resp.into_string()?)?;
+            let value = resp
+                .data
+                .remove(key)
+                .ok_or_else(|| Error::NotFound(secret.into(), key.into()))?;
+            let metadata = match resp.data.remove(KV_V1_METADATA) {
+                Some(metadata) => serde_json::from_value(metadata)?,
+                None => ReadSecretMetadata {
+                    created_time: "1970-01-01T00:00:00Z".into(),
+                    version: 1,
+                },
+            };
+            Ok(ReadResponse::new(
+                metadata.created_time,
+                value,
+                metadata.version,
+            ))
+        }
+        404 => {
+            // Explicitly clear buffer so the stream can be re-used.
+            resp.into_string()?;
+            Err(Error::NotFound(secret.into(), key.into()))
+        }
+        _ => Err(resp.into()),
+    }
+}
+
+/// Processes the response returned by a mount read vault request.
+pub fn process_kv_version_response(mount: &str, resp: Response) -> Result<KvVersion, Error> {
+    if resp.ok() {
+        let resp: ReadMountResponse = serde_json::from_str(&resp.into_string()?)?;
+        if resp.data.mount_type != "kv" {
+            return Err(Error::InternalError(format!(
+                "{} is not a KV secrets engine, but {}",
+                mount, resp.data.mount_type
+            )));
+        }
+        match resp.data.options.and_then(|options| options.version) {
+            Some(version) if version == "2" => Ok(KvVersion::V2),
+            _ => Ok(KvVersion::V1),
+        }
+    } else {
+        Err(resp.into())
+    }
+}
+
 /// Processes the response returned by a token create vault request.
 pub fn process_token_create_response(resp: Response) -> Result<String, Error> {
     if resp.ok() {
@@ -700,19 +1023,53 @@ pub fn process_transit_restore_response(resp: Response) -> Result<(), Error> {
 pub fn process_transit_sign_response(resp: Response) -> Result<Ed25519Signature, Error> {
     if resp.ok() {
         let signature: SignatureResponse = serde_json::from_str(&resp.into_string()?)?;
-        let signature = &signature.data.signature;
-        let signature_pieces: Vec<_> = signature.split(':').collect();
-        let signature = signature_pieces
-            .get(2)
-            .ok_or_else(|| Error::SerializationError(signature.into()))?;
-        Ok(Ed25519Signature::try_from(
-            base64::decode(&signature)?.as_slice(),
-        )?)
+        parse_transit_signature(&signature.data.signature)
+    } else {
+        Err(resp.into())
+    }
+}
+
+/// Processes the response returned by a batched transit sign() vault request, which must hold
+/// one result for each of the `expected` inputs.
+pub fn process_transit_sign_batch_response(
+    resp: Response,
+    expected: usize,
+) -> Result<Vec<Result<Ed25519Signature, Error>>, Error> {
+    if resp.ok() {
+        let signatures: SignatureBatchResponse = serde_json::from_str(&resp.into_string()?)?;
+        let received = signatures.data.batch_results.len();
+        if received != expected {
+            return Err(Error::InternalError(format!(
+                "Expected {} batch results, received {}",
+                expected, received
+            )));
+        }
+        Ok(signatures
+            .data
+            .batch_results
+            .into_iter()
+            .map(|result| match (result.signature, result.error) {
+                (Some(signature), _) => parse_transit_signature(&signature),
+                (None, Some(error)) => Err(Error::InternalError(error)),
+                (None, None) => Err(Error::MissingField("signature".into())),
+            })
+            .collect())
     } else {
         Err(resp.into())
     }
 }
 
+/// Decodes a transit signature, e.g., "vault:v1:<base64 signature>".
+fn parse_transit_signature(signature: &str) -> Result<Ed25519Signature, Error> {
+    let signature_pieces: Vec<_> = signature.split(':').collect();
+    let signature = signature_pieces
+        .get(2)
+        .ok_or_else(|| Error::SerializationError(signature.into()))?;
+    Ok(Ed25519Signature::try_from(
+        base64::decode(&signature)?.as_slice(),
+    )?)
+}
+
 /// Processes the response returned by a seal-status() vault request.
 pub fn process_unsealed_response(resp: Response) -> Result<bool, Error> {
     if resp.ok() {
@@ -776,15 +1133,18 @@ pub struct KeyBackup {
 
 impl KeyBackup {
     pub fn new(key: &Ed25519PrivateKey) -> Self {
-        let mut key_bytes = key.to_bytes().to_vec();
         let pub_key_bytes = key.public_key().to_bytes();
-        key_bytes.extend(&pub_key_bytes);
+        let mut key_bytes = Zeroizing::new(Vec::with_capacity(
+            ED25519_PRIVATE_KEY_LENGTH + pub_key_bytes.len(),
+        ));
+        key_bytes.extend_from_slice(&Zeroizing::new(key.to_bytes())[..]);
+        key_bytes.extend_from_slice(&pub_key_bytes);
 
         let now = chrono::Utc::now();
         let time_as_str = now.to_rfc3339();
 
         let info = KeyBackupInfo {
-            key: Some(base64::encode(key_bytes)),
+            key: Some(base64::encode(&key_bytes[..])),
             public_key: Some(base64::encode(pub_key_bytes)),
             creation_time: now.timestamp_subsec_millis(),
             time: time_as_str.clone(),
@@ -808,6 +1168,27 @@ impl KeyBackup {
         key_backup.policy.keys.insert(1, info);
         key_backup
     }
+
+    /// Encodes the backup of the key expected by the transit restore endpoint. The backup holds
+    /// the private key, so it is zeroized once dropped, as are the intermediate buffers.
+    pub fn encode(key: &Ed25519PrivateKey) -> Result<Zeroizing<String>, Error> {
+        let backup = Zeroizing::new(serde_json::to_string(&Self::new(key))?);
+        Ok(Zeroizing::new(base64::encode(backup.as_bytes())))
+    }
+}
+
+impl Drop for KeyBackup {
+    fn drop(&mut self) {
+        for info in self.policy.keys.values_mut() {
+            info.key.zeroize();
+        }
+    }
+}
+
+/// The body of a transit restore request.
+#[derive(Serialize)]
+struct RestoreRequest<'a> {
+    backup: &'a str,
 }
 
 #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -1054,6 +1435,61 @@ struct ReadSecretMetadata {
     version: u32,
 }
 
+/// Below is a sample output of ReadSecretResponseV1, for a KV v1 engine. Only the data is
+/// returned, see KV_V1_METADATA for the metadata recorded by this client.
+/// {
+///   "data": {
+///     "foo": "bar"
+///   }
+/// }
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct ReadSecretResponseV1 {
+    data: BTreeMap<String, Value>,
+}
+
+/// Below is a sample output of ReadSecretMetadataResponse, for a KV v2 engine. Only fields
+/// leveraged by this framework are decoded.
+/// {
+///   "data": {
+///     "created_time": "2018-03-22T02:24:06.945319214Z",
+///     "current_version": 2,
+///     "updated_time": "2018-03-22T02:36:43.986212308Z",
+///     "versions": {
+///       "1": {
+///         "created_time": "2018-03-22T02:24:06.945319214Z",
+///         "deletion_time": "",
+///         "destroyed": false
+///       },
+///       "2": {
+///         "created_time": "2018-03-22T02:36:43.986212308Z",
+///         "deletion_time": "",
+///         "destroyed": false
+///       }
+///     }
+///   }
+/// }
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct ReadSecretMetadataResponse {
+    data: ReadSecretMetadataData,
+}
+
+/// See ReadSecretMetadataResponse
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct ReadSecretMetadataData {
+    current_version: u32,
+    versions: BTreeMap<String, ReadSecretVersionMetadata>,
+}
+
+/// See ReadSecretMetadataResponse
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct ReadSecretVersionMetadata {
+    created_time: String,
+    #[serde(default)]
+    deletion_time: String,
+    #[serde(default)]
+    destroyed: bool,
+}
+
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
 struct WriteSecretResponse {
     data: ReadSecretMetadata,
@@ -1178,6 +1614,33 @@ struct Signature {
     signature: String,
 }
 
+/// Below is an example of SignatureBatchResponse, in which the second input could not be signed.
+/// {
+///   "data": {
+///     "batch_results": [
+///       { "signature": "vault:v1:MEUCIQCyb869d7KWuA0hBM9b5NJrmWzMW3/pT+0XYCM9VmGR+QIgWWF6ufi4OS2xo1eS2V5IeJQfsi59qeMWtgX0LipxEHI=" },
+///       { "error": "unable to decode input as base64: illegal base64 data at input byte 1" }
+///     ]
+///   }
+/// }
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct SignatureBatchResponse {
+    data: SignatureBatch,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct SignatureBatch {
+    batch_results: Vec<BatchSignature>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct BatchSignature {
+    #[serde(default)]
+    signature: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
 /// Below is an example of SealStatusResponse. Only the fields leveraged by this framework are
 /// decoded.
 /// {
@@ -1195,3 +1658,31 @@ struct Signature {
 struct SealStatusResponse {
     sealed: bool,
 }
+
+/// Below is a sample output of ReadMountResponse. Only the fields leveraged by this framework are
+/// decoded. The options are null for KV v1 engines mounted without a version.
+/// {
+///   "data": {
+///     "type": "kv",
+///     "path": "secret/",
+///     "options": {
+///       "version": "2"
+///     }
+///   }
+/// }
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct ReadMountResponse {
+    data: ReadMountData,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct ReadMountData {
+    #[serde(rename = "type")]
+    mount_type: String,
+    options: Option<ReadMountOptions>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct ReadMountOptions {
+    version: Option<String>,
+}
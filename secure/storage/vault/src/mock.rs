@@ -0,0 +1,352 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{Client, KvVersion, CAS_MISMATCH};
+use aptos_http_mock::{HttpMock, Request, Response};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    sync::{Arc, Mutex},
+};
+
+const TOKEN: &str = "mock_token";
+
+/// A minimal Vault server running within the process, for tests that do not require a Vault
+/// binary. It serves the seal status, the mounts, and KV secrets engines of both versions mounted
+/// at the given paths. As by Vault, authenticated requests with another token are denied with a
+/// 403. Namespaces are not checked, and other engines (e.g., transit) are not supported, but all
+/// requests are recorded. The server runs until the process exits.
+pub struct MockVault {
+    server: HttpMock,
+    state: Arc<Mutex<MockState>>,
+}
+
+/// A request received by a MockVault.
+pub type MockRequest = Request;
+
+/// The versions of each secret of a KV engine, by mount and path. KV v1 secrets only have one.
+struct MockState {
+    mounts: BTreeMap<String, KvVersion>,
+    secrets: BTreeMap<(String, String), Vec<(String, Value)>>,
+    requests: Vec<MockRequest>,
+}
+
+impl MockVault {
+    pub fn start(mounts: &[(&str, KvVersion)]) -> Result<Self, io::Error> {
+        let state = Arc::new(Mutex::new(MockState {
+            mounts: mounts
+                .iter()
+                .map(|(mount, kv_version)| (mount.to_string(), *kv_version))
+                .collect(),
+            secrets: BTreeMap::new(),
+            requests: vec![],
+        }));
+
+        let server_state = state.clone();
+        let server = HttpMock::start("mock-vault", move |request| {
+            let mut state = server_state.lock().unwrap();
+            let response = handle(&mut state, &request);
+            state.requests.push(request);
+            response
+        })?;
+        Ok(Self { server, state })
+    }
+
+    /// The requests received so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn host(&self) -> &str {
+        self.server.url()
+    }
+
+    pub fn token(&self) -> &str {
+        TOKEN
+    }
+
+    /// A client of the KV engine mounted at the given path, which detects its version.
+    pub fn client(&self, mount: &str) -> Client {
+        let mut client = Client::new(self.host().into(), TOKEN.into(), None, None, None);
+        client.set_kv_mount(mount, None);
+        client
+    }
+}
+
+fn errors(status: u16, error: &str) -> Response {
+    (status, Some(json!({ "errors": [error] })))
+}
+
+fn handle(state: &mut MockState, request: &Request) -> Response {
+    let (method, query) = (request.method.as_str(), request.query.as_str());
+    let path = match request.path.strip_prefix("/v1/") {
+        Some(path) => path,
+        None => return errors(404, "unsupported path"),
+    };
+
+    if path == "sys/seal-status" {
+        return (200, Some(json!({ "sealed": false })));
+    }
+    if request.header("X-Vault-Token") != Some(TOKEN) {
+        return errors(403, "permission denied");
+    }
+    if let Some(mount) = path.strip_prefix("sys/internal/ui/mounts/") {
+        let mount = mount.trim_end_matches('/');
+        let options = match state.mounts.get(mount) {
+            Some(KvVersion::V1) => Value::Null,
+            Some(KvVersion::V2) => json!({ "version": "2" }),
+            None => return errors(400, "no matching mount"),
+        };
+        let data = json!({ "type": "kv", "path": format!("{}/", mount), "options": options });
+        return (200, Some(json!({ "data": data })));
+    }
+
+    let (mount, path) = path.split_once('/').unwrap_or((path, ""));
+    let kv_version = match state.mounts.get(mount) {
+        Some(kv_version) => *kv_version,
+        None => return errors(404, "no handler for route"),
+    };
+    let mount = mount.to_string();
+    let body = request.json();
+    match kv_version {
+        KvVersion::V1 => match method {
+            "GET" => match state.secrets.get(&(mount, path.into())) {
+                Some(versions) => (200, Some(json!({ "data": versions[0].1 }))),
+                None => errors(404, ""),
+            },
+            "POST" | "PUT" => {
+                let created_time = chrono::Utc::now().to_rfc3339();
+                state
+                    .secrets
+                    .insert((mount, path.into()), vec![(created_time, body)]);
+                (204, None)
+            }
+            "DELETE" => {
+                state.secrets.remove(&(mount, path.into()));
+                (204, None)
+            }
+            "LIST" => list(state, &mount, path),
+            _ => errors(405, "unsupported method"),
+        },
+        KvVersion::V2 => {
+            if let Some(path) = path.strip_prefix("data/") {
+                let key = (mount, path.to_string());
+                match method {
+                    "GET" => {
+                        let versions = state.secrets.get(&key).map_or(&[][..], |v| &v[..]);
+                        let version = query
+                            .strip_prefix("version=")
+                            .and_then(|version| version.parse().ok())
+                            .filter(|version| *version > 0)
+                            .unwrap_or_else(|| versions.len());
+                        match versions.get(version.wrapping_sub(1)) {
+                            Some((created_time, data)) => {
+                                let metadata =
+                                    json!({ "created_time": created_time, "version": version });
+                                (
+                                    200,
+                                    Some(json!({ "data": { "data": data, "metadata": metadata } })),
+                                )
+                            }
+                            None => errors(404, ""),
+                        }
+                    }
+                    "POST" | "PUT" => {
+                        let current_version = state.secrets.get(&key).map_or(0, Vec::len);
+                        if let Some(cas) = body["options"]["cas"].as_u64() {
+                            if cas != current_version as u64 {
+                                return errors(400, CAS_MISMATCH);
+                            }
+                        }
+                        let versions = state.secrets.entry(key).or_default();
+                        let created_time = chrono::Utc::now().to_rfc3339();
+                        versions.push((created_time.clone(), body["data"].clone()));
+                        let metadata =
+                            json!({ "created_time": created_time, "version": versions.len() });
+                        (200, Some(json!({ "data": metadata })))
+                    }
+                    _ => errors(405, "unsupported method"),
+                }
+            } else if let Some(path) = path.strip_prefix("metadata/") {
+                match method {
+                    "GET" => match state.secrets.get(&(mount, path.into())) {
+                        Some(versions) => {
+                            let metadata: BTreeMap<_, _> = versions
+                                .iter()
+                                .enumerate()
+                                .map(|(index, (created_time, _))| {
+                                    let metadata = json!({
+                                        "created_time": created_time,
+                                        "deletion_time": "",
+                                        "destroyed": false,
+                                    });
+                                    ((index + 1).to_string(), metadata)
+                                })
+                                .collect();
+                            let data =
+                                json!({ "current_version": versions.len(), "versions": metadata });
+                            (200, Some(json!({ "data": data })))
+                        }
+                        None => errors(404, ""),
+                    },
+                    "DELETE" => {
+                        state.secrets.remove(&(mount, path.into()));
+                        (204, None)
+                    }
+                    "LIST" => list(state, &mount, path),
+                    _ => errors(405, "unsupported method"),
+                }
+            } else {
+                errors(404, "no handler for route")
+            }
+        }
+    }
+}
+
+/// Lists the secrets and folders directly within the given path of a mount.
+fn list(state: &MockState, mount: &str, path: &str) -> Response {
+    let keys: BTreeSet<_> = state
+        .secrets
+        .keys()
+        .filter(|(secret_mount, _)| secret_mount == mount)
+        .filter_map(|(_, secret)| secret.strip_prefix(path))
+        .map(|name| match name.find('/') {
+            Some(index) => name[..=index].to_string(),
+            None => name.to_string(),
+        })
+        .collect();
+    if keys.is_empty() {
+        errors(404, "")
+    } else {
+        (200, Some(json!({ "data": { "keys": keys } })))
+    }
+}
+
+#[test]
+fn mock_vault_kv_versions() {
+    use crate::Error;
+
+    let vault = MockVault::start(&[("secret", KvVersion::V2), ("kv", KvVersion::V1)]).unwrap();
+    vault.client("secret").unsealed().unwrap();
+    assert_eq!(vault.client("secret").kv_version(), Ok(KvVersion::V2));
+    assert_eq!(vault.client("/kv/").kv_version(), Ok(KvVersion::V1));
+    assert!(matches!(
+        vault.client("missing").kv_version(),
+        Err(Error::HttpError(400, _, _))
+    ));
+
+    for mount in ["secret", "kv"] {
+        let client = vault.client(mount);
+        let value = json!(1);
+        assert_eq!(client.write_secret("a/b", "b", &value, Some(0)), Ok(1));
+        assert_eq!(client.write_secret("a/b", "b", &json!(2), Some(1)), Ok(2));
+        assert!(matches!(
+            client.write_secret("a/b", "b", &json!(3), Some(1)),
+            Err(Error::HttpError(400, _, body)) if body.contains(CAS_MISMATCH)
+        ));
+        let resp = client.read_secret("a/b", "b").unwrap();
+        assert_eq!((resp.value, resp.version), (json!(2), 2));
+        let metadata = client.read_secret_metadata("a/b", "b").unwrap();
+        assert_eq!(
+            (metadata.creation_time, metadata.version),
+            (resp.creation_time, 2)
+        );
+        assert_eq!(client.list_secrets("").unwrap(), vec!["a/"]);
+        assert_eq!(client.list_secrets("a/").unwrap(), vec!["b"]);
+
+        // Only KV v2 retains previous versions
+        let resp = client.read_secret_version("a/b", "b", 2).unwrap();
+        assert_eq!(resp.value, json!(2));
+        match client.read_secret_version("a/b", "b", 1) {
+            Ok(resp) => assert_eq!((mount, resp.value, resp.version), ("secret", value, 1)),
+            Err(error) => assert_eq!(
+                (mount, error),
+                ("kv", Error::NotFound("a/b".into(), "b".into()))
+            ),
+        }
+
+        client.delete_secret("a/b").unwrap();
+        assert_eq!(
+            client.read_secret("a/b", "b").unwrap_err(),
+            Error::NotFound("a/b".into(), "b".into())
+        );
+        assert_eq!(
+            client.read_secret_metadata("a/b", "b").unwrap_err(),
+            Error::NotFound("a/b".into(), "b".into())
+        );
+        assert!(client.list_secrets("").unwrap().is_empty());
+    }
+}
+
+#[test]
+fn mock_vault_enterprise_namespace() {
+    use crate::{Error, Policy, NAMESPACE_HEADER};
+    use aptos_crypto::ed25519::Ed25519PrivateKey;
+    use std::convert::TryFrom;
+
+    let vault = MockVault::start(&[("secret", KvVersion::V2)]).unwrap();
+    let mut client = Client::new(vault.host().into(), vault.token().into(), None, None, None);
+    client.set_enterprise_namespace(Some("team/validators".into()));
+    client.set_kv_mount("secret", None);
+
+    // Operations on the KV engine succeed, while other engines are not served by the mock
+    client.unsealed().unwrap();
+    client.write_secret("a", "a", &json!(1), None).unwrap();
+    client.read_secret("a", "a").unwrap();
+    client.read_secret_version("a", "a", 1).unwrap();
+    client.list_secrets("").unwrap();
+    client.delete_secret("a").unwrap();
+    let key = Ed25519PrivateKey::try_from(&[7; 32][..]).unwrap();
+    let results = vec![
+        client.renew_token_self(None).map(|_| ()),
+        client.create_token(vec!["policy"]).map(|_| ()),
+        client.revoke_token_self(),
+        client.list_policies().map(|_| ()),
+        client.read_policy("policy").map(|_| ()),
+        client.set_policy("policy", &Policy::new()),
+        client.delete_policy("policy"),
+        client.create_ed25519_key("key", true),
+        client.import_ed25519_key("key", &key),
+        client.export_ed25519_key("key", None).map(|_| ()),
+        client.read_ed25519_key("key").map(|_| ()),
+        client.list_keys().map(|_| ()),
+        client.rotate_key("key"),
+        client.prune_key_versions("key", 1),
+        client.sign_ed25519("key", b"message", None).map(|_| ()),
+        client
+            .sign_ed25519_batch("key", &[&b"message"[..]], None)
+            .map(|_| ()),
+        client.delete_key("key"),
+    ];
+
+    // HTTP errors name the namespace, while some 404s are reported as missing keys
+    let mut http_errors = 0;
+    for result in results {
+        match result {
+            Err(Error::HttpError(404, status_text, _)) => {
+                assert_eq!(status_text, "Not Found (namespace: team/validators)");
+                http_errors += 1;
+            }
+            Err(Error::NotFound(_, _)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+    assert!(http_errors > 0);
+
+    // Every request, including the mount detection, is sent to the namespace
+    let requests = vault.requests();
+    assert!(requests.len() >= 24);
+    assert!(requests
+        .iter()
+        .any(|request| request.path.starts_with("/v1/sys/internal/ui/mounts/")));
+    assert!(requests
+        .iter()
+        .any(|request| request.path == "/v1/auth/token/renew-self"));
+    assert!(requests
+        .iter()
+        .any(|request| request.path == "/v1/transit/sign/key"));
+    for request in requests {
+        assert_eq!(request.header(NAMESPACE_HEADER), Some("team/validators"));
+    }
+}
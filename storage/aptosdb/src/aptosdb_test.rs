@@ -8,6 +8,8 @@ use crate::{
 };
 use aptos_crypto::hash::CryptoHash;
 #[allow(unused_imports)]
+use aptos_crypto::hash::ACCUMULATOR_PLACEHOLDER_HASH;
+#[allow(unused_imports)]
 use aptos_jellyfish_merkle::node_type::{Node, NodeKey};
 use aptos_temppath::TempPath;
 #[allow(unused_imports)]
@@ -17,7 +19,9 @@ use aptos_types::transaction::Transaction;
 use aptos_types::{
     account_address::{AccountAddress, HashAccountAddress},
     account_config::AccountResource,
+    block_info::BlockInfo,
     contract_event::ContractEvent,
+    epoch_state::EpochState,
     ledger_info::LedgerInfo,
     proof::SparseMerkleLeafNode,
     vm_status::{KeptVMStatus, StatusCode},
@@ -698,3 +702,63 @@ fn test_rocksdb_properties_reporter() {
     std::thread::sleep(Duration::from_secs(1));
     assert_eq!(get_metric(), 1);
 }
+
+#[test]
+fn test_force_commit_genesis() {
+    let genesis_li = |accumulator_hash| {
+        LedgerInfo::new(
+            BlockInfo::new(
+                0,
+                0,
+                HashValue::zero(),
+                accumulator_hash,
+                0,
+                0,
+                Some(EpochState::empty()),
+            ),
+            HashValue::zero(),
+        )
+    };
+    let state_key = StateKey::AccountAddressKey(AccountAddress::ZERO);
+    let state_value = StateValue::from(AccountStateBlob::from(vec![1]));
+    let state_items = vec![(state_key.clone(), state_value.clone())];
+
+    // The accumulator hash must match the genesis transaction, unless it is left to the DB
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    assert!(db
+        .force_commit_genesis(genesis_li(HashValue::random()), state_items.clone())
+        .is_err());
+    db.force_commit_genesis(
+        genesis_li(*ACCUMULATOR_PLACEHOLDER_HASH),
+        state_items.clone(),
+    )
+    .unwrap();
+
+    let latest_li = db.get_latest_ledger_info().unwrap();
+    let txn_info = db.get_latest_transaction_info_option().unwrap().unwrap().1;
+    assert_eq!(latest_li.ledger_info().version(), 0);
+    assert!(latest_li.ledger_info().ends_epoch());
+    assert_eq!(
+        latest_li.ledger_info().transaction_accumulator_hash(),
+        txn_info.hash()
+    );
+    assert_eq!(
+        db.get_latest_state_value(state_key).unwrap(),
+        Some(state_value)
+    );
+    db.get_startup_info().unwrap().unwrap();
+
+    // Committing the same genesis to another DB yields the same ledger info
+    let other_tmp_dir = TempPath::new();
+    let other_db = AptosDB::new_for_test(&other_tmp_dir);
+    other_db
+        .force_commit_genesis(latest_li.ledger_info().clone(), state_items.clone())
+        .unwrap();
+    assert_eq!(other_db.get_latest_ledger_info().unwrap(), latest_li);
+
+    // The DB is no longer empty
+    assert!(db
+        .force_commit_genesis(latest_li.ledger_info().clone(), state_items)
+        .is_err());
+}
@@ -64,6 +64,7 @@ use aptos_types::{
     account_address::AccountAddress,
     contract_event::{ContractEvent, EventByVersionWithProof, EventWithProof},
     epoch_change::EpochChangeProof,
+    epoch_state::EpochState,
     event::EventKey,
     ledger_info::LedgerInfoWithSignatures,
     proof::{
@@ -427,6 +428,28 @@ impl AptosDB {
         Ok((lis, more))
     }
 
+    fn get_account_transactions_impl(
+        &self,
+        address: AccountAddress,
+        start_seq_num: u64,
+        limit: u64,
+        include_events: bool,
+        ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+        let txns_with_proofs = self
+            .transaction_store
+            .get_account_transaction_version_iter(address, start_seq_num, limit, ledger_version)?
+            .map(|result| {
+                let (_seq_num, txn_version) = result?;
+                self.get_transaction_with_proof(txn_version, ledger_version, include_events)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(AccountTransactionsWithProof::new(txns_with_proofs))
+    }
+
     fn get_transaction_with_proof(
         &self,
         version: Version,
@@ -685,6 +708,14 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_latest_epoch_state(&self) -> Result<EpochState> {
+        gauged_api("get_latest_epoch_state", || {
+            let latest_ledger_info = self.ledger_store.get_latest_ledger_info()?;
+            self.ledger_store
+                .get_epoch_state(latest_ledger_info.ledger_info().next_block_epoch())
+        })
+    }
+
     fn get_account_transaction(
         &self,
         address: AccountAddress,
@@ -711,23 +742,40 @@ impl DbReader for AptosDB {
         ledger_version: Version,
     ) -> Result<AccountTransactionsWithProof> {
         gauged_api("get_account_transactions", || {
-            error_if_too_many_requested(limit, MAX_LIMIT)?;
-
-            let txns_with_proofs = self
-                .transaction_store
-                .get_account_transaction_version_iter(
-                    address,
-                    start_seq_num,
-                    limit,
-                    ledger_version,
-                )?
-                .map(|result| {
-                    let (_seq_num, txn_version) = result?;
-                    self.get_transaction_with_proof(txn_version, ledger_version, include_events)
-                })
-                .collect::<Result<Vec<_>>>()?;
+            self.get_account_transactions_impl(
+                address,
+                start_seq_num,
+                limit,
+                include_events,
+                ledger_version,
+            )
+        })
+    }
 
-            Ok(AccountTransactionsWithProof::new(txns_with_proofs))
+    fn get_account_transactions_paginated(
+        &self,
+        address: AccountAddress,
+        after_seq_num: Option<u64>,
+        limit: u64,
+        include_events: bool,
+        ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        gauged_api("get_account_transactions_paginated", || {
+            // Sequence numbers are contiguous, so the next page starts right after the cursor
+            let start_seq_num = match after_seq_num {
+                Some(seq_num) => match seq_num.checked_add(1) {
+                    Some(start_seq_num) => start_seq_num,
+                    None => return Ok(AccountTransactionsWithProof::new_empty()),
+                },
+                None => 0,
+            };
+            self.get_account_transactions_impl(
+                address,
+                start_seq_num,
+                limit,
+                include_events,
+                ledger_version,
+            )
         })
     }
 
@@ -1009,6 +1057,25 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_state_value_at_versions(
+        &self,
+        state_key: StateKey,
+        versions: &[Version],
+    ) -> Result<Vec<Option<StateValue>>> {
+        gauged_api("get_state_value_at_versions", || {
+            if let Some(max_version) = versions.iter().max() {
+                let latest_version = self.get_latest_version()?;
+                ensure!(
+                    *max_version <= latest_version,
+                    "The queried version {} is greater than committed version {}.",
+                    max_version,
+                    latest_version
+                );
+            }
+            self.state_store.get_value_at_versions(&state_key, versions)
+        })
+    }
+
     fn get_startup_info(&self) -> Result<Option<StartupInfo>> {
         gauged_api("get_startup_info", || self.ledger_store.get_startup_info())
     }
@@ -1050,6 +1117,14 @@ impl DbReader for AptosDB {
         })
     }
 
+    fn get_gas_usage_by_transaction(&self, version: Version) -> Result<u64> {
+        gauged_api("get_gas_usage_by_transaction", || {
+            self.ledger_store
+                .get_transaction_info(version)
+                .map(|txn_info| txn_info.gas_used())
+        })
+    }
+
     fn get_block_timestamp(&self, version: u64) -> Result<u64> {
         gauged_api("get_block_timestamp", || {
             let ts = match self.transaction_store.get_block_metadata(version)? {
@@ -1394,6 +1469,107 @@ impl DbWriter for AptosDB {
             self.db.write_schemas(db_batch)
         })
     }
+
+    /// The state is committed by a genesis transaction whose write set holds the given items.
+    /// If the transaction accumulator hash of `genesis_li` is the placeholder hash, it is
+    /// replaced by the root hash resulting from that transaction, otherwise it must match.
+    #[cfg(any(test, feature = "testing"))]
+    fn force_commit_genesis(
+        &self,
+        genesis_li: aptos_types::ledger_info::LedgerInfo,
+        state_items: Vec<(StateKey, StateValue)>,
+    ) -> Result<()> {
+        use aptos_crypto::hash::{CryptoHash, ACCUMULATOR_PLACEHOLDER_HASH};
+        use aptos_types::{
+            block_info::BlockInfo,
+            ledger_info::LedgerInfo,
+            transaction::{ChangeSet as GenesisChangeSet, WriteSetPayload},
+            vm_status::KeptVMStatus,
+            write_set::{WriteOp, WriteSetMut},
+        };
+
+        gauged_api("force_commit_genesis", || {
+            ensure!(
+                self.ledger_store.get_latest_ledger_info_option().is_none(),
+                "Genesis can only be committed to an empty DB.",
+            );
+            ensure!(
+                genesis_li.version() == 0 && genesis_li.ends_epoch(),
+                "Genesis ledger info must end the epoch at version 0, got version {}.",
+                genesis_li.version(),
+            );
+
+            let write_set = WriteSetMut::new(
+                state_items
+                    .iter()
+                    .map(|(key, value)| {
+                        let bytes = value.maybe_bytes.clone().ok_or_else(|| {
+                            format_err!("Genesis state value for {:?} is empty.", key)
+                        })?;
+                        Ok((key.clone(), WriteOp::Value(bytes)))
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            )
+            .freeze()?;
+            let transaction = Transaction::GenesisTransaction(WriteSetPayload::Direct(
+                GenesisChangeSet::new(write_set.clone(), vec![]),
+            ));
+            let state_updates: HashMap<_, _> = state_items.into_iter().collect();
+
+            // The state root is computed against the empty DB and discarded, the transaction
+            // below commits the same state
+            let state_root_hash = self.state_store.put_value_sets(
+                vec![&state_updates],
+                None,
+                0,
+                &mut ChangeSet::new(),
+            )?[0];
+            let txn_info = TransactionInfo::new(
+                transaction.hash(),
+                state_root_hash,
+                *ACCUMULATOR_PLACEHOLDER_HASH,
+                0,
+                KeptVMStatus::Executed,
+            );
+
+            let genesis_li =
+                if genesis_li.transaction_accumulator_hash() == *ACCUMULATOR_PLACEHOLDER_HASH {
+                    // The accumulator of a single transaction is rooted at its transaction info
+                    let commit_info = genesis_li.commit_info();
+                    LedgerInfo::new(
+                        BlockInfo::new(
+                            commit_info.epoch(),
+                            commit_info.round(),
+                            commit_info.id(),
+                            txn_info.hash(),
+                            commit_info.version(),
+                            commit_info.timestamp_usecs(),
+                            commit_info.next_epoch_state().cloned(),
+                        ),
+                        genesis_li.consensus_data_hash(),
+                    )
+                } else {
+                    genesis_li
+                };
+
+            let txn_to_commit = TransactionToCommit::new(
+                transaction,
+                txn_info,
+                state_updates,
+                None,
+                write_set,
+                vec![],
+            );
+            self.save_transactions(
+                &[txn_to_commit],
+                0,
+                Some(&LedgerInfoWithSignatures::new(
+                    genesis_li,
+                    Default::default(),
+                )),
+            )
+        })
+    }
 }
 
 // Convert requested range and order to a range in ascending order.
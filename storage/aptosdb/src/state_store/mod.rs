@@ -169,7 +169,45 @@ impl StateStore {
         }
     }
 
-    #[cfg(test)]
+    /// Gets the state values of the state key at each of the given versions, using the state
+    /// value index. All versions are served by a single scan over the versions at which the key
+    /// was written, whatever the order in which they are given.
+    pub fn get_value_at_versions(
+        &self,
+        state_key: &StateKey,
+        versions: &[Version],
+    ) -> Result<Vec<Option<StateValue>>> {
+        let mut indices: Vec<usize> = (0..versions.len()).collect();
+        indices.sort_by_key(|index| versions[*index]);
+
+        let mut iter = self.db.iter::<StateValueIndexSchema>(Default::default())?;
+        iter.seek(&(state_key.clone(), 0))?;
+        let mut next_write = iter.next().transpose()?;
+        // The leaf written last at or before the version being served, and its value once read
+        let mut latest_write: Option<(NodeKey, Option<Option<StateValue>>)> = None;
+        let mut values = vec![None; versions.len()];
+        for index in indices {
+            while let Some(((db_state_key, db_version), num_nibbles)) = &next_write {
+                if db_state_key != state_key || *db_version > versions[index] {
+                    break;
+                }
+                let node_key = NodeKey::new(
+                    *db_version,
+                    NibblePath::new_from_state_key(state_key, *num_nibbles as usize),
+                );
+                latest_write = Some((node_key, None));
+                next_write = iter.next().transpose()?;
+            }
+            if let Some((node_key, value)) = &mut latest_write {
+                if value.is_none() {
+                    *value = Some(self.get_value_by_node_key(node_key)?);
+                }
+                values[index] = value.clone().flatten();
+            }
+        }
+        Ok(values)
+    }
+
     fn get_value_by_node_key(&self, node_key: &NodeKey) -> Result<Option<StateValue>> {
         if let Some(Node::Leaf(leaf)) = self.db.get::<JellyfishMerkleNodeSchema>(node_key)? {
             Ok(Some(leaf.value().value.clone()))
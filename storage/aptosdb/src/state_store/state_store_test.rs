@@ -296,6 +296,49 @@ fn test_get_values_by_key_prefix() {
     assert_eq!(*key_value_map.get(&key5).unwrap(), value5_v2);
 }
 
+#[test]
+fn test_get_value_at_versions() {
+    let tmp_dir = TempPath::new();
+    let db = AptosDB::new_for_test(&tmp_dir);
+    let store = &db.state_store;
+    let address = AccountAddress::new([12u8; AccountAddress::LENGTH]);
+
+    let key1 = StateKey::AccessPath(AccessPath::new(address, b"state_key1".to_vec()));
+    let key2 = StateKey::AccessPath(AccessPath::new(address, b"state_key2".to_vec()));
+
+    let value1_v1 = StateValue::from(String::from("value1_v1").into_bytes());
+    let value1_v3 = StateValue::from(String::from("value1_v3").into_bytes());
+    let value2_v0 = StateValue::from(String::from("value2_v0").into_bytes());
+    let value2_v2 = StateValue::from(String::from("value2_v2").into_bytes());
+
+    put_value_set(store, vec![(key2.clone(), value2_v0)], 0);
+    put_value_set(store, vec![(key1.clone(), value1_v1.clone())], 1);
+    put_value_set(store, vec![(key2.clone(), value2_v2)], 2);
+    put_value_set(store, vec![(key1.clone(), value1_v3.clone())], 3);
+
+    // Versions may be unordered and repeated, and key1 did not exist in version 0.
+    assert_eq!(
+        store
+            .get_value_at_versions(&key1, &[3, 0, 2, 1, 3, 4])
+            .unwrap(),
+        vec![
+            Some(value1_v3.clone()),
+            None,
+            Some(value1_v1.clone()),
+            Some(value1_v1),
+            Some(value1_v3.clone()),
+            Some(value1_v3),
+        ]
+    );
+    assert!(store.get_value_at_versions(&key1, &[]).unwrap().is_empty());
+
+    let key3 = StateKey::AccessPath(AccessPath::new(address, b"state_key3".to_vec()));
+    assert_eq!(
+        store.get_value_at_versions(&key3, &[0, 3]).unwrap(),
+        vec![None, None]
+    );
+}
+
 #[test]
 fn test_retired_records() {
     let address1 = AccountAddress::new([1u8; AccountAddress::LENGTH]);
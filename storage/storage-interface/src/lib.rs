@@ -263,6 +263,12 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Returns the gas used by the transaction at `version`, without fetching the full
+    /// transaction output.
+    fn get_gas_usage_by_transaction(&self, version: Version) -> Result<u64> {
+        unimplemented!()
+    }
+
     /// Returns events by given event key
     fn get_events(
         &self,
@@ -335,6 +341,27 @@ pub trait DbReader: Send + Sync {
             .and_then(|opt| opt.ok_or_else(|| format_err!("Latest LedgerInfo not found.")))
     }
 
+    /// Returns the epoch state of the current epoch, i.e., of the epoch following the latest
+    /// ledger info if it ends an epoch, and otherwise of the epoch of the latest ledger info, as
+    /// carried by the ledger info that ended the previous epoch.
+    fn get_latest_epoch_state(&self) -> Result<EpochState> {
+        let ledger_info_with_sigs = self.get_latest_ledger_info()?;
+        let ledger_info = ledger_info_with_sigs.ledger_info();
+        if let Some(epoch_state) = ledger_info.next_epoch_state() {
+            return Ok(epoch_state.clone());
+        }
+
+        // Only the genesis ledger info is in epoch 0, and it ends the epoch
+        let epoch = ledger_info.epoch();
+        let proof = self.get_epoch_ending_ledger_infos(epoch - 1, epoch)?;
+        proof
+            .ledger_info_with_sigs
+            .first()
+            .and_then(|li| li.ledger_info().next_epoch_state())
+            .cloned()
+            .ok_or_else(|| format_err!("Last LedgerInfo of epoch {} not found.", epoch - 1))
+    }
+
     /// Returns the latest version, None for non-bootstrapped DB.
     fn get_latest_version_option(&self) -> Result<Option<Version>> {
         Ok(self
@@ -390,6 +417,22 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Returns a page of no more than `limit` transactions sent by an account with `address`, for
+    /// paging through all of its transactions. The page starts right after `after_seq_num`, the
+    /// cursor returned by [`AccountTransactionsWithProof::last_seq_num`] for the previous page, or
+    /// at the first transaction if it is `None`. A page with fewer than `limit` transactions is
+    /// the last one as of `ledger_version`.
+    fn get_account_transactions_paginated(
+        &self,
+        address: AccountAddress,
+        after_seq_num: Option<u64>,
+        limit: u64,
+        include_events: bool,
+        ledger_version: Version,
+    ) -> Result<AccountTransactionsWithProof> {
+        unimplemented!()
+    }
+
     /// Returns proof of new state for a given ledger info with signatures relative to version known
     /// to client
     fn get_state_proof_with_ledger_info(
@@ -416,6 +459,17 @@ pub trait DbReader: Send + Sync {
         unimplemented!()
     }
 
+    /// Gets the state values of the state key at each of the given versions, in the same order,
+    /// with `None` where the key did not exist at that version. Cheaper than querying each
+    /// version on its own, e.g., to follow the history of a key.
+    fn get_state_value_at_versions(
+        &self,
+        state_key: StateKey,
+        versions: &[Version],
+    ) -> Result<Vec<Option<StateValue>>> {
+        unimplemented!()
+    }
+
     // Gets an account state by account address, out of the ledger state indicated by the state
     // Merkle tree root with a sparse merkle proof proving state tree root.
     // See [`AptosDB::get_account_state_with_proof_by_version`].
@@ -640,6 +694,18 @@ pub trait DbWriter: Send + Sync {
     fn delete_genesis(&self) -> Result<()> {
         unimplemented!()
     }
+
+    /// Bootstraps an empty DB with the given state as of the genesis ledger info, without
+    /// executing a genesis transaction. This is meant for tools (e.g., network replay or test
+    /// infrastructure) that start from a pre-built genesis state.
+    #[cfg(any(test, feature = "testing"))]
+    fn force_commit_genesis(
+        &self,
+        genesis_li: aptos_types::ledger_info::LedgerInfo,
+        state_items: Vec<(StateKey, StateValue)>,
+    ) -> Result<()> {
+        unimplemented!()
+    }
 }
 
 #[derive(Clone)]
@@ -49,8 +49,13 @@ static ALL_TARGETS: Lazy<BTreeMap<&'static str, Box<dyn FuzzTargetImpl>>> = Lazy
         Box::new(network::PeerNetworkMessagesReceive::default()),
         // Safety Rules Server (LSR)
         Box::new(safety_rules::SafetyRulesConstructAndSignVote::default()),
+        Box::new(safety_rules::SafetyRulesDecodeInput::default()),
+        Box::new(safety_rules::SafetyRulesDecodeSafetyData::default()),
+        Box::new(safety_rules::SafetyRulesDecodeWaypoint::default()),
         Box::new(safety_rules::SafetyRulesInitialize::default()),
         Box::new(safety_rules::SafetyRulesHandleMessage::default()),
+        Box::new(safety_rules::SafetyRulesHandleMessageBytes::default()),
+        Box::new(safety_rules::SafetyRulesSetSafetyData::default()),
         Box::new(safety_rules::SafetyRulesSignProposal::default()),
         Box::new(safety_rules::SafetyRulesSignTimeout::default()),
         // Secure Storage Vault
@@ -4,11 +4,13 @@
 use crate::{corpus_from_strategy, fuzz_data_to_value, FuzzTargetImpl};
 use aptos_proptest_helpers::ValueGenerator;
 use safety_rules::fuzzing_utils::{
-    arb_block_data, arb_epoch_change_proof, arb_maybe_signed_vote_proposal, arb_safety_rules_input,
-    arb_timeout,
+    arb_block_data, arb_epoch_change_proof, arb_maybe_signed_vote_proposal, arb_safety_data,
+    arb_safety_data_bytes, arb_safety_rules_input, arb_safety_rules_input_bytes,
+    arb_safety_rules_message, arb_timeout, arb_waypoint_bytes,
     fuzzing::{
-        fuzz_construct_and_sign_vote, fuzz_handle_message, fuzz_initialize, fuzz_sign_proposal,
-        fuzz_sign_timeout,
+        fuzz_construct_and_sign_vote, fuzz_decode_safety_data, fuzz_decode_safety_rules_input,
+        fuzz_decode_waypoint, fuzz_handle_message, fuzz_handle_message_bytes, fuzz_initialize,
+        fuzz_set_safety_data, fuzz_sign_proposal, fuzz_sign_timeout,
     },
 };
 
@@ -32,6 +34,25 @@ impl FuzzTargetImpl for SafetyRulesHandleMessage {
     }
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct SafetyRulesHandleMessageBytes;
+
+/// This implementation will fuzz the handle_message() method of the safety rules serializer
+/// service with raw, possibly malformed, messages.
+impl FuzzTargetImpl for SafetyRulesHandleMessageBytes {
+    fn description(&self) -> &'static str {
+        "Safety rules: handle_message() with malformed messages"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(gen.generate(arb_safety_rules_message()))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        let _ = fuzz_handle_message_bytes(data.to_vec());
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct SafetyRulesInitialize;
 
@@ -70,6 +91,25 @@ impl FuzzTargetImpl for SafetyRulesConstructAndSignVote {
     }
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct SafetyRulesSetSafetyData;
+
+/// This implementation will fuzz the set_safety_data() method of the safety rules storage.
+impl FuzzTargetImpl for SafetyRulesSetSafetyData {
+    fn description(&self) -> &'static str {
+        "Safety rules: set_safety_data()"
+    }
+
+    fn generate(&self, _idx: usize, _gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(corpus_from_strategy(arb_safety_data()))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        let safety_data = fuzz_data_to_value(data, arb_safety_data());
+        let _ = fuzz_set_safety_data(safety_data);
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct SafetyRulesSignProposal;
 
@@ -107,3 +147,57 @@ impl FuzzTargetImpl for SafetyRulesSignTimeout {
         let _ = fuzz_sign_timeout(timeout);
     }
 }
+
+#[derive(Clone, Debug, Default)]
+pub struct SafetyRulesDecodeInput;
+
+/// This implementation will fuzz the decoding of a SafetyRulesInput from JSON and BCS.
+impl FuzzTargetImpl for SafetyRulesDecodeInput {
+    fn description(&self) -> &'static str {
+        "Safety rules: decoding of SafetyRulesInput"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(gen.generate(arb_safety_rules_input_bytes()))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        let _ = fuzz_decode_safety_rules_input(data);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SafetyRulesDecodeSafetyData;
+
+/// This implementation will fuzz the decoding of SafetyData from JSON and BCS.
+impl FuzzTargetImpl for SafetyRulesDecodeSafetyData {
+    fn description(&self) -> &'static str {
+        "Safety rules: decoding of SafetyData"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(gen.generate(arb_safety_data_bytes()))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        let _ = fuzz_decode_safety_data(data);
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SafetyRulesDecodeWaypoint;
+
+/// This implementation will fuzz the decoding of a Waypoint from JSON and BCS.
+impl FuzzTargetImpl for SafetyRulesDecodeWaypoint {
+    fn description(&self) -> &'static str {
+        "Safety rules: decoding of Waypoint"
+    }
+
+    fn generate(&self, _idx: usize, gen: &mut ValueGenerator) -> Option<Vec<u8>> {
+        Some(gen.generate(arb_waypoint_bytes()))
+    }
+
+    fn fuzz(&self, data: &[u8]) {
+        let _ = fuzz_decode_waypoint(data);
+    }
+}
@@ -3,11 +3,12 @@
 
 use crate::{
     account_address::AccountAddress,
-    block_info::{BlockInfo, Round},
+    block_info::{BlockInfo, Round, GENESIS_EPOCH, GENESIS_ROUND, GENESIS_VERSION},
     epoch_state::EpochState,
     on_chain_config::ValidatorSet,
     transaction::Version,
     validator_verifier::{ValidatorVerifier, VerifyError},
+    waypoint::Waypoint,
 };
 use aptos_crypto::{ed25519::Ed25519Signature, hash::HashValue};
 use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
@@ -19,6 +20,30 @@ use std::{
     fmt::{Display, Formatter},
     ops::{Deref, DerefMut},
 };
+use thiserror::Error;
+
+/// Errors possible when structurally validating a genesis LedgerInfo.
+#[derive(Debug, Error, PartialEq)]
+pub enum VerificationError {
+    #[error("Genesis LedgerInfo has non-genesis version {0}")]
+    /// Genesis must be the first version of the ledger.
+    InvalidVersion(Version),
+    #[error("Genesis LedgerInfo has non-genesis epoch {0}")]
+    /// Genesis must be in the first epoch.
+    InvalidEpoch(u64),
+    #[error("Genesis LedgerInfo has non-genesis round {0}")]
+    /// Genesis is not produced by consensus, so it has no round.
+    InvalidRound(Round),
+    #[error("Genesis LedgerInfo references a previous block: {0}")]
+    /// Genesis has no parent block, so its block id must be zero.
+    UnexpectedBlockId(HashValue),
+    #[error("Genesis LedgerInfo has an empty transaction accumulator root")]
+    /// The genesis transaction must be committed in the accumulator.
+    InvalidAccumulatorRoot,
+    #[error("Genesis LedgerInfo does not end an epoch")]
+    /// Genesis must carry the validator set of the first epoch.
+    MissingNextEpochState,
+}
 
 /// This structure serves a dual purpose.
 ///
@@ -119,6 +144,12 @@ impl LedgerInfo {
         self.next_epoch_state().is_some()
     }
 
+    /// Returns true if the waypoint matches this `LedgerInfo`, i.e., both its version and its
+    /// value, see `Waypoint::verify`.
+    pub fn matches_waypoint(&self, waypoint: &Waypoint) -> bool {
+        waypoint.verify(self).is_ok()
+    }
+
     /// Returns hash of consensus voting data in this `LedgerInfo`.
     pub fn consensus_data_hash(&self) -> HashValue {
         self.consensus_data_hash
@@ -132,6 +163,33 @@ impl LedgerInfo {
     pub fn set_executed_state_id(&mut self, id: HashValue) {
         self.commit_info.set_executed_state_id(id)
     }
+
+    /// Checks that this is structurally a genesis LedgerInfo: it is at the first version, epoch
+    /// and round, has no previous block, commits to a non-empty accumulator and starts the next
+    /// epoch. Signatures are not checked as genesis is trusted via a waypoint.
+    pub fn verify_genesis(&self) -> std::result::Result<(), VerificationError> {
+        if self.version() != GENESIS_VERSION {
+            return Err(VerificationError::InvalidVersion(self.version()));
+        }
+        if self.epoch() != GENESIS_EPOCH {
+            return Err(VerificationError::InvalidEpoch(self.epoch()));
+        }
+        if self.round() != GENESIS_ROUND {
+            return Err(VerificationError::InvalidRound(self.round()));
+        }
+        if self.consensus_block_id() != HashValue::zero() {
+            return Err(VerificationError::UnexpectedBlockId(
+                self.consensus_block_id(),
+            ));
+        }
+        if self.transaction_accumulator_hash() == HashValue::zero() {
+            return Err(VerificationError::InvalidAccumulatorRoot);
+        }
+        if !self.ends_epoch() {
+            return Err(VerificationError::MissingNextEpochState);
+        }
+        Ok(())
+    }
 }
 
 /// Wrapper around LedgerInfoWithScheme to support future upgrades, this is the data being persisted.
@@ -307,6 +365,47 @@ mod tests {
     use super::*;
     use crate::validator_signer::ValidatorSigner;
 
+    #[test]
+    fn test_verify_genesis() {
+        let genesis = LedgerInfo::mock_genesis(None);
+        genesis.verify_genesis().unwrap();
+
+        let not_genesis = LedgerInfo::new(BlockInfo::random(1), HashValue::zero());
+        assert_eq!(
+            not_genesis.verify_genesis(),
+            Err(VerificationError::InvalidEpoch(1))
+        );
+
+        let empty_accumulator = LedgerInfo::genesis(HashValue::zero(), ValidatorSet::empty());
+        assert_eq!(
+            empty_accumulator.verify_genesis(),
+            Err(VerificationError::InvalidAccumulatorRoot)
+        );
+    }
+
+    #[test]
+    fn test_matches_waypoint() {
+        let ledger_info_at = |version, executed_state_id| {
+            LedgerInfo::new(
+                BlockInfo::new(1, 1, HashValue::zero(), executed_state_id, version, 0, None),
+                HashValue::zero(),
+            )
+        };
+        let executed_state_id = HashValue::random();
+        let ledger_info = ledger_info_at(10, executed_state_id);
+        let waypoint = Waypoint::new_any(&ledger_info);
+        assert!(ledger_info.matches_waypoint(&waypoint));
+
+        // Waypoints do not commit to the consensus data
+        let mut other = ledger_info.clone();
+        other.set_consensus_data_hash(HashValue::random());
+        assert!(other.matches_waypoint(&waypoint));
+
+        // Both the version and the value must match
+        assert!(!ledger_info_at(11, executed_state_id).matches_waypoint(&waypoint));
+        assert!(!ledger_info_at(10, HashValue::random()).matches_waypoint(&waypoint));
+    }
+
     #[test]
     fn test_signatures_hash() {
         let ledger_info = LedgerInfo::new(BlockInfo::empty(), HashValue::random());
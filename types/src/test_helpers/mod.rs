@@ -2,3 +2,27 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod transaction_test_helpers;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+/// The environment variable from which `seeded_rng` reads its seed, as 64 hex characters.
+pub const TEST_SEED_ENV_VAR: &str = "APTOS_TEST_SEED";
+
+/// Returns an RNG for tests that need reproducible randomness. Unless a seed is provided through
+/// `TEST_SEED_ENV_VAR`, a random one is used. Either way, the seed is printed at the start of the
+/// test so that a failure can be reproduced by rerunning with it.
+pub fn seeded_rng() -> ChaCha20Rng {
+    let seed = match std::env::var(TEST_SEED_ENV_VAR) {
+        Ok(seed) => {
+            let mut bytes = [0u8; 32];
+            hex::decode_to_slice(seed.trim(), &mut bytes).unwrap_or_else(|error| {
+                panic!("Invalid {}: {}", TEST_SEED_ENV_VAR, error);
+            });
+            bytes
+        }
+        Err(_) => rand::thread_rng().gen(),
+    };
+    eprintln!("{}={}", TEST_SEED_ENV_VAR, hex::encode(seed));
+    ChaCha20Rng::from_seed(seed)
+}
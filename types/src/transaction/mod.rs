@@ -1355,6 +1355,18 @@ impl AccountTransactionsWithProof {
         self.0
     }
 
+    /// Returns the cursor of the page that follows these transactions, i.e., the sequence number
+    /// of the last transaction, or `None` if there are no transactions.
+    pub fn last_seq_num(&self) -> Option<u64> {
+        let last_txn = self.0.last()?;
+        let seq_num = last_txn
+            .transaction
+            .as_signed_user_txn()
+            .ok()?
+            .sequence_number();
+        Some(seq_num)
+    }
+
     // TODO(philiphayes): this will need to change to support CRSNs
     // (Conflict-Resistant Sequence Numbers)[https://github.com/diem/dip/blob/main/dips/dip-168.md].
     //
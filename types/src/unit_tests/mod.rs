@@ -8,4 +8,5 @@ mod contract_event_test;
 mod transaction_test;
 mod trusted_state_test;
 mod validator_set_test;
+mod validator_signer_test;
 mod write_set_test;
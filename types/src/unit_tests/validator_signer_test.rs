@@ -0,0 +1,89 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block_info::BlockInfo,
+    ledger_info::LedgerInfo,
+    test_helpers::seeded_rng,
+    transaction::RawTransaction,
+    validator_signer::ValidatorSigner,
+    validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
+};
+use aptos_crypto::{HashValue, Signature};
+use proptest::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+#[test]
+fn test_sign_ledger_info() {
+    let mut rng = seeded_rng();
+    let ledger_info = LedgerInfo::new(BlockInfo::empty(), HashValue::zero());
+    let signers: Vec<_> = (0..3)
+        .map(|_| ValidatorSigner::from_rng(&mut rng))
+        .collect();
+    let verifier = ValidatorVerifier::new(
+        signers
+            .iter()
+            .map(|signer| {
+                (
+                    signer.author(),
+                    ValidatorConsensusInfo::new(signer.public_key(), 1),
+                )
+            })
+            .collect(),
+    );
+
+    let signed = signers[0].sign_ledger_info(&ledger_info);
+    assert_eq!(signed.ledger_info(), &ledger_info);
+    assert_eq!(signed.signatures().len(), 1);
+    let single_verifier =
+        ValidatorVerifier::new_single(signers[0].author(), signers[0].public_key());
+    signed.verify_signatures(&single_verifier).unwrap();
+
+    let signers: Vec<_> = signers.iter().collect();
+    let signed = ValidatorSigner::sign_ledger_info_multi(&ledger_info, &signers);
+    assert_eq!(signed.ledger_info(), &ledger_info);
+    assert_eq!(signed.signatures().len(), 3);
+    signed.verify_signatures(&verifier).unwrap();
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(20))]
+
+    #[test]
+    fn test_from_bytes_is_deterministic(seed in any::<[u8; 32]>(), txn in any::<RawTransaction>()) {
+        let signer = ValidatorSigner::from_bytes(seed);
+        let other = ValidatorSigner::from_bytes(seed);
+        prop_assert_eq!(signer.author(), other.author());
+        prop_assert_eq!(signer.public_key(), other.public_key());
+
+        let signature = signer.sign(&txn);
+        prop_assert!(signature.verify(&txn, &other.public_key()).is_ok());
+    }
+
+    #[test]
+    fn test_from_rng_is_deterministic(seed in any::<[u8; 32]>(), txn in any::<RawTransaction>()) {
+        let signer = ValidatorSigner::from_rng(&mut ChaCha20Rng::from_seed(seed));
+        let other = ValidatorSigner::from_rng(&mut ChaCha20Rng::from_seed(seed));
+        prop_assert_eq!(signer.author(), other.author());
+        prop_assert_eq!(signer.public_key(), other.public_key());
+
+        let signature = signer.sign(&txn);
+        prop_assert!(signature.verify(&txn, &other.public_key()).is_ok());
+
+        // Consecutive draws from the same RNG yield distinct signers
+        let mut rng = ChaCha20Rng::from_seed(seed);
+        let first = ValidatorSigner::from_rng(&mut rng);
+        let second = ValidatorSigner::from_rng(&mut rng);
+        prop_assert_ne!(first.author(), second.author());
+    }
+
+    #[test]
+    fn test_from_bytes_distinct_seeds(seed1 in any::<[u8; 32]>(), seed2 in any::<[u8; 32]>()) {
+        prop_assume!(seed1 != seed2);
+        let signer1 = ValidatorSigner::from_bytes(seed1);
+        let signer2 = ValidatorSigner::from_bytes(seed2);
+        prop_assert_ne!(signer1.author(), signer2.author());
+        prop_assert_ne!(signer1.public_key(), signer2.public_key());
+    }
+}
@@ -1,14 +1,17 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::account_address::AccountAddress;
+use crate::{
+    account_address::{self, AccountAddress},
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+};
 use aptos_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
     test_utils::TEST_SEED,
     PrivateKey, SigningKey, Uniform,
 };
-use rand::{rngs::StdRng, SeedableRng};
+use rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
 use serde::ser::Serialize;
 use std::convert::TryFrom;
 
@@ -35,6 +38,23 @@ impl ValidatorSigner {
         self.private_key.sign(message)
     }
 
+    /// Signs the ledger info, returning it along with this signer's signature.
+    pub fn sign_ledger_info(&self, ledger_info: &LedgerInfo) -> LedgerInfoWithSignatures {
+        Self::sign_ledger_info_multi(ledger_info, &[self])
+    }
+
+    /// Signs the ledger info with each of the signers, returning it along with their signatures.
+    pub fn sign_ledger_info_multi(
+        ledger_info: &LedgerInfo,
+        signers: &[&ValidatorSigner],
+    ) -> LedgerInfoWithSignatures {
+        let signatures = signers
+            .iter()
+            .map(|signer| (signer.author(), signer.sign(ledger_info)))
+            .collect();
+        LedgerInfoWithSignatures::new(ledger_info.clone(), signatures)
+    }
+
     /// Returns the author associated with this signer.
     pub fn author(&self) -> AccountAddress {
         self.author
@@ -65,6 +85,15 @@ impl ValidatorSigner {
         )
     }
 
+    /// Generates the private key from the given RNG, and derives the author from the resulting
+    /// public key. A seeded RNG thus always yields the same signer, which makes tests using it
+    /// reproducible.
+    pub fn from_rng<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
+        let private_key = Ed25519PrivateKey::generate(rng);
+        let author = account_address::from_public_key(&private_key.public_key());
+        Self::new(author, private_key)
+    }
+
     /// For test only - makes signer with nicely looking account address that has specified integer
     /// as fist byte, and rest are zeroes
     pub fn from_int(num: u8) -> Self {
@@ -73,6 +102,15 @@ impl ValidatorSigner {
         let private_key = Ed25519PrivateKey::generate_for_testing();
         Self::new(AccountAddress::try_from(&address[..]).unwrap(), private_key)
     }
+
+    /// For test only - makes a signer whose private key is the given seed, and whose author is
+    /// derived from the resulting public key, so that distinct seeds yield distinct validators.
+    pub fn from_bytes(seed: [u8; 32]) -> Self {
+        let private_key = Ed25519PrivateKey::try_from(&seed[..])
+            .expect("Any 32 bytes are a valid Ed25519 private key");
+        let author = account_address::from_public_key(&private_key.public_key());
+        Self::new(author, private_key)
+    }
 }
 
 #[cfg(any(test, feature = "fuzzing"))]
@@ -5,10 +5,11 @@ use crate::{
     epoch_change::Verifier,
     epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    proof::AccumulatorProof,
     transaction::Version,
 };
 use anyhow::{ensure, format_err, Error, Result};
-use aptos_crypto::hash::{CryptoHash, HashValue};
+use aptos_crypto::hash::{CryptoHash, CryptoHasher, HashValue};
 use aptos_crypto_derive::{BCSCryptoHash, CryptoHasher};
 #[cfg(any(test, feature = "fuzzing"))]
 use proptest_derive::Arbitrary;
@@ -17,10 +18,22 @@ use std::{
     fmt::{Display, Formatter},
     str::FromStr,
 };
+use thiserror::Error;
 
 // The delimiter between the version and the hash.
 const WAYPOINT_DELIMITER: char = ':';
 
+/// Errors possible when constructing a waypoint from unverified LedgerInfo.
+#[derive(Debug, Error, PartialEq)]
+pub enum WaypointVerificationError {
+    #[error("LedgerInfo at version {0} does not end an epoch")]
+    /// The LedgerInfo does not carry a next epoch state.
+    NotEpochBoundary(Version),
+    #[error("Invalid accumulator proof for LedgerInfo: {0}")]
+    /// The accumulator proof does not authenticate the LedgerInfo against the root hash.
+    InvalidProof(String),
+}
+
 /// Waypoint keeps information about the LedgerInfo on a given version, which provides an
 /// off-chain mechanism to verify the sync process right after the restart.
 /// At high level, a trusted waypoint verifies the LedgerInfo for a certain epoch change.
@@ -50,6 +63,25 @@ impl Waypoint {
         Ok(Self::new_any(ledger_info))
     }
 
+    /// Generates a new waypoint given an epoch change LedgerInfo received from an untrusted
+    /// source. The `proof` must authenticate the hash of `ledger_info` at index
+    /// `ledger_info.version()` in the accumulator whose root hash is `root_hash`.
+    pub fn from_ledger_info_with_proof<H: CryptoHasher>(
+        ledger_info: &LedgerInfo,
+        proof: &AccumulatorProof<H>,
+        root_hash: HashValue,
+    ) -> std::result::Result<Self, WaypointVerificationError> {
+        if !ledger_info.ends_epoch() {
+            return Err(WaypointVerificationError::NotEpochBoundary(
+                ledger_info.version(),
+            ));
+        }
+        proof
+            .verify(root_hash, ledger_info.hash(), ledger_info.version())
+            .map_err(|error| WaypointVerificationError::InvalidProof(error.to_string()))?;
+        Ok(Self::new_any(ledger_info))
+    }
+
     pub fn version(&self) -> Version {
         self.version
     }
@@ -58,6 +90,14 @@ impl Waypoint {
         self.value
     }
 
+    /// Returns a short form of the waypoint for log fields, e.g., `v42:a3f91b2d`, made of the
+    /// version and the first 8 hex characters of the hash. Unlike `Display`, it identifies the
+    /// waypoint for humans only and cannot be parsed back.
+    pub fn display_compact(&self) -> String {
+        // The Display of a HashValue is its first 4 bytes in hex
+        format!("v{}{}{}", self.version, WAYPOINT_DELIMITER, self.value)
+    }
+
     /// Errors in case the given ledger info does not match the waypoint.
     pub fn verify(&self, ledger_info: &LedgerInfo) -> Result<()> {
         ensure!(
@@ -189,7 +229,10 @@ impl Serialize for Waypoint {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::block_info::BlockInfo;
+    use crate::{
+        block_info::BlockInfo,
+        proof::{TransactionAccumulatorInternalNode, TransactionAccumulatorProof},
+    };
     use std::str::FromStr;
 
     #[test]
@@ -203,6 +246,28 @@ mod test {
         assert_eq!(waypoint, parsed_waypoint);
     }
 
+    #[test]
+    fn test_waypoint_display_compact() {
+        let mut hash = [0; HashValue::LENGTH];
+        hash[..4].copy_from_slice(&[0xa3, 0xf9, 0x1b, 0x2d]);
+        hash[4] = 0xff;
+        let waypoint = Waypoint {
+            version: 42,
+            value: HashValue::new(hash),
+        };
+        assert_eq!(waypoint.display_compact(), "v42:a3f91b2d");
+        assert_eq!(Waypoint::default().display_compact(), "v0:00000000");
+
+        // The compact form is a prefix of the full one, apart from the version marker
+        let waypoint = Waypoint {
+            version: 123,
+            value: HashValue::random(),
+        };
+        let compact = waypoint.display_compact();
+        assert!(waypoint.to_string().starts_with(&compact[1..]));
+        assert_eq!(compact.len(), "v123:".len() + 8);
+    }
+
     #[test]
     fn test_waypoint_li_verification() {
         let empty_li = LedgerInfo::new(BlockInfo::empty(), HashValue::zero());
@@ -222,4 +287,47 @@ mod test {
         let waypoint = Waypoint::new_epoch_boundary(&li).unwrap();
         assert!(waypoint.verify(&li).is_ok());
     }
+
+    #[test]
+    fn test_waypoint_from_ledger_info_with_proof() {
+        let empty_li = LedgerInfo::new(BlockInfo::empty(), HashValue::zero());
+        let proof = TransactionAccumulatorProof::new(vec![]);
+        assert_eq!(
+            Waypoint::from_ledger_info_with_proof(&empty_li, &proof, empty_li.hash()),
+            Err(WaypointVerificationError::NotEpochBoundary(0))
+        );
+
+        let li = LedgerInfo::new(
+            BlockInfo::new(
+                1,
+                10,
+                HashValue::random(),
+                HashValue::random(),
+                1,
+                1000,
+                Some(EpochState::empty()),
+            ),
+            HashValue::zero(),
+        );
+
+        // The ledger info is the second leaf of a two-leaf accumulator
+        let sibling = HashValue::random();
+        let root_hash = TransactionAccumulatorInternalNode::new(sibling, li.hash()).hash();
+        let proof = TransactionAccumulatorProof::new(vec![sibling]);
+        proof.verify(root_hash, li.hash(), li.version()).unwrap();
+        let waypoint = Waypoint::from_ledger_info_with_proof(&li, &proof, root_hash).unwrap();
+        assert_eq!(waypoint, Waypoint::new_epoch_boundary(&li).unwrap());
+        assert!(waypoint.verify(&li).is_ok());
+
+        // The proof authenticates neither another root hash nor another sibling
+        assert!(matches!(
+            Waypoint::from_ledger_info_with_proof(&li, &proof, HashValue::random()),
+            Err(WaypointVerificationError::InvalidProof(_))
+        ));
+        let proof = TransactionAccumulatorProof::new(vec![HashValue::random()]);
+        assert!(matches!(
+            Waypoint::from_ledger_info_with_proof(&li, &proof, root_hash),
+            Err(WaypointVerificationError::InvalidProof(_))
+        ));
+    }
 }